@@ -0,0 +1,303 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+
+use application::font::*;
+use application::gui::gui_components::*;
+use application::gui::toast::*;
+use application::gui::*;
+
+use window::show_folder_dialog;
+
+use crate::document::*;
+use crate::editor::*;
+use crate::export::*;
+
+/// File name of the exported document, the title is used if it can be a file name. A name
+/// taken by another exported document or by a file in the folder gets the id of the
+/// document, the flag tells that the name was changed.
+fn get_file_name(
+    id: usize,
+    document: &Document,
+    extension: &str,
+    folder: &Path,
+    taken_names: &mut HashSet<String>,
+) -> (String, bool) {
+    let title = document.get_metadata().title.trim();
+    let valid_title = !title.is_empty()
+        && !title
+            .chars()
+            .any(|c| c.is_control() || "\\/:*?\"<>|".contains(c));
+    let base = if valid_title {
+        title.to_string()
+    } else {
+        format!("Чертёж {}", id)
+    };
+    // names differing by the case are the same file on Windows
+    let is_free = |name: &str, taken_names: &HashSet<String>| {
+        !taken_names.contains(&name.to_lowercase()) && !folder.join(name).exists()
+    };
+    let mut name = format!("{}.{}", base, extension);
+    let mut number = 1;
+    let renamed = !is_free(&name, taken_names);
+    if renamed {
+        name = format!("{} ({}).{}", base, id, extension);
+    }
+    while !is_free(&name, taken_names) {
+        number += 1;
+        name = format!("{} ({}-{}).{}", base, id, number, extension);
+    }
+    taken_names.insert(name.to_lowercase());
+    (name, renamed)
+}
+
+/// File to write by the threads of the export.
+struct ExportJob {
+    path: PathBuf,
+    data: ExportData,
+}
+
+/// Written file or the error of its writing.
+type ExportResult = (PathBuf, Result<(), String>);
+
+/// Threads which take the jobs by turn, write the files and send back the results.
+/// They stop when the job sender is dropped, the jobs left after the cancellation are
+/// skipped.
+fn start_export_threads(
+    count: usize,
+    cancelled: &Arc<AtomicBool>,
+) -> Result<(Sender<ExportJob>, Receiver<ExportResult>), String> {
+    let (job_sender, job_receiver) = mpsc::channel::<ExportJob>();
+    let (result_sender, result_receiver) = mpsc::channel();
+    let job_receiver = Arc::new(Mutex::new(job_receiver));
+    let thread_count = std::thread::available_parallelism().map_or(1, |n| n.get());
+    for _ in 0..thread_count.min(count) {
+        let job_receiver = job_receiver.clone();
+        let result_sender = result_sender.clone();
+        let cancelled = cancelled.clone();
+        std::thread::Builder::new()
+            .name("Экспорт".to_string())
+            .spawn(move || loop {
+                let job = match job_receiver.lock() {
+                    Ok(job_receiver) => job_receiver.recv(),
+                    Err(_) => return,
+                };
+                let job = match job {
+                    Ok(job) => job,
+                    Err(_) => return,
+                };
+                if cancelled.load(Ordering::Relaxed) {
+                    continue;
+                }
+                let text = job.data.write();
+                let result = std::fs::write(&job.path, text).map_err(|e| e.to_string());
+                if result_sender.send((job.path, result)).is_err() {
+                    return;
+                }
+            })
+            .map_err(|e| e.to_string())?;
+    }
+    Ok((job_sender, result_receiver))
+}
+
+/// Exports every open document to the chosen folder. A long task takes one document per
+/// step, the files are made and written by the threads of the export. Progress is shown
+/// above the drawing, Escape cancels the rest.
+pub fn export_all_documents(
+    format: ExportFormat,
+    font: &Font,
+    editor: Rc<RefCell<Editor>>,
+    context: Rc<RefCell<window::Context>>,
+) {
+    let documents = editor.borrow().get_documents();
+    if documents.is_empty() {
+        return;
+    }
+    let folder = match show_folder_dialog(context.clone()) {
+        Some(folder) => folder,
+        None => return,
+    };
+    let count = documents.len();
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let (job_sender, result_receiver) = match start_export_threads(count, &cancelled) {
+        Ok(channels) => channels,
+        Err(e) => {
+            let toast = Toast::error(&format!("Экспорт не начат: {}", e));
+            context.borrow_mut().gui_system.show_toast(toast);
+            return;
+        }
+    };
+
+    let font_height = font.get_size("8").1 as i32 + 2;
+    let width = font.get_size("Экспорт: 0000 из 0000, Esc - отмена").0 as i32 + font_height;
+    let height = font_height * 2 + 1;
+    let main_rect = context
+        .borrow()
+        .gui_system
+        .get_region_rect(MAIN_REGION)
+        .unwrap_or_default();
+    let center = (
+        (main_rect.left_top.0 + main_rect.right_bottom.0) / 2,
+        (main_rect.left_top.1 + main_rect.right_bottom.1) / 2,
+    );
+    let (panel, region_id) = context.borrow_mut().gui_system.add_region(
+        RegionPlacement::Overlay(Rect {
+            left_top: (center.0 - width / 2, center.1 - height / 2),
+            right_bottom: (center.0 + width / 2, center.1 + height / 2),
+        }),
+        Container::new(
+            SizeConstraints(SizeConstraint::flexible(0), SizeConstraint::flexible(0)),
+            ContainerLayout::Vertical,
+        ),
+    );
+    let caption = move |done: usize| format!("Экспорт: {} из {}, Esc - отмена", done, count);
+    let progress_text = panel.borrow_mut().add_child(TextBox::new(
+        SizeConstraints(
+            SizeConstraint::flexible(0),
+            SizeConstraint::fixed(font_height),
+        ),
+        caption(0),
+        font.clone(),
+    ));
+    panel
+        .borrow_mut()
+        .add_child(EmptySpace::new_splitter(SizeConstraints(
+            SizeConstraint::flexible(0),
+            SizeConstraint::fixed(1),
+        )));
+    let progress_bar = panel
+        .borrow_mut()
+        .add_child(ProgressBar::new(SizeConstraints(
+            SizeConstraint::flexible(0),
+            SizeConstraint::flexible(0),
+        )));
+
+    // the documents are read by the steps of the task, the threads write the files
+    let mut job_sender = Some(job_sender);
+    let mut taken_names = HashSet::new();
+    let prepared = Cell::new(0);
+    let done = Rc::new(Cell::new(0));
+    let failures: Rc<RefCell<Vec<String>>> = Rc::default();
+    let renamed: Rc<RefCell<Vec<String>>> = Rc::default();
+    let job_system = context.borrow().job_system.clone();
+    let handle = {
+        let done = done.clone();
+        let failures = failures.clone();
+        let renamed = renamed.clone();
+        job_system.start_task("Экспорт", move |handle| {
+            if let Some(sender) = &job_sender {
+                let (id, document) = &documents[prepared.get()];
+                let document = document.borrow();
+                let (name, was_renamed) = get_file_name(
+                    *id,
+                    &document,
+                    format.get_extension(),
+                    &folder,
+                    &mut taken_names,
+                );
+                let path = folder.join(&name);
+                if was_renamed {
+                    renamed.borrow_mut().push(name);
+                }
+                match format.prepare(&document, ExportScope::Document) {
+                    Ok(data) => {
+                        let _ = sender.send(ExportJob { path, data });
+                    }
+                    Err(e) => {
+                        failures
+                            .borrow_mut()
+                            .push(format!("{}: {}", path.display(), e));
+                        done.set(done.get() + 1);
+                    }
+                }
+                prepared.set(prepared.get() + 1);
+                if prepared.get() == count {
+                    // the threads stop when the jobs are over
+                    job_sender = None;
+                }
+            }
+            while let Ok((path, result)) = result_receiver.try_recv() {
+                if let Err(e) = result {
+                    failures
+                        .borrow_mut()
+                        .push(format!("{}: {}", path.display(), e));
+                }
+                done.set(done.get() + 1);
+            }
+            handle.set_progress(done.get() as f32 / count as f32);
+            progress_text.borrow_mut().set_text(&caption(done.get()));
+            progress_bar
+                .borrow_mut()
+                .set_progress(handle.get_progress());
+            done.get() == count
+        })
+    };
+    let weak_context = Rc::downgrade(&context);
+    handle.set_finish_callback(move || {
+        // the files which are not written yet are skipped after the cancellation
+        cancelled.store(true, Ordering::Relaxed);
+        let context = match weak_context.upgrade() {
+            Some(context) => context,
+            None => return,
+        };
+        let failures = failures.borrow();
+        let renamed = renamed.borrow();
+        let exported = done.get() - failures.len();
+        let mut summary = format!("Экспортировано файлов: {} из {}", exported, count);
+        if done.get() < count {
+            summary += ", экспорт отменён";
+        }
+        if !renamed.is_empty() {
+            summary += &format!(
+                "\nИмена заняты, файлы названы иначе:\n{}",
+                renamed.join("\n")
+            );
+        }
+        let toast = if !failures.is_empty() {
+            Toast::error(&format!("{}\nОшибки:\n{}", summary, failures.join("\n")))
+        } else if !renamed.is_empty() {
+            Toast::warning(&summary)
+        } else {
+            Toast::info(&summary)
+        };
+        let mut context = context.borrow_mut();
+        context.gui_system.remove_region(region_id);
+        context.gui_system.show_toast(toast);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sheet::DocumentMetadata;
+
+    fn titled(title: &str) -> Document {
+        let mut document = Document::new();
+        document.set_metadata(DocumentMetadata {
+            title: title.to_string(),
+            ..DocumentMetadata::default()
+        });
+        document
+    }
+
+    #[test]
+    fn taken_names_get_the_id() {
+        let folder = std::env::temp_dir().join(format!("otcad_batch_{}", std::process::id()));
+        std::fs::create_dir_all(&folder).unwrap();
+        std::fs::write(folder.join("На диске.svg"), "").unwrap();
+        let mut taken_names = HashSet::new();
+        let mut name = |id: usize, title: &str| {
+            get_file_name(id, &titled(title), "svg", &folder, &mut taken_names)
+        };
+
+        assert_eq!(name(1, "Деталь"), ("Деталь.svg".to_string(), false));
+        assert_eq!(name(2, "деталь"), ("деталь (2).svg".to_string(), true));
+        assert_eq!(name(3, "На диске"), ("На диске (3).svg".to_string(), true));
+        assert_eq!(name(4, "a/b"), ("Чертёж 4.svg".to_string(), false));
+        std::fs::remove_dir_all(&folder).unwrap();
+    }
+}
@@ -4,17 +4,44 @@ use std::rc::Rc;
 use application::callback;
 use application::callback_body;
 use application::font::*;
+use application::gui::binding::*;
 use application::gui::gui_components::*;
 use application::gui::*;
+use application::keys::*;
 
-use crate::config::*;
+use crate::document::*;
+use crate::editor::*;
 use crate::gui_helper::*;
+use crate::isometric::*;
+
+/// The status bar is in its own region, changes of the drawing made from it have to
+/// invalidate the main region.
+fn redraw_drawing(context: &Rc<RefCell<window::Context>>) {
+    context
+        .borrow_mut()
+        .gui_system
+        .invalidate_region(MAIN_REGION);
+}
+
+/// Entities and undo steps of the document with their memory in kilobytes.
+fn format_memory_usage(usage: &MemoryUsage) -> String {
+    let kilobytes = |bytes: usize| bytes.div_ceil(1024);
+    format!(
+        "Объектов: {} ({} КБ)  Отмена: {} ({} КБ)",
+        usage.entities,
+        kilobytes(usage.content_bytes),
+        usage.undo_steps,
+        kilobytes(usage.undo_bytes)
+    )
+}
 
 pub fn create_bottom_panel(
     root: &mut Container,
     font: &Font,
-    config: Rc<RefCell<Config>>,
+    editor: Rc<RefCell<Editor>>,
+    context: Rc<RefCell<window::Context>>,
 ) -> Rc<RefCell<Container>> {
+    let config = editor.borrow().config.clone();
     let font_symbol_size = font.get_size("8");
     let font_height = font_symbol_size.1 as i32 + 2;
 
@@ -26,14 +53,76 @@ pub fn create_bottom_panel(
         ContainerLayout::Horizontal,
     ));
 
-    let _grid_button = bottom_panel.borrow_mut().add_child(
-        create_default_size_check_button("Показать сетку", font.clone())
-            .check_box(config.borrow().show_grid)
-            .checkbox_callback(callback!([config] (c) {
-                    config.borrow_mut().show_grid = c;
+    let grid_button = bottom_panel.borrow_mut().add_child(
+        create_default_size_check_button_with_hotkey(
+            "Показать сетку",
+            font.clone(),
+            Hotkey::new(Key::F7),
+            true,
+        )
+        .check_box(false),
+    );
+    bind_check_box(&grid_button, &editor.borrow().show_grid);
+
+    let ortho_button = bottom_panel.borrow_mut().add_child(
+        create_default_size_check_button_with_hotkey(
+            "Орто",
+            font.clone(),
+            Hotkey::new(Key::F8),
+            true,
+        )
+        .check_box(false),
+    );
+    bind_check_box(&ortho_button, &editor.borrow().ortho);
+
+    let _isometric_button = bottom_panel.borrow_mut().add_child(
+        create_default_size_check_button("Изометрия", font.clone())
+            .check_box(config.borrow().isometric)
+            .checkbox_callback(callback!([config, context] (c) {
+                config.borrow_mut().isometric = c;
+                redraw_drawing(&context);
             })),
     );
 
+    let isoplanes = [Isoplane::Left, Isoplane::Top, Isoplane::Right];
+    let isoplane_selector = bottom_panel.borrow_mut().add_child(RadioGroup::new(
+        SizeConstraints(SizeConstraint::fixed(0), SizeConstraint::fixed(font_height)),
+        ContainerLayout::Horizontal,
+        None,
+    ));
+    for caption in ["Лево", "Верх", "Право"] {
+        isoplane_selector
+            .borrow_mut()
+            .add_button(create_default_size_radio_button(caption, font.clone()));
+    }
+    isoplane_selector.borrow_mut().set_id(
+        isoplanes
+            .iter()
+            .position(|p| *p == config.borrow().isoplane)
+            .unwrap_or(0),
+    );
+    isoplane_selector
+        .borrow_mut()
+        .set_change_tab_callback(callback!([config] (index) {
+            config.borrow_mut().isoplane = isoplanes[index];
+        }));
+
+    let _isoplane_button = bottom_panel.borrow_mut().add_child(
+        create_default_size_button_with_hotkey(
+            "Изоплоскость",
+            font.clone(),
+            Hotkey::new(Key::F5),
+            true,
+        )
+        .callback(callback!([config, isoplane_selector] () {
+            let isoplane = config.borrow().isoplane.next();
+            config.borrow_mut().isoplane = isoplane;
+            isoplane_selector
+                .borrow()
+                .set_id(isoplanes.iter().position(|p| *p == isoplane).unwrap_or(0));
+        })),
+    );
+
     let _hr = bottom_panel
         .borrow_mut()
         .add_child(EmptySpace::new_splitter(SizeConstraints(
@@ -41,9 +130,116 @@ pub fn create_bottom_panel(
             SizeConstraint::flexible(0),
         )));
 
-    let _snap_button = bottom_panel
+    let _zoom_caption = bottom_panel
         .borrow_mut()
-        .add_child(create_default_size_text_box("Привязки:", font.clone()));
+        .add_child(create_default_size_text_box("Масштаб:", font.clone()));
+
+    // zoom buttons repeat while held, a step is the same as a mouse wheel notch
+    for (caption, scale_delta) in [("-", -10.0), ("+", 10.0)] {
+        let _zoom_button = bottom_panel.borrow_mut().add_child(
+            create_default_size_button(caption, font.clone())
+                .auto_repeat()
+                .callback(callback!([editor, context] () {
+                    editor.borrow().zoom_active_document(scale_delta);
+                    redraw_drawing(&context);
+                })),
+        );
+    }
+
+    let _extents_button = bottom_panel.borrow_mut().add_child(
+        create_default_size_button_with_hotkey("Всё", font.clone(), Hotkey::new(Key::Home), true)
+            .callback(callback!([editor, context] () {
+                editor.borrow().zoom_extents();
+                redraw_drawing(&context);
+            })),
+    );
+
+    let _selection_zoom_button = bottom_panel.borrow_mut().add_child(
+        create_default_size_button_with_hotkey(
+            "Выделение",
+            font.clone(),
+            Hotkey::shift(Key::Home),
+            true,
+        )
+        .callback(callback!([editor, context] () {
+            if editor.borrow().zoom_to_selection() {
+                redraw_drawing(&context);
+            }
+        })),
+    );
+
+    let _hr = bottom_panel
+        .borrow_mut()
+        .add_child(EmptySpace::new_splitter(SizeConstraints(
+            SizeConstraint::fixed(1),
+            SizeConstraint::flexible(0),
+        )));
+
+    let number_format = config.borrow().number_format;
+    let cursor_position_box = bottom_panel.borrow_mut().add_child(TextBox::new(
+        SizeConstraints(
+            SizeConstraint::fixed(font.get_size("X: -00000.000  Y: -00000.000").0 as i32),
+            SizeConstraint::flexible(0),
+        ),
+        String::new(),
+        font.clone(),
+    ));
+    bind_text_box(
+        &cursor_position_box,
+        &editor.borrow().cursor_ucs_position,
+        move |position| match position {
+            Some(position) => format!(
+                "X: {}  Y: {}",
+                number_format.format(position.x),
+                number_format.format(position.y)
+            ),
+            None => String::new(),
+        },
+    );
+
+    let _hr = bottom_panel
+        .borrow_mut()
+        .add_child(EmptySpace::new_splitter(SizeConstraints(
+            SizeConstraint::fixed(1),
+            SizeConstraint::flexible(0),
+        )));
+
+    let widest_usage = MemoryUsage {
+        entities: 9999999,
+        content_bytes: 9999999 * 1024,
+        undo_steps: 9999,
+        undo_bytes: 9999999 * 1024,
+    };
+    let memory_usage_box = bottom_panel.borrow_mut().add_child(TextBox::new(
+        SizeConstraints(
+            SizeConstraint::fixed(font.get_size(&format_memory_usage(&widest_usage)).0 as i32),
+            SizeConstraint::flexible(0),
+        ),
+        String::new(),
+        font.clone(),
+    ));
+    bind_text_box(&memory_usage_box, &editor.borrow().memory_usage, |usage| {
+        usage.as_ref().map(format_memory_usage).unwrap_or_default()
+    });
+
+    let _hr = bottom_panel
+        .borrow_mut()
+        .add_child(EmptySpace::new_splitter(SizeConstraints(
+            SizeConstraint::fixed(1),
+            SizeConstraint::flexible(0),
+        )));
+
+    // turning the snap off keeps the chosen kinds of points for turning it on
+    let snap_button = bottom_panel.borrow_mut().add_child(
+        create_default_size_check_button_with_hotkey(
+            "Привязки:",
+            font.clone(),
+            Hotkey::new(Key::F9),
+            true,
+        )
+        .check_box(false),
+    );
+    bind_check_box(&snap_button, &editor.borrow().snap_enabled);
 
     let _grid_nodes_button = bottom_panel.borrow_mut().add_child(
         create_default_size_check_button("Узлы сетки", font.clone())
@@ -68,6 +264,21 @@ pub fn create_bottom_panel(
             })),
     );
 
+    let _es = bottom_panel
+        .borrow_mut()
+        .add_child(EmptySpace::new_empty(SizeConstraints(
+            SizeConstraint::fixed(font_symbol_size.0 as i32 / 2),
+            SizeConstraint::flexible(0),
+        )));
+
+    let _midpoints_button = bottom_panel.borrow_mut().add_child(
+        create_default_size_check_button("Середины", font.clone())
+            .check_box(config.borrow().snap_options.snap_midpoints)
+            .checkbox_callback(callback!([config] (c) {
+                config.borrow_mut().snap_options.snap_midpoints = c;
+            })),
+    );
+
     let _es = bottom_panel
         .borrow_mut()
         .add_child(EmptySpace::new_empty(SizeConstraints(
@@ -98,5 +309,50 @@ pub fn create_bottom_panel(
             })),
     );
 
+    let _es = bottom_panel
+        .borrow_mut()
+        .add_child(EmptySpace::new_empty(SizeConstraints(
+            SizeConstraint::fixed(font_symbol_size.0 as i32 / 2),
+            SizeConstraint::flexible(0),
+        )));
+
+    let _quadrants_button = bottom_panel.borrow_mut().add_child(
+        create_default_size_check_button("Квадранты", font.clone())
+            .check_box(config.borrow().snap_options.snap_quadrants)
+            .checkbox_callback(callback!([config] (c) {
+                config.borrow_mut().snap_options.snap_quadrants = c;
+            })),
+    );
+
+    let _es = bottom_panel
+        .borrow_mut()
+        .add_child(EmptySpace::new_empty(SizeConstraints(
+            SizeConstraint::fixed(font_symbol_size.0 as i32 / 2),
+            SizeConstraint::flexible(0),
+        )));
+
+    let _points_button = bottom_panel.borrow_mut().add_child(
+        create_default_size_check_button("Точки", font.clone())
+            .check_box(config.borrow().snap_options.snap_points)
+            .checkbox_callback(callback!([config] (c) {
+                config.borrow_mut().snap_options.snap_points = c;
+            })),
+    );
+
+    let _es = bottom_panel
+        .borrow_mut()
+        .add_child(EmptySpace::new_empty(SizeConstraints(
+            SizeConstraint::fixed(font_symbol_size.0 as i32 / 2),
+            SizeConstraint::flexible(0),
+        )));
+
+    let _guides_button = bottom_panel.borrow_mut().add_child(
+        create_default_size_check_button("Направляющие", font.clone())
+            .check_box(config.borrow().snap_options.smart_guides)
+            .checkbox_callback(callback!([config] (c) {
+                config.borrow_mut().snap_options.smart_guides = c;
+            })),
+    );
+
     bottom_panel
 }
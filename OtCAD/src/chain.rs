@@ -0,0 +1,497 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::document::*;
+
+type Point = curves::points::Point<f64>;
+
+/// Ends closer than this are connected without a gap.
+pub const CHAIN_TOLERANCE: f64 = 1.0e-6;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ChainLink {
+    pub id: usize,
+    /// The curve is walked from its end to its begin.
+    pub reversed: bool,
+}
+
+/// Place where the chain continues over a distance bigger than `CHAIN_TOLERANCE`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ChainGap {
+    /// Index of the link after which the gap is.
+    pub after: usize,
+    pub position: Point,
+    pub distance: f64,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Chain {
+    pub links: Vec<ChainLink>,
+    pub gaps: Vec<ChainGap>,
+    pub closed: bool,
+}
+
+impl Chain {
+    /// The same chain walked the other way, the gaps stay between the same curves.
+    pub fn reversed(&self) -> Self {
+        let count = self.links.len();
+        let mut gaps: Vec<_> = self
+            .gaps
+            .iter()
+            .map(|gap| ChainGap {
+                // the closing gap after the last link stays after the last one
+                after: if gap.after + 1 < count {
+                    count - 2 - gap.after
+                } else {
+                    gap.after
+                },
+                ..*gap
+            })
+            .collect();
+        gaps.sort_by_key(|gap| gap.after);
+        Self {
+            links: self
+                .links
+                .iter()
+                .rev()
+                .map(|link| ChainLink {
+                    id: link.id,
+                    reversed: !link.reversed,
+                })
+                .collect(),
+            gaps,
+            closed: self.closed,
+        }
+    }
+
+    /// Area enclosed by the chain, positive if it goes counterclockwise. The gaps are
+    /// closed by straight lines, an open chain is closed from its end to its begin.
+    pub fn get_signed_area(&self, document: &Document) -> f64 {
+        let mut area = 0.0;
+        for link in &self.links {
+            let curve = match document.get_curve(link.id) {
+                Some(curve) => curve.curve,
+                None => continue,
+            };
+            let curve = if link.reversed {
+                curve.reversed()
+            } else {
+                curve
+            };
+            match curve {
+                curves::Curve::Contour(c) if c.a != 0.0 => {
+                    let radius = c.get_radius();
+                    area += std::f64::consts::PI * radius * radius.abs();
+                }
+                curves::Curve::Contour(_) => {}
+                curves::Curve::Segment(s) => {
+                    // the triangle from zero to the chord and the part of the disk past it
+                    area += curves::points::cross(s.begin, s.end) * 0.5;
+                    if s.contour.a != 0.0 {
+                        let radius = s.contour.get_radius();
+                        let sweep = s.get_sweep() * radius.signum();
+                        area += radius * radius * (sweep - sweep.sin()) * 0.5;
+                    }
+                }
+            }
+        }
+        // the closing side of the gaps and the open ends
+        let ends: Vec<_> = self
+            .links
+            .iter()
+            .filter_map(|link| {
+                let ends = get_ends(document.get_content().get(&link.id)?)?;
+                Some((
+                    link_begin(ends, link.reversed),
+                    link_end(ends, link.reversed),
+                ))
+            })
+            .collect();
+        for (index, (_, end)) in ends.iter().enumerate() {
+            let (next_begin, _) = ends[(index + 1) % ends.len()];
+            area += curves::points::cross(*end, next_begin) * 0.5;
+        }
+        area
+    }
+
+    /// Closed chains go counterclockwise, the open ones stay as they are.
+    pub fn normalized(self, document: &Document) -> Self {
+        if self.closed && self.get_signed_area(document) < 0.0 {
+            self.reversed()
+        } else {
+            self
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+struct Ends {
+    begin: Point,
+    end: Point,
+}
+
+fn get_ends(element: &Element) -> Option<Ends> {
+    match element {
+        Element::Curve(curve) => match curve.curve {
+            curves::Curve::Segment(s) => Some(Ends {
+                begin: s.begin,
+                end: s.end,
+            }),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Link end which is the start of the next link.
+fn link_end(ends: Ends, reversed: bool) -> Point {
+    if reversed {
+        ends.begin
+    } else {
+        ends.end
+    }
+}
+
+fn link_begin(ends: Ends, reversed: bool) -> Point {
+    link_end(ends, !reversed)
+}
+
+struct ChainBuilder<'i> {
+    ends: &'i HashMap<usize, Ends>,
+    used: HashSet<usize>,
+    gap_tolerance: f64,
+}
+
+impl<'i> ChainBuilder<'i> {
+    /// Nearest free curve, which has an end near the position. The link is oriented to
+    /// start at the position.
+    fn find_next(&self, position: Point) -> Option<(ChainLink, f64)> {
+        let mut result = None;
+        let mut best = self.gap_tolerance.max(CHAIN_TOLERANCE);
+        for (id, ends) in self.ends {
+            if self.used.contains(id) {
+                continue;
+            }
+            for reversed in [false, true] {
+                let distance = (link_begin(*ends, reversed) - position).length();
+                if distance <= best {
+                    best = distance;
+                    result = Some((ChainLink { id: *id, reversed }, distance));
+                }
+            }
+        }
+        result
+    }
+
+    fn gap(after: usize, position: Point, distance: f64) -> Option<ChainGap> {
+        if distance > CHAIN_TOLERANCE {
+            Some(ChainGap {
+                after,
+                position,
+                distance,
+            })
+        } else {
+            None
+        }
+    }
+
+    fn build(&mut self, start: usize) -> Option<Chain> {
+        let start_ends = *self.ends.get(&start)?;
+        self.used.insert(start);
+
+        // walk forward from the end of the start curve
+        let mut forward = Vec::new();
+        let mut forward_gaps = Vec::new();
+        let mut position = start_ends.end;
+        while let Some((link, distance)) = self.find_next(position) {
+            forward_gaps.extend(Self::gap(forward.len(), position, distance));
+            self.used.insert(link.id);
+            position = link_end(self.ends[&link.id], link.reversed);
+            forward.push(link);
+        }
+
+        // the chain is closed if it came back to the begin of the start curve
+        let closing_distance = (position - start_ends.begin).length();
+        if closing_distance <= self.gap_tolerance.max(CHAIN_TOLERANCE) {
+            let mut chain = Chain {
+                links: vec![ChainLink {
+                    id: start,
+                    reversed: false,
+                }],
+                gaps: Vec::new(),
+                closed: true,
+            };
+            chain.links.extend(forward);
+            chain.gaps = forward_gaps;
+            let last = chain.links.len() - 1;
+            chain
+                .gaps
+                .extend(Self::gap(last, position, closing_distance));
+            return Some(chain);
+        }
+
+        // walk backward from the begin, these links are put before the start curve
+        let mut backward = Vec::new();
+        let mut backward_gaps = Vec::new();
+        let mut position = start_ends.begin;
+        while let Some((link, distance)) = self.find_next(position) {
+            backward_gaps.push((backward.len(), position, distance));
+            self.used.insert(link.id);
+            position = link_end(self.ends[&link.id], link.reversed);
+            backward.push(ChainLink {
+                id: link.id,
+                reversed: !link.reversed,
+            });
+        }
+
+        let backward_count = backward.len();
+        let mut chain = Chain::default();
+        chain.links.extend(backward.into_iter().rev());
+        chain.links.push(ChainLink {
+            id: start,
+            reversed: false,
+        });
+        chain.links.extend(forward);
+        for (index, position, distance) in backward_gaps {
+            // gap between backward links index and index - 1, counted from the start
+            chain
+                .gaps
+                .extend(Self::gap(backward_count - 1 - index, position, distance));
+        }
+        for gap in forward_gaps {
+            chain.gaps.push(ChainGap {
+                after: gap.after + backward_count,
+                ..gap
+            });
+        }
+        chain.gaps.sort_by_key(|gap| gap.after);
+        Some(chain)
+    }
+}
+
+impl Document {
+    /// Orders the curves connected to the start one by their ends. Ends further than
+    /// `gap_tolerance` are not connected.
+    pub fn find_chain(&self, start: usize, gap_tolerance: f64) -> Option<Chain> {
+        let ends = collect_ends(self);
+        ChainBuilder {
+            ends: &ends,
+            used: HashSet::new(),
+            gap_tolerance,
+        }
+        .build(start)
+    }
+
+    /// Closed chains of segments, and full circles as closed regions of one curve.
+    pub fn find_closed_regions(&self, gap_tolerance: f64) -> Vec<Chain> {
+        let ends = collect_ends(self);
+        let mut result = Vec::new();
+        for (id, element) in self.get_content() {
+            if let Element::Curve(curve) = element {
+                if let curves::Curve::Contour(c) = curve.curve {
+                    if c.a > 0.0 {
+                        result.push(Chain {
+                            links: vec![ChainLink {
+                                id: *id,
+                                reversed: false,
+                            }],
+                            gaps: Vec::new(),
+                            closed: true,
+                        });
+                    }
+                }
+            }
+        }
+
+        let mut ids: Vec<_> = ends.keys().cloned().collect();
+        ids.sort_unstable();
+        let mut builder = ChainBuilder {
+            ends: &ends,
+            used: HashSet::new(),
+            gap_tolerance,
+        };
+        for id in ids {
+            if builder.used.contains(&id) {
+                continue;
+            }
+            if let Some(chain) = builder.build(id) {
+                if chain.closed {
+                    result.push(chain.normalized(self));
+                }
+            }
+        }
+        result
+    }
+
+    /// Turns the selected lines and arcs to go along the chains they make, the closed
+    /// chains go counterclockwise. The unselected curves don't join the chains. One undo
+    /// step, returns false if all the curves already go so.
+    pub fn normalize_selected_directions(&mut self) -> bool {
+        let ends: HashMap<_, _> = collect_ends(self)
+            .into_iter()
+            .filter(|(id, _)| self.get_content()[id].is_selected())
+            .collect();
+        let mut ids: Vec<_> = ends.keys().cloned().collect();
+        ids.sort_unstable();
+        let mut builder = ChainBuilder {
+            ends: &ends,
+            used: HashSet::new(),
+            gap_tolerance: CHAIN_TOLERANCE,
+        };
+        let mut reversed_ids = Vec::new();
+        for id in ids {
+            if builder.used.contains(&id) {
+                continue;
+            }
+            if let Some(chain) = builder.build(id) {
+                let chain = chain.normalized(self);
+                reversed_ids.extend(
+                    chain
+                        .links
+                        .iter()
+                        .filter(|link| link.reversed)
+                        .map(|link| link.id),
+                );
+            }
+        }
+        self.reverse_curves(&reversed_ids)
+    }
+}
+
+fn collect_ends(document: &Document) -> HashMap<usize, Ends> {
+    document
+        .get_content()
+        .iter()
+        .filter_map(|(id, element)| Some((*id, get_ends(element)?)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Segment = curves::Segment<f64>;
+    type Contour = curves::Contour<f64>;
+    type Line = ((f64, f64), (f64, f64));
+
+    fn add_lines(document: &mut Document, lines: &[Line]) -> Vec<usize> {
+        let first = document.get_content().keys().max().map_or(0, |id| id + 1);
+        for &((x1, y1), (x2, y2)) in lines {
+            document.add_entity(Curve::new_segment(Segment::line(
+                Point::new(x1, y1),
+                Point::new(x2, y2),
+            )));
+        }
+        let mut ids: Vec<_> = document
+            .get_content()
+            .keys()
+            .cloned()
+            .filter(|id| *id >= first)
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    fn link(id: usize, reversed: bool) -> ChainLink {
+        ChainLink { id, reversed }
+    }
+
+    #[test]
+    fn open_chain_is_walked_both_ways_from_the_start() {
+        let mut document = Document::new();
+        let ids = add_lines(
+            &mut document,
+            &[
+                ((0.0, 0.0), (1.0, 0.0)),
+                ((2.0, 0.0), (1.0, 0.0)),
+                ((2.0, 0.0), (3.0, 0.0)),
+            ],
+        );
+
+        let chain = document.find_chain(ids[1], CHAIN_TOLERANCE).unwrap();
+        assert!(!chain.closed);
+        assert!(chain.gaps.is_empty());
+        assert_eq!(
+            chain.links,
+            vec![link(ids[2], true), link(ids[1], false), link(ids[0], true)]
+        );
+        assert_eq!(
+            chain.reversed().links,
+            vec![link(ids[0], false), link(ids[1], true), link(ids[2], false)]
+        );
+    }
+
+    #[test]
+    fn gap_joins_the_chain_only_within_the_tolerance() {
+        let mut document = Document::new();
+        let ids = add_lines(
+            &mut document,
+            &[((0.0, 0.0), (1.0, 0.0)), ((1.5, 0.0), (2.0, 0.0))],
+        );
+
+        let chain = document.find_chain(ids[0], 0.1).unwrap();
+        assert_eq!(chain.links, vec![link(ids[0], false)]);
+
+        let chain = document.find_chain(ids[0], 1.0).unwrap();
+        assert_eq!(chain.links, vec![link(ids[0], false), link(ids[1], false)]);
+        assert_eq!(chain.gaps.len(), 1);
+        assert_eq!(chain.gaps[0].after, 0);
+        assert!((chain.gaps[0].distance - 0.5).abs() < 1.0e-9);
+    }
+
+    #[test]
+    fn closed_loop_goes_counterclockwise() {
+        let mut document = Document::new();
+        // clockwise square with one side drawn backward
+        let ids = add_lines(
+            &mut document,
+            &[
+                ((0.0, 0.0), (0.0, 1.0)),
+                ((0.0, 1.0), (1.0, 1.0)),
+                ((1.0, 0.0), (1.0, 1.0)),
+                ((1.0, 0.0), (0.0, 0.0)),
+            ],
+        );
+        document.add_entity(Curve::new_contour(Contour::circle(
+            Point::new(5.0, 5.0),
+            1.0,
+        )));
+
+        let chain = document.find_chain(ids[0], CHAIN_TOLERANCE).unwrap();
+        assert!(chain.closed);
+        assert_eq!(chain.links.len(), 4);
+        assert!((chain.get_signed_area(&document) + 1.0).abs() < 1.0e-9);
+
+        let regions = document.find_closed_regions(CHAIN_TOLERANCE);
+        assert_eq!(regions.len(), 2);
+        for region in &regions {
+            assert!(region.closed);
+            assert!(region.get_signed_area(&document) > 0.0);
+        }
+        let square = regions
+            .iter()
+            .find(|region| region.links.len() == 4)
+            .unwrap();
+        assert!((square.get_signed_area(&document) - 1.0).abs() < 1.0e-9);
+    }
+
+    #[test]
+    fn branching_end_continues_by_one_curve() {
+        let mut document = Document::new();
+        let ids = add_lines(
+            &mut document,
+            &[
+                ((-1.0, 0.0), (0.0, 0.0)),
+                ((0.0, 0.0), (1.0, 0.0)),
+                ((0.0, 0.0), (0.0, 1.0)),
+            ],
+        );
+
+        let chain = document.find_chain(ids[0], CHAIN_TOLERANCE).unwrap();
+        assert!(!chain.closed);
+        assert_eq!(chain.links.len(), 2);
+        assert_eq!(chain.links[0], link(ids[0], false));
+        assert!(!chain.links[1].reversed);
+        assert!(ids[1..].contains(&chain.links[1].id));
+        assert!(document.find_closed_regions(CHAIN_TOLERANCE).is_empty());
+    }
+}
@@ -0,0 +1,129 @@
+use serde::{Deserialize, Serialize};
+
+use crate::document::*;
+use crate::isometric::*;
+use crate::transform::*;
+
+/// Commands kept in the history of the session, the older ones are forgotten.
+const MAX_HISTORY_COMMANDS: usize = 20;
+
+/// Change of the selection with all its parameters, so it can be done again on another
+/// selection.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum Command {
+    /// The caption tells how the transform was given, with the typed or picked values.
+    Transform(String, Transform),
+    Remove,
+    Hide,
+    Isolate,
+    Lock,
+    BringToFront,
+    SendToBack,
+    ConvertToIsometric(Isoplane),
+    /// Both ends of the lines and arcs move by the length, the caption has it formatted.
+    Extend(String, f64),
+    /// Points split the curves into the number of parts.
+    Divide(usize),
+    /// Points go along the curves at the steps, the caption has the step formatted.
+    Measure(String, f64),
+    Reverse,
+    /// The curves go along their chains, the closed chains counterclockwise.
+    NormalizeDirections,
+}
+
+impl Command {
+    pub fn get_caption(&self) -> String {
+        match self {
+            Command::Transform(caption, _) => caption.clone(),
+            Command::Remove => "Удалить".to_string(),
+            Command::Hide => "Скрыть".to_string(),
+            Command::Isolate => "Изолировать".to_string(),
+            Command::Lock => "Заблокировать".to_string(),
+            Command::BringToFront => "На передний план".to_string(),
+            Command::SendToBack => "На задний план".to_string(),
+            Command::ConvertToIsometric(isoplane) => {
+                let isoplane = match isoplane {
+                    Isoplane::Left => "лево",
+                    Isoplane::Top => "верх",
+                    Isoplane::Right => "право",
+                };
+                format!("Круг в изометрию ({})", isoplane)
+            }
+            Command::Extend(caption, _) => caption.clone(),
+            Command::Divide(count) => format!("Разделить на {}", count),
+            Command::Measure(caption, _) => caption.clone(),
+            Command::Reverse => "Развернуть".to_string(),
+            Command::NormalizeDirections => "Выровнять направления".to_string(),
+        }
+    }
+
+    /// Returns false if there was nothing to change.
+    pub fn run(&self, document: &mut Document) -> bool {
+        match self {
+            Command::Transform(_, transform) => document.transform_selected(transform),
+            Command::Remove => {
+                if !document.has_selection() {
+                    return false;
+                }
+                document.remove_selected();
+                true
+            }
+            Command::Hide => document.hide_selected(),
+            Command::Isolate => document.isolate_selected(),
+            Command::Lock => document.lock_selected(),
+            Command::BringToFront => document.bring_selected_to_front(),
+            Command::SendToBack => document.send_selected_to_back(),
+            Command::ConvertToIsometric(isoplane) => {
+                document.convert_selected_circles_to_isometric(*isoplane)
+            }
+            Command::Extend(_, length) => document.extend_selected(*length),
+            Command::Divide(count) => document.divide_selected(*count),
+            Command::Measure(_, step) => document.measure_selected(*step),
+            Command::Reverse => document.reverse_selected(),
+            Command::NormalizeDirections => document.normalize_selected_directions(),
+        }
+    }
+}
+
+/// Commands done in this session, the last one is the latest. A command done again moves
+/// to the end instead of being kept twice. While a macro is recorded, the done commands go
+/// to it too.
+#[derive(Debug, Default)]
+pub struct CommandHistory {
+    commands: Vec<Command>,
+    recording: Option<Vec<Command>>,
+}
+
+impl CommandHistory {
+    pub fn push(&mut self, command: Command) {
+        if let Some(recording) = &mut self.recording {
+            recording.push(command.clone());
+        }
+        self.commands.retain(|c| *c != command);
+        self.commands.push(command);
+        if self.commands.len() > MAX_HISTORY_COMMANDS {
+            self.commands.remove(0);
+        }
+    }
+
+    pub fn get_last(&self) -> Option<&Command> {
+        self.commands.last()
+    }
+
+    pub fn get_commands(&self) -> &[Command] {
+        &self.commands
+    }
+
+    pub fn start_recording(&mut self) {
+        self.recording = Some(Vec::new());
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// Commands done since the start of the recording.
+    pub fn stop_recording(&mut self) -> Vec<Command> {
+        self.recording.take().unwrap_or_default()
+    }
+}
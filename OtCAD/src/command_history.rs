@@ -0,0 +1,64 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use application::callback;
+use application::callback_body;
+use application::font::*;
+use application::gui::gui_components::*;
+use application::gui::*;
+
+use crate::editor::*;
+
+/// Page with the commands done in this session, the latest first. A click closes the page
+/// and does the command again on the selection of the drawing.
+pub fn open_command_history_page(font: &Font, editor: Rc<RefCell<Editor>>) {
+    let commands = editor.borrow().get_command_history();
+
+    let font_height = font.get_size("8").1 as i32 + 2;
+    let mut page = Container::new(
+        SizeConstraints(SizeConstraint::flexible(0), SizeConstraint::flexible(0)),
+        ContainerLayout::Vertical,
+    );
+    if commands.is_empty() {
+        page.add_child(TextBox::new(
+            SizeConstraints(
+                SizeConstraint::flexible(0),
+                SizeConstraint::fixed(font_height),
+            ),
+            "Команд ещё не было".to_string(),
+            font.clone(),
+        ));
+    }
+
+    let mut buttons = Vec::new();
+    for command in commands.into_iter().rev() {
+        let caption = escape_accelerator(&command.get_caption());
+        let button = page.add_child(Button::new(
+            Button::default_size(&caption, None, font),
+            caption,
+            font.clone(),
+        ));
+        buttons.push((button, command));
+    }
+
+    page.add_child(EmptySpace::new_empty(SizeConstraints(
+        SizeConstraint::flexible(0),
+        SizeConstraint::flexible(0),
+    )));
+
+    let document_tab_id = editor.borrow().get_selected_tab_id();
+    let tab_id = match editor.borrow().add_page_tab(font, "История команд", page) {
+        Some(tab_id) => tab_id,
+        None => return,
+    };
+
+    for (button, command) in buttons {
+        button.borrow_mut().set_callback(callback!([editor] () {
+            editor.borrow().close_tab(tab_id);
+            if let Some(document_tab_id) = document_tab_id {
+                editor.borrow().select_tab(document_tab_id);
+            }
+            editor.borrow().run_command(command.clone());
+        }));
+    }
+}
@@ -0,0 +1,303 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use application::font::*;
+use application::gui::toast::*;
+use window::show_open_file_dialog;
+
+use crate::document::*;
+use crate::editor::*;
+use crate::gui_helper::*;
+
+type Point = curves::points::Point<f64>;
+type Contour = curves::Contour<f64>;
+
+/// Entities closer than this are treated as the same.
+pub const COMPARE_TOLERANCE: f64 = 1.0e-6;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum DifferenceKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+impl DifferenceKind {
+    pub fn get_name(self) -> &'static str {
+        match self {
+            DifferenceKind::Added => "Добавлено",
+            DifferenceKind::Removed => "Удалено",
+            DifferenceKind::Modified => "Изменено",
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Difference {
+    pub kind: DifferenceKind,
+    /// Id in the compare document.
+    pub id: usize,
+    pub position: Point,
+}
+
+fn same_point(a: Point, b: Point, tolerance: f64) -> bool {
+    (a - b).length() <= tolerance
+}
+
+fn same_contour(a: &Contour, b: &Contour, tolerance: f64) -> bool {
+    if (a.a > 0.0) != (b.a > 0.0) {
+        return false;
+    }
+    if a.a > 0.0 {
+        return same_point(a.get_center(), b.get_center(), tolerance)
+            && (a.get_radius() - b.get_radius()).abs() <= tolerance;
+    }
+    // the line equation is defined up to the sign
+    let sign = if curves::points::dot(a.n, b.n) < 0.0 {
+        -1.0
+    } else {
+        1.0
+    };
+    same_point(a.n, b.n.scale(sign), tolerance) && (a.c - b.c * sign).abs() <= tolerance
+}
+
+fn same_curve(a: &curves::Curve<f64>, b: &curves::Curve<f64>, tolerance: f64) -> bool {
+    match (a, b) {
+        (curves::Curve::Segment(a), curves::Curve::Segment(b)) => {
+            if a.contour.a == 0.0 && b.contour.a == 0.0 {
+                // a line does not change when its ends are swapped
+                (same_point(a.begin, b.begin, tolerance) && same_point(a.end, b.end, tolerance))
+                    || (same_point(a.begin, b.end, tolerance)
+                        && same_point(a.end, b.begin, tolerance))
+            } else {
+                same_contour(&a.contour, &b.contour, tolerance)
+                    && a.big == b.big
+                    && same_point(a.begin, b.begin, tolerance)
+                    && same_point(a.end, b.end, tolerance)
+            }
+        }
+        (curves::Curve::Contour(a), curves::Curve::Contour(b)) => same_contour(a, b, tolerance),
+        _ => false,
+    }
+}
+
+fn same_element(a: &Element, b: &Element, tolerance: f64) -> bool {
+    match (a, b) {
+        (Element::Curve(a), Element::Curve(b)) => same_curve(&a.curve, &b.curve, tolerance),
+        (Element::Point(a), Element::Point(b)) => same_point(a.position, b.position, tolerance),
+        _ => false,
+    }
+}
+
+fn same_type(a: &Element, b: &Element) -> bool {
+    matches!(
+        (a, b),
+        (Element::Curve(_), Element::Curve(_)) | (Element::Point(_), Element::Point(_))
+    )
+}
+
+fn sorted_ids(document: &Document) -> Vec<usize> {
+    let mut ids: Vec<_> = document
+        .get_content()
+        .iter()
+        .filter(|(_, element)| !matches!(element, Element::Group(_)))
+        .map(|(id, _)| *id)
+        .collect();
+    ids.sort_unstable();
+    ids
+}
+
+/// Document with the new entities and the removed old ones, and the differences in it.
+/// Unmatched entities with the same id and type in both documents are counted as modified.
+pub fn compare_documents(
+    old: &Document,
+    new: &Document,
+    tolerance: f64,
+) -> (Document, Vec<Difference>) {
+    let old_ids = sorted_ids(old);
+    let new_ids = sorted_ids(new);
+    let old_content = old.get_content();
+    let new_content = new.get_content();
+
+    let mut matched_old = HashSet::new();
+    let mut unmatched_new = Vec::new();
+    for new_id in &new_ids {
+        let new_element = &new_content[new_id];
+        // the same id is checked first, usually nothing is renumbered
+        let candidate = std::iter::once(new_id)
+            .chain(old_ids.iter())
+            .find(|old_id| {
+                !matched_old.contains(*old_id)
+                    && old_content
+                        .get(*old_id)
+                        .map(|e| same_element(e, new_element, tolerance))
+                        .unwrap_or(false)
+            });
+        match candidate {
+            Some(old_id) => {
+                matched_old.insert(*old_id);
+            }
+            None => unmatched_new.push(*new_id),
+        }
+    }
+
+    let mut result = Document::new();
+    result.set_metadata(new.get_metadata().clone());
+    result.set_canvas_style(*new.get_canvas_style());
+    let mut differences = Vec::new();
+    let mut push = |result: &mut Document, element: &Element, kind: DifferenceKind| {
+        if let Some(id) = result.add_element(element) {
+            if let Some(position) = element.get_position() {
+                differences.push(Difference { kind, id, position });
+            }
+        }
+    };
+
+    for new_id in &new_ids {
+        let element = &new_content[new_id];
+        if !unmatched_new.contains(new_id) {
+            result.add_element(element);
+            continue;
+        }
+        let modified = !matched_old.contains(new_id)
+            && old_content
+                .get(new_id)
+                .map(|e| same_type(e, element))
+                .unwrap_or(false);
+        if modified {
+            matched_old.insert(*new_id);
+            push(&mut result, element, DifferenceKind::Modified);
+        } else {
+            push(&mut result, element, DifferenceKind::Added);
+        }
+    }
+    for old_id in &old_ids {
+        if !matched_old.contains(old_id) {
+            push(&mut result, &old_content[old_id], DifferenceKind::Removed);
+        }
+    }
+
+    // the compare document is not edited, so there is nothing to undo
+    result.fix_history();
+    (result, differences)
+}
+
+pub fn compare_files(
+    font: &Font,
+    editor: Rc<RefCell<Editor>>,
+    context: Rc<RefCell<window::Context>>,
+) {
+    let open = |caption: &str| -> Option<Document> {
+        let path = show_open_file_dialog(context.clone(), "Чертежи OtCAD", "json")?;
+        match Document::load(&path) {
+            Ok(document) => Some(document),
+            Err(e) => {
+                show_message(context.clone(), &e, caption);
+                None
+            }
+        }
+    };
+    let old = match open("Ошибка открытия старого файла") {
+        Some(old) => old,
+        None => return,
+    };
+    let new = match open("Ошибка открытия нового файла") {
+        Some(new) => new,
+        None => return,
+    };
+
+    let (document, differences) = compare_documents(&old, &new, COMPARE_TOLERANCE);
+    if differences.is_empty() {
+        context
+            .borrow_mut()
+            .gui_system
+            .show_toast(Toast::info("Различий не найдено"));
+        return;
+    }
+    let kinds: HashMap<usize, DifferenceKind> =
+        differences.iter().map(|d| (d.id, d.kind)).collect();
+    editor
+        .borrow_mut()
+        .add_compare_tab(font, document, differences, Rc::new(kinds));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Segment = curves::Segment<f64>;
+
+    fn add_circle(document: &mut Document, x: f64, radius: f64) {
+        document.add_entity(Curve::new_contour(Contour::circle(
+            Point::new(x, 0.0),
+            radius,
+        )));
+    }
+
+    fn get_kinds_and_positions(differences: &[Difference]) -> Vec<(DifferenceKind, Point)> {
+        let mut result: Vec<_> = differences
+            .iter()
+            .map(|difference| (difference.kind, difference.position))
+            .collect();
+        result.sort_by(|a, b| a.1.x.total_cmp(&b.1.x));
+        result
+    }
+
+    #[test]
+    fn added_removed_and_modified_entities() {
+        let mut old = Document::new();
+        add_circle(&mut old, 0.0, 1.0);
+        add_circle(&mut old, 10.0, 1.0);
+        add_circle(&mut old, 20.0, 1.0);
+
+        let mut new = Document::new();
+        add_circle(&mut new, 0.0, 1.0);
+        add_circle(&mut new, 10.0, 2.0);
+        add_circle(&mut new, 20.0, 1.0);
+        let last = *new.get_content().keys().max().unwrap();
+        new.select_only(&HashSet::from([last]));
+        new.remove_selected();
+        add_circle(&mut new, 30.0, 1.0);
+
+        let (document, differences) = compare_documents(&old, &new, COMPARE_TOLERANCE);
+        assert_eq!(
+            get_kinds_and_positions(&differences),
+            vec![
+                (DifferenceKind::Modified, Point::new(10.0, 0.0)),
+                (DifferenceKind::Removed, Point::new(20.0, 0.0)),
+                (DifferenceKind::Added, Point::new(30.0, 0.0)),
+            ]
+        );
+        // the unchanged circle is kept, the removed one is put back to be shown
+        assert_eq!(document.get_content().len(), 4);
+        for difference in &differences {
+            assert!(document.get_content().contains_key(&difference.id));
+        }
+    }
+
+    #[test]
+    fn renumbered_and_reversed_entities_are_the_same() {
+        let mut old = Document::new();
+        old.add_entity(Curve::new_segment(Segment::line(
+            Point::new(0.0, 0.0),
+            Point::new(1.0, 0.0),
+        )));
+
+        let mut new = Document::new();
+        new.add_points(&[Point::new(5.0, 5.0)]);
+        new.add_entity(Curve::new_segment(Segment::line(
+            Point::new(1.0, 0.0),
+            Point::new(0.0, 1.0e-9),
+        )));
+
+        let (_, differences) = compare_documents(&old, &new, COMPARE_TOLERANCE);
+        assert_eq!(
+            get_kinds_and_positions(&differences),
+            vec![(DifferenceKind::Added, Point::new(5.0, 5.0))]
+        );
+        assert!(compare_documents(&new, &new, COMPARE_TOLERANCE)
+            .1
+            .is_empty());
+    }
+}
@@ -1,7 +1,14 @@
 use directories::*;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
 use std::path::*;
+use std::time::SystemTime;
 
+use application::angle_format::AngleFormat;
+use application::number_format::NumberFormat;
+
+use crate::isometric::*;
+use crate::macros::Macro;
 use window::*;
 
 #[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Copy, Clone)]
@@ -9,6 +16,9 @@ pub enum ColorTheme {
     Dark,
     Beige,
     Light,
+    /// Colors which are told apart by the color blind.
+    ColorBlind,
+    HighContrast,
 }
 
 impl Default for ColorTheme {
@@ -30,6 +40,62 @@ impl Default for CurvesAAMode {
     }
 }
 
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Copy, Clone)]
+pub enum PointStyle {
+    Dot,
+    Cross,
+    Circle,
+}
+
+impl Default for PointStyle {
+    fn default() -> Self {
+        PointStyle::Cross
+    }
+}
+
+/// How the selected curves differ from the others.
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Copy, Clone)]
+pub enum SelectionStyle {
+    Thick,
+    Color,
+    Dashed,
+    Halo,
+}
+
+impl Default for SelectionStyle {
+    fn default() -> Self {
+        SelectionStyle::Thick
+    }
+}
+
+/// How the curve under the mouse differs from the others.
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Copy, Clone)]
+pub enum HoverStyle {
+    Color,
+    ThickColor,
+    Off,
+}
+
+impl Default for HoverStyle {
+    fn default() -> Self {
+        HoverStyle::Color
+    }
+}
+
+/// Lines through the picked point across the whole view.
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Copy, Clone)]
+pub enum CrosshairStyle {
+    Off,
+    Solid,
+    Dashed,
+}
+
+impl Default for CrosshairStyle {
+    fn default() -> Self {
+        CrosshairStyle::Off
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Default, Eq, PartialEq, Copy, Clone)]
 pub struct SnapOptions {
     #[serde(default)]
@@ -43,6 +109,45 @@ pub struct SnapOptions {
 
     #[serde(default)]
     pub snap_centers: bool,
+
+    #[serde(default)]
+    pub snap_points: bool,
+
+    #[serde(default)]
+    pub snap_midpoints: bool,
+
+    /// Points of circles and arcs on the horizontal and vertical lines through the center.
+    #[serde(default)]
+    pub snap_quadrants: bool,
+
+    /// Away from the snap points the mouse goes to the same X or Y as the key points,
+    /// to the equal spacing and to the lines parallel to the drawing.
+    #[serde(default)]
+    pub smart_guides: bool,
+}
+
+/// Missing fields are taken from the default limits, not zeroed.
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Copy, Clone)]
+#[serde(default)]
+pub struct DocumentLimits {
+    pub max_entities: usize,
+    /// Bytes, the oldest undo steps are dropped above it.
+    pub max_undo_memory: usize,
+    /// The undo history is written to the file, so it goes on after reopening.
+    pub save_undo_history: bool,
+    /// Bytes of the undo history in the file, the oldest steps are not written above it.
+    pub max_saved_undo_memory: usize,
+}
+
+impl Default for DocumentLimits {
+    fn default() -> Self {
+        Self {
+            max_entities: 1_000_000,
+            max_undo_memory: 256 << 20,
+            save_undo_history: true,
+            max_saved_undo_memory: 16 << 20,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Copy, Clone)]
@@ -60,7 +165,55 @@ impl Default for ConfigFontSize {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Default)]
+/// Step of the polar tracking in degrees, zero turns it off.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Copy, Clone)]
+pub struct PolarStep(pub f64);
+
+impl Default for PolarStep {
+    fn default() -> Self {
+        Self(15.0)
+    }
+}
+
+/// Master switch of the object snap, the snap options choose the kinds of points.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Copy, Clone)]
+pub struct SnapEnabled(pub bool);
+
+impl Default for SnapEnabled {
+    fn default() -> Self {
+        Self(true)
+    }
+}
+
+/// Questions which can be turned off by "Больше не спрашивать", the ids and the
+/// descriptions for the settings.
+pub const CONFIRMATIONS: [(&str, &str); 1] = [(CONFIRM_REMOVE_MANY, "Удаление многих объектов")];
+
+pub const CONFIRM_REMOVE_MANY: &str = "remove_many";
+
+/// Font size for which the distances in pixels are given, they grow with the font, so they
+/// look the same on the screens with more dots per inch.
+const BASE_FONT_SIZE: f64 = 16.0;
+
+/// Distances from the mouse in pixels at `BASE_FONT_SIZE`.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Copy, Clone)]
+pub struct PickOptions {
+    /// Curves closer than this are highlighted and selected by a click.
+    pub pick_radius: f64,
+    /// Snap points closer than this attract the mouse.
+    pub snap_radius: f64,
+}
+
+impl Default for PickOptions {
+    fn default() -> Self {
+        Self {
+            pick_radius: 20.0,
+            snap_radius: 20.0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
 pub struct Config {
     #[serde(default)]
     pub font_size: ConfigFontSize,
@@ -77,11 +230,123 @@ pub struct Config {
     #[serde(default)]
     pub show_grid: bool,
 
+    /// Curves with widths are drawn as wide as they are on the current scale, otherwise
+    /// all curves are thin.
+    #[serde(default)]
+    pub true_widths: bool,
+
+    /// The wheel zooms in small steps on the timer instead of one jump.
+    #[serde(default)]
+    pub smooth_zoom: bool,
+
+    #[serde(default)]
+    pub isometric: bool,
+
+    #[serde(default)]
+    pub isoplane: Isoplane,
+
+    #[serde(default)]
+    pub snap_enabled: SnapEnabled,
+
     #[serde(default)]
     pub snap_options: SnapOptions,
 
+    /// Lines drawn from a point go along the axes of the coordinate system, or along the
+    /// isometric axes.
+    #[serde(default)]
+    pub ortho: bool,
+
+    #[serde(default)]
+    pub point_style: PointStyle,
+
+    #[serde(default)]
+    pub pick_options: PickOptions,
+
+    #[serde(default)]
+    pub polar_step: PolarStep,
+
+    #[serde(default)]
+    pub selection_style: SelectionStyle,
+
+    #[serde(default)]
+    pub hover_style: HoverStyle,
+
+    /// The crosshair goes through the snapped point and shows its coordinates.
+    #[serde(default)]
+    pub crosshair: CrosshairStyle,
+
+    #[serde(default)]
+    pub number_format: NumberFormat,
+
+    #[serde(default)]
+    pub angle_format: AngleFormat,
+
+    #[serde(default)]
+    pub document_limits: DocumentLimits,
+
+    /// Repaints and timers are limited, so the idle program doesn't load the processor.
+    #[serde(default)]
+    pub frame_pacing: FramePacing,
+
+    /// The frames are always drawn in software, this only chooses how they are shown.
+    #[serde(default)]
+    pub presentation: Presentation,
+
+    /// Places of the window by the keys of the sets of monitors, so the place on a
+    /// removed monitor is not used.
+    #[serde(default)]
+    pub window_positions: BTreeMap<String, WindowPosition>,
+
+    /// Opened and saved files, the last one first.
+    #[serde(default)]
+    pub recent_files: Vec<PathBuf>,
+
+    /// Recorded macros, each has a button in the macro menu.
+    #[serde(default)]
+    pub macros: Vec<Macro>,
+
+    /// Ids of the questions which are not asked, the default answer is taken instead.
     #[serde(default)]
-    pub window_position: Option<WindowPosition>,
+    pub suppressed_confirmations: BTreeSet<String>,
+}
+
+impl Config {
+    /// Font and the state of option controls are baked into the gui, so changing them
+    /// requires rebuilding it. Options bound to properties of the editor are not here.
+    pub fn needs_gui_rebuild(&self, other: &Config) -> bool {
+        self.font_size != other.font_size
+            || self.font_aa_mode != other.font_aa_mode
+            || self.color_theme != other.color_theme
+            || self.curves_aa_mode != other.curves_aa_mode
+            || self.isometric != other.isometric
+            || self.isoplane != other.isoplane
+            || self.snap_options != other.snap_options
+            || self.macros != other.macros
+    }
+}
+
+impl Config {
+    /// Screen pixels in a pixel of the distances given in the config.
+    pub fn get_pixel_scale(&self) -> f64 {
+        self.font_size.0 as f64 / BASE_FONT_SIZE
+    }
+
+    pub fn get_pick_radius(&self) -> f64 {
+        self.pick_options.pick_radius * self.get_pixel_scale()
+    }
+
+    pub fn get_snap_radius(&self) -> f64 {
+        self.pick_options.snap_radius * self.get_pixel_scale()
+    }
+
+    /// Directions in degrees for drawing with the angle snap.
+    pub fn get_snap_angles(&self) -> &'static [f64] {
+        if self.isometric {
+            &ISOMETRIC_SNAP_ANGLES
+        } else {
+            &[0.0, 90.0]
+        }
+    }
 }
 
 pub fn get_project_dir() -> Option<ProjectDirs> {
@@ -104,7 +369,54 @@ pub fn load_config() -> Option<Config> {
 pub fn save_config(config: &Config) -> Option<()> {
     let config_dir = get_project_dir()?.config_dir().to_path_buf();
     let config_file = Path::join(&config_dir, CONFIG_FILE);
+    let temp_file = config_file.with_extension("json.tmp");
     std::fs::create_dir_all(&config_dir).ok()?;
-    serde_json::to_writer_pretty(&std::fs::File::create(config_file).ok()?, &config).ok()?;
+    // Write a temporary file first, so a crash never leaves a truncated config
+    serde_json::to_writer_pretty(&std::fs::File::create(&temp_file).ok()?, &config).ok()?;
+    std::fs::rename(temp_file, config_file).ok()?;
     Some(())
 }
+
+/// Settings file to move them to another computer, the marker tells it from the
+/// other json files.
+#[derive(Serialize, Deserialize)]
+struct SettingsProfile {
+    otcad_settings: u32,
+    config: Config,
+}
+
+const SETTINGS_PROFILE_VERSION: u32 = 1;
+
+/// The place of the window and the recent files belong to this computer, they are not
+/// written.
+pub fn export_settings(config: &Config, path: &Path) -> Result<(), String> {
+    let mut config = config.clone();
+    config.window_positions.clear();
+    config.recent_files.clear();
+    let profile = SettingsProfile {
+        otcad_settings: SETTINGS_PROFILE_VERSION,
+        config,
+    };
+    let text = serde_json::to_string_pretty(&profile).map_err(|e| e.to_string())?;
+    std::fs::write(path, text).map_err(|e| e.to_string())
+}
+
+/// Settings from the file, the place of the window and the recent files are kept from
+/// the current ones.
+pub fn import_settings(config: &Config, path: &Path) -> Result<Config, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let profile: SettingsProfile =
+        serde_json::from_str(&text).map_err(|_| "Это не файл настроек OtCAD".to_string())?;
+    if profile.otcad_settings > SETTINGS_PROFILE_VERSION {
+        return Err("Файл настроек от более новой версии OtCAD".to_string());
+    }
+    let mut result = profile.config;
+    result.window_positions = config.window_positions.clone();
+    result.recent_files = config.recent_files.clone();
+    Ok(result)
+}
+
+pub fn get_config_modified_time() -> Option<SystemTime> {
+    let config_file = Path::join(get_project_dir()?.config_dir(), CONFIG_FILE);
+    std::fs::metadata(config_file).ok()?.modified().ok()
+}
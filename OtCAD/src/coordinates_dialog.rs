@@ -0,0 +1,567 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use application::callback;
+use application::callback_body;
+use application::font::*;
+use application::gui::gui_components::*;
+use application::gui::*;
+
+use crate::document::*;
+use crate::editor::*;
+use crate::gui_helper::*;
+
+type Point = curves::points::Point<f64>;
+
+fn get_fields(parameters: CurveParameters) -> Vec<(&'static str, f64)> {
+    match parameters {
+        CurveParameters::Line { begin, end } => vec![
+            ("X начала:", begin.x),
+            ("Y начала:", begin.y),
+            ("X конца:", end.x),
+            ("Y конца:", end.y),
+        ],
+        CurveParameters::Arc { center, radius } | CurveParameters::Circle { center, radius } => {
+            vec![
+                ("X центра:", center.x),
+                ("Y центра:", center.y),
+                ("Радиус:", radius),
+            ]
+        }
+    }
+}
+
+fn set_fields(parameters: CurveParameters, values: &[f64]) -> CurveParameters {
+    match parameters {
+        CurveParameters::Line { .. } => CurveParameters::Line {
+            begin: Point::new(values[0], values[1]),
+            end: Point::new(values[2], values[3]),
+        },
+        CurveParameters::Arc { .. } => CurveParameters::Arc {
+            center: Point::new(values[0], values[1]),
+            radius: values[2],
+        },
+        CurveParameters::Circle { .. } => CurveParameters::Circle {
+            center: Point::new(values[0], values[1]),
+            radius: values[2],
+        },
+    }
+}
+
+/// Points of the parameters moved to other coordinates, the radius stays.
+fn map_points(parameters: CurveParameters, map: impl Fn(Point) -> Point) -> CurveParameters {
+    match parameters {
+        CurveParameters::Line { begin, end } => CurveParameters::Line {
+            begin: map(begin),
+            end: map(end),
+        },
+        CurveParameters::Arc { center, radius } => CurveParameters::Arc {
+            center: map(center),
+            radius,
+        },
+        CurveParameters::Circle { center, radius } => CurveParameters::Circle {
+            center: map(center),
+            radius,
+        },
+    }
+}
+
+/// Opens a page with the defining values of the selected curve, so they can be
+/// entered exactly. The points are in the UCS. Changes are applied as one undo step.
+pub fn open_coordinates_dialog(
+    font: &Font,
+    editor: Rc<RefCell<Editor>>,
+    context: Rc<RefCell<window::Context>>,
+) {
+    let caption = "Координаты";
+    let document = match editor.borrow().get_active_document() {
+        Some(document) => document,
+        None => return,
+    };
+    let selected = document.borrow().get_single_selected();
+    let (id, curve) =
+        match selected.and_then(|id| Some((id, document.borrow().get_curve(id)?.clone()))) {
+            Some(selected) => selected,
+            None => {
+                show_message(context, "Выделите один объект", caption);
+                return;
+            }
+        };
+    let parameters = match curve.get_parameters() {
+        Some(parameters) => parameters,
+        None => {
+            show_message(context, "У объекта нет координат для ввода", caption);
+            return;
+        }
+    };
+
+    let number_format = editor.borrow().config.borrow().number_format;
+    let ucs = *document.borrow().get_ucs();
+    let fields = get_fields(map_points(parameters, |point| ucs.to_local(point)));
+    let font_height = font.get_size("8").1 as i32 + 2;
+    let caption_width = fields
+        .iter()
+        .map(|(name, _)| GuiSystem::default_size(name, None, font).0.absolute)
+        .max()
+        .unwrap_or(0);
+
+    let mut page = Container::new(
+        SizeConstraints(SizeConstraint::flexible(0), SizeConstraint::flexible(0)),
+        ContainerLayout::Vertical,
+    );
+
+    let mut inputs = Vec::new();
+    for (name, value) in &fields {
+        let line = page.add_child(Container::new(
+            SizeConstraints(
+                SizeConstraint::flexible(0),
+                SizeConstraint::fixed(font_height),
+            ),
+            ContainerLayout::Horizontal,
+        ));
+        line.borrow_mut().add_child(TextBox::new(
+            SizeConstraints(
+                SizeConstraint::fixed(caption_width),
+                SizeConstraint::fixed(font_height),
+            ),
+            name.to_string(),
+            font.clone(),
+        ));
+        let input = line.borrow_mut().add_child(create_default_size_edit(
+            "-8888888.888",
+            font.clone(),
+            context.borrow().clipboard.clone(),
+        ));
+        input.borrow_mut().set_text(&number_format.format(*value));
+        inputs.push(input);
+    }
+
+    let buttons_line = page.add_child(Container::new(
+        SizeConstraints(
+            SizeConstraint::flexible(0),
+            SizeConstraint::fixed(font_height),
+        ),
+        ContainerLayout::Horizontal,
+    ));
+    let ok_button = buttons_line
+        .borrow_mut()
+        .add_child(create_default_size_button("ОК", font.clone()));
+    let cancel_button = buttons_line
+        .borrow_mut()
+        .add_child(create_default_size_button("Отмена", font.clone()));
+
+    page.add_child(EmptySpace::new_empty(SizeConstraints(
+        SizeConstraint::flexible(0),
+        SizeConstraint::flexible(0),
+    )));
+
+    let document_tab_id = editor.borrow().get_selected_tab_id();
+    let tab_id = match editor.borrow().add_page_tab(font, caption, page) {
+        Some(tab_id) => tab_id,
+        None => return,
+    };
+
+    let close = Rc::new(callback!([editor] () {
+        editor.borrow().close_tab(tab_id);
+        if let Some(document_tab_id) = document_tab_id {
+            editor.borrow().select_tab(document_tab_id);
+        }
+    }));
+
+    {
+        let close = close.clone();
+        // inputs are owned by the page and the callback, there is no cycle
+        ok_button
+            .borrow_mut()
+            .set_callback(callback!([context, document] () {
+                let mut values = Vec::new();
+                for input in &inputs {
+                    let text = input.borrow().get_text().to_string();
+                    match number_format.parse(&text) {
+                        Some(value) => values.push(value),
+                        None => {
+                            show_message(
+                                context.clone(),
+                                &format!("{} - не число!", text),
+                                "Ошибка ввода",
+                            );
+                            return;
+                        }
+                    }
+                }
+
+                let parameters = set_fields(parameters, &values);
+                match curve.with_parameters(map_points(parameters, |point| ucs.to_world(point))) {
+                    Some(new_curve) => {
+                        document.borrow_mut().replace_curve(id, new_curve);
+                        close();
+                    }
+                    None => show_message(
+                        context.clone(),
+                        "Недопустимые значения",
+                        "Ошибка ввода",
+                    ),
+                }
+            }));
+    }
+
+    cancel_button.borrow_mut().set_callback(move || close());
+}
+
+/// Opens a page with the width of the selected curves in the document units, an empty
+/// width makes them thin.
+pub fn open_width_dialog(
+    font: &Font,
+    editor: Rc<RefCell<Editor>>,
+    context: Rc<RefCell<window::Context>>,
+) {
+    let caption = "Толщина линий";
+    let document = match editor.borrow().get_active_document() {
+        Some(document) => document,
+        None => return,
+    };
+    let widths: Vec<_> = document
+        .borrow()
+        .get_content()
+        .values()
+        .filter_map(|element| match element {
+            Element::Curve(curve) if curve.selected => Some(curve.width),
+            _ => None,
+        })
+        .collect();
+    if widths.is_empty() {
+        show_message(context, "Ничего не выделено", caption);
+        return;
+    }
+
+    let number_format = editor.borrow().config.borrow().number_format;
+    let font_height = font.get_size("8").1 as i32 + 2;
+    let mut page = Container::new(
+        SizeConstraints(SizeConstraint::flexible(0), SizeConstraint::flexible(0)),
+        ContainerLayout::Vertical,
+    );
+
+    let line = page.add_child(Container::new(
+        SizeConstraints(
+            SizeConstraint::flexible(0),
+            SizeConstraint::fixed(font_height),
+        ),
+        ContainerLayout::Horizontal,
+    ));
+    line.borrow_mut()
+        .add_child(create_default_size_text_box("Толщина:", font.clone()));
+    let input = line.borrow_mut().add_child(create_default_size_edit(
+        "-8888888.888",
+        font.clone(),
+        context.borrow().clipboard.clone(),
+    ));
+    // different widths leave the field empty
+    if let Some(width) = widths[0].filter(|_| widths.iter().all(|w| *w == widths[0])) {
+        input.borrow_mut().set_text(&number_format.format(width));
+    }
+
+    let buttons_line = page.add_child(Container::new(
+        SizeConstraints(
+            SizeConstraint::flexible(0),
+            SizeConstraint::fixed(font_height),
+        ),
+        ContainerLayout::Horizontal,
+    ));
+    let ok_button = buttons_line
+        .borrow_mut()
+        .add_child(create_default_size_button("ОК", font.clone()));
+    let cancel_button = buttons_line
+        .borrow_mut()
+        .add_child(create_default_size_button("Отмена", font.clone()));
+
+    page.add_child(EmptySpace::new_empty(SizeConstraints(
+        SizeConstraint::flexible(0),
+        SizeConstraint::flexible(0),
+    )));
+
+    let document_tab_id = editor.borrow().get_selected_tab_id();
+    let tab_id = match editor.borrow().add_page_tab(font, caption, page) {
+        Some(tab_id) => tab_id,
+        None => return,
+    };
+
+    let close = Rc::new(callback!([editor] () {
+        editor.borrow().close_tab(tab_id);
+        if let Some(document_tab_id) = document_tab_id {
+            editor.borrow().select_tab(document_tab_id);
+        }
+    }));
+
+    {
+        let close = close.clone();
+        ok_button
+            .borrow_mut()
+            .set_callback(callback!([context, document] () {
+                let text = input.borrow().get_text().trim().to_string();
+                let width = if text.is_empty() {
+                    None
+                } else {
+                    match number_format.parse(&text) {
+                        Some(width) if width > 0.0 => Some(width),
+                        _ => {
+                            show_message(
+                                context.clone(),
+                                &format!("{} - не положительное число!", text),
+                                "Ошибка ввода",
+                            );
+                            return;
+                        }
+                    }
+                };
+                document.borrow_mut().set_selected_width(width);
+                close();
+            }));
+    }
+
+    cancel_button.borrow_mut().set_callback(move || close());
+}
+
+/// Asks the opacity of the selected curves in percent, faint curves show the reference
+/// geometry.
+pub fn open_opacity_dialog(
+    font: &Font,
+    editor: Rc<RefCell<Editor>>,
+    context: Rc<RefCell<window::Context>>,
+) {
+    let caption = "Непрозрачность";
+    let document = match editor.borrow().get_active_document() {
+        Some(document) => document,
+        None => return,
+    };
+    let opacities: Vec<_> = document
+        .borrow()
+        .get_content()
+        .values()
+        .filter_map(|element| match element {
+            Element::Curve(curve) if curve.selected => Some(curve.opacity),
+            _ => None,
+        })
+        .collect();
+    if opacities.is_empty() {
+        show_message(context, "Ничего не выделено", caption);
+        return;
+    }
+
+    // different opacities leave the field empty
+    let text = if opacities.iter().all(|o| *o == opacities[0]) {
+        opacities[0].0.to_string()
+    } else {
+        String::new()
+    };
+    let on_ok = move |text: &str| match text.trim().parse::<u8>() {
+        Ok(percent) if percent <= 100 => {
+            document.borrow_mut().set_selected_opacity(Opacity(percent));
+            Ok(())
+        }
+        _ => Err(format!("{} - не число от 0 до 100!", text)),
+    };
+    open_input_dialog(
+        font,
+        editor,
+        context,
+        caption,
+        ("Непрозрачность, %:", &text),
+        on_ok,
+        None,
+    );
+}
+
+/// Opens a page with one text field, given by its caption and text. `on_ok` returns the
+/// error to show, otherwise the page is closed. With `on_pick` the page has a button
+/// which closes it and calls `on_pick`, so the value can be picked in the drawing
+/// instead. The field is returned, so it can get a completion or a validator.
+pub fn open_input_dialog(
+    font: &Font,
+    editor: Rc<RefCell<Editor>>,
+    context: Rc<RefCell<window::Context>>,
+    caption: &str,
+    (field, text): (&str, &str),
+    on_ok: impl Fn(&str) -> Result<(), String> + 'static,
+    on_pick: Option<Rc<dyn Fn()>>,
+) -> Option<Rc<RefCell<Edit>>> {
+    let font_height = font.get_size("8").1 as i32 + 2;
+    let mut page = Container::new(
+        SizeConstraints(SizeConstraint::flexible(0), SizeConstraint::flexible(0)),
+        ContainerLayout::Vertical,
+    );
+
+    let line = page.add_child(Container::new(
+        SizeConstraints(
+            SizeConstraint::flexible(0),
+            SizeConstraint::fixed(font_height),
+        ),
+        ContainerLayout::Horizontal,
+    ));
+    line.borrow_mut()
+        .add_child(create_default_size_text_box(field, font.clone()));
+    let input = line.borrow_mut().add_child(create_default_size_edit(
+        "-8888888.888; -8888888.888",
+        font.clone(),
+        context.borrow().clipboard.clone(),
+    ));
+    input.borrow_mut().set_text(text);
+
+    let buttons_line = page.add_child(Container::new(
+        SizeConstraints(
+            SizeConstraint::flexible(0),
+            SizeConstraint::fixed(font_height),
+        ),
+        ContainerLayout::Horizontal,
+    ));
+    let ok_button = buttons_line
+        .borrow_mut()
+        .add_child(create_default_size_button("ОК", font.clone()));
+    let pick_button = on_pick.as_ref().map(|_| {
+        buttons_line
+            .borrow_mut()
+            .add_child(create_default_size_button(
+                "Указать на чертеже",
+                font.clone(),
+            ))
+    });
+    let cancel_button = buttons_line
+        .borrow_mut()
+        .add_child(create_default_size_button("Отмена", font.clone()));
+
+    page.add_child(EmptySpace::new_empty(SizeConstraints(
+        SizeConstraint::flexible(0),
+        SizeConstraint::flexible(0),
+    )));
+
+    let document_tab_id = editor.borrow().get_selected_tab_id();
+    let tab_id = match editor.borrow().add_page_tab(font, caption, page) {
+        Some(tab_id) => tab_id,
+        None => return None,
+    };
+
+    let close = Rc::new(callback!([editor] () {
+        editor.borrow().close_tab(tab_id);
+        if let Some(document_tab_id) = document_tab_id {
+            editor.borrow().select_tab(document_tab_id);
+        }
+    }));
+
+    {
+        let close = close.clone();
+        let input = input.clone();
+        ok_button.borrow_mut().set_callback(callback!([context] () {
+            let text = input.borrow().get_text().trim().to_string();
+            match on_ok(&text) {
+                Ok(()) => close(),
+                Err(error) => show_message(context.clone(), &error, "Ошибка ввода"),
+            }
+        }));
+    }
+
+    if let (Some(pick_button), Some(on_pick)) = (pick_button, on_pick) {
+        let close = close.clone();
+        pick_button.borrow_mut().set_callback(move || {
+            close();
+            on_pick();
+        });
+    }
+
+    cancel_button.borrow_mut().set_callback(move || close());
+    Some(input)
+}
+
+/// Opens a page with a masked password field. With `confirm` the second field has to
+/// repeat the password, so a typo doesn't lock the drawing. The password is not trimmed,
+/// `on_ok` returns the error to show, otherwise the page is closed.
+pub fn open_password_input_dialog(
+    font: &Font,
+    editor: Rc<RefCell<Editor>>,
+    context: Rc<RefCell<window::Context>>,
+    caption: &str,
+    confirm: bool,
+    on_ok: impl Fn(&str) -> Result<(), String> + 'static,
+) {
+    let font_height = font.get_size("8").1 as i32 + 2;
+    let mut page = Container::new(
+        SizeConstraints(SizeConstraint::flexible(0), SizeConstraint::flexible(0)),
+        ContainerLayout::Vertical,
+    );
+
+    let mut add_field = |field: &str| {
+        let line = page.add_child(Container::new(
+            SizeConstraints(
+                SizeConstraint::flexible(0),
+                SizeConstraint::fixed(font_height),
+            ),
+            ContainerLayout::Horizontal,
+        ));
+        line.borrow_mut()
+            .add_child(create_default_size_text_box(field, font.clone()));
+        let input = line.borrow_mut().add_child(create_default_size_edit(
+            "-8888888.888; -8888888.888",
+            font.clone(),
+            context.borrow().clipboard.clone(),
+        ));
+        input.borrow_mut().set_masked(true);
+        input
+    };
+    let (input, repeat_input) = if confirm {
+        (
+            add_field("Новый пароль:"),
+            Some(add_field("Повторите пароль:")),
+        )
+    } else {
+        (add_field("Пароль:"), None)
+    };
+
+    let buttons_line = page.add_child(Container::new(
+        SizeConstraints(
+            SizeConstraint::flexible(0),
+            SizeConstraint::fixed(font_height),
+        ),
+        ContainerLayout::Horizontal,
+    ));
+    let ok_button = buttons_line
+        .borrow_mut()
+        .add_child(create_default_size_button("ОК", font.clone()));
+    let cancel_button = buttons_line
+        .borrow_mut()
+        .add_child(create_default_size_button("Отмена", font.clone()));
+
+    page.add_child(EmptySpace::new_empty(SizeConstraints(
+        SizeConstraint::flexible(0),
+        SizeConstraint::flexible(0),
+    )));
+
+    let document_tab_id = editor.borrow().get_selected_tab_id();
+    let tab_id = match editor.borrow().add_page_tab(font, caption, page) {
+        Some(tab_id) => tab_id,
+        None => return,
+    };
+
+    let close = Rc::new(callback!([editor] () {
+        editor.borrow().close_tab(tab_id);
+        if let Some(document_tab_id) = document_tab_id {
+            editor.borrow().select_tab(document_tab_id);
+        }
+    }));
+
+    {
+        let close = close.clone();
+        ok_button.borrow_mut().set_callback(callback!([context] () {
+            let password = input.borrow().get_text().to_string();
+            if let Some(repeat_input) = &repeat_input {
+                if repeat_input.borrow().get_text() != password {
+                    show_message(context.clone(), "Пароли не совпадают", "Ошибка ввода");
+                    return;
+                }
+            }
+            match on_ok(&password) {
+                Ok(()) => close(),
+                Err(error) => show_message(context.clone(), &error, "Ошибка ввода"),
+            }
+        }));
+    }
+
+    cancel_button.borrow_mut().set_callback(move || close());
+}
@@ -1,7 +1,25 @@
+use application::property::Property;
+
+use crate::chain::*;
 use crate::config::*;
+use crate::ellipse::*;
+use crate::encryption;
+use crate::export::get_curve_points;
+use crate::isometric::*;
+use crate::plot_style::*;
+use crate::sheet::*;
+use crate::smart_guides::*;
+use crate::transform::*;
+use crate::ucs::*;
 use curves::solver::*;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::time::SystemTime;
 
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct Group {
@@ -18,6 +36,40 @@ pub struct Curve {
     pub curve: curves::Curve<f64>,
     pub group_id: Option<usize>,
     pub selected: bool,
+    /// Line width in the document units, thin lines have none and use the pen width.
+    #[serde(default)]
+    pub width: Option<f64>,
+    /// Curves with bigger order are drawn over the others.
+    #[serde(default)]
+    pub order: i64,
+    #[serde(default)]
+    pub opacity: Opacity,
+}
+
+/// Opacity in percent, faint curves show the reference geometry.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Copy, Clone)]
+pub struct Opacity(pub u8);
+
+impl Default for Opacity {
+    fn default() -> Self {
+        Self(100)
+    }
+}
+
+impl Opacity {
+    pub fn is_opaque(self) -> bool {
+        self.0 >= 100
+    }
+
+    /// Opacity from 0 to 255 for the drawing.
+    pub fn get_alpha(self) -> u8 {
+        (self.0.min(100) as u32 * 255 / 100) as u8
+    }
+
+    /// Opacity from 0 to 1 for the output formats.
+    pub fn get_fraction(self) -> f64 {
+        self.0.min(100) as f64 / 100.0
+    }
 }
 
 impl Curve {
@@ -26,6 +78,9 @@ impl Curve {
             curve: curves::Curve::Contour(c),
             group_id: None,
             selected: false,
+            width: None,
+            order: 0,
+            opacity: Opacity::default(),
         }
     }
 
@@ -34,20 +89,296 @@ impl Curve {
             curve: curves::Curve::Segment(s),
             group_id: None,
             selected: false,
+            width: None,
+            order: 0,
+            opacity: Opacity::default(),
+        }
+    }
+}
+
+/// Values which define the curve and can be entered by hand.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CurveParameters {
+    Line { begin: Point, end: Point },
+    Arc { center: Point, radius: f64 },
+    Circle { center: Point, radius: f64 },
+}
+
+impl Curve {
+    pub fn get_parameters(&self) -> Option<CurveParameters> {
+        match self.curve {
+            curves::Curve::Segment(s) if s.contour.a == 0.0 => Some(CurveParameters::Line {
+                begin: s.begin,
+                end: s.end,
+            }),
+            curves::Curve::Segment(s) => Some(CurveParameters::Arc {
+                center: s.contour.get_center(),
+                radius: s.contour.get_radius(),
+            }),
+            curves::Curve::Contour(c) if c.a > 0.0 => Some(CurveParameters::Circle {
+                center: c.get_center(),
+                radius: c.get_radius(),
+            }),
+            // infinite lines have no points to enter
+            curves::Curve::Contour(_) => None,
+        }
+    }
+
+    /// Arcs keep their angles, only center and radius are changed.
+    pub fn with_parameters(&self, parameters: CurveParameters) -> Option<Self> {
+        let curve = match (self.curve, parameters) {
+            (curves::Curve::Segment(_), CurveParameters::Line { begin, end }) => {
+                if (begin - end).sqr_length() < EPS {
+                    return None;
+                }
+                curves::Curve::Segment(Segment::line(begin, end))
+            }
+            (curves::Curve::Segment(s), CurveParameters::Arc { center, radius }) => {
+                if radius <= EPS {
+                    return None;
+                }
+                let old_center = s.contour.get_center();
+                let factor = radius / s.contour.get_radius();
+                curves::Curve::Segment(s.translate(-old_center).scale(factor).translate(center))
+            }
+            (curves::Curve::Contour(_), CurveParameters::Circle { center, radius }) => {
+                if radius <= EPS {
+                    return None;
+                }
+                curves::Curve::Contour(Contour::circle(center, radius))
+            }
+            _ => return None,
+        };
+        Some(Self {
+            curve,
+            group_id: self.group_id,
+            selected: self.selected,
+            width: self.width,
+            order: self.order,
+            opacity: self.opacity,
+        })
+    }
+}
+
+/// Marker, for example a survey point.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PointEntity {
+    pub position: Point,
+    pub selected: bool,
+}
+
+impl PointEntity {
+    pub fn new(position: Point) -> Self {
+        Self {
+            position,
+            selected: false,
         }
     }
 }
 
+/// Ellipse or elliptical arc. It is kept as its axes and angles, the arcs approximating it
+/// are made for the drawing, the export and the picking.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EllipseEntity {
+    pub ellipse: Ellipse,
+    pub selected: bool,
+    #[serde(default)]
+    pub width: Option<f64>,
+    #[serde(default)]
+    pub order: i64,
+    #[serde(default)]
+    pub opacity: Opacity,
+}
+
+impl EllipseEntity {
+    pub fn new(ellipse: Ellipse) -> Self {
+        Self {
+            ellipse,
+            selected: false,
+            width: None,
+            order: 0,
+            opacity: Opacity::default(),
+        }
+    }
+
+    /// Arcs of the approximation with the width, the order and the selection of the
+    /// ellipse.
+    pub fn get_curves(&self) -> Vec<Curve> {
+        self.ellipse
+            .get_arcs()
+            .into_iter()
+            .map(|arc| Curve {
+                curve: curves::Curve::Segment(arc),
+                group_id: None,
+                selected: self.selected,
+                width: self.width,
+                order: self.order,
+                opacity: self.opacity,
+            })
+            .collect()
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum Element {
     Curve(Curve),
     Group(Group),
+    Point(PointEntity),
+    Ellipse(EllipseEntity),
+}
+
+/// Elements copied to the clipboard, the field name tells them from any other text.
+#[derive(Serialize, Deserialize)]
+struct ClipboardElements {
+    ot_cad_elements: Vec<Element>,
+}
+
+pub fn elements_to_clipboard_text(elements: &[Element]) -> String {
+    serde_json::to_string(&ClipboardElements {
+        ot_cad_elements: elements.to_vec(),
+    })
+    .unwrap_or_default()
+}
+
+/// None if the text is not copied elements.
+pub fn elements_from_clipboard_text(text: &str) -> Option<Vec<Element>> {
+    let elements: ClipboardElements = serde_json::from_str(text).ok()?;
+    Some(elements.ot_cad_elements)
+}
+
+impl Element {
+    /// Groups are selected through their curves.
+    pub fn is_selected(&self) -> bool {
+        match self {
+            Element::Curve(curve) => curve.selected,
+            Element::Point(point) => point.selected,
+            Element::Ellipse(ellipse) => ellipse.selected,
+            Element::Group(_) => false,
+        }
+    }
+
+    fn get_selected_mut(&mut self) -> Option<&mut bool> {
+        match self {
+            Element::Curve(curve) => Some(&mut curve.selected),
+            Element::Point(point) => Some(&mut point.selected),
+            Element::Ellipse(ellipse) => Some(&mut ellipse.selected),
+            Element::Group(_) => None,
+        }
+    }
+
+    fn distance(&self, position: Point) -> Option<f64> {
+        match self {
+            Element::Curve(curve) => Some(curve.curve.distance(position).abs()),
+            Element::Point(point) => Some((point.position - position).length()),
+            Element::Ellipse(ellipse) => ellipse
+                .ellipse
+                .get_arcs()
+                .iter()
+                .map(|arc| arc.distance(position).abs())
+                .reduce(f64::min),
+            Element::Group(_) => None,
+        }
+    }
+
+    /// Curves and points moved by the transform, widths are scaled with them.
+    pub fn transformed(&self, transform: &Transform) -> Element {
+        match self {
+            Element::Curve(curve) => Element::Curve(Curve {
+                curve: transform.apply_to_curve(curve.curve),
+                width: curve.width.map(|width| width * transform.factor),
+                ..curve.clone()
+            }),
+            Element::Point(point) => Element::Point(PointEntity {
+                position: transform.apply(point.position),
+                ..point.clone()
+            }),
+            Element::Ellipse(ellipse) => Element::Ellipse(EllipseEntity {
+                ellipse: ellipse.ellipse.transformed(transform),
+                width: ellipse.width.map(|width| width * transform.factor),
+                ..ellipse.clone()
+            }),
+            Element::Group(_) => self.clone(),
+        }
+    }
+
+    /// Point to show when the view goes to the element.
+    pub fn get_position(&self) -> Option<Point> {
+        match self {
+            Element::Curve(curve) => Some(match curve.curve {
+                curves::Curve::Segment(s) => (s.begin + s.end).scale(0.5),
+                curves::Curve::Contour(c) if c.a > 0.0 => c.get_center(),
+                curves::Curve::Contour(c) => c.nearest_point_to_zero(),
+            }),
+            Element::Point(point) => Some(point.position),
+            Element::Ellipse(ellipse) => Some(ellipse.ellipse.center),
+            Element::Group(_) => None,
+        }
+    }
+
+    fn memory_size(&self) -> usize {
+        let heap_size = match self {
+            Element::Group(group) => group.ids.capacity() * std::mem::size_of::<usize>(),
+            _ => 0,
+        };
+        std::mem::size_of::<Self>() + heap_size
+    }
+
+    fn in_rect(&self, corner1: Point, corner2: Point) -> bool {
+        match self {
+            Element::Curve(curve) => curve.curve.in_rect(corner1, corner2),
+            Element::Point(point) => {
+                let p = point.position;
+                p.x >= f64::min(corner1.x, corner2.x)
+                    && p.x <= f64::max(corner1.x, corner2.x)
+                    && p.y >= f64::min(corner1.y, corner2.y)
+                    && p.y <= f64::max(corner1.y, corner2.y)
+            }
+            Element::Ellipse(ellipse) => {
+                let arcs = ellipse.ellipse.get_arcs();
+                !arcs.is_empty() && arcs.iter().all(|arc| arc.in_rect(corner1, corner2))
+            }
+            Element::Group(_) => false,
+        }
+    }
+}
+
+/// Reads "x,y" lines; ';' and tab are accepted as separators too, then ',' may be
+/// the decimal separator. Lines which are not numbers at the start are taken as a header.
+/// Returns the number of the first wrong line as an error.
+pub fn parse_points_csv(text: &str) -> Result<Vec<Point>, usize> {
+    let mut result = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let fields: Vec<String> = if line.contains(';') || line.contains('\t') {
+            line.split(|c| c == ';' || c == '\t')
+                .map(|f| f.trim().replace(',', "."))
+                .collect()
+        } else {
+            line.split(',').map(|f| f.trim().to_string()).collect()
+        };
+        let coords: Vec<Option<f64>> = fields
+            .iter()
+            .take(2)
+            .map(|f| f.parse::<f64>().ok().filter(|v| v.is_finite()))
+            .collect();
+        match coords.as_slice() {
+            [Some(x), Some(y)] => result.push(Point::new(*x, *y)),
+            _ if result.is_empty() && index == 0 => continue,
+            _ => return Err(index + 1),
+        }
+    }
+    Ok(result)
 }
 
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
 pub enum HighlightPointKind {
     None,
     End,
+    Middle,
+    Quadrant,
     Cross,
     Grid,
     Center(usize),
@@ -75,6 +406,20 @@ impl HighlightPoint {
         }
     }
 
+    pub fn middle(position: Point) -> Self {
+        Self {
+            position,
+            kind: HighlightPointKind::Middle,
+        }
+    }
+
+    pub fn quadrant(position: Point) -> Self {
+        Self {
+            position,
+            kind: HighlightPointKind::Quadrant,
+        }
+    }
+
     pub fn cross(position: Point) -> Self {
         Self {
             position,
@@ -156,6 +501,29 @@ impl Diff {
         self.editions.append(&mut other.editions);
         self
     }
+
+    fn memory_size(&self) -> usize {
+        let elements_size: usize = self
+            .editions
+            .iter()
+            .map(|edition| match edition {
+                Edition::Add(element, _) | Edition::Remove(element, _) => element.memory_size(),
+                _ => 0,
+            })
+            .sum();
+        std::mem::size_of::<Self>()
+            + self.editions.capacity() * std::mem::size_of::<Edition>()
+            + elements_size
+    }
+}
+
+/// Estimated memory of the document, for the statistics.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct MemoryUsage {
+    pub entities: usize,
+    pub content_bytes: usize,
+    pub undo_steps: usize,
+    pub undo_bytes: usize,
 }
 
 #[derive(Serialize, Deserialize, Debug, Default)]
@@ -171,6 +539,23 @@ struct DocumentClick {
     selected_id: Option<usize>,
 }
 
+/// The last undo step moved these elements by the shift, the next nudge of the same
+/// elements replaces it.
+#[derive(Debug)]
+struct Nudge {
+    shift: Point,
+    ids: HashSet<usize>,
+}
+
+/// Curves near the last click from the nearest one, clicking the same place again goes
+/// to the next of them.
+#[derive(Debug)]
+struct PickCycle {
+    position: Point,
+    candidates: Vec<usize>,
+    index: usize,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 enum DocumentState {
     Nothing,
@@ -186,6 +571,236 @@ impl Default for DocumentState {
 
 static EPS: f64 = 1.0e-12;
 
+/// Colors of the drawing stored in its file, they take precedence over the color theme.
+#[derive(Serialize, Deserialize, Debug, Default, Copy, Clone, PartialEq)]
+pub struct CanvasStyle {
+    #[serde(default)]
+    pub background: Option<u32>,
+    #[serde(default)]
+    pub grid_color_base: Option<u32>,
+    #[serde(default)]
+    pub grid_color_sub: Option<u32>,
+    #[serde(default)]
+    pub entity_color: Option<u32>,
+}
+
+impl CanvasStyle {
+    /// Empty text is the theme color, otherwise `RRGGBB` with an optional `#`.
+    pub fn parse_color(text: &str) -> Option<Option<u32>> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Some(None);
+        }
+        let hex = text.strip_prefix('#').unwrap_or(text);
+        if hex.len() != 6 {
+            return None;
+        }
+        u32::from_str_radix(hex, 16).ok().map(Some)
+    }
+
+    pub fn format_color(color: Option<u32>) -> String {
+        color.map_or(String::new(), |color| format!("#{:06X}", color))
+    }
+}
+
+/// Pixels per drawing unit at the limits of the zoom.
+const MIN_SCALE: f64 = 1e-4;
+const MAX_SCALE: f64 = 1e5;
+/// Grid lines closer than this number of pixels would merge into a solid fill.
+const MIN_GRID_SPACING: f64 = 8.0;
+
+/// Center, zoom and rotation of a view of the document.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct DocumentView {
+    center: Point,
+    scale: f64,
+    rotation: f64,
+}
+
+/// What changed in the document, so the views and panels update only what they show.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DocumentEvent {
+    /// Entities of one edit, undo or redo, ids are sorted. An edit of a selected entity
+    /// may change the selection too.
+    EntitiesChanged {
+        added: Vec<usize>,
+        removed: Vec<usize>,
+        modified: Vec<usize>,
+    },
+    SelectionChanged,
+    HistoryChanged,
+    /// Hiding and locking.
+    VisibilityChanged,
+    /// The sheet, the styles, the metadata or the UCS.
+    SettingsChanged,
+    ViewChanged,
+    /// Everything may be different.
+    Reloaded,
+}
+
+/// The number makes every event a new value of the property, the equal events are
+/// notified too.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocumentChange {
+    pub number: usize,
+    pub event: DocumentEvent,
+}
+
+impl Default for DocumentChange {
+    fn default() -> Self {
+        Self {
+            number: 0,
+            event: DocumentEvent::Reloaded,
+        }
+    }
+}
+
+impl DocumentEvent {
+    fn from_editions<'i>(editions: impl Iterator<Item = EditionRef<'i>>) -> Self {
+        let mut added = BTreeSet::new();
+        let mut removed = BTreeSet::new();
+        let mut modified = BTreeSet::new();
+        for edition in editions {
+            match edition {
+                EditionRef::Add(_, id) => {
+                    added.insert(id);
+                }
+                EditionRef::Remove(_, id) => {
+                    removed.insert(id);
+                }
+                EditionRef::AddToGroup(group_id, id)
+                | EditionRef::RemoveFromGroup(group_id, id) => {
+                    modified.insert(group_id);
+                    modified.insert(id);
+                }
+            }
+        }
+        // an entity replaced by an edit is removed and added back
+        for id in added.intersection(&removed) {
+            modified.insert(*id);
+        }
+        let added_or_removed = |ids: &BTreeSet<usize>| {
+            ids.iter()
+                .filter(|id| !modified.contains(id))
+                .copied()
+                .collect()
+        };
+        Self::EntitiesChanged {
+            added: added_or_removed(&added),
+            removed: added_or_removed(&removed),
+            modified: modified.iter().copied().collect(),
+        }
+    }
+}
+
+/// Bounds of the visible entities kept between the changes, so they are not searched
+/// through all the entities every time.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum TrackedExtents {
+    /// An entity on the bounds was removed or the visibility changed.
+    Unknown,
+    Known(Option<(Point, Point)>),
+}
+
+impl Default for TrackedExtents {
+    fn default() -> Self {
+        Self::Unknown
+    }
+}
+
+fn get_element_points(element: &Element) -> Vec<Point> {
+    match element {
+        Element::Curve(curve) => get_curve_points(&curve.curve),
+        Element::Point(point) => vec![point.position],
+        Element::Ellipse(ellipse) => ellipse
+            .get_curves()
+            .iter()
+            .flat_map(|curve| get_curve_points(&curve.curve))
+            .collect(),
+        Element::Group(_) => Vec::new(),
+    }
+}
+
+fn get_bounds(points: impl IntoIterator<Item = Point>) -> Option<(Point, Point)> {
+    points.into_iter().fold(None, |bounds, p| {
+        let (min, max) = bounds.unwrap_or((p, p));
+        Some((
+            Point::new(min.x.min(p.x), min.y.min(p.y)),
+            Point::new(max.x.max(p.x), max.y.max(p.y)),
+        ))
+    })
+}
+
+/// Files bigger than this are shown while their entities are loaded.
+const PROGRESSIVE_LOAD_SIZE: u64 = 4 << 20;
+/// Entities sent at once by the thread which reads the file.
+const LOAD_BATCH: usize = 2000;
+
+/// Parts of the file sent by the thread which reads it.
+#[derive(Debug)]
+enum LoadMessage {
+    /// The document without the entities, it comes first.
+    Header {
+        document: serde_json::Value,
+        entities: usize,
+        encrypted: bool,
+    },
+    Entities(Vec<(usize, Element)>),
+    Error(String),
+}
+
+/// Reads, decrypts and parses the file, the entities are sent in the order of ids.
+/// Stops when the document is closed and nobody receives.
+fn read_document(
+    path: &Path,
+    password: Option<&str>,
+    sender: &Sender<LoadMessage>,
+) -> Result<(), String> {
+    let data = std::fs::read(path).map_err(|e| e.to_string())?;
+    let encrypted = encryption::is_encrypted(&data);
+    let text = if encrypted {
+        let password = password.ok_or_else(|| "Чертёж защищён паролем".to_string())?;
+        encryption::decrypt(&data, password)?
+    } else {
+        String::from_utf8(data).map_err(|e| e.to_string())?
+    };
+    let mut document: serde_json::Value = serde_json::from_str(&text).map_err(|e| e.to_string())?;
+    let content = document
+        .as_object_mut()
+        .and_then(|object| object.insert("content".to_string(), serde_json::json!({})));
+    let mut pending = Vec::new();
+    if let Some(serde_json::Value::Object(content)) = content {
+        for (id, element) in content {
+            pending.push((id.parse::<usize>().map_err(|e| e.to_string())?, element));
+        }
+    }
+    pending.sort_unstable_by_key(|(id, _)| *id);
+    let header = LoadMessage::Header {
+        document,
+        entities: pending.len(),
+        encrypted,
+    };
+    if sender.send(header).is_err() {
+        return Ok(());
+    }
+
+    let mut batch = Vec::new();
+    for (id, element) in pending {
+        let element: Element = serde_json::from_value(element).map_err(|e| e.to_string())?;
+        batch.push((id, element));
+        if batch.len() == LOAD_BATCH {
+            if sender.send(LoadMessage::Entities(batch)).is_err() {
+                return Ok(());
+            }
+            batch = Vec::new();
+        }
+    }
+    if !batch.is_empty() {
+        let _ = sender.send(LoadMessage::Entities(batch));
+    }
+    Ok(())
+}
+
 #[derive(Serialize, Deserialize, Debug, Default)]
 pub struct Document {
     content: HashMap<usize, Element>,
@@ -194,25 +809,527 @@ pub struct Document {
     last_entity_id: usize,
 
     center: Point,
-    scale: i32,
+    scale: f64,
+    /// Angle in radians the drawing is turned by on the screen.
+    #[serde(default)]
+    rotation: f64,
     state: DocumentState,
 
+    #[serde(default)]
+    metadata: DocumentMetadata,
+    #[serde(default)]
+    sheet: Option<Sheet>,
+    #[serde(default)]
+    canvas_style: CanvasStyle,
+    #[serde(default)]
+    plot_styles: PlotStyleTable,
+    #[serde(default)]
+    ucs: Ucs,
+    /// The Y axis goes up on the screen and in the exported pictures.
+    #[serde(default)]
+    y_up: bool,
+    #[serde(default)]
+    hidden: HashSet<usize>,
+    #[serde(default)]
+    locked: HashSet<usize>,
+    /// Named selections to select the same entities again, the removed entities stay
+    /// in them and are skipped.
+    #[serde(default)]
+    selection_sets: BTreeMap<String, Vec<usize>>,
+    /// The entities can't be changed until the flag is cleared.
+    #[serde(default)]
+    read_only: bool,
+    /// Other drawings shown under this one, they can't be edited here.
+    #[serde(default)]
+    xrefs: Vec<PathBuf>,
+    /// Visible curves of the referenced drawings, read again when their files change.
+    #[serde(skip)]
+    xref_curves: HashMap<PathBuf, Vec<curves::Curve<f64>>>,
+
     #[serde(skip)]
     highliht_id: Option<usize>,
     #[serde(skip)]
     highlight_point: HighlightPoint,
+    #[serde(skip)]
+    pick_cycle: Option<PickCycle>,
+    #[serde(skip)]
+    nudge: Option<Nudge>,
+    /// Closest points of two curves, shown until the entities or the selection change.
+    #[serde(skip)]
+    witness_line: Cell<Option<(Point, Point)>>,
+    #[serde(skip)]
+    extents: Cell<TrackedExtents>,
+    /// The opened file is fitted into the first view which is drawn, if its saved view
+    /// shows nothing of the drawing.
+    #[serde(skip)]
+    initial_view_pending: bool,
+
+    /// Parts of the file from the thread which reads it, until all the entities are added.
+    #[serde(skip)]
+    loader: Option<Receiver<LoadMessage>>,
+    /// Number of the entities in the file, known when the header is received.
+    #[serde(skip)]
+    loaded_entities_count: Option<usize>,
+
+    #[serde(skip)]
+    limits: DocumentLimits,
+    #[serde(skip)]
+    history_memory: usize,
+    #[serde(skip)]
+    warnings: Vec<String>,
+    #[serde(skip)]
+    entities_warning_shown: bool,
+    #[serde(skip)]
+    undo_warning_shown: bool,
+
+    #[serde(skip)]
+    path: Option<PathBuf>,
+    /// The file is encrypted by the password when it is written.
+    #[serde(skip)]
+    password: Option<String>,
+    /// Modification time of the file when the document was read or written.
+    #[serde(skip)]
+    file_time: Option<SystemTime>,
+    #[serde(skip)]
+    modified: bool,
+    /// Every view of the document is redrawn on the changes.
+    #[serde(skip)]
+    changes: Property<DocumentChange>,
+}
+
+fn get_file_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).ok()?.modified().ok()
 }
 
 impl Document {
     pub fn new() -> Self {
         Self {
-            scale: 300,
+            scale: 300.0,
             ..Default::default()
         }
     }
 
+    /// Document made of the given elements without the undo history, the next entity gets
+    /// an id after all the given ones.
+    pub fn from_content(
+        content: HashMap<usize, Element>,
+        hidden: HashSet<usize>,
+        locked: HashSet<usize>,
+        metadata: DocumentMetadata,
+        ucs: Ucs,
+    ) -> Self {
+        Self {
+            last_entity_id: content.keys().max().map_or(0, |id| id + 1),
+            content,
+            hidden,
+            locked,
+            metadata,
+            ucs,
+            ..Self::new()
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        Self::load_with_password(path, None)
+    }
+
+    /// True if the file can be read only with a password.
+    pub fn is_encrypted_file(path: &Path) -> bool {
+        std::fs::read(path).map_or(false, |data| encryption::is_encrypted(&data))
+    }
+
+    /// The password is used only if the file is encrypted, the document keeps it to
+    /// encrypt the file again on saving.
+    pub fn load_with_password(path: &Path, password: Option<&str>) -> Result<Self, String> {
+        let mut document = Self::start_reading(path, password)?;
+        document.finish_loading()?;
+        Ok(document)
+    }
+
+    /// The file is read and parsed by another thread. Small files are waited for, the
+    /// entities of the big ones are added later by `load_pending_entities`, so the
+    /// drawing is shown while it loads.
+    pub fn start_loading(path: &Path, password: Option<&str>) -> Result<Self, String> {
+        let size = std::fs::metadata(path).map_err(|e| e.to_string())?.len();
+        let mut document = Self::start_reading(path, password)?;
+        if size < PROGRESSIVE_LOAD_SIZE {
+            document.finish_loading()?;
+        }
+        Ok(document)
+    }
+
+    /// Empty document which gets the parts of the file as the thread reads them.
+    fn start_reading(path: &Path, password: Option<&str>) -> Result<Self, String> {
+        let (sender, receiver) = mpsc::channel();
+        let thread_path = path.to_path_buf();
+        let thread_password = password.map(str::to_string);
+        std::thread::Builder::new()
+            .name("Загрузка чертежа".to_string())
+            .spawn(move || {
+                if let Err(e) = read_document(&thread_path, thread_password.as_deref(), &sender) {
+                    let _ = sender.send(LoadMessage::Error(e));
+                }
+            })
+            .map_err(|e| e.to_string())?;
+        Ok(Self {
+            loader: Some(receiver),
+            password: password.map(str::to_string),
+            path: Some(path.to_path_buf()),
+            file_time: get_file_time(path),
+            initial_view_pending: true,
+            ..Self::new()
+        })
+    }
+
+    /// True until all the entities sent by the thread which reads the file are added.
+    pub fn is_loading(&self) -> bool {
+        self.loader.is_some()
+    }
+
+    /// Adds the next part of the file if it is read, returns the loaded part from 0 to 1.
+    pub fn load_pending_entities(&mut self) -> Result<f32, String> {
+        self.receive_loaded_part(false)?;
+        let loaded = self.content.len() as f32;
+        Ok(match self.loaded_entities_count {
+            Some(count) => loaded / count.max(1) as f32,
+            None => 0.0,
+        }
+        .min(1.0))
+    }
+
+    fn finish_loading(&mut self) -> Result<(), String> {
+        while self.is_loading() {
+            self.receive_loaded_part(true)?;
+        }
+        Ok(())
+    }
+
+    fn receive_loaded_part(&mut self, wait: bool) -> Result<(), String> {
+        let loader = match &self.loader {
+            Some(loader) => loader,
+            None => return Ok(()),
+        };
+        let message = if wait {
+            loader.recv().map_err(|_| TryRecvError::Disconnected)
+        } else {
+            loader.try_recv()
+        };
+        match message {
+            Ok(LoadMessage::Header {
+                document,
+                entities,
+                encrypted,
+            }) => self.apply_loaded_header(document, entities, encrypted)?,
+            Ok(LoadMessage::Entities(entities)) => self.add_loaded_entities(entities),
+            Ok(LoadMessage::Error(e)) => {
+                self.loader = None;
+                return Err(e);
+            }
+            Err(TryRecvError::Empty) => {}
+            Err(TryRecvError::Disconnected) => {
+                self.loader = None;
+                if self.loaded_entities_count.is_none() {
+                    return Err("Чертёж не прочитан".to_string());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The settings of the file replace the empty ones, the fields which are not written
+    /// to the file stay.
+    fn apply_loaded_header(
+        &mut self,
+        header: serde_json::Value,
+        entities: usize,
+        encrypted: bool,
+    ) -> Result<(), String> {
+        let mut document: Self = serde_json::from_value(header).map_err(|e| e.to_string())?;
+        document.loader = self.loader.take();
+        document.loaded_entities_count = Some(entities);
+        document.password = self.password.take().filter(|_| encrypted);
+        document.path = self.path.take();
+        document.file_time = self.file_time;
+        document.initial_view_pending = true;
+        document.state = DocumentState::Nothing;
+        document.history_memory = document.history.iter().map(Diff::memory_size).sum();
+        document.warnings = std::mem::take(&mut self.warnings);
+        document.changes = self.changes.clone();
+        document.set_limits(self.limits);
+        *self = document;
+        self.notify(DocumentEvent::Reloaded);
+        Ok(())
+    }
+
+    fn add_loaded_entities(&mut self, entities: Vec<(usize, Element)>) {
+        let mut added = Vec::new();
+        for (id, element) in entities {
+            self.track_extents(EditionRef::Add(&element, id));
+            self.content.insert(id, element);
+            // ids of the entities stay the same, the counter of a hand edited file
+            // must not give them to the new ones
+            self.last_entity_id = self.last_entity_id.max(id + 1);
+            added.push(id);
+        }
+        self.notify(DocumentEvent::EntitiesChanged {
+            added,
+            removed: Vec::new(),
+            modified: Vec::new(),
+        });
+    }
+
+    /// Steps of the undo history which are written to the file. The oldest ones above the
+    /// limit are dropped, the redo steps are kept only with the step they follow.
+    fn get_saved_history(&self) -> Range<usize> {
+        let position = self.history_position;
+        if !self.limits.save_undo_history {
+            return position..position;
+        }
+        let memory_size = |steps: &[Diff]| -> usize { steps.iter().map(Diff::memory_size).sum() };
+        let mut end = self.history.len();
+        let mut budget = self.limits.max_saved_undo_memory;
+        match budget.checked_sub(memory_size(&self.history[position..end])) {
+            Some(rest) => budget = rest,
+            None => end = position,
+        }
+        let mut start = position;
+        while start > 0 {
+            match budget.checked_sub(self.history[start - 1].memory_size()) {
+                Some(rest) => budget = rest,
+                None => break,
+            }
+            start -= 1;
+        }
+        start..end
+    }
+
+    /// Only the part of the undo history given by `get_saved_history` is written.
+    fn to_json(&mut self) -> Result<String, String> {
+        let saved = self.get_saved_history();
+        let mut history = std::mem::take(&mut self.history);
+        let redo_tail = history.split_off(saved.end);
+        self.history = history.split_off(saved.start);
+        self.history_position -= saved.start;
+        let text = serde_json::to_string(self);
+        self.history_position += saved.start;
+        history.append(&mut self.history);
+        history.extend(redo_tail);
+        self.history = history;
+        text.map_err(|e| e.to_string())
+    }
+
+    pub fn save(&mut self, path: &Path) -> Result<(), String> {
+        if self.is_loading() {
+            return Err("Чертёж ещё не загружен".to_string());
+        }
+        let text = self.to_json()?;
+        let data = match &self.password {
+            Some(password) => encryption::encrypt(&text, password)?,
+            None => text.into_bytes(),
+        };
+        let temp_file = path.with_extension("json.tmp");
+        // a failed write doesn't spoil the previous version of the file
+        std::fs::write(&temp_file, data).map_err(|e| e.to_string())?;
+        std::fs::rename(&temp_file, path).map_err(|e| e.to_string())?;
+        self.path = Some(path.to_path_buf());
+        self.file_time = get_file_time(path);
+        self.modified = false;
+        Ok(())
+    }
+
+    /// Reads the file again, unsaved changes are lost, the view stays as it is.
+    pub fn reload(&mut self) -> Result<(), String> {
+        let path = self
+            .path
+            .clone()
+            .ok_or_else(|| "Чертёж не сохранён в файл".to_string())?;
+        let mut document = Self::load_with_password(&path, self.password.as_deref())?;
+        document.center = self.center;
+        document.scale = self.scale;
+        document.rotation = self.rotation;
+        document.set_limits(self.limits);
+        document.changes = self.changes.clone();
+        *self = document;
+        self.reload_xrefs();
+        self.notify(DocumentEvent::Reloaded);
+        Ok(())
+    }
+
+    pub fn get_path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// The file is encrypted from the next saving, no password makes it plain text.
+    pub fn set_password(&mut self, password: Option<&str>) {
+        self.password = password.map(str::to_string);
+        self.set_modified(DocumentEvent::SettingsChanged);
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+        self.set_modified(DocumentEvent::SettingsChanged);
+    }
+
+    /// Why edits of the document are refused, none if they are allowed.
+    fn get_refusal_reason(&self) -> Option<&'static str> {
+        if self.is_loading() {
+            return Some("Чертёж ещё загружается, изменение отменено");
+        }
+        if self.read_only {
+            return Some("Чертёж только для чтения, изменение отменено");
+        }
+        None
+    }
+
+    /// Edits of a read-only or loading document are refused with a warning.
+    fn is_edit_refused(&mut self) -> bool {
+        match self.get_refusal_reason() {
+            Some(reason) => {
+                self.warnings.push(reason.to_string());
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn get_xref_paths(&self) -> &[PathBuf] {
+        &self.xrefs
+    }
+
+    /// Curves of all the referenced drawings which could be read.
+    pub fn get_xref_curves(&self) -> impl Iterator<Item = &curves::Curve<f64>> {
+        self.xrefs
+            .iter()
+            .filter_map(|path| self.xref_curves.get(path))
+            .flatten()
+    }
+
+    /// Shows the drawing from the file under this one. The references of that drawing
+    /// are not followed, so they can't make a loop.
+    pub fn add_xref(&mut self, path: &Path) -> Result<(), String> {
+        if let Some(reason) = self.get_refusal_reason() {
+            return Err(reason.to_string());
+        }
+        if self.path.as_deref() == Some(path) {
+            return Err("Чертёж не может ссылаться на себя".to_string());
+        }
+        if self.xrefs.iter().any(|xref| xref == path) {
+            return Err("Ссылка на этот чертёж уже есть".to_string());
+        }
+        let curves = Self::load_xref_curves(path)?;
+        self.xrefs.push(path.to_path_buf());
+        self.xref_curves.insert(path.to_path_buf(), curves);
+        self.set_modified(DocumentEvent::SettingsChanged);
+        Ok(())
+    }
+
+    /// Returns the paths of the removed references.
+    pub fn remove_xrefs(&mut self) -> Vec<PathBuf> {
+        if self.xrefs.is_empty() || self.is_edit_refused() {
+            return Vec::new();
+        }
+        self.xref_curves.clear();
+        let xrefs = std::mem::take(&mut self.xrefs);
+        self.set_modified(DocumentEvent::SettingsChanged);
+        xrefs
+    }
+
+    /// Reads all the referenced drawings again, the unreadable ones are not shown
+    /// and give a warning.
+    pub fn reload_xrefs(&mut self) {
+        self.xref_curves.clear();
+        for path in self.xrefs.clone() {
+            match Self::load_xref_curves(&path) {
+                Ok(curves) => {
+                    self.xref_curves.insert(path, curves);
+                }
+                Err(e) => self.warnings.push(format!(
+                    "Не удалось прочитать внешнюю ссылку {}: {}",
+                    path.display(),
+                    e
+                )),
+            }
+        }
+        self.notify(DocumentEvent::SettingsChanged);
+    }
+
+    /// The password of an encrypted drawing is not kept, so it can't be a reference.
+    fn load_xref_curves(path: &Path) -> Result<Vec<curves::Curve<f64>>, String> {
+        if Self::is_encrypted_file(path) {
+            return Err("Зашифрованный чертёж нельзя показать как внешнюю ссылку".to_string());
+        }
+        let document = Self::load(path)?;
+        Ok(document
+            .get_shapes_in_draw_order()
+            .into_iter()
+            .map(|(_, curve)| curve.curve)
+            .collect())
+    }
+
+    /// True if there are changes which are not written to the file.
+    pub fn is_modified(&self) -> bool {
+        self.modified
+    }
+
+    fn set_modified(&mut self, event: DocumentEvent) {
+        self.modified = true;
+        self.notify(event);
+    }
+
+    fn notify(&self, event: DocumentEvent) {
+        if !matches!(
+            event,
+            DocumentEvent::ViewChanged | DocumentEvent::SettingsChanged
+        ) {
+            self.witness_line.set(None);
+        }
+        let number = self.changes.get().number.wrapping_add(1);
+        self.changes.set(DocumentChange { number, event });
+    }
+
+    pub fn get_changes(&self) -> &Property<DocumentChange> {
+        &self.changes
+    }
+
+    /// True if the file was written by someone else since it was read or saved.
+    pub fn is_changed_on_disk(&self) -> bool {
+        match &self.path {
+            Some(path) => get_file_time(path) != self.file_time,
+            None => false,
+        }
+    }
+
+    /// The current version of the file is not reported as changed anymore.
+    pub fn accept_disk_change(&mut self) {
+        if let Some(path) = &self.path {
+            self.file_time = get_file_time(path);
+        }
+    }
+
+    pub fn get_view(&self) -> DocumentView {
+        DocumentView {
+            center: self.center,
+            scale: self.scale,
+            rotation: self.rotation,
+        }
+    }
+
+    /// Returns the view which was replaced.
+    pub fn replace_view(&mut self, view: DocumentView) -> DocumentView {
+        let old_view = self.get_view();
+        self.center = view.center;
+        self.scale = view.scale;
+        self.rotation = view.rotation;
+        old_view
+    }
+
     pub fn set_center(&mut self, center: Point) {
         self.center = center;
+        self.notify(DocumentEvent::ViewChanged);
     }
 
     pub fn get_center(&self) -> Point {
@@ -220,65 +1337,584 @@ impl Document {
     }
 
     pub fn get_scale(&self) -> f64 {
-        f64::powi(1.01, self.scale)
+        f64::powf(1.01, self.scale)
+    }
+
+    pub fn get_rotation(&self) -> f64 {
+        self.rotation
+    }
+
+    /// Turns the view around its center, the angle is kept in [0, 2pi).
+    pub fn set_rotation(&mut self, angle: f64) {
+        self.rotation = angle.rem_euclid(std::f64::consts::TAU);
+        self.notify(DocumentEvent::ViewChanged);
+    }
+
+    /// The scale is kept between `MIN_SCALE` and `MAX_SCALE`.
+    pub fn change_scale(&mut self, delta: f64) {
+        let to_exponent = |scale: f64| scale.ln() / 1.01f64.ln();
+        self.scale = (self.scale + delta).clamp(to_exponent(MIN_SCALE), to_exponent(MAX_SCALE));
+        self.notify(DocumentEvent::ViewChanged);
+    }
+
+    /// Bounds of the visible curves and points, or of the selected ones. None if there is
+    /// nothing or only infinite lines. The bounds of all the entities are kept between
+    /// the calls and updated by the changes.
+    pub fn get_extents(&self, selected_only: bool) -> Option<(Point, Point)> {
+        if !selected_only {
+            if let TrackedExtents::Known(extents) = self.extents.get() {
+                return extents;
+            }
+        }
+        let extents = get_bounds(
+            self.content
+                .iter()
+                .filter(|(id, element)| {
+                    !self.hidden.contains(id) && (!selected_only || element.is_selected())
+                })
+                .flat_map(|(_, element)| get_element_points(element)),
+        );
+        if !selected_only {
+            self.extents.set(TrackedExtents::Known(extents));
+        }
+        extents
+    }
+
+    /// Zooms to the extents once after the file is opened, if the saved view doesn't show
+    /// any of the drawing. Returns true if the view was changed.
+    pub fn fit_initial_view(&mut self, view_size: Point) -> bool {
+        if !self.initial_view_pending || self.is_loading() || view_size.x <= 0.0 {
+            return false;
+        }
+        self.initial_view_pending = false;
+        let (min, max) = match self.get_extents(false) {
+            Some(extents) => extents,
+            None => return false,
+        };
+        // the extents and the view are compared along the screen axes
+        let direction = Point::angle(self.rotation);
+        let to_screen = |p: Point| {
+            curves::points::complex_mul(p - self.center, direction).scale(self.get_scale())
+        };
+        let (screen_min, screen_max) = match get_bounds(
+            [min, Point::new(min.x, max.y), Point::new(max.x, min.y), max].map(to_screen),
+        ) {
+            Some(bounds) => bounds,
+            None => return false,
+        };
+        let half = view_size.scale(0.5);
+        let visible = screen_max.x >= -half.x
+            && screen_min.x <= half.x
+            && screen_max.y >= -half.y
+            && screen_min.y <= half.y;
+        if visible {
+            return false;
+        }
+        self.zoom_to_rect(min, max, view_size);
+        true
+    }
+
+    /// Centers the view on the rectangle and zooms it to fill the view of the given size
+    /// in pixels with a margin. A rectangle without a size keeps the scale.
+    pub fn zoom_to_rect(&mut self, corner1: Point, corner2: Point, view_size: Point) {
+        const FILLED_PART: f64 = 0.9;
+        // the size of the turned rectangle along the screen axes
+        let direction = Point::angle(self.rotation);
+        let corners = [
+            corner1,
+            Point::new(corner1.x, corner2.y),
+            Point::new(corner2.x, corner1.y),
+            corner2,
+        ]
+        .map(|corner| curves::points::complex_mul(corner, direction));
+        let (min, max) = corners
+            .iter()
+            .fold((corners[0], corners[0]), |(min, max), p| {
+                (
+                    Point::new(min.x.min(p.x), min.y.min(p.y)),
+                    Point::new(max.x.max(p.x), max.y.max(p.y)),
+                )
+            });
+        let size = max - min;
+        let fit = [(size.x.abs(), view_size.x), (size.y.abs(), view_size.y)]
+            .into_iter()
+            .filter(|(size, view_size)| *size > EPS && *view_size > 0.0)
+            .map(|(size, view_size)| view_size / size * FILLED_PART)
+            .reduce(f64::min);
+        if let Some(fit) = fit {
+            self.scale = fit.clamp(MIN_SCALE, MAX_SCALE).ln() / 1.01f64.ln();
+        }
+        self.center = (corner1 + corner2).scale(0.5);
+        self.notify(DocumentEvent::ViewChanged);
+    }
+
+    pub fn get_content(&self) -> &HashMap<usize, Element> {
+        &self.content
+    }
+
+    pub fn get_metadata(&self) -> &DocumentMetadata {
+        &self.metadata
     }
 
-    pub fn change_scale(&mut self, delta: i32) {
-        self.scale += delta;
-        self.scale = std::cmp::min(std::cmp::max(self.scale, -1000), 1500);
+    pub fn set_metadata(&mut self, metadata: DocumentMetadata) {
+        if self.is_edit_refused() {
+            return;
+        }
+        self.metadata = metadata;
+        self.set_modified(DocumentEvent::SettingsChanged);
+    }
+
+    pub fn get_sheet(&self) -> Option<&Sheet> {
+        self.sheet.as_ref()
+    }
+
+    pub fn set_sheet(&mut self, sheet: Option<Sheet>) {
+        if self.is_edit_refused() {
+            return;
+        }
+        self.sheet = sheet;
+        self.set_modified(DocumentEvent::SettingsChanged);
+    }
+
+    pub fn get_canvas_style(&self) -> &CanvasStyle {
+        &self.canvas_style
+    }
+
+    pub fn set_canvas_style(&mut self, canvas_style: CanvasStyle) {
+        if self.is_edit_refused() {
+            return;
+        }
+        self.canvas_style = canvas_style;
+        self.set_modified(DocumentEvent::SettingsChanged);
+    }
+
+    pub fn get_plot_styles(&self) -> &PlotStyleTable {
+        &self.plot_styles
+    }
+
+    pub fn set_plot_styles(&mut self, plot_styles: PlotStyleTable) {
+        if self.is_edit_refused() {
+            return;
+        }
+        self.plot_styles = plot_styles;
+        self.set_modified(DocumentEvent::SettingsChanged);
+    }
+
+    /// The grid, the coordinates shown and typed and the polar tracking go by the UCS.
+    pub fn get_ucs(&self) -> &Ucs {
+        &self.ucs
+    }
+
+    /// Ends and centers of the visible entities and the directions of the lines, in the UCS.
+    pub fn get_guide_references(&self) -> GuideReferences {
+        let mut references = GuideReferences::default();
+        for (id, element) in &self.content {
+            if self.hidden.contains(id) {
+                continue;
+            }
+            match element {
+                Element::Point(point) => references.points.push(self.ucs.to_local(point.position)),
+                Element::Curve(curve) => match curve.curve {
+                    curves::Curve::Segment(s) if s.contour.a == 0.0 => {
+                        references.points.push(self.ucs.to_local(s.begin));
+                        references.points.push(self.ucs.to_local(s.end));
+                        references
+                            .directions
+                            .push(self.ucs.vector_to_local(s.end - s.begin).normalize());
+                    }
+                    curves::Curve::Segment(s) => {
+                        references.points.push(self.ucs.to_local(s.begin));
+                        references.points.push(self.ucs.to_local(s.end));
+                        references
+                            .points
+                            .push(self.ucs.to_local(s.contour.get_center()));
+                    }
+                    curves::Curve::Contour(c) if c.a != 0.0 => {
+                        references.points.push(self.ucs.to_local(c.get_center()));
+                    }
+                    curves::Curve::Contour(_) => {}
+                },
+                Element::Ellipse(ellipse) => {
+                    let ellipse = &ellipse.ellipse;
+                    let ends = ellipse.get_ends().map_or(Vec::new(), |(a, b)| vec![a, b]);
+                    for point in ends.into_iter().chain([ellipse.center]) {
+                        references.points.push(self.ucs.to_local(point));
+                    }
+                }
+                Element::Group(_) => {}
+            }
+        }
+        references
+    }
+
+    pub fn set_ucs(&mut self, ucs: Ucs) {
+        if self.is_edit_refused() {
+            return;
+        }
+        self.ucs = ucs;
+        self.set_modified(DocumentEvent::SettingsChanged);
+    }
+
+    pub fn is_y_up(&self) -> bool {
+        self.y_up
+    }
+
+    pub fn set_y_up(&mut self, y_up: bool) {
+        if self.is_edit_refused() {
+            return;
+        }
+        self.y_up = y_up;
+        self.set_modified(DocumentEvent::SettingsChanged);
+    }
+
+    /// Document units in one paper millimeter, without a sheet the units are millimeters.
+    pub fn get_units_per_mm(&self) -> f64 {
+        self.sheet.as_ref().map_or(1.0, |sheet| sheet.scale)
+    }
+
+    /// Model curves together with the sheet lines, as they go to the paper.
+    pub fn get_output_curves(&self) -> Vec<curves::Curve<f64>> {
+        self.get_plotted_curves()
+            .into_iter()
+            .map(|plotted| plotted.curve)
+            .collect()
+    }
+
+    /// Output curves with the pens of the plot style table.
+    pub fn get_plotted_curves(&self) -> Vec<PlottedCurve> {
+        self.plot_curves(false)
+    }
+
+    /// Selected curves with the same pens as in `get_plotted_curves`, in the model and in
+    /// the viewports, without the sheet lines.
+    pub fn get_selected_plotted_curves(&self) -> Vec<PlottedCurve> {
+        self.plot_curves(true)
+    }
+
+    fn plot_curves(&self, selected_only: bool) -> Vec<PlottedCurve> {
+        let plot = |curve: curves::Curve<f64>,
+                    width: Option<f64>,
+                    opacity: Opacity,
+                    kind: OutputLineKind| PlottedCurve {
+            curve,
+            style: self.plot_styles.get(kind),
+            width,
+            opacity,
+        };
+        let model: Vec<_> = self
+            .get_shapes_in_draw_order()
+            .into_iter()
+            .filter(|(_, curve)| curve.selected || !selected_only)
+            .map(|(_, curve)| (curve.curve, curve.width, curve.opacity))
+            .collect();
+        let mut result: Vec<_> = model
+            .iter()
+            .map(|(curve, width, opacity)| plot(*curve, *width, *opacity, OutputLineKind::Model))
+            .collect();
+        if let Some(sheet) = &self.sheet {
+            for viewport in &sheet.viewports {
+                // curves crossing the frame are skipped, there is no geometric clipping yet
+                let (corner1, corner2) = sheet.get_viewport_rect(viewport);
+                let factor = sheet.get_viewport_factor(viewport);
+                result.extend(
+                    model
+                        .iter()
+                        .map(|(curve, width, opacity)| {
+                            (
+                                sheet.to_viewport(viewport, *curve),
+                                width.map(|w| w * factor),
+                                *opacity,
+                            )
+                        })
+                        .filter(|(curve, _, _)| curve.in_rect(corner1, corner2))
+                        .map(|(curve, width, opacity)| {
+                            plot(curve, width, opacity, OutputLineKind::Viewport)
+                        }),
+                );
+            }
+            if !selected_only {
+                result.extend(sheet.get_segments().into_iter().map(|segment| {
+                    plot(
+                        curves::Curve::Segment(segment),
+                        None,
+                        Opacity::default(),
+                        OutputLineKind::Sheet,
+                    )
+                }));
+            }
+        }
+        result
+    }
+
+    pub fn get_selection_rectangle(&self) -> Option<(Point, Point)> {
+        if let DocumentState::DocumentSelecting(documelt_selecting) = &self.state {
+            Some((documelt_selecting.corner1, documelt_selecting.corner2))
+        } else {
+            None
+        }
+    }
+
+    pub fn is_highlight(&self, id: usize) -> bool {
+        self.highliht_id.map(|hid| hid == id).unwrap_or(false)
+    }
+
+    /// Number of the picked curve from zero and the number of the curves under the click,
+    /// while there is a choice.
+    pub fn get_pick_cycle(&self) -> Option<(usize, usize)> {
+        let cycle = self.pick_cycle.as_ref()?;
+        Some((cycle.index, cycle.candidates.len()))
+    }
+
+    /// The snap point if the mouse is attracted to one, otherwise the position itself.
+    pub fn get_snapped_position(&self, position: Point) -> Point {
+        match self.highlight_point.kind {
+            HighlightPointKind::None => position,
+            _ => self.highlight_point.position,
+        }
+    }
+
+    pub fn has_selection(&self) -> bool {
+        self.content.values().any(Element::is_selected)
+    }
+
+    pub fn get_entity_count(&self) -> usize {
+        self.content.len()
+    }
+
+    pub fn get_selected_count(&self) -> usize {
+        self.content
+            .values()
+            .filter(|element| element.is_selected())
+            .count()
+    }
+
+    pub fn get_highlight_point(&self) -> &HighlightPoint {
+        &self.highlight_point
+    }
+
+    pub fn get_witness_line(&self) -> Option<(Point, Point)> {
+        self.witness_line.get()
+    }
+
+    /// Finds the closest points of the two selected curves and shows the line between
+    /// them. Returns None unless exactly two curves are selected.
+    pub fn show_closest_points(&self) -> Option<(Point, Point)> {
+        let curves: Vec<_> = self
+            .content
+            .values()
+            .filter_map(|element| match element {
+                Element::Curve(curve) if curve.selected => Some(curve.curve),
+                _ => None,
+            })
+            .collect();
+        let [curve1, curve2] = curves[..] else {
+            return None;
+        };
+        let points = closest_points(&curve1, &curve2, EPS);
+        self.notify(DocumentEvent::ViewChanged);
+        self.witness_line.set(Some(points));
+        Some(points)
+    }
+
+    /// Direction from the begin to the end of the line or the straight segment nearest to
+    /// the position. The selected ones come first, so the rotated line is taken where it
+    /// crosses another one.
+    pub fn get_line_direction_at(&self, position: Point, config: &Config) -> Option<Point> {
+        let direction = |id: &usize| match self.content.get(id) {
+            Some(Element::Curve(curve)) => match curve.curve {
+                curves::Curve::Segment(s) if s.contour.a == 0.0 => Some(s.end - s.begin),
+                curves::Curve::Contour(c) if c.a == 0.0 => Some(c.n.rot90()),
+                _ => None,
+            },
+            _ => None,
+        };
+        let candidates = self.find_pick_candidates(position, self.pick_distance(config));
+        let (selected, others): (Vec<_>, Vec<_>) = candidates
+            .iter()
+            .partition(|id| self.content.get(id).map_or(false, Element::is_selected));
+        selected
+            .into_iter()
+            .chain(others)
+            .filter_map(direction)
+            .find(|direction| direction.length() > EPS)
+    }
+
+    fn apply_diff(content: &mut HashMap<usize, Element>, diff: &Diff) {
+        for edition in &diff.editions {
+            Self::apply_edition(content, edition.redo());
+        }
+    }
+
+    /// Grows the kept extents by an added entity. A removed one which touches them makes
+    /// them unknown, they are searched again when asked.
+    fn track_extents(&self, edition: EditionRef) {
+        let (element, id, added) = match edition {
+            EditionRef::Add(element, id) => (element, id, true),
+            EditionRef::Remove(element, id) => (element, id, false),
+            EditionRef::AddToGroup(..) | EditionRef::RemoveFromGroup(..) => return,
+        };
+        let known = match self.extents.get() {
+            TrackedExtents::Known(known) => known,
+            TrackedExtents::Unknown => return,
+        };
+        if self.hidden.contains(&id) {
+            return;
+        }
+        let bounds = match get_bounds(get_element_points(element)) {
+            Some(bounds) => bounds,
+            None => return,
+        };
+        let extents = match (known, added) {
+            (None, true) => TrackedExtents::Known(Some(bounds)),
+            (Some((min, max)), true) => {
+                TrackedExtents::Known(get_bounds([min, max, bounds.0, bounds.1]))
+            }
+            (Some((min, max)), false)
+                if bounds.0.x > min.x
+                    && bounds.0.y > min.y
+                    && bounds.1.x < max.x
+                    && bounds.1.y < max.y =>
+            {
+                return;
+            }
+            (_, false) => TrackedExtents::Unknown,
+        };
+        self.extents.set(extents);
+    }
+
+    fn track_diff_extents<'a>(&self, editions: impl Iterator<Item = EditionRef<'a>>) {
+        for edition in editions {
+            self.track_extents(edition);
+        }
+    }
+
+    /// Changes which go over the entity limit or change a read-only document are refused
+    /// with a warning.
+    fn add_and_apply_diff(&mut self, diff: Diff) {
+        self.nudge = None;
+        if self.is_edit_refused() {
+            return;
+        }
+        let added = diff
+            .editions
+            .iter()
+            .filter(|e| matches!(e, Edition::Add(..)))
+            .count();
+        let removed = diff
+            .editions
+            .iter()
+            .filter(|e| matches!(e, Edition::Remove(..)))
+            .count();
+        if added > removed && self.content.len() + added - removed > self.limits.max_entities {
+            self.warnings.push(format!(
+                "Превышен лимит в {} объектов, действие отменено",
+                self.limits.max_entities
+            ));
+            return;
+        }
+
+        Self::apply_diff(&mut self.content, &diff);
+        self.track_diff_extents(diff.editions.iter().map(Edition::redo));
+        let event = DocumentEvent::from_editions(diff.editions.iter().map(Edition::redo));
+        for dropped in self.history.drain(self.history_position..) {
+            self.history_memory -= dropped.memory_size();
+        }
+        self.history_memory += diff.memory_size();
+        self.history.push(diff);
+        self.history_position += 1;
+        self.check_limits();
+        self.set_modified(event);
+        self.notify(DocumentEvent::HistoryChanged);
     }
 
-    pub fn get_content(&self) -> &HashMap<usize, Element> {
-        &self.content
-    }
+    fn check_limits(&mut self) {
+        let mut dropped = 0;
+        while self.history_memory > self.limits.max_undo_memory && dropped < self.history_position {
+            self.history_memory -= self.history[dropped].memory_size();
+            dropped += 1;
+        }
+        self.history.drain(..dropped);
+        self.history_position -= dropped;
+        if dropped > 0 && !self.undo_warning_shown {
+            self.undo_warning_shown = true;
+            self.warnings.push(format!(
+                "История отмены сокращена до {} шагов",
+                self.history_position
+            ));
+        }
 
-    pub fn get_selection_rectangle(&self) -> Option<(Point, Point)> {
-        if let DocumentState::DocumentSelecting(documelt_selecting) = &self.state {
-            Some((documelt_selecting.corner1, documelt_selecting.corner2))
+        // warn once when 90% of the limit is reached
+        let entities = self.content.len();
+        if entities * 10 >= self.limits.max_entities * 9 {
+            if !self.entities_warning_shown {
+                self.entities_warning_shown = true;
+                self.warnings.push(format!(
+                    "Объектов {} из {} допустимых",
+                    entities, self.limits.max_entities
+                ));
+            }
         } else {
-            None
+            self.entities_warning_shown = false;
         }
     }
 
-    pub fn is_highlight(&self, id: usize) -> bool {
-        self.highliht_id.map(|hid| hid == id).unwrap_or(false)
+    pub fn set_limits(&mut self, limits: DocumentLimits) {
+        self.limits = limits;
+        self.check_limits();
     }
 
-    pub fn get_highlight_point(&self) -> &HighlightPoint {
-        &self.highlight_point
+    /// Warnings about the limits since the last call.
+    pub fn take_warnings(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.warnings)
     }
 
-    fn apply_diff(content: &mut HashMap<usize, Element>, diff: &Diff) {
-        for edition in &diff.editions {
-            Self::apply_edition(content, edition.redo());
+    pub fn get_memory_usage(&self) -> MemoryUsage {
+        let entry_size = std::mem::size_of::<usize>() + std::mem::size_of::<Element>();
+        let heap_size: usize = self
+            .content
+            .values()
+            .map(|element| element.memory_size() - std::mem::size_of::<Element>())
+            .sum();
+        MemoryUsage {
+            entities: self.content.len(),
+            content_bytes: self.content.capacity() * entry_size + heap_size,
+            undo_steps: self.history_position,
+            undo_bytes: self.history_memory,
         }
     }
 
-    fn add_and_apply_diff(&mut self, diff: Diff) {
-        Self::apply_diff(&mut self.content, &diff);
-        self.history.truncate(self.history_position);
-        self.history.push(diff);
-        self.history_position += 1;
-    }
-
     pub fn fix_history(&mut self) {
         self.history.clear();
         self.history_position = 0;
+        self.history_memory = 0;
+        self.notify(DocumentEvent::HistoryChanged);
     }
 
     pub fn undo(&mut self) {
-        if self.history_position > 0 {
+        self.nudge = None;
+        if self.history_position > 0 && !self.is_edit_refused() {
             self.history_position -= 1;
-            for edition in &self.history[self.history_position].editions {
+            let diff = &self.history[self.history_position];
+            for edition in &diff.editions {
                 Self::apply_edition(&mut self.content, edition.undo());
             }
+            self.track_diff_extents(diff.editions.iter().map(Edition::undo));
+            let event = DocumentEvent::from_editions(diff.editions.iter().map(Edition::undo));
+            self.set_modified(event);
+            self.notify(DocumentEvent::HistoryChanged);
         }
     }
 
     pub fn redo(&mut self) {
-        if self.history_position < self.history.len() {
-            Self::apply_diff(&mut self.content, &self.history[self.history_position]);
+        self.nudge = None;
+        if self.history_position < self.history.len() && !self.is_edit_refused() {
+            let diff = &self.history[self.history_position];
+            Self::apply_diff(&mut self.content, diff);
+            self.track_diff_extents(diff.editions.iter().map(Edition::redo));
+            let event = DocumentEvent::from_editions(diff.editions.iter().map(Edition::redo));
             self.history_position += 1;
+            self.set_modified(event);
+            self.notify(DocumentEvent::HistoryChanged);
         }
     }
 
@@ -346,6 +1982,7 @@ impl Document {
                         diff.editions.push(Edition::RemoveFromGroup(group_id, id));
                     }
                 }
+                Element::Point(_) | Element::Ellipse(_) => {}
             }
             diff.editions.push(Edition::Remove(removed.clone(), id));
         }
@@ -369,29 +2006,662 @@ impl Document {
         self.add_and_apply_diff(diff);
     }
 
+    /// Adds a curve, a point or an ellipse without its group and selection.
+    pub fn add_element(&mut self, element: &Element) -> Option<usize> {
+        let element = match element {
+            Element::Curve(curve) => Element::Curve(Curve {
+                group_id: None,
+                selected: false,
+                ..curve.clone()
+            }),
+            Element::Point(point) => Element::Point(PointEntity::new(point.position)),
+            Element::Ellipse(ellipse) => Element::Ellipse(EllipseEntity {
+                selected: false,
+                ..ellipse.clone()
+            }),
+            Element::Group(_) => return None,
+        };
+        let id = self.last_entity_id;
+        self.last_entity_id += 1;
+        let mut diff = Diff::default();
+        diff.editions.push(Edition::Add(element, id));
+        self.add_and_apply_diff(diff);
+        Some(id)
+    }
+
+    /// Selected curves in the draw order, then the selected points and ellipses, without
+    /// their groups.
+    pub fn get_selected_elements(&self) -> Vec<Element> {
+        let curves = self
+            .get_curves_in_draw_order()
+            .into_iter()
+            .filter(|(_, curve)| curve.selected)
+            .map(|(_, curve)| {
+                Element::Curve(Curve {
+                    group_id: None,
+                    ..curve.clone()
+                })
+            });
+        let points = self.content.values().filter_map(|element| match element {
+            Element::Point(point) if point.selected => Some(element.clone()),
+            Element::Ellipse(ellipse) if ellipse.selected => Some(element.clone()),
+            _ => None,
+        });
+        curves.chain(points).collect()
+    }
+
+    /// Adds curves and points as one undo step, they become the selection.
+    pub fn paste_elements(&mut self, elements: &[Element]) {
+        self.skip_state();
+        let mut diff = Diff::default();
+        for element in elements {
+            let element = match element {
+                Element::Curve(curve) => Element::Curve(Curve {
+                    group_id: None,
+                    selected: true,
+                    ..curve.clone()
+                }),
+                Element::Point(point) => Element::Point(PointEntity {
+                    selected: true,
+                    ..point.clone()
+                }),
+                Element::Ellipse(ellipse) => Element::Ellipse(EllipseEntity {
+                    selected: true,
+                    ..ellipse.clone()
+                }),
+                Element::Group(_) => continue,
+            };
+            diff.editions
+                .push(Edition::Add(element, self.last_entity_id));
+            self.last_entity_id += 1;
+        }
+        self.add_and_apply_diff(diff);
+    }
+
+    /// All points are added as one undo step.
+    pub fn add_points(&mut self, positions: &[Point]) {
+        let mut diff = Diff::default();
+        for position in positions {
+            diff.editions.push(Edition::Add(
+                Element::Point(PointEntity::new(*position)),
+                self.last_entity_id,
+            ));
+            self.last_entity_id += 1;
+        }
+        self.add_and_apply_diff(diff);
+    }
+
+    pub fn get_curve(&self, id: usize) -> Option<&Curve> {
+        match self.content.get(&id) {
+            Some(Element::Curve(curve)) => Some(curve),
+            _ => None,
+        }
+    }
+
+    /// UCS of the single selected curve: a line gives the origin at its begin and X toward
+    /// its end, an arc the origin at its center and X toward its begin, a circle moves only
+    /// the origin to its center.
+    pub fn get_ucs_of_selected(&self) -> Option<Ucs> {
+        let curve = self.get_curve(self.get_single_selected()?)?;
+        match (curve.curve, curve.get_parameters()?) {
+            (_, CurveParameters::Line { begin, end }) => {
+                let direction = end - begin;
+                Some(Ucs::rotated(begin, direction.y.atan2(direction.x)))
+            }
+            (curves::Curve::Segment(s), CurveParameters::Arc { center, .. }) => {
+                let direction = s.begin - center;
+                Some(Ucs::rotated(center, direction.y.atan2(direction.x)))
+            }
+            (_, CurveParameters::Circle { center, .. }) => Some(Ucs {
+                origin: center,
+                ..Ucs::default()
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn get_single_selected(&self) -> Option<usize> {
+        let mut selected = self.content.iter().filter_map(|(id, l)| match l {
+            Element::Curve(curve) if curve.selected => Some(*id),
+            _ => None,
+        });
+        let result = selected.next();
+        if selected.next().is_some() {
+            return None;
+        }
+        result
+    }
+
+    /// Replaces the curve keeping its id, as one undo step.
+    pub fn replace_curve(&mut self, id: usize, curve: Curve) {
+        let old = match self.content.get(&id) {
+            Some(old @ Element::Curve(_)) => old.clone(),
+            _ => return,
+        };
+        let mut diff = Diff::default();
+        diff.editions.push(Edition::Remove(old, id));
+        diff.editions.push(Edition::Add(Element::Curve(curve), id));
+        self.add_and_apply_diff(diff);
+    }
+
+    /// Replaces the selected curves by the changed ones, as one undo step. Returns false
+    /// if no curve was changed.
+    fn change_selected_curves(&mut self, mut change: impl FnMut(&Curve) -> Curve) -> bool {
+        let mut diff = Diff::default();
+        for (id, curve) in self.get_curves_in_draw_order() {
+            if !curve.selected {
+                continue;
+            }
+            let new_curve = change(curve);
+            if new_curve.width != curve.width
+                || new_curve.order != curve.order
+                || new_curve.opacity != curve.opacity
+            {
+                diff.editions
+                    .push(Edition::Remove(Element::Curve(curve.clone()), id));
+                diff.editions
+                    .push(Edition::Add(Element::Curve(new_curve), id));
+            }
+        }
+        if diff.editions.is_empty() {
+            return false;
+        }
+        self.add_and_apply_diff(diff);
+        true
+    }
+
+    /// Sets the width of the selected curves in the document units, as one undo step.
+    /// Returns false if no curve was changed.
+    pub fn set_selected_width(&mut self, width: Option<f64>) -> bool {
+        self.change_selected_curves(|curve| Curve {
+            width,
+            ..curve.clone()
+        })
+    }
+
+    /// Sets the opacity of the selected curves, as one undo step. Returns false if no
+    /// curve was changed.
+    pub fn set_selected_opacity(&mut self, opacity: Opacity) -> bool {
+        self.change_selected_curves(|curve| Curve {
+            opacity,
+            ..curve.clone()
+        })
+    }
+
+    /// Visible curves from the bottom to the top, curves with the same order go by id.
+    pub fn get_curves_in_draw_order(&self) -> Vec<(usize, &Curve)> {
+        let mut curves: Vec<_> = self
+            .content
+            .iter()
+            .filter_map(|(id, element)| match element {
+                Element::Curve(curve) if !self.hidden.contains(id) => Some((*id, curve)),
+                _ => None,
+            })
+            .collect();
+        curves.sort_unstable_by_key(|(id, curve)| (curve.order, *id));
+        curves
+    }
+
+    /// Visible curves and the arcs of the visible ellipses in the draw order, every arc has
+    /// the id of its ellipse. For drawing and output, the editing goes by the elements.
+    pub fn get_shapes_in_draw_order(&self) -> Vec<(usize, Cow<'_, Curve>)> {
+        let mut shapes: Vec<_> = self
+            .content
+            .iter()
+            .filter(|(id, _)| !self.hidden.contains(id))
+            .flat_map(|(id, element)| match element {
+                Element::Curve(curve) => vec![(*id, Cow::Borrowed(curve))],
+                Element::Ellipse(ellipse) => ellipse
+                    .get_curves()
+                    .into_iter()
+                    .map(|curve| (*id, Cow::Owned(curve)))
+                    .collect(),
+                _ => Vec::new(),
+            })
+            .collect();
+        // the stable sort keeps the arcs of an ellipse in their order
+        shapes.sort_by_key(|(id, curve)| (curve.order, *id));
+        shapes
+    }
+
+    /// Visible curves in the draw order, then the visible points.
+    pub fn get_visible_elements(&self) -> Vec<Element> {
+        let mut elements: Vec<_> = self
+            .get_curves_in_draw_order()
+            .into_iter()
+            .map(|(_, curve)| Element::Curve(curve.clone()))
+            .collect();
+        let mut points: Vec<_> = self
+            .content
+            .iter()
+            .filter(|(id, element)| {
+                matches!(element, Element::Point(_)) && !self.hidden.contains(id)
+            })
+            .collect();
+        points.sort_unstable_by_key(|(id, _)| **id);
+        elements.extend(points.into_iter().map(|(_, element)| element.clone()));
+        elements
+    }
+
+    /// Orders of all curves, the hidden ones too.
+    fn get_curve_orders(&self) -> impl Iterator<Item = i64> + '_ {
+        self.content.values().filter_map(|element| match element {
+            Element::Curve(curve) => Some(curve.order),
+            _ => None,
+        })
+    }
+
+    /// Moves the selected curves over all the others, keeping their order among themselves.
+    pub fn bring_selected_to_front(&mut self) -> bool {
+        let top = self.get_curve_orders().max().unwrap_or(0);
+        let mut order = top;
+        self.change_selected_curves(|curve| {
+            order += 1;
+            Curve {
+                order,
+                ..curve.clone()
+            }
+        })
+    }
+
+    /// Moves the selected curves under all the others, keeping their order among themselves.
+    pub fn send_selected_to_back(&mut self) -> bool {
+        let bottom = self.get_curve_orders().min().unwrap_or(0);
+        let selected = self
+            .get_curves_in_draw_order()
+            .iter()
+            .filter(|(_, curve)| curve.selected)
+            .count() as i64;
+        let mut order = bottom - selected;
+        self.change_selected_curves(|curve| {
+            let result = Curve {
+                order,
+                ..curve.clone()
+            };
+            order += 1;
+            result
+        })
+    }
+
+    /// Moves, rotates and scales the selected curves and points, as one undo step. Widths of
+    /// the curves are scaled too. Returns false if nothing is selected.
+    pub fn transform_selected(&mut self, transform: &Transform) -> bool {
+        let mut diff = Diff::default();
+        for (id, element) in &self.content {
+            if !element.is_selected() {
+                continue;
+            }
+            let new_element = element.transformed(transform);
+            diff.editions.push(Edition::Remove(element.clone(), *id));
+            diff.editions.push(Edition::Add(new_element, *id));
+        }
+        if diff.editions.is_empty() {
+            return false;
+        }
+        self.add_and_apply_diff(diff);
+        true
+    }
+
+    /// Moves the selection by the shift, nudges of the same selection in a row are one undo
+    /// step.
+    pub fn nudge_selected(&mut self, shift: Point) {
+        let ids: HashSet<usize> = self
+            .content
+            .iter()
+            .filter(|(_, element)| element.is_selected())
+            .map(|(id, _)| *id)
+            .collect();
+        let mut total = shift;
+        if let Some(nudge) = self.nudge.take() {
+            if nudge.ids == ids && self.history_position == self.history.len() {
+                self.undo();
+                total = nudge.shift + shift;
+            }
+        }
+        if self.transform_selected(&Transform::translation(total)) {
+            self.nudge = Some(Nudge { shift: total, ids });
+        }
+    }
+
+    /// Replaces selected circles by isometric ovals of four arcs, as one undo step. Returns
+    /// false if no circle is selected.
+    pub fn convert_selected_circles_to_isometric(&mut self, isoplane: Isoplane) -> bool {
+        let mut diff = Diff::default();
+        let circles: Vec<_> = self
+            .content
+            .iter()
+            .filter_map(|(id, l)| match l {
+                Element::Curve(curve) if curve.selected => match curve.get_parameters() {
+                    Some(CurveParameters::Circle { center, radius }) => Some((*id, center, radius)),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect();
+        for (id, center, radius) in circles {
+            diff = diff.append(self.remove_entity_diff(id).0);
+            for arc in isometric_ellipse(center, radius, isoplane) {
+                let mut arc = Curve::new_segment(arc);
+                arc.selected = true;
+                diff.editions
+                    .push(Edition::Add(Element::Curve(arc), self.last_entity_id));
+                self.last_entity_id += 1;
+            }
+        }
+        if diff.editions.is_empty() {
+            return false;
+        }
+        self.add_and_apply_diff(diff);
+        true
+    }
+
+    /// Moves both ends of the selected lines and arcs along them by the length, a negative
+    /// length shortens them, as one undo step. Curves which would vanish are left as they
+    /// are. Returns false if no curve was changed.
+    pub fn extend_selected(&mut self, length: f64) -> bool {
+        let mut diff = Diff::default();
+        for (id, curve) in self.get_curves_in_draw_order() {
+            if !curve.selected {
+                continue;
+            }
+            let extended = match curve.curve {
+                curves::Curve::Segment(s) => match s.extended(length, length) {
+                    Some(extended) => extended,
+                    None => continue,
+                },
+                curves::Curve::Contour(_) => continue,
+            };
+            diff.editions
+                .push(Edition::Remove(Element::Curve(curve.clone()), id));
+            diff.editions.push(Edition::Add(
+                Element::Curve(Curve {
+                    curve: curves::Curve::Segment(extended),
+                    ..curve.clone()
+                }),
+                id,
+            ));
+        }
+        if diff.editions.is_empty() {
+            return false;
+        }
+        self.add_and_apply_diff(diff);
+        true
+    }
+
+    /// The curves go the other way, as one undo step. Returns false if there is no curve
+    /// among the ids.
+    pub fn reverse_curves(&mut self, ids: &[usize]) -> bool {
+        let mut diff = Diff::default();
+        for id in ids {
+            let curve = match self.content.get(id) {
+                Some(Element::Curve(curve)) => curve,
+                _ => continue,
+            };
+            diff.editions
+                .push(Edition::Remove(Element::Curve(curve.clone()), *id));
+            diff.editions.push(Edition::Add(
+                Element::Curve(Curve {
+                    curve: curve.curve.reversed(),
+                    ..curve.clone()
+                }),
+                *id,
+            ));
+        }
+        if diff.editions.is_empty() {
+            return false;
+        }
+        self.add_and_apply_diff(diff);
+        true
+    }
+
+    /// Reverses the selected lines, arcs and circles.
+    pub fn reverse_selected(&mut self) -> bool {
+        let mut ids: Vec<_> = self
+            .content
+            .iter()
+            .filter(|(_, element)| element.is_selected())
+            .map(|(id, _)| *id)
+            .collect();
+        ids.sort_unstable();
+        self.reverse_curves(&ids)
+    }
+
+    /// Places the points given for each selected curve, as one undo step. Returns false
+    /// if no point was placed.
+    fn place_points_on_selected(
+        &mut self,
+        get_points: impl Fn(&curves::Curve<f64>) -> Vec<Point>,
+    ) -> bool {
+        let curves: Vec<_> = self
+            .get_curves_in_draw_order()
+            .into_iter()
+            .filter(|(_, curve)| curve.selected)
+            .map(|(_, curve)| curve.curve)
+            .collect();
+        let mut diff = Diff::default();
+        for curve in curves {
+            for point in get_points(&curve) {
+                diff.editions.push(Edition::Add(
+                    Element::Point(PointEntity::new(point)),
+                    self.last_entity_id,
+                ));
+                self.last_entity_id += 1;
+            }
+        }
+        if diff.editions.is_empty() {
+            return false;
+        }
+        self.add_and_apply_diff(diff);
+        true
+    }
+
+    /// Splits the selected curves into parts of the same length by points.
+    pub fn divide_selected(&mut self, count: usize) -> bool {
+        if count > self.limits.max_entities {
+            return false;
+        }
+        self.place_points_on_selected(|curve| curve.divide(count))
+    }
+
+    /// Places points along the selected curves at the steps from their begins.
+    pub fn measure_selected(&mut self, step: f64) -> bool {
+        let max_entities = self.limits.max_entities as f64;
+        self.place_points_on_selected(|curve| match curve.get_length() {
+            // so many points would be refused by the limit anyway
+            Some(length) if length / step < max_entities => curve.measure(step),
+            _ => Vec::new(),
+        })
+    }
+
+    /// Selects the chains of all selected curves and returns the gaps they have.
+    pub fn select_chain(&mut self, gap_tolerance: f64) -> Vec<ChainGap> {
+        let selected: Vec<_> = self
+            .content
+            .iter()
+            .filter(|(_, l)| l.is_selected())
+            .map(|(id, _)| *id)
+            .collect();
+        let mut gaps = Vec::new();
+        let mut chain_ids = HashSet::new();
+        for id in selected {
+            if chain_ids.contains(&id) {
+                continue;
+            }
+            if let Some(chain) = self.find_chain(id, gap_tolerance) {
+                chain_ids.extend(chain.links.iter().map(|link| link.id));
+                gaps.extend(chain.gaps);
+            }
+        }
+        self.set_selection(&chain_ids, true);
+        gaps
+    }
+
+    /// Hidden elements stay in the file, but are not drawn, snapped or selected.
+    pub fn is_hidden(&self, id: usize) -> bool {
+        self.hidden.contains(&id)
+    }
+
+    /// Locked elements are drawn and snapped, but can't be selected, so they can't be
+    /// changed.
+    pub fn is_locked(&self, id: usize) -> bool {
+        self.locked.contains(&id)
+    }
+
+    fn is_selectable(&self, id: usize) -> bool {
+        !self.is_hidden(id) && !self.is_locked(id)
+    }
+
+    fn get_selected_ids(&self) -> HashSet<usize> {
+        self.content
+            .iter()
+            .filter(|(_, element)| element.is_selected())
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
+    /// Returns false if nothing is selected.
+    pub fn hide_selected(&mut self) -> bool {
+        let ids = self.get_selected_ids();
+        if ids.is_empty() || self.is_edit_refused() {
+            return false;
+        }
+        self.skip_state();
+        self.highliht_id = None;
+        self.hidden.extend(ids);
+        self.extents.set(TrackedExtents::Unknown);
+        self.set_modified(DocumentEvent::VisibilityChanged);
+        true
+    }
+
+    /// Hides everything but the selection. Returns false if nothing is selected.
+    pub fn isolate_selected(&mut self) -> bool {
+        let ids = self.get_selected_ids();
+        if ids.is_empty() || self.is_edit_refused() {
+            return false;
+        }
+        let others: Vec<_> = self
+            .content
+            .iter()
+            .filter(|(id, element)| !matches!(element, Element::Group(_)) && !ids.contains(id))
+            .map(|(id, _)| *id)
+            .collect();
+        self.highliht_id = None;
+        self.hidden.extend(others);
+        self.extents.set(TrackedExtents::Unknown);
+        self.set_modified(DocumentEvent::VisibilityChanged);
+        true
+    }
+
+    /// Returns false if nothing was hidden.
+    pub fn unhide_all(&mut self) -> bool {
+        if self.hidden.is_empty() || self.is_edit_refused() {
+            return false;
+        }
+        self.hidden.clear();
+        self.extents.set(TrackedExtents::Unknown);
+        self.set_modified(DocumentEvent::VisibilityChanged);
+        true
+    }
+
+    /// Returns false if nothing is selected.
+    pub fn lock_selected(&mut self) -> bool {
+        let ids = self.get_selected_ids();
+        if ids.is_empty() || self.is_edit_refused() {
+            return false;
+        }
+        self.skip_state();
+        self.highliht_id = None;
+        self.locked.extend(ids);
+        self.set_modified(DocumentEvent::VisibilityChanged);
+        true
+    }
+
+    /// Returns false if nothing was locked.
+    pub fn unlock_all(&mut self) -> bool {
+        if self.locked.is_empty() || self.is_edit_refused() {
+            return false;
+        }
+        self.locked.clear();
+        self.set_modified(DocumentEvent::VisibilityChanged);
+        true
+    }
+
+    /// Remembers the selection under the name, the set with the same name is replaced.
+    /// Returns false if nothing is selected.
+    pub fn save_selection_set(&mut self, name: &str) -> bool {
+        let mut ids: Vec<_> = self.get_selected_ids().into_iter().collect();
+        if ids.is_empty() || self.is_edit_refused() {
+            return false;
+        }
+        ids.sort_unstable();
+        self.selection_sets.insert(name.to_string(), ids);
+        self.set_modified(DocumentEvent::SettingsChanged);
+        true
+    }
+
+    /// Names of the sets with the numbers of their entities which still exist.
+    pub fn get_selection_sets(&self) -> Vec<(String, usize)> {
+        self.selection_sets
+            .iter()
+            .map(|(name, ids)| {
+                let count = ids
+                    .iter()
+                    .filter(|id| self.content.contains_key(id))
+                    .count();
+                (name.clone(), count)
+            })
+            .collect()
+    }
+
+    /// Replaces the selection by the set, the hidden and locked entities are skipped.
+    /// Returns false if nothing of the set can be selected.
+    pub fn select_selection_set(&mut self, name: &str) -> bool {
+        let ids: HashSet<_> = match self.selection_sets.get(name) {
+            Some(ids) => ids.iter().copied().collect(),
+            None => return false,
+        };
+        self.skip_state();
+        self.set_selection(&ids, true);
+        self.has_selection()
+    }
+
+    pub fn remove_selection_set(&mut self, name: &str) -> bool {
+        if self.selection_sets.remove(name).is_none() {
+            return false;
+        }
+        self.set_modified(DocumentEvent::SettingsChanged);
+        true
+    }
+
+    pub fn select_only(&mut self, ids: &HashSet<usize>) {
+        self.skip_state();
+        self.set_selection(ids, true);
+    }
+
     pub fn remove_selected(&mut self) {
         let mut diff = Diff::default();
         for (id, l) in &self.content {
-            if let Element::Curve(curve) = l {
-                if curve.selected {
-                    diff = diff.append(self.remove_entity_diff(*id).0);
-                }
-            };
+            if l.is_selected() {
+                diff = diff.append(self.remove_entity_diff(*id).0);
+            }
         }
         self.add_and_apply_diff(diff);
     }
 
+    /// The finest power of ten whose grid lines are not closer than `MIN_GRID_SPACING`.
     pub fn get_grid_step(&self) -> f64 {
-        let mut grid_step = 1.0;
-        let scale = self.get_scale();
-        while grid_step * scale < 8.0 {
-            grid_step *= 10.0;
-        }
-        grid_step
+        let exponent = (MIN_GRID_SPACING / self.get_scale()).log10().ceil();
+        10f64.powi(exponent as i32)
+    }
+
+    /// Curves closer than this to the mouse are picked.
+    pub fn pick_distance(&self, config: &Config) -> f64 {
+        config.get_pick_radius() / self.get_scale()
     }
 
-    pub fn snap_distance(&self) -> f64 {
-        20.0 / self.get_scale()
+    /// Snap points closer than this to the mouse attract it.
+    pub fn snap_distance(&self, config: &Config) -> f64 {
+        config.get_snap_radius() / self.get_scale()
     }
 
     pub fn slide_distance(&self) -> f64 {
@@ -402,19 +2672,40 @@ impl Document {
         0.1 / self.get_scale()
     }
 
-    pub fn l_button_down(&mut self, position: Point) {
+    pub fn l_button_down(&mut self, position: Point, config: &Config) {
         match &self.state {
             DocumentState::Nothing => {
-                let max_distance = self.snap_distance();
-                let target = self.find_nearest_locc(position, max_distance);
+                let max_distance = self.pick_distance(config);
+                let candidates = self.find_pick_candidates(position, max_distance);
+                let target = match self.pick_cycle.take() {
+                    Some(mut cycle)
+                        if cycle.candidates == candidates
+                            && (cycle.position - position).length() <= self.slide_distance() =>
+                    {
+                        // the previous candidate gets its selection back
+                        self.toggle_selected(cycle.candidates[cycle.index]);
+                        cycle.index = (cycle.index + 1) % cycle.candidates.len();
+                        let target = cycle.candidates[cycle.index];
+                        self.pick_cycle = Some(cycle);
+                        Some(target)
+                    }
+                    _ => {
+                        if candidates.len() > 1 {
+                            self.pick_cycle = Some(PickCycle {
+                                position,
+                                candidates: candidates.clone(),
+                                index: 0,
+                            });
+                        }
+                        candidates.first().copied()
+                    }
+                };
                 self.state = DocumentState::DocumentClick(DocumentClick {
                     point: position,
                     selected_id: target,
                 });
                 if let Some(target) = target {
-                    if let Some(Element::Curve(curve)) = self.content.get_mut(&target) {
-                        curve.selected = !curve.selected;
-                    }
+                    self.toggle_selected(target);
                 }
             }
             _ => {}
@@ -429,37 +2720,60 @@ impl Document {
         }
     }
 
+    fn toggle_selected(&mut self, id: usize) {
+        if let Some(selected) = self
+            .content
+            .get_mut(&id)
+            .and_then(Element::get_selected_mut)
+        {
+            *selected = !*selected;
+            self.notify(DocumentEvent::SelectionChanged);
+        }
+    }
+
     fn set_selection(&mut self, ids: &HashSet<usize>, selected: bool) {
+        let mut changed = false;
         for id in ids {
-            if let Some(Element::Curve(curve)) = self.content.get_mut(id) {
-                curve.selected = selected;
+            if selected && !self.is_selectable(*id) {
+                continue;
             }
+            if let Some(element_selected) =
+                self.content.get_mut(id).and_then(Element::get_selected_mut)
+            {
+                changed |= *element_selected != selected;
+                *element_selected = selected;
+            }
+        }
+        if changed {
+            self.notify(DocumentEvent::SelectionChanged);
         }
     }
 
     fn fill_snap_point_info(&mut self, position: Point, config: &Config) -> bool {
         let mut new_highlight_point = HighlightPoint::default();
-        let mut sqr_dist = self.snap_distance() * self.snap_distance();
+        // with the snap turned off no point is close enough
+        let mut sqr_dist = if config.snap_enabled.0 {
+            self.snap_distance(config) * self.snap_distance(config)
+        } else {
+            0.0
+        };
         let treshold = EPS;
         // step1: try snap to grid
         if config.snap_options.snap_grid {
             let grid_step = self.get_grid_step();
-            let x1 = (position.x / grid_step).floor() * grid_step;
-            let x2 = x1 + grid_step;
-            let y1 = (position.y / grid_step).floor() * grid_step;
-            let y2 = y1 + grid_step;
-            let grid_point = Point::new(
-                if position.x - x1 > x2 - position.x {
-                    x2
-                } else {
-                    x1
-                },
-                if position.y - y1 > y2 - position.y {
-                    y2
-                } else {
-                    y1
-                },
-            );
+            let grid_point = if config.isometric {
+                nearest_isometric_grid_point(position, grid_step)
+            } else {
+                let local = self.ucs.to_local(position);
+                let x1 = (local.x / grid_step).floor() * grid_step;
+                let x2 = x1 + grid_step;
+                let y1 = (local.y / grid_step).floor() * grid_step;
+                let y2 = y1 + grid_step;
+                self.ucs.to_world(Point::new(
+                    if local.x - x1 > x2 - local.x { x2 } else { x1 },
+                    if local.y - y1 > y2 - local.y { y2 } else { y1 },
+                ))
+            };
             let sqr_candidate_dist = (position - grid_point).sqr_length();
             if sqr_candidate_dist < sqr_dist - treshold {
                 sqr_dist = sqr_candidate_dist;
@@ -469,6 +2783,43 @@ impl Document {
         // step2 : try snap to endpoint
         let mut iter1 = self.content.iter();
         while let Some((id, l)) = iter1.next() {
+            if self.hidden.contains(id) {
+                continue;
+            }
+            if let Element::Point(point) = l {
+                if config.snap_options.snap_points {
+                    let sqr_candidate_dist = (position - point.position).sqr_length();
+                    if sqr_candidate_dist < sqr_dist - treshold {
+                        sqr_dist = sqr_candidate_dist;
+                        new_highlight_point = HighlightPoint::end(point.position);
+                    }
+                }
+            }
+            if let Element::Ellipse(ellipse) = l {
+                let shape = ellipse.ellipse;
+                let mut candidates = Vec::new();
+                if config.snap_options.snap_endpoints {
+                    if let Some((begin, end)) = shape.get_ends() {
+                        candidates.push((begin, HighlightPoint::end(begin)));
+                        candidates.push((end, HighlightPoint::end(end)));
+                    }
+                }
+                if config.snap_options.snap_quadrants {
+                    for quadrant in shape.get_quadrant_points() {
+                        candidates.push((quadrant, HighlightPoint::quadrant(quadrant)));
+                    }
+                }
+                if config.snap_options.snap_centers {
+                    candidates.push((shape.center, HighlightPoint::center(shape.center, *id)));
+                }
+                for (candidate, highlight_point) in candidates {
+                    let sqr_candidate_dist = (position - candidate).sqr_length();
+                    if sqr_candidate_dist < sqr_dist - treshold {
+                        sqr_dist = sqr_candidate_dist;
+                        new_highlight_point = highlight_point;
+                    }
+                }
+            }
             if let Element::Curve(curve) = l {
                 if config.snap_options.snap_endpoints {
                     if let curves::Curve::Segment(s) = curve.curve {
@@ -484,6 +2835,24 @@ impl Document {
                         }
                     }
                 }
+                if config.snap_options.snap_midpoints {
+                    if let Some(middle) = curve.curve.get_midpoint() {
+                        let sqr_candidate_dist = (position - middle).sqr_length();
+                        if sqr_candidate_dist < sqr_dist - treshold {
+                            sqr_dist = sqr_candidate_dist;
+                            new_highlight_point = HighlightPoint::middle(middle);
+                        }
+                    }
+                }
+                if config.snap_options.snap_quadrants {
+                    for quadrant in curve.curve.get_quadrant_points() {
+                        let sqr_candidate_dist = (position - quadrant).sqr_length();
+                        if sqr_candidate_dist < sqr_dist - treshold {
+                            sqr_dist = sqr_candidate_dist;
+                            new_highlight_point = HighlightPoint::quadrant(quadrant);
+                        }
+                    }
+                }
                 if config.snap_options.snap_centers {
                     let contour = curve.curve.get_contour();
                     if let Some((sqr_candidate_dist, center)) =
@@ -499,7 +2868,10 @@ impl Document {
                     let dist_to_current = curve.curve.distance(position);
                     if dist_to_current * dist_to_current < sqr_dist {
                         let mut iter2 = iter1.clone();
-                        while let Some((_id2, l2)) = iter2.next() {
+                        while let Some((id2, l2)) = iter2.next() {
+                            if self.hidden.contains(id2) {
+                                continue;
+                            }
                             if let Element::Curve(curve2) = l2 {
                                 let dist_to_current2 = curve2.curve.distance(position);
                                 if dist_to_current2 * dist_to_current2 < sqr_dist {
@@ -536,8 +2908,19 @@ impl Document {
         let mut state = std::mem::take(&mut self.state); // prevent borrowing self
         match &mut state {
             DocumentState::Nothing => {
-                let max_distance = self.snap_distance();
-                let target = self.find_nearest_locc(position, max_distance);
+                let max_distance = self.pick_distance(config);
+                if let Some(cycle) = &self.pick_cycle {
+                    if (cycle.position - position).length() > self.slide_distance() {
+                        self.pick_cycle = None;
+                    }
+                }
+                // while there is a choice, the curve of the next click is highlighted
+                let target = match &self.pick_cycle {
+                    Some(cycle) => {
+                        Some(cycle.candidates[(cycle.index + 1) % cycle.candidates.len()])
+                    }
+                    None => self.find_nearest_locc(position, max_distance),
+                };
                 let result = self.highliht_id != target;
                 self.highliht_id = target;
                 let snap_point_changed = self.fill_snap_point_info(position, config);
@@ -546,9 +2929,15 @@ impl Document {
             DocumentState::DocumentClick(document_click) => {
                 let max_distance = self.slide_distance();
                 if (document_click.point - position).sqr_length() > max_distance * max_distance {
+                    self.pick_cycle = None;
                     if let Some(target) = document_click.selected_id {
-                        if let Some(Element::Curve(curve)) = self.content.get_mut(&target) {
-                            curve.selected = false;
+                        if let Some(selected) = self
+                            .content
+                            .get_mut(&target)
+                            .and_then(Element::get_selected_mut)
+                        {
+                            *selected = false;
+                            self.notify(DocumentEvent::SelectionChanged);
                         }
                     }
                     let new_selection =
@@ -571,47 +2960,67 @@ impl Document {
         }
     }
 
+    /// The selection rectangle follows the mouse.
+    pub fn is_dragging(&self) -> bool {
+        matches!(self.state, DocumentState::DocumentSelecting(_))
+    }
+
     pub fn l_button_up(&mut self, _: Point) {
         self.state = DocumentState::Nothing;
     }
 
     pub fn skip_state(&mut self) {
+        self.cancel_state();
+        self.clear_selection();
+    }
+
+    /// Drops the click or the selection rectangle in progress, returns false if there was
+    /// none.
+    pub fn cancel_state(&mut self) -> bool {
+        let active = !matches!(self.state, DocumentState::Nothing);
         self.state = DocumentState::Nothing;
+        self.pick_cycle = None;
+        active
+    }
+
+    /// Returns false if nothing was selected.
+    pub fn clear_selection(&mut self) -> bool {
+        let mut changed = false;
         for (_, l) in &mut self.content {
-            if let Element::Curve(curve) = l {
-                curve.selected = false;
+            if let Some(selected) = l.get_selected_mut() {
+                changed |= *selected;
+                *selected = false;
             };
         }
+        if changed {
+            self.notify(DocumentEvent::SelectionChanged);
+        }
+        changed
     }
 
     fn find_nearest_locc(&self, position: Point, max_distance: f64) -> Option<usize> {
-        let mut max_distance = max_distance;
-        let mut target = None;
-        for (id, l) in &self.content {
-            let curve = match l {
-                Element::Curve(curve) => curve,
-                _ => continue,
-            };
-
-            let dist = curve.curve.distance(position).abs();
-            if dist < max_distance {
-                max_distance = dist;
-                target = Some(*id);
-            }
-        }
+        self.find_pick_candidates(position, max_distance)
+            .first()
+            .copied()
+    }
 
-        target
+    /// Elements closer than `max_distance`, from the nearest one, equal distances go by id.
+    fn find_pick_candidates(&self, position: Point, max_distance: f64) -> Vec<usize> {
+        let mut candidates: Vec<_> = self
+            .content
+            .iter()
+            .filter(|(id, _)| self.is_selectable(**id))
+            .filter_map(|(id, l)| Some((l.distance(position)?, *id)))
+            .filter(|(dist, _)| *dist < max_distance)
+            .collect();
+        candidates.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        candidates.into_iter().map(|(_, id)| id).collect()
     }
 
     fn find_locc_inside_rect(&self, corner1: Point, corner2: Point) -> HashSet<usize> {
         let mut result = HashSet::new();
         for (id, l) in &self.content {
-            let curve = match l {
-                Element::Curve(curve) if !curve.selected => curve,
-                _ => continue,
-            };
-
-            if curve.curve.in_rect(corner1, corner2) {
+            if !l.is_selected() && self.is_selectable(*id) && l.in_rect(corner1, corner2) {
                 result.insert(*id);
             }
         }
@@ -619,3 +3028,65 @@ impl Document {
         result
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn big_file_is_loaded_in_batches() {
+        let count = LOAD_BATCH * 2 + 10;
+        let mut document = Document::new();
+        for i in 0..count {
+            let c = Contour::circle(Point::new(i as f64, 0.0), 1.0);
+            document.add_entity(Curve::new_contour(c));
+        }
+        let path = std::env::temp_dir().join(format!("otcad_loading_{}.json", std::process::id()));
+        document.save(&path).unwrap();
+
+        let mut loading = Document::start_reading(&path, None).unwrap();
+        let mut loaded_counts = Vec::new();
+        let mut last_progress = 0.0;
+        while loading.is_loading() {
+            let progress = loading.load_pending_entities().unwrap();
+            assert!(progress >= last_progress);
+            last_progress = progress;
+            let loaded = loading.get_content().len();
+            if loaded_counts.last() != Some(&loaded) && loaded > 0 {
+                loaded_counts.push(loaded);
+            }
+        }
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded_counts, [LOAD_BATCH, LOAD_BATCH * 2, count]);
+        assert_eq!(last_progress, 1.0);
+        let mut ids: Vec<_> = loading.get_content().keys().copied().collect();
+        ids.sort_unstable();
+        let mut expected_ids: Vec<_> = document.get_content().keys().copied().collect();
+        expected_ids.sort_unstable();
+        assert_eq!(ids, expected_ids);
+        assert_eq!(loading.last_entity_id, document.last_entity_id);
+        assert_eq!(loading.get_path(), Some(path.as_path()));
+        assert!(!loading.is_modified());
+    }
+
+    #[test]
+    fn missing_file_is_an_error() {
+        let path = std::env::temp_dir().join("otcad_loading_missing.json");
+        let mut loading = Document::start_reading(&path, None).unwrap();
+        assert!(loading.finish_loading().is_err());
+        assert!(!loading.is_loading());
+    }
+
+    #[test]
+    fn read_only_drawing_refuses_xref() {
+        let mut document = Document::new();
+        document.set_read_only(true);
+        let result = document.add_xref(Path::new("other.json"));
+        assert_eq!(
+            result,
+            Err("Чертёж только для чтения, изменение отменено".to_string())
+        );
+        assert!(document.get_xref_paths().is_empty());
+    }
+}
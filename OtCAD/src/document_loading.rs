@@ -0,0 +1,133 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use application::callback;
+use application::callback_body;
+use application::font::*;
+use application::gui::gui_components::*;
+use application::gui::toast::*;
+use application::gui::*;
+
+use crate::editor::*;
+use crate::gui_helper::*;
+
+/// Adds the entities of a big file to the open document by a long task, they are read by
+/// another thread and every step adds the next batch, so the drawing is redrawn while it
+/// loads. The task doesn't block the input, so the drawing can be panned and zoomed and
+/// the overlay's button cancels the load. The cancelled or failed document is closed.
+pub fn load_document_entities(
+    font: &Font,
+    document_id: usize,
+    editor: Rc<RefCell<Editor>>,
+    context: Rc<RefCell<window::Context>>,
+) {
+    let document = match editor
+        .borrow()
+        .get_documents()
+        .into_iter()
+        .find(|(id, _)| *id == document_id)
+    {
+        Some((_, document)) => document,
+        None => return,
+    };
+    if !document.borrow().is_loading() {
+        return;
+    }
+
+    let font_height = font.get_size("8").1 as i32 + 2;
+    let width = font.get_size("Загрузка чертежа: 100%, Esc - отмена").0 as i32 + font_height;
+    let height = font_height * 3 + 1;
+    let main_rect = context
+        .borrow()
+        .gui_system
+        .get_region_rect(MAIN_REGION)
+        .unwrap_or_default();
+    let center = (
+        (main_rect.left_top.0 + main_rect.right_bottom.0) / 2,
+        (main_rect.left_top.1 + main_rect.right_bottom.1) / 2,
+    );
+    let (panel, region_id) = context.borrow_mut().gui_system.add_region(
+        RegionPlacement::Overlay(Rect {
+            left_top: (center.0 - width / 2, center.1 - height / 2),
+            right_bottom: (center.0 + width / 2, center.1 + height / 2),
+        }),
+        Container::new(
+            SizeConstraints(SizeConstraint::flexible(0), SizeConstraint::flexible(0)),
+            ContainerLayout::Vertical,
+        ),
+    );
+    let caption = |progress: f32| {
+        format!(
+            "Загрузка чертежа: {}%, Esc - отмена",
+            (progress * 100.0) as u32
+        )
+    };
+    let progress_text = panel.borrow_mut().add_child(TextBox::new(
+        SizeConstraints(
+            SizeConstraint::flexible(0),
+            SizeConstraint::fixed(font_height),
+        ),
+        caption(0.0),
+        font.clone(),
+    ));
+    let progress_bar = panel
+        .borrow_mut()
+        .add_child(ProgressBar::new(SizeConstraints(
+            SizeConstraint::flexible(0),
+            SizeConstraint::flexible(0),
+        )));
+    let cancel_button = panel
+        .borrow_mut()
+        .add_child(create_default_size_button("Отмена", font.clone()));
+
+    let error: Rc<RefCell<Option<String>>> = Rc::default();
+    let job_system = context.borrow().job_system.clone();
+    let handle = {
+        let document = document.clone();
+        let error = error.clone();
+        job_system.start_background_task("Загрузка", move |handle| {
+            let result = document.borrow_mut().load_pending_entities();
+            match result {
+                Ok(progress) => {
+                    handle.set_progress(progress);
+                    progress_text.borrow_mut().set_text(&caption(progress));
+                    progress_bar.borrow_mut().set_progress(progress);
+                    !document.borrow().is_loading()
+                }
+                Err(e) => {
+                    *error.borrow_mut() = Some(e);
+                    true
+                }
+            }
+        })
+    };
+    {
+        let handle = handle.clone();
+        cancel_button
+            .borrow_mut()
+            .set_callback(move || handle.cancel());
+    }
+
+    let weak_editor = Rc::downgrade(&editor);
+    let weak_context = Rc::downgrade(&context);
+    handle.set_finish_callback(callback!([document] () {
+        let (editor, context) = match (weak_editor.upgrade(), weak_context.upgrade()) {
+            (Some(editor), Some(context)) => (editor, context),
+            _ => return,
+        };
+        context.borrow_mut().gui_system.remove_region(region_id);
+        let path = document.borrow().get_path().map(|path| path.display().to_string());
+        let text = match (&*error.borrow(), document.borrow().is_loading()) {
+            (Some(error), _) => {
+                format!("Не удалось открыть {}: {}", path.unwrap_or_default(), error)
+            }
+            (None, true) => "Открытие чертежа отменено".to_string(),
+            (None, false) => {
+                editor.borrow().finish_opening(&mut document.borrow_mut());
+                return;
+            }
+        };
+        editor.borrow().close_document(document_id);
+        context.borrow_mut().gui_system.show_toast(Toast::warning(&text));
+    }));
+}
@@ -9,7 +9,9 @@ use application::gui::gui_components::*;
 use application::gui::*;
 use application::keys::*;
 
+use crate::document::*;
 use crate::editor::*;
+use crate::ellipse::*;
 use crate::gui_helper::*;
 
 pub fn create_draw_menu(
@@ -18,12 +20,10 @@ pub fn create_draw_menu(
     editor: Rc<RefCell<Editor>>,
 ) -> Rc<RefCell<Container>> {
     let font_height = font.get_size("8").1 as i32 + 2;
-    let menu_caption = "Рисовать";
+    let menu_caption = "&Рисовать";
     let draw_menu = parent.add_tab(
         menu_caption.to_string(),
-        GuiSystem::default_size(&menu_caption, None, &font)
-            .0
-            .absolute,
+        Button::default_size(&menu_caption, None, &font).0.absolute,
         Container::new(
             SizeConstraints(
                 SizeConstraint::flexible(0),
@@ -50,22 +50,83 @@ pub fn create_draw_menu(
         .borrow_mut()
         .add_child(create_default_size_button("Дуга", font.clone()));
 
+    let _ellipse_button = draw_menu.borrow_mut().add_child(
+        create_default_size_button("Эллипс", font.clone()).callback(callback!([editor] () {
+            draw_ellipse_by_center(editor.clone())
+        })),
+    );
+
+    let _ellipse_by_axis_button = draw_menu.borrow_mut().add_child(
+        create_default_size_button("Эллипс по оси", font.clone()).callback(callback!([editor] () {
+            draw_ellipse_by_axis(editor.clone())
+        })),
+    );
+
     let _cut_enlarge_button = draw_menu.borrow_mut().add_child(create_default_size_button(
         "Нарастить/укоротить",
         font.clone(),
     ));
 
     let _skip_button = draw_menu.borrow_mut().add_child(
-        create_default_size_button_with_hotkey(
-            "Сброс",
-            font.clone(),
-            Hotkey::new(Key::Escape),
-            true,
-        )
-        .callback(callback!([editor] () {
-        editor.borrow_mut().skip_state()
+        create_default_size_button("Сброс", font.clone()).callback(callback!([editor] () {
+            editor.borrow_mut().skip_state()
         })),
     );
 
     draw_menu
 }
+
+/// The ellipse goes to the active document, nothing is added if an axis has no length.
+fn add_ellipse(editor: &Rc<RefCell<Editor>>, ellipse: Option<Ellipse>) {
+    let (ellipse, document) = match (ellipse, editor.borrow().get_active_document()) {
+        (Some(ellipse), Some(document)) => (ellipse, document),
+        _ => return,
+    };
+    document
+        .borrow_mut()
+        .add_element(&Element::Ellipse(EllipseEntity::new(ellipse)));
+}
+
+/// Center, then the end of one axis, then the length of the other one.
+fn draw_ellipse_by_center(editor: Rc<RefCell<Editor>>) {
+    editor.borrow().pick_point(
+        PointPick::new("Центр эллипса"),
+        callback!([editor] (center) {
+            editor.borrow().pick_point(
+                PointPick::new("Конец оси").from(center).polar(),
+                callback!([editor] (axis_end) {
+                    editor.borrow().pick_point(
+                        PointPick::new("Длина другой оси").from(center),
+                        callback!([editor] (other) {
+                            add_ellipse(&editor, Ellipse::from_axis(center, axis_end, other));
+                        }),
+                    );
+                }),
+            );
+        }),
+    );
+}
+
+/// Both ends of one axis, then the length of the other one from the middle.
+fn draw_ellipse_by_axis(editor: Rc<RefCell<Editor>>) {
+    editor.borrow().pick_point(
+        PointPick::new("Первый конец оси"),
+        callback!([editor] (axis_begin) {
+            editor.borrow().pick_point(
+                PointPick::new("Второй конец оси").from(axis_begin).polar(),
+                callback!([editor] (axis_end) {
+                    let center = (axis_begin + axis_end).scale(0.5);
+                    editor.borrow().pick_point(
+                        PointPick::new("Длина другой оси").from(center),
+                        callback!([editor] (other) {
+                            add_ellipse(
+                                &editor,
+                                Ellipse::from_axis_ends(axis_begin, axis_end, other),
+                            );
+                        }),
+                    );
+                }),
+            );
+        }),
+    );
+}
@@ -0,0 +1,213 @@
+use std::fmt::Write;
+
+use crate::export::*;
+use crate::plot_style::*;
+
+type Point = curves::points::Point<f64>;
+
+/// The document has no layers, every entity goes to the default one.
+const LAYER: &str = "0";
+/// Color of the layer, white on the dark background and black on the paper.
+const LAYER_COLOR: i32 = 7;
+/// The entities take the color of their layer.
+const COLOR_BY_LAYER: i32 = 256;
+
+fn write_pair(dxf: &mut String, code: i32, value: impl std::fmt::Display) {
+    let _ = writeln!(dxf, "{:>3}\n{}", code, value);
+}
+
+fn write_entity_start(dxf: &mut String, name: &str) {
+    write_pair(dxf, 0, name);
+    write_pair(dxf, 8, LAYER);
+    write_pair(dxf, 62, COLOR_BY_LAYER);
+}
+
+fn write_point(dxf: &mut String, code: i32, point: Point) {
+    write_pair(dxf, code, point.x);
+    write_pair(dxf, code + 10, point.y);
+    write_pair(dxf, code + 20, 0.0);
+}
+
+/// Angle of the point around the center in degrees from 0 to 360, counterclockwise from
+/// the X axis.
+fn get_angle(center: Point, point: Point) -> f64 {
+    (point.y - center.y)
+        .atan2(point.x - center.x)
+        .to_degrees()
+        .rem_euclid(360.0)
+}
+
+/// DXF file of version R12, which any program reads, with lines, circles and arcs. The Y
+/// axis of DXF goes up, the curves going down like the pictures are flipped. Infinite
+/// lines have no entity in R12 and are skipped.
+pub fn to_dxf(plotted_curves: &[PlottedCurve], y_down: bool) -> String {
+    let map = |point: Point| {
+        if y_down {
+            Point::new(point.x, -point.y)
+        } else {
+            point
+        }
+    };
+    let mut entities = String::new();
+    for plotted in plotted_curves {
+        match plotted.curve {
+            curves::Curve::Contour(c) if c.a != 0.0 => {
+                write_entity_start(&mut entities, "CIRCLE");
+                write_point(&mut entities, 10, map(c.get_center()));
+                write_pair(&mut entities, 40, c.get_radius().abs());
+            }
+            curves::Curve::Contour(_) => {}
+            curves::Curve::Segment(s) if s.contour.a == 0.0 => {
+                write_entity_start(&mut entities, "LINE");
+                write_point(&mut entities, 10, map(s.begin));
+                write_point(&mut entities, 11, map(s.end));
+            }
+            curves::Curve::Segment(s) => {
+                let center = map(s.contour.get_center());
+                let (begin, end) = (map(s.begin), map(s.end));
+                let middle = map(get_arc_middle(&s));
+                // DXF arcs go counterclockwise from the start angle
+                let counterclockwise = curves::points::cross(begin - center, middle - center) > 0.0;
+                let (start, finish) = if counterclockwise {
+                    (begin, end)
+                } else {
+                    (end, begin)
+                };
+                write_entity_start(&mut entities, "ARC");
+                write_point(&mut entities, 10, center);
+                write_pair(&mut entities, 40, s.contour.get_radius().abs());
+                write_pair(&mut entities, 50, get_angle(center, start));
+                write_pair(&mut entities, 51, get_angle(center, finish));
+            }
+        }
+    }
+
+    let mut dxf = String::new();
+    write_pair(&mut dxf, 0, "SECTION");
+    write_pair(&mut dxf, 2, "HEADER");
+    write_pair(&mut dxf, 9, "$ACADVER");
+    write_pair(&mut dxf, 1, "AC1009");
+    write_pair(&mut dxf, 0, "ENDSEC");
+    write_pair(&mut dxf, 0, "SECTION");
+    write_pair(&mut dxf, 2, "TABLES");
+    write_pair(&mut dxf, 0, "TABLE");
+    write_pair(&mut dxf, 2, "LAYER");
+    write_pair(&mut dxf, 70, 1);
+    write_pair(&mut dxf, 0, "LAYER");
+    write_pair(&mut dxf, 2, LAYER);
+    write_pair(&mut dxf, 70, 0);
+    write_pair(&mut dxf, 62, LAYER_COLOR);
+    write_pair(&mut dxf, 6, "CONTINUOUS");
+    write_pair(&mut dxf, 0, "ENDTAB");
+    write_pair(&mut dxf, 0, "ENDSEC");
+    write_pair(&mut dxf, 0, "SECTION");
+    write_pair(&mut dxf, 2, "ENTITIES");
+    dxf.push_str(&entities);
+    write_pair(&mut dxf, 0, "ENDSEC");
+    write_pair(&mut dxf, 0, "EOF");
+    dxf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::Opacity;
+
+    type Segment = curves::Segment<f64>;
+    type Contour = curves::Contour<f64>;
+
+    fn plotted(curve: curves::Curve<f64>) -> PlottedCurve {
+        PlottedCurve {
+            curve,
+            style: PlotStyle::solid(0.35),
+            width: None,
+            opacity: Opacity::default(),
+        }
+    }
+
+    /// Group codes and values of the entities section.
+    fn get_entities(dxf: &str) -> Vec<(i32, String)> {
+        let lines: Vec<_> = dxf.lines().collect();
+        let pairs: Vec<_> = lines
+            .chunks(2)
+            .map(|pair| (pair[0].trim().parse().unwrap(), pair[1].to_string()))
+            .collect();
+        let start = pairs
+            .iter()
+            .position(|pair| *pair == (2, "ENTITIES".to_string()))
+            .unwrap();
+        let end = pairs.len() - 2;
+        assert_eq!(pairs[end], (0, "ENDSEC".to_string()));
+        pairs[start + 1..end].to_vec()
+    }
+
+    fn get_value(entities: &[(i32, String)], code: i32) -> f64 {
+        let (_, value) = entities.iter().find(|(c, _)| *c == code).unwrap();
+        value.parse().unwrap()
+    }
+
+    fn assert_near(value: f64, expected: f64) {
+        assert!(
+            (value - expected).abs() < 1.0e-9,
+            "{} != {}",
+            value,
+            expected
+        );
+    }
+
+    #[test]
+    fn line_and_circle() {
+        let curves = [
+            plotted(curves::Curve::Segment(Segment::line(
+                Point::new(1.0, 2.0),
+                Point::new(3.0, 4.0),
+            ))),
+            plotted(curves::Curve::Contour(Contour::circle(
+                Point::new(5.0, 6.0),
+                2.0,
+            ))),
+        ];
+        let entities = get_entities(&to_dxf(&curves, true));
+        let circle_start = entities
+            .iter()
+            .position(|pair| *pair == (0, "CIRCLE".to_string()))
+            .unwrap();
+        let (line, circle) = entities.split_at(circle_start);
+
+        assert_eq!(line[0], (0, "LINE".to_string()));
+        assert!(line.contains(&(8, LAYER.to_string())));
+        assert!(line.contains(&(62, COLOR_BY_LAYER.to_string())));
+        assert_near(get_value(line, 10), 1.0);
+        assert_near(get_value(line, 20), -2.0);
+        assert_near(get_value(line, 11), 3.0);
+        assert_near(get_value(line, 21), -4.0);
+
+        assert_near(get_value(circle, 10), 5.0);
+        assert_near(get_value(circle, 20), -6.0);
+        assert_near(get_value(circle, 40), 2.0);
+    }
+
+    #[test]
+    fn arc_angles() {
+        // the quarter from the X axis to the Y axis of the document
+        let arc = Segment {
+            contour: Contour::circle(Point::new(0.0, 0.0), 1.0),
+            begin: Point::new(1.0, 0.0),
+            end: Point::new(0.0, 1.0),
+            big: false,
+        };
+        let curves = [plotted(curves::Curve::Segment(arc))];
+
+        let y_up = get_entities(&to_dxf(&curves, false));
+        assert_eq!(y_up[0], (0, "ARC".to_string()));
+        assert_near(get_value(&y_up, 40), 1.0);
+        assert_near(get_value(&y_up, 50), 0.0);
+        assert_near(get_value(&y_up, 51), 90.0);
+
+        // flipped, the arc goes clockwise from 0 to -90 degrees
+        let y_down = get_entities(&to_dxf(&curves, true));
+        assert_eq!(y_down[0], (0, "ARC".to_string()));
+        assert_near(get_value(&y_down, 50), 270.0);
+        assert_near(get_value(&y_down, 51), 0.0);
+    }
+}
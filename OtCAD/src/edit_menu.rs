@@ -1,4 +1,4 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
 
 use application::callback;
@@ -6,23 +6,334 @@ use application::callback_body;
 
 use application::font::*;
 use application::gui::gui_components::*;
+use application::gui::message_box::*;
+use application::gui::toast::*;
 use application::gui::*;
 use application::keys::*;
 
+use crate::command::*;
+use crate::command_history::*;
+use crate::config::*;
+use crate::coordinates_dialog::*;
+use crate::document::*;
 use crate::editor::*;
+use crate::export::get_curve_points;
 use crate::gui_helper::*;
+use crate::transform::*;
+use crate::ucs::*;
+
+/// Removing this many entities at once is asked first.
+const REMOVE_CONFIRM_COUNT: usize = 100;
+
+/// Chars of a copied text shown in the clipboard history.
+const CLIPBOARD_CAPTION_LENGTH: usize = 60;
+
+type Point = curves::points::Point<f64>;
+
+/// Quarter turns and the base point of the pasted elements, R and B change them while
+/// the elements follow the mouse.
+#[derive(Debug, Default, Clone, Copy)]
+struct Placement {
+    quarter_turns: u32,
+    base_index: usize,
+}
+
+/// Center of the extents of the elements, then the corners of the extents.
+fn get_base_points(elements: &[Element]) -> Vec<Point> {
+    let points: Vec<_> = elements
+        .iter()
+        .flat_map(|element| match element {
+            Element::Curve(curve) => get_curve_points(&curve.curve),
+            Element::Point(point) => vec![point.position],
+            Element::Ellipse(ellipse) => ellipse
+                .get_curves()
+                .iter()
+                .flat_map(|curve| get_curve_points(&curve.curve))
+                .collect(),
+            Element::Group(_) => Vec::new(),
+        })
+        .collect();
+    let Some(first) = points.first() else {
+        return vec![Point::new(0.0, 0.0)];
+    };
+    let (min, max) = points.iter().fold((*first, *first), |(min, max), p| {
+        (
+            Point::new(min.x.min(p.x), min.y.min(p.y)),
+            Point::new(max.x.max(p.x), max.y.max(p.y)),
+        )
+    });
+    vec![
+        (min + max).scale(0.5),
+        min,
+        Point::new(max.x, min.y),
+        max,
+        Point::new(min.x, max.y),
+    ]
+}
+
+fn copy_selected(editor: &Rc<RefCell<Editor>>, context: &Rc<RefCell<window::Context>>) -> bool {
+    let elements = editor.borrow().get_selected_elements();
+    if elements.is_empty() {
+        return false;
+    }
+    let mut clipboard = context.borrow().clipboard.clone();
+    clipboard.put_string(&elements_to_clipboard_text(&elements));
+    true
+}
+
+fn paste(editor: Rc<RefCell<Editor>>, context: Rc<RefCell<window::Context>>) {
+    let text = context.borrow().clipboard.get_string();
+    match text.as_deref().and_then(elements_from_clipboard_text) {
+        Some(elements) if !elements.is_empty() => paste_elements(editor, elements),
+        _ => {
+            let toast = Toast::warning("В буфере обмена нет элементов чертежа");
+            context.borrow_mut().gui_system.show_toast(toast);
+        }
+    }
+}
+
+/// The pasted elements follow the mouse until the click puts them into the drawing.
+fn paste_elements(editor: Rc<RefCell<Editor>>, elements: Vec<Element>) {
+    let bases = get_base_points(&elements);
+    let placement = Rc::new(Cell::new(Placement::default()));
+    let get_transform: Rc<dyn Fn(Point) -> Transform> = {
+        let placement = placement.clone();
+        let bases = bases.clone();
+        Rc::new(move |position| {
+            let placement = placement.get();
+            let base = bases[placement.base_index];
+            Transform {
+                shift: position - base,
+                ..Transform::rotation(
+                    base,
+                    placement.quarter_turns as f64 * std::f64::consts::FRAC_PI_2,
+                )
+            }
+        })
+    };
+    let geometry = elements
+        .iter()
+        .filter_map(|element| match element {
+            Element::Curve(curve) => Some(curve.curve),
+            _ => None,
+        })
+        .collect();
+    let pick = PointPick::new("Точка вставки")
+        .geometry(geometry)
+        .preview({
+            let get_transform = get_transform.clone();
+            move |position| {
+                (
+                    get_transform(position),
+                    "(R - повернуть, B - сменить базовую точку)".to_string(),
+                )
+            }
+        })
+        .key(Key::R, {
+            let placement = placement.clone();
+            move || {
+                let mut new_placement = placement.get();
+                new_placement.quarter_turns = (new_placement.quarter_turns + 1) % 4;
+                placement.set(new_placement);
+            }
+        })
+        .key(Key::B, move || {
+            let mut new_placement = placement.get();
+            new_placement.base_index = (new_placement.base_index + 1) % bases.len();
+            placement.set(new_placement);
+        });
+    editor.borrow().pick_point(
+        pick,
+        callback!([editor] (position) {
+            let transform = get_transform(position);
+            let placed: Vec<_> = elements
+                .iter()
+                .map(|element| element.transformed(&transform))
+                .collect();
+            editor.borrow().paste_elements(&placed);
+        }),
+    );
+}
+
+/// Text of the history item in one line, the copied elements are only counted.
+fn get_clipboard_item_caption(text: &str) -> String {
+    if let Some(elements) = elements_from_clipboard_text(text) {
+        return format!("Элементы чертежа: {}", elements.len());
+    }
+    let line = text.lines().next().unwrap_or_default();
+    let mut caption: String = line.chars().take(CLIPBOARD_CAPTION_LENGTH).collect();
+    if caption.len() < text.len() {
+        caption.push_str("...");
+    }
+    escape_accelerator(&caption)
+}
+
+/// Page with the texts and the elements copied in the program, the last one first. The
+/// elements are pasted into the drawing, a text is put into the clipboard again.
+fn open_clipboard_history_page(
+    font: &Font,
+    editor: Rc<RefCell<Editor>>,
+    context: Rc<RefCell<window::Context>>,
+) {
+    let clipboard = context.borrow().clipboard.clone();
+    let history = clipboard.get_history();
+    let font_height = font.get_size("8").1 as i32 + 2;
+    let line_size_constraints = SizeConstraints(
+        SizeConstraint::flexible(0),
+        SizeConstraint::fixed(font_height),
+    );
+    // the document tab is shown again when the page is closed
+    let document_tab_id = editor.borrow().get_selected_tab_id();
+    let tab_id = Rc::new(RefCell::new(None));
+
+    let mut page = Container::new(
+        SizeConstraints(SizeConstraint::flexible(0), SizeConstraint::flexible(0)),
+        ContainerLayout::Vertical,
+    );
+    if history.is_empty() {
+        page.add_child(TextBox::new(
+            line_size_constraints,
+            "Ничего не скопировано".to_string(),
+            font.clone(),
+        ));
+    }
+
+    let close_page = {
+        let tab_id = tab_id.clone();
+        callback!([editor] () {
+            if let Some(tab_id) = *tab_id.borrow() {
+                editor.borrow().close_tab(tab_id);
+            }
+            if let Some(document_tab_id) = document_tab_id {
+                editor.borrow().select_tab(document_tab_id);
+            }
+        })
+    };
+    let close_page = Rc::new(close_page);
+
+    for text in history {
+        create_section_caption(&mut page, &get_clipboard_item_caption(&text), font);
+        let line = page.add_child(Container::new(
+            line_size_constraints,
+            ContainerLayout::Horizontal,
+        ));
+        let close_page = close_page.clone();
+        let clipboard = clipboard.clone();
+        line.borrow_mut().add_child(
+            create_default_size_button("Вставить", font.clone()).callback(callback!(
+                [editor, context] () {
+                    close_page();
+                    match elements_from_clipboard_text(&text) {
+                        Some(elements) if !elements.is_empty() => {
+                            paste_elements(editor.clone(), elements)
+                        }
+                        _ => {
+                            clipboard.clone().put_string(&text);
+                            let toast = Toast::info("Текст помещён в буфер обмена");
+                            context.borrow_mut().gui_system.show_toast(toast);
+                        }
+                    }
+                }
+            )),
+        );
+    }
+
+    let buttons_line = page.add_child(Container::new(
+        line_size_constraints,
+        ContainerLayout::Horizontal,
+    ));
+    {
+        let close_page = close_page.clone();
+        let clipboard = clipboard.clone();
+        buttons_line.borrow_mut().add_child(
+            create_default_size_button("Очистить", font.clone()).callback(move || {
+                clipboard.clone().clear_history();
+                close_page();
+            }),
+        );
+    }
+    buttons_line.borrow_mut().add_child(
+        create_default_size_button("Закрыть", font.clone()).callback(move || close_page()),
+    );
+
+    page.add_child(EmptySpace::new_empty(SizeConstraints(
+        SizeConstraint::flexible(0),
+        SizeConstraint::flexible(0),
+    )));
+
+    *tab_id.borrow_mut() = editor.borrow().add_page_tab(font, "Буфер обмена", page);
+}
+
+/// Origin, then the directions of X and Y are picked.
+fn set_ucs_by_points(editor: Rc<RefCell<Editor>>, context: Rc<RefCell<window::Context>>) {
+    editor.borrow().pick_point(
+        PointPick::new("Начало координат"),
+        callback!([editor, context] (origin) {
+            editor.borrow().pick_point(
+                PointPick::new("Точка на оси X").from(origin).polar(),
+                callback!([editor, context] (x_point) {
+                    editor.borrow().pick_point(
+                        PointPick::new("Точка со стороны оси Y").from(origin),
+                        callback!([editor, context] (y_point) {
+                            match Ucs::from_points(origin, x_point, y_point) {
+                                Some(ucs) => editor.borrow().set_ucs(ucs),
+                                None => show_message(
+                                    context,
+                                    "Точки лежат на одной прямой",
+                                    "ПСК",
+                                ),
+                            }
+                        }),
+                    );
+                }),
+            );
+        }),
+    );
+}
+
+/// The view is turned by the typed angle counted from the view without the rotation.
+fn open_view_rotation_dialog(
+    font: &Font,
+    editor: Rc<RefCell<Editor>>,
+    context: Rc<RefCell<window::Context>>,
+) {
+    let number_format = editor.borrow().config.borrow().number_format;
+    let angle_format = editor.borrow().config.borrow().angle_format;
+    let angle = editor.borrow().get_view_rotation();
+    let weak_editor = Rc::downgrade(&editor);
+    let on_ok = move |text: &str| match angle_format.parse_rotation(text, &number_format) {
+        Some(angle) => {
+            if let Some(editor) = weak_editor.upgrade() {
+                editor.borrow().set_view_rotation(angle);
+            }
+            Ok(())
+        }
+        None => Err(format!("{} - не число!", text)),
+    };
+    open_input_dialog(
+        font,
+        editor,
+        context,
+        "Поворот вида",
+        (
+            "Угол:",
+            &angle_format.format_rotation(angle, &number_format),
+        ),
+        on_ok,
+        None,
+    );
+}
 
 pub fn create_edit_menu(
     parent: &mut TabControl,
     font: &Font,
     editor: Rc<RefCell<Editor>>,
+    context: Rc<RefCell<window::Context>>,
 ) -> Rc<RefCell<Container>> {
-    let menu_caption = "Правка";
+    let menu_caption = "&Правка";
     let file_menu = parent.add_tab(
         menu_caption.to_string(),
-        GuiSystem::default_size(&menu_caption, None, &font)
-            .0
-            .absolute,
+        Button::default_size(&menu_caption, None, &font).0.absolute,
         Container::new(
             SizeConstraints(SizeConstraint::fixed(0), SizeConstraint::fixed(0)),
             ContainerLayout::Horizontal,
@@ -41,39 +352,305 @@ pub fn create_edit_menu(
             Hotkey::new(Key::Delete),
             true,
         )
-        .callback(callback!([editor](){
-            editor.borrow_mut().remove_selected()
+        .callback(callback!([editor, context](){
+            let count = editor.borrow().get_selected_count();
+            if count < REMOVE_CONFIRM_COUNT {
+                editor.borrow().run_command(Command::Remove);
+                return;
+            }
+            let text = format!("Удалить {} объектов?", count);
+            let message_box = MessageBox::yes_no("Удаление", &text);
+            let remove = callback!([editor] (index) {
+                if index == MessageBox::YES {
+                    editor.borrow().run_command(Command::Remove);
+                }
+            });
+            confirm(CONFIRM_REMOVE_MANY, message_box, &editor, &context, remove);
         })),
     );
 
-    let _cut_button = default_panel
-        .borrow_mut()
-        .add_child(create_default_size_button_with_hotkey(
+    let _cut_button = default_panel.borrow_mut().add_child(
+        create_default_size_button_with_hotkey(
             "Вырезать",
             font.clone(),
             Hotkey::ctrl(Key::X),
             true,
-        ));
+        )
+        .callback(callback!([editor, context](){
+            if copy_selected(&editor, &context) {
+                editor.borrow().remove_selected();
+            }
+        })),
+    );
+
+    let _copy_button = default_panel.borrow_mut().add_child(
+        create_default_size_button_with_hotkey(
+            "Копировать",
+            font.clone(),
+            Hotkey::ctrl(Key::C),
+            true,
+        )
+        .callback(callback!([editor, context](){
+            copy_selected(&editor, &context);
+        })),
+    );
+
+    let _paste_button = default_panel.borrow_mut().add_child(
+        create_default_size_button_with_hotkey(
+            "Вставить",
+            font.clone(),
+            Hotkey::ctrl(Key::V),
+            true,
+        )
+        .callback(callback!([editor, context](){
+            paste(editor, context);
+        })),
+    );
+
+    {
+        let font = font.clone();
+        let _clipboard_history_button = default_panel.borrow_mut().add_child(
+            create_default_size_button("Буфер обмена...", font.clone()).callback(callback!(
+                [editor, context]() {
+                    open_clipboard_history_page(&font, editor, context);
+                }
+            )),
+        );
+    }
+
+    let precision_panel = file_menu.borrow_mut().add_child(Container::new(
+        SizeConstraints(SizeConstraint::fixed(0), SizeConstraint::fixed(0)),
+        ContainerLayout::Vertical,
+    ));
+
+    {
+        let font = font.clone();
+        let _coordinates_button = precision_panel.borrow_mut().add_child(
+            create_default_size_button("Координаты...", font.clone()).callback(callback!(
+                [editor, context]() {
+                    open_coordinates_dialog(&font, editor, context);
+                }
+            )),
+        );
+    }
+
+    {
+        let font = font.clone();
+        let _width_button = precision_panel.borrow_mut().add_child(
+            create_default_size_button("Толщина линий...", font.clone()).callback(callback!(
+                [editor, context]() {
+                    open_width_dialog(&font, editor, context);
+                }
+            )),
+        );
+    }
+
+    {
+        let font = font.clone();
+        let _opacity_button = precision_panel.borrow_mut().add_child(
+            create_default_size_button("Непрозрачность...", font.clone()).callback(callback!(
+                [editor, context]() {
+                    open_opacity_dialog(&font, editor, context);
+                }
+            )),
+        );
+    }
+
+    let _select_chain_button = precision_panel.borrow_mut().add_child(
+        create_default_size_button("Выделить цепочку", font.clone()).callback(callback!(
+            [editor, context]() {
+                let gaps = editor.borrow().select_chain();
+                if !gaps.is_empty() {
+                    let max_gap = gaps.iter().map(|gap| gap.distance).fold(0.0, f64::max);
+                    let text = format!(
+                        "Цепочка содержит разрывы: {}, наибольший {}",
+                        gaps.len(),
+                        editor.borrow().config.borrow().number_format.format(max_gap),
+                    );
+                    context.borrow_mut().gui_system.show_toast(Toast::warning(&text));
+                }
+            }
+        )),
+    );
+
+    let _distance_button = precision_panel.borrow_mut().add_child(
+        create_default_size_button("Расстояние", font.clone()).callback(callback!(
+            [editor, context]() {
+                let distance = match editor.borrow().show_closest_distance() {
+                    Some(distance) => distance,
+                    None => return show_message(context, "Выделите две кривые", "Расстояние"),
+                };
+                let text = editor.borrow().config.borrow().number_format.format(distance);
+                // clicking the toast copies the value
+                let clipboard = context.borrow().clipboard.clone();
+                let toast = Toast::info(&format!(
+                    "Расстояние {}. Нажмите, чтобы скопировать",
+                    text
+                ))
+                .action(move || clipboard.clone().put_string(&text));
+                context.borrow_mut().gui_system.show_toast(toast);
+            }
+        )),
+    );
+
+    let ucs_panel = file_menu.borrow_mut().add_child(Container::new(
+        SizeConstraints(SizeConstraint::fixed(0), SizeConstraint::fixed(0)),
+        ContainerLayout::Vertical,
+    ));
+
+    let _ucs_by_entity_button = ucs_panel.borrow_mut().add_child(
+        create_default_size_button("ПСК по объекту", font.clone()).callback(callback!(
+            [editor, context]() {
+                let ucs = editor.borrow().get_ucs_of_selected();
+                match ucs {
+                    Some(ucs) => editor.borrow().set_ucs(ucs),
+                    None => show_message(
+                        context,
+                        "Выделите один отрезок, дугу или окружность",
+                        "ПСК",
+                    ),
+                }
+            }
+        )),
+    );
+
+    let _ucs_by_points_button = ucs_panel.borrow_mut().add_child(
+        create_default_size_button("ПСК по 3 точкам", font.clone()).callback(callback!(
+            [editor, context]() {
+                set_ucs_by_points(editor, context);
+            }
+        )),
+    );
+
+    let _world_ucs_button = ucs_panel.borrow_mut().add_child(
+        create_default_size_button("Мировая СК", font.clone()).callback(callback!(
+            [editor]() {
+                editor.borrow().set_ucs(Ucs::default());
+            }
+        )),
+    );
+
+    {
+        let font = font.clone();
+        let _view_rotation_button = ucs_panel.borrow_mut().add_child(
+            create_default_size_button("Поворот вида...", font.clone()).callback(callback!(
+                [editor, context]() {
+                    open_view_rotation_dialog(&font, editor, context);
+                }
+            )),
+        );
+    }
+
+    let _reset_view_rotation_button = ucs_panel.borrow_mut().add_child(
+        create_default_size_button("Вид без поворота", font.clone()).callback(callback!(
+            [editor]() {
+                editor.borrow().set_view_rotation(0.0);
+            }
+        )),
+    );
+
+    let _flip_y_button = ucs_panel.borrow_mut().add_child(
+        create_default_size_button("Перевернуть ось Y", font.clone()).callback(callback!(
+            [editor]() {
+                editor.borrow().flip_y_axis();
+            }
+        )),
+    );
+
+    let visibility_panel = file_menu.borrow_mut().add_child(Container::new(
+        SizeConstraints(SizeConstraint::fixed(0), SizeConstraint::fixed(0)),
+        ContainerLayout::Vertical,
+    ));
+
+    let _hide_button = visibility_panel.borrow_mut().add_child(
+        create_default_size_button("Скрыть", font.clone()).callback(callback!([editor](){
+            editor.borrow().run_command(Command::Hide)
+        })),
+    );
+
+    let _isolate_button = visibility_panel.borrow_mut().add_child(
+        create_default_size_button("Изолировать", font.clone()).callback(callback!([editor](){
+            editor.borrow().run_command(Command::Isolate)
+        })),
+    );
+
+    let _unhide_button = visibility_panel.borrow_mut().add_child(
+        create_default_size_button("Показать все", font.clone()).callback(callback!([editor](){
+            editor.borrow().unhide_all()
+        })),
+    );
+
+    let lock_panel = file_menu.borrow_mut().add_child(Container::new(
+        SizeConstraints(SizeConstraint::fixed(0), SizeConstraint::fixed(0)),
+        ContainerLayout::Vertical,
+    ));
+
+    let _lock_button = lock_panel.borrow_mut().add_child(
+        create_default_size_button("Заблокировать", font.clone()).callback(callback!([editor](){
+            editor.borrow().run_command(Command::Lock)
+        })),
+    );
+
+    let _unlock_button = lock_panel.borrow_mut().add_child(
+        create_default_size_button("Разблокировать все", font.clone()).callback(
+            callback!([editor](){
+                editor.borrow().unlock_all()
+            }),
+        ),
+    );
+
+    let order_panel = file_menu.borrow_mut().add_child(Container::new(
+        SizeConstraints(SizeConstraint::fixed(0), SizeConstraint::fixed(0)),
+        ContainerLayout::Vertical,
+    ));
+
+    let _front_button = order_panel.borrow_mut().add_child(
+        create_default_size_button_with_hotkey(
+            "На передний план",
+            font.clone(),
+            Hotkey::ctrl_shift(Key::PageUp),
+            true,
+        )
+        .callback(callback!([editor](){
+            editor.borrow().run_command(Command::BringToFront)
+        })),
+    );
+
+    let _back_button = order_panel.borrow_mut().add_child(
+        create_default_size_button_with_hotkey(
+            "На задний план",
+            font.clone(),
+            Hotkey::ctrl_shift(Key::PageDown),
+            true,
+        )
+        .callback(callback!([editor](){
+            editor.borrow().run_command(Command::SendToBack)
+        })),
+    );
+
+    let command_panel = file_menu.borrow_mut().add_child(Container::new(
+        SizeConstraints(SizeConstraint::fixed(0), SizeConstraint::fixed(0)),
+        ContainerLayout::Vertical,
+    ));
+
+    let _repeat_button = command_panel.borrow_mut().add_child(
+        create_default_size_button("Повторить команду", font.clone()).callback(callback!(
+            [editor]() {
+                editor.borrow().repeat_last_command()
+            }
+        )),
+    );
 
-    let _copy_button =
-        default_panel
-            .borrow_mut()
-            .add_child(create_default_size_button_with_hotkey(
-                "Копировать",
-                font.clone(),
-                Hotkey::ctrl(Key::C),
-                true,
-            ));
-
-    let _paste_button =
-        default_panel
-            .borrow_mut()
-            .add_child(create_default_size_button_with_hotkey(
-                "Вставить",
-                font.clone(),
-                Hotkey::ctrl(Key::V),
-                true,
-            ));
+    {
+        let font = font.clone();
+        let _history_button = command_panel.borrow_mut().add_child(
+            create_default_size_button("История команд...", font.clone()).callback(callback!(
+                [editor]() {
+                    open_command_history_page(&font, editor);
+                }
+            )),
+        );
+    }
 
     let time_machine_panel = file_menu.borrow_mut().add_child(Container::new(
         SizeConstraints(SizeConstraint::flexible(0), SizeConstraint::fixed(0)),
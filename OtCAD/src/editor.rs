@@ -1,35 +1,62 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
+use std::time::{Duration, Instant, SystemTime};
 
 use application::callback;
 use application::callback_body;
+use application::file_watcher::*;
 use application::font::*;
+use application::gui::animation::*;
+use application::gui::binding::*;
 use application::gui::gui_components::*;
+use application::gui::tooltip::*;
 use application::gui::*;
 use application::image::*;
 use application::keys::*;
+use application::property::*;
 
+use crate::chain::*;
+use crate::command::*;
+use crate::compare::*;
 use crate::config::*;
 use crate::document::*;
+use crate::export::{get_arc_middle, get_curve_points};
+use crate::gui_helper::*;
 use crate::picts::*;
+use crate::smart_guides::*;
+use crate::transform::*;
+use crate::transform_gizmo::*;
+use crate::transform_menu::{rotation_command, scaling_command};
+use crate::ucs::*;
 use curves::points::*;
 use curves::render::*;
 use curves::*;
 
 use rand::*;
 
+#[derive(Copy, Clone)]
 pub struct CadColorTheme {
     line_color: u32,
     line_aa_color: u32,
     highlight_line_color: u32,
     highlight_line_aa_color: u32,
+    selected_line_color: u32,
+    selection_halo_color: u32,
     grid_color_base: u32,
     grid_color_sub: u32,
     grid_font: u32,
     selection_rect_color: u32,
     selection_bevel_color: u32,
     pic_color: u32,
+    sheet_color: u32,
+    added_color: u32,
+    removed_color: u32,
+    modified_color: u32,
+    /// Selection and hover are shown by the line shape too, so the hue alone isn't needed
+    /// to tell them.
+    state_by_shape: bool,
 }
 
 static CAD_DARK_THEME: CadColorTheme = CadColorTheme {
@@ -37,12 +64,19 @@ static CAD_DARK_THEME: CadColorTheme = CadColorTheme {
     line_aa_color: 0xCCFFCC,
     highlight_line_color: 0xAA8800,
     highlight_line_aa_color: 0xFFCC00,
+    selected_line_color: 0x66CCFF,
+    selection_halo_color: 0x334455,
     grid_color_base: 0x444444,
     grid_color_sub: 0x282828,
     grid_font: 0x808080,
     selection_rect_color: 0x3F2F00,
     selection_bevel_color: 0xBF8F00,
     pic_color: 0xBF8F00,
+    sheet_color: 0x777799,
+    added_color: 0x44CC44,
+    removed_color: 0xDD4444,
+    modified_color: 0x4499FF,
+    state_by_shape: false,
 };
 
 static CAD_BEIGE_THEME: CadColorTheme = CadColorTheme {
@@ -50,12 +84,19 @@ static CAD_BEIGE_THEME: CadColorTheme = CadColorTheme {
     line_aa_color: 0x002244,
     highlight_line_color: 0x0088FF,
     highlight_line_aa_color: 0x0088FF,
+    selected_line_color: 0xAA3300,
+    selection_halo_color: 0xEEAA66,
     grid_color_base: 0xA09070,
     grid_color_sub: 0xC0B090,
     grid_font: 0x908060,
     selection_rect_color: 0x001F3F,
     selection_bevel_color: 0x007FFF,
     pic_color: 0x007FFF,
+    sheet_color: 0x806040,
+    added_color: 0x007700,
+    removed_color: 0xAA0000,
+    modified_color: 0x7700AA,
+    state_by_shape: false,
 };
 
 static CAD_LIGHT_THEME: CadColorTheme = CadColorTheme {
@@ -63,12 +104,60 @@ static CAD_LIGHT_THEME: CadColorTheme = CadColorTheme {
     line_aa_color: 0x000000,
     highlight_line_color: 0x338800,
     highlight_line_aa_color: 0x338800,
+    selected_line_color: 0x0055DD,
+    selection_halo_color: 0x99CCFF,
     grid_color_base: 0xBBBBBB,
     grid_color_sub: 0xDDDDDD,
     grid_font: 0xAAAAAA,
     selection_rect_color: 0x1F2F0F,
     selection_bevel_color: 0x3F5F00,
     pic_color: 0x3F8000,
+    sheet_color: 0x666666,
+    added_color: 0x008844,
+    removed_color: 0xCC0000,
+    modified_color: 0x0055DD,
+    state_by_shape: false,
+};
+
+/// Okabe-Ito colors, they differ for the deuteranopia and the protanopia.
+static CAD_COLOR_BLIND_THEME: CadColorTheme = CadColorTheme {
+    line_color: 0x000000,
+    line_aa_color: 0x000000,
+    highlight_line_color: 0xE69F00,
+    highlight_line_aa_color: 0xE69F00,
+    selected_line_color: 0x0072B2,
+    selection_halo_color: 0x9ED0EE,
+    grid_color_base: 0xBBBBBB,
+    grid_color_sub: 0xDDDDDD,
+    grid_font: 0x999999,
+    selection_rect_color: 0x002A40,
+    selection_bevel_color: 0x0072B2,
+    pic_color: 0xD55E00,
+    sheet_color: 0x666666,
+    added_color: 0x009E73,
+    removed_color: 0xD55E00,
+    modified_color: 0xCC79A7,
+    state_by_shape: true,
+};
+
+static CAD_HIGH_CONTRAST_THEME: CadColorTheme = CadColorTheme {
+    line_color: 0xFFFFFF,
+    line_aa_color: 0xFFFFFF,
+    highlight_line_color: 0xFFFF00,
+    highlight_line_aa_color: 0xFFFF00,
+    selected_line_color: 0x00FFFF,
+    selection_halo_color: 0x0000CC,
+    grid_color_base: 0x808080,
+    grid_color_sub: 0x404040,
+    grid_font: 0xFFFFFF,
+    selection_rect_color: 0x404000,
+    selection_bevel_color: 0xFFFF00,
+    pic_color: 0xFFFF00,
+    sheet_color: 0xFFFFFF,
+    added_color: 0x00FF00,
+    removed_color: 0xFF4040,
+    modified_color: 0xFF00FF,
+    state_by_shape: true,
 };
 
 static BEIGE_THEME: GuiColorTheme = GuiColorTheme {
@@ -80,13 +169,53 @@ static BEIGE_THEME: GuiColorTheme = GuiColorTheme {
     selected: 0x4499CC,
     inactive: 0xAA9988,
     edit_focused: 0xEEEEEE,
+    error: 0xAA2200,
 };
 
+static COLOR_BLIND_THEME: GuiColorTheme = GuiColorTheme {
+    background: 0xFFFFFF,
+    font: 0x000000,
+    splitter: 0x0072B2,
+    highlight: 0xF0C060,
+    pressed: 0x222222,
+    selected: 0x56B4E9,
+    inactive: 0xBBBBBB,
+    edit_focused: 0xEEEEEE,
+    error: 0xD55E00,
+};
+
+static HIGH_CONTRAST_THEME: GuiColorTheme = GuiColorTheme {
+    background: 0x000000,
+    font: 0xFFFFFF,
+    splitter: 0xFFFFFF,
+    highlight: 0x808000,
+    pressed: 0xFFFFFF,
+    selected: 0x008080,
+    inactive: 0x404040,
+    edit_focused: 0x202020,
+    error: 0xFF4040,
+};
+
+impl CadColorTheme {
+    /// Colors of the document take precedence over the theme.
+    fn with_style(&self, style: &CanvasStyle) -> Self {
+        Self {
+            line_color: style.entity_color.unwrap_or(self.line_color),
+            line_aa_color: style.entity_color.unwrap_or(self.line_aa_color),
+            grid_color_base: style.grid_color_base.unwrap_or(self.grid_color_base),
+            grid_color_sub: style.grid_color_sub.unwrap_or(self.grid_color_sub),
+            ..*self
+        }
+    }
+}
+
 pub fn get_cad_color_theme(config: &Config) -> &'static CadColorTheme {
     match config.color_theme {
         ColorTheme::Dark => &CAD_DARK_THEME,
         ColorTheme::Beige => &CAD_BEIGE_THEME,
         ColorTheme::Light => &CAD_LIGHT_THEME,
+        ColorTheme::ColorBlind => &CAD_COLOR_BLIND_THEME,
+        ColorTheme::HighContrast => &CAD_HIGH_CONTRAST_THEME,
     }
 }
 
@@ -95,35 +224,258 @@ pub fn get_gui_color_theme(config: &Config) -> &'static GuiColorTheme {
         ColorTheme::Dark => &DARK_THEME,
         ColorTheme::Beige => &BEIGE_THEME,
         ColorTheme::Light => &LIGHT_THEME,
+        ColorTheme::ColorBlind => &COLOR_BLIND_THEME,
+        ColorTheme::HighContrast => &HIGH_CONTRAST_THEME,
+    }
+}
+
+pub const THUMBNAIL_SIZE: ImageSize = (160, 120);
+
+/// Small picture of the whole drawing, it is rendered from the document without the
+/// view, so it doesn't depend on the zoom and the grid.
+pub fn render_thumbnail(document: &Document, config: &Config, size: ImageSize) -> Image<u32> {
+    let mut image = Image::new(size);
+    let canvas_style = document.get_canvas_style();
+    let cad_color_theme = get_cad_color_theme(config).with_style(canvas_style);
+    let background = canvas_style
+        .background
+        .unwrap_or(get_gui_color_theme(config).background);
+    let mut buf = image.as_view_mut();
+    buf.fill(|p| *p = background);
+
+    // the drawing is mirrored like in the views if its Y axis goes up
+    let curves: Vec<_> = document
+        .get_output_curves()
+        .into_iter()
+        .map(|curve| {
+            if document.is_y_up() {
+                curve.flip_y()
+            } else {
+                curve
+            }
+        })
+        .collect();
+    let points: Vec<_> = curves.iter().flat_map(get_curve_points).collect();
+    if points.is_empty() {
+        return image;
+    }
+    let (min, max) = points.iter().fold((points[0], points[0]), |(min, max), p| {
+        (
+            Point::new(min.x.min(p.x), min.y.min(p.y)),
+            Point::new(max.x.max(p.x), max.y.max(p.y)),
+        )
+    });
+    let span_count = size.1 * 2;
+    let size = Point::new(size.0 as f64, size.1 as f64);
+    let margin = 4.0;
+    let extents = max - min;
+    let scale = f64::min(
+        (size.x - margin * 2.0) / extents.x.max(1.0e-9),
+        (size.y - margin * 2.0) / extents.y.max(1.0e-9),
+    );
+    let center = (min + max).scale(0.5);
+    let mut span_buffer = vec![(0, 0); span_count];
+    for curve in &curves {
+        let l = curve
+            .translate(center.neg())
+            .scale(scale)
+            .translate(size.scale(0.5));
+        draw_locc(
+            &mut buf,
+            &l,
+            cad_color_theme.line_aa_color,
+            1.0,
+            &mut span_buffer,
+            2,
+        );
     }
+    image
 }
 
 struct EditorInternal {
     pub selected_document_id: usize,
     pub documents: HashMap<usize, Rc<RefCell<Document>>>,
     pub tab_id_to_document_id: HashMap<usize, usize>,
+    pub settings_tab_id: Option<usize>,
+    pub file_watcher: Option<FileWatcher>,
+    pub thumbnails: HashMap<PathBuf, Rc<Image<u32>>>,
+}
+
+/// Recent files kept in the config.
+const MAX_RECENT_FILES: usize = 10;
+
+/// Priorities of the Escape handlers, the tool is stopped before the selection is dropped.
+pub const ESCAPE_PRIORITY_TOOL: i32 = 2;
+pub const ESCAPE_PRIORITY_SELECTION: i32 = 1;
+
+/// Tab caption of the document, the name of its file if it has one.
+fn get_document_caption(document_id: usize, document: &Document) -> String {
+    document
+        .get_path()
+        .and_then(|path| path.file_name())
+        .map_or(format!("Новый чертёж {}", document_id), |name| {
+            name.to_string_lossy().to_string()
+        })
 }
+
+type ConfigListener = Rc<dyn Fn(&Config)>;
+
 pub struct Editor {
     pub last_document_id: usize,
     pub config: Rc<RefCell<Config>>,
     pub tab_control: Option<Rc<RefCell<TabControl>>>,
+    pub top_panel: Option<Rc<RefCell<TabControl>>>,
     pub picts: Rc<RefCell<Picts>>,
+    /// Mirrors `Config::show_grid`, the drawings and the check box are bound to it.
+    pub show_grid: Property<bool>,
+    /// Mirrors `Config::snap_enabled`.
+    pub snap_enabled: Property<bool>,
+    /// Mirrors `Config::ortho`.
+    pub ortho: Property<bool>,
+    /// Document coordinates under the mouse in the last hovered drawing.
+    pub cursor_position: Property<Option<Point<f64>>>,
+    /// The same position in the UCS of that drawing.
+    pub cursor_ucs_position: Property<Option<Point<f64>>>,
+    /// Memory of the active document, none without documents.
+    pub memory_usage: Property<Option<MemoryUsage>>,
+    /// Id and change number of the document the memory was counted for.
+    memory_usage_source: Cell<Option<(usize, usize)>>,
+    rect_picker: Rc<RefCell<RectPicker>>,
+    point_picker: Rc<RefCell<PointPicker>>,
+    command_history: Rc<RefCell<CommandHistory>>,
+    /// Size in pixels of the last drawn drawing, the zoom commands fit the view to it.
+    view_size: Rc<Cell<Point<f64>>>,
     internal: Rc<RefCell<EditorInternal>>,
+    config_listeners: Rc<RefCell<Vec<ConfigListener>>>,
+    /// Modification time of the config file after its last save or load, so our own
+    /// saves are not taken for changes made outside.
+    config_modified_time: Cell<Option<SystemTime>>,
 }
 
 impl Editor {
     pub fn new(config: Config) -> Self {
+        let show_grid = Property::new(config.show_grid);
+        let snap_enabled = Property::new(config.snap_enabled.0);
+        let ortho = Property::new(config.ortho);
+        let config = Rc::new(RefCell::new(config));
+        show_grid.subscribe(callback!([config] (show_grid) {
+            config.borrow_mut().show_grid = *show_grid;
+        }));
+        snap_enabled.subscribe(callback!([config] (snap_enabled) {
+            config.borrow_mut().snap_enabled = SnapEnabled(*snap_enabled);
+        }));
+        ortho.subscribe(callback!([config] (ortho) {
+            config.borrow_mut().ortho = *ortho;
+        }));
         Self {
             last_document_id: 0,
-            config: Rc::new(RefCell::new(config)),
+            config,
             tab_control: None,
+            top_panel: None,
             picts: Rc::new(RefCell::new(Picts::new())),
+            show_grid,
+            snap_enabled,
+            ortho,
+            cursor_position: Property::new(None),
+            cursor_ucs_position: Property::new(None),
+            memory_usage: Property::new(None),
+            memory_usage_source: Cell::new(None),
+            rect_picker: Rc::default(),
+            point_picker: Rc::default(),
+            command_history: Rc::default(),
+            view_size: Rc::default(),
             internal: Rc::new(RefCell::new(EditorInternal {
                 selected_document_id: 1,
                 documents: HashMap::new(),
                 tab_id_to_document_id: HashMap::new(),
+                settings_tab_id: None,
+                file_watcher: None,
+                thumbnails: HashMap::new(),
             })),
+            config_listeners: Rc::new(RefCell::new(Vec::new())),
+            config_modified_time: Cell::new(get_config_modified_time()),
+        }
+    }
+
+    /// Listener gets the old config, the new one is already in `Editor::config`.
+    pub fn add_config_listener(&self, listener: impl Fn(&Config) + 'static) {
+        self.config_listeners.borrow_mut().push(Rc::new(listener));
+    }
+
+    pub fn clear_config_listeners(&self) {
+        self.config_listeners.borrow_mut().clear();
+    }
+
+    /// Replaces the config, saves it to disk and broadcasts the change.
+    pub fn set_config(&self, config: Config) {
+        let old_config = std::mem::replace(&mut *self.config.borrow_mut(), config);
+        if old_config == *self.config.borrow() {
+            return;
+        }
+
+        save_config(&self.config.borrow());
+        self.config_modified_time.set(get_config_modified_time());
+        let show_grid = self.config.borrow().show_grid;
+        self.show_grid.set(show_grid);
+        let snap_enabled = self.config.borrow().snap_enabled.0;
+        self.snap_enabled.set(snap_enabled);
+        let ortho = self.config.borrow().ortho;
+        self.ortho.set(ortho);
+        let limits = self.config.borrow().document_limits;
+        for document in self.internal.borrow().documents.values() {
+            document.borrow_mut().set_limits(limits);
         }
+        let listeners = self.config_listeners.borrow().clone();
+        for listener in listeners {
+            listener(&old_config);
+        }
+    }
+
+    /// True if the config file was changed since the last check, by another instance or
+    /// by hand.
+    pub fn is_config_file_changed(&self) -> bool {
+        let modified_time = get_config_modified_time();
+        self.config_modified_time.replace(modified_time) != modified_time
+    }
+
+    /// The next rectangle dragged in a drawing goes to the callback, in document
+    /// coordinates.
+    pub fn pick_rect(&self, callback: impl Fn(Point<f64>, Point<f64>) + 'static) {
+        self.point_picker.borrow_mut().cancel();
+        let mut rect_picker = self.rect_picker.borrow_mut();
+        rect_picker.cancel();
+        rect_picker.callback = Some(Rc::new(callback));
+    }
+
+    /// The next click in a drawing gives the snapped point to the callback, in document
+    /// coordinates.
+    pub fn pick_point(&self, pick: PointPick, callback: impl Fn(Point<f64>) + 'static) {
+        self.rect_picker.borrow_mut().cancel();
+        let mut point_picker = self.point_picker.borrow_mut();
+        point_picker.cancel();
+        point_picker.callback = Some(Rc::new(callback));
+        point_picker.pick = pick;
+    }
+
+    fn bind_cad_view(&self, cad_view: &Rc<RefCell<CadView>>) {
+        cad_view.borrow_mut().cursor_position = self.cursor_position.clone();
+        cad_view.borrow_mut().cursor_ucs_position = self.cursor_ucs_position.clone();
+        cad_view.borrow_mut().rect_picker = self.rect_picker.clone();
+        cad_view.borrow_mut().point_picker = self.point_picker.clone();
+        cad_view.borrow_mut().command_history = self.command_history.clone();
+        cad_view.borrow_mut().view_size = self.view_size.clone();
+        redraw_on_change(cad_view, &self.show_grid);
+        let weak_view = Rc::downgrade(cad_view);
+        let document = cad_view.borrow().document.clone();
+        let subscription = document.borrow().get_changes().subscribe(move |change| {
+            if change.event == DocumentEvent::HistoryChanged {
+                return;
+            }
+            if let Some(cad_view) = weak_view.upgrade() {
+                redraw_if_idle(&cad_view);
+            }
+        });
+        cad_view.borrow_mut().changes_subscription = Some(subscription);
     }
 
     pub fn add_tab_by_existing_document(
@@ -131,6 +483,22 @@ impl Editor {
         font: Font,
         document_id: usize,
         force_selected_document_id: Option<usize>,
+    ) {
+        self.add_document_tab(font, document_id, force_selected_document_id, false);
+    }
+
+    /// One more tab with the active document, it starts with its own pan and zoom.
+    pub fn add_view_of_active_document(&self, font: Font) {
+        let document_id = self.internal.borrow().selected_document_id;
+        self.add_document_tab(font, document_id, None, true);
+    }
+
+    fn add_document_tab(
+        &self,
+        font: Font,
+        document_id: usize,
+        force_selected_document_id: Option<usize>,
+        extra_view: bool,
     ) {
         let document =
             if let Some(document) = self.internal.borrow_mut().documents.get(&document_id) {
@@ -143,7 +511,7 @@ impl Editor {
         } else {
             return;
         };
-        let new_file_caption = format!("Новый чертёж {}", document_id);
+        let new_file_caption = get_document_caption(document_id, &document.borrow());
 
         let font_height = font.get_size("8").1 as i32 + 2;
         let mut tab_content = Container::new(
@@ -151,11 +519,15 @@ impl Editor {
             ContainerLayout::Vertical,
         );
 
-        tab_content.add_child(TextBox::new(
+        let header = tab_content.add_child(Container::new(
             SizeConstraints(
                 SizeConstraint::flexible(0),
                 SizeConstraint::fixed(font_height),
             ),
+            ContainerLayout::Horizontal,
+        ));
+        header.borrow_mut().add_child(TextBox::new(
+            SizeConstraints(SizeConstraint::flexible(0), SizeConstraint::flexible(0)),
             new_file_caption.clone(),
             font.clone(),
         ));
@@ -165,16 +537,44 @@ impl Editor {
             SizeConstraint::fixed(1),
         )));
 
-        tab_content.add_child(CadView::new(
+        let cad_view = tab_content.add_child(CadView::new(
             SizeConstraints(SizeConstraint::flexible(200), SizeConstraint::flexible(200)),
             document.clone(),
             self.picts.clone(),
             self.config.clone(),
             font.clone(),
         ));
+        self.bind_cad_view(&cad_view);
+
+        if extra_view {
+            cad_view.borrow_mut().set_synchronized(false);
+            header.borrow_mut().add_child(
+                create_default_size_check_button("Синхронизировать вид", font.clone())
+                    .check_box(false)
+                    .checkbox_callback(callback!([cad_view] (c) {
+                        cad_view.borrow_mut().set_synchronized(c);
+                        redraw_if_idle(&cad_view);
+                    })),
+            );
+        }
 
-        let (_cad_tab, tab_id) = tab_control.borrow_mut().add_tab_with_id(
-            new_file_caption.clone(),
+        // document tabs are kept in the order of documents, before the other pages
+        let tab_index = {
+            let internal = self.internal.borrow();
+            let tab_ids = tab_control.borrow().get_tab_ids();
+            tab_ids
+                .iter()
+                .position(|tab_id| {
+                    internal
+                        .tab_id_to_document_id
+                        .get(tab_id)
+                        .map_or(true, |id| *id > document_id)
+                })
+                .unwrap_or(tab_ids.len())
+        };
+        let (_cad_tab, tab_id) = tab_control.borrow_mut().insert_tab_with_id(
+            tab_index,
+            escape_accelerator(&new_file_caption),
             GuiSystem::default_size(&new_file_caption, None, &font)
                 .0
                 .absolute,
@@ -185,6 +585,9 @@ impl Editor {
             .borrow_mut()
             .tab_id_to_document_id
             .insert(tab_id, document_id);
+        tab_control
+            .borrow()
+            .set_tab_tooltip(tab_id, Some(self.get_document_tooltip(&document)));
 
         if force_selected_document_id
             .map(|f| f == document_id)
@@ -194,6 +597,157 @@ impl Editor {
         }
     }
 
+    /// Tab with the compare document and the navigable list of differences.
+    pub fn add_compare_tab(
+        &mut self,
+        font: &Font,
+        mut document: Document,
+        differences: Vec<Difference>,
+        difference_kinds: Rc<HashMap<usize, DifferenceKind>>,
+    ) {
+        let tab_control = if let Some(tab_control) = &self.tab_control {
+            tab_control.clone()
+        } else {
+            return;
+        };
+        let document_id = self.get_next_id();
+        document.set_limits(self.config.borrow().document_limits);
+        let document = Rc::new(RefCell::new(document));
+        let caption = format!("Сравнение {}", document_id);
+
+        let font_height = font.get_size("8").1 as i32 + 2;
+        let side_width = font.get_size("Следующее").0 as i32 * 2;
+        let mut tab_content = Container::new(
+            SizeConstraints(SizeConstraint::flexible(0), SizeConstraint::flexible(0)),
+            ContainerLayout::Vertical,
+        );
+
+        let cad_color_theme = get_cad_color_theme(&self.config.borrow());
+        let mut caption_runs = vec![TextRun::new(&caption).bold()];
+        for (kind, color) in [
+            (DifferenceKind::Added, cad_color_theme.added_color),
+            (DifferenceKind::Removed, cad_color_theme.removed_color),
+            (DifferenceKind::Modified, cad_color_theme.modified_color),
+        ] {
+            let count = differences.iter().filter(|d| d.kind == kind).count();
+            caption_runs
+                .push(TextRun::new(&format!("   {}: {}", kind.get_name(), count)).color(color));
+        }
+        tab_content.add_child(
+            TextBox::new(
+                SizeConstraints(
+                    SizeConstraint::flexible(0),
+                    SizeConstraint::fixed(font_height),
+                ),
+                caption.clone(),
+                font.clone(),
+            )
+            .runs(caption_runs),
+        );
+
+        tab_content.add_child(EmptySpace::new_splitter(SizeConstraints(
+            SizeConstraint::flexible(0),
+            SizeConstraint::fixed(1),
+        )));
+
+        let compare_line = tab_content.add_child(Container::new(
+            SizeConstraints(SizeConstraint::flexible(0), SizeConstraint::flexible(0)),
+            ContainerLayout::Horizontal,
+        ));
+
+        let cad_view = compare_line.borrow_mut().add_child(
+            CadView::new(
+                SizeConstraints(SizeConstraint::flexible(200), SizeConstraint::flexible(200)),
+                document.clone(),
+                self.picts.clone(),
+                self.config.clone(),
+                font.clone(),
+            )
+            .difference_kinds(difference_kinds),
+        );
+        self.bind_cad_view(&cad_view);
+
+        compare_line
+            .borrow_mut()
+            .add_child(EmptySpace::new_splitter(SizeConstraints(
+                SizeConstraint::fixed(1),
+                SizeConstraint::flexible(0),
+            )));
+
+        let side_panel = compare_line.borrow_mut().add_child(Container::new(
+            SizeConstraints(
+                SizeConstraint::fixed(side_width),
+                SizeConstraint::flexible(0),
+            ),
+            ContainerLayout::Vertical,
+        ));
+
+        let mut list = ListBox::new(
+            SizeConstraints(SizeConstraint::flexible(0), SizeConstraint::flexible(0)),
+            font_height,
+            font.clone(),
+        );
+        list.lines = differences
+            .iter()
+            .enumerate()
+            .map(|(index, difference)| format!("{}. {}", index + 1, difference.kind.get_name()))
+            .collect();
+        side_panel.borrow_mut().add_child(list);
+
+        let status = side_panel.borrow_mut().add_child(TextBox::new(
+            SizeConstraints(
+                SizeConstraint::flexible(0),
+                SizeConstraint::fixed(font_height),
+            ),
+            format!("Различий: {}", differences.len()),
+            font.clone(),
+        ));
+
+        let buttons_line = side_panel.borrow_mut().add_child(Container::new(
+            SizeConstraints(
+                SizeConstraint::flexible(0),
+                SizeConstraint::fixed(font_height),
+            ),
+            ContainerLayout::Horizontal,
+        ));
+        let previous_button = buttons_line
+            .borrow_mut()
+            .add_child(create_default_size_button("Предыдущее", font.clone()));
+        let next_button = buttons_line
+            .borrow_mut()
+            .add_child(create_default_size_button("Следующее", font.clone()));
+
+        let differences = Rc::new(differences);
+        let current = Rc::new(RefCell::new(None));
+        {
+            let differences = differences.clone();
+            let current = current.clone();
+            previous_button
+                .borrow_mut()
+                .set_callback(callback!([document, status]() {
+                    go_to_difference(&document, &status, &differences, &current, false);
+                }));
+        }
+        next_button
+            .borrow_mut()
+            .set_callback(callback!([document, status]() {
+                go_to_difference(&document, &status, &differences, &current, true);
+            }));
+
+        let (_compare_tab, tab_id) = tab_control.borrow_mut().add_tab_with_id(
+            caption.clone(),
+            GuiSystem::default_size(&caption, None, font).0.absolute,
+            tab_content,
+        );
+
+        let mut internal = self.internal.borrow_mut();
+        internal.documents.insert(document_id, document);
+        internal.tab_id_to_document_id.insert(tab_id, document_id);
+        internal.selected_document_id = document_id;
+        drop(internal);
+        tab_control.borrow_mut().select_tab(tab_id);
+    }
+
     fn close_tab_impl(
         internal: Rc<RefCell<EditorInternal>>,
         tab_control: Rc<RefCell<TabControl>>,
@@ -201,10 +755,103 @@ impl Editor {
     ) {
         tab_control.borrow_mut().delete_tab(id);
         let mut internal = internal.borrow_mut();
-        if let Some(document_id) = internal.tab_id_to_document_id.get(&id).copied() {
-            internal.documents.remove(&document_id);
+        if internal.settings_tab_id == Some(id) {
+            internal.settings_tab_id = None;
+        }
+        let document_id = internal.tab_id_to_document_id.remove(&id);
+        // the document stays open while another tab shows it
+        if let Some(document_id) = document_id.filter(|document_id| {
+            !internal
+                .tab_id_to_document_id
+                .values()
+                .any(|id| id == document_id)
+        }) {
+            let document = internal.documents.remove(&document_id);
+            if let (Some(file_watcher), Some(document)) = (&internal.file_watcher, document) {
+                let document = document.borrow();
+                if let Some(path) = document.get_path() {
+                    file_watcher.unwatch(path);
+                }
+                for path in document.get_xref_paths() {
+                    file_watcher.unwatch(path);
+                }
+            }
+        }
+    }
+
+    pub fn close_tab(&self, id: usize) {
+        if let Some(tab_control) = &self.tab_control {
+            Self::close_tab_impl(self.internal.clone(), tab_control.clone(), id);
+        }
+    }
+
+    /// Closes every tab of the document without asking to save it.
+    pub fn close_document(&self, document_id: usize) {
+        let tab_ids: Vec<_> = self
+            .internal
+            .borrow()
+            .tab_id_to_document_id
+            .iter()
+            .filter(|(_, id)| **id == document_id)
+            .map(|(tab_id, _)| *tab_id)
+            .collect();
+        for tab_id in tab_ids {
+            self.close_tab(tab_id);
+        }
+    }
+
+    /// Adds a non-document page, such as a dialog, to the document tabs and selects it.
+    pub fn add_page_tab<Control: GuiControl>(
+        &self,
+        font: &Font,
+        caption: &str,
+        control: Control,
+    ) -> Option<usize> {
+        let tab_control = self.tab_control.clone()?;
+        let (_, tab_id) = tab_control.borrow_mut().add_tab_with_id(
+            caption.to_string(),
+            Button::default_size(caption, None, font).0.absolute,
+            control,
+        );
+        tab_control.borrow_mut().select_tab(tab_id);
+        Some(tab_id)
+    }
+
+    pub fn add_settings_tab<Control: GuiControl>(
+        &self,
+        font: &Font,
+        caption: &str,
+        control: Control,
+    ) -> Option<usize> {
+        let tab_id = self.add_page_tab(font, caption, control)?;
+        self.internal.borrow_mut().settings_tab_id = Some(tab_id);
+        Some(tab_id)
+    }
+
+    pub fn select_tab(&self, id: usize) {
+        if let Some(tab_control) = &self.tab_control {
+            tab_control.borrow_mut().select_tab(id);
+        }
+    }
+
+    pub fn get_selected_tab_id(&self) -> Option<usize> {
+        Some(self.tab_control.as_ref()?.borrow().selected_tab_id())
+    }
+
+    pub fn get_settings_tab_id(&self) -> Option<usize> {
+        self.internal.borrow().settings_tab_id
+    }
+
+    /// Selects the settings tab if it is already open.
+    pub fn select_settings_tab(&self) -> bool {
+        let settings_tab_id = self.internal.borrow().settings_tab_id;
+        match (settings_tab_id, &self.tab_control) {
+            (Some(tab_id), Some(tab_control)) => {
+                tab_control.borrow_mut().select_tab(tab_id);
+                return true;
+            }
+            _ => return false,
         }
-        internal.tab_id_to_document_id.remove(&id);
     }
 
     pub fn close_selected_tab(&mut self) {
@@ -219,6 +866,8 @@ impl Editor {
 
     pub fn set_tab_control(&mut self, font: Font, tab_control: Rc<RefCell<TabControl>>) {
         let internal = self.internal.clone();
+        // pages of the old tab control are not restored
+        internal.borrow_mut().settings_tab_id = None;
 
         tab_control
             .borrow_mut()
@@ -280,99 +929,1312 @@ impl Editor {
             document.add_entity(new_entity);
         }
 
-        let c = Contour::circle(Point::new(0.0, 0.0), 10.0);
-        document.add_entity(crate::document::Curve::new_contour(c));
-        let c = Contour::circle(Point::new(15.0, 5.0), 5.0);
-        document.add_entity(crate::document::Curve::new_contour(c));
-        document.fix_history();
+        let c = Contour::circle(Point::new(0.0, 0.0), 10.0);
+        document.add_entity(crate::document::Curve::new_contour(c));
+        let c = Contour::circle(Point::new(15.0, 5.0), 5.0);
+        document.add_entity(crate::document::Curve::new_contour(c));
+        document.fix_history();
+        document.set_limits(self.config.borrow().document_limits);
+
+        let document_id = self.get_next_id();
+        self.internal
+            .borrow_mut()
+            .documents
+            .insert(document_id, Rc::new(RefCell::new(document)));
+        document_id
+    }
+
+    /// Reads the document from the file, the tab is added by `add_tab_by_existing_document`.
+    /// The password is needed for the encrypted files only. The entities of a big file
+    /// are left for `load_document_entities`.
+    pub fn open_document(&mut self, path: &Path, password: Option<&str>) -> Result<usize, String> {
+        let mut document = Document::start_loading(path, password)?;
+        document.set_limits(self.config.borrow().document_limits);
+        if !document.is_loading() {
+            self.finish_opening(&mut document);
+        }
+        self.add_recent_file(path);
+        let document_id = self.get_next_id();
+        let mut internal = self.internal.borrow_mut();
+        if let Some(file_watcher) = &internal.file_watcher {
+            file_watcher.watch(path);
+        }
+        internal
+            .documents
+            .insert(document_id, Rc::new(RefCell::new(document)));
+        Ok(document_id)
+    }
+
+    /// The references are known when the settings of the file are read, they are shown
+    /// and watched together with the loaded drawing.
+    pub fn finish_opening(&self, document: &mut Document) {
+        document.reload_xrefs();
+        if let Some(file_watcher) = &self.internal.borrow().file_watcher {
+            for xref in document.get_xref_paths() {
+                file_watcher.watch(xref);
+            }
+        }
+        self.update_thumbnail(document);
+    }
+
+    /// Takes a document not read from a file, for example an imported one, the tab is added
+    /// by `add_tab_by_existing_document`.
+    pub fn add_document(&mut self, mut document: Document) -> usize {
+        document.set_limits(self.config.borrow().document_limits);
+        let document_id = self.get_next_id();
+        self.internal
+            .borrow_mut()
+            .documents
+            .insert(document_id, Rc::new(RefCell::new(document)));
+        document_id
+    }
+
+    /// Writes the document to the file, the document is watched under its new name.
+    pub fn save_document(
+        &self,
+        document: &Rc<RefCell<Document>>,
+        path: &Path,
+    ) -> Result<(), String> {
+        let old_path = document.borrow().get_path().map(Path::to_path_buf);
+        document.borrow_mut().save(path)?;
+
+        let internal = self.internal.borrow();
+        if old_path.as_deref() != Some(path) {
+            if let Some(file_watcher) = &internal.file_watcher {
+                if let Some(old_path) = &old_path {
+                    file_watcher.unwatch(old_path);
+                }
+                file_watcher.watch(path);
+            }
+        }
+        let document_id = internal
+            .documents
+            .iter()
+            .find(|(_, d)| Rc::ptr_eq(d, document))
+            .map(|(id, _)| *id);
+        let tab_ids: Vec<_> = internal
+            .tab_id_to_document_id
+            .iter()
+            .filter(|(_, id)| Some(**id) == document_id)
+            .map(|(tab_id, _)| *tab_id)
+            .collect();
+        drop(internal);
+        self.update_thumbnail(&document.borrow());
+        self.add_recent_file(path);
+        if let (Some(tab_control), Some(document_id)) = (&self.tab_control, document_id) {
+            let caption = get_document_caption(document_id, &document.borrow());
+            for tab_id in tab_ids {
+                tab_control
+                    .borrow()
+                    .set_tab_caption(tab_id, &escape_accelerator(&caption));
+                tab_control
+                    .borrow()
+                    .set_tab_tooltip(tab_id, Some(self.get_document_tooltip(document)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Renders the thumbnail of the document again, it is cached by the file name.
+    pub fn update_thumbnail(&self, document: &Document) -> Option<Rc<Image<u32>>> {
+        let path = document.get_path()?.to_path_buf();
+        let thumbnail = Rc::new(render_thumbnail(
+            document,
+            &self.config.borrow(),
+            THUMBNAIL_SIZE,
+        ));
+        self.internal
+            .borrow_mut()
+            .thumbnails
+            .insert(path, thumbnail.clone());
+        Some(thumbnail)
+    }
+
+    /// Thumbnail of the file, the file is read if it was not opened or saved before.
+    pub fn get_thumbnail(&self, path: &Path) -> Option<Rc<Image<u32>>> {
+        if let Some(thumbnail) = self.internal.borrow().thumbnails.get(path) {
+            return Some(thumbnail.clone());
+        }
+        let document = Document::load(path).ok()?;
+        self.update_thumbnail(&document)
+    }
+
+    /// The picture of the tooltip is rendered when it is shown, so it has the unsaved
+    /// changes too.
+    fn get_document_tooltip(&self, document: &Rc<RefCell<Document>>) -> Tooltip {
+        let text = match document.borrow().get_path() {
+            Some(path) => path.to_string_lossy().to_string(),
+            None => "Чертёж не сохранён".to_string(),
+        };
+        let weak_document = Rc::downgrade(document);
+        let config = self.config.clone();
+        Tooltip::new(&text).image_source(move || {
+            let document = weak_document.upgrade()?;
+            let document = document.try_borrow().ok()?;
+            Some(Rc::new(render_thumbnail(
+                &document,
+                &config.borrow(),
+                THUMBNAIL_SIZE,
+            )))
+        })
+    }
+
+    /// Picture of the active drawing for the taskbar, as big as it may be.
+    pub fn render_taskbar_thumbnail(&self, max_size: ImageSize) -> Option<Image<u32>> {
+        let document = self.get_active_document()?;
+        let document = document.try_borrow().ok()?;
+        Some(render_thumbnail(&document, &self.config.borrow(), max_size))
+    }
+
+    fn add_recent_file(&self, path: &Path) {
+        let mut config = self.config.borrow().clone();
+        config.recent_files.retain(|file| file != path);
+        config.recent_files.insert(0, path.to_path_buf());
+        config.recent_files.truncate(MAX_RECENT_FILES);
+        self.set_config(config);
+    }
+
+    pub fn set_file_watcher(&self, file_watcher: FileWatcher) {
+        self.internal.borrow_mut().file_watcher = Some(file_watcher);
+    }
+
+    /// Documents whose files were changed by other programs since the last call.
+    pub fn take_changed_on_disk_documents(&self) -> Vec<Rc<RefCell<Document>>> {
+        let internal = self.internal.borrow();
+        let changed_files = match &internal.file_watcher {
+            Some(file_watcher) => file_watcher.take_changed_files(),
+            None => return Vec::new(),
+        };
+        let mut ids: Vec<_> = internal.documents.keys().copied().collect();
+        ids.sort_unstable();
+        let mut result = Vec::new();
+        for id in ids {
+            let document = &internal.documents[&id];
+            let xref_changed = document
+                .borrow()
+                .get_xref_paths()
+                .iter()
+                .any(|xref| changed_files.contains(xref));
+            if xref_changed {
+                document.borrow_mut().reload_xrefs();
+            }
+            let changed = {
+                let document = document.borrow();
+                document
+                    .get_path()
+                    .map_or(false, |path| changed_files.iter().any(|f| f == path))
+                    && document.is_changed_on_disk()
+            };
+            if changed {
+                document.borrow_mut().accept_disk_change();
+                result.push(document.clone());
+            }
+        }
+        result
+    }
+
+    /// The referenced drawing is watched and shown again after the changes of its file.
+    pub fn add_xref(&self, path: &Path) -> Result<(), String> {
+        let document = match self.get_active_document() {
+            Some(document) => document,
+            None => return Ok(()),
+        };
+        document.borrow_mut().add_xref(path)?;
+        if let Some(file_watcher) = &self.internal.borrow().file_watcher {
+            file_watcher.watch(path);
+        }
+        Ok(())
+    }
+
+    pub fn remove_xrefs(&self) {
+        let document = match self.get_active_document() {
+            Some(document) => document,
+            None => return,
+        };
+        let xrefs = document.borrow_mut().remove_xrefs();
+        if let Some(file_watcher) = &self.internal.borrow().file_watcher {
+            for xref in &xrefs {
+                file_watcher.unwatch(xref);
+            }
+        }
+    }
+
+    pub fn get_active_document(&self) -> Option<Rc<RefCell<Document>>> {
+        let internal = self.internal.borrow();
+        internal
+            .documents
+            .get(&internal.selected_document_id)
+            .cloned()
+    }
+
+    /// Open documents in the order of their ids.
+    pub fn get_documents(&self) -> Vec<(usize, Rc<RefCell<Document>>)> {
+        let mut documents: Vec<_> = self
+            .internal
+            .borrow()
+            .documents
+            .iter()
+            .map(|(id, document)| (*id, document.clone()))
+            .collect();
+        documents.sort_by_key(|(id, _)| *id);
+        documents
+    }
+
+    /// Counts the memory of the active document again if it has changed.
+    pub fn update_memory_usage(&self) {
+        let active = {
+            let internal = self.internal.borrow();
+            let id = internal.selected_document_id;
+            internal
+                .documents
+                .get(&id)
+                .map(|document| (id, document.clone()))
+        };
+        let source = active
+            .as_ref()
+            .map(|(id, document)| (*id, document.borrow().get_changes().get().number));
+        if self.memory_usage_source.replace(source) == source {
+            return;
+        }
+        self.memory_usage
+            .set(active.map(|(_, document)| document.borrow().get_memory_usage()));
+    }
+
+    /// Limit warnings of all documents since the last call.
+    pub fn take_document_warnings(&self) -> Vec<String> {
+        let internal = self.internal.borrow();
+        let mut ids: Vec<_> = internal.documents.keys().copied().collect();
+        ids.sort_unstable();
+        ids.iter()
+            .flat_map(|id| internal.documents[id].borrow_mut().take_warnings())
+            .collect()
+    }
+
+    pub fn skip_state(&self) {
+        if let Some(document) = self.get_active_document() {
+            document.borrow_mut().skip_state();
+        }
+    }
+
+    /// Stops the tool asking for points or a rectangle, its preview is dropped, or the
+    /// click in progress. Returns false if there was nothing to stop.
+    pub fn cancel_tool(&self) -> bool {
+        let mut point_picker = self.point_picker.borrow_mut();
+        let mut rect_picker = self.rect_picker.borrow_mut();
+        if point_picker.is_active() || rect_picker.is_active() {
+            point_picker.cancel();
+            rect_picker.cancel();
+            return true;
+        }
+        drop((point_picker, rect_picker));
+        match self.get_active_document() {
+            Some(document) => document.borrow_mut().cancel_state(),
+            None => false,
+        }
+    }
+
+    /// Returns false if nothing was selected.
+    pub fn clear_selection(&self) -> bool {
+        match self.get_active_document() {
+            Some(document) => document.borrow_mut().clear_selection(),
+            None => false,
+        }
+    }
+
+    pub fn get_selected_count(&self) -> usize {
+        match self.get_active_document() {
+            Some(document) => document.borrow().get_selected_count(),
+            None => 0,
+        }
+    }
+
+    pub fn remove_selected(&self) {
+        if let Some(document) = self.get_active_document() {
+            document.borrow_mut().remove_selected();
+        }
+    }
+
+    pub fn get_selected_elements(&self) -> Vec<Element> {
+        match self.get_active_document() {
+            Some(document) => document.borrow().get_selected_elements(),
+            None => Vec::new(),
+        }
+    }
+
+    pub fn paste_elements(&self, elements: &[Element]) {
+        if let Some(document) = self.get_active_document() {
+            document.borrow_mut().paste_elements(elements);
+        }
+    }
+
+    /// Ends closer than the snap distance are connected.
+    pub fn select_chain(&self) -> Vec<ChainGap> {
+        match self.get_active_document() {
+            Some(document) => {
+                let gap_tolerance = document.borrow().snap_distance(&self.config.borrow());
+                document.borrow_mut().select_chain(gap_tolerance)
+            }
+            None => Vec::new(),
+        }
+    }
+
+    pub fn has_selection(&self) -> bool {
+        match self.get_active_document() {
+            Some(document) => document.borrow().has_selection(),
+            None => false,
+        }
+    }
+
+    /// World coordinates without an active drawing.
+    pub fn get_ucs(&self) -> Ucs {
+        match self.get_active_document() {
+            Some(document) => *document.borrow().get_ucs(),
+            None => Ucs::default(),
+        }
+    }
+
+    /// Direction of the line under the point in the active drawing.
+    pub fn get_line_direction_at(&self, position: Point<f64>) -> Option<Point<f64>> {
+        let document = self.get_active_document()?;
+        let config = self.config.borrow();
+        let direction = document.borrow().get_line_direction_at(position, &config);
+        direction
+    }
+
+    pub fn set_ucs(&self, ucs: Ucs) {
+        if let Some(document) = self.get_active_document() {
+            document.borrow_mut().set_ucs(ucs);
+        }
+    }
+
+    pub fn get_ucs_of_selected(&self) -> Option<Ucs> {
+        self.get_active_document()?.borrow().get_ucs_of_selected()
+    }
+
+    /// Shows the closest points of the two selected curves, returns the distance.
+    pub fn show_closest_distance(&self) -> Option<f64> {
+        let (from, to) = self.get_active_document()?.borrow().show_closest_points()?;
+        Some((to - from).length())
+    }
+
+    /// Runs the command on the active drawing, it goes to the history if it changed
+    /// something.
+    pub fn run_command(&self, command: Command) {
+        if let Some(document) = self.get_active_document() {
+            if command.run(&mut document.borrow_mut()) {
+                self.command_history.borrow_mut().push(command);
+            }
+        }
+    }
+
+    pub fn repeat_last_command(&self) {
+        let command = self.command_history.borrow().get_last().cloned();
+        if let Some(command) = command {
+            self.run_command(command);
+        }
+    }
+
+    /// The latest command is the last.
+    pub fn get_command_history(&self) -> Vec<Command> {
+        self.command_history.borrow().get_commands().to_vec()
+    }
+
+    /// Commands done from now on are recorded, until `stop_macro_recording`.
+    pub fn start_macro_recording(&self) {
+        self.command_history.borrow_mut().start_recording();
+    }
+
+    pub fn is_recording_macro(&self) -> bool {
+        self.command_history.borrow().is_recording()
+    }
+
+    pub fn stop_macro_recording(&self) -> Vec<Command> {
+        self.command_history.borrow_mut().stop_recording()
+    }
+
+    pub fn unhide_all(&self) {
+        if let Some(document) = self.get_active_document() {
+            document.borrow_mut().unhide_all();
+        }
+    }
+
+    pub fn unlock_all(&self) {
+        if let Some(document) = self.get_active_document() {
+            document.borrow_mut().unlock_all();
+        }
+    }
+
+    /// Fits the visible drawing into the view.
+    pub fn zoom_extents(&self) {
+        if let Some(document) = self.get_active_document() {
+            zoom_to_extents(&mut document.borrow_mut(), false, self.view_size.get());
+        }
+    }
+
+    /// Fits the selection into the view, returns false if nothing is selected.
+    pub fn zoom_to_selection(&self) -> bool {
+        match self.get_active_document() {
+            Some(document) => {
+                zoom_to_extents(&mut document.borrow_mut(), true, self.view_size.get())
+            }
+            None => false,
+        }
+    }
+
+    /// Scale delta is in the same units as `Document::change_scale`, the view center stays.
+    pub fn zoom_active_document(&self, scale_delta: f64) {
+        if let Some(document) = self.get_active_document() {
+            document.borrow_mut().change_scale(scale_delta);
+        }
+    }
+
+    /// Turns the view of the active drawing, the angle is counted from the unturned view.
+    pub fn set_view_rotation(&self, angle: f64) {
+        if let Some(document) = self.get_active_document() {
+            document.borrow_mut().set_rotation(angle);
+        }
+    }
+
+    /// Makes the Y axis of the active drawing go the other way on the screen.
+    pub fn flip_y_axis(&self) {
+        if let Some(document) = self.get_active_document() {
+            let y_up = document.borrow().is_y_up();
+            document.borrow_mut().set_y_up(!y_up);
+        }
+    }
+
+    pub fn get_view_rotation(&self) -> f64 {
+        self.get_active_document()
+            .map_or(0.0, |document| document.borrow().get_rotation())
+    }
+
+    pub fn undo(&self) {
+        if let Some(document) = self.get_active_document() {
+            document.borrow_mut().undo();
+        }
+    }
+
+    pub fn redo(&self) {
+        if let Some(document) = self.get_active_document() {
+            document.borrow_mut().redo();
+        }
+    }
+}
+
+fn go_to_difference(
+    document: &RefCell<Document>,
+    status: &RefCell<TextBox>,
+    differences: &[Difference],
+    current: &RefCell<Option<usize>>,
+    forward: bool,
+) {
+    let count = differences.len();
+    if count == 0 {
+        return;
+    }
+    let index = match (*current.borrow(), forward) {
+        (None, true) => 0,
+        (None, false) => count - 1,
+        (Some(index), true) => (index + 1) % count,
+        (Some(index), false) => (index + count - 1) % count,
+    };
+    *current.borrow_mut() = Some(index);
+
+    let difference = differences[index];
+    let mut document = document.borrow_mut();
+    document.set_center(difference.position);
+    document.select_only(&[difference.id].into_iter().collect());
+    status.borrow_mut().set_text(&format!(
+        "{} из {}: {}",
+        index + 1,
+        count,
+        difference.kind.get_name()
+    ));
+}
+
+type RectPickerCallback = Rc<dyn Fn(Point<f64>, Point<f64>)>;
+
+/// Rectangle which the next drag in a drawing gives to the callback instead of selecting,
+/// shared by all drawings of the editor.
+#[derive(Default)]
+pub struct RectPicker {
+    callback: Option<RectPickerCallback>,
+    corners: Option<(Point<f64>, Point<f64>)>,
+}
+
+impl RectPicker {
+    pub fn is_active(&self) -> bool {
+        self.callback.is_some()
+    }
+
+    pub fn cancel(&mut self) {
+        self.callback = None;
+        self.corners = None;
+    }
+}
+
+type PointPickerCallback = Rc<dyn Fn(Point<f64>)>;
+
+/// Transform of the selection drawn while picking, and the text shown near the mouse.
+pub type PickPreview = Rc<dyn Fn(Point<f64>) -> (Transform, String)>;
+
+/// What the next click in a drawing is asked for, the prompt is shown near the mouse.
+#[derive(Default)]
+pub struct PointPick {
+    prompt: String,
+    from: Option<Point<f64>>,
+    polar: bool,
+    preview: Option<PickPreview>,
+    /// Curves drawn through the preview transform instead of the selection.
+    geometry: Option<Vec<curves::Curve<f64>>>,
+    keys: Vec<(Key, Rc<dyn Fn()>)>,
+}
+
+impl PointPick {
+    pub fn new(prompt: &str) -> Self {
+        Self {
+            prompt: prompt.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// A rubber line goes from this point to the mouse.
+    pub fn from(mut self, from: Point<f64>) -> Self {
+        self.from = Some(from);
+        self
+    }
+
+    /// Away from the snap points, the direction from `from` goes in steps of the polar
+    /// tracking.
+    pub fn polar(mut self) -> Self {
+        self.polar = true;
+        self
+    }
+
+    pub fn preview(
+        mut self,
+        preview: impl Fn(Point<f64>) -> (Transform, String) + 'static,
+    ) -> Self {
+        self.preview = Some(Rc::new(preview));
+        self
+    }
+
+    pub fn geometry(mut self, geometry: Vec<curves::Curve<f64>>) -> Self {
+        self.geometry = Some(geometry);
+        self
+    }
+
+    /// The key pressed over the drawing while picking calls the callback, the preview is
+    /// redrawn then.
+    pub fn key(mut self, key: Key, callback: impl Fn() + 'static) -> Self {
+        self.keys.push((key, Rc::new(callback)));
+        self
+    }
+}
+
+/// Point which the next click in a drawing gives to the callback instead of selecting,
+/// shared by all drawings of the editor.
+#[derive(Default)]
+pub struct PointPicker {
+    callback: Option<PointPickerCallback>,
+    pick: PointPick,
+    pressed: bool,
+}
+
+impl PointPicker {
+    pub fn is_active(&self) -> bool {
+        self.callback.is_some()
+    }
+
+    pub fn cancel(&mut self) {
+        self.callback = None;
+        self.pick = PointPick::default();
+        self.pressed = false;
+    }
+
+    /// Point which a click at the position gives. Away from the snap points the smart
+    /// guides pull the point, then the ortho mode keeps the direction from `from` along
+    /// the axes, otherwise the polar step applies.
+    fn get_point(&self, document: &Document, position: Point<f64>, config: &Config) -> Point<f64> {
+        match self.get_smart_guides(document, position, config) {
+            Some(guides) => guides.position,
+            None => self.get_tracked_point(document, position, config),
+        }
+    }
+
+    fn get_smart_guides(
+        &self,
+        document: &Document,
+        position: Point<f64>,
+        config: &Config,
+    ) -> Option<SmartGuides> {
+        if !config.snap_enabled.0
+            || !config.snap_options.smart_guides
+            || document.get_snapped_position(position) != position
+        {
+            return None;
+        }
+        find_smart_guides(
+            &document.get_guide_references(),
+            document.get_ucs(),
+            position,
+            self.pick.from,
+            document.snap_distance(config),
+        )
+    }
+
+    fn get_tracked_point(
+        &self,
+        document: &Document,
+        position: Point<f64>,
+        config: &Config,
+    ) -> Point<f64> {
+        let snapped = document.get_snapped_position(position);
+        let start = document.get_ucs().get_angle();
+        match self.pick.from {
+            Some(from) if self.pick.polar && snapped == position && config.ortho => {
+                snap_ortho(from, position, config.get_snap_angles(), start)
+            }
+            Some(from) if self.pick.polar && snapped == position && config.polar_step.0 > 0.0 => {
+                snap_polar(from, position, config.polar_step.0.to_radians(), start)
+            }
+            _ => snapped,
+        }
+    }
+}
+
+/// The rest of the smooth zoom shrinks with this time constant, so it ends in about 100 ms.
+const ZOOM_TIME_CONSTANT: Duration = Duration::from_millis(20);
+
+/// Documents with this many entities are drawn with less anti-aliasing while the view is
+/// panned, zoomed or dragged, four times more drop it completely.
+const PROGRESSIVE_AA_ENTITIES: usize = 5000;
+/// The full anti-aliasing comes back when the view is still for this time.
+const PROGRESSIVE_AA_IDLE: Duration = Duration::from_millis(250);
+
+/// Samples per pixel while the view moves, the cost of a frame grows with both.
+fn get_interactive_aa(aa: usize, entity_count: usize) -> usize {
+    if entity_count < PROGRESSIVE_AA_ENTITIES {
+        aa
+    } else if entity_count < PROGRESSIVE_AA_ENTITIES * 4 {
+        aa / 2
+    } else {
+        1
+    }
+    .max(1)
+}
+
+/// Zoom done on timer messages, the point under the cursor stays in place.
+#[derive(Debug)]
+struct ZoomAnimation {
+    /// Scale delta applied so far, it follows the delta requested by the wheel.
+    delta: SmoothValue,
+    position: Position,
+}
+
+pub struct CadView {
+    base: GuiControlBase,
+    document: Rc<RefCell<Document>>,
+    picts: Rc<RefCell<Picts>>,
+    config: Rc<RefCell<Config>>,
+    font: Font,
+    difference_kinds: Option<Rc<HashMap<usize, DifferenceKind>>>,
+    cursor_position: Property<Option<Point<f64>>>,
+    cursor_ucs_position: Property<Option<Point<f64>>>,
+    rect_picker: Rc<RefCell<RectPicker>>,
+    point_picker: Rc<RefCell<PointPicker>>,
+    command_history: Rc<RefCell<CommandHistory>>,
+    view_size: Rc<Cell<Point<f64>>>,
+    zoom_animation: Option<ZoomAnimation>,
+    /// Last move of the view, until it gets idle the drawing can be rougher.
+    last_interaction: Option<Instant>,
+    /// The last frame was drawn with less anti-aliasing, it is redrawn when idle.
+    reduced_aa: bool,
+    /// Handle of the box around the selection being dragged.
+    gizmo_drag: Option<GizmoDrag>,
+    /// Center and scale of a view which is not synchronized with the other views of the
+    /// document, they share the ones stored in the document.
+    own_view: Rc<Cell<Option<DocumentView>>>,
+    changes_subscription: Option<usize>,
+}
+
+impl Drop for CadView {
+    fn drop(&mut self) {
+        if let (Some(id), Ok(document)) = (self.changes_subscription, self.document.try_borrow()) {
+            document.get_changes().unsubscribe(id);
+        }
+    }
+}
+
+/// Returns false if there is nothing to fit.
+fn zoom_to_extents(document: &mut Document, selected_only: bool, view_size: Point<f64>) -> bool {
+    match document.get_extents(selected_only) {
+        Some((min, max)) => {
+            document.zoom_to_rect(min, max, view_size);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Runs the action with the own view of a view put into the document.
+fn with_own_view<R>(
+    document: &RefCell<Document>,
+    own_view: &Cell<Option<DocumentView>>,
+    action: impl FnOnce() -> R,
+) -> R {
+    let view = match own_view.get() {
+        Some(view) => view,
+        None => return action(),
+    };
+    let shared_view = document.borrow_mut().replace_view(view);
+    let result = action();
+    let view = document.borrow_mut().replace_view(shared_view);
+    if own_view.get().is_some() {
+        own_view.set(Some(view));
+    }
+    result
+}
+
+impl std::fmt::Debug for CadView {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.base.fmt(f)
+    }
+}
+
+impl CadView {
+    pub fn new(
+        size_constraints: SizeConstraints,
+        document: Rc<RefCell<Document>>,
+        picts: Rc<RefCell<Picts>>,
+        config: Rc<RefCell<Config>>,
+        font: Font,
+    ) -> Self {
+        Self {
+            base: GuiControlBase::new(size_constraints),
+            document,
+            picts,
+            config,
+            font,
+            difference_kinds: None,
+            cursor_position: Property::new(None),
+            cursor_ucs_position: Property::new(None),
+            rect_picker: Rc::default(),
+            point_picker: Rc::default(),
+            command_history: Rc::default(),
+            view_size: Rc::default(),
+            zoom_animation: None,
+            last_interaction: None,
+            reduced_aa: false,
+            gizmo_drag: None,
+            own_view: Rc::default(),
+            changes_subscription: None,
+        }
+    }
+
+    /// A synchronized view pans and zooms together with the other synchronized views of the
+    /// document, otherwise it keeps its own center and scale.
+    pub fn set_synchronized(&mut self, synchronized: bool) {
+        if synchronized {
+            self.own_view.set(None);
+        } else {
+            self.own_view.set(Some(self.document.borrow().get_view()));
+        }
+    }
+
+    /// Entities of the compare document are drawn in the colors of their differences.
+    pub fn difference_kinds(
+        mut self,
+        difference_kinds: Rc<HashMap<usize, DifferenceKind>>,
+    ) -> Self {
+        self.difference_kinds = Some(difference_kinds);
+        self
+    }
+
+    fn get_difference_color(&self, id: usize, cad_color_theme: &CadColorTheme) -> Option<u32> {
+        let kind = self.difference_kinds.as_ref()?.get(&id)?;
+        Some(match kind {
+            DifferenceKind::Added => cad_color_theme.added_color,
+            DifferenceKind::Removed => cad_color_theme.removed_color,
+            DifferenceKind::Modified => cad_color_theme.modified_color,
+        })
+    }
+
+    /// The view of the mouse messages.
+    fn get_view_transform(&mut self) -> ViewTransform {
+        let rect = self.get_base_mut().get_rect();
+        let screen_center = Point::new(
+            (rect.right_bottom.0 as f64 + rect.left_top.0 as f64) * 0.5,
+            (rect.right_bottom.1 as f64) * 0.5,
+        );
+        ViewTransform::new(&self.document.borrow(), screen_center)
+    }
+
+    pub fn screen_coord_to_document_coord(
+        &mut self,
+        position: Point<f64>,
+    ) -> (Point<f64>, Point<f64>) {
+        let view = self.get_view_transform();
+        (position - view.screen_center, view.to_document(position))
+    }
+
+    fn document_coord_to_screen_coord(&mut self, position: Point<f64>) -> Position {
+        let screen = self.get_view_transform().to_screen(position);
+        (screen.x.round() as i32, screen.y.round() as i32)
+    }
+
+    /// Bounds of the selection which get the gizmo, it is not shown while the mouse is busy
+    /// with a tool or the selection rectangle.
+    fn get_gizmo_bounds(&self, document: &Document) -> Option<(Point<f64>, Point<f64>)> {
+        if self.point_picker.borrow().is_active()
+            || self.rect_picker.borrow().is_active()
+            || document.is_dragging()
+        {
+            return None;
+        }
+        document.get_extents(true)
+    }
+
+    /// The angle snaps to the steps of the polar tracking. None while the handle is where
+    /// it was grabbed.
+    fn get_gizmo_command(&self, drag: &GizmoDrag, position: Point<f64>) -> Option<Command> {
+        let config = self.config.borrow();
+        let change = drag.get_change(position, config.polar_step.0.to_radians());
+        if change.is_identity() {
+            return None;
+        }
+        Some(match change {
+            GizmoChange::Rotation(base, angle) => {
+                rotation_command(base, angle, &config.number_format, &config.angle_format)
+            }
+            GizmoChange::Scaling(base, factor) => {
+                scaling_command(base, factor, &config.number_format)
+            }
+        })
+    }
+
+    fn get_view_size(&mut self) -> Point<f64> {
+        let (width, height) = self.get_base_mut().get_size();
+        Point::new(width as f64, height as f64)
+    }
+
+    /// Smooth or instant zoom by the wheel, the point under the position stays in place.
+    fn zoom_by_wheel(&mut self, position: Position, delta: f32) {
+        let scale_delta = -delta as f64 * 10.0;
+        if self.config.borrow().smooth_zoom {
+            self.animate_zoom(position, scale_delta);
+        } else {
+            self.change_scale_at(position, scale_delta);
+        }
+    }
+
+    /// Adds the delta to the running zoom, which moves to the new cursor position.
+    fn animate_zoom(&mut self, position: Position, scale_delta: f64) {
+        let animation = self.zoom_animation.get_or_insert_with(|| ZoomAnimation {
+            delta: SmoothValue::new(0.0, ZOOM_TIME_CONSTANT),
+            position,
+        });
+        let target = animation.delta.get_target() + scale_delta as f32;
+        animation.delta.set_target(target);
+        animation.position = position;
+    }
+
+    /// Returns true if the scale was changed.
+    fn update_zoom_animation(&mut self) -> bool {
+        let animation = match &mut self.zoom_animation {
+            Some(animation) => animation,
+            None => return false,
+        };
+        let old_delta = animation.delta.get();
+        animation.delta.update();
+        let step = animation.delta.get() - old_delta;
+        let position = animation.position;
+        if !animation.delta.is_running() {
+            self.zoom_animation = None;
+        }
+        if step == 0.0 {
+            return false;
+        }
+        self.change_scale_at(position, step as f64);
+        true
+    }
+
+    fn mark_interaction(&mut self) {
+        self.last_interaction = Some(Instant::now());
+    }
+
+    /// Returns true if the rough frame has to be redrawn with the full anti-aliasing.
+    fn update_interaction(&mut self) -> bool {
+        match self.last_interaction {
+            Some(time) if time.elapsed() >= PROGRESSIVE_AA_IDLE => {
+                self.last_interaction = None;
+                std::mem::replace(&mut self.reduced_aa, false)
+            }
+            _ => false,
+        }
+    }
+
+    fn change_scale_at(&mut self, position: Position, scale_delta: f64) {
+        self.mark_interaction();
+        let (rel_position, document_position) =
+            self.screen_coord_to_document_coord(Point::new(position.0 as f64, position.1 as f64));
+        let mut document = self.document.borrow_mut();
+        document.change_scale(scale_delta);
+        let view = ViewTransform::new(&document, Point::new(0.0, 0.0));
+        let new_center = document_position - view.vector_to_document(rel_position);
+        document.set_center(new_center);
+    }
+
+    fn shift_view(&mut self, screen_shift: Point<f64>) {
+        self.mark_interaction();
+        let mut document = self.document.borrow_mut();
+        let view = ViewTransform::new(&document, Point::new(0.0, 0.0));
+        let new_center = document.get_center() + view.vector_to_document(screen_shift);
+        document.set_center(new_center);
+    }
+}
+
+/// Screen lengths of the dash and the gap of the dashed selection.
+const SELECTION_DASH: (f64, f64) = (6.0, 4.0);
+/// Curves of the referenced drawings are drawn faint.
+const XREF_ALPHA: u8 = 100;
+
+/// Points along the curve on the screen, arcs are split into chords which differ from the
+/// arc less than a quarter of a pixel. Infinite lines are cut by the screen diagonal.
+fn get_screen_polyline(curve: &curves::Curve<f64>, buf_size: Point<f64>) -> Vec<Point<f64>> {
+    let (contour, from, sweep) = match curve {
+        curves::Curve::Segment(s) if s.contour.a == 0.0 => return vec![s.begin, s.end],
+        curves::Curve::Contour(c) if c.a == 0.0 => {
+            let half = buf_size.scale(0.5);
+            let middle = c.translate(half.neg()).nearest_point_to_zero() + half;
+            let direction = c.n.rot90().normalize().scale(buf_size.length());
+            return vec![middle - direction, middle + direction];
+        }
+        curves::Curve::Contour(c) => (c, 0.0, std::f64::consts::TAU),
+        curves::Curve::Segment(s) => {
+            let center = s.contour.get_center();
+            let angle = |p: Point<f64>| (p.y - center.y).atan2(p.x - center.x);
+            let wrap = |a: f64| {
+                (a + std::f64::consts::PI).rem_euclid(std::f64::consts::TAU) - std::f64::consts::PI
+            };
+            let (begin, middle, end) = (angle(s.begin), angle(get_arc_middle(s)), angle(s.end));
+            (&s.contour, begin, wrap(middle - begin) + wrap(end - middle))
+        }
+    };
+    let center = contour.get_center();
+    let radius = contour.get_radius().abs();
+    let step = (2.0 / radius.max(1.0)).sqrt();
+    let count = ((sweep.abs() / step).ceil() as usize).clamp(1, 2000);
+    (0..=count)
+        .map(|index| {
+            let angle = from + sweep * index as f64 / count as f64;
+            center + Point::angle(angle).scale(radius)
+        })
+        .collect()
+}
+
+/// Dashes along the curve on the screen for the dashed selection.
+fn get_selection_dashes(
+    curve: &curves::Curve<f64>,
+    buf_size: Point<f64>,
+) -> Vec<curves::Curve<f64>> {
+    let (dash, gap) = SELECTION_DASH;
+    let mut result = Vec::new();
+    // position inside the period of the pattern, it goes on through the corners
+    let mut phase = 0.0;
+    for pair in get_screen_polyline(curve, buf_size).windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        let length = (b - a).length();
+        if length < 1.0e-9 {
+            continue;
+        }
+        let direction = (b - a).scale(1.0 / length);
+        let mut position = 0.0;
+        while position < length {
+            let next = if phase < dash {
+                let next = (position + dash - phase).min(length);
+                result.push(curves::Curve::Segment(Segment::line(
+                    a + direction.scale(position),
+                    a + direction.scale(next),
+                )));
+                next
+            } else {
+                (position + dash + gap - phase).min(length)
+            };
+            phase += next - position;
+            if phase >= dash + gap {
+                phase -= dash + gap;
+            }
+            position = next;
+        }
+    }
+    result
+}
+
+/// Width of the curve on the screen, the thin display draws every curve one pixel wide.
+fn get_screen_width(config: &Config, width: Option<f64>, scale: f64) -> f64 {
+    match width {
+        Some(width) if config.true_widths => (width * scale).max(1.0),
+        _ => 1.0,
+    }
+}
+
+/// Every tenth line is the base one.
+fn get_grid_color(index: i64, cad_color_theme: &CadColorTheme) -> u32 {
+    if index.rem_euclid(10) == 0 {
+        cad_color_theme.grid_color_base
+    } else {
+        cad_color_theme.grid_color_sub
+    }
+}
+
+/// Maps the document to the pixels of a view: the view center goes to the screen center,
+/// the drawing is mirrored if its Y axis goes up, turned by the view rotation and zoomed.
+#[derive(Copy, Clone)]
+struct ViewTransform {
+    center: Point<f64>,
+    scale: f64,
+    direction: Point<f64>,
+    y_up: bool,
+    screen_center: Point<f64>,
+}
+
+impl ViewTransform {
+    fn new(document: &Document, screen_center: Point<f64>) -> Self {
+        Self {
+            center: document.get_center(),
+            scale: document.get_scale(),
+            direction: Point::angle(document.get_rotation()),
+            y_up: document.is_y_up(),
+            screen_center,
+        }
+    }
+
+    fn is_turned(&self) -> bool {
+        self.direction.y != 0.0
+    }
 
-        let document_id = self.get_next_id();
-        self.internal
-            .borrow_mut()
-            .documents
-            .insert(document_id, Rc::new(RefCell::new(document)));
-        document_id
+    /// Mirroring is its own inverse.
+    fn flip(&self, vector: Point<f64>) -> Point<f64> {
+        if self.y_up {
+            Point::new(vector.x, -vector.y)
+        } else {
+            vector
+        }
     }
 
-    pub fn get_active_document(&self) -> Option<Rc<RefCell<Document>>> {
-        let internal = self.internal.borrow();
-        internal
-            .documents
-            .get(&internal.selected_document_id)
-            .cloned()
+    fn to_screen(&self, position: Point<f64>) -> Point<f64> {
+        let vector = self.flip(position - self.center);
+        complex_mul(vector, self.direction).scale(self.scale) + self.screen_center
     }
 
-    pub fn skip_state(&self) {
-        if let Some(document) = self.get_active_document() {
-            document.borrow_mut().skip_state();
-        }
+    fn curve_to_screen(&self, curve: curves::Curve<f64>) -> curves::Curve<f64> {
+        let curve = curve.translate(self.center.neg());
+        let curve = if self.y_up { curve.flip_y() } else { curve };
+        curve
+            .rotate(self.direction)
+            .scale(self.scale)
+            .translate(self.screen_center)
     }
 
-    pub fn remove_selected(&self) {
-        if let Some(document) = self.get_active_document() {
-            document.borrow_mut().remove_selected();
-        }
+    /// Document vector shown by the screen one.
+    fn vector_to_document(&self, vector: Point<f64>) -> Point<f64> {
+        self.flip(complex_mul(vector, self.direction.complex_conj()))
+            .scale(1.0 / self.scale)
     }
 
-    pub fn undo(&self) {
-        if let Some(document) = self.get_active_document() {
-            document.borrow_mut().undo();
-        }
+    fn to_document(&self, position: Point<f64>) -> Point<f64> {
+        self.vector_to_document(position - self.screen_center) + self.center
     }
 
-    pub fn redo(&self) {
-        if let Some(document) = self.get_active_document() {
-            document.borrow_mut().redo();
-        }
+    /// Corners of the screen rectangle around the document one.
+    fn rect_to_screen(
+        &self,
+        (corner1, corner2): (Point<f64>, Point<f64>),
+    ) -> (Point<f64>, Point<f64>) {
+        let corners = [
+            corner1,
+            Point::new(corner1.x, corner2.y),
+            Point::new(corner2.x, corner1.y),
+            corner2,
+        ]
+        .map(|corner| self.to_screen(corner));
+        corners
+            .iter()
+            .fold((corners[0], corners[0]), |(min, max), p| {
+                (
+                    Point::new(min.x.min(p.x), min.y.min(p.y)),
+                    Point::new(max.x.max(p.x), max.y.max(p.y)),
+                )
+            })
+    }
+
+    /// Document rectangle around the screen one.
+    fn rect_to_document(&self, size: Point<f64>) -> (Point<f64>, Point<f64>) {
+        let corners = [
+            Point::new(0.0, 0.0),
+            Point::new(size.x, 0.0),
+            Point::new(0.0, size.y),
+            size,
+        ]
+        .map(|corner| self.to_document(corner));
+        corners
+            .iter()
+            .fold((corners[0], corners[0]), |(min, max), p| {
+                (
+                    Point::new(min.x.min(p.x), min.y.min(p.y)),
+                    Point::new(max.x.max(p.x), max.y.max(p.y)),
+                )
+            })
     }
 }
 
-pub struct CadView {
-    base: GuiControlBase,
-    document: Rc<RefCell<Document>>,
-    picts: Rc<RefCell<Picts>>,
-    config: Rc<RefCell<Config>>,
-    font: Font,
+/// Grid of a turned UCS or a turned view, its lines are drawn as segments across the view.
+fn draw_ucs_grid(
+    buf: &mut ImageViewMut<u32>,
+    ucs: &Ucs,
+    step: f64,
+    view: &ViewTransform,
+    cad_color_theme: &CadColorTheme,
+) {
+    let size = Point::new(buf.get_size().0 as f64, buf.get_size().1 as f64);
+    let corners = [
+        Point::new(0.0, 0.0),
+        Point::new(size.x, 0.0),
+        Point::new(0.0, size.y),
+        size,
+    ]
+    .map(|corner| ucs.to_local(view.to_document(corner)));
+    let min = corners.iter().fold(corners[0], |min, p| {
+        Point::new(min.x.min(p.x), min.y.min(p.y))
+    });
+    let max = corners.iter().fold(corners[0], |max, p| {
+        Point::new(max.x.max(p.x), max.y.max(p.y))
+    });
+    let to_screen = |local: Point<f64>| view.to_screen(ucs.to_world(local));
+    let mut span_buffer = vec![(0, 0); buf.get_size().1];
+    for index in (min.x / step).floor() as i64..=(max.x / step).ceil() as i64 {
+        let x = index as f64 * step;
+        let line = Contour::line(
+            to_screen(Point::new(x, min.y)),
+            to_screen(Point::new(x, max.y)),
+        );
+        let color = get_grid_color(index, cad_color_theme);
+        draw_locc(
+            buf,
+            &curves::Curve::Contour(line),
+            color,
+            1.0,
+            &mut span_buffer,
+            1,
+        );
+    }
+    for index in (min.y / step).floor() as i64..=(max.y / step).ceil() as i64 {
+        let y = index as f64 * step;
+        let line = Contour::line(
+            to_screen(Point::new(min.x, y)),
+            to_screen(Point::new(max.x, y)),
+        );
+        let color = get_grid_color(index, cad_color_theme);
+        draw_locc(
+            buf,
+            &curves::Curve::Contour(line),
+            color,
+            1.0,
+            &mut span_buffer,
+            1,
+        );
+    }
 }
 
-impl std::fmt::Debug for CadView {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.base.fmt(f)
+/// Arrow in the corner of a turned view pointing where the top of the view without the
+/// rotation goes.
+fn draw_compass(
+    buf: &mut ImageViewMut<u32>,
+    view: &ViewTransform,
+    cad_color_theme: &CadColorTheme,
+) {
+    const RADIUS: f64 = 16.0;
+    let size = Point::new(buf.get_size().0 as f64, buf.get_size().1 as f64);
+    let center = size - Point::new(RADIUS * 2.0, RADIUS * 2.0);
+    let up = complex_mul(Point::new(0.0, -1.0), view.direction);
+    let tip = center + up.scale(RADIUS);
+    let mut span_buffer = vec![(0, 0); buf.get_size().1 * 2];
+    let mut draw = |curve: curves::Curve<f64>| {
+        let color = cad_color_theme.grid_font;
+        draw_locc(buf, &curve, color, 1.0, &mut span_buffer, 2);
+    };
+    draw(curves::Curve::Contour(Contour::circle(center, RADIUS)));
+    draw(curves::Curve::Segment(Segment::line(
+        center - up.scale(RADIUS),
+        tip,
+    )));
+    for side in [up.rot90(), up.rot90().neg()] {
+        let wing = tip - up.scale(RADIUS * 0.4) + side.scale(RADIUS * 0.3);
+        draw(curves::Curve::Segment(Segment::line(wing, tip)));
     }
 }
 
-impl CadView {
-    pub fn new(
-        size_constraints: SizeConstraints,
-        document: Rc<RefCell<Document>>,
-        picts: Rc<RefCell<Picts>>,
-        config: Rc<RefCell<Config>>,
-        font: Font,
-    ) -> Self {
-        Self {
-            base: GuiControlBase::new(size_constraints),
-            document,
-            picts,
-            config,
-            font,
+fn draw_isometric_grid(
+    buf: &mut ImageViewMut<u32>,
+    step: f64,
+    view: &ViewTransform,
+    cad_color_theme: &CadColorTheme,
+) {
+    let size = Point::new(buf.get_size().0 as f64, buf.get_size().1 as f64);
+    let get_color = |index: i64| get_grid_color(index, cad_color_theme);
+    let (left_top, right_bottom) = view.rect_to_document(size);
+    let (left, top) = (left_top.x, left_top.y);
+    let (right, bottom) = (right_bottom.x, right_bottom.y);
+    let mut span_buffer = vec![(0, 0); buf.get_size().1];
+
+    // vertical lines go through the columns of nodes
+    let column_step = step * 30f64.to_radians().cos();
+    for k in (left / column_step).floor() as i64..=(right / column_step).ceil() as i64 {
+        let x = k as f64 * column_step;
+        let color = get_color(k);
+        if view.is_turned() {
+            let line = Segment::line(
+                view.to_screen(Point::new(x, top)),
+                view.to_screen(Point::new(x, bottom)),
+            );
+            draw_locc(
+                buf,
+                &curves::Curve::Segment(line),
+                color,
+                1.0,
+                &mut span_buffer,
+                1,
+            );
+            continue;
+        }
+        let coord = view.to_screen(Point::new(x, 0.0)).x;
+        if coord >= 0.0 && coord < size.x {
+            for l in buf.lines_mut(..) {
+                l[coord as usize] = color;
+            }
         }
     }
 
-    pub fn screen_coord_to_document_coord(
-        &mut self,
-        position: Point<f64>,
-    ) -> (Point<f64>, Point<f64>) {
-        let rect = self.get_base_mut().get_rect();
-        let screen_center = Point::new(
-            (rect.right_bottom.0 as f64 + rect.left_top.0 as f64) * 0.5,
-            (rect.right_bottom.1 as f64) * 0.5,
-        );
-        let document = self.document.borrow();
-        let rel_position = position - screen_center;
-        (
-            rel_position,
-            rel_position.scale(1.0 / document.get_scale()) + document.get_center(),
-        )
+    // slanted lines are y -+ x * tan30 = index * step
+    let tan = 30f64.to_radians().tan();
+    for sign in [-1.0, 1.0] {
+        let values = [
+            top - sign * left * tan,
+            top - sign * right * tan,
+            bottom - sign * left * tan,
+            bottom - sign * right * tan,
+        ];
+        let min = values.iter().cloned().fold(f64::MAX, f64::min);
+        let max = values.iter().cloned().fold(f64::MIN, f64::max);
+        for index in (min / step).floor() as i64..=(max / step).ceil() as i64 {
+            let to_screen = |x: f64| {
+                let y = index as f64 * step + sign * x * tan;
+                view.to_screen(Point::new(x, y))
+            };
+            let line = curves::Curve::Segment(Segment::line(to_screen(left), to_screen(right)));
+            draw_locc(buf, &line, get_color(index), 1.0, &mut span_buffer, 1);
+        }
     }
 }
 
@@ -382,16 +2244,28 @@ impl GuiControl for CadView {
     }
 
     fn on_message(&mut self, m: GuiMessage) -> bool {
+        let document = self.document.clone();
+        let own_view = self.own_view.clone();
+        with_own_view(&document, &own_view, || self.handle_message(m))
+    }
+}
+
+impl CadView {
+    fn handle_message(&mut self, m: GuiMessage) -> bool {
         match m {
             GuiMessage::GetHotkeys(hotkey_map, active) => {
                 if active {
                     let document = self.document.clone();
+                    let own_view = self.own_view.clone();
                     let mut add_shifting_key = |hotkey: Hotkey, shift: Point<f64>| {
                         hotkey_map.insert(hotkey, HotkeyCallback::new(Rc::new(
-                        callback!([document]() {
-                            let mut document = document.borrow_mut();
-                            let new_center = document.get_center() + shift.scale(1.0 / document.get_scale());
-                            document.set_center(new_center);
+                        callback!([document, own_view]() {
+                            with_own_view(&document, &own_view, || {
+                                let mut document = document.borrow_mut();
+                                let view = ViewTransform::new(&document, Point::new(0.0, 0.0));
+                                let new_center = document.get_center() + view.vector_to_document(shift);
+                                document.set_center(new_center);
+                            });
                         }
                         ))));
                     };
@@ -408,29 +2282,78 @@ impl GuiControl for CadView {
                 return true;
             }
             GuiMessage::MouseWheel(position, delta) => {
-                let (rel_position, document_position) = self.screen_coord_to_document_coord(
-                    Point::new(position.0 as f64, position.1 as f64),
-                );
-                let mut document = self.document.borrow_mut();
-                document.change_scale(-delta * 10);
-                let new_center = document_position - rel_position.scale(1.0 / document.get_scale());
-                document.set_center(new_center);
+                self.zoom_by_wheel(position, delta);
+                return true;
+            }
+            GuiMessage::MouseWheelWithShift(position, delta) => {
+                // the center of the selection stays in place instead of the mouse position
+                let selection = self.document.borrow().get_extents(true);
+                let position = match selection {
+                    Some((min, max)) => self.document_coord_to_screen_coord((min + max).scale(0.5)),
+                    None => position,
+                };
+                self.zoom_by_wheel(position, delta);
+                return true;
+            }
+            GuiMessage::MiddleDoubleClick(_) => {
+                self.zoom_animation = None;
+                let view_size = self.get_view_size();
+                return zoom_to_extents(&mut self.document.borrow_mut(), false, view_size);
+            }
+            GuiMessage::Timer(_) => {
+                return self.update_zoom_animation() | self.update_interaction();
+            }
+            GuiMessage::MouseHWheel(_, delta) => {
+                self.shift_view(Point::new(delta as f64 * 40.0, 0.0));
+                return true;
+            }
+            GuiMessage::Zoom(position, factor) => {
+                // Document scale is stored as a power of 1.01
+                self.change_scale_at(position, (factor as f64).ln() / 1.01f64.ln());
+                return true;
+            }
+            GuiMessage::Pan(_, delta) => {
+                self.shift_view(Point::new(-delta.0 as f64, -delta.1 as f64));
                 return true;
             }
             GuiMessage::Draw(buf, theme, force) => {
+                let view_size = self.get_view_size();
+                self.view_size.set(view_size);
+                self.document.borrow_mut().fit_initial_view(view_size);
                 if self.base.can_draw(force) {
-                    GuiSystem::erase_background(buf, EmptySpaceState::Empty, theme);
                     let document = self.document.borrow();
                     let config = self.config.borrow();
+                    let canvas_style = document.get_canvas_style();
+                    match canvas_style.background {
+                        Some(background) => buf.fill(|p| *p = background),
+                        None => GuiSystem::erase_background(buf, EmptySpaceState::Empty, theme),
+                    }
                     let scale = document.get_scale();
                     let center = document.get_center();
                     let buf_f64 = Point::new(buf.get_size().0 as f64, buf.get_size().1 as f64);
                     let buf_center = (buf_f64
                         - Point::new(0.0, self.base.get_rect().left_top.1 as f64))
                     .scale(0.5);
-                    let cad_color_theme = get_cad_color_theme(&config);
-                    if config.show_grid {
+                    let view = ViewTransform::new(&document, buf_center);
+                    let cad_color_theme = &get_cad_color_theme(&config).with_style(canvas_style);
+                    if config.show_grid && config.isometric {
+                        draw_isometric_grid(buf, document.get_grid_step(), &view, cad_color_theme);
+                    } else if config.show_grid
+                        && (!document.get_ucs().is_axis_aligned() || view.is_turned())
+                    {
+                        draw_ucs_grid(
+                            buf,
+                            document.get_ucs(),
+                            document.get_grid_step(),
+                            &view,
+                            cad_color_theme,
+                        );
+                    } else if config.show_grid {
+                        // the values of the lines are counted from the UCS origin
+                        let grid_center = center - document.get_ucs().origin;
                         let grid_step = document.get_grid_step();
+                        // with the Y axis going up the rows go by the negated values
+                        let row_sign = if view.y_up { -1.0 } else { 1.0 };
                         fn each_grid_line(
                             step: f64,
                             scale: f64,
@@ -439,12 +2362,14 @@ impl GuiControl for CadView {
                             sz: f64,
                             mut f: impl FnMut(f64, usize, usize),
                         ) {
+                            // values are counted from a multiple of the major step, adding
+                            // the step repeatedly would lose it against large coordinates
                             let mut index = 0;
-                            let mut value = ((((-bc / scale) + c) / (step * 10.0)).floor() - 1.0)
-                                * (step * 10.0);
+                            let first =
+                                ((((-bc / scale) + c) / (step * 10.0)).floor() - 1.0) * 10.0;
                             loop {
-                                value += step;
                                 index += 1;
+                                let value = (first + index as f64) * step;
                                 let coord = (value - c) * scale + bc;
                                 if coord <= 0.0 {
                                     continue;
@@ -459,7 +2384,7 @@ impl GuiControl for CadView {
                         each_grid_line(
                             grid_step,
                             scale,
-                            center.x,
+                            grid_center.x,
                             buf_center.x,
                             buf_f64.x,
                             |_, coord, index| {
@@ -478,7 +2403,7 @@ impl GuiControl for CadView {
                         each_grid_line(
                             grid_step,
                             scale,
-                            center.y,
+                            grid_center.y * row_sign,
                             buf_center.y,
                             buf_f64.y,
                             |_, coord, index| {
@@ -494,99 +2419,307 @@ impl GuiControl for CadView {
                             },
                         );
 
+                        // labels which would overlap the previous one are skipped, the labels
+                        // of the rows start below the labels of the columns
+                        let label_height = self.font.get_size("8").1 as i32;
+                        let label_gap = label_height / 2;
+                        let mut labels_right = i32::MIN;
                         each_grid_line(
                             grid_step,
                             scale,
-                            center.x,
+                            grid_center.x,
                             buf_center.x,
                             buf_f64.x,
                             |value, coord, index| {
                                 if index % 10 == 0 {
+                                    let text = config.number_format.format(value);
+                                    let width = self.font.get_size(&text).0 as i32;
+                                    let left = coord as i32 - width / 2;
+                                    if left < labels_right + label_gap {
+                                        return;
+                                    }
+                                    labels_right = left + width;
                                     self.font
                                         .color(cad_color_theme.grid_font)
                                         .layout_vertical(TextLayoutVertical::TOP)
                                         .layout_horizontal(TextLayoutHorizontal::MIDDLE)
-                                        .draw(&format!("{value}"), (coord as i32, 0), buf);
+                                        .draw(&text, (coord as i32, 0), buf);
                                 }
                             },
                         );
 
+                        let mut labels_bottom = label_height;
                         each_grid_line(
                             grid_step,
                             scale,
-                            center.y,
+                            grid_center.y * row_sign,
                             buf_center.y,
                             buf_f64.y,
                             |value, coord, index| {
                                 if index % 10 == 0 {
+                                    let top = coord as i32 - label_height / 2;
+                                    if top < labels_bottom + label_gap {
+                                        return;
+                                    }
+                                    labels_bottom = top + label_height;
                                     self.font
                                         .color(cad_color_theme.grid_font)
                                         .layout_vertical(TextLayoutVertical::MIDDLE)
                                         .layout_horizontal(TextLayoutHorizontal::LEFT)
-                                        .draw(&format!("{value}"), (0, coord as i32), buf);
+                                        .draw(
+                                            &config.number_format.format(value * row_sign),
+                                            (0, coord as i32),
+                                            buf,
+                                        );
                                 }
                             },
                         );
                     }
                     let highlight_point = document.get_highlight_point();
                     let mut span_buffer = vec![(0, 0); buf.get_size().1 * 4];
-                    for (id, element) in document.get_content() {
-                        let curve = match element {
-                            Element::Curve(curve) => curve,
-                            _ => continue,
+                    let full_aa = match config.curves_aa_mode {
+                        CurvesAAMode::NoAntiAliasing => 1,
+                        CurvesAAMode::AntiAliasingX2 => 2,
+                        CurvesAAMode::AntiAliasingX4 => 4,
+                    };
+                    let aa = match self.last_interaction {
+                        Some(_) => get_interactive_aa(full_aa, document.get_entity_count()),
+                        None => full_aa,
+                    };
+                    self.reduced_aa = aa < full_aa;
+                    if let Some(sheet) = document.get_sheet() {
+                        for segment in sheet.get_segments() {
+                            let l = view.curve_to_screen(curves::Curve::Segment(segment));
+                            draw_locc(
+                                buf,
+                                &l,
+                                cad_color_theme.sheet_color,
+                                1.0,
+                                &mut span_buffer,
+                                aa,
+                            );
+                        }
+
+                        let viewport_line_color = if aa == 1 {
+                            cad_color_theme.line_color
+                        } else {
+                            cad_color_theme.line_aa_color
                         };
+                        for viewport in &sheet.viewports {
+                            // a turned frame clips by the rectangle around it
+                            let (s1, s2) = view.rect_to_screen(sheet.get_viewport_rect(viewport));
+                            let left = s1.x.min(s2.x).max(0.0) as usize;
+                            let top = s1.y.min(s2.y).max(0.0) as usize;
+                            let right = (s1.x.max(s2.x).max(0.0) as usize).min(buf.get_size().0);
+                            let bottom = (s1.y.max(s2.y).max(0.0) as usize).min(buf.get_size().1);
+                            if left >= right || top >= bottom {
+                                continue;
+                            }
 
-                        let mut l = curve.curve;
-                        l = l.translate(center.neg());
-                        l = l.scale(scale);
-                        l = l.translate(buf_center);
+                            // drawing into the window of the buffer clips by the frame
+                            let mut window = buf.window_mut((left, top), (right, bottom));
+                            let window_shift = Point::new(left as f64, top as f64);
+                            for (_, curve) in document.get_shapes_in_draw_order() {
+                                let l = view
+                                    .curve_to_screen(sheet.to_viewport(viewport, curve.curve))
+                                    .translate(window_shift.neg());
+                                let width = get_screen_width(
+                                    &config,
+                                    curve.width,
+                                    scale * sheet.get_viewport_factor(viewport),
+                                );
+                                draw_locc_blended(
+                                    &mut window,
+                                    &l,
+                                    viewport_line_color,
+                                    curve.opacity.get_alpha(),
+                                    width,
+                                    &mut span_buffer,
+                                    aa,
+                                );
+                            }
+                        }
 
-                        let width: f64 = if curve.selected { 3.0 } else { 1.0 };
-                        let mut highlight = document.is_highlight(*id);
+                        // the font has fixed size, so the title block is filled only when it fits
+                        let font_height = self.font.get_size("8").1 as f64;
+                        for text in sheet.get_texts(document.get_metadata()) {
+                            if text.height * scale < font_height {
+                                break;
+                            }
+                            let position = view.to_screen(text.position);
+                            self.font
+                                .color(cad_color_theme.sheet_color)
+                                .layout_vertical(TextLayoutVertical::MIDDLE)
+                                .layout_horizontal(TextLayoutHorizontal::LEFT)
+                                .draw(&text.text, (position.x as i32, position.y as i32), buf);
+                        }
+                    }
+                    let to_buf = |curve: curves::Curve<f64>| view.curve_to_screen(curve);
+                    // the referenced drawings are dimmed and can't be picked
+                    let xref_color = if aa == 1 {
+                        cad_color_theme.line_color
+                    } else {
+                        cad_color_theme.line_aa_color
+                    };
+                    for curve in document.get_xref_curves() {
+                        draw_locc_blended(
+                            buf,
+                            &to_buf(*curve),
+                            xref_color,
+                            XREF_ALPHA,
+                            get_screen_width(&config, None, scale),
+                            &mut span_buffer,
+                            aa,
+                        );
+                    }
+                    if config.selection_style == SelectionStyle::Halo {
+                        // halos go under all curves, so they don't cover the neighbours
+                        for (_, curve) in document.get_shapes_in_draw_order() {
+                            if curve.selected {
+                                draw_locc(
+                                    buf,
+                                    &to_buf(curve.curve),
+                                    cad_color_theme.selection_halo_color,
+                                    get_screen_width(&config, curve.width, scale) + 6.0,
+                                    &mut span_buffer,
+                                    aa,
+                                );
+                            }
+                        }
+                    }
+                    for (id, curve) in document.get_shapes_in_draw_order() {
+                        let l = to_buf(curve.curve);
+                        let mut width = get_screen_width(&config, curve.width, scale);
+                        let mut color = match self.get_difference_color(id, cad_color_theme) {
+                            Some(color) => color,
+                            None if aa == 1 => cad_color_theme.line_color,
+                            None => cad_color_theme.line_aa_color,
+                        };
+                        if curve.selected {
+                            match config.selection_style {
+                                SelectionStyle::Thick => width += 2.0,
+                                SelectionStyle::Color => {
+                                    color = cad_color_theme.selected_line_color
+                                }
+                                SelectionStyle::Dashed | SelectionStyle::Halo => {}
+                            }
+                        }
+                        let mut opacity = curve.opacity.get_alpha();
+                        let mut highlight = document.is_highlight(id);
                         if let HighlightPointKind::Center(center_arc_id) = highlight_point.kind {
-                            if center_arc_id == *id {
+                            if center_arc_id == id {
                                 highlight = true;
                             }
                         }
+                        if highlight && config.hover_style != HoverStyle::Off {
+                            color = if aa == 1 {
+                                cad_color_theme.highlight_line_color
+                            } else {
+                                cad_color_theme.highlight_line_aa_color
+                            };
+                            if config.hover_style == HoverStyle::ThickColor
+                                || cad_color_theme.state_by_shape
+                            {
+                                width += 2.0;
+                            }
+                            // the faint curve under the mouse is shown as it is picked
+                            opacity = u8::MAX;
+                        }
 
-                        match config.curves_aa_mode {
-                            CurvesAAMode::NoAntiAliasing => draw_locc(
-                                buf,
-                                &l,
-                                if highlight {
-                                    cad_color_theme.highlight_line_color
-                                } else {
-                                    cad_color_theme.line_color
-                                },
-                                width,
-                                &mut span_buffer,
-                                1,
-                            ),
-                            CurvesAAMode::AntiAliasingX2 => draw_locc(
-                                buf,
-                                &l,
-                                if highlight {
-                                    cad_color_theme.highlight_line_aa_color
-                                } else {
-                                    cad_color_theme.line_aa_color
-                                },
-                                width,
-                                &mut span_buffer,
-                                2,
-                            ),
-                            CurvesAAMode::AntiAliasingX4 => draw_locc(
+                        draw_locc_blended(buf, &l, color, opacity, width, &mut span_buffer, aa);
+                        let dashed = match config.selection_style {
+                            SelectionStyle::Dashed => true,
+                            SelectionStyle::Color => cad_color_theme.state_by_shape,
+                            SelectionStyle::Thick | SelectionStyle::Halo => false,
+                        };
+                        if curve.selected && dashed {
+                            for dash in get_selection_dashes(&l, buf_f64) {
+                                draw_locc(
+                                    buf,
+                                    &dash,
+                                    cad_color_theme.selected_line_color,
+                                    width,
+                                    &mut span_buffer,
+                                    aa,
+                                );
+                            }
+                        }
+                    }
+
+                    let point_radius = 4.0;
+                    for (id, element) in document.get_content() {
+                        let point = match element {
+                            Element::Point(point) if !document.is_hidden(*id) => point,
+                            _ => continue,
+                        };
+
+                        let position = view.to_screen(point.position);
+                        if position.x < -point_radius
+                            || position.y < -point_radius
+                            || position.x > buf_f64.x + point_radius
+                            || position.y > buf_f64.y + point_radius
+                        {
+                            continue;
+                        }
+
+                        let difference_color = self.get_difference_color(*id, cad_color_theme);
+                        let highlight =
+                            document.is_highlight(*id) && config.hover_style != HoverStyle::Off;
+                        let selected_color =
+                            point.selected && config.selection_style != SelectionStyle::Thick;
+                        let color = match (highlight, selected_color, difference_color, aa) {
+                            (true, _, _, 1) => cad_color_theme.highlight_line_color,
+                            (true, _, _, _) => cad_color_theme.highlight_line_aa_color,
+                            (false, true, _, _) => cad_color_theme.selected_line_color,
+                            (false, false, Some(color), _) => color,
+                            (false, false, None, 1) => cad_color_theme.line_color,
+                            (false, false, None, _) => cad_color_theme.line_aa_color,
+                        };
+                        let width: f64 = if point.selected { 3.0 } else { 1.0 };
+                        let marks = match config.point_style {
+                            PointStyle::Dot => vec![curves::Curve::Contour(Contour::circle(
+                                position,
+                                width * 0.5,
+                            ))],
+                            PointStyle::Cross => vec![
+                                curves::Curve::Segment(Segment::line(
+                                    position - Point::new(point_radius, 0.0),
+                                    position + Point::new(point_radius, 0.0),
+                                )),
+                                curves::Curve::Segment(Segment::line(
+                                    position - Point::new(0.0, point_radius),
+                                    position + Point::new(0.0, point_radius),
+                                )),
+                            ],
+                            PointStyle::Circle => vec![curves::Curve::Contour(Contour::circle(
+                                position,
+                                point_radius,
+                            ))],
+                        };
+                        let width = if config.point_style == PointStyle::Dot {
+                            width + 2.0
+                        } else {
+                            width
+                        };
+                        for mark in &marks {
+                            draw_locc(buf, mark, color, width, &mut span_buffer, aa);
+                        }
+                    }
+
+                    // the ends are marked with the pictures below
+                    let witness_line = document.get_witness_line();
+                    if let Some((from, to)) = witness_line {
+                        if (to - from).length() > 0.0 {
+                            let line = to_buf(curves::Curve::Segment(Segment::line(from, to)));
+                            draw_locc(
                                 buf,
-                                &l,
-                                if highlight {
-                                    cad_color_theme.highlight_line_aa_color
-                                } else {
-                                    cad_color_theme.line_aa_color
-                                },
-                                width,
+                                &line,
+                                cad_color_theme.highlight_line_aa_color,
+                                1.0,
                                 &mut span_buffer,
-                                4,
-                            ),
-                        };
+                                aa,
+                            );
+                        }
                     }
 
                     let mut draw_pic = |position: Point<f64>, pic: &ImageView<bool>| {
@@ -612,31 +2745,274 @@ impl GuiControl for CadView {
                     };
                     match highlight_point.kind {
                         HighlightPointKind::Grid => {
-                            let pic_center =
-                                (highlight_point.position - center).scale(scale) + buf_center;
+                            let pic_center = view.to_screen(highlight_point.position);
                             draw_pic(pic_center, &self.picts.borrow().grid_point.as_view());
                         }
                         HighlightPointKind::End => {
-                            let pic_center =
-                                (highlight_point.position - center).scale(scale) + buf_center;
+                            let pic_center = view.to_screen(highlight_point.position);
                             draw_pic(pic_center, &self.picts.borrow().end_point.as_view());
                         }
+                        HighlightPointKind::Middle => {
+                            let pic_center = view.to_screen(highlight_point.position);
+                            draw_pic(pic_center, &self.picts.borrow().middle_point.as_view());
+                        }
+                        HighlightPointKind::Quadrant => {
+                            let pic_center = view.to_screen(highlight_point.position);
+                            draw_pic(pic_center, &self.picts.borrow().quadrant_point.as_view());
+                        }
                         HighlightPointKind::Center(_) => {
-                            let pic_center =
-                                (highlight_point.position - center).scale(scale) + buf_center;
+                            let pic_center = view.to_screen(highlight_point.position);
                             draw_pic(pic_center, &self.picts.borrow().center_point.as_view());
                         }
                         HighlightPointKind::Cross => {
-                            let pic_center =
-                                (highlight_point.position - center).scale(scale) + buf_center;
+                            let pic_center = view.to_screen(highlight_point.position);
                             draw_pic(pic_center, &self.picts.borrow().cross_point.as_view());
                         }
                         _ => {}
                     }
 
-                    if let Some((c1, c2)) = document.get_selection_rectangle() {
-                        let c1 = (c1 - center).scale(scale) + buf_center;
-                        let c2 = (c2 - center).scale(scale) + buf_center;
+                    if let Some((from, to)) = witness_line {
+                        for position in [from, to] {
+                            let pic_center = view.to_screen(position);
+                            draw_pic(pic_center, &self.picts.borrow().end_point.as_view());
+                        }
+                    }
+
+                    let point_picker = self.point_picker.borrow();
+                    // the crosshair shows the point which a click gives
+                    let mut crosshair = self
+                        .cursor_position
+                        .get()
+                        .map(|cursor| document.get_snapped_position(cursor));
+                    let mut hint = document.get_pick_cycle().map(|(index, count)| {
+                        format!(
+                            "{} из {}, щёлкните ещё раз для следующего",
+                            index + 1,
+                            count
+                        )
+                    });
+                    if let (true, Some(cursor)) =
+                        (point_picker.is_active(), self.cursor_position.get())
+                    {
+                        let pick = &point_picker.pick;
+                        let guides = point_picker.get_smart_guides(&document, cursor, &config);
+                        let to = match &guides {
+                            Some(guides) => guides.position,
+                            None => point_picker.get_tracked_point(&document, cursor, &config),
+                        };
+                        crosshair = Some(to);
+                        for (a, b) in guides.iter().flat_map(|guides| &guides.lines) {
+                            if (*b - *a).length() == 0.0 {
+                                continue;
+                            }
+                            let line = to_buf(curves::Curve::Segment(Segment::line(*a, *b)));
+                            for dash in get_selection_dashes(&line, buf_f64) {
+                                draw_locc(
+                                    buf,
+                                    &dash,
+                                    cad_color_theme.pic_color,
+                                    1.0,
+                                    &mut span_buffer,
+                                    aa,
+                                );
+                            }
+                        }
+                        hint = Some(pick.prompt.clone());
+                        if let Some(preview) = &pick.preview {
+                            let (transform, text) = preview(to);
+                            let geometry = match &pick.geometry {
+                                Some(geometry) => geometry.clone(),
+                                None => document
+                                    .get_shapes_in_draw_order()
+                                    .into_iter()
+                                    .filter(|(_, curve)| curve.selected)
+                                    .map(|(_, curve)| curve.curve)
+                                    .collect(),
+                            };
+                            for curve in geometry {
+                                draw_locc(
+                                    buf,
+                                    &to_buf(transform.apply_to_curve(curve)),
+                                    cad_color_theme.highlight_line_aa_color,
+                                    1.0,
+                                    &mut span_buffer,
+                                    aa,
+                                );
+                            }
+                            if !text.is_empty() {
+                                hint = Some(format!("{} {}", pick.prompt, text));
+                            }
+                        }
+                        if let Some(from) = pick.from {
+                            if (to - from).length() > 0.0 {
+                                let line = to_buf(curves::Curve::Segment(Segment::line(from, to)));
+                                draw_locc(
+                                    buf,
+                                    &line,
+                                    cad_color_theme.highlight_line_aa_color,
+                                    1.0,
+                                    &mut span_buffer,
+                                    aa,
+                                );
+                            }
+                        }
+                    }
+                    drop(point_picker);
+
+                    // the box around the selection with the handles to turn and scale it
+                    if let Some(bounds) = self.get_gizmo_bounds(&document) {
+                        let screen_corners =
+                            get_box_corners(bounds).map(|corner| view.to_screen(corner));
+                        let mut gizmo_lines = Vec::new();
+                        for (index, corner) in screen_corners.iter().enumerate() {
+                            let next = screen_corners[(index + 1) % screen_corners.len()];
+                            let side = curves::Curve::Segment(Segment::line(*corner, next));
+                            gizmo_lines.extend(get_selection_dashes(&side, buf_f64));
+                        }
+                        for (handle, position) in get_handle_positions(&screen_corners) {
+                            match handle {
+                                GizmoHandle::Rotate => gizmo_lines.push(curves::Curve::Contour(
+                                    Contour::circle(position, HANDLE_RADIUS),
+                                )),
+                                GizmoHandle::Scale(_) => {
+                                    let corners = get_box_corners((
+                                        position - Point::new(HANDLE_RADIUS, HANDLE_RADIUS),
+                                        position + Point::new(HANDLE_RADIUS, HANDLE_RADIUS),
+                                    ));
+                                    for (index, corner) in corners.iter().enumerate() {
+                                        let next = corners[(index + 1) % corners.len()];
+                                        gizmo_lines.push(curves::Curve::Segment(Segment::line(
+                                            *corner, next,
+                                        )));
+                                    }
+                                }
+                            }
+                        }
+                        for line in gizmo_lines {
+                            draw_locc(
+                                buf,
+                                &line,
+                                cad_color_theme.pic_color,
+                                1.0,
+                                &mut span_buffer,
+                                aa,
+                            );
+                        }
+                    }
+                    let gizmo_command = match (&self.gizmo_drag, self.cursor_position.get()) {
+                        (Some(drag), Some(cursor)) => self.get_gizmo_command(drag, cursor),
+                        _ => None,
+                    };
+                    if let Some(Command::Transform(caption, transform)) = gizmo_command {
+                        for (_, curve) in document.get_shapes_in_draw_order() {
+                            if !curve.selected {
+                                continue;
+                            }
+                            draw_locc(
+                                buf,
+                                &to_buf(transform.apply_to_curve(curve.curve)),
+                                cad_color_theme.highlight_line_aa_color,
+                                1.0,
+                                &mut span_buffer,
+                                aa,
+                            );
+                        }
+                        hint = Some(caption);
+                    }
+
+                    if let (Some(point), true) =
+                        (crosshair, config.crosshair != CrosshairStyle::Off)
+                    {
+                        let position = view.to_screen(point);
+                        let lines = [
+                            Segment::line(
+                                Point::new(0.0, position.y),
+                                Point::new(buf_f64.x, position.y),
+                            ),
+                            Segment::line(
+                                Point::new(position.x, 0.0),
+                                Point::new(position.x, buf_f64.y),
+                            ),
+                        ];
+                        for line in lines {
+                            let line = curves::Curve::Segment(line);
+                            let dashes = match config.crosshair {
+                                CrosshairStyle::Dashed => get_selection_dashes(&line, buf_f64),
+                                _ => vec![line],
+                            };
+                            for dash in dashes {
+                                draw_locc(
+                                    buf,
+                                    &dash,
+                                    cad_color_theme.highlight_line_aa_color,
+                                    1.0,
+                                    &mut span_buffer,
+                                    1,
+                                );
+                            }
+                        }
+                        // the coordinates go above the cursor, the hint is below it
+                        let local = document.get_ucs().to_local(point);
+                        let font_height = self.font.get_size("8").1 as i32;
+                        self.font
+                            .color(cad_color_theme.grid_font)
+                            .layout_vertical(TextLayoutVertical::BOTTOM)
+                            .layout_horizontal(TextLayoutHorizontal::LEFT)
+                            .draw(
+                                &format!(
+                                    "{}; {}",
+                                    config.number_format.format(local.x),
+                                    config.number_format.format(local.y)
+                                ),
+                                (
+                                    position.x as i32 + font_height / 2,
+                                    position.y as i32 - font_height / 2,
+                                ),
+                                buf,
+                            );
+                    }
+
+                    // the hint goes below the cursor, like the tooltips of the controls
+                    if let (Some(hint), Some(cursor)) = (hint, self.cursor_position.get()) {
+                        let position = view.to_screen(cursor);
+                        let font_height = self.font.get_size("8").1 as i32;
+                        self.font
+                            .color(cad_color_theme.grid_font)
+                            .layout_vertical(TextLayoutVertical::TOP)
+                            .layout_horizontal(TextLayoutHorizontal::LEFT)
+                            .draw(
+                                &hint,
+                                (
+                                    position.x as i32 + font_height / 2,
+                                    position.y as i32 + font_height,
+                                ),
+                                buf,
+                            );
+                    }
+
+                    let rubber_band = self
+                        .rect_picker
+                        .borrow()
+                        .corners
+                        .or_else(|| document.get_selection_rectangle());
+                    if let (Some((c1, c2)), true) = (rubber_band, view.is_turned()) {
+                        // the picked area is a rectangle of the document, turned on the screen
+                        let corners = [c1, Point::new(c2.x, c1.y), c2, Point::new(c1.x, c2.y)];
+                        for (index, corner) in corners.iter().enumerate() {
+                            let next = corners[(index + 1) % corners.len()];
+                            let side = Segment::line(view.to_screen(*corner), view.to_screen(next));
+                            draw_locc(
+                                buf,
+                                &curves::Curve::Segment(side),
+                                cad_color_theme.selection_bevel_color,
+                                1.0,
+                                &mut span_buffer,
+                                1,
+                            );
+                        }
+                    } else if let Some((c1, c2)) = rubber_band {
+                        let c1 = view.to_screen(c1);
+                        let c2 = view.to_screen(c2);
 
                         let bounded1 = Point::new(
                             f64::max(-1.0, f64::min(c1.x, c2.x)),
@@ -697,6 +3073,10 @@ impl GuiControl for CadView {
                             }
                         }
                     }
+
+                    if view.is_turned() {
+                        draw_compass(buf, &view, cad_color_theme);
+                    }
                 }
 
                 return true;
@@ -707,17 +3087,84 @@ impl GuiControl for CadView {
                     position.1 as f64,
                 ));
 
+                let mut rect_picker = self.rect_picker.borrow_mut();
+                if rect_picker.is_active() {
+                    rect_picker.corners = Some((document_position, document_position));
+                    return true;
+                }
+                drop(rect_picker);
+
+                let mut point_picker = self.point_picker.borrow_mut();
+                if point_picker.is_active() {
+                    point_picker.pressed = true;
+                    return true;
+                }
+                drop(point_picker);
+
+                let bounds = self.get_gizmo_bounds(&self.document.borrow());
+                if let Some(bounds) = bounds {
+                    let view = self.get_view_transform();
+                    let screen_corners =
+                        get_box_corners(bounds).map(|corner| view.to_screen(corner));
+                    let screen_position = Point::new(position.0 as f64, position.1 as f64);
+                    if let Some(handle) = find_handle(&screen_corners, screen_position) {
+                        self.gizmo_drag = Some(GizmoDrag {
+                            handle,
+                            bounds,
+                            start: document_position,
+                        });
+                        return true;
+                    }
+                }
+
                 let mut document = self.document.borrow_mut();
-                document.l_button_down(document_position);
+                document.l_button_down(document_position, &self.config.borrow());
 
                 return true;
             }
-            GuiMessage::MouseUp(position, _) => {
+            GuiMessage::MouseUp(position, job_system) => {
                 let (_, document_position) = self.screen_coord_to_document_coord(Point::new(
                     position.0 as f64,
                     position.1 as f64,
                 ));
 
+                let mut rect_picker = self.rect_picker.borrow_mut();
+                if let Some((corner1, _)) = rect_picker.corners {
+                    if let Some(callback) = rect_picker.callback.clone() {
+                        job_system
+                            .add_callback(Rc::new(move || callback(corner1, document_position)));
+                    }
+                    rect_picker.cancel();
+                    return true;
+                }
+                drop(rect_picker);
+
+                let mut point_picker = self.point_picker.borrow_mut();
+                if point_picker.pressed {
+                    // the callback may start picking the next point
+                    if let Some(callback) = point_picker.callback.clone() {
+                        let point = point_picker.get_point(
+                            &self.document.borrow(),
+                            document_position,
+                            &self.config.borrow(),
+                        );
+                        job_system.add_callback(Rc::new(move || callback(point)));
+                    }
+                    point_picker.cancel();
+                    return true;
+                }
+                drop(point_picker);
+
+                if let Some(drag) = self.gizmo_drag.take() {
+                    // the same command as typed in the transform menu, so it can be repeated
+                    if let Some(command) = self.get_gizmo_command(&drag, document_position) {
+                        if command.run(&mut self.document.borrow_mut()) {
+                            self.command_history.borrow_mut().push(command);
+                        }
+                    }
+                    return true;
+                }
+
                 let mut document = self.document.borrow_mut();
                 document.l_button_up(document_position);
 
@@ -729,8 +3176,87 @@ impl GuiControl for CadView {
                     position.1 as f64,
                 ));
 
+                self.cursor_position.set(Some(document_position));
+                let ucs_position = self.document.borrow().get_ucs().to_local(document_position);
+                self.cursor_ucs_position.set(Some(ucs_position));
+                if let Some((_, corner2)) = &mut self.rect_picker.borrow_mut().corners {
+                    *corner2 = document_position;
+                    self.last_interaction = Some(Instant::now());
+                    return true;
+                }
+                if self.gizmo_drag.is_some() {
+                    // the preview of the dragged handle follows the mouse
+                    self.last_interaction = Some(Instant::now());
+                    return true;
+                }
+                let mut document = self.document.borrow_mut();
+                let config = self.config.borrow();
+                let changed = document.mouse_move(document_position, &config);
+                if document.is_dragging() {
+                    // the selection is recalculated and redrawn with every move
+                    self.last_interaction = Some(Instant::now());
+                }
+                // the rubber line and the crosshair follow the mouse
+                return changed
+                    || self.point_picker.borrow().is_active()
+                    || config.crosshair != CrosshairStyle::Off;
+            }
+            GuiMessage::Hotkey(hotkey, _) if !hotkey.ctrl && !hotkey.alt => {
+                let key_callback = self
+                    .point_picker
+                    .borrow()
+                    .pick
+                    .keys
+                    .iter()
+                    .find(|(key, _)| *key == hotkey.key)
+                    .map(|(_, callback)| callback.clone());
+                if let Some(callback) = key_callback {
+                    callback();
+                    return true;
+                }
+                if matches!(hotkey.key, Key::Enter | Key::Space) && !hotkey.shift {
+                    // without a tool asking for points the last command is done again
+                    if self.point_picker.borrow().is_active()
+                        || self.rect_picker.borrow().is_active()
+                    {
+                        return false;
+                    }
+                    let command = self.command_history.borrow().get_last().cloned();
+                    let Some(command) = command else {
+                        return false;
+                    };
+                    if !command.run(&mut self.document.borrow_mut()) {
+                        return false;
+                    }
+                    // a macro being recorded gets the repeated command too
+                    self.command_history.borrow_mut().push(command);
+                    return true;
+                }
+                let direction = match hotkey.key {
+                    Key::Left => Point::new(-1.0, 0.0),
+                    Key::Right => Point::new(1.0, 0.0),
+                    Key::Up => Point::new(0.0, -1.0),
+                    Key::Down => Point::new(0.0, 1.0),
+                    _ => return false,
+                };
+                // the grid step, or a tenth of it with Shift
                 let mut document = self.document.borrow_mut();
-                return document.mouse_move(document_position, &self.config.borrow());
+                if !document.has_selection() {
+                    return false;
+                }
+                let mut step = document.get_grid_step();
+                if hotkey.shift {
+                    step *= 0.1;
+                }
+                // the arrows go along the screen
+                let direction = if document.is_y_up() {
+                    Point::new(direction.x, -direction.y)
+                } else {
+                    direction
+                };
+                let shift = document.get_ucs().vector_to_world(direction.scale(step));
+                document.nudge_selected(shift);
+                return true;
             }
             _ => return false,
         }
@@ -0,0 +1,185 @@
+use std::f64::consts::{FRAC_PI_2, TAU};
+
+use curves::points::{complex_mul, cross, dot};
+use serde::{Deserialize, Serialize};
+
+use crate::transform::*;
+
+type Point = curves::points::Point<f64>;
+type Contour = curves::Contour<f64>;
+type Segment = curves::Segment<f64>;
+
+/// The arcs go closer to the ellipse than this part of its major radius.
+const RELATIVE_TOLERANCE: f64 = 1.0e-4;
+/// Halvings of a quarter of the ellipse, the flattest ellipses stop there.
+const MAX_DEPTH: u32 = 10;
+/// Shorter axes give no ellipse.
+const EPS: f64 = 1.0e-9;
+
+/// Elliptical arc: the center, the half of the major axis and the ratio of the minor axis
+/// to the major one. The angles are the parameters of the points counterclockwise from the
+/// major axis, the arc goes from the start angle to the end one, the full ellipse goes
+/// from zero to tau.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq)]
+pub struct Ellipse {
+    pub center: Point,
+    pub major: Point,
+    pub ratio: f64,
+    pub start_angle: f64,
+    pub end_angle: f64,
+}
+
+impl Ellipse {
+    /// The other axis is given by its end or by any point of its side, so the ellipse is
+    /// the same for the end of the axis and for a point picked along it. The longer axis
+    /// becomes the major one. None if an axis has no length.
+    pub fn from_axis(center: Point, axis_end: Point, other: Point) -> Option<Self> {
+        let axis = axis_end - center;
+        if axis.length() < EPS {
+            return None;
+        }
+        let other_length = cross(axis.normalize(), other - center).abs();
+        if other_length < EPS {
+            return None;
+        }
+        let (major, ratio) = if other_length > axis.length() {
+            (
+                axis.normalize().rot90().scale(other_length),
+                axis.length() / other_length,
+            )
+        } else {
+            (axis, other_length / axis.length())
+        };
+        Some(Self {
+            center,
+            major,
+            ratio,
+            start_angle: 0.0,
+            end_angle: TAU,
+        })
+    }
+
+    /// The axis goes between the two points, the center is in its middle.
+    pub fn from_axis_ends(axis_begin: Point, axis_end: Point, other: Point) -> Option<Self> {
+        Self::from_axis((axis_begin + axis_end).scale(0.5), axis_end, other)
+    }
+
+    fn get_minor(&self) -> Point {
+        self.major.rot90().scale(self.ratio)
+    }
+
+    fn get_sweep(&self) -> f64 {
+        (self.end_angle - self.start_angle).clamp(0.0, TAU)
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.get_sweep() >= TAU - EPS
+    }
+
+    pub fn point_at(&self, angle: f64) -> Point {
+        self.center + self.major.scale(angle.cos()) + self.get_minor().scale(angle.sin())
+    }
+
+    /// Ends of an elliptical arc, the full ellipse has none.
+    pub fn get_ends(&self) -> Option<(Point, Point)> {
+        if self.is_closed() {
+            return None;
+        }
+        Some((
+            self.point_at(self.start_angle),
+            self.point_at(self.end_angle),
+        ))
+    }
+
+    /// Ends of the axes which lie on the arc.
+    pub fn get_quadrant_points(&self) -> Vec<Point> {
+        let sweep = self.get_sweep();
+        (0..4)
+            .map(|index| index as f64 * FRAC_PI_2)
+            .filter(|angle| (angle - self.start_angle).rem_euclid(TAU) <= sweep)
+            .map(|angle| self.point_at(angle))
+            .collect()
+    }
+
+    /// Transforms keep the shape: they only turn, scale and move it.
+    pub fn transformed(&self, transform: &Transform) -> Self {
+        Self {
+            center: transform.apply(self.center),
+            major: complex_mul(self.major, transform.rotation).scale(transform.factor),
+            ..*self
+        }
+    }
+
+    /// Arcs going along the ellipse counterclockwise, every one goes through three points
+    /// of the ellipse. The parts are halved until the arcs are close enough to it.
+    pub fn get_arcs(&self) -> Vec<Segment> {
+        let sweep = self.get_sweep();
+        if sweep <= 0.0 || self.major.length() < EPS {
+            return Vec::new();
+        }
+        let tolerance = self.major.length() * RELATIVE_TOLERANCE;
+        let parts = (sweep / FRAC_PI_2).ceil().max(1.0) as usize;
+        let step = sweep / parts as f64;
+        let mut result = Vec::new();
+        for part in 0..parts {
+            let begin = self.start_angle + step * part as f64;
+            self.approximate(begin, begin + step, tolerance, 0, &mut result);
+        }
+        result
+    }
+
+    fn approximate(
+        &self,
+        begin: f64,
+        end: f64,
+        tolerance: f64,
+        depth: u32,
+        result: &mut Vec<Segment>,
+    ) {
+        let arc = arc_through(
+            self.point_at(begin),
+            self.point_at((begin + end) * 0.5),
+            self.point_at(end),
+        );
+        // the error is the biggest between the points the arc goes through
+        let error = [0.25, 0.75]
+            .iter()
+            .map(|t| {
+                let point = self.point_at(begin + (end - begin) * t);
+                arc.contour.distance(point).abs()
+            })
+            .fold(0.0, f64::max);
+        if error <= tolerance || depth >= MAX_DEPTH {
+            result.push(arc);
+            return;
+        }
+        let middle = (begin + end) * 0.5;
+        self.approximate(begin, middle, tolerance, depth + 1, result);
+        self.approximate(middle, end, tolerance, depth + 1, result);
+    }
+}
+
+/// Arc from the begin to the end through the middle, a line if the points are on one.
+fn arc_through(begin: Point, middle: Point, end: Point) -> Segment {
+    let b = middle - begin;
+    let c = end - begin;
+    let d = 2.0 * cross(b, c);
+    if d.abs() < EPS * EPS {
+        return Segment::line(begin, end);
+    }
+    let center = begin
+        + Point::new(
+            c.y * dot(b, b) - b.y * dot(c, c),
+            b.x * dot(c, c) - c.x * dot(b, b),
+        )
+        .scale(1.0 / d);
+    let circle = Contour::circle(center, (begin - center).length());
+    Segment {
+        // the clockwise arcs have the negative radius
+        contour: if d > 0.0 { circle } else { circle.neg() },
+        begin,
+        end,
+        // more than a half of the circle if the center is on the side of the middle
+        big: cross(c, center - begin) * d > 0.0,
+    }
+}
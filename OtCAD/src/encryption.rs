@@ -0,0 +1,81 @@
+use argon2::Argon2;
+use chacha20poly1305::aead::Aead;
+use chacha20poly1305::{KeyInit, XChaCha20Poly1305, XNonce};
+use rand::RngCore;
+
+/// Encrypted drawings start with it instead of the JSON text.
+const MAGIC: &[u8] = b"OtCAD encrypted 1\n";
+const SALT_SIZE: usize = 16;
+const NONCE_SIZE: usize = 24;
+
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+/// The key is derived from the password by Argon2, every write gets a new salt and nonce.
+pub fn encrypt(text: &str, password: &str) -> Result<Vec<u8>, String> {
+    let mut salt = [0u8; SALT_SIZE];
+    let mut nonce = [0u8; NONCE_SIZE];
+    rand::thread_rng().fill_bytes(&mut salt);
+    rand::thread_rng().fill_bytes(&mut nonce);
+    let cipher = get_cipher(password, &salt)?;
+    let encrypted = cipher
+        .encrypt(XNonce::from_slice(&nonce), text.as_bytes())
+        .map_err(|_| "Не удалось зашифровать чертёж".to_string())?;
+
+    let mut result = MAGIC.to_vec();
+    result.extend_from_slice(&salt);
+    result.extend_from_slice(&nonce);
+    result.extend_from_slice(&encrypted);
+    Ok(result)
+}
+
+/// A wrong password and a damaged file can't be told apart, the error says about both.
+pub fn decrypt(data: &[u8], password: &str) -> Result<String, String> {
+    let data = data
+        .strip_prefix(MAGIC)
+        .ok_or_else(|| "Чертёж не зашифрован".to_string())?;
+    if data.len() < SALT_SIZE + NONCE_SIZE {
+        return Err("Файл повреждён".to_string());
+    }
+    let (salt, data) = data.split_at(SALT_SIZE);
+    let (nonce, encrypted) = data.split_at(NONCE_SIZE);
+    let cipher = get_cipher(password, salt)?;
+    let text = cipher
+        .decrypt(XNonce::from_slice(nonce), encrypted)
+        .map_err(|_| "Неверный пароль или файл повреждён".to_string())?;
+    String::from_utf8(text).map_err(|e| e.to_string())
+}
+
+fn get_cipher(password: &str, salt: &[u8]) -> Result<XChaCha20Poly1305, String> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(password.as_bytes(), salt, &mut key)
+        .map_err(|e| e.to_string())?;
+    Ok(XChaCha20Poly1305::new(&key.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let text = "{\"content\": []}";
+        let data = encrypt(text, "пароль").unwrap();
+        assert!(is_encrypted(&data));
+        assert!(!data.windows(text.len()).any(|w| w == text.as_bytes()));
+        assert_eq!(decrypt(&data, "пароль").unwrap(), text);
+        // a new salt and nonce every time
+        assert_ne!(encrypt(text, "пароль").unwrap(), data);
+    }
+
+    #[test]
+    fn wrong_password_is_rejected() {
+        let data = encrypt("{}", "пароль").unwrap();
+        assert!(decrypt(&data, "Пароль").is_err());
+        assert!(decrypt(&data, "").is_err());
+        assert!(decrypt(b"{}", "пароль").is_err());
+        assert!(decrypt(&data[..MAGIC.len() + SALT_SIZE], "пароль").is_err());
+    }
+}
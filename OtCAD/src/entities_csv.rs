@@ -0,0 +1,305 @@
+use std::f64::consts::{PI, TAU};
+
+use application::angle_format::AngleFormat;
+use application::number_format::NumberFormat;
+
+use crate::document::*;
+use crate::ucs::*;
+
+type Point = curves::points::Point<f64>;
+type Contour = curves::Contour<f64>;
+type Segment = curves::Segment<f64>;
+
+/// Columns of the entity table. Circles and arcs have the center in X and Y, arcs go
+/// counterclockwise from the start angle to the end angle.
+const HEADER: [&str; 8] = ["type", "x", "y", "x2", "y2", "radius", "start", "end"];
+
+/// Settings of the document the table is written in: coordinates are in the UCS, numbers
+/// and angles are in the formats of the config.
+#[derive(Debug, Copy, Clone)]
+pub struct CsvUnits {
+    pub ucs: Ucs,
+    pub number_format: NumberFormat,
+    pub angle_format: AngleFormat,
+}
+
+impl CsvUnits {
+    /// Semicolons separate the fields when the comma is the decimal separator.
+    fn get_separator(&self) -> char {
+        if self.number_format.get_decimal_separator() == ',' {
+            ';'
+        } else {
+            ','
+        }
+    }
+
+    fn format_point(&self, point: Point) -> [String; 2] {
+        let local = self.ucs.to_local(point);
+        [
+            self.number_format.format(local.x),
+            self.number_format.format(local.y),
+        ]
+    }
+
+    /// Direction from the center in the UCS.
+    fn format_direction(&self, vector: Point) -> String {
+        let local = self.ucs.vector_to_local(vector);
+        self.angle_format
+            .format_direction(local.y.atan2(local.x), &self.number_format)
+    }
+
+    fn parse_point(&self, x: &str, y: &str) -> Option<Point> {
+        let x = self.number_format.parse(x)?;
+        let y = self.number_format.parse(y)?;
+        Some(self.ucs.to_world(Point::new(x, y)))
+    }
+
+    fn parse_direction(&self, text: &str) -> Option<Point> {
+        let angle = self
+            .angle_format
+            .parse_direction(text, &self.number_format)?;
+        Some(self.ucs.vector_to_world(Point::angle(angle)))
+    }
+}
+
+/// Row of the table, none for infinite lines, ellipses and groups.
+fn format_element(element: &Element, units: &CsvUnits) -> Option<Vec<String>> {
+    let curve = match element {
+        Element::Point(point) => {
+            let mut row = vec!["point".to_string()];
+            row.extend(units.format_point(point.position));
+            return Some(row);
+        }
+        Element::Curve(curve) => curve.curve,
+        Element::Ellipse(_) | Element::Group(_) => return None,
+    };
+    let row = match curve {
+        curves::Curve::Segment(s) if s.contour.a == 0.0 => {
+            let mut row = vec!["line".to_string()];
+            row.extend(units.format_point(s.begin));
+            row.extend(units.format_point(s.end));
+            row
+        }
+        curves::Curve::Segment(s) => {
+            let center = s.contour.get_center();
+            let (begin, end) = if units.ucs.is_mirrored() {
+                (s.end, s.begin)
+            } else {
+                (s.begin, s.end)
+            };
+            let mut row = vec!["arc".to_string()];
+            row.extend(units.format_point(center));
+            row.extend([String::new(), String::new()]);
+            row.push(units.number_format.format(s.contour.get_radius().abs()));
+            row.push(units.format_direction(begin - center));
+            row.push(units.format_direction(end - center));
+            row
+        }
+        curves::Curve::Contour(c) if c.a != 0.0 => {
+            let mut row = vec!["circle".to_string()];
+            row.extend(units.format_point(c.get_center()));
+            row.extend([String::new(), String::new()]);
+            row.push(units.number_format.format(c.get_radius().abs()));
+            row
+        }
+        curves::Curve::Contour(_) => return None,
+    };
+    Some(row)
+}
+
+/// The table with a header, the elements which can't be written are skipped.
+pub fn elements_to_csv(elements: &[Element], units: &CsvUnits) -> String {
+    let separator = units.get_separator().to_string();
+    let mut result = HEADER.join(&separator);
+    result.push('\n');
+    for row in elements
+        .iter()
+        .filter_map(|element| format_element(element, units))
+    {
+        let fields: Vec<_> = row
+            .iter()
+            .map(|field| escape_field(field, units.get_separator()))
+            .collect();
+        result.push_str(&fields.join(&separator));
+        result.push('\n');
+    }
+    result
+}
+
+/// Quotes the field if it has the separator or a quote, the quotes are doubled.
+fn escape_field(field: &str, separator: char) -> String {
+    if field.contains(separator) || field.contains('"') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Splits by the separators which are not in quotes, the quotes around the fields are
+/// removed and the doubled ones are made single.
+fn split_fields(line: &str, is_separator: impl Fn(char) -> bool) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        let field = fields.last_mut().unwrap();
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' => quoted = !quoted,
+            c if !quoted && is_separator(c) => fields.push(String::new()),
+            c => field.push(c),
+        }
+    }
+    fields
+        .into_iter()
+        .map(|field| field.trim().to_string())
+        .collect()
+}
+
+/// Semicolons or tabs out of the quotes.
+fn has_semicolons_or_tabs(line: &str) -> bool {
+    split_fields(line, |c| c == ';' || c == '\t').len() > 1
+}
+
+fn parse_row(fields: &[&str], units: &CsvUnits) -> Option<Element> {
+    let field = |index: usize| fields.get(index).copied().unwrap_or("");
+    let radius = || units.number_format.parse(field(5)).filter(|r| *r > 0.0);
+    let element = match field(0).to_lowercase().as_str() {
+        "point" => Element::Point(PointEntity::new(units.parse_point(field(1), field(2))?)),
+        "line" => {
+            let begin = units.parse_point(field(1), field(2))?;
+            let end = units.parse_point(field(3), field(4))?;
+            if begin == end {
+                return None;
+            }
+            Element::Curve(Curve::new_segment(Segment::line(begin, end)))
+        }
+        "circle" => {
+            let center = units.parse_point(field(1), field(2))?;
+            Element::Curve(Curve::new_contour(Contour::circle(center, radius()?)))
+        }
+        "arc" => {
+            let center = units.parse_point(field(1), field(2))?;
+            let radius = radius()?;
+            let start = units.parse_direction(field(6))?;
+            let end = units.parse_direction(field(7))?;
+            // the arc goes clockwise in the document if the UCS is mirrored
+            let (begin, end) = if units.ucs.is_mirrored() {
+                (end, start)
+            } else {
+                (start, end)
+            };
+            let sweep = (end.y.atan2(end.x) - begin.y.atan2(begin.x)).rem_euclid(TAU);
+            if sweep == 0.0 {
+                return None;
+            }
+            Element::Curve(Curve::new_segment(Segment {
+                contour: Contour::circle(center, radius),
+                begin: center + begin.scale(radius),
+                end: center + end.scale(radius),
+                big: sweep > PI,
+            }))
+        }
+        _ => return None,
+    };
+    Some(element)
+}
+
+/// Reads the rows of `elements_to_csv`. Semicolons and tabs are accepted as separators,
+/// then the comma may be the decimal separator, fields may be quoted as spreadsheets
+/// write them. The first row is skipped if it is not an element, as a header. Returns the
+/// number of the first wrong line as an error.
+pub fn elements_from_csv(text: &str, units: &CsvUnits) -> Result<Vec<Element>, usize> {
+    let mut result = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let fields = if has_semicolons_or_tabs(line) {
+            split_fields(line, |c| c == ';' || c == '\t')
+        } else {
+            split_fields(line, |c| c == ',')
+        };
+        let fields: Vec<&str> = fields.iter().map(String::as_str).collect();
+        match parse_row(&fields, units) {
+            Some(element) => result.push(element),
+            None if index == 0 => continue,
+            None => return Err(index + 1),
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn units_with(decimal_separator: char) -> CsvUnits {
+        CsvUnits {
+            ucs: Ucs::default(),
+            number_format: NumberFormat {
+                decimal_separator: Some(decimal_separator),
+                ..Default::default()
+            },
+            angle_format: AngleFormat::default(),
+        }
+    }
+
+    fn get_line_ends(element: &Element) -> (Point, Point) {
+        match element {
+            Element::Curve(Curve {
+                curve: curves::Curve::Segment(s),
+                ..
+            }) => (s.begin, s.end),
+            _ => panic!("not a line: {:?}", element),
+        }
+    }
+
+    #[test]
+    fn decimal_comma_switches_the_separator() {
+        let units = units_with(',');
+        let line = Element::Curve(Curve::new_segment(Segment::line(
+            Point::new(0.5, 1.25),
+            Point::new(2.0, 3.0),
+        )));
+        let text = elements_to_csv(&[line], &units);
+        assert_eq!(text, "type;x;y;x2;y2;radius;start;end\nline;0,5;1,25;2;3\n");
+
+        let elements = elements_from_csv(&text, &units).unwrap();
+        assert_eq!(elements.len(), 1);
+        assert_eq!(
+            get_line_ends(&elements[0]),
+            (Point::new(0.5, 1.25), Point::new(2.0, 3.0))
+        );
+    }
+
+    #[test]
+    fn quoted_fields_keep_their_separators_and_quotes() {
+        assert_eq!(escape_field("0,5", ','), "\"0,5\"");
+        assert_eq!(escape_field("0,5", ';'), "0,5");
+        assert_eq!(escape_field("say \"hi\"", ';'), "\"say \"\"hi\"\"\"");
+        assert_eq!(
+            split_fields("\"say \"\"hi\"\", ok\", 2", |c| c == ','),
+            vec!["say \"hi\", ok".to_string(), "2".to_string()]
+        );
+
+        // a spreadsheet quotes the numbers with the decimal comma
+        let text = "\"line\",\"0,5\",\"1,25\",2,3\n\"Line\";\"0,5\";1;2;3\n";
+        let elements = elements_from_csv(text, &units_with(',')).unwrap();
+        assert_eq!(
+            elements.iter().map(get_line_ends).collect::<Vec<_>>(),
+            vec![
+                (Point::new(0.5, 1.25), Point::new(2.0, 3.0)),
+                (Point::new(0.5, 1.0), Point::new(2.0, 3.0)),
+            ]
+        );
+        assert_eq!(
+            elements_from_csv("type\nline,\"0\"\"5\",1,2,3\n", &units_with('.')).unwrap_err(),
+            2
+        );
+    }
+}
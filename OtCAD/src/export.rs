@@ -0,0 +1,965 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::chain::*;
+use crate::document::*;
+use crate::dxf::*;
+use crate::plot_style::*;
+use crate::sheet::*;
+
+type Point = curves::points::Point<f64>;
+type Segment = curves::Segment<f64>;
+
+static EPS: f64 = 1.0e-9;
+
+/// Part of the document which goes to the output.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ExportScope {
+    Document,
+    Selection,
+    /// Curves which lie inside the rectangle, the corners are in document coordinates.
+    Window(Point, Point),
+}
+
+impl ExportScope {
+    /// Names of the scopes for selectors, the window is picked after the choice.
+    pub const NAMES: [&'static str; 3] = ["Весь чертёж", "Выделение", "Рамка"];
+}
+
+/// Plotted curves and sheet texts of the scope.
+pub fn get_scope_output(
+    document: &Document,
+    scope: ExportScope,
+) -> (Vec<PlottedCurve>, Vec<SheetText>) {
+    let texts = document
+        .get_sheet()
+        .map(|sheet| sheet.get_texts(document.get_metadata()))
+        .unwrap_or_default();
+    match scope {
+        ExportScope::Document => (document.get_plotted_curves(), texts),
+        ExportScope::Selection => (document.get_selected_plotted_curves(), Vec::new()),
+        ExportScope::Window(corner1, corner2) => {
+            let inside = |p: Point| {
+                p.x >= corner1.x.min(corner2.x)
+                    && p.x <= corner1.x.max(corner2.x)
+                    && p.y >= corner1.y.min(corner2.y)
+                    && p.y <= corner1.y.max(corner2.y)
+            };
+            let curves = document
+                .get_plotted_curves()
+                .into_iter()
+                .filter(|plotted| plotted.curve.in_rect(corner1, corner2))
+                .collect();
+            let texts = texts
+                .into_iter()
+                .filter(|text| inside(text.position))
+                .collect();
+            (curves, texts)
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub enum ExportFormat {
+    Svg,
+    Gerber,
+    Pdf,
+    Dxf,
+}
+
+impl ExportFormat {
+    pub fn get_name(self) -> &'static str {
+        match self {
+            ExportFormat::Svg => "SVG",
+            ExportFormat::Gerber => "Gerber",
+            ExportFormat::Pdf => "PDF",
+            ExportFormat::Dxf => "DXF",
+        }
+    }
+
+    pub fn get_extension(self) -> &'static str {
+        match self {
+            ExportFormat::Svg => "svg",
+            ExportFormat::Gerber => "gbr",
+            ExportFormat::Pdf => "pdf",
+            ExportFormat::Dxf => "dxf",
+        }
+    }
+
+    pub fn export(self, document: &Document, scope: ExportScope) -> Result<String, String> {
+        Ok(self.prepare(document, scope)?.write())
+    }
+
+    /// Takes from the document what the exporter needs, the output is written from it
+    /// on any thread.
+    pub fn prepare(self, document: &Document, scope: ExportScope) -> Result<ExportData, String> {
+        let (mut plotted_curves, mut texts) = get_scope_output(document, scope);
+        if plotted_curves.is_empty() && texts.is_empty() {
+            return Err("нечего экспортировать".to_string());
+        }
+        // without a sheet the whole drawing is its model, its kept extents size the page
+        let mut extents = match (scope, document.get_sheet()) {
+            (ExportScope::Document, None) => document.get_extents(false),
+            _ => None,
+        };
+        // the pictures are mirrored like the views, the sheet turns upside down with them
+        let mut sheet = document.get_sheet().cloned();
+        if document.is_y_up() {
+            for plotted in &mut plotted_curves {
+                plotted.curve = plotted.curve.flip_y();
+            }
+            for text in &mut texts {
+                text.position.y = -text.position.y;
+            }
+            if let Some(sheet) = &mut sheet {
+                let height = sheet.get_paper_size_mm().1 * sheet.scale;
+                sheet.origin.y = -sheet.origin.y - height;
+            }
+            if let Some((min, max)) = &mut extents {
+                (min.y, max.y) = (-max.y, -min.y);
+            }
+        }
+        let regions = match self {
+            ExportFormat::Gerber => get_gerber_regions(document, scope)?,
+            _ => Vec::new(),
+        };
+        Ok(ExportData {
+            format: self,
+            plotted_curves,
+            texts,
+            extents,
+            sheet,
+            metadata: document.get_metadata().clone(),
+            units_per_mm: document.get_units_per_mm(),
+            y_up: document.is_y_up(),
+            model_width_mm: document.get_plot_styles().model.width_mm,
+            regions,
+        })
+    }
+}
+
+/// Link of a closed region of the Gerber output, in the order of its chain.
+#[derive(Debug, Clone)]
+struct GerberLink {
+    curve: curves::Curve<f64>,
+    /// Line width in the document units, none takes the pen of the model.
+    width: Option<f64>,
+    reversed: bool,
+}
+
+/// Part of the document to export in one format, it doesn't refer to the document.
+#[derive(Debug, Clone)]
+pub struct ExportData {
+    format: ExportFormat,
+    plotted_curves: Vec<PlottedCurve>,
+    texts: Vec<SheetText>,
+    extents: Option<(Point, Point)>,
+    sheet: Option<Sheet>,
+    metadata: DocumentMetadata,
+    units_per_mm: f64,
+    y_up: bool,
+    model_width_mm: f64,
+    regions: Vec<Vec<GerberLink>>,
+}
+
+impl ExportData {
+    pub fn write(&self) -> String {
+        match self.format {
+            ExportFormat::Svg => export_svg(
+                &self.plotted_curves,
+                &self.texts,
+                self.extents,
+                self.units_per_mm,
+            ),
+            ExportFormat::Gerber => export_gerber(
+                &self.regions,
+                self.units_per_mm,
+                self.y_up,
+                self.model_width_mm,
+            ),
+            ExportFormat::Pdf => export_pdf(
+                &self.plotted_curves,
+                self.sheet.as_ref(),
+                self.extents,
+                &self.metadata,
+                self.units_per_mm,
+            ),
+            // the curves go down here, the texts of the sheet are not exported
+            ExportFormat::Dxf => to_dxf(&self.plotted_curves, true),
+        }
+    }
+}
+
+pub fn export_to_file(
+    document: &Document,
+    format: ExportFormat,
+    scope: ExportScope,
+    path: &Path,
+) -> Result<(), String> {
+    let text = format.export(document, scope)?;
+    std::fs::write(path, text).map_err(|e| e.to_string())
+}
+
+/// Point in the middle of the arc, it defines which way the arc goes.
+pub fn get_arc_middle(segment: &Segment) -> Point {
+    let center = segment.contour.get_center();
+    let radius = segment.contour.get_radius().abs();
+    let chord_direction = (segment.begin + segment.end).scale(0.5) - center;
+    if chord_direction.length() > EPS {
+        let direction = chord_direction.normalize().scale(radius);
+        return if segment.big {
+            center - direction
+        } else {
+            center + direction
+        };
+    }
+    // half of a circle, the chord goes through the center
+    let direction = (segment.begin - center).rot90().normalize().scale(radius);
+    if segment.inside_sector(center + direction, 0.0, false) {
+        center + direction
+    } else {
+        center - direction
+    }
+}
+
+/// Infinite lines go to the page cut by its bounds, the ones which miss the page are
+/// left out.
+fn clip_infinite_lines(
+    plotted_curves: &[PlottedCurve],
+    corner1: Point,
+    corner2: Point,
+) -> Vec<PlottedCurve> {
+    plotted_curves
+        .iter()
+        .filter_map(|plotted| match plotted.curve {
+            curves::Curve::Contour(c) if c.a == 0.0 => {
+                c.clip_line(corner1, corner2).map(|segment| PlottedCurve {
+                    curve: curves::Curve::Segment(segment),
+                    ..*plotted
+                })
+            }
+            _ => Some(*plotted),
+        })
+        .collect()
+}
+
+/// Points which bound the curve, none for infinite lines.
+pub fn get_curve_points(curve: &curves::Curve<f64>) -> Vec<Point> {
+    match curve {
+        curves::Curve::Contour(c) if c.a != 0.0 => {
+            let center = c.get_center();
+            let radius = c.get_radius().abs();
+            vec![
+                center - Point::new(radius, radius),
+                center + Point::new(radius, radius),
+            ]
+        }
+        // infinite lines have no extents
+        curves::Curve::Contour(_) => Vec::new(),
+        curves::Curve::Segment(s) if s.contour.a == 0.0 => vec![s.begin, s.end],
+        curves::Curve::Segment(s) => {
+            let center = s.contour.get_center();
+            let radius = s.contour.get_radius().abs();
+            let mut points = vec![s.begin, s.end, get_arc_middle(s)];
+            for direction in [
+                Point::new(radius, 0.0),
+                Point::new(-radius, 0.0),
+                Point::new(0.0, radius),
+                Point::new(0.0, -radius),
+            ] {
+                if s.inside_sector(center + direction, 0.0, false) {
+                    points.push(center + direction);
+                }
+            }
+            points
+        }
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn write_svg_curve(svg: &mut String, plotted: &PlottedCurve, units_per_mm: f64) {
+    let mut style = format!(
+        "fill=\"none\" stroke=\"black\" stroke-width=\"{:.4}\"",
+        plotted.get_width(units_per_mm)
+    );
+    let dash = plotted.style.dash.get_lengths_mm();
+    if !dash.is_empty() {
+        let lengths: Vec<_> = dash
+            .iter()
+            .map(|length| format!("{:.4}", length * units_per_mm))
+            .collect();
+        let _ = write!(style, " stroke-dasharray=\"{}\"", lengths.join(","));
+    }
+    if !plotted.opacity.is_opaque() {
+        let _ = write!(
+            style,
+            " stroke-opacity=\"{:.2}\"",
+            plotted.opacity.get_fraction()
+        );
+    }
+    let _ = match plotted.curve {
+        curves::Curve::Contour(c) if c.a != 0.0 => {
+            let center = c.get_center();
+            writeln!(
+                svg,
+                "  <circle cx=\"{:.4}\" cy=\"{:.4}\" r=\"{:.4}\" {}/>",
+                center.x,
+                center.y,
+                c.get_radius().abs(),
+                style
+            )
+        }
+        curves::Curve::Contour(_) => Ok(()),
+        curves::Curve::Segment(s) if s.contour.a == 0.0 => writeln!(
+            svg,
+            "  <line x1=\"{:.4}\" y1=\"{:.4}\" x2=\"{:.4}\" y2=\"{:.4}\" {}/>",
+            s.begin.x, s.begin.y, s.end.x, s.end.y, style
+        ),
+        curves::Curve::Segment(s) => {
+            let center = s.contour.get_center();
+            let middle = get_arc_middle(&s);
+            let sweep = curves::points::cross(s.begin - center, middle - center) > 0.0;
+            writeln!(
+                svg,
+                "  <path d=\"M {:.4} {:.4} A {:.4} {:.4} 0 {} {} {:.4} {:.4}\" {}/>",
+                s.begin.x,
+                s.begin.y,
+                s.contour.get_radius().abs(),
+                s.contour.get_radius().abs(),
+                s.big as i32,
+                sweep as i32,
+                s.end.x,
+                s.end.y,
+                style
+            )
+        }
+    };
+}
+
+/// Bounds of the curves, or the known ones of the exported drawing.
+fn get_plotted_extents(
+    plotted_curves: &[PlottedCurve],
+    texts: &[SheetText],
+    extents: Option<(Point, Point)>,
+) -> Option<(Point, Point)> {
+    if extents.is_some() {
+        return extents;
+    }
+    plotted_curves
+        .iter()
+        .flat_map(|plotted| get_curve_points(&plotted.curve))
+        .chain(texts.iter().map(|text| text.position))
+        .fold(None, |bounds, p| {
+            let (min, max) = bounds.unwrap_or((p, p));
+            Some((
+                Point::new(min.x.min(p.x), min.y.min(p.y)),
+                Point::new(max.x.max(p.x), max.y.max(p.y)),
+            ))
+        })
+}
+
+/// The document coordinates go to the file as they are, the size of the picture is
+/// set in millimeters, so the pens get their widths on the paper. The picture fits
+/// the extents if they are given, otherwise the curves.
+pub fn export_svg(
+    plotted_curves: &[PlottedCurve],
+    texts: &[SheetText],
+    extents: Option<(Point, Point)>,
+    units_per_mm: f64,
+) -> String {
+    let (min, max) = get_plotted_extents(plotted_curves, texts, extents)
+        .unwrap_or((Point::new(0.0, 0.0), Point::new(1.0, 1.0)));
+    // thick pens stick out of the extents
+    let margin = plotted_curves
+        .iter()
+        .map(|plotted| plotted.get_width(units_per_mm))
+        .fold(0.0, f64::max);
+    let min = min - Point::new(margin, margin);
+    let size = max - min + Point::new(margin, margin);
+    let plotted_curves = clip_infinite_lines(plotted_curves, min, min + size);
+
+    let mut svg = String::new();
+    let _ = writeln!(svg, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>");
+    let _ = writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:.4}mm\" height=\"{:.4}mm\" viewBox=\"{:.4} {:.4} {:.4} {:.4}\">",
+        size.x / units_per_mm,
+        size.y / units_per_mm,
+        min.x,
+        min.y,
+        size.x,
+        size.y
+    );
+    for plotted in &plotted_curves {
+        write_svg_curve(&mut svg, plotted, units_per_mm);
+    }
+    for text in texts {
+        let _ = writeln!(
+            svg,
+            "  <text x=\"{:.4}\" y=\"{:.4}\" font-size=\"{:.4}\" dominant-baseline=\"middle\">{}</text>",
+            text.position.x,
+            text.position.y,
+            text.height,
+            escape_xml(&text.text)
+        );
+    }
+    let _ = writeln!(svg, "</svg>");
+    svg
+}
+
+/// Ids of the model curves of the scope.
+fn get_scope_curve_ids(document: &Document, scope: ExportScope) -> HashSet<usize> {
+    document
+        .get_shapes_in_draw_order()
+        .into_iter()
+        .filter(|(_, curve)| match scope {
+            ExportScope::Document => true,
+            ExportScope::Selection => curve.selected,
+            ExportScope::Window(corner1, corner2) => curve.curve.in_rect(corner1, corner2),
+        })
+        .map(|(id, _)| id)
+        .collect()
+}
+
+/// Gerber coordinates are in millionths of millimeter, its Y axis goes up, so the
+/// document Y is flipped unless it goes up too.
+fn to_gerber(point: Point, units_per_mm: f64, y_up: bool) -> (i64, i64) {
+    let y = if y_up { point.y } else { -point.y };
+    (
+        (point.x / units_per_mm * 1.0e6).round() as i64,
+        (y / units_per_mm * 1.0e6).round() as i64,
+    )
+}
+
+/// Round apertures by their diameters in millimeters, the numbers start from 10.
+#[derive(Default)]
+struct Apertures {
+    diameters: Vec<f64>,
+}
+
+impl Apertures {
+    fn get(&mut self, diameter_mm: f64) -> usize {
+        // the diameter is written with micrometer precision
+        let diameter_mm = (diameter_mm * 1000.0).round() / 1000.0;
+        let index = match self.diameters.iter().position(|d| *d == diameter_mm) {
+            Some(index) => index,
+            None => {
+                self.diameters.push(diameter_mm);
+                self.diameters.len() - 1
+            }
+        };
+        index + 10
+    }
+}
+
+/// Closed chains of the scope with their curves, full circles are regions of one link.
+fn get_gerber_regions(
+    document: &Document,
+    scope: ExportScope,
+) -> Result<Vec<Vec<GerberLink>>, String> {
+    let ids = get_scope_curve_ids(document, scope);
+    let regions: Vec<Vec<_>> = document
+        .find_closed_regions(CHAIN_TOLERANCE)
+        .into_iter()
+        .filter(|chain| chain.links.iter().all(|link| ids.contains(&link.id)))
+        .map(|chain| {
+            chain
+                .links
+                .iter()
+                .filter_map(|link| {
+                    let curve = document.get_curve(link.id)?;
+                    Some(GerberLink {
+                        curve: curve.curve,
+                        width: curve.width,
+                        reversed: link.reversed,
+                    })
+                })
+                .collect()
+        })
+        .collect();
+    if regions.is_empty() {
+        return Err("нет замкнутых контуров и окружностей".to_string());
+    }
+    Ok(regions)
+}
+
+/// Closed chains of lines and arcs are drawn by the apertures of their line widths, full
+/// circles are flashed as round pads of their diameter, like the drill holes of a board.
+/// Open chains and infinite lines have no outline and are skipped.
+fn export_gerber(
+    regions: &[Vec<GerberLink>],
+    units_per_mm: f64,
+    y_up: bool,
+    model_width_mm: f64,
+) -> String {
+    let mut apertures = Apertures::default();
+    let mut commands = String::new();
+    let mut current_aperture = None;
+    let mut select_aperture = |commands: &mut String, aperture: usize| {
+        if current_aperture != Some(aperture) {
+            let _ = writeln!(commands, "D{}*", aperture);
+            current_aperture = Some(aperture);
+        }
+    };
+    for region in regions {
+        let mut position = None;
+        for link in region {
+            if let curves::Curve::Contour(c) = link.curve {
+                let (x, y) = to_gerber(c.get_center(), units_per_mm, y_up);
+                let diameter_mm = 2.0 * c.get_radius().abs() / units_per_mm;
+                select_aperture(&mut commands, apertures.get(diameter_mm));
+                let _ = writeln!(commands, "X{}Y{}D03*", x, y);
+                continue;
+            }
+            let s = match link.curve {
+                curves::Curve::Segment(s) => s,
+                _ => continue,
+            };
+            let width_mm = link
+                .width
+                .map_or(model_width_mm, |width| width / units_per_mm);
+            select_aperture(&mut commands, apertures.get(width_mm));
+            let (begin, end) = if link.reversed {
+                (s.end, s.begin)
+            } else {
+                (s.begin, s.end)
+            };
+            if position != Some(begin) {
+                let (x, y) = to_gerber(begin, units_per_mm, y_up);
+                let _ = writeln!(commands, "X{}Y{}D02*", x, y);
+            }
+            let (x, y) = to_gerber(end, units_per_mm, y_up);
+            if s.contour.a == 0.0 {
+                let _ = writeln!(commands, "G01X{}Y{}D01*", x, y);
+            } else {
+                let center = s.contour.get_center();
+                let middle = get_arc_middle(&s);
+                // if the Y axis is flipped, the counterclockwise arc of the document goes
+                // clockwise in the file
+                let counterclockwise = curves::points::cross(begin - center, middle - center) > 0.0;
+                let file_counterclockwise = counterclockwise == y_up;
+                let (i, j) = to_gerber(center - begin, units_per_mm, y_up);
+                let _ = writeln!(
+                    commands,
+                    "{}X{}Y{}I{}J{}D01*",
+                    if file_counterclockwise { "G03" } else { "G02" },
+                    x,
+                    y,
+                    i,
+                    j
+                );
+            }
+            position = Some(end);
+        }
+    }
+
+    let mut gerber = String::new();
+    let _ = writeln!(gerber, "G04 OtCAD outlines*");
+    let _ = writeln!(gerber, "%FSLAX46Y46*%");
+    let _ = writeln!(gerber, "%MOMM*%");
+    let _ = writeln!(gerber, "%LPD*%");
+    for (index, diameter) in apertures.diameters.iter().enumerate() {
+        let _ = writeln!(gerber, "%ADD{}C,{:.3}*%", index + 10, diameter);
+    }
+    let _ = writeln!(gerber, "G75*");
+    gerber.push_str(&commands);
+    let _ = writeln!(gerber, "M02*");
+    gerber
+}
+
+const POINTS_PER_MM: f64 = 72.0 / 25.4;
+
+/// Text string of the PDF dictionaries, UTF-16 keeps the cyrillic letters.
+fn pdf_text(text: &str) -> String {
+    let mut result = "<FEFF".to_string();
+    for unit in text.encode_utf16() {
+        let _ = write!(result, "{:04X}", unit);
+    }
+    result.push('>');
+    result
+}
+
+/// Bezier pieces of the arc, no piece turns more than a quarter. The arc goes
+/// counterclockwise for a positive sweep.
+fn get_arc_beziers(center: Point, radius: f64, start: f64, sweep: f64) -> Vec<[Point; 4]> {
+    let count = (sweep.abs() / std::f64::consts::FRAC_PI_2).ceil().max(1.0) as usize;
+    let step = sweep / count as f64;
+    let k = 4.0 / 3.0 * (step / 4.0).tan();
+    (0..count)
+        .map(|index| {
+            let a0 = start + step * index as f64;
+            let a1 = a0 + step;
+            let p0 = center + Point::angle(a0).scale(radius);
+            let p3 = center + Point::angle(a1).scale(radius);
+            [
+                p0,
+                p0 + Point::angle(a0).rot90().scale(k * radius),
+                p3 - Point::angle(a1).rot90().scale(k * radius),
+                p3,
+            ]
+        })
+        .collect()
+}
+
+/// Path operators of the curve, `to_page` maps the document points to the page.
+fn write_pdf_curve(
+    content: &mut String,
+    curve: &curves::Curve<f64>,
+    to_page: &dyn Fn(Point) -> Point,
+) {
+    let move_to = |content: &mut String, p: Point| {
+        let p = to_page(p);
+        let _ = writeln!(content, "{:.3} {:.3} m", p.x, p.y);
+    };
+    let write_beziers = |content: &mut String, beziers: &[[Point; 4]]| {
+        for bezier in beziers {
+            let [_, p1, p2, p3] = bezier.map(to_page);
+            let _ = writeln!(
+                content,
+                "{:.3} {:.3} {:.3} {:.3} {:.3} {:.3} c",
+                p1.x, p1.y, p2.x, p2.y, p3.x, p3.y
+            );
+        }
+    };
+    match curve {
+        curves::Curve::Contour(c) if c.a != 0.0 => {
+            let center = c.get_center();
+            let radius = c.get_radius().abs();
+            move_to(content, center + Point::new(radius, 0.0));
+            write_beziers(
+                content,
+                &get_arc_beziers(center, radius, 0.0, std::f64::consts::TAU),
+            );
+            let _ = writeln!(content, "h S");
+        }
+        curves::Curve::Contour(_) => {}
+        curves::Curve::Segment(s) if s.contour.a == 0.0 => {
+            move_to(content, s.begin);
+            let end = to_page(s.end);
+            let _ = writeln!(content, "{:.3} {:.3} l S", end.x, end.y);
+        }
+        curves::Curve::Segment(s) => {
+            let center = s.contour.get_center();
+            let radius = s.contour.get_radius().abs();
+            let middle = get_arc_middle(s);
+            let counterclockwise = curves::points::cross(s.begin - center, middle - center) > 0.0;
+            let start = (s.begin - center).y.atan2((s.begin - center).x);
+            let end = (s.end - center).y.atan2((s.end - center).x);
+            let sweep = if counterclockwise {
+                (end - start).rem_euclid(std::f64::consts::TAU)
+            } else {
+                -(start - end).rem_euclid(std::f64::consts::TAU)
+            };
+            move_to(content, s.begin);
+            write_beziers(content, &get_arc_beziers(center, radius, start, sweep));
+            let _ = writeln!(content, "S");
+        }
+    }
+}
+
+/// One page of vector drawing. With a sheet the page is its paper, otherwise the page
+/// fits the extents or the curves and one document unit is one millimeter. The metadata of the document
+/// goes to the document information, the sheet texts are not written, because the standard
+/// fonts of PDF have no cyrillic letters.
+pub fn export_pdf(
+    plotted_curves: &[PlottedCurve],
+    sheet: Option<&Sheet>,
+    extents: Option<(Point, Point)>,
+    metadata: &DocumentMetadata,
+    units_per_mm: f64,
+) -> String {
+    // left top corner of the page in the document and the page size in millimeters
+    let (origin, size_mm) = match sheet {
+        Some(sheet) => {
+            let (w, h) = sheet.get_paper_size_mm();
+            (sheet.origin, Point::new(w, h))
+        }
+        None => {
+            // thick pens stick out of the extents
+            let margin = plotted_curves
+                .iter()
+                .map(|plotted| plotted.get_width(units_per_mm))
+                .fold(0.0, f64::max);
+            match get_plotted_extents(plotted_curves, &[], extents) {
+                Some((min, max)) => {
+                    let min = min - Point::new(margin, margin);
+                    let max = max + Point::new(margin, margin);
+                    (min, (max - min).scale(1.0 / units_per_mm))
+                }
+                None => (Point::new(0.0, 0.0), Point::new(1.0, 1.0)),
+            }
+        }
+    };
+    let plotted_curves =
+        &clip_infinite_lines(plotted_curves, origin, origin + size_mm.scale(units_per_mm));
+    // the Y axis of the page goes up
+    let to_page = |p: Point| {
+        let mm = (p - origin).scale(1.0 / units_per_mm);
+        Point::new(mm.x, size_mm.y - mm.y).scale(POINTS_PER_MM)
+    };
+
+    let mut content = String::new();
+    let _ = writeln!(content, "1 J 1 j");
+    // a graphics state for each opacity of the curves, named by the percents
+    let mut opacities: Vec<_> = plotted_curves
+        .iter()
+        .map(|plotted| plotted.opacity.0.min(100))
+        .collect();
+    opacities.sort_unstable();
+    opacities.dedup();
+    let mut opacity = 100;
+    for plotted in plotted_curves {
+        if plotted.opacity.0.min(100) != opacity {
+            opacity = plotted.opacity.0.min(100);
+            let _ = writeln!(content, "/GS{} gs", opacity);
+        }
+        let width_mm = plotted.get_width(units_per_mm) / units_per_mm;
+        let dash: Vec<_> = plotted
+            .style
+            .dash
+            .get_lengths_mm()
+            .iter()
+            .map(|length| format!("{:.3}", length * POINTS_PER_MM))
+            .collect();
+        let _ = writeln!(
+            content,
+            "{:.3} w [{}] 0 d",
+            width_mm * POINTS_PER_MM,
+            dash.join(" ")
+        );
+        write_pdf_curve(&mut content, &plotted.curve, &to_page);
+    }
+
+    let page_size = size_mm.scale(POINTS_PER_MM);
+    let resources = if opacities.iter().all(|opacity| *opacity == 100) {
+        String::new()
+    } else {
+        let states: Vec<_> = opacities
+            .iter()
+            .map(|opacity| {
+                format!(
+                    "/GS{} << /CA {:.2} >>",
+                    opacity,
+                    Opacity(*opacity).get_fraction()
+                )
+            })
+            .collect();
+        format!(" /Resources << /ExtGState << {} >> >>", states.join(" "))
+    };
+    let mut info = "<< /Producer (OtCAD)".to_string();
+    for (key, value) in [
+        ("Title", &metadata.title),
+        ("Subject", &metadata.drawing_number),
+        ("Author", &metadata.author),
+        ("DrawingDate", &metadata.date),
+    ] {
+        if !value.is_empty() {
+            let _ = write!(info, " /{} {}", key, pdf_text(value));
+        }
+    }
+    info.push_str(" >>");
+    let objects = [
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+        format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.3} {:.3}] /Contents 4 0 R{} >>",
+            page_size.x, page_size.y, resources
+        ),
+        format!(
+            "<< /Length {} >>\nstream\n{}endstream",
+            content.len(),
+            content
+        ),
+        info,
+    ];
+
+    let mut pdf = "%PDF-1.4\n".to_string();
+    let mut offsets = Vec::new();
+    for (index, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        let _ = writeln!(pdf, "{} 0 obj\n{}\nendobj", index + 1, object);
+    }
+    let xref = pdf.len();
+    let _ = writeln!(pdf, "xref\n0 {}\n0000000000 65535 f ", objects.len() + 1);
+    for offset in offsets {
+        let _ = writeln!(pdf, "{:010} 00000 n ", offset);
+    }
+    let _ = writeln!(
+        pdf,
+        "trailer\n<< /Size {} /Root 1 0 R /Info 5 0 R >>\nstartxref\n{}\n%%EOF",
+        objects.len() + 1,
+        xref
+    );
+    pdf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Contour = curves::Contour<f64>;
+
+    fn get_style_widths(curves: &[PlottedCurve]) -> Vec<f64> {
+        curves
+            .iter()
+            .map(|plotted| plotted.style.width_mm)
+            .collect()
+    }
+
+    #[test]
+    fn selection_has_the_pens_of_the_full_output() {
+        let mut document = Document::new();
+        document.add_entity(Curve::new_contour(Contour::circle(
+            Point::new(0.0, 0.0),
+            1.0,
+        )));
+        document.add_entity(Curve::new_contour(Contour::circle(
+            Point::new(50.0, 0.0),
+            1.0,
+        )));
+        document.set_sheet(Some(Sheet {
+            viewports: vec![Viewport {
+                paper_left_top: Point::new(10.0, 10.0),
+                paper_size: Point::new(80.0, 60.0),
+                model_center: Point::new(0.0, 0.0),
+                scale: 1.0,
+            }],
+            ..Sheet::default()
+        }));
+        let first = *document.get_content().keys().min().unwrap();
+        document.select_only(&HashSet::from([first]));
+
+        let (selected, texts) = get_scope_output(&document, ExportScope::Selection);
+        let styles = document.get_plot_styles();
+        assert!(texts.is_empty());
+        assert_eq!(
+            get_style_widths(&selected),
+            [styles.model.width_mm, styles.viewport.width_mm]
+        );
+        let (all, _) = get_scope_output(&document, ExportScope::Document);
+        assert_eq!(
+            get_style_widths(&all[..1]),
+            get_style_widths(&selected[..1])
+        );
+    }
+
+    #[test]
+    fn gerber_flashes_circles_and_draws_closed_outlines() {
+        let mut document = Document::new();
+        for (begin, end) in [
+            ((0.0, 0.0), (2.0, 0.0)),
+            ((2.0, 0.0), (2.0, 2.0)),
+            ((2.0, 2.0), (0.0, 2.0)),
+            ((0.0, 2.0), (0.0, 0.0)),
+        ] {
+            document.add_entity(Curve::new_segment(Segment::line(
+                Point::new(begin.0, begin.1),
+                Point::new(end.0, end.1),
+            )));
+        }
+        document.add_entity(Curve::new_contour(Contour::circle(
+            Point::new(1.0, 5.0),
+            0.5,
+        )));
+        // an open line has no outline
+        document.add_entity(Curve::new_segment(Segment::line(
+            Point::new(5.0, 0.0),
+            Point::new(6.0, 0.0),
+        )));
+        // half of a disk with a thinner pen
+        document.add_entity(Curve {
+            width: Some(0.2),
+            ..Curve::new_segment(Segment::line(Point::new(10.0, 0.0), Point::new(12.0, 0.0)))
+        });
+        document.add_entity(Curve {
+            width: Some(0.2),
+            ..Curve::new_segment(Segment {
+                contour: Contour::circle(Point::new(11.0, 0.0), 1.0),
+                begin: Point::new(12.0, 0.0),
+                end: Point::new(10.0, 0.0),
+                big: false,
+            })
+        });
+
+        let gerber = ExportFormat::Gerber
+            .export(&document, ExportScope::Document)
+            .unwrap();
+        // the Y axis of the document goes down, the one of Gerber goes up, so the
+        // counterclockwise arc is written clockwise
+        assert_eq!(
+            gerber,
+            "G04 OtCAD outlines*\n\
+             %FSLAX46Y46*%\n\
+             %MOMM*%\n\
+             %LPD*%\n\
+             %ADD10C,1.000*%\n\
+             %ADD11C,0.350*%\n\
+             %ADD12C,0.200*%\n\
+             G75*\n\
+             D10*\n\
+             X1000000Y-5000000D03*\n\
+             D11*\n\
+             X0Y0D02*\n\
+             G01X2000000Y0D01*\n\
+             G01X2000000Y-2000000D01*\n\
+             G01X0Y-2000000D01*\n\
+             G01X0Y0D01*\n\
+             D12*\n\
+             X10000000Y0D02*\n\
+             G01X12000000Y0D01*\n\
+             G02X10000000Y0I-1000000J0D01*\n\
+             M02*\n"
+        );
+    }
+
+    #[test]
+    fn pdf_page_fits_the_drawing_and_strokes_its_curves() {
+        let mut document = Document::new();
+        document.add_entity(Curve::new_segment(Segment::line(
+            Point::new(0.0, 0.0),
+            Point::new(10.0, 20.0),
+        )));
+        let pdf = ExportFormat::Pdf
+            .export(&document, ExportScope::Document)
+            .unwrap();
+
+        // the page is the extents with the pen width around, one unit is one millimeter
+        let pen = document.get_plot_styles().model.width_mm;
+        let points = |mm: f64| format!("{:.3}", mm * POINTS_PER_MM);
+        assert!(pdf.contains(&format!(
+            "/MediaBox [0 0 {} {}]",
+            points(10.0 + 2.0 * pen),
+            points(20.0 + 2.0 * pen)
+        )));
+        // the Y axis of the page goes up
+        let path = format!(
+            "{} w [] 0 d\n{} {} m\n{} {} l S\n",
+            points(pen),
+            points(pen),
+            points(20.0 + pen),
+            points(10.0 + pen),
+            points(pen)
+        );
+        assert!(pdf.contains(&path), "{}", pdf);
+
+        // the cross-reference table points to the objects
+        let xref = pdf.rfind("startxref\n").unwrap();
+        let xref: usize = pdf[xref..].lines().nth(1).unwrap().parse().unwrap();
+        let offsets: Vec<usize> = pdf[xref..]
+            .lines()
+            .skip(3)
+            .take(5)
+            .map(|line| line[..10].parse().unwrap())
+            .collect();
+        for (index, offset) in offsets.iter().enumerate() {
+            assert!(pdf[*offset..].starts_with(&format!("{} 0 obj", index + 1)));
+        }
+    }
+}
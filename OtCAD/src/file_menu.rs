@@ -1,27 +1,41 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::path::Path;
 use std::rc::Rc;
 
 use application::callback;
 use application::callback_body;
 use application::font::*;
 use application::gui::gui_components::*;
+use application::gui::toast::*;
 use application::gui::*;
 use application::keys::*;
 
+use window::{show_open_file_dialog, show_save_file_dialog};
+
+use crate::batch_export::*;
+use crate::compare::*;
+use crate::coordinates_dialog::*;
+use crate::document::*;
+use crate::document_loading::*;
 use crate::editor::*;
+use crate::entities_csv::*;
+use crate::export::*;
 use crate::gui_helper::*;
+use crate::interchange::*;
+use crate::plot_style_dialog::*;
+use crate::recent_files::*;
+use crate::sheet_dialog::*;
 
 pub fn create_file_menu(
     parent: &mut TabControl,
     font: &Font,
     editor: Rc<RefCell<Editor>>,
+    context: Rc<RefCell<window::Context>>,
 ) -> Rc<RefCell<Container>> {
-    let menu_caption = "Файл";
+    let menu_caption = "&Файл";
     let file_menu = parent.add_tab(
         menu_caption.to_string(),
-        GuiSystem::default_size(&menu_caption, None, &font)
-            .0
-            .absolute,
+        Button::default_size(&menu_caption, None, &font).0.absolute,
         Container::new(
             SizeConstraints(SizeConstraint::fixed(0), SizeConstraint::fixed(0)),
             ContainerLayout::Horizontal,
@@ -37,7 +51,7 @@ pub fn create_file_menu(
         let font = font.clone();
         let _new_button = default_panel.borrow_mut().add_child(
             create_default_size_button_with_hotkey(
-                "Новый",
+                "&Новый",
                 font.clone(),
                 Hotkey::ctrl(Key::N),
                 true,
@@ -48,35 +62,63 @@ pub fn create_file_menu(
         );
     }
 
-    let _open_button =
-        default_panel
-            .borrow_mut()
-            .add_child(create_default_size_button_with_hotkey(
-                "Открыть",
+    {
+        let font = font.clone();
+        let _open_button = default_panel.borrow_mut().add_child(
+            create_default_size_button_with_hotkey(
+                "О&ткрыть",
                 font.clone(),
                 Hotkey::ctrl(Key::O),
                 true,
-            ));
+            )
+            .callback(callback!([editor, context]() {
+                open_file(font.clone(), editor, context);
+            })),
+        );
+    }
 
-    let _save_button =
-        default_panel
-            .borrow_mut()
-            .add_child(create_default_size_button_with_hotkey(
-                "Сохранить",
-                font.clone(),
-                Hotkey::ctrl(Key::S),
-                true,
-            ));
+    let _save_button = default_panel.borrow_mut().add_child(
+        create_default_size_button_with_hotkey(
+            "&Сохранить",
+            font.clone(),
+            Hotkey::ctrl(Key::S),
+            true,
+        )
+        .callback(callback!([editor, context]() {
+            save_active_document(false, editor, context);
+        })),
+    );
 
-    let _save_as_button =
-        default_panel
-            .borrow_mut()
-            .add_child(create_default_size_button_with_hotkey(
-                "Сохранить как",
-                font.clone(),
-                Hotkey::ctrl_shift(Key::S),
-                true,
-            ));
+    let _save_as_button = default_panel.borrow_mut().add_child(
+        create_default_size_button_with_hotkey(
+            "Сохранить &как",
+            font.clone(),
+            Hotkey::ctrl_shift(Key::S),
+            true,
+        )
+        .callback(callback!([editor, context]() {
+            save_active_document(true, editor, context);
+        })),
+    );
+
+    let _revert_button = default_panel.borrow_mut().add_child(
+        create_default_size_button("Вернуть сохранённый", font.clone()).callback(callback!(
+            [editor, context]() {
+                revert_active_document(editor, context);
+            }
+        )),
+    );
+
+    {
+        let font = font.clone();
+        let _recent_button = default_panel.borrow_mut().add_child(
+            create_default_size_button("Недавние файлы...", font.clone()).callback(callback!(
+                [editor, context]() {
+                    open_recent_files_page(&font, editor, context);
+                }
+            )),
+        );
+    }
 
     let close_button =
         default_panel
@@ -94,6 +136,101 @@ pub fn create_file_menu(
             editor.borrow_mut().close_selected_tab();
         }));
 
+    let sheet_panel = file_menu.borrow_mut().add_child(Container::new(
+        SizeConstraints(SizeConstraint::fixed(0), SizeConstraint::fixed(0)),
+        ContainerLayout::Vertical,
+    ));
+
+    {
+        let font = font.clone();
+        let _sheet_button = sheet_panel.borrow_mut().add_child(
+            create_default_size_button("Лист и штамп...", font.clone()).callback(callback!(
+                [editor, context]() {
+                    open_sheet_dialog(&font, editor, context);
+                }
+            )),
+        );
+    }
+
+    {
+        let font = font.clone();
+        let _plot_style_button = sheet_panel.borrow_mut().add_child(
+            create_default_size_button("Стили печати...", font.clone()).callback(callback!(
+                [editor, context]() {
+                    open_plot_style_dialog(&font, editor, context);
+                }
+            )),
+        );
+    }
+
+    {
+        let font = font.clone();
+        let _compare_button = sheet_panel.borrow_mut().add_child(
+            create_default_size_button("Сравнить файлы...", font.clone()).callback(callback!(
+                [editor, context]() {
+                    compare_files(&font, editor, context);
+                }
+            )),
+        );
+    }
+
+    {
+        let font = font.clone();
+        let _view_button = sheet_panel.borrow_mut().add_child(
+            create_default_size_button("Новый вид чертежа", font.clone()).callback(callback!(
+                [editor]() {
+                    editor.borrow().add_view_of_active_document(font.clone());
+                }
+            )),
+        );
+    }
+
+    {
+        let font = font.clone();
+        let _password_button = sheet_panel.borrow_mut().add_child(
+            create_default_size_button("Пароль...", font.clone()).callback(callback!(
+                [editor, context]() {
+                    open_password_dialog(&font, editor, context);
+                }
+            )),
+        );
+    }
+
+    let _read_only_button = sheet_panel.borrow_mut().add_child(
+        create_default_size_button("Только чтение", font.clone()).callback(callback!(
+            [editor, context]() {
+                let document = match editor.borrow().get_active_document() {
+                    Some(document) => document,
+                    None => return,
+                };
+                let read_only = !document.borrow().is_read_only();
+                document.borrow_mut().set_read_only(read_only);
+                let text = if read_only {
+                    "Чертёж только для чтения"
+                } else {
+                    "Чертёж можно изменять"
+                };
+                context.borrow_mut().gui_system.show_toast(Toast::info(text));
+            }
+        )),
+    );
+
+    let _xref_button = sheet_panel.borrow_mut().add_child(
+        create_default_size_button("Внешняя ссылка...", font.clone()).callback(callback!(
+            [editor, context]() {
+                add_xref(editor, context);
+            }
+        )),
+    );
+
+    let _remove_xrefs_button = sheet_panel.borrow_mut().add_child(
+        create_default_size_button("Убрать ссылки", font.clone()).callback(callback!(
+            [editor]() {
+                editor.borrow().remove_xrefs();
+            }
+        )),
+    );
+
     let dxf_panel = file_menu.borrow_mut().add_child(Container::new(
         SizeConstraints(SizeConstraint::flexible(0), SizeConstraint::fixed(0)),
         ContainerLayout::Vertical,
@@ -103,15 +240,138 @@ pub fn create_file_menu(
         .borrow_mut()
         .add_child(create_default_size_button("Импорт из *.dxf", font.clone()));
 
-    let _export_button = dxf_panel
-        .borrow_mut()
-        .add_child(create_default_size_button("Экспорт в *.dxf", font.clone()));
-
     let _export_many_button = dxf_panel.borrow_mut().add_child(create_default_size_button(
         "Экспорт контуров в разные *.dxf",
         font.clone(),
     ));
 
+    let export_scope_index = Rc::new(Cell::new(0));
+    create_selector(
+        &mut dxf_panel.borrow_mut(),
+        "Область:",
+        &ExportScope::NAMES,
+        export_scope_index.get(),
+        font,
+        {
+            let export_scope_index = export_scope_index.clone();
+            move |index| export_scope_index.set(index)
+        },
+    );
+
+    {
+        let export_scope_index = export_scope_index.clone();
+        let _export_button = dxf_panel.borrow_mut().add_child(
+            create_default_size_button("Экспорт в *.dxf", font.clone()).callback(callback!(
+                [editor, context]() {
+                    export_active_document(ExportFormat::Dxf, export_scope_index.get(), editor, context);
+                }
+            )),
+        );
+    }
+
+    {
+        let export_scope_index = export_scope_index.clone();
+        let _export_svg_button = dxf_panel.borrow_mut().add_child(
+            create_default_size_button("Экспорт в *.svg", font.clone()).callback(callback!(
+                [editor, context]() {
+                    export_active_document(ExportFormat::Svg, export_scope_index.get(), editor, context);
+                }
+            )),
+        );
+    }
+
+    {
+        let export_scope_index = export_scope_index.clone();
+        let _export_pdf_button = dxf_panel.borrow_mut().add_child(
+            create_default_size_button("Экспорт в *.pdf", font.clone()).callback(callback!(
+                [editor, context]() {
+                    export_active_document(ExportFormat::Pdf, export_scope_index.get(), editor, context);
+                }
+            )),
+        );
+    }
+
+    let _export_gerber_button = dxf_panel.borrow_mut().add_child(
+        create_default_size_button("Экспорт контуров в *.gbr", font.clone()).callback(callback!(
+            [editor, context]() {
+                export_active_document(ExportFormat::Gerber, export_scope_index.get(), editor, context);
+            }
+        )),
+    );
+
+    {
+        let font = font.clone();
+        let _export_all_svg_button = dxf_panel.borrow_mut().add_child(
+            create_default_size_button("Экспорт всех вкладок в *.svg...", font.clone()).callback(
+                callback!([editor, context]() {
+                    export_all_documents(ExportFormat::Svg, &font, editor, context);
+                }),
+            ),
+        );
+    }
+
+    {
+        let font = font.clone();
+        let _export_all_dxf_button = dxf_panel.borrow_mut().add_child(
+            create_default_size_button("Экспорт всех вкладок в *.dxf...", font.clone()).callback(
+                callback!([editor, context]() {
+                    export_all_documents(ExportFormat::Dxf, &font, editor, context);
+                }),
+            ),
+        );
+    }
+
+    let _import_points_button = dxf_panel.borrow_mut().add_child(
+        create_default_size_button("Импорт точек из *.csv", font.clone()).callback(callback!(
+            [editor, context]() {
+                import_points(editor, context);
+            }
+        )),
+    );
+
+    let _import_entities_button = dxf_panel.borrow_mut().add_child(
+        create_default_size_button("Импорт объектов из *.csv", font.clone()).callback(callback!(
+            [editor, context]() {
+                import_entities_csv(editor, context);
+            }
+        )),
+    );
+
+    let _export_entities_button = dxf_panel.borrow_mut().add_child(
+        create_default_size_button("Экспорт объектов в *.csv", font.clone()).callback(callback!(
+            [editor, context]() {
+                export_entities_csv(editor, context);
+            }
+        )),
+    );
+
+    {
+        let font = font.clone();
+        let _import_interchange_button = dxf_panel.borrow_mut().add_child(
+            create_default_size_button("Импорт из обменного *.json", font.clone()).callback(
+                callback!([editor, context]() {
+                    import_interchange(font.clone(), editor, context);
+                }),
+            ),
+        );
+    }
+
+    let _export_interchange_button = dxf_panel.borrow_mut().add_child(
+        create_default_size_button("Экспорт в обменный *.json", font.clone()).callback(callback!(
+            [editor, context]() {
+                export_interchange(editor, context);
+            }
+        )),
+    );
+
+    let _schema_button = dxf_panel.borrow_mut().add_child(
+        create_default_size_button("Схема обменного *.json...", font.clone()).callback(
+            callback!([context]() {
+                save_interchange_schema(context);
+            }),
+        ),
+    );
+
     file_menu
 }
 
@@ -121,3 +381,385 @@ pub fn new_file(font: Font, editor: Rc<RefCell<Editor>>) {
         .borrow_mut()
         .add_tab_by_existing_document(font, document_id, None);
 }
+
+pub fn open_file(font: Font, editor: Rc<RefCell<Editor>>, context: Rc<RefCell<window::Context>>) {
+    if let Some(path) = show_open_file_dialog(context.clone(), "Чертежи OtCAD", "json") {
+        open_path(font, &path, editor, context);
+    }
+}
+
+/// Shows another drawing under the active one, it is read again when its file changes.
+fn add_xref(editor: Rc<RefCell<Editor>>, context: Rc<RefCell<window::Context>>) {
+    if editor.borrow().get_active_document().is_none() {
+        return;
+    }
+    let path = match show_open_file_dialog(context.clone(), "Чертежи OtCAD", "json") {
+        Some(path) => path,
+        None => return,
+    };
+    let result = editor.borrow().add_xref(&path);
+    if let Err(e) = result {
+        show_message(
+            context,
+            &format!("{}\n{}", path.display(), e),
+            "Ошибка внешней ссылки",
+        );
+    }
+}
+
+pub fn open_path(
+    font: Font,
+    path: &Path,
+    editor: Rc<RefCell<Editor>>,
+    context: Rc<RefCell<window::Context>>,
+) {
+    if Document::is_encrypted_file(path) {
+        open_encrypted_path(font, path, editor, context);
+        return;
+    }
+    let result = editor.borrow_mut().open_document(path, None);
+    match result {
+        Ok(document_id) => {
+            editor
+                .borrow_mut()
+                .add_tab_by_existing_document(font.clone(), document_id, None);
+            load_document_entities(&font, document_id, editor, context);
+        }
+        Err(e) => show_message(
+            context,
+            &format!("{}\n{}", path.display(), e),
+            "Ошибка открытия файла",
+        ),
+    }
+}
+
+/// Asks for the password until the file is read or the dialog is cancelled.
+fn open_encrypted_path(
+    font: Font,
+    path: &Path,
+    editor: Rc<RefCell<Editor>>,
+    context: Rc<RefCell<window::Context>>,
+) {
+    let weak_editor = Rc::downgrade(&editor);
+    let weak_context = Rc::downgrade(&context);
+    let job_system = context.borrow().job_system.clone();
+    let path = path.to_path_buf();
+    let on_ok = {
+        let font = font.clone();
+        move |text: &str| {
+            let (editor, context) = match (weak_editor.upgrade(), weak_context.upgrade()) {
+                (Some(editor), Some(context)) => (editor, context),
+                _ => return Ok(()),
+            };
+            let document_id = editor.borrow_mut().open_document(&path, Some(text))?;
+            // the tab is added after the dialog is closed, so it stays selected
+            let font = font.clone();
+            job_system.add_callback(Rc::new(move || {
+                editor
+                    .borrow_mut()
+                    .add_tab_by_existing_document(font.clone(), document_id, None);
+                load_document_entities(&font, document_id, editor.clone(), context.clone());
+            }));
+            Ok(())
+        }
+    };
+    open_password_input_dialog(&font, editor, context, "Пароль чертежа", false, on_ok);
+}
+
+/// Empty password saves the drawing without encryption.
+fn open_password_dialog(
+    font: &Font,
+    editor: Rc<RefCell<Editor>>,
+    context: Rc<RefCell<window::Context>>,
+) {
+    let document = match editor.borrow().get_active_document() {
+        Some(document) => document,
+        None => return,
+    };
+    let weak_document = Rc::downgrade(&document);
+    let on_ok = move |text: &str| {
+        if let Some(document) = weak_document.upgrade() {
+            let password = Some(text).filter(|text| !text.is_empty());
+            document.borrow_mut().set_password(password);
+        }
+        Ok(())
+    };
+    open_password_input_dialog(font, editor, context, "Защита паролем", true, on_ok);
+}
+
+/// Asks for the file name if the document has no file or `save_as` is set.
+pub fn save_active_document(
+    save_as: bool,
+    editor: Rc<RefCell<Editor>>,
+    context: Rc<RefCell<window::Context>>,
+) {
+    let document = match editor.borrow().get_active_document() {
+        Some(document) => document,
+        None => return,
+    };
+    let path = match document.borrow().get_path() {
+        Some(path) if !save_as => Some(path.to_path_buf()),
+        _ => None,
+    };
+    let path = match path
+        .or_else(|| show_save_file_dialog(context.clone(), "Чертежи OtCAD", "json"))
+    {
+        Some(path) => path,
+        None => return,
+    };
+    if let Err(e) = editor.borrow().save_document(&document, &path) {
+        show_message(context, &e, "Ошибка сохранения файла");
+    }
+}
+
+/// Reloads the document from its file, the reload is postponed, because it can be
+/// asked from a toast while the gui is busy.
+fn reload_document(document: Rc<RefCell<Document>>, context: Rc<RefCell<window::Context>>) {
+    let job_system = context.borrow().job_system.clone();
+    job_system.add_callback(Rc::new(callback!([context] () {
+        let result = document.borrow_mut().reload();
+        let mut context = context.borrow_mut();
+        match result {
+            Ok(()) => context.gui_system.invalidate_all(),
+            Err(e) => context
+                .gui_system
+                .show_toast(Toast::error(&format!("Не удалось перечитать файл: {}", e))),
+        }
+    })));
+}
+
+pub fn revert_active_document(editor: Rc<RefCell<Editor>>, context: Rc<RefCell<window::Context>>) {
+    let document = match editor.borrow().get_active_document() {
+        Some(document) => document,
+        None => return,
+    };
+    if document.borrow().get_path().is_none() {
+        context
+            .borrow_mut()
+            .gui_system
+            .show_toast(Toast::error("Чертёж не сохранён в файл"));
+        return;
+    }
+    reload_document(document, context);
+}
+
+/// The document file was changed by another program, clicking the toast reloads it.
+pub fn offer_reload(document: Rc<RefCell<Document>>, context: Rc<RefCell<window::Context>>) {
+    let name = document
+        .borrow()
+        .get_path()
+        .and_then(|path| path.file_name())
+        .map_or(String::new(), |name| name.to_string_lossy().to_string());
+    let toast = if document.borrow().is_modified() {
+        Toast::warning(&format!(
+            "Файл {} изменён на диске. Нажмите, чтобы перечитать его, несохранённые изменения будут потеряны",
+            name
+        ))
+    } else {
+        Toast::info(&format!(
+            "Файл {} изменён на диске. Нажмите, чтобы перечитать его",
+            name
+        ))
+    };
+    let weak_context = Rc::downgrade(&context);
+    let toast = toast.action(move || {
+        if let Some(context) = weak_context.upgrade() {
+            reload_document(document.clone(), context);
+        }
+    });
+    context.borrow_mut().gui_system.show_toast(toast);
+}
+
+/// Asks for a CSV file and reads it, a failure is shown by a toast.
+fn read_csv_file(context: &Rc<RefCell<window::Context>>) -> Option<String> {
+    let path = show_open_file_dialog(context.clone(), "CSV", "csv")?;
+    match std::fs::read_to_string(&path) {
+        Ok(text) => Some(text),
+        Err(e) => {
+            let text = format!("Не удалось прочитать файл: {}", e);
+            let mut toast = Toast::error(&text);
+            if let Some(folder) = path.parent() {
+                // clicking the toast shows the file location
+                let shell = context.borrow().shell.clone();
+                let folder = folder.to_string_lossy().to_string();
+                toast = toast.action(move || {
+                    let _ = shell.open(&folder);
+                });
+            }
+            context.borrow_mut().gui_system.show_toast(toast);
+            None
+        }
+    }
+}
+
+pub fn import_points(editor: Rc<RefCell<Editor>>, context: Rc<RefCell<window::Context>>) {
+    let document = match editor.borrow().get_active_document() {
+        Some(document) => document,
+        None => return,
+    };
+    let text = match read_csv_file(&context) {
+        Some(text) => text,
+        None => return,
+    };
+    let toast = match parse_points_csv(&text) {
+        Ok(points) => {
+            document.borrow_mut().add_points(&points);
+            Toast::info(&format!("Импортировано точек: {}", points.len()))
+        }
+        Err(line) => Toast::error(&format!("Строка {} - не координаты точки!", line)),
+    };
+    context.borrow_mut().gui_system.show_toast(toast);
+}
+
+/// Units of the CSV table: the UCS of the document and the formats of the config.
+fn get_csv_units(editor: &Editor, document: &Document) -> CsvUnits {
+    let config = editor.config.borrow();
+    CsvUnits {
+        ucs: *document.get_ucs(),
+        number_format: config.number_format,
+        angle_format: config.angle_format,
+    }
+}
+
+pub fn import_entities_csv(editor: Rc<RefCell<Editor>>, context: Rc<RefCell<window::Context>>) {
+    let document = match editor.borrow().get_active_document() {
+        Some(document) => document,
+        None => return,
+    };
+    let text = match read_csv_file(&context) {
+        Some(text) => text,
+        None => return,
+    };
+    let units = get_csv_units(&editor.borrow(), &document.borrow());
+    let toast = match elements_from_csv(&text, &units) {
+        Ok(elements) => {
+            document.borrow_mut().paste_elements(&elements);
+            Toast::info(&format!("Импортировано объектов: {}", elements.len()))
+        }
+        Err(line) => Toast::error(&format!("Строка {} - не объект чертежа!", line)),
+    };
+    context.borrow_mut().gui_system.show_toast(toast);
+}
+
+/// Visible points, lines and arcs go to the table, in the units of the document.
+pub fn export_entities_csv(editor: Rc<RefCell<Editor>>, context: Rc<RefCell<window::Context>>) {
+    let document = match editor.borrow().get_active_document() {
+        Some(document) => document,
+        None => return,
+    };
+    let path = match show_save_file_dialog(context.clone(), "CSV", "csv") {
+        Some(path) => path,
+        None => return,
+    };
+    let units = get_csv_units(&editor.borrow(), &document.borrow());
+    let text = elements_to_csv(&document.borrow().get_visible_elements(), &units);
+    let toast = match std::fs::write(&path, text) {
+        Ok(()) => Toast::info(&format!("Экспортировано в {}", path.display())),
+        Err(e) => Toast::error(&format!("Не удалось экспортировать: {}", e)),
+    };
+    context.borrow_mut().gui_system.show_toast(toast);
+}
+
+/// The document of the interchange file opens in a new tab without a file name.
+pub fn import_interchange(
+    font: Font,
+    editor: Rc<RefCell<Editor>>,
+    context: Rc<RefCell<window::Context>>,
+) {
+    let path = match show_open_file_dialog(context.clone(), "Обменный JSON", "json") {
+        Some(path) => path,
+        None => return,
+    };
+    let result = std::fs::read_to_string(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|text| document_from_interchange(&text));
+    match result {
+        Ok(document) => {
+            let document_id = editor.borrow_mut().add_document(document);
+            editor
+                .borrow_mut()
+                .add_tab_by_existing_document(font, document_id, None);
+        }
+        Err(e) => show_message(
+            context,
+            &format!("{}\n{}", path.display(), e),
+            "Ошибка импорта",
+        ),
+    }
+}
+
+pub fn export_interchange(editor: Rc<RefCell<Editor>>, context: Rc<RefCell<window::Context>>) {
+    let document = match editor.borrow().get_active_document() {
+        Some(document) => document,
+        None => return,
+    };
+    let path = match show_save_file_dialog(context.clone(), "Обменный JSON", "json") {
+        Some(path) => path,
+        None => return,
+    };
+    let text = document_to_interchange(&document.borrow());
+    let toast = match std::fs::write(&path, text) {
+        Ok(()) => Toast::info(&format!("Экспортировано в {}", path.display())),
+        Err(e) => Toast::error(&format!("Не удалось экспортировать: {}", e)),
+    };
+    context.borrow_mut().gui_system.show_toast(toast);
+}
+
+/// Writes the JSON schema of the interchange files for other programs.
+pub fn save_interchange_schema(context: Rc<RefCell<window::Context>>) {
+    let path = match show_save_file_dialog(context.clone(), "Схема JSON", "json") {
+        Some(path) => path,
+        None => return,
+    };
+    let toast = match std::fs::write(&path, INTERCHANGE_SCHEMA) {
+        Ok(()) => Toast::info(&format!("Схема сохранена в {}", path.display())),
+        Err(e) => Toast::error(&format!("Не удалось сохранить схему: {}", e)),
+    };
+    context.borrow_mut().gui_system.show_toast(toast);
+}
+
+/// Scope index is in `ExportScope::NAMES`, the window is picked in the drawing first.
+pub fn export_active_document(
+    format: ExportFormat,
+    scope_index: usize,
+    editor: Rc<RefCell<Editor>>,
+    context: Rc<RefCell<window::Context>>,
+) {
+    let document = match editor.borrow().get_active_document() {
+        Some(document) => document,
+        None => return,
+    };
+    match scope_index {
+        0 => export_document(format, ExportScope::Document, &document, context),
+        1 => export_document(format, ExportScope::Selection, &document, context),
+        _ => {
+            context
+                .borrow_mut()
+                .gui_system
+                .show_toast(Toast::info("Выделите рамкой область экспорта"));
+            editor
+                .borrow()
+                .pick_rect(callback!([context] (corner1, corner2) {
+                    export_document(format, ExportScope::Window(corner1, corner2), &document, context);
+                }));
+        }
+    }
+}
+
+fn export_document(
+    format: ExportFormat,
+    scope: ExportScope,
+    document: &Rc<RefCell<Document>>,
+    context: Rc<RefCell<window::Context>>,
+) {
+    let path =
+        match show_save_file_dialog(context.clone(), format.get_name(), format.get_extension()) {
+            Some(path) => path,
+            None => return,
+        };
+    let toast = match export_to_file(&document.borrow(), format, scope, &path) {
+        Ok(()) => Toast::info(&format!("Экспортировано в {}", path.display())),
+        Err(e) => Toast::error(&format!("Не удалось экспортировать: {}", e)),
+    };
+    context.borrow_mut().gui_system.show_toast(toast);
+}
@@ -1,20 +1,186 @@
 use std::cell::RefCell;
 use std::rc::Rc;
 
+use application::callback;
+use application::callback_body;
 use application::font::*;
+use application::gui::completion::*;
 use application::gui::gui_components::*;
+use application::gui::toast::*;
 use application::gui::*;
 
+use crate::coordinates_dialog::*;
+use crate::document::*;
+use crate::editor::*;
 use crate::gui_helper::*;
 
-pub fn create_group_menu(parent: &mut TabControl, font: &Font) -> Rc<RefCell<Container>> {
+/// Asks the name and remembers the selection of the active document under it.
+fn save_selection_set(
+    font: &Font,
+    editor: Rc<RefCell<Editor>>,
+    context: Rc<RefCell<window::Context>>,
+) {
+    let document = match editor.borrow().get_active_document() {
+        Some(document) => document,
+        None => return,
+    };
+    if !document.borrow().has_selection() {
+        show_message(context, "Ничего не выделено", "Набор выделения");
+        return;
+    }
+    let sets = document.borrow().get_selection_sets();
+    let name = format!("Набор {}", sets.len() + 1);
+    // an existing name can be chosen to replace the set
+    let names = sets.into_iter().map(|(name, _)| name).collect();
+    let on_ok = move |text: &str| {
+        let name = text.trim();
+        if name.is_empty() {
+            return Err("Введите имя набора".to_string());
+        }
+        document.borrow_mut().save_selection_set(name);
+        Ok(())
+    };
+    let input = open_input_dialog(
+        font,
+        editor,
+        context,
+        "Набор выделения",
+        ("Имя:", &name),
+        on_ok,
+        None,
+    );
+    if let Some(input) = input {
+        let provider = CompletionProvider::prefix(names);
+        input.borrow_mut().set_completion_provider(provider);
+    }
+}
+
+/// Page with the selection sets of the document: each one can be selected, the removed
+/// ones are marked and removed by the "ОК" button.
+fn open_selection_sets_page(
+    font: &Font,
+    document: Rc<RefCell<Document>>,
+    editor: Rc<RefCell<Editor>>,
+    context: Rc<RefCell<window::Context>>,
+) {
+    let sets = document.borrow().get_selection_sets();
+    let removed = Rc::new(RefCell::new(vec![false; sets.len()]));
+    let font_height = font.get_size("8").1 as i32 + 2;
+    let line_size_constraints = SizeConstraints(
+        SizeConstraint::flexible(0),
+        SizeConstraint::fixed(font_height),
+    );
+    // the document tab is shown again when the page is closed
+    let document_tab_id = editor.borrow().get_selected_tab_id();
+    let tab_id = Rc::new(RefCell::new(None));
+
+    let mut page = Container::new(
+        SizeConstraints(SizeConstraint::flexible(0), SizeConstraint::flexible(0)),
+        ContainerLayout::Vertical,
+    );
+    if sets.is_empty() {
+        page.add_child(TextBox::new(
+            line_size_constraints,
+            "Наборов нет, запомните выделение кнопкой \"Запомнить выделение\"".to_string(),
+            font.clone(),
+        ));
+    }
+
+    let close_page = {
+        let tab_id = tab_id.clone();
+        callback!([editor] () {
+            if let Some(tab_id) = *tab_id.borrow() {
+                editor.borrow().close_tab(tab_id);
+            }
+            if let Some(document_tab_id) = document_tab_id {
+                editor.borrow().select_tab(document_tab_id);
+            }
+        })
+    };
+    let close_page = Rc::new(close_page);
+
+    for (index, (name, count)) in sets.iter().enumerate() {
+        create_section_caption(
+            &mut page,
+            &format!("{}: {} объектов", escape_accelerator(name), count),
+            font,
+        );
+        let line = page.add_child(Container::new(
+            line_size_constraints,
+            ContainerLayout::Horizontal,
+        ));
+        let name = name.clone();
+        let close_page = close_page.clone();
+        line.borrow_mut().add_child(
+            create_default_size_button("Выделить", font.clone()).callback(callback!(
+                [document, context] () {
+                    if document.borrow_mut().select_selection_set(&name) {
+                        close_page();
+                    } else {
+                        let text = "Объекты набора удалены, скрыты или заблокированы";
+                        context.borrow_mut().gui_system.show_toast(Toast::warning(text));
+                    }
+                }
+            )),
+        );
+        create_check_box(
+            &mut line.borrow_mut(),
+            "Удалить набор",
+            false,
+            font,
+            callback!([removed] (checked) {
+                removed.borrow_mut()[index] = checked;
+            }),
+        );
+    }
+
+    let buttons_line = page.add_child(Container::new(
+        line_size_constraints,
+        ContainerLayout::Horizontal,
+    ));
+    let ok_button = buttons_line
+        .borrow_mut()
+        .add_child(create_default_size_button("ОК", font.clone()));
+    let cancel_button = buttons_line
+        .borrow_mut()
+        .add_child(create_default_size_button("Отмена", font.clone()));
+
+    page.add_child(EmptySpace::new_empty(SizeConstraints(
+        SizeConstraint::flexible(0),
+        SizeConstraint::flexible(0),
+    )));
+
+    *tab_id.borrow_mut() = editor.borrow().add_page_tab(font, "Наборы выделения", page);
+
+    {
+        let close_page = close_page.clone();
+        ok_button
+            .borrow_mut()
+            .set_callback(callback!([document] () {
+                for ((name, _), removed) in sets.iter().zip(removed.borrow().iter()) {
+                    if *removed {
+                        document.borrow_mut().remove_selection_set(name);
+                    }
+                }
+                close_page();
+            }));
+    }
+    cancel_button
+        .borrow_mut()
+        .set_callback(move || close_page());
+}
+
+pub fn create_group_menu(
+    parent: &mut TabControl,
+    font: &Font,
+    editor: Rc<RefCell<Editor>>,
+    context: Rc<RefCell<window::Context>>,
+) -> Rc<RefCell<Container>> {
     let font_height = font.get_size("8").1 as i32 + 2;
-    let menu_caption = "Группы";
+    let menu_caption = "&Группы";
     let group_menu = parent.add_tab(
         menu_caption.to_string(),
-        GuiSystem::default_size(&menu_caption, None, &font)
-            .0
-            .absolute,
+        Button::default_size(&menu_caption, None, &font).0.absolute,
         Container::new(
             SizeConstraints(
                 SizeConstraint::flexible(0),
@@ -40,5 +206,37 @@ pub fn create_group_menu(parent: &mut TabControl, font: &Font) -> Rc<RefCell<Con
         .borrow_mut()
         .add_child(create_default_size_button("Исключить", font.clone()));
 
+    let _hr = group_menu
+        .borrow_mut()
+        .add_child(EmptySpace::new_splitter(SizeConstraints(
+            SizeConstraint::fixed(1),
+            SizeConstraint::flexible(0),
+        )));
+
+    {
+        let font = font.clone();
+        let _save_set_button = group_menu.borrow_mut().add_child(
+            create_default_size_button("Запомнить выделение...", font.clone()).callback(
+                callback!([editor, context] () {
+                    save_selection_set(&font, editor, context);
+                }),
+            ),
+        );
+    }
+
+    {
+        let font = font.clone();
+        let _sets_button = group_menu.borrow_mut().add_child(
+            create_default_size_button("Наборы...", font.clone()).callback(callback!(
+                [editor, context] () {
+                    let document = editor.borrow().get_active_document();
+                    if let Some(document) = document {
+                        open_selection_sets_page(&font, document, editor, context);
+                    }
+                }
+            )),
+        );
+    }
+
     group_menu
 }
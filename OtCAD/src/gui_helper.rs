@@ -1,8 +1,17 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use application::callback;
+use application::callback_body;
 use application::clipboard::*;
 use application::font::*;
 use application::gui::gui_components::*;
+use application::gui::message_box::*;
 use application::gui::*;
 use application::keys::*;
+use application::shell::*;
+
+use crate::editor::*;
 
 pub fn create_default_size_button(text: &str, font: Font) -> Button {
     Button::new(
@@ -34,6 +43,20 @@ pub fn create_default_size_check_button(text: &str, font: Font) -> Button {
     )
 }
 
+pub fn create_default_size_check_button_with_hotkey(
+    text: &str,
+    font: Font,
+    hotkey: Hotkey,
+    global: bool,
+) -> Button {
+    Button::new(
+        Button::default_checkbox_size(text, Some(hotkey), &font),
+        text.to_string(),
+        font,
+    )
+    .hotkey(hotkey, global)
+}
+
 pub fn create_default_size_radio_button(text: &str, font: Font) -> Button {
     Button::new(
         Button::default_radiobutton_size(text, None, &font),
@@ -46,6 +69,24 @@ pub fn create_default_size_edit(text: &str, font: Font, clipboard: Clipboard) ->
     Edit::new(GuiSystem::default_size(text, None, &font), font, clipboard).text(text)
 }
 
+/// Error of the first invalid input, they go in the order of the dialog.
+pub fn first_input_error(inputs: &[&Rc<RefCell<Edit>>]) -> Option<String> {
+    inputs
+        .iter()
+        .find_map(|input| input.borrow().validate().err())
+}
+
+/// Accepts numbers above zero.
+pub fn positive_number_validator() -> Validator {
+    Validator::parsed("Не число", |value: f64| {
+        if value > 0.0 && value.is_finite() {
+            Ok(())
+        } else {
+            Err("Число должно быть больше нуля".to_string())
+        }
+    })
+}
+
 pub fn create_default_size_text_box(text: &str, font: Font) -> TextBox {
     TextBox::new(
         GuiSystem::default_size(text, None, &font),
@@ -53,3 +94,149 @@ pub fn create_default_size_text_box(text: &str, font: Font) -> TextBox {
         font,
     )
 }
+
+pub fn create_default_size_link(text: &str, target: &str, font: Font, shell: Shell) -> Link {
+    Link::new(
+        GuiSystem::default_size(text, None, &font),
+        text.to_string(),
+        target.to_string(),
+        font,
+        shell,
+    )
+}
+
+pub fn create_section_caption(parent: &mut Container, caption: &str, font: &Font) {
+    let font_height = font.get_size("8").1 as i32 + 2;
+    parent.add_child(EmptySpace::new_splitter(SizeConstraints(
+        SizeConstraint::flexible(0),
+        SizeConstraint::fixed(1),
+    )));
+    parent.add_child(TextBox::new(
+        SizeConstraints(
+            SizeConstraint::flexible(0),
+            SizeConstraint::fixed(font_height),
+        ),
+        caption.to_string(),
+        font.clone(),
+    ));
+}
+
+pub fn create_selector(
+    parent: &mut Container,
+    caption: &str,
+    options: &[&str],
+    selected: usize,
+    font: &Font,
+    callback: impl Fn(usize) + 'static,
+) {
+    let font_height = font.get_size("8").1 as i32 + 2;
+    let selector = parent.add_child(RadioGroup::new(
+        SizeConstraints(
+            SizeConstraint::flexible(0),
+            SizeConstraint::fixed(font_height),
+        ),
+        ContainerLayout::Horizontal,
+        Some(create_default_size_text_box(caption, font.clone())),
+    ));
+
+    for option in options {
+        selector
+            .borrow_mut()
+            .add_button(create_default_size_radio_button(option, font.clone()));
+    }
+    selector.borrow_mut().set_id(selected);
+    selector.borrow_mut().set_change_tab_callback(callback);
+}
+
+pub fn create_check_box(
+    parent: &mut Container,
+    caption: &str,
+    checked: bool,
+    font: &Font,
+    callback: impl Fn(bool) + 'static,
+) {
+    parent.add_child(
+        create_default_size_check_button(caption, font.clone())
+            .check_box(checked)
+            .checkbox_callback(callback),
+    );
+}
+
+pub fn create_labeled_edit(
+    parent: &mut Container,
+    caption: &str,
+    caption_width: i32,
+    value: &str,
+    font: &Font,
+    context: &Rc<RefCell<window::Context>>,
+) -> Rc<RefCell<Edit>> {
+    let font_height = font.get_size("8").1 as i32 + 2;
+    let line = parent.add_child(Container::new(
+        SizeConstraints(
+            SizeConstraint::flexible(0),
+            SizeConstraint::fixed(font_height),
+        ),
+        ContainerLayout::Horizontal,
+    ));
+    line.borrow_mut().add_child(TextBox::new(
+        SizeConstraints(
+            SizeConstraint::fixed(caption_width),
+            SizeConstraint::fixed(font_height),
+        ),
+        caption.to_string(),
+        font.clone(),
+    ));
+    let edit = line.borrow_mut().add_child(Edit::new(
+        SizeConstraints(
+            SizeConstraint::flexible(0),
+            SizeConstraint::fixed(font_height),
+        ),
+        font.clone(),
+        context.borrow().clipboard.clone(),
+    ));
+    edit.borrow_mut().set_text(value);
+    edit
+}
+
+/// Warning in the message box of the window, the caller does not wait for it to be closed.
+pub fn show_message(context: Rc<RefCell<window::Context>>, text: &str, caption: &str) {
+    context.borrow_mut().gui_system.show_message_box(
+        MessageBox::new(caption, text).icon(MessageIcon::Warning),
+        |_| {},
+    );
+}
+
+/// Asks the question unless it was turned off by its check box, then the callback gets
+/// the default button at once.
+pub fn confirm(
+    id: &'static str,
+    message_box: MessageBox,
+    editor: &Rc<RefCell<Editor>>,
+    context: &Rc<RefCell<window::Context>>,
+    callback: impl Fn(usize) + 'static,
+) {
+    if editor
+        .borrow()
+        .config
+        .borrow()
+        .suppressed_confirmations
+        .contains(id)
+    {
+        callback(message_box.get_default_button());
+        return;
+    }
+    let message_box = message_box.check_box(
+        "Больше не спрашивать",
+        callback!([editor] (checked) {
+            if checked {
+                let mut config = editor.borrow().config.borrow().clone();
+                config.suppressed_confirmations.insert(id.to_string());
+                editor.borrow().set_config(config);
+            }
+        }),
+    );
+    context
+        .borrow_mut()
+        .gui_system
+        .show_message_box(message_box, callback);
+}
@@ -0,0 +1,449 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::document::*;
+use crate::ellipse::*;
+use crate::sheet::*;
+use crate::ucs::*;
+
+type Point = curves::points::Point<f64>;
+type Contour = curves::Contour<f64>;
+type Segment = curves::Segment<f64>;
+
+const FORMAT_NAME: &str = "ot_cad_interchange";
+const FORMAT_VERSION: u32 = 1;
+
+/// JSON schema of the interchange file, for the tools reading and writing it.
+pub const INTERCHANGE_SCHEMA: &str = include_str!("interchange_schema.json");
+
+/// Geometry of an entity in the world coordinates, angles are in degrees counterclockwise
+/// from the X axis, arcs go counterclockwise from the start angle to the end angle.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Geometry {
+    Point {
+        x: f64,
+        y: f64,
+    },
+    Line {
+        x1: f64,
+        y1: f64,
+        x2: f64,
+        y2: f64,
+    },
+    /// Goes through both points, from the first one to the second one.
+    InfiniteLine {
+        x1: f64,
+        y1: f64,
+        x2: f64,
+        y2: f64,
+    },
+    Circle {
+        x: f64,
+        y: f64,
+        radius: f64,
+    },
+    Arc {
+        x: f64,
+        y: f64,
+        radius: f64,
+        start_angle: f64,
+        end_angle: f64,
+    },
+    /// The major axis goes from the center to its end, the angles are the parameters of the
+    /// ellipse counterclockwise from the major axis.
+    Ellipse {
+        x: f64,
+        y: f64,
+        major_x: f64,
+        major_y: f64,
+        ratio: f64,
+        start_angle: f64,
+        end_angle: f64,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct InterchangeEntity {
+    id: usize,
+    #[serde(flatten)]
+    geometry: Geometry,
+    /// Line width in the document units, thin lines have none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    width: Option<f64>,
+    /// Entities with bigger order are drawn over the others.
+    #[serde(default)]
+    order: i64,
+    #[serde(default)]
+    hidden: bool,
+    #[serde(default)]
+    locked: bool,
+}
+
+/// Entities moved and selected together.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct InterchangeGroup {
+    id: usize,
+    members: Vec<usize>,
+}
+
+/// Stable form of the document for other programs: no undo history, no view and no
+/// settings of the program. The document has no layers and blocks, the groups are its
+/// only collections of entities.
+#[derive(Serialize, Deserialize, Debug)]
+struct Interchange {
+    format: String,
+    version: u32,
+    #[serde(default)]
+    metadata: DocumentMetadata,
+    #[serde(default)]
+    ucs: Ucs,
+    #[serde(default)]
+    entities: Vec<InterchangeEntity>,
+    #[serde(default)]
+    groups: Vec<InterchangeGroup>,
+}
+
+fn to_degrees(vector: Point) -> f64 {
+    vector.y.atan2(vector.x).to_degrees()
+}
+
+fn curve_geometry(curve: curves::Curve<f64>) -> Geometry {
+    match curve {
+        curves::Curve::Segment(s) if s.contour.a == 0.0 => Geometry::Line {
+            x1: s.begin.x,
+            y1: s.begin.y,
+            x2: s.end.x,
+            y2: s.end.y,
+        },
+        curves::Curve::Segment(s) => {
+            let center = s.contour.get_center();
+            // an inverted circle goes clockwise
+            let (begin, end) = if s.contour.a > 0.0 {
+                (s.begin, s.end)
+            } else {
+                (s.end, s.begin)
+            };
+            Geometry::Arc {
+                x: center.x,
+                y: center.y,
+                radius: s.contour.get_radius().abs(),
+                start_angle: to_degrees(begin - center),
+                end_angle: to_degrees(end - center),
+            }
+        }
+        curves::Curve::Contour(c) if c.a == 0.0 => {
+            // the nearest point to the origin and one more point along the line
+            let first = c.n.scale(-c.c);
+            let second = first - Point::new(c.n.y, -c.n.x);
+            Geometry::InfiniteLine {
+                x1: first.x,
+                y1: first.y,
+                x2: second.x,
+                y2: second.y,
+            }
+        }
+        curves::Curve::Contour(c) => {
+            let center = c.get_center();
+            Geometry::Circle {
+                x: center.x,
+                y: center.y,
+                radius: c.get_radius().abs(),
+            }
+        }
+    }
+}
+
+pub fn document_to_interchange(document: &Document) -> String {
+    let mut entities = Vec::new();
+    let mut groups = Vec::new();
+    for (id, element) in document.get_content() {
+        let (geometry, width, order) = match element {
+            Element::Curve(curve) => (curve_geometry(curve.curve), curve.width, curve.order),
+            Element::Point(point) => (
+                Geometry::Point {
+                    x: point.position.x,
+                    y: point.position.y,
+                },
+                None,
+                0,
+            ),
+            Element::Ellipse(ellipse) => {
+                let shape = ellipse.ellipse;
+                let geometry = Geometry::Ellipse {
+                    x: shape.center.x,
+                    y: shape.center.y,
+                    major_x: shape.major.x,
+                    major_y: shape.major.y,
+                    ratio: shape.ratio,
+                    start_angle: shape.start_angle.to_degrees(),
+                    end_angle: shape.end_angle.to_degrees(),
+                };
+                (geometry, ellipse.width, ellipse.order)
+            }
+            Element::Group(group) => {
+                let mut members: Vec<_> = group.ids.iter().copied().collect();
+                members.sort_unstable();
+                groups.push(InterchangeGroup { id: *id, members });
+                continue;
+            }
+        };
+        entities.push(InterchangeEntity {
+            id: *id,
+            geometry,
+            width,
+            order,
+            hidden: document.is_hidden(*id),
+            locked: document.is_locked(*id),
+        });
+    }
+    // the same document always gives the same text
+    entities.sort_unstable_by_key(|entity| entity.id);
+    groups.sort_unstable_by_key(|group| group.id);
+    let interchange = Interchange {
+        format: FORMAT_NAME.to_string(),
+        version: FORMAT_VERSION,
+        metadata: document.get_metadata().clone(),
+        ucs: *document.get_ucs(),
+        entities,
+        groups,
+    };
+    serde_json::to_string_pretty(&interchange).unwrap_or_default()
+}
+
+fn geometry_element(geometry: &Geometry) -> Result<Element, String> {
+    let curve = match *geometry {
+        Geometry::Point { x, y } => {
+            return Ok(Element::Point(PointEntity::new(Point::new(x, y))));
+        }
+        Geometry::Line { x1, y1, x2, y2 } => {
+            let (begin, end) = (Point::new(x1, y1), Point::new(x2, y2));
+            if begin == end {
+                return Err("у отрезка совпадают концы".to_string());
+            }
+            Curve::new_segment(Segment::line(begin, end))
+        }
+        Geometry::InfiniteLine { x1, y1, x2, y2 } => {
+            let (first, second) = (Point::new(x1, y1), Point::new(x2, y2));
+            if first == second {
+                return Err("у прямой совпадают точки".to_string());
+            }
+            Curve::new_contour(Contour::line(first, second))
+        }
+        Geometry::Circle { x, y, radius } => {
+            if radius <= 0.0 {
+                return Err("радиус окружности не больше нуля".to_string());
+            }
+            Curve::new_contour(Contour::circle(Point::new(x, y), radius))
+        }
+        Geometry::Arc {
+            x,
+            y,
+            radius,
+            start_angle,
+            end_angle,
+        } => {
+            if radius <= 0.0 {
+                return Err("радиус дуги не больше нуля".to_string());
+            }
+            let sweep = (end_angle - start_angle).rem_euclid(360.0);
+            if sweep == 0.0 {
+                return Err("у дуги совпадают углы".to_string());
+            }
+            let center = Point::new(x, y);
+            Curve::new_segment(Segment {
+                contour: Contour::circle(center, radius),
+                begin: center + Point::angle(start_angle.to_radians()).scale(radius),
+                end: center + Point::angle(end_angle.to_radians()).scale(radius),
+                big: sweep > 180.0,
+            })
+        }
+        Geometry::Ellipse {
+            x,
+            y,
+            major_x,
+            major_y,
+            ratio,
+            start_angle,
+            end_angle,
+        } => {
+            if major_x == 0.0 && major_y == 0.0 {
+                return Err("у эллипса нет большой оси".to_string());
+            }
+            if ratio <= 0.0 || ratio > 1.0 {
+                return Err("отношение осей эллипса не от 0 до 1".to_string());
+            }
+            if end_angle <= start_angle || end_angle - start_angle > 360.0 {
+                return Err("углы эллипса не дают дугу".to_string());
+            }
+            return Ok(Element::Ellipse(EllipseEntity::new(Ellipse {
+                center: Point::new(x, y),
+                major: Point::new(major_x, major_y),
+                ratio,
+                start_angle: start_angle.to_radians(),
+                end_angle: end_angle.to_radians(),
+            })));
+        }
+    };
+    Ok(Element::Curve(curve))
+}
+
+/// New document from the interchange text, the error tells what is wrong in it.
+pub fn document_from_interchange(text: &str) -> Result<Document, String> {
+    let interchange: Interchange = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    if interchange.format != FORMAT_NAME {
+        return Err(format!("Неизвестный формат \"{}\"", interchange.format));
+    }
+    if interchange.version > FORMAT_VERSION {
+        return Err(format!(
+            "Версия формата {} новее поддерживаемой {}",
+            interchange.version, FORMAT_VERSION
+        ));
+    }
+
+    let mut content = HashMap::new();
+    let mut hidden = HashSet::new();
+    let mut locked = HashSet::new();
+    for entity in &interchange.entities {
+        let mut element = geometry_element(&entity.geometry)
+            .map_err(|e| format!("Объект {}: {}", entity.id, e))?;
+        match &mut element {
+            Element::Curve(curve) => {
+                curve.width = entity.width;
+                curve.order = entity.order;
+            }
+            Element::Ellipse(ellipse) => {
+                ellipse.width = entity.width;
+                ellipse.order = entity.order;
+            }
+            _ => {}
+        }
+        if content.insert(entity.id, element).is_some() {
+            return Err(format!("Объект {} повторяется", entity.id));
+        }
+        if entity.hidden {
+            hidden.insert(entity.id);
+        }
+        if entity.locked {
+            locked.insert(entity.id);
+        }
+    }
+    for group in &interchange.groups {
+        if content.contains_key(&group.id) {
+            return Err(format!("Группа {} повторяет номер объекта", group.id));
+        }
+        for member in &group.members {
+            match content.get_mut(member) {
+                Some(Element::Curve(curve)) if curve.group_id.is_none() => {
+                    curve.group_id = Some(group.id);
+                }
+                _ => {
+                    return Err(format!(
+                        "Группа {}: объект {} не кривая или уже в группе",
+                        group.id, member
+                    ))
+                }
+            }
+        }
+        content.insert(
+            group.id,
+            Element::Group(Group {
+                ids: group.members.iter().copied().collect(),
+                selected: false,
+            }),
+        );
+    }
+
+    Ok(Document::from_content(
+        content,
+        hidden,
+        locked,
+        interchange.metadata,
+        interchange.ucs,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOLERANCE: f64 = 1.0e-9;
+
+    /// Numbers may differ by the rounding of the angles.
+    fn assert_same_json(a: &serde_json::Value, b: &serde_json::Value, path: &str) {
+        use serde_json::Value;
+        match (a, b) {
+            (Value::Number(a), Value::Number(b)) => {
+                let (a, b) = (a.as_f64().unwrap(), b.as_f64().unwrap());
+                assert!((a - b).abs() < TOLERANCE, "{}: {} != {}", path, a, b);
+            }
+            (Value::Array(a), Value::Array(b)) => {
+                assert_eq!(a.len(), b.len(), "{}", path);
+                for (index, (a, b)) in a.iter().zip(b).enumerate() {
+                    assert_same_json(a, b, &format!("{}[{}]", path, index));
+                }
+            }
+            (Value::Object(a), Value::Object(b)) => {
+                let mut keys: Vec<_> = a.keys().chain(b.keys()).collect();
+                keys.sort_unstable();
+                keys.dedup();
+                for key in keys {
+                    match (a.get(key), b.get(key)) {
+                        (Some(a), Some(b)) => assert_same_json(a, b, &format!("{}.{}", path, key)),
+                        (a, b) => panic!("{}.{}: {:?} != {:?}", path, key, a, b),
+                    }
+                }
+            }
+            _ => assert_eq!(a, b, "{}", path),
+        }
+    }
+
+    #[test]
+    fn import_and_export_keep_the_entities() {
+        let text = r#"{
+            "format": "ot_cad_interchange",
+            "version": 1,
+            "metadata": {"title": "Фланец", "drawing_number": "", "author": "", "date": ""},
+            "entities": [
+                {"id": 1, "type": "point", "x": 1.0, "y": 2.0, "order": 0, "hidden": false, "locked": false},
+                {"id": 2, "type": "line", "x1": 0.0, "y1": 0.0, "x2": 10.0, "y2": 5.0, "width": 0.5,
+                    "order": 3, "hidden": false, "locked": true},
+                {"id": 3, "type": "infinite_line", "x1": 0.0, "y1": 2.0, "x2": 1.0, "y2": 2.0,
+                    "order": 0, "hidden": true, "locked": false},
+                {"id": 4, "type": "circle", "x": 5.0, "y": 5.0, "radius": 2.5, "order": 0,
+                    "hidden": false, "locked": false},
+                {"id": 5, "type": "arc", "x": -3.0, "y": 1.0, "radius": 2.0, "start_angle": 30.0,
+                    "end_angle": -90.0, "order": 0, "hidden": false, "locked": false},
+                {"id": 6, "type": "ellipse", "x": 0.0, "y": -4.0, "major_x": 3.0, "major_y": 0.0,
+                    "ratio": 0.5, "start_angle": 0.0, "end_angle": 180.0, "width": 0.25, "order": 1,
+                    "hidden": false, "locked": false}
+            ],
+            "groups": [{"id": 7, "members": [2, 4]}]
+        }"#;
+
+        let document = document_from_interchange(text).unwrap();
+        assert_eq!(document.get_metadata().title, "Фланец");
+        assert!(document.is_locked(2));
+        assert!(document.is_hidden(3));
+
+        let exported = document_to_interchange(&document);
+        let expected: serde_json::Value = serde_json::from_str(text).unwrap();
+        let mut actual: serde_json::Value = serde_json::from_str(&exported).unwrap();
+        // the coordinate system is not in the text, it is the default one
+        assert_eq!(
+            serde_json::from_value::<Ucs>(actual["ucs"].clone()).unwrap(),
+            Ucs::default()
+        );
+        actual.as_object_mut().unwrap().remove("ucs");
+        assert_same_json(&actual, &expected, "");
+
+        // the exported text gives the same document again
+        let again = document_to_interchange(&document_from_interchange(&exported).unwrap());
+        assert_same_json(
+            &serde_json::from_str(&again).unwrap(),
+            &serde_json::from_str(&exported).unwrap(),
+            "",
+        );
+    }
+}
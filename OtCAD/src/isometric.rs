@@ -0,0 +1,105 @@
+use curves::points::{cross, dot};
+use serde::{Deserialize, Serialize};
+
+type Point = curves::points::Point<f64>;
+type Contour = curves::Contour<f64>;
+type Segment = curves::Segment<f64>;
+
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Copy, Clone)]
+pub enum Isoplane {
+    Left,
+    Top,
+    Right,
+}
+
+impl Default for Isoplane {
+    fn default() -> Self {
+        Isoplane::Top
+    }
+}
+
+const COS_30: f64 = 0.8660254037844386;
+
+/// Snap directions in degrees, counted counterclockwise from the X axis.
+pub const ISOMETRIC_SNAP_ANGLES: [f64; 3] = [30.0, 90.0, 150.0];
+
+impl Isoplane {
+    pub fn next(self) -> Self {
+        match self {
+            Isoplane::Left => Isoplane::Top,
+            Isoplane::Top => Isoplane::Right,
+            Isoplane::Right => Isoplane::Left,
+        }
+    }
+
+    /// Directions of the two axes of the plane, as they look on the screen (Y goes down).
+    pub fn get_axes(self) -> (Point, Point) {
+        let x_axis = Point::new(COS_30, -0.5);
+        let y_axis = Point::new(-COS_30, -0.5);
+        let z_axis = Point::new(0.0, -1.0);
+        match self {
+            Isoplane::Left => (y_axis, z_axis),
+            Isoplane::Top => (x_axis, y_axis),
+            Isoplane::Right => (x_axis, z_axis),
+        }
+    }
+}
+
+/// Nodes of the isometric grid are (k * step * cos30, m * step + k * step / 2).
+pub fn nearest_isometric_grid_point(position: Point, step: f64) -> Point {
+    let column_step = step * COS_30;
+    let k0 = (position.x / column_step).floor();
+    let mut result = Point::new(0.0, 0.0);
+    let mut sqr_dist = f64::MAX;
+    for k in [k0, k0 + 1.0] {
+        let m = ((position.y - k * step * 0.5) / step).round();
+        let candidate = Point::new(k * column_step, m * step + k * step * 0.5);
+        let candidate_dist = (candidate - position).sqr_length();
+        if candidate_dist < sqr_dist {
+            sqr_dist = candidate_dist;
+            result = candidate;
+        }
+    }
+    result
+}
+
+fn arc(center: Point, radius: f64, begin: Point, end: Point) -> Segment {
+    Segment {
+        contour: Contour::circle(center, radius),
+        begin,
+        end,
+        big: false,
+    }
+}
+
+/// Four arc approximation of a circle drawn on the isoplane. The arcs touch the sides
+/// of the isometric square in their middles.
+pub fn isometric_ellipse(center: Point, radius: f64, isoplane: Isoplane) -> [Segment; 4] {
+    let (mut a, mut b) = isoplane.get_axes();
+    // make the angle between axes obtuse and the order of arcs positive
+    if dot(a, b) > 0.0 {
+        b = b.neg();
+    }
+    if cross(a, b) < 0.0 {
+        std::mem::swap(&mut a, &mut b);
+    }
+
+    let touch1 = center + a.scale(radius);
+    let touch2 = center + b.scale(radius);
+    let touch3 = center - a.scale(radius);
+    let touch4 = center - b.scale(radius);
+
+    let big_center1 = center + (a + b).scale(radius);
+    let big_center2 = center - (a + b).scale(radius);
+    let small_center1 = center + (b - a).scale(radius / 3.0);
+    let small_center2 = center + (a - b).scale(radius / 3.0);
+    let big_radius = radius * 3f64.sqrt();
+    let small_radius = radius / 3f64.sqrt();
+
+    [
+        arc(big_center2, big_radius, touch1, touch2),
+        arc(small_center1, small_radius, touch2, touch3),
+        arc(big_center1, big_radius, touch3, touch4),
+        arc(small_center2, small_radius, touch4, touch1),
+    ]
+}
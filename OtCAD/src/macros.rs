@@ -0,0 +1,317 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
+
+use application::callback;
+use application::callback_body;
+use application::font::*;
+use application::gui::gui_components::*;
+use application::gui::*;
+use application::keys::*;
+
+use crate::command::*;
+use crate::coordinates_dialog::*;
+use crate::editor::*;
+use crate::gui_helper::*;
+
+/// Macros get Ctrl+Shift with one of these keys.
+const MACRO_KEYS: [Key; 9] = [
+    Key::Num1,
+    Key::Num2,
+    Key::Num3,
+    Key::Num4,
+    Key::Num5,
+    Key::Num6,
+    Key::Num7,
+    Key::Num8,
+    Key::Num9,
+];
+
+/// Recorded command, a transform may be done around the base point asked when the macro
+/// runs instead of the recorded one.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MacroStep {
+    pub command: Command,
+    #[serde(default)]
+    pub ask_base_point: bool,
+}
+
+/// Named sequence of commands, kept in the config.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Macro {
+    pub name: String,
+    pub steps: Vec<MacroStep>,
+    #[serde(default)]
+    pub hotkey: Option<Hotkey>,
+}
+
+/// Runs the steps from the first one on, a step asking for a point runs the rest after the
+/// point is picked.
+fn run_steps(editor: Rc<RefCell<Editor>>, steps: Rc<Vec<MacroStep>>, first: usize) {
+    for (index, step) in steps.iter().enumerate().skip(first) {
+        match &step.command {
+            Command::Transform(caption, transform) if step.ask_base_point => {
+                let (caption, transform) = (caption.clone(), *transform);
+                let pick = PointPick::new(&format!("{}: базовая точка", caption))
+                    .preview(move |base| (transform.with_base(base), String::new()));
+                let rest = steps.clone();
+                editor.borrow().pick_point(
+                    pick,
+                    callback!([editor] (base) {
+                        let command = Command::Transform(caption.clone(), transform.with_base(base));
+                        editor.borrow().run_command(command);
+                        run_steps(editor, rest.clone(), index + 1);
+                    }),
+                );
+                return;
+            }
+            command => editor.borrow().run_command(command.clone()),
+        }
+    }
+}
+
+pub fn run_macro(editor: Rc<RefCell<Editor>>, macro_: &Macro) {
+    run_steps(editor, Rc::new(macro_.steps.clone()), 0);
+}
+
+/// Asks for the name of the recorded macro and adds it to the config.
+fn save_recorded_macro(
+    font: &Font,
+    editor: Rc<RefCell<Editor>>,
+    context: Rc<RefCell<window::Context>>,
+) {
+    let commands = editor.borrow().stop_macro_recording();
+    if commands.is_empty() {
+        show_message(context, "Ни одной команды не записано", "Макрос");
+        return;
+    }
+    let steps: Vec<_> = commands
+        .into_iter()
+        .map(|command| MacroStep {
+            command,
+            ask_base_point: false,
+        })
+        .collect();
+    let name = format!(
+        "Макрос {}",
+        editor.borrow().config.borrow().macros.len() + 1
+    );
+    let weak_editor = Rc::downgrade(&editor);
+    let on_ok = move |text: &str| {
+        if text.is_empty() {
+            return Err("Введите имя макроса".to_string());
+        }
+        if let Some(editor) = weak_editor.upgrade() {
+            let mut config = editor.borrow().config.borrow().clone();
+            config.macros.push(Macro {
+                name: text.to_string(),
+                steps: steps.clone(),
+                hotkey: None,
+            });
+            editor.borrow().set_config(config);
+        }
+        Ok(())
+    };
+    open_input_dialog(
+        font,
+        editor,
+        context,
+        "Макрос",
+        ("Имя:", &name),
+        on_ok,
+        None,
+    );
+}
+
+/// Page with the macros of the config: their hotkeys, the steps asking for the base point
+/// and removing. Changes are applied by the "ОК" button.
+fn open_macros_page(font: &Font, editor: Rc<RefCell<Editor>>) {
+    let macros = Rc::new(RefCell::new(editor.borrow().config.borrow().macros.clone()));
+    let removed = Rc::new(RefCell::new(vec![false; macros.borrow().len()]));
+    let font_height = font.get_size("8").1 as i32 + 2;
+
+    let mut page = Container::new(
+        SizeConstraints(SizeConstraint::flexible(0), SizeConstraint::flexible(0)),
+        ContainerLayout::Vertical,
+    );
+    if macros.borrow().is_empty() {
+        page.add_child(TextBox::new(
+            SizeConstraints(
+                SizeConstraint::flexible(0),
+                SizeConstraint::fixed(font_height),
+            ),
+            "Макросов нет, запишите их кнопкой \"Запись\"".to_string(),
+            font.clone(),
+        ));
+    }
+
+    let mut key_options = vec!["нет".to_string()];
+    key_options.extend(MACRO_KEYS.iter().map(|key| format!("{:?}", key)));
+    let key_options: Vec<_> = key_options.iter().map(String::as_str).collect();
+
+    let macros_copy = macros.borrow().clone();
+    for (macro_index, macro_) in macros_copy.iter().enumerate() {
+        create_section_caption(&mut page, &escape_accelerator(&macro_.name), font);
+        let selected_key = macro_
+            .hotkey
+            .and_then(|hotkey| MACRO_KEYS.iter().position(|key| *key == hotkey.key))
+            .map_or(0, |position| position + 1);
+        create_selector(
+            &mut page,
+            "Ctrl+Shift+",
+            &key_options,
+            selected_key,
+            font,
+            callback!([macros] (index) {
+                macros.borrow_mut()[macro_index].hotkey =
+                    index.checked_sub(1).map(|index| Hotkey::ctrl_shift(MACRO_KEYS[index]));
+            }),
+        );
+        for (step_index, step) in macro_.steps.iter().enumerate() {
+            let caption = escape_accelerator(&step.command.get_caption());
+            match step.command {
+                Command::Transform(..) => create_check_box(
+                    &mut page,
+                    &format!("{}, спрашивать базовую точку", caption),
+                    step.ask_base_point,
+                    font,
+                    callback!([macros] (checked) {
+                        macros.borrow_mut()[macro_index].steps[step_index].ask_base_point = checked;
+                    }),
+                ),
+                _ => {
+                    page.add_child(create_default_size_text_box(&caption, font.clone()));
+                }
+            }
+        }
+        create_check_box(
+            &mut page,
+            "Удалить макрос",
+            false,
+            font,
+            callback!([removed] (checked) {
+                removed.borrow_mut()[macro_index] = checked;
+            }),
+        );
+    }
+
+    let buttons_line = page.add_child(Container::new(
+        SizeConstraints(
+            SizeConstraint::flexible(0),
+            SizeConstraint::fixed(font_height),
+        ),
+        ContainerLayout::Horizontal,
+    ));
+    let ok_button = buttons_line
+        .borrow_mut()
+        .add_child(create_default_size_button("ОК", font.clone()));
+    let cancel_button = buttons_line
+        .borrow_mut()
+        .add_child(create_default_size_button("Отмена", font.clone()));
+
+    page.add_child(EmptySpace::new_empty(SizeConstraints(
+        SizeConstraint::flexible(0),
+        SizeConstraint::flexible(0),
+    )));
+
+    let tab_id = match editor.borrow().add_page_tab(font, "Макросы", page) {
+        Some(tab_id) => tab_id,
+        None => return,
+    };
+
+    // the macros are owned by the callbacks, the controls only keep weak references
+    ok_button.borrow_mut().set_callback(callback!([editor] () {
+        let removed = removed.borrow();
+        let mut config = editor.borrow().config.borrow().clone();
+        config.macros = macros
+            .borrow()
+            .iter()
+            .zip(removed.iter())
+            .filter(|(_, removed)| !**removed)
+            .map(|(macro_, _)| macro_.clone())
+            .collect();
+        editor.borrow().close_tab(tab_id);
+        editor.borrow().set_config(config);
+    }));
+
+    cancel_button
+        .borrow_mut()
+        .set_callback(callback!([editor] () {
+            editor.borrow().close_tab(tab_id);
+        }));
+}
+
+pub fn create_macro_menu(
+    parent: &mut TabControl,
+    font: &Font,
+    editor: Rc<RefCell<Editor>>,
+    context: Rc<RefCell<window::Context>>,
+) -> Rc<RefCell<Container>> {
+    let font_height = font.get_size("8").1 as i32 + 2;
+    let menu_caption = "&Макросы";
+    let macro_menu = parent.add_tab(
+        menu_caption.to_string(),
+        Button::default_size(&menu_caption, None, &font).0.absolute,
+        Container::new(
+            SizeConstraints(
+                SizeConstraint::flexible(0),
+                SizeConstraint::fixed(font_height),
+            ),
+            ContainerLayout::Horizontal,
+        ),
+    );
+
+    {
+        let font = font.clone();
+        let recording = editor.borrow().is_recording_macro();
+        let _record_button = macro_menu.borrow_mut().add_child(
+            create_default_size_check_button("Запись", font.clone())
+                .check_box(recording)
+                .checkbox_callback(callback!([editor, context] (checked) {
+                    if checked {
+                        editor.borrow().start_macro_recording();
+                    } else {
+                        save_recorded_macro(&font, editor, context);
+                    }
+                })),
+        );
+    }
+
+    {
+        let font = font.clone();
+        let _macros_button = macro_menu.borrow_mut().add_child(
+            create_default_size_button("Настроить...", font.clone()).callback(callback!(
+                [editor]() {
+                    open_macros_page(&font, editor);
+                }
+            )),
+        );
+    }
+
+    let _hr = macro_menu
+        .borrow_mut()
+        .add_child(EmptySpace::new_splitter(SizeConstraints(
+            SizeConstraint::fixed(1),
+            SizeConstraint::flexible(0),
+        )));
+
+    let macros = editor.borrow().config.borrow().macros.clone();
+    for macro_ in macros {
+        let caption = escape_accelerator(&macro_.name);
+        let button = match macro_.hotkey {
+            Some(hotkey) => {
+                create_default_size_button_with_hotkey(&caption, font.clone(), hotkey, true)
+            }
+            None => create_default_size_button(&caption, font.clone()),
+        };
+        let _macro_button =
+            macro_menu
+                .borrow_mut()
+                .add_child(button.callback(callback!([editor] () {
+                    run_macro(editor, &macro_);
+                })));
+    }
+
+    macro_menu
+}
@@ -1,46 +1,158 @@
 use std::cell::RefCell;
+use std::path::PathBuf;
 use std::rc::Rc;
+use std::time::{Duration, Instant};
 
+use application::callback;
+use application::callback_body;
+use application::font::*;
 use application::gui::gui_components::*;
+use application::gui::toast::*;
 use application::gui::*;
+use application::image::*;
+use application::keys::*;
 
 use window::*;
 
 use bottom_panel::*;
 use config::*;
 use editor::*;
+use file_menu::{offer_reload, open_path};
+use settings_dialog::open_settings_dialog;
 use top_panel::*;
+use window_placement::*;
 
+mod batch_export;
 mod bottom_panel;
+mod chain;
+mod command;
+mod command_history;
+mod compare;
 mod config;
+mod coordinates_dialog;
 mod document;
+mod document_loading;
 mod draw_menu;
+mod dxf;
 mod edit_menu;
 mod editor;
+mod ellipse;
+mod encryption;
+mod entities_csv;
+mod export;
 mod file_menu;
 mod group_menu;
 mod gui_helper;
+mod interchange;
+mod isometric;
+mod macros;
 mod options_menu;
 mod picts;
+mod plot_style;
+mod plot_style_dialog;
+mod recent_files;
+mod settings_dialog;
+mod sheet;
+mod sheet_dialog;
+mod smart_guides;
 mod top_panel;
+mod transform;
+mod transform_gizmo;
 mod transform_menu;
+mod ucs;
+mod window_placement;
+
+enum InputLog {
+    Record(PathBuf),
+    Playback(PathBuf),
+}
+
+/// Command line: the input log options and the drawings to open.
+#[derive(Default)]
+struct Args {
+    input_log: Option<InputLog>,
+    files: Vec<PathBuf>,
+}
+
+fn parse_args() -> Args {
+    let mut result = Args::default();
+    let mut args = std::env::args_os().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.to_str() {
+            Some("--record") => {
+                result.input_log = args.next().map(|path| InputLog::Record(path.into()))
+            }
+            Some("--playback") => {
+                result.input_log = args.next().map(|path| InputLog::Playback(path.into()))
+            }
+            _ => {
+                let path = PathBuf::from(arg);
+                // the folder of the file is watched, so it has to be known
+                result
+                    .files
+                    .push(std::path::absolute(&path).unwrap_or(path));
+            }
+        }
+    }
+    return result;
+}
 
 struct GuiTest {
     editor: Rc<RefCell<Editor>>,
+    input_log: Option<InputLog>,
+    files: Vec<PathBuf>,
+    config_check_time: Instant,
+    /// Monitors for the key of the window place, asked again at most once a second.
+    monitors: Vec<MonitorInfo>,
+    monitors_check_time: Instant,
 }
 
 impl GuiTest {
-    fn new(config: Config) -> Self {
+    fn new(config: Config, args: Args, monitors: Vec<MonitorInfo>) -> Self {
         Self {
             editor: Rc::new(RefCell::new(Editor::new(config))),
+            input_log: args.input_log,
+            files: args.files,
+            config_check_time: Instant::now(),
+            monitors,
+            monitors_check_time: Instant::now(),
+        }
+    }
+
+    /// The window moves often while it is dragged, the monitors are not asked every time.
+    fn update_monitors(&mut self) {
+        if self.monitors_check_time.elapsed() < Duration::from_secs(1) {
+            return;
+        }
+        self.monitors_check_time = Instant::now();
+        self.monitors = get_monitors();
+    }
+
+    /// Picks up changes made to the config file by other instances or by hand.
+    fn reload_config_if_changed(&mut self) -> bool {
+        if self.config_check_time.elapsed() < Duration::from_secs(1) {
+            return false;
+        }
+        self.config_check_time = Instant::now();
+
+        let editor = self.editor.borrow();
+        if !editor.is_config_file_changed() {
+            return false;
+        }
+
+        if let Some(mut config) = load_config() {
+            config.window_positions = editor.config.borrow().window_positions.clone();
+            editor.set_config(config);
+            return true;
         }
+        return false;
     }
 
     fn rebuild_gui(
         editor: Rc<RefCell<Editor>>,
         context: Rc<RefCell<window::Context>>,
         top_panel_index: usize,
-    ) {
+    ) -> Font {
         let config = editor.borrow().config.clone();
         let font_size = config.borrow().font_size.0;
         let font_aa_mode = config.borrow().font_aa_mode;
@@ -52,11 +164,35 @@ impl GuiTest {
                 .new_font("MS Sans Serif", font_size, font_aa_mode);
         let font_height = default_font.get_size("8").1 as i32 + 2;
 
+        context.borrow_mut().gui_system.remove_regions();
         let root = context.borrow_mut().gui_system.set_root(Container::new(
             SizeConstraints(SizeConstraint::flexible(0), SizeConstraint::flexible(0)),
             ContainerLayout::Vertical,
         ));
 
+        editor.borrow().clear_config_listeners();
+        editor
+            .borrow()
+            .add_config_listener(callback!([editor, context] (old_config) {
+                let frame_pacing = editor.borrow().config.borrow().frame_pacing;
+                context.borrow_mut().set_frame_pacing(frame_pacing);
+                let presentation = editor.borrow().config.borrow().presentation;
+                context.borrow_mut().set_presentation(presentation);
+                if !editor.borrow().config.borrow().needs_gui_rebuild(old_config) {
+                    return;
+                }
+                // Listeners can be called from callbacks of the controls which are rebuilt
+                let job_system = context.borrow().job_system.clone();
+                job_system.add_callback(Rc::new(callback!([editor, context] () {
+                    let top_panel_index = editor
+                        .borrow()
+                        .top_panel
+                        .as_ref()
+                        .map_or(DRAW_MENU_INDEX, |top_panel| top_panel.borrow().selected_tab_id());
+                    Self::rebuild_gui(editor, context, top_panel_index);
+                })));
+            }));
+
         let _hr = root
             .borrow_mut()
             .add_child(EmptySpace::new_splitter(SizeConstraints(
@@ -64,42 +200,170 @@ impl GuiTest {
                 SizeConstraint::fixed(1),
             )));
 
-        let middle = root
-            .borrow_mut()
-            .add_child(TabControl::new(font_height, default_font.clone(), true).compressed());
+        // the settings page belongs to the old tab control, it is opened again in the new one
+        let settings_tab_id = editor.borrow().get_settings_tab_id();
+        let settings_selected =
+            settings_tab_id.is_some() && settings_tab_id == editor.borrow().get_selected_tab_id();
+
+        let middle = root.borrow_mut().add_child(
+            TabControl::new(font_height, default_font.clone(), true)
+                .compressed()
+                .navigation_hotkeys(),
+        );
+        editor
+            .borrow()
+            .set_file_watcher(context.borrow().file_watcher.clone());
         editor
             .borrow_mut()
             .set_tab_control(default_font.clone(), middle.clone());
 
-        create_top_panel(
+        let top_panel = create_top_panel(
             &mut root.borrow_mut(),
             &default_font,
             editor.clone(),
             context.clone(),
             top_panel_index,
         );
+        let menu_bar: Rc<RefCell<dyn GuiControl>> = top_panel.clone();
+        context.borrow_mut().gui_system.set_menu_bar(&menu_bar);
+        editor.borrow_mut().top_panel = Some(top_panel);
 
-        let _hr = root
+        context
+            .borrow_mut()
+            .gui_system
+            .set_color_theme(*get_gui_color_theme(&config.borrow()));
+        context
+            .borrow_mut()
+            .gui_system
+            .set_toast_font(default_font.clone());
+        context
+            .borrow_mut()
+            .gui_system
+            .set_tooltip_font(default_font.clone());
+        context
+            .borrow_mut()
+            .gui_system
+            .set_inspector_font(default_font.clone());
+        context
+            .borrow_mut()
+            .gui_system
+            .set_message_box_font(default_font.clone());
+        // the inspector of the controls helps to find the problems of the layout
+        let toggle_inspector = callback!([context] () {
+            let shown = context.borrow().gui_system.is_inspector_shown();
+            context.borrow_mut().gui_system.show_inspector(!shown);
+        });
+        context
+            .borrow_mut()
+            .gui_system
+            .add_global_hotkey(Hotkey::ctrl_shift(Key::I), Rc::new(toggle_inspector));
+
+        // the status bar is a separate region, so redrawing of the drawing doesn't touch it
+        let (status_region, _) = context.borrow_mut().gui_system.add_region(
+            RegionPlacement::Bottom(font_height + 1),
+            Container::new(
+                SizeConstraints(SizeConstraint::flexible(0), SizeConstraint::flexible(0)),
+                ContainerLayout::Vertical,
+            ),
+        );
+        let _hr = status_region
             .borrow_mut()
             .add_child(EmptySpace::new_splitter(SizeConstraints(
                 SizeConstraint::flexible(0),
                 SizeConstraint::fixed(1),
             )));
+        create_bottom_panel(
+            &mut status_region.borrow_mut(),
+            &default_font,
+            editor.clone(),
+            context.clone(),
+        );
 
-        context
-            .borrow_mut()
-            .gui_system
-            .set_color_theme(*get_gui_color_theme(&config.borrow()));
-        create_bottom_panel(&mut root.borrow_mut(), &default_font, config.clone());
+        if settings_tab_id.is_some() {
+            let selected_tab_id = editor.borrow().get_selected_tab_id();
+            open_settings_dialog(&default_font, editor.clone(), context.clone());
+            match selected_tab_id {
+                Some(tab_id) if !settings_selected => editor.borrow().select_tab(tab_id),
+                _ => {}
+            }
+        }
+
+        default_font
     }
 }
 
 impl window::Application for GuiTest {
     fn on_create(&mut self, context: Rc<RefCell<window::Context>>) {
-        for _ in 1..7 {
-            self.editor.borrow_mut().add_random_document();
+        if self.files.is_empty() {
+            for _ in 1..7 {
+                self.editor.borrow_mut().add_random_document();
+            }
+        }
+        let font = Self::rebuild_gui(self.editor.clone(), context.clone(), DRAW_MENU_INDEX);
+        let frame_pacing = self.editor.borrow().config.borrow().frame_pacing;
+        context.borrow_mut().set_frame_pacing(frame_pacing);
+        let presentation = self.editor.borrow().config.borrow().presentation;
+        context.borrow_mut().set_presentation(presentation);
+
+        // Escape stops the tool first, the next press drops the selection
+        for (priority, cancel) in [
+            (
+                ESCAPE_PRIORITY_TOOL,
+                Editor::cancel_tool as fn(&Editor) -> bool,
+            ),
+            (ESCAPE_PRIORITY_SELECTION, Editor::clear_selection),
+        ] {
+            let editor = Rc::downgrade(&self.editor);
+            context
+                .borrow_mut()
+                .gui_system
+                .add_escape_handler(priority, move || {
+                    editor
+                        .upgrade()
+                        .map_or(false, |editor| cancel(&editor.borrow()))
+                });
+        }
+
+        // the files are opened when the window is shown, so the error messages have an owner
+        let files = std::mem::take(&mut self.files);
+        if !files.is_empty() {
+            let editor = self.editor.clone();
+            let job_system = context.borrow().job_system.clone();
+            job_system.add_callback(Rc::new(callback!([editor, context]() {
+                for path in &files {
+                    open_path(font.clone(), path, editor.clone(), context.clone());
+                }
+            })));
+        }
+
+        let result = match &self.input_log {
+            Some(InputLog::Record(path)) => context.borrow_mut().start_recording(path),
+            Some(InputLog::Playback(path)) => context.borrow_mut().start_playback(path),
+            None => Ok(()),
+        };
+        if let Err(e) = result {
+            eprintln!("Input log error: {}", e);
+        }
+    }
+
+    fn on_timer(&mut self, context: Rc<RefCell<window::Context>>) -> bool {
+        let warnings = self.editor.borrow().take_document_warnings();
+        for warning in &warnings {
+            context
+                .borrow_mut()
+                .gui_system
+                .show_toast(Toast::warning(warning));
+        }
+        self.editor.borrow().update_memory_usage();
+        let changed_documents = self.editor.borrow().take_changed_on_disk_documents();
+        for document in changed_documents {
+            offer_reload(document, context.clone());
         }
-        Self::rebuild_gui(self.editor.clone(), context.clone(), DRAW_MENU_INDEX);
+        self.reload_config_if_changed()
+    }
+
+    fn get_taskbar_thumbnail(&mut self, max_size: ImageSize) -> Option<Image<u32>> {
+        self.editor.borrow().render_taskbar_thumbnail(max_size)
     }
 
     fn on_close(&mut self, _context: Rc<RefCell<window::Context>>) {
@@ -107,27 +371,29 @@ impl window::Application for GuiTest {
     }
 
     fn on_change_position(&mut self, window_position: WindowPosition) {
+        self.update_monitors();
         let config = self.editor.borrow_mut().config.clone();
-        let config = &mut config.borrow_mut();
-        let mut config_window_position = &mut config.window_position;
-        if window_position.maximized {
-            if let Some(config_window_position) = &mut config_window_position {
-                config_window_position.maximized = true;
-            } else {
-                *config_window_position = Some(window_position);
-            }
-        } else {
-            *config_window_position = Some(window_position);
-        }
+        let window_positions = &mut config.borrow_mut().window_positions;
+        let key = get_monitor_configuration_key(&self.monitors);
+        let saved = window_positions.get(&key).copied();
+        let position = update_position(saved, window_position, &self.monitors);
+        window_positions.insert(key, position);
     }
 }
 
 fn main() {
     let config = load_config().unwrap_or_default();
-    let window_position = config.window_position;
-    if let Err(_) =
-        window::run_application("ОтКАД", Box::new(GuiTest::new(config)), window_position)
-    {
+    let monitors = get_monitors();
+    // a place saved with other monitors may be out of the screen
+    let window_position = config
+        .window_positions
+        .get(&get_monitor_configuration_key(&monitors))
+        .and_then(|position| get_valid_position(*position, &monitors));
+    if let Err(_) = window::run_application(
+        "ОтКАД",
+        Box::new(GuiTest::new(config, parse_args(), monitors)),
+        window_position,
+    ) {
         // Do nothing, read message and exit
     }
 }
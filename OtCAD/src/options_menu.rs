@@ -7,11 +7,13 @@ use application::font::*;
 use application::gui::gui_components::*;
 use application::gui::*;
 
-use window::show_message;
+use application::gui::toast::*;
+use window::{show_open_file_dialog, show_save_file_dialog};
 
 use crate::config::*;
 use crate::editor::*;
 use crate::gui_helper::*;
+use crate::settings_dialog::*;
 use crate::GuiTest;
 use crate::OPTIONS_MENU_INDEX;
 
@@ -23,12 +25,10 @@ pub fn create_options_menu(
 ) -> Rc<RefCell<Container>> {
     let config = editor.borrow().config.clone();
     let font_height = font.get_size("8").1 as i32 + 2;
-    let menu_caption = "Опции";
+    let menu_caption = "&Опции";
     let options_menu = parent.add_tab(
         menu_caption.to_string(),
-        GuiSystem::default_size(&menu_caption, None, &font)
-            .0
-            .absolute,
+        Button::default_size(&menu_caption, None, &font).0.absolute,
         Container::new(
             SizeConstraints(SizeConstraint::flexible(0), SizeConstraint::fixed(0)),
             ContainerLayout::Vertical,
@@ -131,7 +131,7 @@ pub fn create_options_menu(
         });
     font_anti_aliasing_selector
         .borrow_mut()
-        .set_change_tab_callback(callback!([config, context] (aa_index) {
+        .set_change_tab_callback(callback!([config, context, editor] (aa_index) {
             let old_aa_index = config.borrow().font_aa_mode;
             match aa_index {
                 0 => config.borrow_mut().font_aa_mode = FontAntiAliasingMode::NoAA,
@@ -209,12 +209,30 @@ pub fn create_options_menu(
         .borrow_mut()
         .add_button(create_default_size_radio_button("Светлая", font.clone()));
 
+    let _color_blind_theme_button =
+        theme_selector
+            .borrow_mut()
+            .add_button(create_default_size_radio_button(
+                "Для дальтоников",
+                font.clone(),
+            ));
+
+    let _high_contrast_theme_button =
+        theme_selector
+            .borrow_mut()
+            .add_button(create_default_size_radio_button(
+                "Высокий контраст",
+                font.clone(),
+            ));
+
     theme_selector
         .borrow_mut()
         .set_id(match config.borrow().color_theme {
             ColorTheme::Dark => 0,
             ColorTheme::Beige => 1,
             ColorTheme::Light => 2,
+            ColorTheme::ColorBlind => 3,
+            ColorTheme::HighContrast => 4,
         });
     theme_selector.borrow_mut().set_change_tab_callback(
         callback!([config, context] (color_theme) {
@@ -222,6 +240,8 @@ pub fn create_options_menu(
                     0 => config.borrow_mut().color_theme = ColorTheme::Dark,
                     1 => config.borrow_mut().color_theme = ColorTheme::Beige,
                     2 => config.borrow_mut().color_theme = ColorTheme::Light,
+                    3 => config.borrow_mut().color_theme = ColorTheme::ColorBlind,
+                    4 => config.borrow_mut().color_theme = ColorTheme::HighContrast,
                     _ => {}
                 }
 
@@ -233,5 +253,75 @@ pub fn create_options_menu(
         ),
     );
 
+    {
+        let font = font.clone();
+        let _settings_button = options_menu.borrow_mut().add_child(
+            create_default_size_button("Все настройки...", font.clone()).callback(callback!(
+                [editor, context]() {
+                    open_settings_dialog(&font, editor, context);
+                }
+            )),
+        );
+    }
+
+    let _export_button = options_menu.borrow_mut().add_child(
+        create_default_size_button("Экспорт настроек...", font.clone()).callback(callback!(
+            [editor, context]() {
+                export_settings_to_file(&editor.borrow(), context.clone());
+            }
+        )),
+    );
+    let _import_button = options_menu.borrow_mut().add_child(
+        create_default_size_button("Импорт настроек...", font.clone()).callback(callback!(
+            [editor, context]() {
+                import_settings_from_file(&editor.borrow(), context.clone());
+            }
+        )),
+    );
+
+    if let Some(project_dir) = get_project_dir() {
+        let _config_folder_link = options_menu.borrow_mut().add_child(
+            create_default_size_link(
+                "Папка настроек",
+                &project_dir.config_dir().to_string_lossy(),
+                font.clone(),
+                context.borrow().shell.clone(),
+            )
+            .error_callback(callback!([context](e) {
+                let toast = Toast::error(&format!("Не удалось открыть папку: {}", e));
+                context.borrow_mut().gui_system.show_toast(toast);
+            })),
+        );
+    }
+
     options_menu
 }
+
+fn export_settings_to_file(editor: &Editor, context: Rc<RefCell<window::Context>>) {
+    let path = match show_save_file_dialog(context.clone(), "Настройки OtCAD", "json") {
+        Some(path) => path,
+        None => return,
+    };
+    if let Err(e) = export_settings(&editor.config.borrow(), &path) {
+        show_message(context, &e, "Ошибка экспорта настроек");
+    }
+}
+
+/// The gui is rebuilt by the config listener if the imported settings need it.
+fn import_settings_from_file(editor: &Editor, context: Rc<RefCell<window::Context>>) {
+    let path = match show_open_file_dialog(context.clone(), "Настройки OtCAD", "json") {
+        Some(path) => path,
+        None => return,
+    };
+    let result = import_settings(&editor.config.borrow(), &path);
+    match result {
+        Ok(config) => {
+            editor.set_config(config);
+            context
+                .borrow_mut()
+                .gui_system
+                .show_toast(Toast::info("Настройки загружены"));
+        }
+        Err(e) => show_message(context, &e, "Ошибка импорта настроек"),
+    }
+}
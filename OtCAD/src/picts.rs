@@ -2,6 +2,8 @@ use application::image::*;
 
 pub struct Picts {
     pub end_point: Image<bool>,
+    pub middle_point: Image<bool>,
+    pub quadrant_point: Image<bool>,
     pub tangent_point: Image<bool>,
     pub cross_point: Image<bool>,
     pub grid_point: Image<bool>,
@@ -13,6 +15,8 @@ impl Picts {
         let size = 17;
         let grid_point_size = 27;
         let mut end_point = Image::new((size, size));
+        let mut middle_point = Image::new((size, size));
+        let mut quadrant_point = Image::new((size, size));
         let mut tangent_point = Image::new((size, size));
         let mut cross_point = Image::new((size, size));
         let mut grid_point = Image::new((grid_point_size, grid_point_size));
@@ -22,6 +26,20 @@ impl Picts {
             .as_view_mut()
             .fill_with_coord(|p, (x, y)| *p = x <= 1 || x >= size - 2 || y <= 1 || y >= size - 2);
 
+        // triangle standing on its base
+        middle_point.as_view_mut().fill_with_coord(|p, (x, y)| {
+            let x = (x as i32 * 2 - (size as i32 - 1)).abs();
+            let y = y as i32;
+            *p = y >= size as i32 - 2 || (x - y >= -2 && x - y <= 0);
+        });
+
+        // rhombus
+        quadrant_point.as_view_mut().fill_with_coord(|p, (x, y)| {
+            let half = size as i32 / 2;
+            let distance = (x as i32 - half).abs() + (y as i32 - half).abs();
+            *p = distance >= half - 1 && distance <= half;
+        });
+
         cross_point.as_view_mut().fill_with_coord(|p, (x, y)| {
             *p = (x <= y + 1 && y <= x + 1) || (x + y >= size - 2 && x + y <= size)
         });
@@ -59,6 +77,8 @@ impl Picts {
 
         Self {
             end_point,
+            middle_point,
+            quadrant_point,
             tangent_point,
             cross_point,
             grid_point,
@@ -0,0 +1,140 @@
+use serde::{Deserialize, Serialize};
+
+use crate::document::Opacity;
+
+/// Kinds of lines which go to the paper, entities have no colors or layers, so the
+/// pens are chosen by where the line comes from.
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Copy, Clone)]
+pub enum OutputLineKind {
+    Model,
+    Viewport,
+    Sheet,
+}
+
+impl OutputLineKind {
+    pub const ALL: [OutputLineKind; 3] = [
+        OutputLineKind::Model,
+        OutputLineKind::Viewport,
+        OutputLineKind::Sheet,
+    ];
+
+    pub fn get_name(self) -> &'static str {
+        match self {
+            OutputLineKind::Model => "Модель",
+            OutputLineKind::Viewport => "Виды",
+            OutputLineKind::Sheet => "Рамка и штамп",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Copy, Clone)]
+pub enum DashPattern {
+    Solid,
+    Dashed,
+    Dotted,
+    DashDot,
+}
+
+impl Default for DashPattern {
+    fn default() -> Self {
+        DashPattern::Solid
+    }
+}
+
+impl DashPattern {
+    pub const ALL: [DashPattern; 4] = [
+        DashPattern::Solid,
+        DashPattern::Dashed,
+        DashPattern::Dotted,
+        DashPattern::DashDot,
+    ];
+
+    pub fn get_name(self) -> &'static str {
+        match self {
+            DashPattern::Solid => "Сплошная",
+            DashPattern::Dashed => "Штриховая",
+            DashPattern::Dotted => "Пунктир",
+            DashPattern::DashDot => "Штрихпунктир",
+        }
+    }
+
+    /// Lengths of dashes and gaps on the paper in millimeters, empty for solid lines.
+    pub fn get_lengths_mm(self) -> &'static [f64] {
+        match self {
+            DashPattern::Solid => &[],
+            DashPattern::Dashed => &[4.0, 2.0],
+            DashPattern::Dotted => &[0.5, 1.5],
+            DashPattern::DashDot => &[8.0, 2.0, 0.5, 2.0],
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Copy, Clone)]
+pub struct PlotStyle {
+    /// Pen width on the paper in millimeters.
+    pub width_mm: f64,
+    #[serde(default)]
+    pub dash: DashPattern,
+}
+
+impl PlotStyle {
+    pub fn solid(width_mm: f64) -> Self {
+        Self {
+            width_mm,
+            dash: DashPattern::Solid,
+        }
+    }
+}
+
+/// Pens of the output lines, stored in the document.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Copy, Clone)]
+pub struct PlotStyleTable {
+    pub model: PlotStyle,
+    pub viewport: PlotStyle,
+    pub sheet: PlotStyle,
+}
+
+impl Default for PlotStyleTable {
+    fn default() -> Self {
+        Self {
+            model: PlotStyle::solid(0.35),
+            viewport: PlotStyle::solid(0.25),
+            sheet: PlotStyle::solid(0.5),
+        }
+    }
+}
+
+impl PlotStyleTable {
+    pub fn get(&self, kind: OutputLineKind) -> PlotStyle {
+        match kind {
+            OutputLineKind::Model => self.model,
+            OutputLineKind::Viewport => self.viewport,
+            OutputLineKind::Sheet => self.sheet,
+        }
+    }
+
+    pub fn set(&mut self, kind: OutputLineKind, style: PlotStyle) {
+        match kind {
+            OutputLineKind::Model => self.model = style,
+            OutputLineKind::Viewport => self.viewport = style,
+            OutputLineKind::Sheet => self.sheet = style,
+        }
+    }
+}
+
+/// Curve as it goes to the output, with its pen.
+#[derive(Debug, Copy, Clone)]
+pub struct PlottedCurve {
+    pub curve: curves::Curve<f64>,
+    pub style: PlotStyle,
+    /// Width of the curve in the output coordinates, it overrides the width of the pen.
+    pub width: Option<f64>,
+    pub opacity: Opacity,
+}
+
+impl PlottedCurve {
+    /// Width in the output coordinates, `units_per_mm` converts the pen width to them.
+    pub fn get_width(&self, units_per_mm: f64) -> f64 {
+        self.width.unwrap_or(self.style.width_mm * units_per_mm)
+    }
+}
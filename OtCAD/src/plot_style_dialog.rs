@@ -0,0 +1,142 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use application::callback;
+use application::callback_body;
+use application::font::*;
+use application::gui::gui_components::*;
+use application::gui::*;
+
+use crate::editor::*;
+use crate::gui_helper::*;
+use crate::plot_style::*;
+
+/// Opens the pens of the active document for print and export.
+pub fn open_plot_style_dialog(
+    font: &Font,
+    editor: Rc<RefCell<Editor>>,
+    context: Rc<RefCell<window::Context>>,
+) {
+    let document = match editor.borrow().get_active_document() {
+        Some(document) => document,
+        None => return,
+    };
+
+    let plot_styles = Rc::new(RefCell::new(*document.borrow().get_plot_styles()));
+    let number_format = editor.borrow().config.borrow().number_format;
+
+    let font_height = font.get_size("8").1 as i32 + 2;
+    let caption_width = GuiSystem::default_size("Толщина, мм:", None, font)
+        .0
+        .absolute;
+
+    let mut page = Container::new(
+        SizeConstraints(SizeConstraint::flexible(0), SizeConstraint::flexible(0)),
+        ContainerLayout::Vertical,
+    );
+
+    let dash_names: Vec<_> = DashPattern::ALL.iter().map(|d| d.get_name()).collect();
+    let mut width_inputs = Vec::new();
+    for kind in OutputLineKind::ALL {
+        let style = plot_styles.borrow().get(kind);
+        create_section_caption(&mut page, kind.get_name(), font);
+        width_inputs.push((
+            kind,
+            create_labeled_edit(
+                &mut page,
+                "Толщина, мм:",
+                caption_width,
+                &number_format.format(style.width_mm),
+                font,
+                &context,
+            ),
+        ));
+        create_selector(
+            &mut page,
+            "Линия:",
+            &dash_names,
+            DashPattern::ALL
+                .iter()
+                .position(|d| *d == style.dash)
+                .unwrap_or(0),
+            font,
+            {
+                let plot_styles = plot_styles.clone();
+                move |index| {
+                    let mut plot_styles = plot_styles.borrow_mut();
+                    let mut style = plot_styles.get(kind);
+                    style.dash = DashPattern::ALL[index];
+                    plot_styles.set(kind, style);
+                }
+            },
+        );
+    }
+
+    page.add_child(EmptySpace::new_splitter(SizeConstraints(
+        SizeConstraint::flexible(0),
+        SizeConstraint::fixed(1),
+    )));
+
+    let buttons_line = page.add_child(Container::new(
+        SizeConstraints(
+            SizeConstraint::flexible(0),
+            SizeConstraint::fixed(font_height),
+        ),
+        ContainerLayout::Horizontal,
+    ));
+    let ok_button = buttons_line
+        .borrow_mut()
+        .add_child(create_default_size_button("ОК", font.clone()));
+    let cancel_button = buttons_line
+        .borrow_mut()
+        .add_child(create_default_size_button("Отмена", font.clone()));
+
+    page.add_child(EmptySpace::new_empty(SizeConstraints(
+        SizeConstraint::flexible(0),
+        SizeConstraint::flexible(0),
+    )));
+
+    let document_tab_id = editor.borrow().get_selected_tab_id();
+    let tab_id = match editor.borrow().add_page_tab(font, "Стили печати", page) {
+        Some(tab_id) => tab_id,
+        None => return,
+    };
+
+    let close = Rc::new(callback!([editor] () {
+        editor.borrow().close_tab(tab_id);
+        if let Some(document_tab_id) = document_tab_id {
+            editor.borrow().select_tab(document_tab_id);
+        }
+    }));
+
+    {
+        let close = close.clone();
+        ok_button
+            .borrow_mut()
+            .set_callback(callback!([context, document] () {
+                let mut result = *plot_styles.borrow();
+                for (kind, input) in &width_inputs {
+                    let text = input.borrow().get_text().to_string();
+                    match number_format.parse(&text) {
+                        Some(width_mm) if width_mm > 0.0 => {
+                            let mut style = result.get(*kind);
+                            style.width_mm = width_mm;
+                            result.set(*kind, style);
+                        }
+                        _ => {
+                            show_message(
+                                context.clone(),
+                                &format!("{} - не подходит для толщины линии!", text),
+                                "Ошибка ввода",
+                            );
+                            return;
+                        }
+                    }
+                }
+                document.borrow_mut().set_plot_styles(result);
+                close();
+            }));
+    }
+
+    cancel_button.borrow_mut().set_callback(move || close());
+}
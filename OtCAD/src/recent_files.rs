@@ -0,0 +1,117 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use application::callback;
+use application::callback_body;
+use application::font::*;
+use application::gui::gui_components::*;
+use application::gui::tooltip::*;
+use application::gui::*;
+
+use crate::editor::*;
+use crate::file_menu::*;
+
+/// Thumbnails of the recent files in a row.
+const COLUMNS: usize = 5;
+
+/// Page with the thumbnails of the recent files, a click opens the file.
+pub fn open_recent_files_page(
+    font: &Font,
+    editor: Rc<RefCell<Editor>>,
+    context: Rc<RefCell<window::Context>>,
+) {
+    let recent_files: Vec<_> = editor
+        .borrow()
+        .config
+        .borrow()
+        .recent_files
+        .iter()
+        .filter(|path| path.exists())
+        .cloned()
+        .collect();
+
+    let font_height = font.get_size("8").1 as i32 + 2;
+    let cell_width = THUMBNAIL_SIZE.0 as i32 + font_height;
+    let mut page = Container::new(
+        SizeConstraints(SizeConstraint::flexible(0), SizeConstraint::flexible(0)),
+        ContainerLayout::Vertical,
+    );
+    if recent_files.is_empty() {
+        page.add_child(TextBox::new(
+            SizeConstraints(
+                SizeConstraint::flexible(0),
+                SizeConstraint::fixed(font_height),
+            ),
+            "Недавно открытых файлов нет".to_string(),
+            font.clone(),
+        ));
+    }
+
+    let mut buttons = Vec::new();
+    for row_files in recent_files.chunks(COLUMNS) {
+        let row = page.add_child(Container::new(
+            SizeConstraints(
+                SizeConstraint::flexible(0),
+                SizeConstraint::fixed(THUMBNAIL_SIZE.1 as i32 + font_height),
+            ),
+            ContainerLayout::Horizontal,
+        ));
+        for path in row_files {
+            let name = path
+                .file_name()
+                .map_or(String::new(), |name| name.to_string_lossy().to_string());
+            let cell = row.borrow_mut().add_child(Container::new(
+                SizeConstraints(
+                    SizeConstraint::fixed(cell_width),
+                    SizeConstraint::flexible(0),
+                ),
+                ContainerLayout::Vertical,
+            ));
+            cell.borrow_mut().add_child(Picture::new(
+                SizeConstraints(
+                    SizeConstraint::flexible(0),
+                    SizeConstraint::fixed(THUMBNAIL_SIZE.1 as i32),
+                ),
+                editor.borrow().get_thumbnail(path),
+                &name,
+            ));
+            let button = cell.borrow_mut().add_child(
+                Button::new(
+                    SizeConstraints(
+                        SizeConstraint::flexible(0),
+                        SizeConstraint::fixed(font_height),
+                    ),
+                    escape_accelerator(&name),
+                    font.clone(),
+                )
+                .tooltip(Tooltip::new(&path.to_string_lossy())),
+            );
+            buttons.push((button, path.clone()));
+        }
+        row.borrow_mut()
+            .add_child(EmptySpace::new_empty(SizeConstraints(
+                SizeConstraint::flexible(0),
+                SizeConstraint::flexible(0),
+            )));
+    }
+
+    page.add_child(EmptySpace::new_empty(SizeConstraints(
+        SizeConstraint::flexible(0),
+        SizeConstraint::flexible(0),
+    )));
+
+    let tab_id = match editor.borrow().add_page_tab(font, "Недавние файлы", page) {
+        Some(tab_id) => tab_id,
+        None => return,
+    };
+
+    for (button, path) in buttons {
+        let font = font.clone();
+        button
+            .borrow_mut()
+            .set_callback(callback!([editor, context] () {
+                editor.borrow().close_tab(tab_id);
+                open_path(font.clone(), &path, editor, context);
+            }));
+    }
+}
@@ -0,0 +1,712 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use application::angle_format::*;
+use application::callback;
+use application::callback_body;
+use application::font::*;
+use application::gui::gui_components::*;
+use application::gui::*;
+
+use window::Presentation;
+
+use crate::config::*;
+use crate::editor::*;
+use crate::gui_helper::*;
+
+/// Limits of the frame rate in the selector, zero is no limit.
+const FRAME_RATES: [u32; 4] = [30, 60, 120, 0];
+
+/// Opens the settings as a page in the document tabs. Changes are collected in a copy
+/// of the config and applied all at once by the "ОК" button.
+pub fn open_settings_dialog(
+    font: &Font,
+    editor: Rc<RefCell<Editor>>,
+    context: Rc<RefCell<window::Context>>,
+) {
+    if editor.borrow().select_settings_tab() {
+        return;
+    }
+
+    let settings = Rc::new(RefCell::new(editor.borrow().config.borrow().clone()));
+    let font_height = font.get_size("8").1 as i32 + 2;
+    let line_size_constraints = SizeConstraints(
+        SizeConstraint::flexible(0),
+        SizeConstraint::fixed(font_height),
+    );
+
+    let mut page = Container::new(
+        SizeConstraints(SizeConstraint::flexible(0), SizeConstraint::flexible(0)),
+        ContainerLayout::Vertical,
+    );
+
+    create_section_caption(&mut page, "Внешний вид", font);
+
+    let font_size_line = page.add_child(Container::new(
+        line_size_constraints,
+        ContainerLayout::Horizontal,
+    ));
+    font_size_line
+        .borrow_mut()
+        .add_child(create_default_size_text_box("Размер шрифта:", font.clone()));
+    let font_size_input = font_size_line
+        .borrow_mut()
+        .add_child(create_default_size_edit(
+            "8888",
+            font.clone(),
+            context.borrow().clipboard.clone(),
+        ));
+    font_size_input
+        .borrow_mut()
+        .set_text(&format!("{}", settings.borrow().font_size.0));
+    font_size_input
+        .borrow_mut()
+        .set_validator(Validator::parsed("Не целое число", |_: i32| {
+            Ok(())
+        }));
+
+    let s = settings.borrow().clone();
+    create_selector(
+        &mut page,
+        "Сглаживание шрифта:",
+        &["Нету", "Пиксельное", "Субпиксельное (true type)"],
+        match s.font_aa_mode {
+            FontAntiAliasingMode::NoAA => 0,
+            FontAntiAliasingMode::AA => 1,
+            FontAntiAliasingMode::TT => 2,
+        },
+        font,
+        {
+            let settings = settings.clone();
+            move |index| {
+                settings.borrow_mut().font_aa_mode = match index {
+                    0 => FontAntiAliasingMode::NoAA,
+                    1 => FontAntiAliasingMode::AA,
+                    _ => FontAntiAliasingMode::TT,
+                };
+            }
+        },
+    );
+
+    create_selector(
+        &mut page,
+        "Цветовая тема:",
+        &[
+            "Тёмная",
+            "Бежевая",
+            "Светлая",
+            "Для дальтоников",
+            "Высокий контраст",
+        ],
+        match s.color_theme {
+            ColorTheme::Dark => 0,
+            ColorTheme::Beige => 1,
+            ColorTheme::Light => 2,
+            ColorTheme::ColorBlind => 3,
+            ColorTheme::HighContrast => 4,
+        },
+        font,
+        {
+            let settings = settings.clone();
+            move |index| {
+                settings.borrow_mut().color_theme = match index {
+                    0 => ColorTheme::Dark,
+                    1 => ColorTheme::Beige,
+                    2 => ColorTheme::Light,
+                    3 => ColorTheme::ColorBlind,
+                    _ => ColorTheme::HighContrast,
+                };
+            }
+        },
+    );
+
+    create_selector(
+        &mut page,
+        "Вид точек:",
+        &["Точка", "Крест", "Кружок"],
+        match s.point_style {
+            PointStyle::Dot => 0,
+            PointStyle::Cross => 1,
+            PointStyle::Circle => 2,
+        },
+        font,
+        {
+            let settings = settings.clone();
+            move |index| {
+                settings.borrow_mut().point_style = match index {
+                    0 => PointStyle::Dot,
+                    1 => PointStyle::Cross,
+                    _ => PointStyle::Circle,
+                };
+            }
+        },
+    );
+
+    create_selector(
+        &mut page,
+        "Выделение:",
+        &["Толще", "Цветом", "Пунктиром", "Ореолом"],
+        match s.selection_style {
+            SelectionStyle::Thick => 0,
+            SelectionStyle::Color => 1,
+            SelectionStyle::Dashed => 2,
+            SelectionStyle::Halo => 3,
+        },
+        font,
+        {
+            let settings = settings.clone();
+            move |index| {
+                settings.borrow_mut().selection_style = match index {
+                    0 => SelectionStyle::Thick,
+                    1 => SelectionStyle::Color,
+                    2 => SelectionStyle::Dashed,
+                    _ => SelectionStyle::Halo,
+                };
+            }
+        },
+    );
+
+    create_selector(
+        &mut page,
+        "Подсветка под курсором:",
+        &["Цветом", "Цветом и толще", "Нет"],
+        match s.hover_style {
+            HoverStyle::Color => 0,
+            HoverStyle::ThickColor => 1,
+            HoverStyle::Off => 2,
+        },
+        font,
+        {
+            let settings = settings.clone();
+            move |index| {
+                settings.borrow_mut().hover_style = match index {
+                    0 => HoverStyle::Color,
+                    1 => HoverStyle::ThickColor,
+                    _ => HoverStyle::Off,
+                };
+            }
+        },
+    );
+
+    create_selector(
+        &mut page,
+        "Перекрестие курсора:",
+        &["Нет", "Линии", "Пунктир"],
+        match s.crosshair {
+            CrosshairStyle::Off => 0,
+            CrosshairStyle::Solid => 1,
+            CrosshairStyle::Dashed => 2,
+        },
+        font,
+        {
+            let settings = settings.clone();
+            move |index| {
+                settings.borrow_mut().crosshair = match index {
+                    0 => CrosshairStyle::Off,
+                    1 => CrosshairStyle::Solid,
+                    _ => CrosshairStyle::Dashed,
+                };
+            }
+        },
+    );
+
+    create_section_caption(&mut page, "Сетка", font);
+
+    let grid_line = page.add_child(Container::new(
+        line_size_constraints,
+        ContainerLayout::Horizontal,
+    ));
+    create_check_box(
+        &mut grid_line.borrow_mut(),
+        "Показать сетку",
+        s.show_grid,
+        font,
+        {
+            let settings = settings.clone();
+            move |c| {
+                settings.borrow_mut().show_grid = c;
+            }
+        },
+    );
+
+    create_section_caption(&mut page, "Привязки", font);
+
+    let snap_line = page.add_child(Container::new(
+        line_size_constraints,
+        ContainerLayout::Horizontal,
+    ));
+    create_check_box(
+        &mut snap_line.borrow_mut(),
+        "Узлы сетки",
+        s.snap_options.snap_grid,
+        font,
+        {
+            let settings = settings.clone();
+            move |c| {
+                settings.borrow_mut().snap_options.snap_grid = c;
+            }
+        },
+    );
+    create_check_box(
+        &mut snap_line.borrow_mut(),
+        "Концы",
+        s.snap_options.snap_endpoints,
+        font,
+        {
+            let settings = settings.clone();
+            move |c| {
+                settings.borrow_mut().snap_options.snap_endpoints = c;
+            }
+        },
+    );
+    create_check_box(
+        &mut snap_line.borrow_mut(),
+        "Середины",
+        s.snap_options.snap_midpoints,
+        font,
+        {
+            let settings = settings.clone();
+            move |c| {
+                settings.borrow_mut().snap_options.snap_midpoints = c;
+            }
+        },
+    );
+    create_check_box(
+        &mut snap_line.borrow_mut(),
+        "Пересечения",
+        s.snap_options.snap_crosses,
+        font,
+        {
+            let settings = settings.clone();
+            move |c| {
+                settings.borrow_mut().snap_options.snap_crosses = c;
+            }
+        },
+    );
+    create_check_box(
+        &mut snap_line.borrow_mut(),
+        "Центры дуг",
+        s.snap_options.snap_centers,
+        font,
+        {
+            let settings = settings.clone();
+            move |c| {
+                settings.borrow_mut().snap_options.snap_centers = c;
+            }
+        },
+    );
+    create_check_box(
+        &mut snap_line.borrow_mut(),
+        "Квадранты",
+        s.snap_options.snap_quadrants,
+        font,
+        {
+            let settings = settings.clone();
+            move |c| {
+                settings.borrow_mut().snap_options.snap_quadrants = c;
+            }
+        },
+    );
+    create_check_box(
+        &mut snap_line.borrow_mut(),
+        "Точки",
+        s.snap_options.snap_points,
+        font,
+        {
+            let settings = settings.clone();
+            move |c| {
+                settings.borrow_mut().snap_options.snap_points = c;
+            }
+        },
+    );
+    create_check_box(
+        &mut snap_line.borrow_mut(),
+        "Направляющие",
+        s.snap_options.smart_guides,
+        font,
+        {
+            let settings = settings.clone();
+            move |c| {
+                settings.borrow_mut().snap_options.smart_guides = c;
+            }
+        },
+    );
+
+    const POLAR_STEPS: [f64; 6] = [0.0, 5.0, 15.0, 30.0, 45.0, 90.0];
+    create_selector(
+        &mut page,
+        "Шаг полярного отслеживания:",
+        &["Нет", "5°", "15°", "30°", "45°", "90°"],
+        POLAR_STEPS
+            .iter()
+            .position(|step| *step == s.polar_step.0)
+            .unwrap_or(0),
+        font,
+        {
+            let settings = settings.clone();
+            move |index| {
+                settings.borrow_mut().polar_step = PolarStep(POLAR_STEPS[index]);
+            }
+        },
+    );
+
+    let pick_line = page.add_child(Container::new(
+        line_size_constraints,
+        ContainerLayout::Horizontal,
+    ));
+    let add_radius_input = |caption: &str, value: f64| {
+        pick_line
+            .borrow_mut()
+            .add_child(create_default_size_text_box(caption, font.clone()));
+        let input = pick_line.borrow_mut().add_child(create_default_size_edit(
+            "8888",
+            font.clone(),
+            context.borrow().clipboard.clone(),
+        ));
+        input.borrow_mut().set_text(&format!("{}", value));
+        input
+            .borrow_mut()
+            .set_validator(positive_number_validator());
+        input
+    };
+    let pick_radius_input = add_radius_input("Радиус выбора:", s.pick_options.pick_radius);
+    let snap_radius_input = add_radius_input("Радиус привязки:", s.pick_options.snap_radius);
+
+    create_section_caption(&mut page, "Числа", font);
+
+    create_selector(
+        &mut page,
+        "Десятичный разделитель:",
+        &["Системный", "Точка", "Запятая"],
+        match s.number_format.decimal_separator {
+            None => 0,
+            Some(',') => 2,
+            Some(_) => 1,
+        },
+        font,
+        {
+            let settings = settings.clone();
+            move |index| {
+                settings.borrow_mut().number_format.decimal_separator = match index {
+                    0 => None,
+                    1 => Some('.'),
+                    _ => Some(','),
+                };
+            }
+        },
+    );
+
+    const PRECISIONS: [usize; 5] = [0, 1, 2, 3, 6];
+    create_selector(
+        &mut page,
+        "Знаков после запятой:",
+        &["0", "1", "2", "3", "6"],
+        PRECISIONS
+            .iter()
+            .position(|p| *p == s.number_format.precision)
+            .unwrap_or(3),
+        font,
+        {
+            let settings = settings.clone();
+            move |index| {
+                settings.borrow_mut().number_format.precision = PRECISIONS[index];
+            }
+        },
+    );
+
+    let trailing_zeros_line = page.add_child(Container::new(
+        line_size_constraints,
+        ContainerLayout::Horizontal,
+    ));
+    create_check_box(
+        &mut trailing_zeros_line.borrow_mut(),
+        "Убирать нули в конце",
+        s.number_format.trim_trailing_zeros,
+        font,
+        {
+            let settings = settings.clone();
+            move |c| {
+                settings.borrow_mut().number_format.trim_trailing_zeros = c;
+            }
+        },
+    );
+
+    create_section_caption(&mut page, "Углы", font);
+
+    create_selector(
+        &mut page,
+        "Единицы углов:",
+        &["Градусы", "Радианы", "Гоны"],
+        match s.angle_format.units {
+            AngleUnits::Degrees => 0,
+            AngleUnits::Radians => 1,
+            AngleUnits::Gradians => 2,
+        },
+        font,
+        {
+            let settings = settings.clone();
+            move |index| {
+                settings.borrow_mut().angle_format.units = match index {
+                    0 => AngleUnits::Degrees,
+                    1 => AngleUnits::Radians,
+                    _ => AngleUnits::Gradians,
+                };
+            }
+        },
+    );
+
+    create_selector(
+        &mut page,
+        "Отсчёт углов:",
+        &["Против часовой стрелки", "По часовой стрелке"],
+        match s.angle_format.direction {
+            AngleDirection::Counterclockwise => 0,
+            AngleDirection::Clockwise => 1,
+        },
+        font,
+        {
+            let settings = settings.clone();
+            move |index| {
+                settings.borrow_mut().angle_format.direction = match index {
+                    0 => AngleDirection::Counterclockwise,
+                    _ => AngleDirection::Clockwise,
+                };
+            }
+        },
+    );
+
+    create_selector(
+        &mut page,
+        "Нулевое направление:",
+        &["Восток (ось X)", "Север (ось Y)"],
+        match s.angle_format.zero {
+            AngleZero::East => 0,
+            AngleZero::North => 1,
+        },
+        font,
+        {
+            let settings = settings.clone();
+            move |index| {
+                settings.borrow_mut().angle_format.zero = match index {
+                    0 => AngleZero::East,
+                    _ => AngleZero::North,
+                };
+            }
+        },
+    );
+
+    create_section_caption(&mut page, "Производительность", font);
+
+    create_selector(
+        &mut page,
+        "Сглаживание линий:",
+        &["Нету", "Среднее", "Высшее"],
+        match s.curves_aa_mode {
+            CurvesAAMode::NoAntiAliasing => 0,
+            CurvesAAMode::AntiAliasingX2 => 1,
+            CurvesAAMode::AntiAliasingX4 => 2,
+        },
+        font,
+        {
+            let settings = settings.clone();
+            move |index| {
+                settings.borrow_mut().curves_aa_mode = match index {
+                    0 => CurvesAAMode::NoAntiAliasing,
+                    1 => CurvesAAMode::AntiAliasingX2,
+                    _ => CurvesAAMode::AntiAliasingX4,
+                };
+            }
+        },
+    );
+
+    create_selector(
+        &mut page,
+        "Кадров в секунду:",
+        &["30", "60", "120", "Без ограничения"],
+        FRAME_RATES
+            .iter()
+            .position(|&max_fps| max_fps == s.frame_pacing.max_fps)
+            .unwrap_or(1),
+        font,
+        {
+            let settings = settings.clone();
+            move |index| {
+                settings.borrow_mut().frame_pacing.max_fps = FRAME_RATES[index];
+            }
+        },
+    );
+
+    create_selector(
+        &mut page,
+        "Вывод на экран:",
+        &["GDI", "OpenGL", "OpenGL с синхронизацией"],
+        match s.presentation {
+            Presentation::Gdi => 0,
+            Presentation::OpenGl => 1,
+            Presentation::OpenGlVsync => 2,
+        },
+        font,
+        {
+            let settings = settings.clone();
+            move |index| {
+                settings.borrow_mut().presentation = match index {
+                    0 => Presentation::Gdi,
+                    1 => Presentation::OpenGl,
+                    _ => Presentation::OpenGlVsync,
+                };
+            }
+        },
+    );
+
+    let background_line = page.add_child(Container::new(
+        line_size_constraints,
+        ContainerLayout::Horizontal,
+    ));
+    create_check_box(
+        &mut background_line.borrow_mut(),
+        "Не обновлять неактивное окно",
+        s.frame_pacing.pause_in_background,
+        font,
+        {
+            let settings = settings.clone();
+            move |c| {
+                settings.borrow_mut().frame_pacing.pause_in_background = c;
+            }
+        },
+    );
+
+    let zoom_line = page.add_child(Container::new(
+        line_size_constraints,
+        ContainerLayout::Horizontal,
+    ));
+    create_check_box(
+        &mut zoom_line.borrow_mut(),
+        "Плавное масштабирование",
+        s.smooth_zoom,
+        font,
+        {
+            let settings = settings.clone();
+            move |c| {
+                settings.borrow_mut().smooth_zoom = c;
+            }
+        },
+    );
+
+    let widths_line = page.add_child(Container::new(
+        line_size_constraints,
+        ContainerLayout::Horizontal,
+    ));
+    create_check_box(
+        &mut widths_line.borrow_mut(),
+        "Показывать толщину линий",
+        s.true_widths,
+        font,
+        {
+            let settings = settings.clone();
+            move |c| {
+                settings.borrow_mut().true_widths = c;
+            }
+        },
+    );
+
+    let undo_history_line = page.add_child(Container::new(
+        line_size_constraints,
+        ContainerLayout::Horizontal,
+    ));
+    create_check_box(
+        &mut undo_history_line.borrow_mut(),
+        "Сохранять историю отмены в файле",
+        s.document_limits.save_undo_history,
+        font,
+        {
+            let settings = settings.clone();
+            move |c| {
+                settings.borrow_mut().document_limits.save_undo_history = c;
+            }
+        },
+    );
+
+    // the questions turned off by "Больше не спрашивать" are turned on here
+    create_section_caption(&mut page, "Вопросы", font);
+    for (id, description) in CONFIRMATIONS {
+        let line = page.add_child(Container::new(
+            line_size_constraints,
+            ContainerLayout::Horizontal,
+        ));
+        create_check_box(
+            &mut line.borrow_mut(),
+            &format!("Спрашивать: {}", description),
+            !s.suppressed_confirmations.contains(id),
+            font,
+            {
+                let settings = settings.clone();
+                move |c| {
+                    let suppressed = &mut settings.borrow_mut().suppressed_confirmations;
+                    if c {
+                        suppressed.remove(id);
+                    } else {
+                        suppressed.insert(id.to_string());
+                    }
+                }
+            },
+        );
+    }
+
+    page.add_child(EmptySpace::new_splitter(SizeConstraints(
+        SizeConstraint::flexible(0),
+        SizeConstraint::fixed(1),
+    )));
+
+    let buttons_line = page.add_child(Container::new(
+        line_size_constraints,
+        ContainerLayout::Horizontal,
+    ));
+    let ok_button = buttons_line
+        .borrow_mut()
+        .add_child(create_default_size_button("ОК", font.clone()));
+    let cancel_button = buttons_line
+        .borrow_mut()
+        .add_child(create_default_size_button("Отмена", font.clone()));
+
+    page.add_child(EmptySpace::new_empty(SizeConstraints(
+        SizeConstraint::flexible(0),
+        SizeConstraint::flexible(0),
+    )));
+
+    let tab_id = match editor.borrow().add_settings_tab(font, "Настройки", page) {
+        Some(tab_id) => tab_id,
+        None => return,
+    };
+
+    // settings are owned by the callbacks, other controls only keep weak references
+    let ok_settings = settings.clone();
+    ok_button.borrow_mut().set_callback(callback!(
+        [editor, context, font_size_input, pick_radius_input, snap_radius_input] () {
+            let settings = &ok_settings;
+            let inputs = [&font_size_input, &pick_radius_input, &snap_radius_input];
+            if let Some(error) = first_input_error(&inputs) {
+                show_message(context.clone(), &error, "Ошибка ввода");
+                return;
+            }
+            // the validators have accepted the texts
+            let text = |input: &Rc<RefCell<Edit>>| input.borrow().get_text().trim().to_string();
+            let font_size = text(&font_size_input).parse().unwrap_or_default();
+            settings.borrow_mut().font_size = ConfigFontSize(font_size).adjusted();
+            settings.borrow_mut().pick_options = PickOptions {
+                pick_radius: text(&pick_radius_input).parse().unwrap_or_default(),
+                snap_radius: text(&snap_radius_input).parse().unwrap_or_default(),
+            };
+
+            let mut new_config = settings.borrow().clone();
+            new_config.window_positions = editor.borrow().config.borrow().window_positions.clone();
+            editor.borrow().close_tab(tab_id);
+            editor.borrow().set_config(new_config);
+        }
+    ));
+
+    cancel_button
+        .borrow_mut()
+        .set_callback(callback!([editor] () {
+            editor.borrow().close_tab(tab_id);
+        }));
+}
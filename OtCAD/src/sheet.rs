@@ -0,0 +1,305 @@
+use serde::{Deserialize, Serialize};
+
+type Point = curves::points::Point<f64>;
+type Segment = curves::Segment<f64>;
+
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Copy, Clone)]
+pub enum PaperSize {
+    A4,
+    A3,
+    A2,
+    A1,
+    A0,
+    AnsiA,
+    AnsiB,
+    AnsiC,
+    AnsiD,
+}
+
+impl Default for PaperSize {
+    fn default() -> Self {
+        PaperSize::A4
+    }
+}
+
+impl PaperSize {
+    pub const ALL: [PaperSize; 9] = [
+        PaperSize::A4,
+        PaperSize::A3,
+        PaperSize::A2,
+        PaperSize::A1,
+        PaperSize::A0,
+        PaperSize::AnsiA,
+        PaperSize::AnsiB,
+        PaperSize::AnsiC,
+        PaperSize::AnsiD,
+    ];
+
+    pub fn get_name(self) -> &'static str {
+        match self {
+            PaperSize::A4 => "A4",
+            PaperSize::A3 => "A3",
+            PaperSize::A2 => "A2",
+            PaperSize::A1 => "A1",
+            PaperSize::A0 => "A0",
+            PaperSize::AnsiA => "ANSI A",
+            PaperSize::AnsiB => "ANSI B",
+            PaperSize::AnsiC => "ANSI C",
+            PaperSize::AnsiD => "ANSI D",
+        }
+    }
+
+    /// Portrait size in millimeters.
+    pub fn get_size_mm(self) -> (f64, f64) {
+        match self {
+            PaperSize::A4 => (210.0, 297.0),
+            PaperSize::A3 => (297.0, 420.0),
+            PaperSize::A2 => (420.0, 594.0),
+            PaperSize::A1 => (594.0, 841.0),
+            PaperSize::A0 => (841.0, 1189.0),
+            PaperSize::AnsiA => (215.9, 279.4),
+            PaperSize::AnsiB => (279.4, 431.8),
+            PaperSize::AnsiC => (431.8, 558.8),
+            PaperSize::AnsiD => (558.8, 863.6),
+        }
+    }
+}
+
+/// Fields of the title block.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct DocumentMetadata {
+    #[serde(default)]
+    pub title: String,
+
+    #[serde(default)]
+    pub drawing_number: String,
+
+    #[serde(default)]
+    pub author: String,
+
+    #[serde(default)]
+    pub date: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SheetText {
+    /// Left side, vertical middle of the text.
+    pub position: Point,
+    pub height: f64,
+    pub text: String,
+}
+
+/// Detail view: a region of the model around `model_center`, shown inside the sheet
+/// at its own scale.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Viewport {
+    /// Left top corner on the paper, in millimeters.
+    pub paper_left_top: Point,
+    pub paper_size: Point,
+    pub model_center: Point,
+    /// Paper millimeters per document unit.
+    pub scale: f64,
+}
+
+/// Paper outline placed in model space. One paper millimeter is `scale` document units.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Sheet {
+    #[serde(default)]
+    pub paper_size: PaperSize,
+
+    #[serde(default)]
+    pub landscape: bool,
+
+    #[serde(default = "Sheet::default_scale")]
+    pub scale: f64,
+
+    /// Left top corner of the paper.
+    #[serde(default)]
+    pub origin: Point,
+
+    #[serde(default)]
+    pub viewports: Vec<Viewport>,
+}
+
+impl Default for Sheet {
+    fn default() -> Self {
+        Self {
+            paper_size: PaperSize::default(),
+            landscape: false,
+            scale: Self::default_scale(),
+            origin: Point::new(0.0, 0.0),
+            viewports: Vec::new(),
+        }
+    }
+}
+
+const FRAME_MARGIN: f64 = 5.0;
+const TITLE_LABEL_WIDTH: f64 = 30.0;
+const TITLE_VALUE_WIDTH: f64 = 90.0;
+const TITLE_ROW_HEIGHT: f64 = 8.0;
+const TITLE_ROWS: usize = 4;
+const VIEWPORT_SIZE: (f64, f64) = (80.0, 60.0);
+const VIEWPORT_GAP: f64 = 5.0;
+
+impl Sheet {
+    fn default_scale() -> f64 {
+        1.0
+    }
+
+    /// Paper size in millimeters with the orientation applied.
+    pub fn get_paper_size_mm(&self) -> (f64, f64) {
+        let (w, h) = self.paper_size.get_size_mm();
+        if self.landscape {
+            (h, w)
+        } else {
+            (w, h)
+        }
+    }
+
+    fn to_model(&self, x: f64, y: f64) -> Point {
+        self.origin + Point::new(x, y).scale(self.scale)
+    }
+
+    fn add_rect(&self, result: &mut Vec<Segment>, x1: f64, y1: f64, x2: f64, y2: f64) {
+        let corners = [
+            self.to_model(x1, y1),
+            self.to_model(x2, y1),
+            self.to_model(x2, y2),
+            self.to_model(x1, y2),
+        ];
+        for i in 0..corners.len() {
+            result.push(Segment::line(corners[i], corners[(i + 1) % corners.len()]));
+        }
+    }
+
+    fn get_title_block_corner(&self) -> (f64, f64) {
+        let (w, h) = self.get_paper_size_mm();
+        (
+            w - FRAME_MARGIN - TITLE_LABEL_WIDTH - TITLE_VALUE_WIDTH,
+            h - FRAME_MARGIN - TITLE_ROW_HEIGHT * TITLE_ROWS as f64,
+        )
+    }
+
+    /// Paper outline, drawing frame and title block lines, ready for rendering and export.
+    pub fn get_segments(&self) -> Vec<Segment> {
+        let (w, h) = self.get_paper_size_mm();
+        let mut result = Vec::new();
+        self.add_rect(&mut result, 0.0, 0.0, w, h);
+        self.add_rect(
+            &mut result,
+            FRAME_MARGIN,
+            FRAME_MARGIN,
+            w - FRAME_MARGIN,
+            h - FRAME_MARGIN,
+        );
+
+        let (x, y) = self.get_title_block_corner();
+        let right = w - FRAME_MARGIN;
+        let bottom = h - FRAME_MARGIN;
+        self.add_rect(&mut result, x, y, right, bottom);
+        for row in 1..TITLE_ROWS {
+            let row_y = y + TITLE_ROW_HEIGHT * row as f64;
+            result.push(Segment::line(
+                self.to_model(x, row_y),
+                self.to_model(right, row_y),
+            ));
+        }
+        result.push(Segment::line(
+            self.to_model(x + TITLE_LABEL_WIDTH, y),
+            self.to_model(x + TITLE_LABEL_WIDTH, bottom),
+        ));
+
+        for viewport in &self.viewports {
+            let left_top = viewport.paper_left_top;
+            let right_bottom = left_top + viewport.paper_size;
+            self.add_rect(
+                &mut result,
+                left_top.x,
+                left_top.y,
+                right_bottom.x,
+                right_bottom.y,
+            );
+        }
+        result
+    }
+
+    /// Frame corners of the viewport in model space.
+    pub fn get_viewport_rect(&self, viewport: &Viewport) -> (Point, Point) {
+        let left_top = viewport.paper_left_top;
+        let right_bottom = left_top + viewport.paper_size;
+        (
+            self.to_model(left_top.x, left_top.y),
+            self.to_model(right_bottom.x, right_bottom.y),
+        )
+    }
+
+    /// Model lengths are multiplied by this inside the viewport.
+    pub fn get_viewport_factor(&self, viewport: &Viewport) -> f64 {
+        viewport.scale * self.scale
+    }
+
+    /// Moves a model curve to its place inside the viewport frame. Clipping by the frame
+    /// is left to the caller.
+    pub fn to_viewport(
+        &self,
+        viewport: &Viewport,
+        curve: curves::Curve<f64>,
+    ) -> curves::Curve<f64> {
+        let frame_center = viewport.paper_left_top + viewport.paper_size.scale(0.5);
+        curve
+            .translate(viewport.model_center.neg())
+            .scale(self.get_viewport_factor(viewport))
+            .translate(self.to_model(frame_center.x, frame_center.y))
+    }
+
+    /// New viewports are put in rows from the left top corner of the frame.
+    pub fn add_viewport(&mut self, model_center: Point, scale: f64) {
+        let (w, _) = self.get_paper_size_mm();
+        let per_row = (((w - FRAME_MARGIN * 2.0 - VIEWPORT_GAP) / (VIEWPORT_SIZE.0 + VIEWPORT_GAP))
+            as usize)
+            .max(1);
+        let index = self.viewports.len();
+        let paper_left_top = Point::new(
+            FRAME_MARGIN
+                + VIEWPORT_GAP
+                + (index % per_row) as f64 * (VIEWPORT_SIZE.0 + VIEWPORT_GAP),
+            FRAME_MARGIN
+                + VIEWPORT_GAP
+                + (index / per_row) as f64 * (VIEWPORT_SIZE.1 + VIEWPORT_GAP),
+        );
+        self.viewports.push(Viewport {
+            paper_left_top,
+            paper_size: Point::new(VIEWPORT_SIZE.0, VIEWPORT_SIZE.1),
+            model_center,
+            scale,
+        });
+    }
+
+    pub fn get_texts(&self, metadata: &DocumentMetadata) -> Vec<SheetText> {
+        let (x, y) = self.get_title_block_corner();
+        let rows = [
+            ("Название", &metadata.title),
+            ("Чертёж №", &metadata.drawing_number),
+            ("Автор", &metadata.author),
+            ("Дата", &metadata.date),
+        ];
+
+        let padding = 2.0;
+        let mut result = Vec::new();
+        for (row, (label, value)) in rows.iter().enumerate() {
+            let row_y = y + TITLE_ROW_HEIGHT * (row as f64 + 0.5);
+            let mut add_text = |text_x: f64, text: &str| {
+                if !text.is_empty() {
+                    result.push(SheetText {
+                        position: self.to_model(text_x, row_y),
+                        height: TITLE_ROW_HEIGHT * 0.5 * self.scale,
+                        text: text.to_string(),
+                    });
+                }
+            };
+            add_text(x + padding, label);
+            add_text(x + TITLE_LABEL_WIDTH + padding, value);
+        }
+        result
+    }
+}
@@ -0,0 +1,331 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use application::callback;
+use application::callback_body;
+use application::font::*;
+use application::gui::gui_components::*;
+use application::gui::*;
+
+use crate::document::*;
+use crate::editor::*;
+use crate::gui_helper::*;
+use crate::sheet::*;
+
+type Point = curves::points::Point<f64>;
+
+/// Opens the paper sheet and title block settings of the active document.
+pub fn open_sheet_dialog(
+    font: &Font,
+    editor: Rc<RefCell<Editor>>,
+    context: Rc<RefCell<window::Context>>,
+) {
+    let document = match editor.borrow().get_active_document() {
+        Some(document) => document,
+        None => return,
+    };
+
+    let show_sheet = Rc::new(Cell::new(document.borrow().get_sheet().is_some()));
+    let sheet = Rc::new(RefCell::new(
+        document.borrow().get_sheet().cloned().unwrap_or_else(|| {
+            // new sheet is centered in the view
+            let mut sheet = Sheet::default();
+            let (w, h) = sheet.get_paper_size_mm();
+            sheet.origin = document.borrow().get_center() - Point::new(w, h).scale(0.5);
+            sheet
+        }),
+    ));
+    let metadata = document.borrow().get_metadata().clone();
+    let canvas_style = *document.borrow().get_canvas_style();
+    let number_format = editor.borrow().config.borrow().number_format;
+
+    let font_height = font.get_size("8").1 as i32 + 2;
+    let caption_width = GuiSystem::default_size("Масштаб вида:", None, font)
+        .0
+        .absolute;
+
+    let mut page = Container::new(
+        SizeConstraints(SizeConstraint::flexible(0), SizeConstraint::flexible(0)),
+        ContainerLayout::Vertical,
+    );
+
+    create_section_caption(&mut page, "Лист", font);
+
+    let show_line = page.add_child(Container::new(
+        SizeConstraints(
+            SizeConstraint::flexible(0),
+            SizeConstraint::fixed(font_height),
+        ),
+        ContainerLayout::Horizontal,
+    ));
+    create_check_box(
+        &mut show_line.borrow_mut(),
+        "Показать лист",
+        show_sheet.get(),
+        font,
+        {
+            let show_sheet = show_sheet.clone();
+            move |c| show_sheet.set(c)
+        },
+    );
+
+    let s = sheet.borrow().clone();
+    let paper_names: Vec<_> = PaperSize::ALL.iter().map(|p| p.get_name()).collect();
+    create_selector(
+        &mut page,
+        "Формат:",
+        &paper_names,
+        PaperSize::ALL
+            .iter()
+            .position(|p| *p == s.paper_size)
+            .unwrap_or(0),
+        font,
+        {
+            let sheet = sheet.clone();
+            move |index| {
+                sheet.borrow_mut().paper_size = PaperSize::ALL[index];
+            }
+        },
+    );
+
+    create_selector(
+        &mut page,
+        "Ориентация:",
+        &["Книжная", "Альбомная"],
+        if s.landscape { 1 } else { 0 },
+        font,
+        {
+            let sheet = sheet.clone();
+            move |index| {
+                sheet.borrow_mut().landscape = index == 1;
+            }
+        },
+    );
+
+    let scale_input = create_labeled_edit(
+        &mut page,
+        "Масштаб:",
+        caption_width,
+        &number_format.format(s.scale),
+        font,
+        &context,
+    );
+    let scale_validator = Validator::new(move |text| match number_format.parse(text) {
+        Some(scale) if scale > 0.0 => Ok(()),
+        _ => Err(format!("{} - не подходит для масштаба!", text)),
+    });
+    scale_input
+        .borrow_mut()
+        .set_validator(scale_validator.clone());
+
+    create_section_caption(&mut page, "Штамп", font);
+
+    let title_input = create_labeled_edit(
+        &mut page,
+        "Название:",
+        caption_width,
+        &metadata.title,
+        font,
+        &context,
+    );
+    let number_input = create_labeled_edit(
+        &mut page,
+        "Чертёж №:",
+        caption_width,
+        &metadata.drawing_number,
+        font,
+        &context,
+    );
+    let author_input = create_labeled_edit(
+        &mut page,
+        "Автор:",
+        caption_width,
+        &metadata.author,
+        font,
+        &context,
+    );
+    let date_input = create_labeled_edit(
+        &mut page,
+        "Дата:",
+        caption_width,
+        &metadata.date,
+        font,
+        &context,
+    );
+
+    create_section_caption(&mut page, "Цвета чертежа (RRGGBB, пусто - из темы)", font);
+
+    let color_inputs: Vec<_> = [
+        ("Фон:", canvas_style.background),
+        ("Сетка:", canvas_style.grid_color_base),
+        ("Сетка мелкая:", canvas_style.grid_color_sub),
+        ("Линии:", canvas_style.entity_color),
+    ]
+    .iter()
+    .map(|(caption, color)| {
+        let input = create_labeled_edit(
+            &mut page,
+            caption,
+            caption_width,
+            &CanvasStyle::format_color(*color),
+            font,
+            &context,
+        );
+        input.borrow_mut().set_validator(Validator::new(|text| {
+            match CanvasStyle::parse_color(text) {
+                Some(_) => Ok(()),
+                None => Err(format!("{} - не подходит для цвета!", text)),
+            }
+        }));
+        input
+    })
+    .collect();
+
+    create_section_caption(&mut page, "Виды", font);
+
+    let viewport_scale_input = create_labeled_edit(
+        &mut page,
+        "Масштаб вида:",
+        caption_width,
+        &number_format.format(2.0),
+        font,
+        &context,
+    );
+    viewport_scale_input
+        .borrow_mut()
+        .set_validator(scale_validator);
+    let viewports_line = page.add_child(Container::new(
+        SizeConstraints(
+            SizeConstraint::flexible(0),
+            SizeConstraint::fixed(font_height),
+        ),
+        ContainerLayout::Horizontal,
+    ));
+    let add_viewport_button = viewports_line
+        .borrow_mut()
+        .add_child(create_default_size_button("Добавить вид", font.clone()));
+    let clear_viewports_button = viewports_line
+        .borrow_mut()
+        .add_child(create_default_size_button("Удалить виды", font.clone()));
+    let viewports_count = viewports_line.borrow_mut().add_child(TextBox::new(
+        SizeConstraints(
+            SizeConstraint::flexible(0),
+            SizeConstraint::fixed(font_height),
+        ),
+        format!("Видов: {}", s.viewports.len()),
+        font.clone(),
+    ));
+
+    // new views show the model around the current view center
+    let model_center = document.borrow().get_center();
+    {
+        let sheet = sheet.clone();
+        add_viewport_button.borrow_mut().set_callback(callback!(
+            [context, viewport_scale_input, viewports_count] () {
+                if let Some(error) = first_input_error(&[&viewport_scale_input]) {
+                    show_message(context.clone(), &error, "Ошибка ввода");
+                    return;
+                }
+                let text = viewport_scale_input.borrow().get_text().to_string();
+                if let Some(scale) = number_format.parse(&text) {
+                    let mut sheet = sheet.borrow_mut();
+                    sheet.add_viewport(model_center, scale);
+                    viewports_count
+                        .borrow_mut()
+                        .set_text(&format!("Видов: {}", sheet.viewports.len()));
+                }
+            }
+        ));
+    }
+    {
+        let sheet = sheet.clone();
+        clear_viewports_button
+            .borrow_mut()
+            .set_callback(callback!([viewports_count] () {
+                sheet.borrow_mut().viewports.clear();
+                viewports_count.borrow_mut().set_text("Видов: 0");
+            }));
+    }
+
+    page.add_child(EmptySpace::new_splitter(SizeConstraints(
+        SizeConstraint::flexible(0),
+        SizeConstraint::fixed(1),
+    )));
+
+    let buttons_line = page.add_child(Container::new(
+        SizeConstraints(
+            SizeConstraint::flexible(0),
+            SizeConstraint::fixed(font_height),
+        ),
+        ContainerLayout::Horizontal,
+    ));
+    let ok_button = buttons_line
+        .borrow_mut()
+        .add_child(create_default_size_button("ОК", font.clone()));
+    let cancel_button = buttons_line
+        .borrow_mut()
+        .add_child(create_default_size_button("Отмена", font.clone()));
+
+    page.add_child(EmptySpace::new_empty(SizeConstraints(
+        SizeConstraint::flexible(0),
+        SizeConstraint::flexible(0),
+    )));
+
+    let document_tab_id = editor.borrow().get_selected_tab_id();
+    let tab_id = match editor.borrow().add_page_tab(font, "Лист", page) {
+        Some(tab_id) => tab_id,
+        None => return,
+    };
+
+    let close = Rc::new(callback!([editor] () {
+        editor.borrow().close_tab(tab_id);
+        if let Some(document_tab_id) = document_tab_id {
+            editor.borrow().select_tab(document_tab_id);
+        }
+    }));
+
+    {
+        let close = close.clone();
+        ok_button.borrow_mut().set_callback(callback!(
+            [context, document, scale_input, title_input, number_input, author_input, date_input] () {
+                let inputs: Vec<_> = std::iter::once(&scale_input).chain(&color_inputs).collect();
+                if let Some(error) = first_input_error(&inputs) {
+                    show_message(context.clone(), &error, "Ошибка ввода");
+                    return;
+                }
+
+                // the validators have accepted the texts
+                if let Some(scale) = number_format.parse(scale_input.borrow().get_text()) {
+                    sheet.borrow_mut().scale = scale;
+                }
+                let colors: Vec<_> = color_inputs
+                    .iter()
+                    .map(|input| CanvasStyle::parse_color(input.borrow().get_text()).flatten())
+                    .collect();
+
+                let mut document = document.borrow_mut();
+                document.set_canvas_style(CanvasStyle {
+                    background: colors[0],
+                    grid_color_base: colors[1],
+                    grid_color_sub: colors[2],
+                    entity_color: colors[3],
+                });
+                document.set_metadata(DocumentMetadata {
+                    title: title_input.borrow().get_text().to_string(),
+                    drawing_number: number_input.borrow().get_text().to_string(),
+                    author: author_input.borrow().get_text().to_string(),
+                    date: date_input.borrow().get_text().to_string(),
+                });
+                document.set_sheet(if show_sheet.get() {
+                    Some(sheet.borrow().clone())
+                } else {
+                    None
+                });
+                drop(document);
+                close();
+            }
+        ));
+    }
+
+    cancel_button.borrow_mut().set_callback(move || close());
+}
@@ -0,0 +1,192 @@
+use curves::points::{cross, dot};
+
+use crate::ucs::*;
+
+type Point = curves::points::Point<f64>;
+
+/// Points of two entities closer than this are on the same line of the guide.
+const ALIGN_EPS: f64 = 1e-9;
+
+/// Position which the guides pull the mouse to, and the dashed lines which show why.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SmartGuides {
+    pub position: Point,
+    pub lines: Vec<(Point, Point)>,
+}
+
+/// What the guides are made of: the ends and the centers of the entities, and the
+/// directions of their lines. All of them are in the UCS.
+#[derive(Debug, Default, Clone)]
+pub struct GuideReferences {
+    pub points: Vec<Point>,
+    pub directions: Vec<Point>,
+}
+
+/// Key point which has the same coordinate as the position, the nearest one along the
+/// guide is taken.
+fn find_aligned(points: &[Point], position: Point, tolerance: f64, by_x: bool) -> Option<Point> {
+    let coordinates = |p: Point| if by_x { (p.x, p.y) } else { (p.y, p.x) };
+    let (across, along) = coordinates(position);
+    let mut result: Option<(f64, f64, Point)> = None;
+    for point in points {
+        let (point_across, point_along) = coordinates(*point);
+        let offset = (point_across - across).abs();
+        let distance = (point_along - along).abs();
+        if offset >= tolerance {
+            continue;
+        }
+        if result.map_or(true, |(best_offset, best_distance, _)| {
+            (offset, distance) < (best_offset, best_distance)
+        }) {
+            result = Some((offset, distance, *point));
+        }
+    }
+    result.map(|(_, _, point)| point)
+}
+
+/// Continues a row of evenly spaced points through the reference, returns the new
+/// coordinate along the row and the lines of the spacing.
+fn find_equal_spacing(
+    points: &[Point],
+    reference: Point,
+    along: f64,
+    tolerance: f64,
+    by_x: bool,
+) -> Option<(f64, Vec<(f64, f64)>)> {
+    let coordinates = |p: Point| if by_x { (p.x, p.y) } else { (p.y, p.x) };
+    let across = coordinates(reference).0;
+    let mut row: Vec<f64> = points
+        .iter()
+        .map(|point| coordinates(*point))
+        .filter(|(point_across, _)| (point_across - across).abs() < ALIGN_EPS)
+        .map(|(_, point_along)| point_along)
+        .collect();
+    row.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    row.dedup_by(|a, b| (*a - *b).abs() < ALIGN_EPS);
+    let mut result: Option<(f64, f64, Vec<(f64, f64)>)> = None;
+    for pair in row.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        let spacing = b - a;
+        for (candidate, lines) in [
+            (b + spacing, vec![(a, b), (b, b + spacing)]),
+            (a - spacing, vec![(a - spacing, a), (a, b)]),
+        ] {
+            let offset = (candidate - along).abs();
+            // the candidate must not be one of the points of the row
+            if offset < tolerance
+                && !row.iter().any(|p| (p - candidate).abs() < ALIGN_EPS)
+                && result.as_ref().map_or(true, |(best, _, _)| offset < *best)
+            {
+                result = Some((offset, candidate, lines));
+            }
+        }
+    }
+    result.map(|(_, candidate, lines)| (candidate, lines))
+}
+
+/// Direction of a line of the drawing which the rubber line from `from` is parallel to.
+fn find_parallel(
+    directions: &[Point],
+    from: Point,
+    position: Point,
+    tolerance: f64,
+) -> Option<Point> {
+    let mut result: Option<(f64, Point)> = None;
+    for direction in directions {
+        let offset = cross(position - from, *direction).abs();
+        if offset < tolerance && result.map_or(true, |(best, _)| offset < best) {
+            result = Some((offset, *direction));
+        }
+    }
+    result.map(|(_, direction)| direction)
+}
+
+/// Finds the guides near the position: the same X or Y as a key point, a row of equally
+/// spaced points and a line parallel to one of the drawing from the `from` point.
+/// Returns None if no guide is closer than the tolerance.
+pub fn find_smart_guides(
+    references: &GuideReferences,
+    ucs: &Ucs,
+    position: Point,
+    from: Option<Point>,
+    tolerance: f64,
+) -> Option<SmartGuides> {
+    let local = ucs.to_local(position);
+    let from = from.map(|from| ucs.to_local(from));
+    let points = &references.points;
+    let aligned_x = find_aligned(points, local, tolerance, true);
+    let aligned_y = find_aligned(points, local, tolerance, false);
+    let parallel = from.and_then(|from| {
+        find_parallel(&references.directions, from, local, tolerance)
+            .map(|direction| (from, direction))
+    });
+
+    let mut x = aligned_x.map(|point| point.x);
+    let mut y = aligned_y.map(|point| point.y);
+    let mut lines = Vec::new();
+    if let (Some(reference), None) = (aligned_y, x) {
+        if let Some((along, spacing)) =
+            find_equal_spacing(points, reference, local.x, tolerance, false)
+        {
+            x = Some(along);
+            lines.extend(
+                spacing
+                    .into_iter()
+                    .map(|(a, b)| (Point::new(a, reference.y), Point::new(b, reference.y))),
+            );
+        }
+    }
+    if let (Some(reference), None) = (aligned_x, y) {
+        if let Some((along, spacing)) =
+            find_equal_spacing(points, reference, local.y, tolerance, true)
+        {
+            y = Some(along);
+            lines.extend(
+                spacing
+                    .into_iter()
+                    .map(|(a, b)| (Point::new(reference.x, a), Point::new(reference.x, b))),
+            );
+        }
+    }
+
+    // the parallel line is kept only if it goes through the snapped point
+    let (snapped, parallel) = match (x, y, parallel) {
+        (Some(x), Some(y), _) => (Point::new(x, y), None),
+        (Some(x), None, Some((from, direction))) if direction.x.abs() > ALIGN_EPS => (
+            Point::new(x, from.y + direction.y * (x - from.x) / direction.x),
+            Some(from),
+        ),
+        (None, Some(y), Some((from, direction))) if direction.y.abs() > ALIGN_EPS => (
+            Point::new(from.x + direction.x * (y - from.y) / direction.y, y),
+            Some(from),
+        ),
+        (Some(x), None, _) => (Point::new(x, local.y), None),
+        (None, Some(y), _) => (Point::new(local.x, y), None),
+        (None, None, Some((from, direction))) => (
+            from + direction.scale(dot(local - from, direction)),
+            Some(from),
+        ),
+        (None, None, None) => return None,
+    };
+    // the crossing of two guides can be far away for almost parallel ones
+    if (snapped - local).length() > tolerance * 2.0 {
+        return None;
+    }
+
+    if let (Some(point), Some(_)) = (aligned_x, x) {
+        lines.push((point, Point::new(point.x, snapped.y)));
+    }
+    if let (Some(point), Some(_)) = (aligned_y, y) {
+        lines.push((point, Point::new(snapped.x, point.y)));
+    }
+    if let Some(from) = parallel {
+        lines.push((from, snapped));
+    }
+    Some(SmartGuides {
+        position: ucs.to_world(snapped),
+        lines: lines
+            .into_iter()
+            .map(|(a, b)| (ucs.to_world(a), ucs.to_world(b)))
+            .collect(),
+    })
+}
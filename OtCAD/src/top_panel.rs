@@ -9,6 +9,7 @@ use crate::edit_menu::*;
 use crate::editor::*;
 use crate::file_menu::*;
 use crate::group_menu::*;
+use crate::macros::*;
 use crate::options_menu::*;
 use crate::transform_menu::*;
 
@@ -25,12 +26,38 @@ pub fn create_top_panel(
     let font_height = font.get_size("8").1 as i32 + 2;
     let top_panel = root.insert_child(0, TabControl::new(font_height, font.clone(), false));
 
-    create_file_menu(&mut top_panel.borrow_mut(), font, editor.clone());
-    create_edit_menu(&mut top_panel.borrow_mut(), font, editor.clone());
+    create_file_menu(
+        &mut top_panel.borrow_mut(),
+        font,
+        editor.clone(),
+        context.clone(),
+    );
+    create_edit_menu(
+        &mut top_panel.borrow_mut(),
+        font,
+        editor.clone(),
+        context.clone(),
+    );
     create_draw_menu(&mut top_panel.borrow_mut(), font, editor.clone()); // DRAW_MENU_INDEX
-    create_group_menu(&mut top_panel.borrow_mut(), font);
-    create_transform_menu(&mut top_panel.borrow_mut(), font);
-    create_options_menu(&mut top_panel.borrow_mut(), font, editor, context); // OPTIONS_MENU_INDEX
+    create_group_menu(
+        &mut top_panel.borrow_mut(),
+        font,
+        editor.clone(),
+        context.clone(),
+    );
+    create_transform_menu(
+        &mut top_panel.borrow_mut(),
+        font,
+        editor.clone(),
+        context.clone(),
+    );
+    create_options_menu(
+        &mut top_panel.borrow_mut(),
+        font,
+        editor.clone(),
+        context.clone(),
+    ); // OPTIONS_MENU_INDEX
+    create_macro_menu(&mut top_panel.borrow_mut(), font, editor, context);
     top_panel.borrow_mut().select_tab(top_panel_index);
 
     top_panel
@@ -0,0 +1,164 @@
+use application::angle_format::AngleFormat;
+use application::number_format::NumberFormat;
+use curves::points::*;
+use serde::{Deserialize, Serialize};
+
+type Point = curves::points::Point<f64>;
+
+/// Points closer than this are the same point for the transforms.
+const EPS: f64 = 1.0e-9;
+
+/// Rotation and scaling around the base point, then the shift. Scaling is always uniform,
+/// arcs and circles stay arcs and circles only then.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq)]
+pub struct Transform {
+    pub base: Point,
+    /// Unit vector of the rotation angle.
+    pub rotation: Point,
+    pub factor: f64,
+    pub shift: Point,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            base: Point::new(0.0, 0.0),
+            rotation: Point::new(1.0, 0.0),
+            factor: 1.0,
+            shift: Point::new(0.0, 0.0),
+        }
+    }
+}
+
+impl Transform {
+    pub fn translation(shift: Point) -> Self {
+        Self {
+            shift,
+            ..Self::default()
+        }
+    }
+
+    pub fn scaling(base: Point, factor: f64) -> Self {
+        Self {
+            base,
+            factor,
+            ..Self::default()
+        }
+    }
+
+    /// Angle is in radians, counterclockwise in the document coordinates.
+    pub fn rotation(base: Point, angle: f64) -> Self {
+        Self {
+            base,
+            rotation: Point::angle(angle),
+            ..Self::default()
+        }
+    }
+
+    /// Turns the reference direction to the new one around the base. The rotation is taken
+    /// from the directions themselves, not through their angles, so a picked direction
+    /// is matched exactly. None if a direction has no length.
+    pub fn rotation_by_reference(base: Point, reference: Point, direction: Point) -> Option<Self> {
+        if reference.length() < EPS || direction.length() < EPS {
+            return None;
+        }
+        Some(Self {
+            base,
+            rotation: complex_mul(direction, reference.complex_conj()).normalize(),
+            ..Self::default()
+        })
+    }
+
+    /// Moves the first source point to the first destination point and turns the direction
+    /// to the second source point to the second destination point. With `scale` the distance
+    /// between the points is matched too. Coinciding source points give only the shift.
+    pub fn align(source: (Point, Point), destination: (Point, Point), scale: bool) -> Self {
+        let source_direction = source.1 - source.0;
+        let destination_direction = destination.1 - destination.0;
+        let shift = destination.0 - source.0;
+        if source_direction.length() < EPS || destination_direction.length() < EPS {
+            return Self {
+                shift,
+                ..Self::default()
+            };
+        }
+        Self {
+            base: source.0,
+            rotation: complex_mul(destination_direction, source_direction.complex_conj())
+                .normalize(),
+            factor: if scale {
+                destination_direction.length() / source_direction.length()
+            } else {
+                1.0
+            },
+            shift,
+        }
+    }
+
+    /// The same rotation, scaling and shift done around another base point.
+    pub fn with_base(&self, base: Point) -> Self {
+        Self { base, ..*self }
+    }
+
+    pub fn apply(&self, point: Point) -> Point {
+        complex_mul(point - self.base, self.rotation).scale(self.factor) + self.base + self.shift
+    }
+
+    pub fn apply_to_curve(&self, curve: curves::Curve<f64>) -> curves::Curve<f64> {
+        curve
+            .translate(self.base.neg())
+            .rotate(self.rotation)
+            .scale(self.factor)
+            .translate(self.base + self.shift)
+    }
+}
+
+/// Point at the same distance from the base, turned to the nearest direction of the step.
+/// The steps are counted from the start angle.
+pub fn snap_polar(base: Point, point: Point, step: f64, start: f64) -> Point {
+    let delta = point - base;
+    let angle = ((delta.y.atan2(delta.x) - start) / step).round() * step + start;
+    base + Point::angle(angle).scale(delta.length())
+}
+
+/// Projection of the point to the nearest of the lines through the base in the given
+/// directions, the angles in degrees are counted from the start angle in radians.
+pub fn snap_ortho(base: Point, point: Point, angles: &[f64], start: f64) -> Point {
+    let delta = point - base;
+    angles
+        .iter()
+        .map(|angle| {
+            let direction = Point::angle(angle.to_radians() + start);
+            base + direction.scale(dot(delta, direction))
+        })
+        .min_by(|a, b| {
+            (point - *a)
+                .sqr_length()
+                .total_cmp(&(point - *b).sqr_length())
+        })
+        .unwrap_or(point)
+}
+
+/// Offset typed as `dx; dy`, or as `length<angle` with the angle of the direction in the
+/// angle format. A comma separates the coordinates too, unless it is the decimal separator.
+pub fn parse_offset(
+    text: &str,
+    number_format: &NumberFormat,
+    angle_format: &AngleFormat,
+) -> Option<Point> {
+    if let Some((length, angle)) = text.split_once('<') {
+        let length = number_format.parse(length)?;
+        let angle = angle_format.parse_direction(angle, number_format)?;
+        return Some(Point::angle(angle).scale(length));
+    }
+    let separators: &[char] = if number_format.get_decimal_separator() == ',' {
+        &[';']
+    } else {
+        &[';', ',']
+    };
+    let (dx, dy) = text.split_once(separators)?;
+    Some(Point::new(
+        number_format.parse(dx)?,
+        number_format.parse(dy)?,
+    ))
+}
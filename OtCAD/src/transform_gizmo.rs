@@ -0,0 +1,122 @@
+use curves::points::{cross, dot};
+
+type Point = curves::points::Point<f64>;
+
+/// Distance in pixels from the top of the selection box to the rotate handle.
+const ROTATE_HANDLE_OFFSET: f64 = 24.0;
+/// The mouse closer than this in pixels grabs the handle.
+pub const HANDLE_RADIUS: f64 = 5.0;
+/// The selection can't be scaled to nothing or turned inside out by a corner.
+const MIN_FACTOR: f64 = 1.0e-3;
+/// Shorter drags from the base give no reliable angle or factor.
+const EPS: f64 = 1.0e-9;
+
+/// Handles of the box around the selection: the corners scale it around the opposite
+/// corner, the handle above the box turns it around the center.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum GizmoHandle {
+    Rotate,
+    /// Index of the corner in `get_box_corners`.
+    Scale(usize),
+}
+
+/// Change of the selection given by the dragged handle.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum GizmoChange {
+    /// The angle is in radians, counterclockwise in the document coordinates.
+    Rotation(Point, f64),
+    Scaling(Point, f64),
+}
+
+impl GizmoChange {
+    /// The handle is back where it was grabbed.
+    pub fn is_identity(&self) -> bool {
+        match self {
+            GizmoChange::Rotation(_, angle) => *angle == 0.0,
+            GizmoChange::Scaling(_, factor) => *factor == 1.0,
+        }
+    }
+}
+
+/// Corners of the bounds in order around the box, so the opposite one is two steps away.
+pub fn get_box_corners((min, max): (Point, Point)) -> [Point; 4] {
+    [min, Point::new(max.x, min.y), max, Point::new(min.x, max.y)]
+}
+
+/// Positions of the handles on the screen from the screen corners of the box. The rotate
+/// handle is above the box whatever the turn of the view is.
+pub fn get_handle_positions(screen_corners: &[Point; 4]) -> Vec<(GizmoHandle, Point)> {
+    let top = screen_corners
+        .iter()
+        .map(|corner| corner.y)
+        .fold(f64::INFINITY, f64::min);
+    let center = (screen_corners[0] + screen_corners[2]).scale(0.5);
+    let mut result = vec![(
+        GizmoHandle::Rotate,
+        Point::new(center.x, top - ROTATE_HANDLE_OFFSET),
+    )];
+    result.extend(
+        screen_corners
+            .iter()
+            .enumerate()
+            .map(|(index, corner)| (GizmoHandle::Scale(index), *corner)),
+    );
+    result
+}
+
+/// The nearest handle to the screen position, None if all of them are farther than
+/// `HANDLE_RADIUS`.
+pub fn find_handle(screen_corners: &[Point; 4], position: Point) -> Option<GizmoHandle> {
+    get_handle_positions(screen_corners)
+        .into_iter()
+        .map(|(handle, point)| (handle, (point - position).length()))
+        .filter(|(_, distance)| *distance <= HANDLE_RADIUS)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(handle, _)| handle)
+}
+
+/// Handle being dragged, everything is in the document coordinates.
+#[derive(Debug, Copy, Clone)]
+pub struct GizmoDrag {
+    pub handle: GizmoHandle,
+    pub bounds: (Point, Point),
+    pub start: Point,
+}
+
+impl GizmoDrag {
+    /// The angle goes in steps of `angle_step` radians, zero turns the snap off. The scale
+    /// follows the projection of the mouse to the diagonal of the box.
+    pub fn get_change(&self, position: Point, angle_step: f64) -> GizmoChange {
+        let corners = get_box_corners(self.bounds);
+        match self.handle {
+            GizmoHandle::Rotate => {
+                let center = (self.bounds.0 + self.bounds.1).scale(0.5);
+                let from = self.start - center;
+                let to = position - center;
+                let angle = if from.length() < EPS || to.length() < EPS {
+                    0.0
+                } else {
+                    cross(from, to).atan2(dot(from, to))
+                };
+                let angle = if angle_step > 0.0 {
+                    (angle / angle_step).round() * angle_step
+                } else {
+                    angle
+                };
+                GizmoChange::Rotation(center, angle)
+            }
+            GizmoHandle::Scale(index) => {
+                let base = corners[(index + 2) % corners.len()];
+                // the factor is one where the handle was grabbed, not only at the corner
+                let diagonal = corners[index] - base;
+                let grabbed = dot(self.start - base, diagonal);
+                let factor = if grabbed < EPS {
+                    1.0
+                } else {
+                    (dot(position - base, diagonal) / grabbed).max(MIN_FACTOR)
+                };
+                GizmoChange::Scaling(base, factor)
+            }
+        }
+    }
+}
@@ -1,21 +1,652 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
 
+use application::angle_format::AngleFormat;
+use application::callback;
+use application::callback_body;
 use application::font::*;
 use application::gui::gui_components::*;
 use application::gui::*;
+use application::keys::*;
+use application::number_format::NumberFormat;
 
+use crate::command::*;
+use crate::coordinates_dialog::*;
+use crate::editor::*;
 use crate::gui_helper::*;
+use crate::transform::*;
+use crate::ucs::*;
 
-pub fn create_transform_menu(parent: &mut TabControl, font: &Font) -> Rc<RefCell<Container>> {
+type Point = curves::points::Point<f64>;
+
+/// Shorter reference segments give no reliable factor.
+const MIN_REFERENCE_LENGTH: f64 = 1.0e-9;
+
+pub fn scaling_command(base: Point, factor: f64, number_format: &NumberFormat) -> Command {
+    Command::Transform(
+        format!("Масштаб {}", number_format.format(factor)),
+        Transform::scaling(base, factor),
+    )
+}
+
+/// The offset is shown in the UCS, as it is typed.
+fn translation_command(shift: Point, ucs: &Ucs, number_format: &NumberFormat) -> Command {
+    let local = ucs.vector_to_local(shift);
+    Command::Transform(
+        format!(
+            "Сдвиг {}; {}",
+            number_format.format(local.x),
+            number_format.format(local.y)
+        ),
+        Transform::translation(shift),
+    )
+}
+
+pub fn rotation_command(
+    base: Point,
+    angle: f64,
+    number_format: &NumberFormat,
+    angle_format: &AngleFormat,
+) -> Command {
+    Command::Transform(
+        format!(
+            "Поворот {}",
+            angle_format.format_rotation(angle, number_format)
+        ),
+        Transform::rotation(base, angle),
+    )
+}
+
+/// The angle in the caption is only shown, the transform keeps the exact turn.
+fn reference_rotation_command(
+    transform: Transform,
+    number_format: &NumberFormat,
+    angle_format: &AngleFormat,
+) -> Command {
+    let angle = transform.rotation.y.atan2(transform.rotation.x);
+    Command::Transform(
+        format!(
+            "Поворот {}",
+            angle_format.format_rotation(angle, number_format)
+        ),
+        transform,
+    )
+}
+
+/// Asks for the selection first, the transforms have nothing to do without it.
+fn check_selection(editor: &Rc<RefCell<Editor>>, context: Rc<RefCell<window::Context>>) -> bool {
+    if editor.borrow().has_selection() {
+        return true;
+    }
+    show_message(context, "Ничего не выделено", "Преобразование");
+    false
+}
+
+/// Scales the selection around the base point so that the reference segment gets the new
+/// length, which is typed or picked as a distance from the base point.
+fn scale_by_reference(
+    font: Font,
+    editor: Rc<RefCell<Editor>>,
+    context: Rc<RefCell<window::Context>>,
+) {
+    if !check_selection(&editor, context.clone()) {
+        return;
+    }
+    editor.borrow().pick_point(
+        PointPick::new("Базовая точка"),
+        callback!([editor, context] (base) {
+            let font = font.clone();
+            editor.borrow().pick_point(
+                PointPick::new("Первая точка образца").from(base),
+                callback!([editor, context] (reference1) {
+                    let font = font.clone();
+                    editor.borrow().pick_point(
+                        PointPick::new("Вторая точка образца").from(reference1),
+                        callback!([editor, context] (reference2) {
+                            let reference = (reference2 - reference1).length();
+                            if reference < MIN_REFERENCE_LENGTH {
+                                show_message(context, "Длина образца равна нулю", "Масштаб");
+                                return;
+                            }
+                            open_new_length_dialog(&font, editor, context, base, reference);
+                        }),
+                    );
+                }),
+            );
+        }),
+    );
+}
+
+fn open_new_length_dialog(
+    font: &Font,
+    editor: Rc<RefCell<Editor>>,
+    context: Rc<RefCell<window::Context>>,
+    base: Point,
+    reference: f64,
+) {
+    let number_format = editor.borrow().config.borrow().number_format;
+    let weak_editor = Rc::downgrade(&editor);
+    let on_ok = move |text: &str| match number_format.parse(text) {
+        Some(length) if length > 0.0 => {
+            if let Some(editor) = weak_editor.upgrade() {
+                editor.borrow().run_command(scaling_command(
+                    base,
+                    length / reference,
+                    &number_format,
+                ));
+            }
+            Ok(())
+        }
+        _ => Err(format!("{} - не положительное число!", text)),
+    };
+    let on_pick: Rc<dyn Fn()> = Rc::new(callback!([editor] () {
+        let pick = PointPick::new("Новая длина:").from(base).preview(move |point| {
+            let length = (point - base).length();
+            let transform = if length < MIN_REFERENCE_LENGTH {
+                Transform::default()
+            } else {
+                Transform::scaling(base, length / reference)
+            };
+            (transform, number_format.format(length))
+        });
+        editor.borrow().pick_point(
+            pick,
+            callback!([editor] (point) {
+                let length = (point - base).length();
+                if length >= MIN_REFERENCE_LENGTH {
+                    editor.borrow().run_command(scaling_command(
+                        base,
+                        length / reference,
+                        &number_format,
+                    ));
+                }
+            }),
+        );
+    }));
+    open_input_dialog(
+        font,
+        editor,
+        context,
+        "Масштаб",
+        ("Новая длина:", &number_format.format(reference)),
+        on_ok,
+        Some(on_pick),
+    );
+}
+
+/// Moves the selection by the offset from the picked base point, the offset is typed or
+/// given by the picked destination point.
+fn translate(font: Font, editor: Rc<RefCell<Editor>>, context: Rc<RefCell<window::Context>>) {
+    if !check_selection(&editor, context.clone()) {
+        return;
+    }
+    editor.borrow().pick_point(
+        PointPick::new("Базовая точка"),
+        callback!([editor, context] (base) {
+            open_offset_dialog(&font, editor, context, base);
+        }),
+    );
+}
+
+fn open_offset_dialog(
+    font: &Font,
+    editor: Rc<RefCell<Editor>>,
+    context: Rc<RefCell<window::Context>>,
+    base: Point,
+) {
+    let number_format = editor.borrow().config.borrow().number_format;
+    let angle_format = editor.borrow().config.borrow().angle_format;
+    // offsets are typed and shown in the UCS
+    let ucs = editor.borrow().get_ucs();
+    let weak_editor = Rc::downgrade(&editor);
+    let on_ok = move |text: &str| match parse_offset(text, &number_format, &angle_format) {
+        Some(offset) => {
+            if let Some(editor) = weak_editor.upgrade() {
+                editor.borrow().run_command(translation_command(
+                    ucs.vector_to_world(offset),
+                    &ucs,
+                    &number_format,
+                ));
+            }
+            Ok(())
+        }
+        None => Err(format!(
+            "{} - не смещение, введите \"dx; dy\" или \"длина<угол\"",
+            text
+        )),
+    };
+    let on_pick: Rc<dyn Fn()> = Rc::new(callback!([editor] () {
+        let pick = PointPick::new("Точка назначения:").from(base).polar().preview(move |point| {
+            let offset = point - base;
+            let local = ucs.vector_to_local(offset);
+            let text = format!(
+                "{}<{}",
+                number_format.format(offset.length()),
+                angle_format.format_direction(get_angle(Point::new(0.0, 0.0), local), &number_format)
+            );
+            (Transform::translation(offset), text)
+        });
+        editor.borrow().pick_point(
+            pick,
+            callback!([editor] (point) {
+                editor
+                    .borrow()
+                    .run_command(translation_command(point - base, &ucs, &number_format));
+            }),
+        );
+    }));
+    let text = format!(
+        "{}; {}",
+        number_format.format(0.0),
+        number_format.format(0.0)
+    );
+    open_input_dialog(
+        font,
+        editor,
+        context,
+        "Сдвиг",
+        ("Смещение:", &text),
+        on_ok,
+        Some(on_pick),
+    );
+}
+
+/// Angle of the direction from the base in radians.
+fn get_angle(base: Point, point: Point) -> f64 {
+    (point.y - base.y).atan2(point.x - base.x)
+}
+
+/// Rotates the selection around the picked center, the angle is typed or picked as the
+/// direction from the center, measured from the zero direction of the angle format.
+fn rotate(font: Font, editor: Rc<RefCell<Editor>>, context: Rc<RefCell<window::Context>>) {
+    if !check_selection(&editor, context.clone()) {
+        return;
+    }
+    editor.borrow().pick_point(
+        PointPick::new("Центр поворота"),
+        callback!([editor, context] (base) {
+            open_rotation_dialog(&font, editor, context, base);
+        }),
+    );
+}
+
+fn open_rotation_dialog(
+    font: &Font,
+    editor: Rc<RefCell<Editor>>,
+    context: Rc<RefCell<window::Context>>,
+    base: Point,
+) {
+    let number_format = editor.borrow().config.borrow().number_format;
+    let angle_format = editor.borrow().config.borrow().angle_format;
+    let get_picked_angle = move |point| get_angle(base, point) - angle_format.get_zero_angle();
+    let weak_editor = Rc::downgrade(&editor);
+    let on_ok = move |text: &str| match angle_format.parse_rotation(text, &number_format) {
+        Some(angle) => {
+            if let Some(editor) = weak_editor.upgrade() {
+                editor.borrow().run_command(rotation_command(
+                    base,
+                    angle,
+                    &number_format,
+                    &angle_format,
+                ));
+            }
+            Ok(())
+        }
+        None => Err(format!("{} - не число!", text)),
+    };
+    let on_pick: Rc<dyn Fn()> = Rc::new(callback!([editor] () {
+        let pick = PointPick::new("Угол:").from(base).polar().preview(move |point| {
+            let angle = get_picked_angle(point);
+            (
+                Transform::rotation(base, angle),
+                angle_format.format_rotation(angle, &number_format),
+            )
+        });
+        editor.borrow().pick_point(
+            pick,
+            callback!([editor] (point) {
+                if point != base {
+                    editor.borrow().run_command(rotation_command(
+                        base,
+                        get_picked_angle(point),
+                        &number_format,
+                        &angle_format,
+                    ));
+                }
+            }),
+        );
+    }));
+    open_input_dialog(
+        font,
+        editor,
+        context,
+        "Поворот",
+        (
+            "Угол:",
+            &angle_format.format_rotation(std::f64::consts::FRAC_PI_2, &number_format),
+        ),
+        on_ok,
+        Some(on_pick),
+    );
+}
+
+/// Where the reference direction of the rotation is taken from.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum ReferenceMode {
+    TwoPoints,
+    FromCenter,
+    Entity,
+}
+
+impl ReferenceMode {
+    fn get_hint(self) -> &'static str {
+        match self {
+            ReferenceMode::TwoPoints => "по двум точкам (C - от центра, E - по объекту)",
+            ReferenceMode::FromCenter => "от центра (C - по двум точкам, E - по объекту)",
+            ReferenceMode::Entity => "по объекту (E - по двум точкам, C - от центра)",
+        }
+    }
+
+    /// The key turns its mode on, or back to two points if it is already on.
+    fn toggle(self, mode: ReferenceMode) -> Self {
+        if self == mode {
+            ReferenceMode::TwoPoints
+        } else {
+            mode
+        }
+    }
+}
+
+/// Rotates the selection around the picked center, so that the reference direction goes
+/// to the new one. The reference is given by two points, by one point and the center, or
+/// by a line of the drawing, the new direction is typed or picked.
+fn rotate_by_reference(
+    font: Font,
+    editor: Rc<RefCell<Editor>>,
+    context: Rc<RefCell<window::Context>>,
+) {
+    if !check_selection(&editor, context.clone()) {
+        return;
+    }
+    editor.borrow().pick_point(
+        PointPick::new("Центр поворота"),
+        callback!([editor, context] (base) {
+            let font = font.clone();
+            let mode = Rc::new(Cell::new(ReferenceMode::TwoPoints));
+            let pick = PointPick::new("Образец")
+                .from(base)
+                .preview({
+                    let mode = mode.clone();
+                    move |_| (Transform::default(), mode.get().get_hint().to_string())
+                })
+                .key(Key::C, {
+                    let mode = mode.clone();
+                    move || mode.set(mode.get().toggle(ReferenceMode::FromCenter))
+                })
+                .key(Key::E, {
+                    let mode = mode.clone();
+                    move || mode.set(mode.get().toggle(ReferenceMode::Entity))
+                });
+            editor.borrow().pick_point(
+                pick,
+                callback!([editor, context] (point) {
+                    let font = font.clone();
+                    match mode.get() {
+                        ReferenceMode::TwoPoints => editor.borrow().pick_point(
+                            PointPick::new("Вторая точка образца").from(point),
+                            callback!([editor, context] (point2) {
+                                let reference = point2 - point;
+                                open_new_direction_dialog(&font, editor, context, base, reference);
+                            }),
+                        ),
+                        ReferenceMode::FromCenter => {
+                            open_new_direction_dialog(&font, editor, context, base, point - base);
+                        }
+                        ReferenceMode::Entity => {
+                            let reference = editor.borrow().get_line_direction_at(point);
+                            match reference {
+                                Some(reference) => open_new_direction_dialog(
+                                    &font, editor, context, base, reference,
+                                ),
+                                None => show_message(
+                                    context,
+                                    "Под курсором нет отрезка или прямой",
+                                    "Поворот по образцу",
+                                ),
+                            }
+                        }
+                    }
+                }),
+            );
+        }),
+    );
+}
+
+fn open_new_direction_dialog(
+    font: &Font,
+    editor: Rc<RefCell<Editor>>,
+    context: Rc<RefCell<window::Context>>,
+    base: Point,
+    reference: Point,
+) {
+    if reference.length() < MIN_REFERENCE_LENGTH {
+        show_message(context, "Длина образца равна нулю", "Поворот по образцу");
+        return;
+    }
+    let number_format = editor.borrow().config.borrow().number_format;
+    let angle_format = editor.borrow().config.borrow().angle_format;
+    // directions are typed and shown in the UCS
+    let ucs = editor.borrow().get_ucs();
+    let weak_editor = Rc::downgrade(&editor);
+    let on_ok = move |text: &str| match angle_format.parse_direction(text, &number_format) {
+        Some(angle) => {
+            let direction = ucs.vector_to_world(Point::angle(angle));
+            let transform = Transform::rotation_by_reference(base, reference, direction);
+            if let (Some(editor), Some(transform)) = (weak_editor.upgrade(), transform) {
+                editor.borrow().run_command(reference_rotation_command(
+                    transform,
+                    &number_format,
+                    &angle_format,
+                ));
+            }
+            Ok(())
+        }
+        None => Err(format!("{} - не число!", text)),
+    };
+    let on_pick: Rc<dyn Fn()> = Rc::new(callback!([editor] () {
+        let get_transform =
+            move |point: Point| Transform::rotation_by_reference(base, reference, point - base);
+        let pick = PointPick::new("Новое направление:").from(base).polar().preview(move |point| {
+            let transform = get_transform(point).unwrap_or_default();
+            let angle = transform.rotation.y.atan2(transform.rotation.x);
+            (transform, angle_format.format_rotation(angle, &number_format))
+        });
+        editor.borrow().pick_point(
+            pick,
+            callback!([editor] (point) {
+                if let Some(transform) = get_transform(point) {
+                    editor.borrow().run_command(reference_rotation_command(
+                        transform,
+                        &number_format,
+                        &angle_format,
+                    ));
+                }
+            }),
+        );
+    }));
+    let reference_angle = get_angle(Point::new(0.0, 0.0), ucs.vector_to_local(reference));
+    open_input_dialog(
+        font,
+        editor,
+        context,
+        "Поворот по образцу",
+        (
+            "Новый угол:",
+            &angle_format.format_direction(reference_angle, &number_format),
+        ),
+        on_ok,
+        Some(on_pick),
+    );
+}
+
+/// Moves and turns the selection, so that two picked source points go to two picked
+/// destination points.
+fn align(editor: Rc<RefCell<Editor>>, context: Rc<RefCell<window::Context>>, scale: bool) {
+    if !check_selection(&editor, context) {
+        return;
+    }
+    editor.borrow().pick_point(
+        PointPick::new("Первая исходная точка"),
+        callback!([editor] (source1) {
+            editor.borrow().pick_point(
+                PointPick::new("Первая целевая точка").from(source1),
+                callback!([editor] (destination1) {
+                    editor.borrow().pick_point(
+                        PointPick::new("Вторая исходная точка"),
+                        callback!([editor] (source2) {
+                            let source = (source1, source2);
+                            let pick = PointPick::new("Вторая целевая точка")
+                                .from(destination1)
+                                .preview(move |destination2| {
+                                    let destination = (destination1, destination2);
+                                    (Transform::align(source, destination, scale), String::new())
+                                });
+                            editor.borrow().pick_point(
+                                pick,
+                                callback!([editor] (destination2) {
+                                    let destination = (destination1, destination2);
+                                    let caption = if scale {
+                                        "Выровнять с масштабом"
+                                    } else {
+                                        "Выровнять"
+                                    };
+                                    editor.borrow().run_command(Command::Transform(
+                                        caption.to_string(),
+                                        Transform::align(source, destination, scale),
+                                    ));
+                                }),
+                            );
+                        }),
+                    );
+                }),
+            );
+        }),
+    );
+}
+
+/// Lengthens or shortens the selected lines and arcs at both ends by the typed length.
+fn open_extend_dialog(
+    font: &Font,
+    editor: Rc<RefCell<Editor>>,
+    context: Rc<RefCell<window::Context>>,
+) {
+    if !check_selection(&editor, context.clone()) {
+        return;
+    }
+    let number_format = editor.borrow().config.borrow().number_format;
+    let weak_editor = Rc::downgrade(&editor);
+    let on_ok = move |text: &str| match number_format.parse(text) {
+        Some(length) if length != 0.0 => {
+            if let Some(editor) = weak_editor.upgrade() {
+                editor.borrow().run_command(Command::Extend(
+                    format!("Удлинить на {}", number_format.format(length)),
+                    length,
+                ));
+            }
+            Ok(())
+        }
+        _ => Err(format!("{} - не число или ноль!", text)),
+    };
+    open_input_dialog(
+        font,
+        editor,
+        context,
+        "Удлинить",
+        ("Длина, отрицательная укорачивает:", ""),
+        on_ok,
+        None,
+    );
+}
+
+/// More parts would hardly be told apart, and the points could exceed the entity limit.
+const MAX_DIVISIONS: usize = 1000;
+
+/// Places points splitting each selected curve into the typed number of equal parts.
+fn open_divide_dialog(
+    font: &Font,
+    editor: Rc<RefCell<Editor>>,
+    context: Rc<RefCell<window::Context>>,
+) {
+    if !check_selection(&editor, context.clone()) {
+        return;
+    }
+    let weak_editor = Rc::downgrade(&editor);
+    let on_ok = move |text: &str| match text.trim().parse::<usize>() {
+        Ok(count) if (2..=MAX_DIVISIONS).contains(&count) => {
+            if let Some(editor) = weak_editor.upgrade() {
+                editor.borrow().run_command(Command::Divide(count));
+            }
+            Ok(())
+        }
+        _ => Err(format!("{} - не число от 2 до {}!", text, MAX_DIVISIONS)),
+    };
+    open_input_dialog(
+        font,
+        editor,
+        context,
+        "Разделить",
+        ("Число частей:", "2"),
+        on_ok,
+        None,
+    );
+}
+
+/// Places points along each selected curve at the typed steps from its begin.
+fn open_measure_dialog(
+    font: &Font,
+    editor: Rc<RefCell<Editor>>,
+    context: Rc<RefCell<window::Context>>,
+) {
+    if !check_selection(&editor, context.clone()) {
+        return;
+    }
+    let number_format = editor.borrow().config.borrow().number_format;
+    let weak_editor = Rc::downgrade(&editor);
+    let on_ok = move |text: &str| match number_format.parse(text) {
+        Some(step) if step > 0.0 => {
+            if let Some(editor) = weak_editor.upgrade() {
+                editor.borrow().run_command(Command::Measure(
+                    format!("Разметить с шагом {}", number_format.format(step)),
+                    step,
+                ));
+            }
+            Ok(())
+        }
+        _ => Err(format!("{} - не положительное число!", text)),
+    };
+    open_input_dialog(
+        font,
+        editor,
+        context,
+        "Разметить",
+        ("Шаг:", ""),
+        on_ok,
+        None,
+    );
+}
+
+pub fn create_transform_menu(
+    parent: &mut TabControl,
+    font: &Font,
+    editor: Rc<RefCell<Editor>>,
+    context: Rc<RefCell<window::Context>>,
+) -> Rc<RefCell<Container>> {
     let font_height = font.get_size("8").1 as i32 + 2;
 
-    let menu_caption = "Преобразовать";
+    let menu_caption = "Преобразо&вать";
     let transform_menu = parent.add_tab(
         menu_caption.to_string(),
-        GuiSystem::default_size(&menu_caption, None, &font)
-            .0
-            .absolute,
+        Button::default_size(&menu_caption, None, &font).0.absolute,
         Container::new(
             SizeConstraints(
                 SizeConstraint::flexible(0),
@@ -25,21 +656,131 @@ pub fn create_transform_menu(parent: &mut TabControl, font: &Font) -> Rc<RefCell
         ),
     );
 
-    let _translate_button = transform_menu
-        .borrow_mut()
-        .add_child(create_default_size_button("Сдвиг", font.clone()));
+    {
+        let font = font.clone();
+        let _translate_button = transform_menu.borrow_mut().add_child(
+            create_default_size_button("Сдвиг", font.clone()).callback(callback!(
+                [editor, context]() {
+                    translate(font.clone(), editor, context);
+                }
+            )),
+        );
+    }
 
     let _copy_button = transform_menu
         .borrow_mut()
         .add_child(create_default_size_button("Копия", font.clone()));
 
-    let _rotate_button = transform_menu
-        .borrow_mut()
-        .add_child(create_default_size_button("Поворот", font.clone()));
+    {
+        let font = font.clone();
+        let _rotate_button = transform_menu.borrow_mut().add_child(
+            create_default_size_button("Поворот", font.clone()).callback(callback!(
+                [editor, context]() {
+                    rotate(font.clone(), editor, context);
+                }
+            )),
+        );
+    }
+
+    {
+        let font = font.clone();
+        let _rotate_reference_button = transform_menu.borrow_mut().add_child(
+            create_default_size_button("Поворот по образцу", font.clone()).callback(callback!(
+                [editor, context]() {
+                    rotate_by_reference(font.clone(), editor, context);
+                }
+            )),
+        );
+    }
+
+    let _align_button = transform_menu.borrow_mut().add_child(
+        create_default_size_button("Выровнять", font.clone()).callback(callback!(
+            [editor, context]() {
+                align(editor, context, false);
+            }
+        )),
+    );
+
+    let _align_scale_button = transform_menu.borrow_mut().add_child(
+        create_default_size_button("Выровнять с масштабом", font.clone()).callback(callback!(
+            [editor, context]() {
+                align(editor, context, true);
+            }
+        )),
+    );
 
     let _rotate_array_button = transform_menu
         .borrow_mut()
         .add_child(create_default_size_button("Круговой массив", font.clone()));
 
+    {
+        let font = font.clone();
+        let _scale_button = transform_menu.borrow_mut().add_child(
+            create_default_size_button("Масштаб по образцу", font.clone()).callback(callback!(
+                [editor, context]() {
+                    scale_by_reference(font.clone(), editor, context);
+                }
+            )),
+        );
+    }
+
+    {
+        let font = font.clone();
+        let _extend_button = transform_menu.borrow_mut().add_child(
+            create_default_size_button("Удлинить", font.clone()).callback(callback!(
+                [editor, context]() {
+                    open_extend_dialog(&font, editor, context);
+                }
+            )),
+        );
+    }
+
+    {
+        let font = font.clone();
+        let _divide_button = transform_menu.borrow_mut().add_child(
+            create_default_size_button("Разделить", font.clone()).callback(callback!(
+                [editor, context]() {
+                    open_divide_dialog(&font, editor, context);
+                }
+            )),
+        );
+    }
+
+    {
+        let font = font.clone();
+        let _measure_button = transform_menu.borrow_mut().add_child(
+            create_default_size_button("Разметить", font.clone()).callback(callback!(
+                [editor, context]() {
+                    open_measure_dialog(&font, editor, context);
+                }
+            )),
+        );
+    }
+
+    let _reverse_button = transform_menu.borrow_mut().add_child(
+        create_default_size_button("Развернуть", font.clone()).callback(callback!(
+            [editor]() {
+                editor.borrow().run_command(Command::Reverse);
+            }
+        )),
+    );
+
+    let _normalize_directions_button = transform_menu.borrow_mut().add_child(
+        create_default_size_button("Выровнять направления", font.clone()).callback(callback!(
+            [editor]() {
+                editor.borrow().run_command(Command::NormalizeDirections);
+            }
+        )),
+    );
+
+    let _isometric_circle_button = transform_menu.borrow_mut().add_child(
+        create_default_size_button("Круг в изометрию", font.clone()).callback(callback!(
+            [editor]() {
+                let isoplane = editor.borrow().config.borrow().isoplane;
+                editor.borrow().run_command(Command::ConvertToIsometric(isoplane));
+            }
+        )),
+    );
+
     transform_menu
 }
@@ -0,0 +1,95 @@
+use curves::points::*;
+use serde::{Deserialize, Serialize};
+
+type Point = curves::points::Point<f64>;
+
+/// Points closer than this can't define the axes.
+const EPS: f64 = 1.0e-9;
+
+/// User coordinate system: the origin and the unit axes in the document coordinates.
+/// The axes are perpendicular, Y may go either way from X.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq)]
+pub struct Ucs {
+    pub origin: Point,
+    pub x_axis: Point,
+    pub y_axis: Point,
+}
+
+impl Default for Ucs {
+    fn default() -> Self {
+        Self {
+            origin: Point::new(0.0, 0.0),
+            x_axis: Point::new(1.0, 0.0),
+            y_axis: Point::new(0.0, 1.0),
+        }
+    }
+}
+
+impl Ucs {
+    /// Axes go along the document axes, only the origin is moved.
+    pub fn is_axis_aligned(&self) -> bool {
+        self.x_axis == Point::new(1.0, 0.0) && self.y_axis == Point::new(0.0, 1.0)
+    }
+
+    /// X axis goes from the origin to the second point, Y goes to the side of the third
+    /// one.
+    pub fn from_points(origin: Point, x_point: Point, y_point: Point) -> Option<Self> {
+        let x_direction = x_point - origin;
+        if x_direction.length() < EPS {
+            return None;
+        }
+        let x_axis = x_direction.normalize();
+        let side = cross(x_axis, y_point - origin);
+        if side.abs() < EPS {
+            return None;
+        }
+        let y_axis = if side > 0.0 {
+            x_axis.rot90()
+        } else {
+            x_axis.rot90().neg()
+        };
+        Some(Self {
+            origin,
+            x_axis,
+            y_axis,
+        })
+    }
+
+    /// Axes turned by the angle counterclockwise from the document X axis, Y is turned
+    /// like in the document.
+    pub fn rotated(origin: Point, angle: f64) -> Self {
+        let x_axis = Point::angle(angle);
+        Self {
+            origin,
+            x_axis,
+            y_axis: x_axis.rot90(),
+        }
+    }
+
+    pub fn to_local(&self, point: Point) -> Point {
+        self.vector_to_local(point - self.origin)
+    }
+
+    pub fn to_world(&self, point: Point) -> Point {
+        self.origin + self.vector_to_world(point)
+    }
+
+    /// Offsets don't depend on the origin.
+    pub fn vector_to_local(&self, vector: Point) -> Point {
+        Point::new(dot(vector, self.x_axis), dot(vector, self.y_axis))
+    }
+
+    pub fn vector_to_world(&self, vector: Point) -> Point {
+        self.x_axis.scale(vector.x) + self.y_axis.scale(vector.y)
+    }
+
+    /// Y goes clockwise from X, so counterclockwise in the UCS is clockwise in the document.
+    pub fn is_mirrored(&self) -> bool {
+        cross(self.x_axis, self.y_axis) < 0.0
+    }
+
+    /// Direction of the X axis in radians, counted like the document angles.
+    pub fn get_angle(&self) -> f64 {
+        self.x_axis.y.atan2(self.x_axis.x)
+    }
+}
@@ -0,0 +1,137 @@
+use std::cmp::{max, min};
+
+use application::image::Position;
+use window::{MonitorInfo, WindowPosition};
+
+/// Part of the window at its top which has to be on a monitor, so it can be dragged by the
+/// title.
+const VISIBLE_TITLE_HEIGHT: i32 = 16;
+
+/// Key of the set of monitors, the same for the same monitors in any order. FNV-1a is
+/// used, as the hash of std may change between the versions of Rust.
+pub fn get_monitor_configuration_key(monitors: &[MonitorInfo]) -> String {
+    let mut rects: Vec<_> = monitors
+        .iter()
+        .map(|monitor| (monitor.left_top, monitor.right_bottom))
+        .collect();
+    rects.sort();
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for ((left, top), (right, bottom)) in rects {
+        for byte in [left, top, right, bottom]
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+        {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    format!("{:016x}", hash)
+}
+
+fn get_intersection_area(
+    (left_top, right_bottom): (Position, Position),
+    (other_left_top, other_right_bottom): (Position, Position),
+) -> i64 {
+    let width = min(right_bottom.0, other_right_bottom.0) - max(left_top.0, other_left_top.0);
+    let height = min(right_bottom.1, other_right_bottom.1) - max(left_top.1, other_left_top.1);
+    if width <= 0 || height <= 0 {
+        return 0;
+    }
+    width as i64 * height as i64
+}
+
+/// Monitor with the greatest part of the window, the maximized window is a bit bigger than
+/// its monitor.
+fn find_monitor(monitors: &[MonitorInfo], position: &WindowPosition) -> Option<MonitorInfo> {
+    let rect = (position.left_top, position.right_bottom);
+    let (monitor, area) = monitors
+        .iter()
+        .map(|monitor| {
+            let monitor_rect = (monitor.left_top, monitor.right_bottom);
+            (monitor, get_intersection_area(rect, monitor_rect))
+        })
+        .max_by_key(|(_, area)| *area)?;
+    if area == 0 {
+        return None;
+    }
+    Some(*monitor)
+}
+
+/// Moves the window into the work area of the monitor, the size is kept if it fits.
+fn move_to_work_area(position: &mut WindowPosition, monitor: &MonitorInfo) {
+    let (work_left, work_top) = monitor.work_left_top;
+    let (work_right, work_bottom) = monitor.work_right_bottom;
+    let width = min(
+        position.right_bottom.0 - position.left_top.0,
+        work_right - work_left,
+    );
+    let height = min(
+        position.right_bottom.1 - position.left_top.1,
+        work_bottom - work_top,
+    );
+    let left = position.left_top.0.clamp(work_left, work_right - width);
+    let top = position.left_top.1.clamp(work_top, work_bottom - height);
+    position.left_top = (left, top);
+    position.right_bottom = (left + width, top + height);
+}
+
+/// The saved place if the title of the window is on one of the monitors, otherwise None
+/// and the window is placed by default.
+pub fn get_valid_position(
+    position: WindowPosition,
+    monitors: &[MonitorInfo],
+) -> Option<WindowPosition> {
+    let (left, top) = position.left_top;
+    let (right, bottom) = position.right_bottom;
+    if right <= left || bottom <= top {
+        return None;
+    }
+    let title = ((left, top), (right, top + VISIBLE_TITLE_HEIGHT));
+    let monitor = monitors.iter().find(|monitor| {
+        let work_rect = (monitor.work_left_top, monitor.work_right_bottom);
+        get_intersection_area(title, work_rect) > 0
+    })?;
+    let mut result = position;
+    // the resolution is a part of the key, so this only fixes a changed taskbar
+    move_to_work_area(&mut result, monitor);
+    Some(result)
+}
+
+/// The place to save after the window has moved. The maximized window keeps the saved
+/// place as the one it is restored to, moved to the monitor it was maximized on.
+pub fn update_position(
+    saved: Option<WindowPosition>,
+    current: WindowPosition,
+    monitors: &[MonitorInfo],
+) -> WindowPosition {
+    if !current.maximized {
+        return current;
+    }
+    let mut result = match saved {
+        Some(saved) => saved,
+        None => return current,
+    };
+    result.maximized = true;
+    let saved_monitor = find_monitor(monitors, &result);
+    let current_monitor = match find_monitor(monitors, &current) {
+        Some(monitor) => monitor,
+        None => return result,
+    };
+    if saved_monitor == Some(current_monitor) {
+        return result;
+    }
+    if let Some(saved_monitor) = saved_monitor {
+        // the same place relative to the other monitor
+        let offset = (
+            current_monitor.work_left_top.0 - saved_monitor.work_left_top.0,
+            current_monitor.work_left_top.1 - saved_monitor.work_left_top.1,
+        );
+        result.left_top = (result.left_top.0 + offset.0, result.left_top.1 + offset.1);
+        result.right_bottom = (
+            result.right_bottom.0 + offset.0,
+            result.right_bottom.1 + offset.1,
+        );
+    }
+    move_to_work_area(&mut result, &current_monitor);
+    return result;
+}
@@ -0,0 +1,151 @@
+use std::f64::consts::{FRAC_PI_2, TAU};
+
+use serde::{Deserialize, Serialize};
+
+use crate::number_format::NumberFormat;
+
+#[derive(Serialize, Deserialize, Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum AngleUnits {
+    #[default]
+    Degrees,
+    Radians,
+    Gradians,
+}
+
+impl AngleUnits {
+    fn get_full_turn(self) -> f64 {
+        match self {
+            AngleUnits::Degrees => 360.0,
+            AngleUnits::Radians => TAU,
+            AngleUnits::Gradians => 400.0,
+        }
+    }
+
+    pub fn get_suffix(self) -> &'static str {
+        match self {
+            AngleUnits::Degrees => "°",
+            AngleUnits::Radians => " рад",
+            AngleUnits::Gradians => " гон",
+        }
+    }
+}
+
+/// Counterclockwise goes from the X axis to the Y axis.
+#[derive(Serialize, Deserialize, Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum AngleDirection {
+    #[default]
+    Counterclockwise,
+    Clockwise,
+}
+
+/// East is the X axis, north is the Y axis.
+#[derive(Serialize, Deserialize, Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum AngleZero {
+    #[default]
+    East,
+    North,
+}
+
+/// How angles are shown and typed. Inside, angles are radians counted from the X axis
+/// toward the Y axis.
+#[derive(Serialize, Deserialize, Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct AngleFormat {
+    #[serde(default)]
+    pub units: AngleUnits,
+    #[serde(default)]
+    pub direction: AngleDirection,
+    #[serde(default)]
+    pub zero: AngleZero,
+}
+
+impl AngleFormat {
+    fn get_sign(&self) -> f64 {
+        match self.direction {
+            AngleDirection::Counterclockwise => 1.0,
+            AngleDirection::Clockwise => -1.0,
+        }
+    }
+
+    /// Direction of the zero angle in radians.
+    pub fn get_zero_angle(&self) -> f64 {
+        match self.zero {
+            AngleZero::East => 0.0,
+            AngleZero::North => FRAC_PI_2,
+        }
+    }
+
+    fn to_units(self, angle: f64) -> f64 {
+        angle * self.units.get_full_turn() / TAU
+    }
+
+    /// The suffix of the units may be typed or not.
+    fn parse_units(&self, text: &str, number_format: &NumberFormat) -> Option<f64> {
+        let text = text.trim();
+        let suffix = self.units.get_suffix().trim();
+        let value = number_format.parse(text.strip_suffix(suffix).unwrap_or(text))?;
+        Some(value * TAU / self.units.get_full_turn())
+    }
+
+    /// Angle of a turn, the zero direction doesn't matter for it.
+    pub fn format_rotation(&self, angle: f64, number_format: &NumberFormat) -> String {
+        let value = self.to_units(angle * self.get_sign());
+        format!("{}{}", number_format.format(value), self.units.get_suffix())
+    }
+
+    pub fn parse_rotation(&self, text: &str, number_format: &NumberFormat) -> Option<f64> {
+        Some(self.parse_units(text, number_format)? * self.get_sign())
+    }
+
+    /// Angle of a direction, counted from the zero direction within a full turn.
+    pub fn format_direction(&self, angle: f64, number_format: &NumberFormat) -> String {
+        let angle = ((angle - self.get_zero_angle()) * self.get_sign()).rem_euclid(TAU);
+        format!(
+            "{}{}",
+            number_format.format(self.to_units(angle)),
+            self.units.get_suffix()
+        )
+    }
+
+    pub fn parse_direction(&self, text: &str, number_format: &NumberFormat) -> Option<f64> {
+        Some(self.parse_units(text, number_format)? * self.get_sign() + self.get_zero_angle())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn number_format() -> NumberFormat {
+        NumberFormat {
+            decimal_separator: Some('.'),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_default_format() {
+        let format = AngleFormat::default();
+        let number_format = number_format();
+        assert_eq!(format.format_direction(-FRAC_PI_2, &number_format), "270°");
+        assert_eq!(format.format_rotation(-FRAC_PI_2, &number_format), "-90°");
+        let angle = format.parse_direction("45°", &number_format).unwrap();
+        assert!((angle - TAU / 8.0).abs() < 1e-12);
+        assert_eq!(format.parse_rotation("abc", &number_format), None);
+    }
+
+    #[test]
+    fn test_clockwise_from_north() {
+        let format = AngleFormat {
+            units: AngleUnits::Gradians,
+            direction: AngleDirection::Clockwise,
+            zero: AngleZero::North,
+        };
+        let number_format = number_format();
+        // the X axis is a quarter turn clockwise from the Y axis
+        assert_eq!(format.format_direction(0.0, &number_format), "100 гон");
+        let angle = format.parse_direction("100", &number_format).unwrap();
+        assert!(angle.abs() < 1e-12);
+        let angle = format.parse_rotation("50 гон", &number_format).unwrap();
+        assert!((angle + TAU / 8.0).abs() < 1e-12);
+    }
+}
@@ -1,6 +1,10 @@
 use std::cell::RefCell;
+use std::collections::VecDeque;
 use std::rc::Rc;
 
+/// Texts put by the program which are remembered, the older ones are forgotten.
+pub const CLIPBOARD_HISTORY_SIZE: usize = 20;
+
 pub trait ClipboardHandler: std::fmt::Debug {
     fn get_string(&self) -> Option<String>;
     fn put_string(&mut self, text: &str);
@@ -9,18 +13,32 @@ pub trait ClipboardHandler: std::fmt::Debug {
 #[derive(Debug, Clone)]
 pub struct Clipboard {
     handler: Rc<RefCell<dyn ClipboardHandler>>,
+    /// The last put text first, the texts copied in other programs are not here.
+    history: Rc<RefCell<VecDeque<String>>>,
 }
 
 impl Clipboard {
     pub fn new(handler: impl ClipboardHandler + 'static) -> Self {
         Self {
             handler: Rc::new(RefCell::new(handler)),
+            history: Rc::new(RefCell::new(VecDeque::new())),
         }
     }
     pub fn get_string(&self) -> Option<String> {
         self.handler.borrow().get_string()
     }
     pub fn put_string(&mut self, text: &str) {
-        self.handler.borrow_mut().put_string(text)
+        self.handler.borrow_mut().put_string(text);
+        // putting a text again moves it to the top
+        let mut history = self.history.borrow_mut();
+        history.retain(|item| item != text);
+        history.push_front(text.to_string());
+        history.truncate(CLIPBOARD_HISTORY_SIZE);
+    }
+    pub fn get_history(&self) -> Vec<String> {
+        self.history.borrow().iter().cloned().collect()
+    }
+    pub fn clear_history(&mut self) {
+        self.history.borrow_mut().clear();
     }
 }
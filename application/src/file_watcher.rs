@@ -0,0 +1,137 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+pub trait FileWatcherHandler: std::fmt::Debug {
+    /// Starts reporting changes of the files in the folder.
+    fn watch_folder(&mut self, folder: &Path);
+    fn unwatch_folder(&mut self, folder: &Path);
+    /// Files changed in the watched folders since the last call, in any order.
+    fn take_changes(&mut self) -> Vec<PathBuf>;
+}
+
+/// Reports changes of the watched files, a file can be watched several times.
+#[derive(Debug, Clone)]
+pub struct FileWatcher {
+    handler: Rc<RefCell<dyn FileWatcherHandler>>,
+    files: Rc<RefCell<HashMap<PathBuf, usize>>>,
+}
+
+fn get_folder(file: &Path) -> PathBuf {
+    file.parent().unwrap_or(Path::new("")).to_path_buf()
+}
+
+impl FileWatcher {
+    pub fn new(handler: impl FileWatcherHandler + 'static) -> Self {
+        Self {
+            handler: Rc::new(RefCell::new(handler)),
+            files: Rc::default(),
+        }
+    }
+
+    fn is_folder_watched(&self, folder: &Path) -> bool {
+        self.files
+            .borrow()
+            .keys()
+            .any(|file| get_folder(file) == folder)
+    }
+
+    pub fn watch(&self, file: &Path) {
+        let folder = get_folder(file);
+        if !self.is_folder_watched(&folder) {
+            self.handler.borrow_mut().watch_folder(&folder);
+        }
+        *self
+            .files
+            .borrow_mut()
+            .entry(file.to_path_buf())
+            .or_default() += 1;
+    }
+
+    pub fn unwatch(&self, file: &Path) {
+        {
+            let mut files = self.files.borrow_mut();
+            match files.get_mut(file) {
+                Some(count) if *count > 1 => {
+                    *count -= 1;
+                    return;
+                }
+                Some(_) => {
+                    files.remove(file);
+                }
+                None => return,
+            }
+        }
+        let folder = get_folder(file);
+        if !self.is_folder_watched(&folder) {
+            self.handler.borrow_mut().unwatch_folder(&folder);
+        }
+    }
+
+    /// Watched files changed since the last call, each file once.
+    pub fn take_changed_files(&self) -> Vec<PathBuf> {
+        let changes = self.handler.borrow_mut().take_changes();
+        let files = self.files.borrow();
+        let mut result: Vec<PathBuf> = Vec::new();
+        for file in changes {
+            if files.contains_key(&file) && !result.contains(&file) {
+                result.push(file);
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Default)]
+    struct TestHandler {
+        folders: Rc<RefCell<Vec<PathBuf>>>,
+        changes: Rc<RefCell<Vec<PathBuf>>>,
+    }
+
+    impl FileWatcherHandler for TestHandler {
+        fn watch_folder(&mut self, folder: &Path) {
+            self.folders.borrow_mut().push(folder.to_path_buf());
+        }
+
+        fn unwatch_folder(&mut self, folder: &Path) {
+            self.folders.borrow_mut().retain(|f| f != folder);
+        }
+
+        fn take_changes(&mut self) -> Vec<PathBuf> {
+            std::mem::take(&mut self.changes.borrow_mut())
+        }
+    }
+
+    #[test]
+    fn folders_are_watched_while_files_are() {
+        let handler = TestHandler::default();
+        let folders = handler.folders.clone();
+        let changes = handler.changes.clone();
+        let watcher = FileWatcher::new(handler);
+        let a = Path::new("drawings/a.json");
+        let b = Path::new("drawings/b.json");
+        watcher.watch(a);
+        watcher.watch(a);
+        watcher.watch(b);
+        assert_eq!(*folders.borrow(), vec![PathBuf::from("drawings")]);
+
+        changes.borrow_mut().extend([
+            a.to_path_buf(),
+            PathBuf::from("drawings/c.json"),
+            a.to_path_buf(),
+        ]);
+        assert_eq!(watcher.take_changed_files(), vec![a.to_path_buf()]);
+        assert!(watcher.take_changed_files().is_empty());
+
+        watcher.unwatch(a);
+        watcher.unwatch(b);
+        assert_eq!(folders.borrow().len(), 1);
+        watcher.unwatch(a);
+        assert!(folders.borrow().is_empty());
+    }
+}
@@ -0,0 +1,104 @@
+use std::time::{Duration, Instant};
+
+/// Value which follows its target smoothly: the rest of the way shrinks exponentially,
+/// so the motion slows down near the target. Controls update it on timer messages.
+#[derive(Debug, Copy, Clone)]
+pub struct SmoothValue {
+    current: f32,
+    target: f32,
+    time_constant: Duration,
+    last_update: Option<Instant>,
+}
+
+/// Distance to the target which is treated as reached.
+const SMOOTH_VALUE_EPS: f32 = 0.01;
+
+impl SmoothValue {
+    pub fn new(value: f32, time_constant: Duration) -> Self {
+        Self {
+            current: value,
+            target: value,
+            time_constant,
+            last_update: None,
+        }
+    }
+
+    pub fn get(&self) -> f32 {
+        self.current
+    }
+
+    pub fn get_target(&self) -> f32 {
+        self.target
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.current != self.target
+    }
+
+    pub fn set_target(&mut self, target: f32) {
+        if self.target == target {
+            return;
+        }
+        if !self.is_running() {
+            self.last_update = Some(Instant::now());
+        }
+        self.target = target;
+    }
+
+    /// Moves to the value without animation.
+    pub fn jump(&mut self, value: f32) {
+        self.current = value;
+        self.target = value;
+        self.last_update = None;
+    }
+
+    /// Advances the value by the time passed since the last update, returns true if it
+    /// was changed.
+    pub fn update(&mut self) -> bool {
+        self.update_at(Instant::now())
+    }
+
+    pub fn update_at(&mut self, now: Instant) -> bool {
+        if !self.is_running() {
+            return false;
+        }
+        let last_update = self.last_update.unwrap_or(now);
+        self.last_update = Some(now);
+        let elapsed = now.saturating_duration_since(last_update).as_secs_f32();
+        let factor = (-elapsed / self.time_constant.as_secs_f32().max(f32::EPSILON)).exp();
+        let old_value = self.current;
+        self.current = self.target + (self.current - self.target) * factor;
+        if (self.current - self.target).abs() < SMOOTH_VALUE_EPS {
+            self.current = self.target;
+        }
+        self.current != old_value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn smooth_value_reaches_target() {
+        let start = Instant::now();
+        let mut value = SmoothValue::new(0.0, Duration::from_millis(100));
+        value.set_target(10.0);
+        value.update_at(start);
+        assert!(value.update_at(start + Duration::from_millis(100)));
+        assert!(value.get() > 5.0 && value.get() < 10.0);
+        assert!(value.update_at(start + Duration::from_secs(2)));
+        assert_eq!(value.get(), 10.0);
+        assert!(!value.is_running());
+        assert!(!value.update_at(start + Duration::from_secs(3)));
+    }
+
+    #[test]
+    fn smooth_value_jump_stops_animation() {
+        let mut value = SmoothValue::new(0.0, Duration::from_millis(100));
+        value.set_target(10.0);
+        value.jump(3.0);
+        assert_eq!(value.get(), 3.0);
+        assert!(!value.update());
+    }
+}
@@ -0,0 +1,66 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use crate::gui::gui_components::*;
+use crate::gui::*;
+use crate::property::*;
+
+thread_local! {
+    static REDRAW_REQUESTED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Controls changed outside of the message handlers are repainted on the next timer.
+pub fn request_redraw() {
+    REDRAW_REQUESTED.with(|requested| requested.set(true));
+}
+
+pub(crate) fn take_redraw_request() -> bool {
+    REDRAW_REQUESTED.with(|requested| requested.replace(false))
+}
+
+/// Marks the control to be repainted on the next timer. A control which is busy with
+/// a message is skipped, it is repainted after the message anyway.
+pub fn redraw_if_idle<C: GuiControl>(control: &RefCell<C>) {
+    if let Ok(mut control) = control.try_borrow_mut() {
+        control.get_base_mut().need_redraw = true;
+    }
+    request_redraw();
+}
+
+/// The check box shows the property and sets it when clicked.
+pub fn bind_check_box(button: &Rc<RefCell<Button>>, property: &Property<bool>) {
+    button.borrow_mut().set_checked(property.get());
+    let checked_property = property.clone();
+    button.borrow_mut().set_checkbox_callback(move |c| {
+        checked_property.set(c);
+    });
+    property.subscribe_weak(button, |button, checked| {
+        button.set_checked(*checked);
+        button.get_base_mut().need_redraw = true;
+        request_redraw();
+    });
+}
+
+/// The text box shows the property formatted by `format`.
+pub fn bind_text_box<T: PartialEq + Clone + 'static>(
+    text_box: &Rc<RefCell<TextBox>>,
+    property: &Property<T>,
+    format: impl Fn(&T) -> String + 'static,
+) {
+    text_box.borrow_mut().set_text(&format(&property.get()));
+    property.subscribe_weak(text_box, move |text_box, value| {
+        text_box.set_text(&format(value));
+        request_redraw();
+    });
+}
+
+/// The control is repainted whenever the property changes.
+pub fn redraw_on_change<C: GuiControl, T: PartialEq + Clone + 'static>(
+    control: &Rc<RefCell<C>>,
+    property: &Property<T>,
+) {
+    property.subscribe_weak(control, |control, _| {
+        control.get_base_mut().need_redraw = true;
+        request_redraw();
+    });
+}
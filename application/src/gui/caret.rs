@@ -0,0 +1,70 @@
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+use crate::gui::*;
+
+/// The caret is shown for this long, then hidden for as long.
+const CARET_BLINK_PERIOD: Duration = Duration::from_millis(530);
+
+thread_local! {
+    /// Caret of the focused control in the last frame, the blink redraws only its owner.
+    static CARET_RECT: Cell<Option<Rect>> = const { Cell::new(None) };
+    static CARET_SHOWN: Cell<bool> = const { Cell::new(true) };
+}
+
+/// Called by the focused text control while drawing, registers its caret and returns
+/// true if the caret is drawn in this frame.
+pub fn place_caret(rect: Rect) -> bool {
+    CARET_RECT.with(|caret| caret.set(Some(rect)));
+    CARET_SHOWN.with(Cell::get)
+}
+
+/// Rect of the caret of the focused control, it is in the window coordinates.
+pub fn get_caret_rect() -> Option<Rect> {
+    CARET_RECT.with(Cell::get)
+}
+
+#[derive(Debug)]
+pub(crate) struct CaretBlink {
+    /// Zero stops the blinking, the caret is always shown.
+    period: Duration,
+    since: Instant,
+}
+
+impl Default for CaretBlink {
+    fn default() -> Self {
+        Self {
+            period: CARET_BLINK_PERIOD,
+            since: Instant::now(),
+        }
+    }
+}
+
+impl CaretBlink {
+    pub(crate) fn set_period(&mut self, period: Duration) {
+        self.period = period;
+        self.restart();
+    }
+
+    /// The caret is shown from the start of a period, so it doesn't vanish while typing.
+    pub(crate) fn restart(&mut self) {
+        self.since = Instant::now();
+        CARET_SHOWN.with(|shown| shown.set(true));
+    }
+
+    /// The focus moved, the new owner registers its caret when it is drawn.
+    pub(crate) fn reset(&mut self) {
+        CARET_RECT.with(|caret| caret.set(None));
+        self.restart();
+    }
+
+    /// Returns true if the caret appeared or disappeared, so its owner has to be redrawn.
+    pub(crate) fn on_timer(&mut self) -> bool {
+        if get_caret_rect().is_none() {
+            return false;
+        }
+        let shown = self.period.is_zero()
+            || (self.since.elapsed().as_nanos() / self.period.as_nanos()).is_multiple_of(2);
+        CARET_SHOWN.with(|caret_shown| caret_shown.replace(shown) != shown)
+    }
+}
@@ -0,0 +1,150 @@
+use std::rc::Rc;
+
+use crate::font::*;
+use crate::gui::*;
+use crate::image::*;
+
+/// Candidates shown at once, the list scrolls with the selection.
+const VISIBLE_CANDIDATES: usize = 8;
+
+type ProvideFn = dyn Fn(&str) -> Vec<String> + 'static;
+
+/// Gives the candidates for the text of the input, the best one first.
+#[derive(Clone)]
+pub struct CompletionProvider(Rc<ProvideFn>);
+
+impl std::fmt::Debug for CompletionProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad("CompletionProvider")
+    }
+}
+
+impl CompletionProvider {
+    pub fn new(provide: impl Fn(&str) -> Vec<String> + 'static) -> Self {
+        Self(Rc::new(provide))
+    }
+
+    /// The words starting with the text, the case is ignored.
+    pub fn prefix(words: Vec<String>) -> Self {
+        Self::new(move |text| {
+            let text = text.to_lowercase();
+            words
+                .iter()
+                .filter(|word| word.to_lowercase().starts_with(&text))
+                .cloned()
+                .collect()
+        })
+    }
+
+    /// Candidates other than the text itself, there is nothing to complete with it.
+    pub fn get_candidates(&self, text: &str) -> Vec<String> {
+        let mut candidates = (self.0)(text);
+        candidates.retain(|candidate| candidate != text);
+        candidates
+    }
+}
+
+/// List of the candidates under the focused input, the input keeps it and the gui
+/// system draws it above everything.
+#[derive(Debug, Clone)]
+pub struct CompletionPopup {
+    pub candidates: Vec<String>,
+    pub selected: usize,
+    /// Rect of the input, the list goes below it or above if there is no space.
+    pub anchor: Rect,
+    pub font: Font,
+}
+
+impl CompletionPopup {
+    /// Popups looking the same, the font is not compared.
+    pub(crate) fn same_as(&self, other: &CompletionPopup) -> bool {
+        self.candidates == other.candidates
+            && self.selected == other.selected
+            && self.anchor == other.anchor
+    }
+
+    fn get_line_height(&self) -> i32 {
+        self.font.get_size("8").1 as i32 + 2
+    }
+
+    fn get_first_visible(&self) -> usize {
+        (self.selected + 1).saturating_sub(VISIBLE_CANDIDATES)
+    }
+
+    pub(crate) fn get_rect(&self, window_size: Position) -> Rect {
+        let line_height = self.get_line_height();
+        let count = self.candidates.len().min(VISIBLE_CANDIDATES) as i32;
+        let text_width = self
+            .candidates
+            .iter()
+            .map(|candidate| self.font.get_size(candidate).0 as i32)
+            .max()
+            .unwrap_or(0);
+        let width = (text_width + 4).max(self.anchor.right_bottom.0 - self.anchor.left_top.0);
+        let height = line_height * count + 2;
+        let left = self.anchor.left_top.0.min(window_size.0 - width).max(0);
+        let mut top = self.anchor.right_bottom.1;
+        if top + height > window_size.1 {
+            top = (self.anchor.left_top.1 - height).max(0);
+        }
+        Rect {
+            left_top: (left, top),
+            right_bottom: (left + width, top + height),
+        }
+    }
+
+    /// Index of the candidate under the mouse.
+    pub(crate) fn get_index_at(&self, window_size: Position, position: Position) -> Option<usize> {
+        let rect = self.get_rect(window_size);
+        if !rect.contains(position) {
+            return None;
+        }
+        let line = ((position.1 - rect.left_top.1 - 1).max(0) / self.get_line_height()) as usize;
+        let index = self.get_first_visible() + line;
+        if index < self.candidates.len() {
+            Some(index)
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn draw(&self, buf: &mut ImageViewMut<u32>, color_theme: &GuiColorTheme) {
+        let buf_size = image_size_to_position(buf.get_size());
+        let rect = self.get_rect(buf_size);
+        if rect.right_bottom.0 > buf_size.0 || rect.right_bottom.1 > buf_size.1 {
+            return;
+        }
+        let (width, height) = (
+            rect.right_bottom.0 - rect.left_top.0,
+            rect.right_bottom.1 - rect.left_top.1,
+        );
+        let mut popup_buf = buf.window_mut(
+            position_to_image_size(rect.left_top),
+            position_to_image_size(rect.right_bottom),
+        );
+        popup_buf.fill(|p| *p = color_theme.splitter);
+        popup_buf
+            .window_mut((1, 1), ((width - 1) as usize, (height - 1) as usize))
+            .fill(|p| *p = color_theme.background);
+        let line_height = self.get_line_height();
+        let text_font = self
+            .font
+            .color(color_theme.font)
+            .layout_vertical(TextLayoutVertical::MIDDLE)
+            .layout_horizontal(TextLayoutHorizontal::LEFT);
+        let first = self.get_first_visible();
+        let visible = self.candidates.iter().enumerate().skip(first);
+        for (line, (index, candidate)) in visible.take(VISIBLE_CANDIDATES).enumerate() {
+            let top = 1 + line_height * line as i32;
+            if index == self.selected {
+                popup_buf
+                    .window_mut(
+                        (1, top as usize),
+                        ((width - 1) as usize, (top + line_height) as usize),
+                    )
+                    .fill(|p| *p = color_theme.selected);
+            }
+            text_font.draw(candidate, (2, top + line_height / 2), &mut popup_buf);
+        }
+    }
+}
@@ -1,15 +1,22 @@
-use std::cell::RefCell;
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
 use std::cmp::{max, min};
 use std::ops::DerefMut;
 use std::rc::Rc;
+use std::time::{Duration, Instant};
 
 use crate::callback;
 use crate::callback_body;
 use crate::clipboard::Clipboard;
 use crate::font::*;
+use crate::gui::animation::*;
+use crate::gui::completion::*;
+use crate::gui::inspector::*;
+use crate::gui::tooltip::*;
 use crate::gui::*;
 use crate::image::*;
 use crate::keys::*;
+use crate::shell::Shell;
 
 #[derive(Debug, Copy, Clone)]
 pub enum ContainerLayout {
@@ -80,7 +87,8 @@ macro_rules! set_layout {
         let mut sum_relative = 0;
         let mut sum_absolute = 0;
         for child in &$self.children {
-            let child_size_constraints = child.borrow_mut().get_base_mut().current_size_constraints;
+            let child_size_constraints =
+                child.borrow_mut().get_base_mut().layout_size_constraints();
             sum_relative += child_size_constraints.$index1.relative;
             sum_absolute += child_size_constraints.$index1.absolute;
         }
@@ -101,7 +109,7 @@ macro_rules! set_layout {
 
         for child in &$self.children {
             let mut child = child.borrow_mut();
-            let child_size_constraints = child.get_base_mut().current_size_constraints;
+            let child_size_constraints = child.get_base_mut().layout_size_constraints();
             sum_child_absolute += child_size_constraints.$index1.absolute;
             sum_child_relative += child_size_constraints.$index1.relative;
 
@@ -182,13 +190,13 @@ impl GuiControl for Container {
                 let child_real_constraints: Vec<_> = self
                     .children
                     .iter_mut()
-                    .map(|c| c.borrow_mut().get_base_mut().current_size_constraints)
+                    .map(|c| c.borrow_mut().get_base_mut().layout_size_constraints())
                     .collect();
 
                 let child_minimal_sizes: Vec<_> = self
                     .children
                     .iter_mut()
-                    .map(|c| c.borrow_mut().get_base_mut().minimal_size)
+                    .map(|c| c.borrow_mut().get_base_mut().layout_minimal_size())
                     .collect();
 
                 let mut abs_constraints: Position = (0, 0);
@@ -285,10 +293,29 @@ impl GuiControl for Container {
                 }
                 return false;
             }
-            GuiMessage::Timer => {
+            GuiMessage::GetAccessibleNodes(nodes) => {
+                if self.base.visible {
+                    for child in &self.children {
+                        child
+                            .borrow_mut()
+                            .on_message(GuiMessage::GetAccessibleNodes(nodes));
+                    }
+                }
+                return true;
+            }
+            GuiMessage::GetDebugNodes(nodes) => {
+                for child in &self.children {
+                    nodes.push(DebugNode::new(child.borrow_mut().deref_mut()));
+                }
+                return true;
+            }
+            GuiMessage::Timer(job_system) => {
                 let mut result = false;
                 for child in &self.children {
-                    if child.borrow_mut().on_message(GuiMessage::Timer) {
+                    if child
+                        .borrow_mut()
+                        .on_message(GuiMessage::Timer(job_system.clone()))
+                    {
                         child.borrow_mut().get_base_mut().need_redraw = true;
                         result = true;
                     }
@@ -378,6 +405,38 @@ impl GuiControl for EmptySpace {
     }
 }
 
+/// Splits the caption like "&Файл" into the text to show and the accelerator: index of the
+/// underlined char and the char in lower case. "&&" stands for "&".
+pub fn parse_accelerator(caption: &str) -> (String, Option<(usize, char)>) {
+    let mut text = String::new();
+    let mut accelerator = None;
+    let mut chars = caption.chars().peekable();
+    let mut index = 0;
+    while let Some(c) = chars.next() {
+        if c == '&' {
+            match chars.next() {
+                Some('&') => text.push('&'),
+                Some(next) => {
+                    if accelerator.is_none() {
+                        accelerator = next.to_lowercase().next().map(|lower| (index, lower));
+                    }
+                    text.push(next);
+                }
+                None => break,
+            }
+        } else {
+            text.push(c);
+        }
+        index += 1;
+    }
+    (text, accelerator)
+}
+
+/// Caption showing the text as is, for names given by the user.
+pub fn escape_accelerator(text: &str) -> String {
+    text.replace('&', "&&")
+}
+
 #[derive(Clone)]
 pub struct ButtonCallback(Rc<dyn Fn() + 'static>);
 
@@ -393,6 +452,7 @@ enum ButtonCheckState {
     CheckBox(bool),
     RadioButton(bool),
     TabButton(bool),
+    ToggleButton(bool),
 }
 
 #[derive(Debug)]
@@ -405,6 +465,14 @@ pub struct Button {
     hotkey_is_global: bool,
     callback: Option<ButtonCallback>,
     check_state: Rc<RefCell<ButtonCheckState>>,
+    enabled: bool,
+    auto_repeat: bool,
+    /// Next time of the callback while the auto repeat button is held.
+    repeat_time: Option<Instant>,
+    repeated: bool,
+    tooltip: Option<Tooltip>,
+    /// Index of the underlined char of the text and the char activating the button with Alt.
+    accelerator: Option<(usize, char)>,
 }
 
 impl Button {
@@ -414,6 +482,7 @@ impl Button {
         font: Font,
         hotkey: Option<Hotkey>,
     ) -> Self {
+        let (text, accelerator) = parse_accelerator(&text);
         Self {
             base: GuiControlBase::new(size_constraints),
             holded_when_pushed: false,
@@ -425,6 +494,12 @@ impl Button {
             hotkey_is_global: false,
             callback: None,
             check_state: Rc::new(RefCell::new(ButtonCheckState::None)),
+            enabled: true,
+            auto_repeat: false,
+            repeat_time: None,
+            repeated: false,
+            tooltip: None,
+            accelerator,
         }
     }
 
@@ -440,6 +515,15 @@ impl Button {
         self.set_callback_impl(ButtonCallback(Rc::new(callback)));
     }
 
+    pub fn tooltip(mut self, tooltip: Tooltip) -> Self {
+        self.tooltip = Some(tooltip);
+        self
+    }
+
+    pub fn set_tooltip(&mut self, tooltip: Option<Tooltip>) {
+        self.tooltip = tooltip;
+    }
+
     pub fn global(mut self) -> Self {
         self.hotkey_is_global = true;
         self
@@ -453,7 +537,9 @@ impl Button {
     pub fn set_checkbox_callback(&mut self, callback: impl Fn(bool) + 'static) {
         let check_state = self.check_state.clone();
         self.callback = Some(ButtonCallback(Rc::new(callback!([check_state]() {
-            callback(*check_state.borrow() == ButtonCheckState::CheckBox(true));
+            // the state is not borrowed by the callback, it can set the state again
+            let checked = *check_state.borrow() == ButtonCheckState::CheckBox(true);
+            callback(checked);
         }))));
     }
 
@@ -462,6 +548,63 @@ impl Button {
         self
     }
 
+    /// Callback gets the new state when the toggle button is clicked, but not when the
+    /// state is set by `set_toggled`.
+    pub fn set_toggle_callback(&mut self, callback: impl Fn(bool) + 'static) {
+        let check_state = self.check_state.clone();
+        self.callback = Some(ButtonCallback(Rc::new(callback!([check_state]() {
+            let checked = *check_state.borrow() == ButtonCheckState::ToggleButton(true);
+            callback(checked);
+        }))));
+    }
+
+    pub fn toggle_callback(mut self, callback: impl Fn(bool) + 'static) -> Self {
+        self.set_toggle_callback(callback);
+        self
+    }
+
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.set_enabled(enabled);
+        self
+    }
+
+    /// Disabled button is grayed and ignores clicks and hotkeys.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        if self.enabled != enabled {
+            self.enabled = enabled;
+            self.base.need_redraw = true;
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// The text can have the accelerator marked with "&".
+    pub fn set_text(&mut self, text: &str) {
+        (self.text, self.accelerator) = parse_accelerator(text);
+        self.base.need_redraw = true;
+    }
+
+    pub fn get_text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn get_accelerator(&self) -> Option<char> {
+        self.accelerator.map(|(_, c)| c)
+    }
+
+    /// Fires the callback periodically while the button is held, instead of once on release.
+    pub fn auto_repeat(mut self) -> Self {
+        self.set_auto_repeat(true);
+        self
+    }
+
+    pub fn set_auto_repeat(&mut self, auto_repeat: bool) {
+        self.auto_repeat = auto_repeat;
+        self.repeat_time = None;
+    }
+
     pub fn check_box(self, state: bool) -> Self {
         *self.check_state.borrow_mut() = ButtonCheckState::CheckBox(state);
         self
@@ -477,6 +620,25 @@ impl Button {
         self
     }
 
+    /// Button which stays pressed after a click until the next one, drawn without a check mark.
+    pub fn toggle_button(self, state: bool) -> Self {
+        *self.check_state.borrow_mut() = ButtonCheckState::ToggleButton(state);
+        self
+    }
+
+    pub fn is_toggled(&self) -> bool {
+        *self.check_state.borrow() == ButtonCheckState::ToggleButton(true)
+    }
+
+    pub fn set_toggled(&mut self, toggled: bool) {
+        if let ButtonCheckState::ToggleButton(c) = self.check_state.borrow_mut().deref_mut() {
+            if *c != toggled {
+                *c = toggled;
+                self.base.need_redraw = true;
+            }
+        }
+    }
+
     pub fn hotkey(mut self, hotkey: Hotkey, global: bool) -> Self {
         self.hotkey = Some(hotkey);
         self.hotkey_is_global = global;
@@ -489,6 +651,7 @@ impl Button {
             ButtonCheckState::CheckBox(_) => true,
             ButtonCheckState::RadioButton(_) => true,
             ButtonCheckState::TabButton(_) => false,
+            ButtonCheckState::ToggleButton(_) => false,
         }
     }
 
@@ -498,6 +661,7 @@ impl Button {
             ButtonCheckState::CheckBox(c) => c,
             ButtonCheckState::RadioButton(c) => c,
             ButtonCheckState::TabButton(c) => c,
+            ButtonCheckState::ToggleButton(c) => c,
         }
     }
 
@@ -507,6 +671,7 @@ impl Button {
             ButtonCheckState::CheckBox(c) => *c = checked,
             ButtonCheckState::RadioButton(c) => *c = checked,
             ButtonCheckState::TabButton(c) => *c = checked,
+            ButtonCheckState::ToggleButton(c) => *c = checked,
         }
     }
 
@@ -516,6 +681,7 @@ impl Button {
             ButtonCheckState::CheckBox(_) => "V",
             ButtonCheckState::RadioButton(_) => "●",
             ButtonCheckState::TabButton(_) => "",
+            ButtonCheckState::ToggleButton(_) => "",
         }
     }
 
@@ -541,6 +707,7 @@ impl Button {
                 }
             }
             ButtonCheckState::TabButton(_) => "",
+            ButtonCheckState::ToggleButton(_) => "",
         }
     }
 
@@ -572,7 +739,7 @@ impl Button {
     }
 
     pub fn default_size(text: &str, hotkey: Option<Hotkey>, font: &Font) -> SizeConstraints {
-        GuiSystem::default_size(text, hotkey, font)
+        GuiSystem::default_size(&parse_accelerator(text).0, hotkey, font)
     }
 
     pub fn default_checkbox_size(
@@ -609,6 +776,10 @@ impl GuiControl for Button {
 
     fn on_message(&mut self, m: GuiMessage) -> bool {
         match m {
+            GuiMessage::GetTooltip(_, tooltip) => {
+                *tooltip = self.tooltip.clone();
+                return self.tooltip.is_some();
+            }
             GuiMessage::Draw(buf, theme, force) => {
                 if self.base.can_draw(force) {
                     let size = buf.get_size();
@@ -690,12 +861,50 @@ impl GuiControl for Button {
                         } else {
                             self.font.layout_horizontal(TextLayoutHorizontal::MIDDLE)
                         };
-                        font.color(theme.font).draw(
+                        let font_color = if self.enabled {
+                            theme.font
+                        } else {
+                            theme.inactive
+                        };
+                        font.color(font_color).draw(
                             &caption_text,
                             caption_position,
                             &mut caption_dst,
                         );
 
+                        // Underline the accelerator, unless it was cut off with the text
+                        if let Some((index, _)) = self.accelerator {
+                            let shown_text = if text_too_long {
+                                caption_text.trim_end_matches("...")
+                            } else {
+                                &caption_text
+                            };
+                            let prefix: String = shown_text.chars().take(index).collect();
+                            let underlined: String =
+                                shown_text.chars().skip(index).take(1).collect();
+                            if !underlined.is_empty() {
+                                let caption_size = self.font.get_size(&caption_text);
+                                let left = if with_checks {
+                                    caption_position.0
+                                } else {
+                                    caption_position.0 - caption_size.0 as i32 / 2
+                                } + self.font.get_size(&prefix).0 as i32;
+                                let right = left + self.font.get_size(&underlined).0 as i32;
+                                let y = caption_position.1 - caption_size.1 as i32 / 2
+                                    + caption_size.1 as i32 * 7 / 8;
+                                let dst_size = image_size_to_position(caption_dst.get_size());
+                                let (left, right) = (left.max(0), right.min(dst_size.0));
+                                if left < right && y >= 0 && y < dst_size.1 {
+                                    caption_dst
+                                        .window_mut(
+                                            position_to_image_size((left, y)),
+                                            position_to_image_size((right, y + 1)),
+                                        )
+                                        .fill(|p| *p = font_color);
+                                }
+                            }
+                        }
+
                         // Draw hotkey
                         let mut hotkey_text_dst =
                             buf.window_mut((buf.get_size().0 - hotkey_width, 0), buf.get_size());
@@ -718,14 +927,61 @@ impl GuiControl for Button {
                             .color(theme.font)
                             .layout_horizontal(TextLayoutHorizontal::MIDDLE)
                             .draw(check_symbol, check_position, &mut check_dst);
+
+                        // Draw keyboard focus frame
+                        if self.base.focus && self.base.focus_visible {
+                            let (x, y) = buf.get_size();
+                            let frame_color = theme.font;
+                            buf.window_mut((0, 0), (x, 1)).fill(|p| *p = frame_color);
+                            buf.window_mut((0, y - 1), (x, y))
+                                .fill(|p| *p = frame_color);
+                            buf.window_mut((0, 0), (1, y)).fill(|p| *p = frame_color);
+                            buf.window_mut((x - 1, 0), (x, y))
+                                .fill(|p| *p = frame_color);
+                        }
                     }
                 }
                 return false;
             }
             GuiMessage::MouseDown(_) => {
+                if !self.enabled {
+                    return false;
+                }
                 self.holded_when_pushed = true;
+                if self.auto_repeat {
+                    // the first call is on the next timer, so it gets the job system
+                    self.repeat_time = Some(Instant::now());
+                    self.repeated = false;
+                }
                 return true;
             }
+            GuiMessage::Timer(job_system) => {
+                let repeat_time = match self.repeat_time {
+                    Some(repeat_time) => repeat_time,
+                    None => return false,
+                };
+                if !self.base.pressed {
+                    self.repeat_time = None;
+                    return false;
+                }
+                if !self.holded_when_pushed || Instant::now() < repeat_time {
+                    return false;
+                }
+                self.repeat_time = Some(
+                    Instant::now()
+                        + if self.repeated {
+                            REPEAT_INTERVAL
+                        } else {
+                            REPEAT_DELAY
+                        },
+                );
+                self.repeated = true;
+                if let Some(ButtonCallback(callback)) = &self.callback {
+                    job_system.add_callback(callback.clone());
+                    return true;
+                }
+                return false;
+            }
             GuiMessage::MouseMove(position) => {
                 if self.base.pressed {
                     let prev_holded_when_pushed = self.holded_when_pushed;
@@ -736,11 +992,17 @@ impl GuiControl for Button {
                 }
             }
             GuiMessage::MouseUp(position, job_system) => {
-                if self.base.pressed && self.base.rect.contains(position) {
-                    if let ButtonCheckState::CheckBox(c) =
-                        &mut self.check_state.borrow_mut().deref_mut()
-                    {
-                        *c = !*c;
+                let repeated = self.repeat_time.take().is_some() && self.repeated;
+                if self.base.pressed
+                    && self.base.rect.contains(position)
+                    && self.enabled
+                    && !repeated
+                {
+                    match &mut self.check_state.borrow_mut().deref_mut() {
+                        ButtonCheckState::CheckBox(c) | ButtonCheckState::ToggleButton(c) => {
+                            *c = !*c
+                        }
+                        _ => {}
                     }
                     if let Some(ButtonCallback(callback)) = &self.callback {
                         job_system.add_callback(callback.clone());
@@ -748,11 +1010,53 @@ impl GuiControl for Button {
                 }
                 return true;
             }
+            GuiMessage::GetAccessibleNodes(nodes) => {
+                if self.base.visible {
+                    let check_state = *self.check_state.borrow();
+                    let role = match check_state {
+                        ButtonCheckState::None | ButtonCheckState::ToggleButton(_) => {
+                            AccessibleRole::Button
+                        }
+                        ButtonCheckState::CheckBox(_) => AccessibleRole::CheckBox,
+                        ButtonCheckState::RadioButton(_) => AccessibleRole::RadioButton,
+                        ButtonCheckState::TabButton(_) => AccessibleRole::Tab,
+                    };
+                    let mut node = AccessibleNode::new(role, self.text.clone(), &self.base)
+                        .shortcut(self.hotkey)
+                        .accelerator(self.get_accelerator())
+                        .enabled(self.enabled);
+                    if check_state != ButtonCheckState::None {
+                        node = node.checked(self.checked());
+                    }
+                    nodes.push(node);
+                }
+                return true;
+            }
             GuiMessage::GetHotkeys(hotkey_map, active) => {
+                if !self.enabled {
+                    return false;
+                }
                 if let Some(hotkey) = self.hotkey {
                     if let Some(ButtonCallback(callback)) = &self.callback {
                         if self.hotkey_is_global || active {
-                            hotkey_map.insert(hotkey, HotkeyCallback(callback.clone()));
+                            let callback = match *self.check_state.borrow() {
+                                // the hotkey flips the state as a click does
+                                ButtonCheckState::CheckBox(_)
+                                | ButtonCheckState::ToggleButton(_) => {
+                                    let check_state = self.check_state.clone();
+                                    let callback = callback.clone();
+                                    Rc::new(move || {
+                                        match check_state.borrow_mut().deref_mut() {
+                                            ButtonCheckState::CheckBox(c)
+                                            | ButtonCheckState::ToggleButton(c) => *c = !*c,
+                                            _ => {}
+                                        }
+                                        callback();
+                                    })
+                                }
+                                _ => callback.clone(),
+                            };
+                            hotkey_map.insert(hotkey, HotkeyCallback(callback));
                         }
                         return true;
                     }
@@ -764,12 +1068,74 @@ impl GuiControl for Button {
     }
 }
 
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct TextStyle {
+    /// Theme font color if none.
+    pub color: Option<u32>,
+    pub bold: bool,
+    pub underline: bool,
+}
+
+/// Part of the text of a TextBox with its own style. Link runs call the callback when
+/// they are clicked.
+#[derive(Debug, Clone)]
+pub struct TextRun {
+    pub text: String,
+    pub style: TextStyle,
+    link: Option<ButtonCallback>,
+}
+
+impl TextRun {
+    pub fn new(text: &str) -> Self {
+        Self {
+            text: text.to_string(),
+            style: TextStyle::default(),
+            link: None,
+        }
+    }
+
+    /// Links are underlined and drawn with the selection color of the theme.
+    pub fn link(text: &str, callback: impl Fn() + 'static) -> Self {
+        Self {
+            text: text.to_string(),
+            style: TextStyle {
+                underline: true,
+                ..TextStyle::default()
+            },
+            link: Some(ButtonCallback(Rc::new(callback))),
+        }
+    }
+
+    pub fn color(mut self, color: u32) -> Self {
+        self.style.color = Some(color);
+        self
+    }
+
+    pub fn bold(mut self) -> Self {
+        self.style.bold = true;
+        self
+    }
+
+    pub fn underline(mut self) -> Self {
+        self.style.underline = true;
+        self
+    }
+
+    pub fn is_link(&self) -> bool {
+        self.link.is_some()
+    }
+}
+
 #[derive(Debug)]
 pub struct TextBox {
     base: GuiControlBase,
     padding: bool,
     font: Font,
     text: String,
+    runs: Vec<TextRun>,
+    /// Horizontal ranges of the drawn link runs, relative to the control, and run indices.
+    link_areas: Vec<(i32, i32, usize)>,
+    pressed_link: Option<usize>,
 }
 
 impl TextBox {
@@ -781,6 +1147,74 @@ impl TextBox {
                 .layout_vertical(TextLayoutVertical::MIDDLE)
                 .layout_horizontal(TextLayoutHorizontal::LEFT),
             text,
+            runs: Vec::new(),
+            link_areas: Vec::new(),
+            pressed_link: None,
+        }
+    }
+
+    pub fn runs(mut self, runs: Vec<TextRun>) -> Self {
+        self.set_runs(runs);
+        self
+    }
+
+    pub fn set_text(&mut self, text: &str) {
+        self.text = text.to_string();
+        self.runs.clear();
+        self.link_areas.clear();
+        self.base.need_redraw = true;
+    }
+
+    /// Replaces the text by styled runs, the plain text is their concatenation.
+    pub fn set_runs(&mut self, runs: Vec<TextRun>) {
+        self.text = runs.iter().map(|run| run.text.as_str()).collect();
+        self.runs = runs;
+        self.link_areas.clear();
+        self.base.need_redraw = true;
+    }
+
+    fn find_link(&self, position: Position) -> Option<usize> {
+        let rect = self.base.rect;
+        if !rect.contains(position) {
+            return None;
+        }
+        let x = rect.relative(position).0;
+        self.link_areas
+            .iter()
+            .find(|(left, right, _)| *left <= x && x < *right)
+            .map(|(_, _, index)| *index)
+    }
+
+    fn draw_runs(&mut self, buf: &mut ImageViewMut<u32>, theme: &GuiColorTheme, x: i32) {
+        let center_y = buf.get_size().1 as i32 / 2;
+        let mut x = x;
+        self.link_areas.clear();
+        for (index, run) in self.runs.iter().enumerate() {
+            let color = match (run.style.color, run.is_link()) {
+                (Some(color), _) => color,
+                (None, true) => theme.selected,
+                (None, false) => theme.font,
+            };
+            let font = self.font.color(color);
+            let (width, height) = self.font.get_size(&run.text);
+            // bold is synthesized by drawing the text twice with a shift
+            let width = width as i32 + run.style.bold as i32;
+            font.draw(&run.text, (x, center_y), buf);
+            if run.style.bold {
+                font.draw(&run.text, (x + 1, center_y), buf);
+            }
+            let underline_y = center_y + height as i32 / 2 - 1;
+            if run.style.underline && underline_y >= 0 && underline_y < buf.get_size().1 as i32 {
+                let left = x.clamp(0, buf.get_size().0 as i32) as usize;
+                let right = (x + width).clamp(0, buf.get_size().0 as i32) as usize;
+                for p in &mut buf[underline_y as usize][left..right] {
+                    *p = color;
+                }
+            }
+            if run.is_link() {
+                self.link_areas.push((x, x + width, index));
+            }
+            x += width;
         }
     }
 }
@@ -802,14 +1236,319 @@ impl GuiControl for TextBox {
                         } else {
                             0
                         };
-                        let caption_position = (position, buf.get_size().1 as i32 / 2);
-                        self.font
-                            .color(theme.font)
-                            .draw(&self.text, caption_position, buf);
+                        if self.runs.is_empty() {
+                            let caption_position = (position, buf.get_size().1 as i32 / 2);
+                            self.font
+                                .color(theme.font)
+                                .draw(&self.text, caption_position, buf);
+                        } else {
+                            self.draw_runs(buf, theme, position);
+                        }
                     }
                 }
                 return false;
             }
+            GuiMessage::GetCursor(position, cursor) => {
+                if self.find_link(position).is_some() {
+                    *cursor = MouseCursor::Hand;
+                }
+                return true;
+            }
+            GuiMessage::MouseDown(position) => {
+                self.pressed_link = self.find_link(position);
+                return self.pressed_link.is_some();
+            }
+            GuiMessage::MouseUp(position, job_system) => {
+                let pressed_link = self.pressed_link.take();
+                if pressed_link.is_some() && pressed_link == self.find_link(position) {
+                    if let Some(ButtonCallback(callback)) =
+                        pressed_link.and_then(|index| self.runs[index].link.as_ref())
+                    {
+                        job_system.add_callback(callback.clone());
+                    }
+                }
+                return pressed_link.is_some();
+            }
+            GuiMessage::GetAccessibleNodes(nodes) => {
+                if self.base.visible {
+                    nodes.push(AccessibleNode::new(
+                        AccessibleRole::Text,
+                        self.text.clone(),
+                        &self.base,
+                    ));
+                }
+                return true;
+            }
+            _ => return false,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct OpenErrorCallback(Rc<dyn Fn(&str) + 'static>);
+
+impl std::fmt::Debug for OpenErrorCallback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad("OpenErrorCallback")
+    }
+}
+
+/// Underlined text which opens the URL or the file location with the system shell.
+#[derive(Debug)]
+pub struct Link {
+    base: GuiControlBase,
+    font: Font,
+    text: String,
+    target: String,
+    shell: Shell,
+    error_callback: Option<OpenErrorCallback>,
+}
+
+impl Link {
+    pub fn new(
+        size_constraints: SizeConstraints,
+        text: String,
+        target: String,
+        font: Font,
+        shell: Shell,
+    ) -> Self {
+        Self {
+            base: GuiControlBase::new(size_constraints),
+            font: font
+                .layout_vertical(TextLayoutVertical::MIDDLE)
+                .layout_horizontal(TextLayoutHorizontal::LEFT),
+            text,
+            target,
+            shell,
+            error_callback: None,
+        }
+    }
+
+    pub fn set_error_callback(&mut self, error_callback: impl Fn(&str) + 'static) {
+        self.error_callback = Some(OpenErrorCallback(Rc::new(error_callback)));
+    }
+
+    /// Gets the error of the shell when the target can't be opened.
+    pub fn error_callback(mut self, error_callback: impl Fn(&str) + 'static) -> Self {
+        self.set_error_callback(error_callback);
+        self
+    }
+
+    pub fn set_target(&mut self, target: &str) {
+        self.target = target.to_string();
+    }
+
+    pub fn get_target(&self) -> &str {
+        &self.target
+    }
+}
+
+impl GuiControl for Link {
+    fn get_base_mut(&mut self) -> &mut GuiControlBase {
+        &mut self.base
+    }
+
+    fn on_message(&mut self, m: GuiMessage) -> bool {
+        match m {
+            GuiMessage::Draw(buf, theme, force) => {
+                if self.base.can_draw(force) {
+                    GuiSystem::erase_background(buf, EmptySpaceState::Empty, theme);
+                    let size = buf.get_size();
+                    if size.0 > 0 && size.1 > 0 {
+                        let color = if self.base.pressed {
+                            theme.pressed
+                        } else if self.base.highlight {
+                            theme.highlight
+                        } else {
+                            theme.selected
+                        };
+                        let (width, height) = self.font.get_size(&self.text);
+                        let x = height as i32 / 2;
+                        let center_y = size.1 as i32 / 2;
+                        self.font.color(color).draw(&self.text, (x, center_y), buf);
+                        let underline_y =
+                            (center_y + height as i32 / 2 - 1).clamp(0, size.1 as i32 - 1);
+                        let right = (x as usize + width).min(size.0);
+                        for p in &mut buf[underline_y as usize][(x as usize).min(right)..right] {
+                            *p = color;
+                        }
+                        if self.base.focus && self.base.focus_visible {
+                            let (x, y) = size;
+                            let frame_color = theme.font;
+                            buf.window_mut((0, 0), (x, 1)).fill(|p| *p = frame_color);
+                            buf.window_mut((0, y - 1), (x, y))
+                                .fill(|p| *p = frame_color);
+                            buf.window_mut((0, 0), (1, y)).fill(|p| *p = frame_color);
+                            buf.window_mut((x - 1, 0), (x, y))
+                                .fill(|p| *p = frame_color);
+                        }
+                    }
+                }
+                return false;
+            }
+            GuiMessage::MouseDown(_) => {
+                return true;
+            }
+            GuiMessage::MouseUp(position, job_system) => {
+                if self.base.pressed && self.base.rect.contains(position) {
+                    let shell = self.shell.clone();
+                    let target = self.target.clone();
+                    let error_callback = self.error_callback.clone();
+                    job_system.add_callback(Rc::new(move || {
+                        if let (Err(e), Some(error_callback)) =
+                            (shell.open(&target), &error_callback)
+                        {
+                            (error_callback.0)(&e);
+                        }
+                    }));
+                }
+                return true;
+            }
+            GuiMessage::GetCursor(_, cursor) => {
+                *cursor = MouseCursor::Hand;
+                return true;
+            }
+            GuiMessage::GetAccessibleNodes(nodes) => {
+                if self.base.visible {
+                    nodes.push(
+                        AccessibleNode::new(AccessibleRole::Link, self.text.clone(), &self.base)
+                            .value(self.target.clone()),
+                    );
+                }
+                return true;
+            }
+            _ => return false,
+        }
+    }
+}
+
+/// Horizontal bar filled by the part of the work done.
+#[derive(Debug)]
+pub struct ProgressBar {
+    base: GuiControlBase,
+    progress: f32,
+}
+
+impl ProgressBar {
+    pub fn new(size_constraints: SizeConstraints) -> Self {
+        Self {
+            base: GuiControlBase::new(size_constraints),
+            progress: 0.0,
+        }
+    }
+
+    pub fn set_progress(&mut self, progress: f32) {
+        let progress = progress.clamp(0.0, 1.0);
+        if self.progress != progress {
+            self.progress = progress;
+            self.base.need_redraw = true;
+        }
+    }
+
+    pub fn get_progress(&self) -> f32 {
+        self.progress
+    }
+}
+
+impl GuiControl for ProgressBar {
+    fn get_base_mut(&mut self) -> &mut GuiControlBase {
+        &mut self.base
+    }
+
+    fn on_message(&mut self, m: GuiMessage) -> bool {
+        match m {
+            GuiMessage::Draw(buf, theme, force) => {
+                if self.base.can_draw(force) {
+                    let size = buf.get_size();
+                    buf.fill(|p| *p = theme.splitter);
+                    if size.0 > 2 && size.1 > 2 {
+                        let mut inner = buf.window_mut((1, 1), (size.0 - 1, size.1 - 1));
+                        inner.fill(|p| *p = theme.background);
+                        let filled = ((size.0 - 2) as f32 * self.progress) as usize;
+                        inner
+                            .window_mut((0, 0), (filled, size.1 - 2))
+                            .fill(|p| *p = theme.selected);
+                    }
+                }
+                return false;
+            }
+            GuiMessage::GetAccessibleNodes(nodes) => {
+                if self.base.visible {
+                    nodes.push(
+                        AccessibleNode::new(AccessibleRole::ProgressBar, String::new(), &self.base)
+                            .value(format!("{}%", (self.progress * 100.0).round())),
+                    );
+                }
+                return true;
+            }
+            _ => return false,
+        }
+    }
+}
+
+/// Shows the image in the middle of the control, the name is for accessibility.
+#[derive(Debug)]
+pub struct Picture {
+    base: GuiControlBase,
+    image: Option<Rc<Image<u32>>>,
+    name: String,
+}
+
+impl Picture {
+    pub fn new(
+        size_constraints: SizeConstraints,
+        image: Option<Rc<Image<u32>>>,
+        name: &str,
+    ) -> Self {
+        Self {
+            base: GuiControlBase::new(size_constraints),
+            image,
+            name: name.to_string(),
+        }
+    }
+
+    pub fn set_image(&mut self, image: Option<Rc<Image<u32>>>) {
+        self.image = image;
+        self.base.need_redraw = true;
+    }
+}
+
+impl GuiControl for Picture {
+    fn get_base_mut(&mut self) -> &mut GuiControlBase {
+        &mut self.base
+    }
+
+    fn on_message(&mut self, m: GuiMessage) -> bool {
+        match m {
+            GuiMessage::Draw(buf, theme, force) => {
+                if self.base.can_draw(force) {
+                    GuiSystem::erase_background(buf, EmptySpaceState::Empty, theme);
+                    if let Some(image) = &self.image {
+                        let size = buf.get_size();
+                        let image = image.as_view();
+                        let image_size = image.get_size();
+                        let width = min(size.0, image_size.0);
+                        let height = min(size.1, image_size.1);
+                        let left = (size.0 - width) / 2;
+                        let top = (size.1 - height) / 2;
+                        let mut window = buf.window_mut((left, top), (left + width, top + height));
+                        for y in 0..height {
+                            window[y].copy_from_slice(&image[y][..width]);
+                        }
+                    }
+                }
+                return false;
+            }
+            GuiMessage::GetAccessibleNodes(nodes) => {
+                if self.base.visible {
+                    nodes.push(AccessibleNode::new(
+                        AccessibleRole::Image,
+                        self.name.clone(),
+                        &self.base,
+                    ));
+                }
+                return true;
+            }
             _ => return false,
         }
     }
@@ -833,6 +1572,34 @@ impl std::fmt::Debug for EnterCallback {
     }
 }
 
+/// Checks the text of an input, the error is a message for the user.
+#[derive(Clone)]
+pub struct Validator(Rc<dyn Fn(&str) -> Result<(), String> + 'static>);
+
+impl std::fmt::Debug for Validator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad("Validator")
+    }
+}
+
+impl Validator {
+    pub fn new(validate: impl Fn(&str) -> Result<(), String> + 'static) -> Self {
+        Self(Rc::new(validate))
+    }
+
+    /// The text has to be parsed as `T` and then pass the check.
+    pub fn parsed<T: std::str::FromStr>(
+        parse_error: &str,
+        check: impl Fn(T) -> Result<(), String> + 'static,
+    ) -> Self {
+        let parse_error = parse_error.to_string();
+        Self::new(move |text| match text.trim().parse::<T>() {
+            Ok(value) => check(value),
+            Err(_) => Err(parse_error.clone()),
+        })
+    }
+}
+
 #[derive(Debug)]
 pub struct Edit {
     base: GuiControlBase,
@@ -843,8 +1610,18 @@ pub struct Edit {
     cursor_position: i32,
     skip_callback: Option<SkipCallback>,
     enter_callback: Option<EnterCallback>,
+    validator: Option<Validator>,
+    completion_provider: Option<CompletionProvider>,
+    /// Candidates of the open popup, empty when it is closed.
+    completion: Vec<String>,
+    selected_completion: usize,
+    /// The characters are drawn as dots and the text is not exposed, for passwords.
+    masked: bool,
 }
 
+/// Drawn instead of every character of a masked input.
+const MASK_CHAR: &str = "\u{2022}";
+
 impl Edit {
     pub fn new(size_constraints: SizeConstraints, font: Font, clipboard: Clipboard) -> Self {
         Self {
@@ -858,11 +1635,17 @@ impl Edit {
             cursor_position: 0,
             skip_callback: None,
             enter_callback: None,
+            validator: None,
+            completion_provider: None,
+            completion: Vec::new(),
+            selected_completion: 0,
+            masked: false,
         }
     }
 
     pub fn set_text(&mut self, text: &str) {
         self.text = text.to_string();
+        self.completion.clear();
         self.adjust_cursor_position();
     }
 
@@ -871,22 +1654,100 @@ impl Edit {
         self
     }
 
+    pub fn get_text(&self) -> &str {
+        &self.text
+    }
+
     pub fn set_skip_callback(&mut self, callback: impl Fn() + 'static) {
         self.skip_callback = Some(SkipCallback(Rc::new(callback)));
     }
 
-    pub fn skip_callback(mut self, skip_callback: impl Fn() + 'static) -> Self {
-        self.set_skip_callback(skip_callback);
-        self
+    pub fn skip_callback(mut self, skip_callback: impl Fn() + 'static) -> Self {
+        self.set_skip_callback(skip_callback);
+        self
+    }
+
+    pub fn set_enter_callback(&mut self, enter_callback: impl Fn(&str) + 'static) {
+        self.enter_callback = Some(EnterCallback(Rc::new(enter_callback)));
+    }
+
+    pub fn enter_callback(mut self, enter_callback: impl Fn(&str) + 'static) -> Self {
+        self.set_enter_callback(enter_callback);
+        self
+    }
+
+    pub fn set_validator(&mut self, validator: Validator) {
+        self.validator = Some(validator);
+    }
+
+    /// Invalid text gets a red border and the error in the tooltip, Enter doesn't
+    /// commit it.
+    pub fn validator(mut self, validator: Validator) -> Self {
+        self.set_validator(validator);
+        self
+    }
+
+    pub fn set_completion_provider(&mut self, provider: CompletionProvider) {
+        self.completion_provider = Some(provider);
+    }
+
+    /// Typing opens the list of the candidates for the text, the arrows choose one and
+    /// Tab or Enter takes it.
+    pub fn completion_provider(mut self, provider: CompletionProvider) -> Self {
+        self.set_completion_provider(provider);
+        self
+    }
+
+    pub fn set_masked(&mut self, masked: bool) {
+        self.masked = masked;
+        self.completion.clear();
+    }
+
+    /// Password input: dots are shown, the text is not copied to the accessibility tree
+    /// and the input log skips the keys typed into it.
+    pub fn masked(mut self) -> Self {
+        self.set_masked(true);
+        self
+    }
+
+    pub fn is_masked(&self) -> bool {
+        self.masked
+    }
+
+    /// The part of the text as it is drawn, the byte positions are of the real text.
+    fn get_shown(&self, from: i32, to: i32) -> Cow<'_, str> {
+        let text = &self.text[from as usize..to as usize];
+        if self.masked {
+            return Cow::Owned(MASK_CHAR.repeat(text.chars().count()));
+        }
+        return Cow::Borrowed(text);
+    }
+
+    fn update_completion(&mut self) {
+        self.completion = match &self.completion_provider {
+            Some(provider) if !self.text.is_empty() && !self.masked => {
+                provider.get_candidates(&self.text)
+            }
+            _ => Vec::new(),
+        };
+        self.selected_completion = 0;
     }
 
-    pub fn set_enter_callback(&mut self, enter_callback: impl Fn(&str) + 'static) {
-        self.enter_callback = Some(EnterCallback(Rc::new(enter_callback)));
+    fn accept_completion(&mut self, index: usize) -> bool {
+        let candidate = match self.completion.get(index) {
+            Some(candidate) => candidate.clone(),
+            None => return false,
+        };
+        self.set_text(&candidate);
+        self.set_cursor_position(self.text.len() as i32);
+        return true;
     }
 
-    pub fn enter_callback(mut self, enter_callback: impl Fn(&str) + 'static) -> Self {
-        self.set_enter_callback(enter_callback);
-        self
+    pub fn validate(&self) -> Result<(), String> {
+        match &self.validator {
+            Some(Validator(validate)) => validate(&self.text),
+            None => Ok(()),
+        }
     }
 
     pub fn set_cursor_position(&mut self, cursor_position: i32) {
@@ -927,7 +1788,7 @@ impl Edit {
             let prev_scroll = self.prev_position(minimal_scroll);
             width_before_cursor += self
                 .font
-                .get_size(&self.text[prev_scroll as usize..minimal_scroll as usize])
+                .get_size(&self.get_shown(prev_scroll, minimal_scroll))
                 .0 as i32;
             if width_before_cursor > width {
                 break;
@@ -953,6 +1814,7 @@ impl Edit {
                 .insert_str(self.cursor_position as usize, &filtered_text);
             self.cursor_position += filtered_text.len() as i32;
             self.adjust_cursor_position();
+            self.update_completion();
             return true;
         }
 
@@ -971,7 +1833,11 @@ impl GuiControl for Edit {
                 if self.base.can_draw(force) {
                     let (x, y) = buf.get_size();
                     if x > 2 && y > 2 {
-                        let border_color = theme.font;
+                        let border_color = if self.validate().is_ok() {
+                            theme.font
+                        } else {
+                            theme.error
+                        };
                         buf.window_mut((0, 0), (x, 1)).fill(|p| *p = border_color);
                         buf.window_mut((0, y - 1), (x, y))
                             .fill(|p| *p = border_color);
@@ -995,11 +1861,18 @@ impl GuiControl for Edit {
 
                         inner.fill(|d| *d = color);
                         if self.base.focus {
-                            let text_before_cursor = &self.text
-                                [self.scroll_position as usize..self.cursor_position as usize];
+                            let text_before_cursor =
+                                self.get_shown(self.scroll_position, self.cursor_position);
                             let cursor_position_on_screen =
                                 self.font.get_size(&text_before_cursor).0;
-                            if cursor_position_on_screen + 2 <= x - 2 {
+                            // inside of the border
+                            let caret_left =
+                                self.base.rect.left_top.0 + 1 + cursor_position_on_screen as i32;
+                            let caret_rect = Rect {
+                                left_top: (caret_left, self.base.rect.left_top.1 + 1),
+                                right_bottom: (caret_left + 2, self.base.rect.right_bottom.1 - 1),
+                            };
+                            if cursor_position_on_screen + 2 <= x - 2 && place_caret(caret_rect) {
                                 inner
                                     .window_mut(
                                         (cursor_position_on_screen, 0),
@@ -1014,10 +1887,7 @@ impl GuiControl for Edit {
                         while pos < self.text.len() as i32 {
                             let next_pos = self.next_position(pos);
 
-                            width += self
-                                .font
-                                .get_size(&self.text[pos as usize..next_pos as usize])
-                                .0;
+                            width += self.font.get_size(&self.get_shown(pos, next_pos)).0;
                             if width > x - 2 {
                                 break;
                             }
@@ -1025,7 +1895,7 @@ impl GuiControl for Edit {
                         }
 
                         self.font.color(theme.font).draw(
-                            &self.text[self.scroll_position as usize..pos as usize],
+                            &self.get_shown(self.scroll_position, pos),
                             (1, (y / 2) as i32),
                             &mut inner,
                         );
@@ -1042,7 +1912,7 @@ impl GuiControl for Edit {
                     let next_index = self.next_position(char_index);
                     let new_symbol_width = self
                         .font
-                        .get_size(&self.text[char_index as usize..next_index as usize])
+                        .get_size(&self.get_shown(char_index, next_index))
                         .0 as i32;
                     if width_before_mouse + new_symbol_width / 2 > cursor_x {
                         break;
@@ -1061,6 +1931,7 @@ impl GuiControl for Edit {
                     self.text.insert(self.cursor_position as usize, c);
                     self.cursor_position += c.len_utf8() as i32;
                     self.adjust_cursor_position();
+                    self.update_completion();
                     return true;
                 }
                 return false;
@@ -1080,6 +1951,28 @@ impl GuiControl for Edit {
                 return false;
             }
             GuiMessage::KeyDown(k, job_system, unfocus) => {
+                if !self.completion.is_empty() {
+                    let count = self.completion.len();
+                    match k {
+                        Key::Down => {
+                            self.selected_completion = (self.selected_completion + 1) % count;
+                            return true;
+                        }
+                        Key::Up => {
+                            self.selected_completion =
+                                (self.selected_completion + count - 1) % count;
+                            return true;
+                        }
+                        Key::Tab | Key::Enter => {
+                            return self.accept_completion(self.selected_completion);
+                        }
+                        Key::Escape => {
+                            self.completion.clear();
+                            return true;
+                        }
+                        _ => {}
+                    }
+                }
                 match k {
                     Key::Left => {
                         if self.cursor_position > 0 {
@@ -1099,6 +1992,7 @@ impl GuiControl for Edit {
                         if self.cursor_position < self.text.len() as i32 {
                             self.text.remove(self.cursor_position as usize);
                             self.adjust_cursor_position();
+                            self.update_completion();
                             return true;
                         }
                     }
@@ -1107,6 +2001,7 @@ impl GuiControl for Edit {
                             self.cursor_position = self.prev_position(self.cursor_position);
                             self.text.remove(self.cursor_position as usize);
                             self.adjust_cursor_position();
+                            self.update_completion();
                             return true;
                         }
                     }
@@ -1119,6 +2014,10 @@ impl GuiControl for Edit {
                         return true;
                     }
                     Key::Enter => {
+                        // the input stays focused until the text is fixed
+                        if self.validate().is_err() {
+                            return true;
+                        }
                         if let Some(EnterCallback(enter_callback)) = &self.enter_callback {
                             let text = self.text.clone();
                             job_system.add_callback(Rc::new(callback!([enter_callback] () {
@@ -1133,7 +2032,47 @@ impl GuiControl for Edit {
 
                 return false;
             }
+            GuiMessage::GetAccessibleNodes(nodes) => {
+                if self.base.visible {
+                    nodes.push(
+                        AccessibleNode::new(AccessibleRole::Edit, String::new(), &self.base).value(
+                            if self.masked {
+                                MASK_CHAR.repeat(self.text.chars().count())
+                            } else {
+                                self.text.clone()
+                            },
+                        ),
+                    );
+                }
+                return true;
+            }
+            GuiMessage::GetTooltip(_, tooltip) => {
+                *tooltip = self.validate().err().map(|error| Tooltip::new(&error));
+                return tooltip.is_some();
+            }
+            GuiMessage::GetCompletion(completion) => {
+                if self.completion.is_empty() {
+                    return false;
+                }
+                *completion = Some(CompletionPopup {
+                    candidates: self.completion.clone(),
+                    selected: self.selected_completion,
+                    anchor: self.base.rect,
+                    font: self.font.clone(),
+                });
+                return true;
+            }
+            GuiMessage::AcceptCompletion(index) => return self.accept_completion(index),
+            GuiMessage::IsMasked(masked) => {
+                *masked = self.masked;
+                return true;
+            }
             GuiMessage::FocusLose(job_system) => {
+                self.completion.clear();
+                if self.validate().is_err() {
+                    // invalid text is not committed
+                    return false;
+                }
                 if let Some(EnterCallback(enter_callback)) = &self.enter_callback {
                     let text = self.text.clone();
                     job_system.add_callback(Rc::new(callback!([enter_callback] () {
@@ -1151,9 +2090,31 @@ impl GuiControl for Edit {
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 enum ScrollState {
     Invalid,
+    UpArrow,
     Less,
     OnButton(i32, i32),
     Greater,
+    DownArrow,
+}
+
+#[derive(Clone)]
+pub struct ScrollCallback(Rc<dyn Fn(i32) + 'static>);
+
+impl std::fmt::Debug for ScrollCallback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad("ScrollCallback")
+    }
+}
+
+/// Pause before a held auto repeat button, scroll arrow or page area starts to repeat,
+/// and the repeat period.
+const REPEAT_DELAY: Duration = Duration::from_millis(400);
+const REPEAT_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Copy, Clone)]
+struct ScrollRepeat {
+    next_time: Instant,
+    mouse_position: Position,
 }
 
 #[derive(Debug)]
@@ -1164,6 +2125,10 @@ pub struct ScrollV {
     content_size: i32,
     scroll_position: i32,
     scroll_state: ScrollState,
+    wheel_remainder: f32,
+    arrow_buttons: bool,
+    repeat: Option<ScrollRepeat>,
+    scroll_callback: Option<ScrollCallback>,
 }
 
 impl ScrollV {
@@ -1174,15 +2139,65 @@ impl ScrollV {
             content_size: 10,
             scroll_position: 45,
             scroll_state: ScrollState::Invalid,
+            wheel_remainder: 0.0,
+            arrow_buttons: false,
+            repeat: None,
+            scroll_callback: None,
+        }
+    }
+
+    /// Square buttons at the ends, which scroll by one step.
+    pub fn arrow_buttons(mut self, arrow_buttons: bool) -> Self {
+        self.arrow_buttons = arrow_buttons;
+        self
+    }
+
+    /// Gets the new position when the user scrolls. Changes made by the mouse buttons are
+    /// reported right away, the repeated ones are reported through the job system.
+    pub fn scroll_callback(mut self, callback: impl Fn(i32) + 'static) -> Self {
+        self.set_scroll_callback(callback);
+        self
+    }
+
+    pub fn set_scroll_callback(&mut self, callback: impl Fn(i32) + 'static) {
+        self.scroll_callback = Some(ScrollCallback(Rc::new(callback)));
+    }
+
+    pub fn set_range(&mut self, scroll_range: i32, content_size: i32) {
+        self.scroll_range = scroll_range;
+        self.content_size = content_size;
+        self.base.need_redraw = true;
+    }
+
+    pub fn get_scroll_position(&self) -> i32 {
+        self.scroll_position
+    }
+
+    pub fn set_scroll_position(&mut self, scroll_position: i32) {
+        self.scroll_position = scroll_position;
+        self.base.need_redraw = true;
+    }
+
+    fn get_arrow_size(&self) -> i32 {
+        if self.arrow_buttons {
+            let (width, height) = self.base.get_size();
+            min(width, height / 3)
+        } else {
+            0
         }
     }
 
+    fn get_track_height(&self) -> i32 {
+        self.base.rect.right_bottom.1 - self.base.rect.left_top.1 - self.get_arrow_size() * 2
+    }
+
+    /// Shift is counted from the top of the control.
     fn get_button_size_and_shift(&self) -> (i32, i32) {
-        let height = self.base.rect.right_bottom.1 - self.base.rect.left_top.1;
+        let height = self.get_track_height();
         let button_size = max(5, self.content_size * height / self.scroll_range);
         let button_position = self.scroll_position * (height - button_size)
             / max(1, self.scroll_range - self.content_size);
-        (button_size, button_position)
+        (button_size, button_position + self.get_arrow_size())
     }
 
     fn get_button_rect(&self) -> Rect {
@@ -1194,13 +2209,18 @@ impl ScrollV {
     }
 
     fn very_small(&self) -> bool {
-        self.base.rect.right_bottom.1 - self.base.rect.left_top.1 < 10
+        self.get_track_height() < 10
     }
 
     fn set_in_button(&mut self, position: Position) {
         let button_rect = self.get_button_rect();
+        let arrow_size = self.get_arrow_size();
         self.scroll_state = if self.very_small() {
             ScrollState::Invalid
+        } else if position.1 < self.base.rect.left_top.1 + arrow_size {
+            ScrollState::UpArrow
+        } else if position.1 >= self.base.rect.right_bottom.1 - arrow_size {
+            ScrollState::DownArrow
         } else if position.1 < button_rect.left_top.1 {
             ScrollState::Less
         } else if position.1 >= button_rect.right_bottom.1 {
@@ -1212,6 +2232,51 @@ impl ScrollV {
             )
         };
     }
+
+    fn clamp_scroll_position(&mut self) {
+        self.scroll_position = max(
+            0,
+            min(self.scroll_range - self.content_size, self.scroll_position),
+        );
+    }
+
+    /// Scrolls by the pressed part, returns true if the position was changed.
+    fn step(&mut self) -> bool {
+        let old_scroll_position = self.scroll_position;
+        match self.scroll_state {
+            ScrollState::UpArrow => self.scroll_position -= 1,
+            ScrollState::Less => self.scroll_position -= self.content_size,
+            ScrollState::Greater => self.scroll_position += self.content_size,
+            ScrollState::DownArrow => self.scroll_position += 1,
+            _ => {}
+        }
+        self.clamp_scroll_position();
+        old_scroll_position != self.scroll_position
+    }
+
+    fn notify(&self) {
+        if let Some(ScrollCallback(callback)) = &self.scroll_callback {
+            callback(self.scroll_position);
+        }
+    }
+
+    fn draw_arrow(buf: &mut ImageViewMut<u32>, up: bool, color: u32) {
+        let (width, height) = buf.get_size();
+        let half = min(width, height) / 4;
+        let (center_x, center_y) = (width / 2, height / 2);
+        for row in 0..half {
+            let y = if up {
+                center_y - half / 2 + row
+            } else {
+                center_y + half / 2 - row
+            };
+            if y < height {
+                for p in &mut buf[y][center_x - row..=min(center_x + row, width - 1)] {
+                    *p = color;
+                }
+            }
+        }
+    }
 }
 
 impl GuiControl for ScrollV {
@@ -1225,10 +2290,7 @@ impl GuiControl for ScrollV {
                 if self.base.can_draw(force) {
                     self.scroll_range = max(1, self.scroll_range);
                     self.content_size = max(1, min(self.content_size, self.scroll_range));
-                    self.scroll_position = max(
-                        0,
-                        min(self.scroll_range - self.content_size, self.scroll_position),
-                    );
+                    self.clamp_scroll_position();
 
                     if self.content_size >= self.scroll_range {
                         return true;
@@ -1240,10 +2302,15 @@ impl GuiControl for ScrollV {
                     }
 
                     let (button_size, button_shift) = self.get_button_size_and_shift();
+                    let arrow_size = self.get_arrow_size() as usize;
+                    let track_bottom = buf.get_size().1 - arrow_size;
 
                     macro_rules! top_scroll {
                         () => {
-                            buf.window_mut((0, 0), (buf.get_size().0, button_shift as usize))
+                            buf.window_mut(
+                                (0, arrow_size),
+                                (buf.get_size().0, button_shift as usize),
+                            )
                         };
                     }
 
@@ -1260,7 +2327,7 @@ impl GuiControl for ScrollV {
                         () => {
                             buf.window_mut(
                                 (0, (button_size + button_shift) as usize),
-                                buf.get_size(),
+                                (buf.get_size().0, track_bottom),
                             )
                         };
                     }
@@ -1276,43 +2343,72 @@ impl GuiControl for ScrollV {
                     } else {
                         button_scroll!().fill(|d| *d = avg_color(*d, theme.inactive));
                     }
+
+                    if arrow_size > 0 {
+                        let arrow_color = |pressed: bool| {
+                            if self.base.pressed && pressed {
+                                theme.pressed
+                            } else if self.base.highlight {
+                                theme.highlight
+                            } else {
+                                theme.inactive
+                            }
+                        };
+                        let width = buf.get_size().0;
+                        let up_color = arrow_color(self.scroll_state == ScrollState::UpArrow);
+                        let down_color = arrow_color(self.scroll_state == ScrollState::DownArrow);
+                        Self::draw_arrow(
+                            &mut buf.window_mut((0, 0), (width, arrow_size)),
+                            true,
+                            up_color,
+                        );
+                        Self::draw_arrow(
+                            &mut buf.window_mut((0, track_bottom), buf.get_size()),
+                            false,
+                            down_color,
+                        );
+                    }
                 }
                 return true;
             }
             GuiMessage::MouseWheel(_, delta) => {
+                // Small touchpad deltas are accumulated until they make a whole step
+                self.wheel_remainder += delta;
+                let steps = self.wheel_remainder.trunc();
+                self.wheel_remainder -= steps;
                 let old_scroll_position = self.scroll_position;
-                self.scroll_position = max(
-                    0,
-                    min(
-                        self.scroll_range - self.content_size,
-                        self.scroll_position + delta,
-                    ),
-                );
-                return old_scroll_position != self.scroll_position;
+                self.scroll_position += steps as i32;
+                self.clamp_scroll_position();
+                if old_scroll_position != self.scroll_position {
+                    self.notify();
+                    return true;
+                }
+                return false;
             }
             GuiMessage::MouseDown(position) => {
                 self.set_in_button(position);
-                match self.scroll_state {
-                    ScrollState::Less => self.scroll_position -= self.content_size,
-                    ScrollState::Greater => self.scroll_position += self.content_size,
-                    _ => {}
+                self.repeat = match self.scroll_state {
+                    ScrollState::Invalid | ScrollState::OnButton(_, _) => None,
+                    _ => Some(ScrollRepeat {
+                        next_time: Instant::now() + REPEAT_DELAY,
+                        mouse_position: position,
+                    }),
+                };
+                if self.step() {
+                    self.notify();
                 }
-
-                self.scroll_position = max(
-                    0,
-                    min(self.scroll_range - self.content_size, self.scroll_position),
-                );
                 return true;
             }
             GuiMessage::MouseMove(position) => {
+                if let Some(repeat) = &mut self.repeat {
+                    repeat.mouse_position = position;
+                }
                 if self.base.pressed {
                     match self.scroll_state {
                         ScrollState::OnButton(yl, yh) => {
                             let prev_scroll_position = self.scroll_position;
                             let (button_size, button_shift) = self.get_button_size_and_shift(); // before shift
-                            let free_scroll_size = self.base.rect.right_bottom.1
-                                - self.base.rect.left_top.1
-                                - button_size;
+                            let free_scroll_size = self.get_track_height() - button_size;
                             if yl + yh > 0 && free_scroll_size > 0 {
                                 let old_position = self.base.rect.left_top.1
                                     + button_shift
@@ -1324,15 +2420,13 @@ impl GuiControl for ScrollV {
                                         / 2)
                                     / max(1, free_scroll_size);
                                 self.scroll_position += delta;
-                                self.scroll_position = max(
-                                    0,
-                                    min(
-                                        self.scroll_range - self.content_size,
-                                        self.scroll_position,
-                                    ),
-                                );
+                                self.clamp_scroll_position();
                             }
-                            return prev_scroll_position != self.scroll_position;
+                            if prev_scroll_position != self.scroll_position {
+                                self.notify();
+                                return true;
+                            }
+                            return false;
                         }
                         _ => {
                             return false;
@@ -1343,6 +2437,32 @@ impl GuiControl for ScrollV {
                 }
             }
             GuiMessage::MouseUp(_, _) => {
+                self.repeat = None;
+                return true;
+            }
+            GuiMessage::Timer(job_system) => {
+                let mut repeat = match self.repeat {
+                    Some(repeat) if repeat.next_time <= Instant::now() => repeat,
+                    _ => return false,
+                };
+                // paging stops when the button comes under the mouse
+                let pressed_state = self.scroll_state;
+                self.set_in_button(repeat.mouse_position);
+                if self.scroll_state != pressed_state {
+                    self.scroll_state = pressed_state;
+                    return false;
+                }
+
+                repeat.next_time = Instant::now() + REPEAT_INTERVAL;
+                self.repeat = Some(repeat);
+                if !self.step() {
+                    return false;
+                }
+                if let Some(ScrollCallback(callback)) = &self.scroll_callback {
+                    let callback = callback.clone();
+                    let scroll_position = self.scroll_position;
+                    job_system.add_callback(Rc::new(move || callback(scroll_position)));
+                }
                 return true;
             }
             _ => return false,
@@ -1350,31 +2470,60 @@ impl GuiControl for ScrollV {
     }
 }
 
+const LIST_SCROLL_TIME_CONSTANT: Duration = Duration::from_millis(50);
+
 #[derive(Debug)]
 pub struct ListBox {
     base: GuiControlBase,
     scroll_width: i32,
     scroll: ScrollV,
     font: Font,
+    smooth_scrolling: bool,
+    /// Shown position in lines, can be between lines while it moves to the scroll position.
+    scroll_offset: SmoothValue,
     pub lines: Vec<String>,
 }
 
 impl ListBox {
     pub fn new(size_constraints: SizeConstraints, scroll_width: i32, font: Font) -> Self {
+        let scroll = ScrollV::new(size_constraints);
+        let scroll_offset = SmoothValue::new(
+            scroll.get_scroll_position() as f32,
+            LIST_SCROLL_TIME_CONSTANT,
+        );
         Self {
             base: GuiControlBase::new(size_constraints),
             scroll_width,
-            scroll: ScrollV::new(size_constraints),
+            scroll,
             font: font
                 .layout_vertical(TextLayoutVertical::TOP)
                 .layout_horizontal(TextLayoutHorizontal::LEFT),
+            smooth_scrolling: true,
+            scroll_offset,
             lines: Default::default(),
         }
     }
 
+    pub fn smooth_scrolling(mut self, smooth_scrolling: bool) -> Self {
+        self.smooth_scrolling = smooth_scrolling;
+        self
+    }
+
     fn get_item_height(&self) -> i32 {
         self.font.get_size("M").1 as i32 * 5 / 4
     }
+
+    /// Returns true if the shown position was changed.
+    fn update_scroll_offset(&mut self) -> bool {
+        let target = self.scroll.scroll_position as f32;
+        if !self.smooth_scrolling {
+            let changed = self.scroll_offset.get() != target;
+            self.scroll_offset.jump(target);
+            return changed;
+        }
+        self.scroll_offset.set_target(target);
+        self.scroll_offset.update()
+    }
 }
 
 impl GuiControl for ListBox {
@@ -1395,6 +2544,8 @@ impl GuiControl for ListBox {
             }
             GuiMessage::Draw(buf, theme, force) => {
                 if self.base.can_draw(force) {
+                    // lines move between frames, so the old ones are erased first
+                    GuiSystem::erase_background(buf, EmptySpaceState::Empty, theme);
                     self.scroll.base.highlight = self.base.highlight;
                     self.scroll.base.focus = self.base.focus;
                     self.scroll.base.pressed = self.base.pressed;
@@ -1404,6 +2555,8 @@ impl GuiControl for ListBox {
 
                     self.scroll.content_size = item_count - 1;
                     self.scroll.scroll_range = self.lines.len() as i32;
+                    self.scroll.clamp_scroll_position();
+                    self.update_scroll_offset();
                     let scroll_rect = self.scroll.base.rect;
                     let mut buf_for_child = buf.window_mut(
                         position_to_image_size(self.base.rect.relative(scroll_rect.left_top)),
@@ -1411,14 +2564,13 @@ impl GuiControl for ListBox {
                     );
                     let scroll_result =
                         self.scroll
-                            .on_message(GuiMessage::Draw(&mut buf_for_child, theme, force));
+                            .on_message(GuiMessage::Draw(&mut buf_for_child, theme, true));
 
-                    let first_line = min(
-                        max(0, self.scroll.scroll_position) as usize,
-                        self.lines.len(),
-                    );
-                    let last_line = min(first_line + (item_count + 1) as usize, self.lines.len());
-                    let mut position = (0, 0);
+                    let scroll_offset = self.scroll_offset.get().max(0.0);
+                    let first_line = min(scroll_offset as usize, self.lines.len());
+                    let last_line = min(first_line + (item_count + 2) as usize, self.lines.len());
+                    let line_shift = (scroll_offset.fract() * self.get_item_height() as f32) as i32;
+                    let mut position = (0, -line_shift);
                     for line in self.lines[first_line..last_line].iter() {
                         self.font.color(theme.font).draw(&line, position, buf);
                         position.1 += self.get_item_height();
@@ -1428,6 +2580,61 @@ impl GuiControl for ListBox {
                 }
                 return true;
             }
+            GuiMessage::KeyDown(key, _, _) => {
+                let page = max(1, self.scroll.content_size);
+                let old_scroll_position = self.scroll.scroll_position;
+                match key {
+                    Key::Up => self.scroll.scroll_position -= 1,
+                    Key::Down => self.scroll.scroll_position += 1,
+                    Key::PageUp => self.scroll.scroll_position -= page,
+                    Key::PageDown => self.scroll.scroll_position += page,
+                    Key::Home => self.scroll.scroll_position = 0,
+                    Key::End => self.scroll.scroll_position = self.scroll.scroll_range,
+                    _ => return false,
+                }
+                self.scroll.clamp_scroll_position();
+                if old_scroll_position != self.scroll.scroll_position {
+                    self.scroll.notify();
+                }
+                return true;
+            }
+            GuiMessage::Timer(job_system) => {
+                let scroll_changed = self.scroll.on_message(GuiMessage::Timer(job_system));
+                let offset_changed = self.update_scroll_offset();
+                return scroll_changed || offset_changed;
+            }
+            GuiMessage::GetDebugNodes(nodes) => {
+                nodes.push(DebugNode::new(&mut self.scroll));
+                return true;
+            }
+            GuiMessage::GetAccessibleNodes(nodes) => {
+                if self.base.visible {
+                    let mut node =
+                        AccessibleNode::new(AccessibleRole::List, String::new(), &self.base);
+                    let item_height = self.get_item_height();
+                    let first_line = max(0, self.scroll.scroll_position) as usize;
+                    for (index, line) in self.lines.iter().enumerate() {
+                        let mut item =
+                            AccessibleNode::new(AccessibleRole::ListItem, line.clone(), &self.base);
+                        item.focused = false;
+                        item.rect = Rect::default();
+                        if index >= first_line {
+                            let top = self.base.rect.left_top.1
+                                + (index - first_line) as i32 * item_height;
+                            if top < self.base.rect.right_bottom.1 {
+                                item.rect.left_top = (self.base.rect.left_top.0, top);
+                                item.rect.right_bottom = (
+                                    self.scroll.base.rect.left_top.0,
+                                    min(top + item_height, self.base.rect.right_bottom.1),
+                                );
+                            }
+                        }
+                        node.children.push(item);
+                    }
+                    nodes.push(node);
+                }
+                return true;
+            }
             _ => return self.scroll.on_message(m),
         }
     }
@@ -1573,6 +2780,14 @@ impl RadioGroup {
     }
 
     pub fn add_button(&mut self, button: Button) {
+        let count = self.internal.borrow().ids.len();
+        self.insert_button_at(count, button);
+    }
+
+    /// Index is the position among the buttons, not counting the caption.
+    pub fn insert_button_at(&mut self, index: usize, button: Button) {
+        let index = min(index, self.internal.borrow().ids.len());
+        let holder_index = self.container.child_count() - self.internal.borrow().ids.len() + index;
         let last_id = self.last_id;
         let mut button = if self.tab {
             button.tab_button()
@@ -1589,10 +2804,10 @@ impl RadioGroup {
             holder_constraints.0.absolute += close_button_size.0;
         }
 
-        let new_button_holder = self.container.add_child(Container::new(
-            holder_constraints,
-            ContainerLayout::Horizontal,
-        ));
+        let new_button_holder = self.container.insert_child(
+            holder_index,
+            Container::new(holder_constraints, ContainerLayout::Horizontal),
+        );
 
         button.base.set_size_constaints(SizeConstraints(
             SizeConstraint::flexible(0),
@@ -1613,7 +2828,7 @@ impl RadioGroup {
         let first;
         {
             let mut internal = self.internal.borrow_mut();
-            internal.buttons.push(Rc::downgrade(&new_button));
+            internal.buttons.insert(index, Rc::downgrade(&new_button));
             if self.with_closes {
                 let close_button_holder = new_button_holder.borrow_mut().add_child(
                     Container::new(
@@ -1647,10 +2862,10 @@ impl RadioGroup {
                         }
                     } ));
                 }
-                internal.close_buttons.push(Rc::downgrade(&button));
+                internal.close_buttons.insert(index, Rc::downgrade(&button));
             }
             first = internal.ids.is_empty();
-            internal.ids.push(last_id);
+            internal.ids.insert(index, last_id);
         }
         if first {
             self.set_id(self.last_id);
@@ -1660,7 +2875,9 @@ impl RadioGroup {
 
     pub fn delete_button(&mut self, id: usize) {
         if let Some(index) = self.find_index(id) {
-            self.container.delete_child(index);
+            let holder_index =
+                self.container.child_count() - self.internal.borrow().ids.len() + index;
+            self.container.delete_child(holder_index);
             let mut prev_id = None;
             {
                 let mut internal = self.internal.borrow_mut();
@@ -1689,6 +2906,67 @@ impl RadioGroup {
             }
         }
     }
+
+    pub fn get_ids(&self) -> Vec<usize> {
+        self.internal.borrow().ids.clone()
+    }
+
+    fn get_button(&self, id: usize) -> Option<Rc<RefCell<Button>>> {
+        let index = self.find_index(id)?;
+        self.internal.borrow().buttons.get(index)?.upgrade()
+    }
+
+    pub fn set_caption(&self, id: usize, caption: &str) {
+        if let Some(button) = self.get_button(id) {
+            button.borrow_mut().set_text(caption);
+        }
+    }
+
+    pub fn set_tooltip(&self, id: usize, tooltip: Option<Tooltip>) {
+        if let Some(button) = self.get_button(id) {
+            button.borrow_mut().set_tooltip(tooltip);
+        }
+    }
+
+    /// Disabled button can't be selected by click, its close button is disabled too.
+    pub fn set_enabled(&self, id: usize, enabled: bool) {
+        if let Some(button) = self.get_button(id) {
+            button.borrow_mut().set_enabled(enabled);
+        }
+        if let Some(index) = self.find_index(id) {
+            let close_button = self.internal.borrow().close_buttons.get(index).cloned();
+            if let Some(close_button) = close_button.and_then(|b| b.upgrade()) {
+                close_button.borrow_mut().set_enabled(enabled);
+            }
+        }
+    }
+}
+
+impl RadioGroup {
+    fn button_holders(&self) -> &[Rc<RefCell<dyn GuiControl>>] {
+        let children = &self.container.children;
+        &children[children.len() - self.internal.borrow().ids.len()..]
+    }
+
+    fn button_widths(&self) -> Vec<i32> {
+        self.button_holders()
+            .iter()
+            .map(|holder| {
+                holder
+                    .borrow_mut()
+                    .get_base_mut()
+                    .current_size_constraints
+                    .0
+                    .absolute
+            })
+            .collect()
+    }
+
+    fn set_first_visible_button(&mut self, first: usize) {
+        for (index, holder) in self.button_holders().iter().enumerate() {
+            holder.borrow_mut().get_base_mut().visible = index >= first;
+        }
+    }
 }
 
 impl GuiControl for RadioGroup {
@@ -1708,6 +2986,14 @@ pub struct TabControl {
     header: RadioGroup,
     children: Vec<Rc<RefCell<dyn GuiControl>>>,
     font: Font,
+    navigation_hotkeys: bool,
+    scroll_buttons: Rc<RefCell<dyn GuiControl>>,
+    scroll_buttons_width: i32,
+    first_visible_tab: Rc<Cell<usize>>,
+    /// First visible tab when the last one is shown at the end, ">" stops there.
+    max_first_visible_tab: Rc<Cell<usize>>,
+    last_selected_tab_id: usize,
+    overflow: bool,
 }
 
 impl TabControl {
@@ -1716,6 +3002,44 @@ impl TabControl {
             SizeConstraints(SizeConstraint::flexible(0), SizeConstraint::fixed(height));
         let full_constrains =
             SizeConstraints(SizeConstraint::flexible(0), SizeConstraint::fixed(height));
+
+        let first_visible_tab = Rc::new(Cell::new(0));
+        let max_first_visible_tab = Rc::new(Cell::new(0));
+        let arrow_width = GuiSystem::default_size("<", None, &font).0.absolute;
+        let arrow_constraints = SizeConstraints(
+            SizeConstraint::fixed(arrow_width),
+            SizeConstraint::fixed(height),
+        );
+        let mut scroll_buttons = Container::new(
+            SizeConstraints(
+                SizeConstraint::fixed(arrow_width * 2),
+                SizeConstraint::fixed(height),
+            ),
+            ContainerLayout::Horizontal,
+        )
+        .empty_space_state(EmptySpaceState::Inactive);
+        scroll_buttons.add_child(
+            Button::new(arrow_constraints, "<".to_string(), font.clone())
+                .tab_button()
+                .callback(callback!([first_visible_tab] () {
+                    let first = first_visible_tab.get();
+                    if first > 0 {
+                        first_visible_tab.set(first - 1);
+                    }
+                })),
+        );
+        scroll_buttons.add_child(
+            Button::new(arrow_constraints, ">".to_string(), font.clone())
+                .tab_button()
+                .callback(callback!([first_visible_tab, max_first_visible_tab] () {
+                    let first = first_visible_tab.get();
+                    if first < max_first_visible_tab.get() {
+                        first_visible_tab.set(first + 1);
+                    }
+                })),
+        );
+        let (scroll_buttons, _) = GuiSystem::create_rc_by_control(scroll_buttons);
+
         Self {
             base: GuiControlBase::new(full_constrains),
             height,
@@ -1728,6 +3052,13 @@ impl TabControl {
             ),
             children: vec![],
             font,
+            navigation_hotkeys: false,
+            scroll_buttons,
+            scroll_buttons_width: arrow_width * 2,
+            first_visible_tab,
+            max_first_visible_tab,
+            last_selected_tab_id: 0,
+            overflow: false,
         }
     }
 
@@ -1736,12 +3067,129 @@ impl TabControl {
         self
     }
 
+    pub fn navigation_hotkeys(mut self) -> Self {
+        self.navigation_hotkeys = true;
+        self
+    }
+
+    fn add_select_hotkey(
+        &self,
+        hotkey_map: &mut HashMap<Hotkey, HotkeyCallback>,
+        hotkey: Hotkey,
+        select: impl Fn(usize, usize) -> usize + 'static,
+    ) {
+        let internal = self.header.internal.clone();
+        hotkey_map.insert(
+            hotkey,
+            HotkeyCallback::new(Rc::new(callback!([internal] () {
+                let mut internal = internal.borrow_mut();
+                let count = internal.ids.len();
+                if count > 0 {
+                    let current =
+                        RadioGroup::find_index_by(&internal.ids, internal.selected_id).unwrap_or(0);
+                    if let Some(&new_id) = internal.ids.get(select(current, count)) {
+                        RadioGroup::change_id_by(internal.deref_mut(), new_id);
+                    }
+                }
+            }))),
+        );
+    }
+
+    fn add_navigation_hotkeys(&self, hotkey_map: &mut HashMap<Hotkey, HotkeyCallback>) {
+        self.add_select_hotkey(hotkey_map, Hotkey::ctrl(Key::Tab), |current, count| {
+            (current + 1) % count
+        });
+        self.add_select_hotkey(
+            hotkey_map,
+            Hotkey::ctrl_shift(Key::Tab),
+            |current, count| (current + count - 1) % count,
+        );
+
+        let number_keys = [
+            Key::Num1,
+            Key::Num2,
+            Key::Num3,
+            Key::Num4,
+            Key::Num5,
+            Key::Num6,
+            Key::Num7,
+            Key::Num8,
+        ];
+        for (index, &key) in number_keys.iter().enumerate() {
+            self.add_select_hotkey(hotkey_map, Hotkey::ctrl(key), move |_, _| index);
+        }
+        self.add_select_hotkey(hotkey_map, Hotkey::ctrl(Key::Num9), |_, count| count - 1);
+
+        let internal = self.header.internal.clone();
+        hotkey_map.insert(
+            Hotkey::ctrl(Key::W),
+            HotkeyCallback::new(Rc::new(callback!([internal] () {
+                let (maybe_close_callback, selected_id) = {
+                    let internal = internal.borrow();
+                    (internal.close_tab_callback.clone(), internal.selected_id)
+                };
+                if let Some(RadioGroupCallback(callback)) = maybe_close_callback {
+                    callback(selected_id);
+                }
+            }))),
+        );
+    }
+
+    fn update_header_scroll(&mut self, width: i32) {
+        let widths = self.header.button_widths();
+        let total_width: i32 = widths.iter().sum();
+        let selected_tab_id = self.selected_tab_id();
+        self.overflow = total_width > width;
+
+        let mut first = 0;
+        let mut max_first = 0;
+        if self.overflow {
+            let available_width = width - self.scroll_buttons_width;
+            let fits =
+                |from: usize, to: usize| widths[from..to].iter().sum::<i32>() <= available_width;
+
+            max_first = widths.len() - 1;
+            while max_first > 0 && fits(max_first - 1, widths.len()) {
+                max_first -= 1;
+            }
+            first = min(self.first_visible_tab.get(), widths.len() - 1);
+            if selected_tab_id != self.last_selected_tab_id {
+                if let Some(selected) = self.header.find_index(selected_tab_id) {
+                    first = min(first, selected);
+                    while first < selected && !fits(first, selected + 1) {
+                        first += 1;
+                    }
+                }
+            }
+            while first > 0 && fits(first - 1, widths.len()) {
+                first -= 1;
+            }
+        }
+
+        self.last_selected_tab_id = selected_tab_id;
+        self.first_visible_tab.set(first);
+        self.max_first_visible_tab.set(max_first);
+        self.header.set_first_visible_button(first);
+    }
+
     pub fn add_tab_with_id<Control: GuiControl>(
         &mut self,
         caption: String,
         width: i32,
         control: Control,
     ) -> (Rc<RefCell<Control>>, usize) {
+        let count = self.children.len();
+        self.insert_tab_with_id(count, caption, width, control)
+    }
+
+    pub fn insert_tab_with_id<Control: GuiControl>(
+        &mut self,
+        index: usize,
+        caption: String,
+        width: i32,
+        control: Control,
+    ) -> (Rc<RefCell<Control>>, usize) {
+        let index = min(index, self.children.len());
         let tab_id = self.header.get_last_id();
         let (untyped, typed) = GuiSystem::create_rc_by_control(control);
         let button = Button::new(
@@ -1753,12 +3201,30 @@ impl TabControl {
             self.font.clone(),
         );
 
-        self.header.add_button(button);
+        self.header.insert_button_at(index, button);
 
-        self.children.push(untyped);
+        self.children.insert(index, untyped);
         (typed, tab_id)
     }
 
+    /// Tab ids in the order of the tabs.
+    pub fn get_tab_ids(&self) -> Vec<usize> {
+        self.header.get_ids()
+    }
+
+    pub fn set_tab_caption(&self, id: usize, caption: &str) {
+        self.header.set_caption(id, caption);
+    }
+
+    /// Shown when the mouse rests over the tab header.
+    pub fn set_tab_tooltip(&self, id: usize, tooltip: Option<Tooltip>) {
+        self.header.set_tooltip(id, tooltip);
+    }
+
+    pub fn set_tab_enabled(&self, id: usize, enabled: bool) {
+        self.header.set_enabled(id, enabled);
+    }
+
     pub fn set_change_tab_callback(&mut self, callback: impl Fn(usize) + 'static) {
         self.header.set_change_tab_callback(callback);
     }
@@ -1837,6 +3303,18 @@ impl GuiControl for TabControl {
                     }
                 }
 
+                if self.overflow
+                    && self
+                        .scroll_buttons
+                        .borrow_mut()
+                        .get_base_mut()
+                        .rect
+                        .contains(position)
+                {
+                    *dest = GuiSystem::get_child(&self.scroll_buttons, position);
+                    return true;
+                }
+
                 return self
                     .header
                     .on_message(GuiMessage::FindDestination(dest, position));
@@ -1872,7 +3350,21 @@ impl GuiControl for TabControl {
             GuiMessage::RectUpdated => {
                 let mut header_rect = self.base.rect;
                 header_rect.right_bottom.1 = header_rect.left_top.1 + self.height;
+                self.update_header_scroll(header_rect.right_bottom.0 - header_rect.left_top.0);
+                if self.overflow {
+                    header_rect.right_bottom.0 = max(
+                        header_rect.left_top.0,
+                        header_rect.right_bottom.0 - self.scroll_buttons_width,
+                    );
+                }
+                let mut scroll_buttons_rect = header_rect;
+                scroll_buttons_rect.left_top.0 = header_rect.right_bottom.0;
+                scroll_buttons_rect.right_bottom.0 = self.base.rect.right_bottom.0;
                 GuiSystem::set_rect(&mut self.header, header_rect);
+                GuiSystem::set_rect(
+                    self.scroll_buttons.borrow_mut().deref_mut(),
+                    scroll_buttons_rect,
+                );
                 if let Some(selected_tab) = self.get_selected_tab() {
                     let mut selected_tab_rect = self.base.rect;
                     selected_tab_rect.left_top.1 = self.base.rect.left_top.1 + self.height;
@@ -1885,6 +3377,19 @@ impl GuiControl for TabControl {
                     let need_force = self.base.can_draw(force);
                     self.header
                         .on_message(GuiMessage::Draw(buf, theme, need_force));
+                    if self.overflow {
+                        let mut scroll_buttons = self.scroll_buttons.borrow_mut();
+                        let rect = scroll_buttons.get_base_mut().rect;
+                        let mut buf_for_scroll_buttons = buf.window_mut(
+                            position_to_image_size(self.base.rect.relative(rect.left_top)),
+                            position_to_image_size(self.base.rect.relative(rect.right_bottom)),
+                        );
+                        scroll_buttons.on_message(GuiMessage::Draw(
+                            &mut buf_for_scroll_buttons,
+                            theme,
+                            need_force,
+                        ));
+                    }
                     if let Some(selected_tab) = self.get_selected_tab() {
                         let mut child = selected_tab.borrow_mut();
                         let rect = child.get_base_mut().rect;
@@ -1903,6 +3408,9 @@ impl GuiControl for TabControl {
                     active && self.base.visible && self.header.get_base_mut().visible;
                 self.header
                     .on_message(GuiMessage::GetHotkeys(hotkey_map, header_active));
+                if self.navigation_hotkeys && header_active {
+                    self.add_navigation_hotkeys(hotkey_map);
+                }
                 for (index, child) in self.children.iter().enumerate() {
                     let active = active
                         && self.base.visible
@@ -1914,10 +3422,38 @@ impl GuiControl for TabControl {
                 }
                 return false;
             }
-            GuiMessage::Timer => {
+            GuiMessage::GetAccessibleNodes(nodes) => {
+                if self.base.visible {
+                    self.header
+                        .on_message(GuiMessage::GetAccessibleNodes(nodes));
+                    if self.overflow {
+                        self.scroll_buttons
+                            .borrow_mut()
+                            .on_message(GuiMessage::GetAccessibleNodes(nodes));
+                    }
+                    if let Some(selected_tab) = self.get_selected_tab() {
+                        selected_tab
+                            .borrow_mut()
+                            .on_message(GuiMessage::GetAccessibleNodes(nodes));
+                    }
+                }
+                return true;
+            }
+            GuiMessage::GetDebugNodes(nodes) => {
+                nodes.push(DebugNode::new(&mut self.header));
+                nodes.push(DebugNode::new(self.scroll_buttons.borrow_mut().deref_mut()));
+                for child in &self.children {
+                    nodes.push(DebugNode::new(child.borrow_mut().deref_mut()));
+                }
+                return true;
+            }
+            GuiMessage::Timer(job_system) => {
                 let mut result = false;
                 for child in &self.children {
-                    if child.borrow_mut().on_message(GuiMessage::Timer) {
+                    if child
+                        .borrow_mut()
+                        .on_message(GuiMessage::Timer(job_system.clone()))
+                    {
                         child.borrow_mut().get_base_mut().need_redraw = true;
                         result = true;
                     }
@@ -1926,6 +3462,9 @@ impl GuiControl for TabControl {
             }
             GuiMessage::Create => {
                 self.header.on_message(m);
+                self.scroll_buttons
+                    .borrow_mut()
+                    .on_message(GuiMessage::Create);
                 for child in &self.children {
                     child.borrow_mut().on_message(GuiMessage::Create);
                 }
@@ -1933,6 +3472,9 @@ impl GuiControl for TabControl {
             }
             GuiMessage::Destroy => {
                 self.header.on_message(m);
+                self.scroll_buttons
+                    .borrow_mut()
+                    .on_message(GuiMessage::Destroy);
                 for child in &self.children {
                     child.borrow_mut().on_message(GuiMessage::Destroy);
                 }
@@ -1940,6 +3482,9 @@ impl GuiControl for TabControl {
             }
             GuiMessage::Show => {
                 self.header.on_message(m);
+                self.scroll_buttons
+                    .borrow_mut()
+                    .on_message(GuiMessage::Show);
                 if let Some(child) = self.get_selected_tab() {
                     let mut child = child.borrow_mut();
                     if child.get_base_mut().visible {
@@ -1950,6 +3495,9 @@ impl GuiControl for TabControl {
             }
             GuiMessage::Hide => {
                 self.header.on_message(m);
+                self.scroll_buttons
+                    .borrow_mut()
+                    .on_message(GuiMessage::Hide);
                 if let Some(child) = self.get_selected_tab() {
                     let mut child = child.borrow_mut();
                     if child.get_base_mut().visible {
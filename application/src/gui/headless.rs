@@ -0,0 +1,374 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::rc::Rc;
+
+use crate::clipboard::*;
+use crate::draw_context::*;
+use crate::font::*;
+use crate::gui::input_log::*;
+use crate::gui::*;
+use crate::image::*;
+use crate::job_system::*;
+use crate::keys::*;
+use crate::shell::*;
+
+/// Draws every glyph as a hollow box, so text layout is deterministic without system fonts.
+#[derive(Debug, Default)]
+pub struct BoxFontLoader {}
+
+impl FontLoader for BoxFontLoader {
+    fn load_glyphs(
+        &mut self,
+        _font_name: &str,
+        font_size: i32,
+        code_from: u32,
+        code_to: u32,
+        _anti_aliasing_mode: FontAntiAliasingMode,
+    ) -> HashMap<char, Glyph> {
+        let height = font_size.abs().max(2) as usize;
+        let width = (height / 2).max(2);
+        (code_from..code_to)
+            .filter_map(std::char::from_u32)
+            .map(|c| {
+                let mut image = Image::<bool>::new((width, height));
+                if !c.is_whitespace() {
+                    image.as_view_mut().fill_with_coord(|p, (x, y)| {
+                        *p = x == 0 || y == 0 || x == width - 2 || y == height - 1;
+                    });
+                }
+                (c, Glyph::NoAA(image))
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct MemoryClipboard {
+    text: Option<String>,
+}
+
+impl ClipboardHandler for MemoryClipboard {
+    fn get_string(&self) -> Option<String> {
+        self.text.clone()
+    }
+
+    fn put_string(&mut self, text: &str) {
+        self.text = Some(text.to_string());
+    }
+}
+
+/// Remembers the opened targets instead of opening them.
+#[derive(Debug, Default)]
+pub struct MemoryShell {
+    opened: Rc<RefCell<Vec<String>>>,
+}
+
+impl ShellHandler for MemoryShell {
+    fn open(&mut self, target: &str) -> Result<(), String> {
+        self.opened.borrow_mut().push(target.to_string());
+        Ok(())
+    }
+}
+
+/// Runs a GuiSystem without a window: events are injected directly and frames are
+/// rendered into an in-memory image.
+pub struct HeadlessGui {
+    pub job_system: JobSystem,
+    pub gui_system: GuiSystem,
+    pub font_factory: FontFactory,
+    pub clipboard: Clipboard,
+    pub shell: Shell,
+    opened: Rc<RefCell<Vec<String>>>,
+    buffer: Image<u32>,
+}
+
+impl HeadlessGui {
+    pub fn new(size: ImageSize) -> Self {
+        let job_system = JobSystem::new();
+        let opened = Rc::new(RefCell::new(Vec::new()));
+        Self {
+            gui_system: GuiSystem::new(job_system.clone()),
+            job_system,
+            font_factory: FontFactory::new(BoxFontLoader::default()),
+            clipboard: Clipboard::new(MemoryClipboard::default()),
+            shell: Shell::new(MemoryShell {
+                opened: opened.clone(),
+            }),
+            opened,
+            buffer: Image::new(size),
+        }
+    }
+
+    pub fn font(&mut self, size: i32) -> Font {
+        self.font_factory
+            .new_font("headless", size, FontAntiAliasingMode::NoAA)
+    }
+
+    /// Targets opened with the shell, in order.
+    pub fn opened_targets(&self) -> Vec<String> {
+        self.opened.borrow().clone()
+    }
+
+    pub fn set_root<Control: GuiControl>(&mut self, control: Control) -> Rc<RefCell<Control>> {
+        self.gui_system.set_root(control)
+    }
+
+    pub fn resize(&mut self, size: ImageSize) {
+        self.buffer = Image::new(size);
+        self.gui_system.on_resize();
+    }
+
+    pub fn get_minimal_size(&self) -> Position {
+        self.gui_system.get_minimal_size_of_system()
+    }
+
+    pub fn run_jobs(&mut self) -> bool {
+        self.job_system.run_all()
+    }
+
+    /// Runs all started tasks to the end.
+    pub fn run_tasks(&mut self) {
+        while self.job_system.has_tasks() {
+            self.job_system
+                .run_task_steps(std::time::Duration::from_millis(10));
+            self.run_jobs();
+        }
+    }
+
+    pub fn render(&mut self) -> &Image<u32> {
+        self.run_jobs();
+        let mut draw_context = DrawContext {
+            buffer: self.buffer.as_view_mut(),
+            font_factory: &mut self.font_factory,
+        };
+        self.gui_system.on_draw(&mut draw_context);
+        &self.buffer
+    }
+
+    pub fn mouse_down(&mut self, position: Position) -> bool {
+        self.input(InputEvent::MouseDown(position))
+    }
+
+    pub fn mouse_move(&mut self, position: Position) -> bool {
+        self.input(InputEvent::MouseMove(position))
+    }
+
+    pub fn mouse_up(&mut self, position: Position) -> bool {
+        self.input(InputEvent::MouseUp(position))
+    }
+
+    pub fn mouse_wheel(&mut self, position: Position, delta: f32) -> bool {
+        self.input(InputEvent::MouseWheel(position, delta))
+    }
+
+    pub fn mouse_wheel_with_shift(&mut self, position: Position, delta: f32) -> bool {
+        self.input(InputEvent::MouseWheelWithShift(position, delta))
+    }
+
+    pub fn middle_double_click(&mut self, position: Position) -> bool {
+        self.input(InputEvent::MiddleDoubleClick(position))
+    }
+
+    pub fn mouse_hwheel(&mut self, position: Position, delta: f32) -> bool {
+        self.input(InputEvent::MouseHWheel(position, delta))
+    }
+
+    pub fn zoom(&mut self, position: Position, factor: f32) -> bool {
+        self.input(InputEvent::Zoom(position, factor))
+    }
+
+    pub fn pan(&mut self, position: Position, delta: Position) -> bool {
+        self.input(InputEvent::Pan(position, delta))
+    }
+
+    pub fn click(&mut self, position: Position) {
+        self.mouse_move(position);
+        self.mouse_down(position);
+        self.mouse_up(position);
+    }
+
+    pub fn hotkey(&mut self, hotkey: Hotkey) -> bool {
+        let result = self.input(InputEvent::KeyDown(hotkey));
+        self.input(InputEvent::KeyUp(hotkey.key));
+        result
+    }
+
+    pub fn key(&mut self, key: Key) -> bool {
+        self.hotkey(Hotkey::new(key))
+    }
+
+    pub fn type_text(&mut self, text: &str) {
+        for c in text.chars() {
+            self.input(InputEvent::Char(c));
+        }
+    }
+
+    pub fn input(&mut self, event: InputEvent) -> bool {
+        if let InputEvent::Resize(size) = event {
+            self.resize(size);
+            return true;
+        }
+        let result = event.dispatch(&mut self.gui_system);
+        self.run_jobs();
+        result
+    }
+
+    /// Replays a recorded log immediately, ignoring timestamps.
+    pub fn replay(&mut self, player: &InputPlayer) {
+        for recorded in player.events() {
+            self.input(recorded.event);
+        }
+    }
+
+    pub fn accessible_nodes(&self) -> Vec<AccessibleNode> {
+        fn flatten(nodes: Vec<AccessibleNode>, result: &mut Vec<AccessibleNode>) {
+            for mut node in nodes {
+                let children = std::mem::take(&mut node.children);
+                result.push(node);
+                flatten(children, result);
+            }
+        }
+
+        let mut result = Vec::new();
+        flatten(self.gui_system.get_accessible_nodes(), &mut result);
+        result
+    }
+
+    pub fn find_node(&self, role: AccessibleRole, name: &str) -> Option<AccessibleNode> {
+        self.accessible_nodes()
+            .into_iter()
+            .find(|node| node.role == role && node.name == name)
+    }
+
+    pub fn focused_node(&self) -> Option<AccessibleNode> {
+        self.accessible_nodes()
+            .into_iter()
+            .find(|node| node.focused)
+    }
+
+    pub fn click_node(&mut self, role: AccessibleRole, name: &str) -> bool {
+        if let Some(node) = self.find_node(role, name) {
+            let rect = node.rect;
+            self.click((
+                (rect.left_top.0 + rect.right_bottom.0) / 2,
+                (rect.left_top.1 + rect.right_bottom.1) / 2,
+            ));
+            return true;
+        }
+        return false;
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ImageDifference {
+    pub different_pixels: usize,
+    pub max_channel_delta: u32,
+}
+
+pub fn compare_images(a: &ImageView<u32>, b: &ImageView<u32>) -> Option<ImageDifference> {
+    if a.get_size() != b.get_size() {
+        return None;
+    }
+
+    let mut result = ImageDifference {
+        different_pixels: 0,
+        max_channel_delta: 0,
+    };
+    for (line_a, line_b) in a.lines(..).zip(b.lines(..)) {
+        for (pa, pb) in line_a.iter().zip(line_b.iter()) {
+            if pa != pb {
+                result.different_pixels += 1;
+                for shift in [0, 8, 16] {
+                    let ca = (pa >> shift) & 0xFF;
+                    let cb = (pb >> shift) & 0xFF;
+                    result.max_channel_delta = result.max_channel_delta.max(ca.abs_diff(cb));
+                }
+            }
+        }
+    }
+    Some(result)
+}
+
+pub fn save_ppm(image: &ImageView<u32>, path: &Path) -> std::io::Result<()> {
+    let size = image.get_size();
+    let mut data = format!("P6\n{} {}\n255\n", size.0, size.1).into_bytes();
+    for line in image.lines(..) {
+        for p in line {
+            data.extend_from_slice(&[(p >> 16) as u8, (p >> 8) as u8, *p as u8]);
+        }
+    }
+    std::fs::File::create(path)?.write_all(&data)
+}
+
+pub fn load_ppm(path: &Path) -> std::io::Result<Image<u32>> {
+    let invalid = || std::io::Error::new(std::io::ErrorKind::InvalidData, "bad ppm file");
+    let mut data = Vec::new();
+    std::fs::File::open(path)?.read_to_end(&mut data)?;
+
+    let mut fields = Vec::new();
+    let mut position = 0;
+    while fields.len() < 4 {
+        while position < data.len() && data[position].is_ascii_whitespace() {
+            position += 1;
+        }
+        let start = position;
+        while position < data.len() && !data[position].is_ascii_whitespace() {
+            position += 1;
+        }
+        if start == position {
+            return Err(invalid());
+        }
+        fields.push(String::from_utf8_lossy(&data[start..position]).to_string());
+    }
+    position += 1;
+
+    let parse = |s: &str| s.parse::<usize>().map_err(|_| invalid());
+    if fields[0] != "P6" || parse(&fields[3])? != 255 {
+        return Err(invalid());
+    }
+
+    let size = (parse(&fields[1])?, parse(&fields[2])?);
+    let pixels = data.get(position..).ok_or_else(invalid)?;
+    if pixels.len() < size.0 * size.1 * 3 {
+        return Err(invalid());
+    }
+
+    let mut image = Image::<u32>::new(size);
+    for (p, rgb) in image.get_data_mut().iter_mut().zip(pixels.chunks(3)) {
+        *p = ((rgb[0] as u32) << 16) | ((rgb[1] as u32) << 8) | rgb[2] as u32;
+    }
+    Ok(image)
+}
+
+/// Compares the image with a stored golden file. When the `UPDATE_GOLDEN` environment
+/// variable is set, the golden file is written instead, a missing file fails the test.
+pub fn assert_golden(image: &ImageView<u32>, path: &Path, max_different_pixels: usize) {
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).unwrap();
+        }
+        save_ppm(image, path).unwrap();
+        return;
+    }
+    assert!(
+        path.exists(),
+        "golden file {:?} is missing, run with UPDATE_GOLDEN=1 to write it",
+        path
+    );
+
+    let golden = load_ppm(path).unwrap();
+    let difference = compare_images(image, &golden.as_view());
+    match difference {
+        Some(difference) if difference.different_pixels <= max_different_pixels => {}
+        _ => {
+            let actual_path = path.with_extension("actual.ppm");
+            save_ppm(image, &actual_path).unwrap();
+            panic!(
+                "image differs from {:?}: {:?}, actual image saved to {:?}",
+                path, difference, actual_path
+            );
+        }
+    }
+}
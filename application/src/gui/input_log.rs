@@ -0,0 +1,179 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+use crate::gui::*;
+use crate::image::*;
+use crate::keys::*;
+
+/// Input as it reaches the GuiSystem from the platform layer.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq)]
+pub enum InputEvent {
+    Resize(ImageSize),
+    Char(char),
+    KeyDown(Hotkey),
+    KeyUp(Key),
+    /// Char typed with Alt.
+    Accelerator(char),
+    /// Alt pressed and released alone.
+    MenuKey,
+    MouseDown(Position),
+    MouseMove(Position),
+    MouseUp(Position),
+    MouseWheel(Position, f32),
+    MouseWheelWithShift(Position, f32),
+    MouseHWheel(Position, f32),
+    MiddleDoubleClick(Position),
+    Zoom(Position, f32),
+    Pan(Position, Position),
+    MouseLeave,
+    Deactivate,
+}
+
+impl InputEvent {
+    /// Keys which type the text, the password inputs keep them out of the log.
+    pub fn is_typing(&self) -> bool {
+        match self {
+            InputEvent::Char(_) | InputEvent::KeyDown(_) | InputEvent::KeyUp(_) => return true,
+            _ => return false,
+        }
+    }
+
+    /// Resize is not dispatched: the platform layer has to change the size of the
+    /// output buffer itself.
+    pub fn dispatch(&self, gui_system: &mut GuiSystem) -> bool {
+        if gui_system.is_busy() {
+            return self.dispatch_while_busy(gui_system);
+        }
+
+        match *self {
+            InputEvent::Resize(_) => return false,
+            InputEvent::Char(c) => return gui_system.on_char(c),
+            InputEvent::KeyDown(hotkey) => {
+                return gui_system.on_hotkey(hotkey)
+                    || (!hotkey.alt && gui_system.on_key_down(hotkey.key))
+                    || (hotkey == Hotkey::new(Key::Escape) && gui_system.on_escape())
+            }
+            InputEvent::KeyUp(key) => return gui_system.on_key_up(key),
+            InputEvent::Accelerator(c) => return gui_system.on_accelerator(c),
+            InputEvent::MenuKey => return gui_system.on_menu_key(),
+            InputEvent::MouseDown(position) => return gui_system.on_mouse_down(position),
+            InputEvent::MouseMove(position) => return gui_system.on_mouse_move(position),
+            InputEvent::MouseUp(position) => return gui_system.on_mouse_up(position),
+            InputEvent::MouseWheel(position, delta) => {
+                return gui_system.on_mouse_wheel(position, delta)
+            }
+            InputEvent::MouseWheelWithShift(position, delta) => {
+                return gui_system.on_mouse_wheel_with_shift(position, delta)
+            }
+            InputEvent::MouseHWheel(position, delta) => {
+                return gui_system.on_mouse_hwheel(position, delta)
+            }
+            InputEvent::MiddleDoubleClick(position) => {
+                return gui_system.on_middle_double_click(position)
+            }
+            InputEvent::Zoom(position, factor) => return gui_system.on_zoom(position, factor),
+            InputEvent::Pan(position, delta) => return gui_system.on_pan(position, delta),
+            InputEvent::MouseLeave => return gui_system.on_mouse_leave(),
+            InputEvent::Deactivate => return gui_system.on_deactivate(),
+        }
+    }
+
+    /// Commands are suppressed while a long task is running, Escape cancels it.
+    fn dispatch_while_busy(&self, gui_system: &mut GuiSystem) -> bool {
+        match *self {
+            InputEvent::KeyDown(hotkey) if hotkey.key == Key::Escape => {
+                return gui_system.on_escape();
+            }
+            InputEvent::MouseLeave => return gui_system.on_mouse_leave(),
+            InputEvent::Deactivate => return gui_system.on_deactivate(),
+            _ => return false,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq)]
+pub struct RecordedEvent {
+    pub time_ms: u64,
+    pub event: InputEvent,
+}
+
+/// Writes input events with timestamps to a file, one JSON object per line.
+pub struct InputRecorder {
+    writer: Box<dyn Write>,
+    start: Instant,
+}
+
+impl InputRecorder {
+    pub fn new(writer: Box<dyn Write>) -> Self {
+        Self {
+            writer,
+            start: Instant::now(),
+        }
+    }
+
+    pub fn create(path: &Path) -> std::io::Result<Self> {
+        Ok(Self::new(Box::new(BufWriter::new(File::create(path)?))))
+    }
+
+    pub fn record(&mut self, event: InputEvent) -> std::io::Result<()> {
+        let recorded = RecordedEvent {
+            time_ms: self.start.elapsed().as_millis() as u64,
+            event,
+        };
+        serde_json::to_writer(&mut self.writer, &recorded)?;
+        self.writer.write_all(b"\n")?;
+        // Flush every event, the log is most useful when the application crashes
+        self.writer.flush()
+    }
+}
+
+/// Returns recorded events when their time comes, counting from the first poll.
+pub struct InputPlayer {
+    events: Vec<RecordedEvent>,
+    next: usize,
+    start: Option<Instant>,
+}
+
+impl InputPlayer {
+    pub fn new(events: Vec<RecordedEvent>) -> Self {
+        Self {
+            events,
+            next: 0,
+            start: None,
+        }
+    }
+
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        let mut events = Vec::new();
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            events.push(serde_json::from_str(&line)?);
+        }
+        Ok(Self::new(events))
+    }
+
+    pub fn events(&self) -> &[RecordedEvent] {
+        &self.events
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.events.len()
+    }
+
+    pub fn next_due_event(&mut self) -> Option<InputEvent> {
+        let start = *self.start.get_or_insert_with(Instant::now);
+        let event = self.events.get(self.next)?;
+        if event.time_ms > start.elapsed().as_millis() as u64 {
+            return None;
+        }
+        self.next += 1;
+        Some(event.event)
+    }
+}
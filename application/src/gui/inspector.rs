@@ -0,0 +1,308 @@
+use crate::font::*;
+use crate::gui::*;
+use crate::image::*;
+
+/// Layout state of one control and its children, for diagnosing the layout.
+#[derive(Debug, Clone)]
+pub struct DebugNode {
+    pub type_name: &'static str,
+    pub rect: Rect,
+    pub size_constraints: SizeConstraints,
+    /// The constraints grown by the children.
+    pub current_size_constraints: SizeConstraints,
+    pub minimal_size: Position,
+    pub visible: bool,
+    pub focus: bool,
+    pub highlight: bool,
+    pub pressed: bool,
+    pub children: Vec<DebugNode>,
+}
+
+impl DebugNode {
+    pub fn new(control: &mut dyn GuiControl) -> Self {
+        let mut children = Vec::new();
+        control.on_message(GuiMessage::GetDebugNodes(&mut children));
+        let type_name = control.get_type_name();
+        let base = control.get_base_mut();
+        Self {
+            // the module path only makes the lines longer
+            type_name: type_name.rsplit("::").next().unwrap_or(type_name),
+            rect: base.rect,
+            size_constraints: base.size_constraints,
+            current_size_constraints: base.current_size_constraints,
+            minimal_size: base.minimal_size,
+            visible: base.visible,
+            focus: base.focus,
+            highlight: base.highlight,
+            pressed: base.pressed,
+            children,
+        }
+    }
+
+    /// Indices of the children down to the deepest visible control under the position,
+    /// it is the control which gets the mouse messages.
+    pub fn find_path(&self, position: Position) -> Vec<usize> {
+        let mut path = Vec::new();
+        let mut node = self;
+        while let Some(index) = node
+            .children
+            .iter()
+            .position(|child| child.visible && child.rect.contains(position))
+        {
+            path.push(index);
+            node = &node.children[index];
+        }
+        path
+    }
+
+    pub fn get_descendant(&self, path: &[usize]) -> Option<&DebugNode> {
+        path.iter()
+            .try_fold(self, |node, index| node.children.get(*index))
+    }
+
+    /// One line: the type, the rect, the constraints as `absolute+relative%` and the flags.
+    pub fn describe(&self) -> String {
+        let constraint = |c: SizeConstraint| format!("{}+{}%", c.absolute, c.relative);
+        let mut flags = String::new();
+        for (flag, name) in [
+            (!self.visible, " hidden"),
+            (self.focus, " focus"),
+            (self.highlight, " highlight"),
+            (self.pressed, " pressed"),
+        ] {
+            if flag {
+                flags.push_str(name);
+            }
+        }
+        format!(
+            "{} ({}, {})-({}, {}) {} x {} cur {} x {} min {} x {}{}",
+            self.type_name,
+            self.rect.left_top.0,
+            self.rect.left_top.1,
+            self.rect.right_bottom.0,
+            self.rect.right_bottom.1,
+            constraint(self.size_constraints.0),
+            constraint(self.size_constraints.1),
+            constraint(self.current_size_constraints.0),
+            constraint(self.current_size_constraints.1),
+            self.minimal_size.0,
+            self.minimal_size.1,
+            flags
+        )
+    }
+
+    /// The whole tree as indented lines, for logs.
+    pub fn dump(&self) -> String {
+        let mut result = String::new();
+        self.dump_to(0, &mut result);
+        result
+    }
+
+    fn dump_to(&self, depth: usize, result: &mut String) {
+        result.push_str(&"  ".repeat(depth));
+        result.push_str(&self.describe());
+        result.push('\n');
+        for child in &self.children {
+            child.dump_to(depth + 1, result);
+        }
+    }
+
+    /// Lines of the tree opened along the path: every node on it shows its children.
+    fn collect_lines(&self, path: &[usize], depth: usize, lines: &mut Vec<(usize, String, bool)>) {
+        lines.push((depth, self.describe(), path.is_empty()));
+        let (next, rest) = match path.split_first() {
+            Some((next, rest)) => (Some(*next), rest),
+            None => (None, path),
+        };
+        for (index, child) in self.children.iter().enumerate() {
+            if Some(index) == next {
+                child.collect_lines(rest, depth + 1, lines);
+            } else {
+                lines.push((depth + 1, child.describe(), false));
+            }
+        }
+    }
+}
+
+/// Developer overlay with the tree of the controls. The click picks the control under the
+/// mouse instead of pressing it, the tree is opened down to the picked control and its
+/// bounds are outlined, the bounds of the control under the mouse too.
+#[derive(Debug, Default)]
+pub(crate) struct Inspector {
+    shown: bool,
+    font: Option<Font>,
+    hovered: Option<Position>,
+    picked: Option<Position>,
+    changed: bool,
+    pressed: bool,
+}
+
+/// The outline of the picked control.
+const PICKED_COLOR: u32 = 0xFF00FF;
+/// The outline of the control under the mouse.
+const HOVERED_COLOR: u32 = 0x00AAFF;
+
+impl Inspector {
+    pub(crate) fn set_font(&mut self, font: Font) {
+        self.font = Some(font);
+        self.changed = true;
+    }
+
+    pub(crate) fn is_shown(&self) -> bool {
+        self.shown
+    }
+
+    pub(crate) fn set_shown(&mut self, shown: bool) {
+        self.shown = shown;
+        self.hovered = None;
+        self.picked = None;
+        self.changed = true;
+    }
+
+    pub(crate) fn on_mouse_move(&mut self, position: Position) -> bool {
+        if !self.shown {
+            return false;
+        }
+        self.hovered = Some(position);
+        self.changed = true;
+        true
+    }
+
+    /// Returns true if the click picked a control and must not go to it.
+    pub(crate) fn on_mouse_down(&mut self, position: Position) -> bool {
+        if !self.shown {
+            return false;
+        }
+        self.picked = Some(position);
+        self.changed = true;
+        self.pressed = true;
+        true
+    }
+
+    /// The mouse up after a pick does not go to the controls under it.
+    pub(crate) fn on_mouse_up(&mut self) -> bool {
+        std::mem::replace(&mut self.pressed, false)
+    }
+
+    pub(crate) fn take_changed(&mut self) -> bool {
+        std::mem::replace(&mut self.changed, false)
+    }
+
+    /// `find_root` gives the index of the region under the position, as the mouse
+    /// messages go.
+    pub(crate) fn draw(
+        &self,
+        buf: &mut ImageViewMut<u32>,
+        color_theme: &GuiColorTheme,
+        roots: &[DebugNode],
+        find_root: impl Fn(Position) -> Option<usize>,
+    ) {
+        let font = match (&self.font, self.shown) {
+            (Some(font), true) => font,
+            _ => return,
+        };
+        let find = |position: Option<Position>| {
+            let position = position?;
+            let root = find_root(position)?;
+            let path = roots[root].find_path(position);
+            Some((root, path))
+        };
+        let picked = find(self.picked);
+        self.draw_tree(buf, color_theme, font, roots, picked.as_ref());
+        // the outlines go above the tree, the picked control can be under it
+        for (position, color) in [(self.hovered, HOVERED_COLOR), (self.picked, PICKED_COLOR)] {
+            if let Some(node) =
+                find(position).and_then(|(root, path)| roots[root].get_descendant(&path))
+            {
+                draw_outline(buf, node.rect, color);
+            }
+        }
+    }
+
+    /// The panel is in the right top corner, the lines which don't fit are cut.
+    fn draw_tree(
+        &self,
+        buf: &mut ImageViewMut<u32>,
+        color_theme: &GuiColorTheme,
+        font: &Font,
+        roots: &[DebugNode],
+        picked: Option<&(usize, Vec<usize>)>,
+    ) {
+        let mut lines = Vec::new();
+        for (index, root) in roots.iter().enumerate() {
+            match picked {
+                Some((picked_root, path)) if *picked_root == index => {
+                    root.collect_lines(path, 0, &mut lines)
+                }
+                _ => root.collect_lines(&[], 0, &mut lines),
+            }
+        }
+        let buf_size = image_size_to_position(buf.get_size());
+        let line_height = font.get_size("8").1 as i32;
+        let indent = font.get_size("  ").0 as i32;
+        let padding = line_height / 4 + 1;
+        let visible_lines = ((buf_size.1 - padding * 2) / line_height.max(1)).max(0) as usize;
+        lines.truncate(visible_lines);
+        let text_width = lines
+            .iter()
+            .map(|(depth, line, _)| indent * *depth as i32 + font.get_size(line).0 as i32)
+            .max()
+            .unwrap_or(0);
+        let width = (text_width + padding * 2).min(buf_size.0);
+        let height = line_height * lines.len() as i32 + padding * 2;
+        if width <= 2 || height <= 2 || height > buf_size.1 {
+            return;
+        }
+        let left = buf_size.0 - width;
+        let mut panel_buf = buf.window_mut(
+            position_to_image_size((left, 0)),
+            position_to_image_size((buf_size.0, height)),
+        );
+        panel_buf.fill(|p| *p = color_theme.splitter);
+        panel_buf
+            .window_mut((1, 1), ((width - 1) as usize, (height - 1) as usize))
+            .fill(|p| *p = color_theme.background);
+        let text_font = font
+            .layout_vertical(TextLayoutVertical::TOP)
+            .layout_horizontal(TextLayoutHorizontal::LEFT);
+        for (index, (depth, line, is_picked)) in lines.iter().enumerate() {
+            let color = if *is_picked {
+                color_theme.selected
+            } else {
+                color_theme.font
+            };
+            text_font.color(color).draw(
+                line,
+                (
+                    padding + indent * *depth as i32,
+                    padding + line_height * index as i32,
+                ),
+                &mut panel_buf,
+            );
+        }
+    }
+}
+
+/// One pixel frame along the inner side of the rect, the parts out of the buffer are cut.
+fn draw_outline(buf: &mut ImageViewMut<u32>, rect: Rect, color: u32) {
+    let size = image_size_to_position(buf.get_size());
+    let left = rect.left_top.0.clamp(0, size.0);
+    let top = rect.left_top.1.clamp(0, size.1);
+    let right = rect.right_bottom.0.clamp(left, size.0);
+    let bottom = rect.right_bottom.1.clamp(top, size.1);
+    if right == left || bottom == top {
+        return;
+    }
+    for (left_top, right_bottom) in [
+        ((left, top), (right, top + 1)),
+        ((left, bottom - 1), (right, bottom)),
+        ((left, top), (left + 1, bottom)),
+        ((right - 1, top), (right, bottom)),
+    ] {
+        buf.window_mut(
+            position_to_image_size(left_top),
+            position_to_image_size(right_bottom),
+        )
+        .fill(|p| *p = color);
+    }
+}
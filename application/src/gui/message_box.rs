@@ -0,0 +1,410 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use crate::font::*;
+use crate::gui::gui_components::*;
+use crate::gui::*;
+
+/// Escape closes the message box before anything under it is cancelled.
+pub const ESCAPE_PRIORITY_MESSAGE_BOX: i32 = i32::MAX;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MessageIcon {
+    None,
+    Info,
+    Warning,
+    Error,
+    Question,
+}
+
+impl MessageIcon {
+    fn get_symbol(self) -> Option<&'static str> {
+        match self {
+            MessageIcon::None => None,
+            MessageIcon::Info => Some("i"),
+            MessageIcon::Warning => Some("!"),
+            MessageIcon::Error => Some("×"),
+            MessageIcon::Question => Some("?"),
+        }
+    }
+
+    /// The same colors as the toasts of the same kind.
+    fn get_color(self, color_theme: &GuiColorTheme) -> u32 {
+        match self {
+            MessageIcon::Warning => 0xDDAA00,
+            MessageIcon::Error => 0xDD3333,
+            _ => color_theme.selected,
+        }
+    }
+}
+
+/// Check box under the text, e.g. "Больше не спрашивать".
+#[derive(Clone)]
+pub struct MessageBoxCheck {
+    caption: String,
+    callback: Rc<dyn Fn(bool) + 'static>,
+}
+
+impl std::fmt::Debug for MessageBoxCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MessageBoxCheck")
+            .field("caption", &self.caption)
+            .finish()
+    }
+}
+
+/// Modal dialog above the window, the index of the pressed button is given to the
+/// callback of `GuiSystem::show_message_box`.
+#[derive(Debug, Clone)]
+pub struct MessageBox {
+    caption: String,
+    text: String,
+    details: Option<String>,
+    icon: MessageIcon,
+    buttons: Vec<String>,
+    default_button: usize,
+    cancel_button: Option<usize>,
+    check: Option<MessageBoxCheck>,
+}
+
+impl MessageBox {
+    /// Button indices of `yes_no` and `yes_no_cancel`.
+    pub const YES: usize = 0;
+    pub const NO: usize = 1;
+    pub const CANCEL: usize = 2;
+
+    /// The only button is "ОК", Escape presses it too.
+    pub fn new(caption: &str, text: &str) -> Self {
+        Self {
+            caption: caption.to_string(),
+            text: text.to_string(),
+            details: None,
+            icon: MessageIcon::None,
+            buttons: vec!["ОК".to_string()],
+            default_button: 0,
+            cancel_button: Some(0),
+            check: None,
+        }
+    }
+
+    /// "ОК" is 0 and "Отмена" is 1.
+    pub fn ok_cancel(caption: &str, text: &str) -> Self {
+        Self::new(caption, text)
+            .buttons(&["ОК", "Отмена"])
+            .cancel_button(Some(1))
+    }
+
+    /// Escape can't close it, one of the answers has to be chosen.
+    pub fn yes_no(caption: &str, text: &str) -> Self {
+        Self::new(caption, text)
+            .icon(MessageIcon::Question)
+            .buttons(&["Да", "Нет"])
+    }
+
+    pub fn yes_no_cancel(caption: &str, text: &str) -> Self {
+        Self::yes_no(caption, text)
+            .buttons(&["Да", "Нет", "Отмена"])
+            .cancel_button(Some(Self::CANCEL))
+    }
+
+    /// Buttons from left to right, the default and the cancel buttons are reset to
+    /// the first one and none.
+    pub fn buttons(mut self, captions: &[&str]) -> Self {
+        self.buttons = captions.iter().map(|caption| caption.to_string()).collect();
+        self.default_button = 0;
+        self.cancel_button = None;
+        self
+    }
+
+    /// The button focused when the box is shown, Enter presses it.
+    pub fn default_button(mut self, index: usize) -> Self {
+        self.default_button = index;
+        self
+    }
+
+    /// The button chosen by Escape, without it Escape does nothing.
+    pub fn cancel_button(mut self, index: Option<usize>) -> Self {
+        self.cancel_button = index;
+        self
+    }
+
+    pub fn icon(mut self, icon: MessageIcon) -> Self {
+        self.icon = icon;
+        self
+    }
+
+    /// Text hidden under the "Подробности" button, e.g. the full error.
+    pub fn details(mut self, details: &str) -> Self {
+        self.details = Some(details.to_string());
+        self
+    }
+
+    /// The callback gets the state of the check box when the box is closed by any button
+    /// except the cancel one.
+    pub fn check_box(mut self, caption: &str, callback: impl Fn(bool) + 'static) -> Self {
+        self.check = Some(MessageBoxCheck {
+            caption: caption.to_string(),
+            callback: Rc::new(callback),
+        });
+        self
+    }
+
+    pub fn get_caption(&self) -> &str {
+        &self.caption
+    }
+
+    pub fn get_text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn get_buttons(&self) -> &[String] {
+        &self.buttons
+    }
+
+    pub fn get_default_button(&self) -> usize {
+        self.default_button
+    }
+
+    pub(crate) fn get_cancel_button(&self) -> Option<usize> {
+        self.cancel_button
+            .filter(|index| *index < self.buttons.len())
+    }
+
+    /// Gives the state of the check box to its callback, if the choice is not the cancel.
+    pub(crate) fn report_check(&self, state: &MessageBoxState, index: usize) {
+        if let Some(check) = &self.check {
+            if Some(index) != self.get_cancel_button() {
+                (check.callback)(state.checked.get());
+            }
+        }
+    }
+
+    /// The controls of the box with a frame, `choose` is called by the buttons.
+    pub(crate) fn build(
+        &self,
+        font: &Font,
+        color_theme: &GuiColorTheme,
+        state: &Rc<MessageBoxState>,
+        choose: Rc<dyn Fn(usize)>,
+    ) -> BuiltMessageBox {
+        let font_height = font.get_size("8").1 as i32 + 2;
+        let padding = font_height / 2;
+        let line_width = |line: &str| font.get_size(line).0 as i32 + font_height;
+        let text_lines: Vec<_> = self.text.lines().collect();
+        let detail_lines: Vec<_> = self.details.iter().flat_map(|d| d.lines()).collect();
+        let icon_width = self.icon.get_symbol().map_or(0, |_| font_height * 3 / 2);
+        let fixed_height = |height: i32| {
+            SizeConstraints(SizeConstraint::flexible(0), SizeConstraint::fixed(height))
+        };
+        let text_box =
+            |line: &str| TextBox::new(fixed_height(font_height), line.to_string(), font.clone());
+
+        let mut content = Container::new(
+            SizeConstraints(SizeConstraint::flexible(0), SizeConstraint::flexible(0)),
+            ContainerLayout::Vertical,
+        );
+        content.add_child(EmptySpace::new_splitter(fixed_height(1)));
+        content.add_child(
+            TextBox::new(fixed_height(font_height), String::new(), font.clone())
+                .runs(vec![TextRun::new(&self.caption).bold()]),
+        );
+        content.add_child(EmptySpace::new_splitter(fixed_height(1)));
+        content.add_child(EmptySpace::new_empty(fixed_height(padding)));
+
+        let text_height = font_height * text_lines.len().max(1) as i32;
+        let body = content.add_child(Container::new(
+            fixed_height(text_height),
+            ContainerLayout::Horizontal,
+        ));
+        if let Some(symbol) = self.icon.get_symbol() {
+            body.borrow_mut().add_child(
+                TextBox::new(
+                    SizeConstraints(
+                        SizeConstraint::fixed(icon_width),
+                        SizeConstraint::fixed(font_height),
+                    ),
+                    String::new(),
+                    font.clone(),
+                )
+                .runs(vec![TextRun::new(symbol)
+                    .color(self.icon.get_color(color_theme))
+                    .bold()]),
+            );
+        }
+        let text_column = body.borrow_mut().add_child(Container::new(
+            SizeConstraints(SizeConstraint::flexible(0), SizeConstraint::flexible(0)),
+            ContainerLayout::Vertical,
+        ));
+        for line in &text_lines {
+            text_column.borrow_mut().add_child(text_box(line));
+        }
+
+        let details_height = font_height * detail_lines.len() as i32;
+        let details = content.add_child(Container::new(
+            fixed_height(details_height),
+            ContainerLayout::Vertical,
+        ));
+        details.borrow_mut().get_base_mut().visible = false;
+        for line in &detail_lines {
+            details.borrow_mut().add_child(text_box(line));
+        }
+
+        let mut check_width = 0;
+        if let Some(check) = &self.check {
+            let size_constraints = Button::default_checkbox_size(&check.caption, None, font);
+            check_width = size_constraints.0.absolute + padding;
+            let check_row = content.add_child(Container::new(
+                fixed_height(font_height),
+                ContainerLayout::Horizontal,
+            ));
+            check_row
+                .borrow_mut()
+                .add_child(EmptySpace::new_empty(SizeConstraints(
+                    SizeConstraint::fixed(icon_width),
+                    SizeConstraint::flexible(0),
+                )));
+            let state = state.clone();
+            check_row.borrow_mut().add_child(
+                Button::new(size_constraints, check.caption.clone(), font.clone())
+                    .check_box(false)
+                    .checkbox_callback(move |checked| state.checked.set(checked)),
+            );
+        }
+
+        content.add_child(EmptySpace::new_empty(fixed_height(padding)));
+        let button_row = content.add_child(Container::new(
+            fixed_height(font_height),
+            ContainerLayout::Horizontal,
+        ));
+        content.add_child(EmptySpace::new_empty(fixed_height(padding)));
+        content.add_child(EmptySpace::new_splitter(fixed_height(1)));
+
+        let gap = || {
+            EmptySpace::new_empty(SizeConstraints(
+                SizeConstraint::fixed(padding),
+                SizeConstraint::flexible(0),
+            ))
+        };
+        let mut buttons_width = padding;
+        button_row.borrow_mut().add_child(gap());
+        if self.details.is_some() {
+            let caption = "Подробности";
+            let size_constraints = GuiSystem::default_size(caption, None, font);
+            buttons_width += size_constraints.0.absolute;
+            let state = state.clone();
+            button_row.borrow_mut().add_child(
+                Button::new(size_constraints, caption.to_string(), font.clone())
+                    .toggle_button(false)
+                    .toggle_callback(move |shown| {
+                        details.borrow_mut().get_base_mut().visible = shown;
+                        state.expanded.set(shown);
+                    }),
+            );
+        }
+        button_row
+            .borrow_mut()
+            .add_child(EmptySpace::new_empty(SizeConstraints(
+                SizeConstraint::flexible(0),
+                SizeConstraint::flexible(0),
+            )));
+        let mut default_button = None;
+        for (index, caption) in self.buttons.iter().enumerate() {
+            let size_constraints = GuiSystem::default_size(caption, None, font);
+            buttons_width += size_constraints.0.absolute + padding;
+            let choose = choose.clone();
+            let button = button_row.borrow_mut().add_child(
+                Button::new(size_constraints, caption.clone(), font.clone())
+                    .callback(move || choose(index)),
+            );
+            if index == self.default_button || default_button.is_none() {
+                let button: Rc<RefCell<dyn GuiControl>> = button;
+                default_button = Some(button);
+            }
+            button_row.borrow_mut().add_child(gap());
+        }
+
+        let width = [
+            line_width(&self.caption) + 1,
+            icon_width
+                + text_lines
+                    .iter()
+                    .map(|line| line_width(line))
+                    .max()
+                    .unwrap_or(0),
+            detail_lines
+                .iter()
+                .map(|line| line_width(line))
+                .max()
+                .unwrap_or(0),
+            icon_width + check_width,
+            buttons_width + padding,
+        ]
+        .into_iter()
+        .max()
+        .unwrap_or(0);
+        let check_height = if self.check.is_some() { font_height } else { 0 };
+        let collapsed_height = font_height * 2 + text_height + check_height + padding * 3 + 3;
+
+        let mut frame = Container::new(
+            SizeConstraints(SizeConstraint::flexible(0), SizeConstraint::flexible(0)),
+            ContainerLayout::Horizontal,
+        );
+        let side = || {
+            EmptySpace::new_splitter(SizeConstraints(
+                SizeConstraint::fixed(1),
+                SizeConstraint::flexible(0),
+            ))
+        };
+        frame.add_child(side());
+        frame.add_child(content);
+        frame.add_child(side());
+
+        BuiltMessageBox {
+            frame,
+            default_button,
+            collapsed_size: (width + 2, collapsed_height),
+            expanded_size: (width + 2, collapsed_height + details_height),
+        }
+    }
+}
+
+/// Shared by the buttons and the escape handler of the shown box.
+#[derive(Debug, Default)]
+pub(crate) struct MessageBoxState {
+    pub(crate) choice: Cell<Option<usize>>,
+    pub(crate) expanded: Cell<bool>,
+    pub(crate) checked: Cell<bool>,
+}
+
+pub(crate) struct BuiltMessageBox {
+    pub(crate) frame: Container,
+    pub(crate) default_button: Option<Rc<RefCell<dyn GuiControl>>>,
+    pub(crate) collapsed_size: Position,
+    pub(crate) expanded_size: Position,
+}
+
+/// The box is closed by the next `on_draw` after the choice.
+#[derive(Debug)]
+pub(crate) struct ActiveMessageBox {
+    pub(crate) region_id: usize,
+    pub(crate) escape_handler_id: usize,
+    pub(crate) state: Rc<MessageBoxState>,
+    pub(crate) collapsed_size: Position,
+    pub(crate) expanded_size: Position,
+}
+
+impl ActiveMessageBox {
+    /// The box is in the center of the window.
+    pub(crate) fn get_rect(&self, window_size: Position) -> Rect {
+        let size = if self.state.expanded.get() {
+            self.expanded_size
+        } else {
+            self.collapsed_size
+        };
+        let left_top = ((window_size.0 - size.0) / 2, (window_size.1 - size.1) / 2);
+        Rect {
+            left_top,
+            right_bottom: (left_top.0 + size.0, left_top.1 + size.1),
+        }
+    }
+}
@@ -1,15 +1,32 @@
+pub mod animation;
+pub mod binding;
+pub mod caret;
+pub mod completion;
 pub mod gui_components;
+pub mod headless;
+pub mod input_log;
+pub mod inspector;
+pub mod message_box;
+pub mod toast;
+pub mod tooltip;
 
 use crate::draw_context::*;
 use crate::font::*;
 use crate::image::*;
 use crate::job_system::*;
 use crate::keys::*;
+use caret::*;
+use completion::*;
+use inspector::*;
+use message_box::*;
+use toast::*;
+use tooltip::*;
 
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::ops::DerefMut;
 use std::rc::{Rc, Weak};
+use std::time::Duration;
 
 #[derive(Default, Copy, Clone, Debug)]
 pub struct SizeConstraint {
@@ -36,7 +53,7 @@ impl SizeConstraint {
 #[derive(Default, Copy, Clone, Debug)]
 pub struct SizeConstraints(pub SizeConstraint, pub SizeConstraint);
 
-#[derive(Default, Copy, Clone, Debug)]
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
 pub struct Rect {
     pub left_top: Position,
     pub right_bottom: Position,
@@ -70,6 +87,138 @@ impl HotkeyCallback {
     }
 }
 
+/// Cancels something on Escape, returns false if there was nothing to cancel.
+struct EscapeHandler {
+    id: usize,
+    priority: i32,
+    handler: Rc<dyn Fn() -> bool>,
+}
+
+impl std::fmt::Debug for EscapeHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EscapeHandler")
+            .field("id", &self.id)
+            .field("priority", &self.priority)
+            .finish()
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AccessibleRole {
+    Button,
+    CheckBox,
+    RadioButton,
+    Tab,
+    Text,
+    Edit,
+    List,
+    ListItem,
+    Link,
+    ProgressBar,
+    Image,
+}
+
+#[derive(Debug, Clone)]
+pub struct AccessibleNode {
+    pub role: AccessibleRole,
+    pub name: String,
+    pub value: Option<String>,
+    pub checked: Option<bool>,
+    pub shortcut: Option<Hotkey>,
+    /// Char activating the control with Alt, in lower case.
+    pub accelerator: Option<char>,
+    pub focused: bool,
+    pub enabled: bool,
+    pub rect: Rect,
+    pub control: Option<Weak<RefCell<dyn GuiControl>>>,
+    pub children: Vec<AccessibleNode>,
+}
+
+impl AccessibleNode {
+    pub fn new(role: AccessibleRole, name: String, base: &GuiControlBase) -> Self {
+        Self {
+            role,
+            name,
+            value: None,
+            checked: None,
+            shortcut: None,
+            accelerator: None,
+            focused: base.focus,
+            enabled: true,
+            rect: base.rect,
+            control: base.self_ref.clone(),
+            children: Vec::new(),
+        }
+    }
+
+    pub fn value(mut self, value: String) -> Self {
+        self.value = Some(value);
+        self
+    }
+
+    pub fn checked(mut self, checked: bool) -> Self {
+        self.checked = Some(checked);
+        self
+    }
+
+    pub fn shortcut(mut self, shortcut: Option<Hotkey>) -> Self {
+        self.shortcut = shortcut;
+        self
+    }
+
+    pub fn accelerator(mut self, accelerator: Option<char>) -> Self {
+        self.accelerator = accelerator;
+        self
+    }
+
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    pub fn focusable(&self) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        match self.role {
+            AccessibleRole::Button
+            | AccessibleRole::CheckBox
+            | AccessibleRole::RadioButton
+            | AccessibleRole::Tab
+            | AccessibleRole::Edit
+            | AccessibleRole::Link => true,
+            _ => false,
+        }
+    }
+
+    pub fn get_control(&self) -> Option<Rc<RefCell<dyn GuiControl>>> {
+        self.control.as_ref().and_then(Weak::upgrade)
+    }
+
+    fn flatten<'a>(&'a self, result: &mut Vec<&'a AccessibleNode>) {
+        result.push(self);
+        for child in &self.children {
+            child.flatten(result);
+        }
+    }
+
+    fn collect_focusable(&self, result: &mut Vec<Rc<RefCell<dyn GuiControl>>>) {
+        if self.focusable() {
+            if let Some(control) = self.get_control() {
+                result.push(control);
+            }
+        }
+        for child in &self.children {
+            child.collect_focusable(result);
+        }
+    }
+}
+
+thread_local! {
+    /// Rects of the controls which redrew themselves in the current frame.
+    static DRAWN_RECTS: RefCell<Vec<Rect>> = const { RefCell::new(Vec::new()) };
+}
+
 #[derive(Debug)]
 pub struct GuiControlBase {
     pub(crate) size_constraints: SizeConstraints,
@@ -79,6 +228,7 @@ pub struct GuiControlBase {
     pub visible: bool,
     pub(crate) need_redraw: bool,
     pub(crate) focus: bool,
+    pub(crate) focus_visible: bool,
     pub(crate) highlight: bool,
     pub(crate) pressed: bool,
     pub(crate) rect: Rect,
@@ -94,6 +244,7 @@ impl GuiControlBase {
             visible: true,
             need_redraw: false,
             focus: false,
+            focus_visible: false,
             highlight: false,
             pressed: false,
             rect: Rect::default(),
@@ -104,10 +255,30 @@ impl GuiControlBase {
 
     pub fn can_draw(&mut self, force: bool) -> bool {
         let result = self.visible && (self.need_redraw || force);
+        // a forced control is inside of the rect of the control which forces it
+        if result && !force {
+            DRAWN_RECTS.with(|rects| rects.borrow_mut().push(self.rect));
+        }
         self.need_redraw = false;
         result
     }
 
+    pub(crate) fn layout_size_constraints(&self) -> SizeConstraints {
+        if self.visible {
+            self.current_size_constraints
+        } else {
+            SizeConstraints::default()
+        }
+    }
+
+    pub(crate) fn layout_minimal_size(&self) -> Position {
+        if self.visible {
+            self.minimal_size
+        } else {
+            (0, 0)
+        }
+    }
+
     pub fn set_size_constaints(&mut self, constraints: SizeConstraints) {
         self.size_constraints = constraints;
         self.current_size_constraints = constraints;
@@ -135,22 +306,50 @@ pub enum GuiMessage<'i, 'j> {
     MouseDown(Position),
     MouseMove(Position),
     MouseUp(Position, JobSystem),
-    MouseWheel(Position, i32),
+    MouseWheel(Position, f32),
+    MouseWheelWithShift(Position, f32),
+    MouseHWheel(Position, f32),
+    MiddleDoubleClick(Position),
+    Zoom(Position, f32),
+    Pan(Position, Position),
     Char(char),
     KeyDown(Key, JobSystem, &'i mut bool),
     KeyUp(Key),
     Hotkey(Hotkey, &'i mut bool),
     GetHotkeys(&'i mut HashMap<Hotkey, HotkeyCallback>, bool),
-    Timer,
+    GetAccessibleNodes(&'i mut Vec<AccessibleNode>),
+    /// Every child control adds its node, the hidden ones too.
+    GetDebugNodes(&'i mut Vec<DebugNode>),
+    GetCursor(Position, &'i mut MouseCursor),
+    GetTooltip(Position, &'i mut Option<Tooltip>),
+    /// Asked from the focused control only.
+    GetCompletion(&'i mut Option<CompletionPopup>),
+    /// The candidate of the completion popup is clicked.
+    AcceptCompletion(usize),
+    /// Asked from the focused control only, true for the password inputs.
+    IsMasked(&'i mut bool),
+    Timer(JobSystem),
     Show,
     Hide,
     Create,
     Destroy,
 }
 
+/// Mouse cursor shape over a control.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MouseCursor {
+    Arrow,
+    Hand,
+}
+
 pub trait GuiControl: std::fmt::Debug + 'static {
     fn get_base_mut(&mut self) -> &mut GuiControlBase;
     fn on_message(&mut self, m: GuiMessage) -> bool;
+
+    /// Shown by the inspector.
+    fn get_type_name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
 }
 
 pub(crate) fn avg_color(color1: u32, color2: u32) -> u32 {
@@ -167,6 +366,8 @@ pub struct GuiColorTheme {
     pub selected: u32,
     pub inactive: u32,
     pub edit_focused: u32,
+    /// Border of the inputs with invalid text.
+    pub error: u32,
 }
 
 pub static DARK_THEME: GuiColorTheme = GuiColorTheme {
@@ -178,6 +379,7 @@ pub static DARK_THEME: GuiColorTheme = GuiColorTheme {
     selected: 0x66CC66,
     inactive: 0x666666,
     edit_focused: 0x999999,
+    error: 0xFF5555,
 };
 
 pub static LIGHT_THEME: GuiColorTheme = GuiColorTheme {
@@ -189,19 +391,63 @@ pub static LIGHT_THEME: GuiColorTheme = GuiColorTheme {
     selected: 0xCC8844,
     inactive: 0xBBBBBB,
     edit_focused: 0xEEEEEE,
+    error: 0xCC0000,
 };
 
+/// Place of a root region in the window.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RegionPlacement {
+    /// The space left by the strips, it is used by the main root.
+    Fill,
+    /// Strips of the given height, stacked from the window edge in the order of adding.
+    Top(i32),
+    Bottom(i32),
+    /// Rectangle above the other regions, it is redrawn with every frame.
+    Overlay(Rect),
+}
+
+/// Id of the region of the root set by `GuiSystem::set_root`.
+pub const MAIN_REGION: usize = 0;
+
+/// Independent root of controls with its own layout and invalidation, so changes in one
+/// region don't force redrawing of the others.
+struct GuiRegion {
+    id: usize,
+    placement: RegionPlacement,
+    control: Rc<RefCell<dyn GuiControl>>,
+    rect: Rect,
+    updated: bool,
+}
+
 pub struct GuiSystem {
     job_system: JobSystem,
-    root: Option<Rc<RefCell<dyn GuiControl>>>,
+    regions: Vec<GuiRegion>,
+    last_region_id: usize,
     focus: Option<Weak<RefCell<dyn GuiControl>>>,
     highlight: Option<Weak<RefCell<dyn GuiControl>>>,
     pressed: Option<Weak<RefCell<dyn GuiControl>>>,
     color_theme: GuiColorTheme,
-    updated: bool,
     updated_hotkeys: bool,
     hotkeys: HashMap<Hotkey, HotkeyCallback>,
     global_hotkeys: HashMap<Hotkey, HotkeyCallback>,
+    toasts: Toasts,
+    tooltips: Tooltips,
+    /// Popup of the focused control as it was drawn last time.
+    completion: Option<CompletionPopup>,
+    inspector: Inspector,
+    message_box_font: Option<Font>,
+    /// From the oldest, only the last one gets the clicks.
+    message_boxes: Vec<ActiveMessageBox>,
+    /// Size of the last `on_draw`, the message boxes are centered in it.
+    window_size: Position,
+    /// Tab control whose tabs are focused with Alt and walked with the arrows.
+    menu_bar: Option<Weak<RefCell<dyn GuiControl>>>,
+    /// From the biggest priority, see `on_escape`.
+    escape_handlers: Vec<EscapeHandler>,
+    last_escape_handler_id: usize,
+    /// Parts of the buffer changed by the last `on_draw`.
+    damage: Vec<Rect>,
+    caret: CaretBlink,
 }
 
 macro_rules! set_property {
@@ -213,7 +459,8 @@ macro_rules! set_property {
                 old.get_base_mut().$field = false;
                 if $handle_lose {
                     if old.on_message(GuiMessage::FocusLose(s.job_system.clone())) {
-                        s.updated = false;
+                        let position = old.get_base_mut().rect.left_top;
+                        s.invalidate_region_at(position);
                         s.updated_hotkeys = false;
                     }
                 }
@@ -258,15 +505,27 @@ impl GuiSystem {
     pub fn new(job_system: JobSystem) -> Self {
         Self {
             job_system,
-            root: None,
+            regions: Vec::new(),
+            last_region_id: MAIN_REGION,
             focus: None,
             highlight: None,
             pressed: None,
             color_theme: LIGHT_THEME,
-            updated: false,
             updated_hotkeys: false,
             hotkeys: Default::default(),
             global_hotkeys: Default::default(),
+            toasts: Default::default(),
+            tooltips: Default::default(),
+            completion: None,
+            inspector: Default::default(),
+            message_box_font: None,
+            message_boxes: Vec::new(),
+            window_size: (0, 0),
+            menu_bar: None,
+            escape_handlers: Vec::new(),
+            last_escape_handler_id: 0,
+            damage: Vec::new(),
+            caret: Default::default(),
         }
     }
 
@@ -301,6 +560,21 @@ impl GuiSystem {
         new_focus: Option<Rc<RefCell<dyn GuiControl>>>,
         handle_lose: bool,
     ) -> bool {
+        let changed = self.replace_focus(new_focus, handle_lose);
+        if changed {
+            self.caret.reset();
+        }
+        return changed;
+    }
+
+    fn replace_focus(
+        &mut self,
+        new_focus: Option<Rc<RefCell<dyn GuiControl>>>,
+        handle_lose: bool,
+    ) -> bool {
+        if let Some(old_focus) = self.get_focus() {
+            old_focus.borrow_mut().get_base_mut().focus_visible = false;
+        }
         set_property!(self, new_focus, get_focus, focus, handle_lose);
     }
 
@@ -320,38 +594,432 @@ impl GuiSystem {
         set_property!(self, new_pressed, get_pressed, pressed, false);
     }
 
+    fn layout_regions(&mut self, size: Position) {
+        let mut top = 0;
+        let mut bottom = size.1;
+        let mut rects = Vec::with_capacity(self.regions.len());
+        for region in &self.regions {
+            rects.push(match region.placement {
+                RegionPlacement::Top(height) => {
+                    let height = height.clamp(0, bottom - top);
+                    top += height;
+                    Some(Rect {
+                        left_top: (0, top - height),
+                        right_bottom: (size.0, top),
+                    })
+                }
+                RegionPlacement::Bottom(height) => {
+                    let height = height.clamp(0, bottom - top);
+                    bottom -= height;
+                    Some(Rect {
+                        left_top: (0, bottom),
+                        right_bottom: (size.0, bottom + height),
+                    })
+                }
+                RegionPlacement::Overlay(rect) => Some(Rect {
+                    left_top: (
+                        rect.left_top.0.clamp(0, size.0),
+                        rect.left_top.1.clamp(0, size.1),
+                    ),
+                    right_bottom: (
+                        rect.right_bottom
+                            .0
+                            .clamp(rect.left_top.0.clamp(0, size.0), size.0),
+                        rect.right_bottom
+                            .1
+                            .clamp(rect.left_top.1.clamp(0, size.1), size.1),
+                    ),
+                }),
+                RegionPlacement::Fill => None,
+            });
+        }
+        let fill_rect = Rect {
+            left_top: (0, top),
+            right_bottom: (size.0, bottom),
+        };
+        for (region, rect) in self.regions.iter_mut().zip(rects) {
+            let rect = rect.unwrap_or(fill_rect);
+            if region.rect != rect {
+                region.rect = rect;
+                region.updated = false;
+            }
+        }
+    }
+
     pub fn on_draw(&mut self, draw_context: &mut DrawContext) {
-        if let Some(root) = &self.root {
-            let mut root = root.borrow_mut();
-            if !self.updated {
-                Self::set_rect(
-                    root.deref_mut(),
-                    Rect {
-                        left_top: (0, 0),
-                        right_bottom: image_size_to_position(draw_context.buffer.get_size()),
-                    },
-                );
-                self.updated = true;
+        let size = image_size_to_position(draw_context.buffer.get_size());
+        self.window_size = size;
+        self.update_message_boxes();
+        self.layout_regions(size);
+        let completion = self.get_completion();
+        let completion_changed = match (&completion, &self.completion) {
+            (Some(completion), Some(last)) => !completion.same_as(last),
+            (None, None) => false,
+            _ => true,
+        };
+        self.completion = completion;
+        // closed toasts leave garbage, which is covered by the full redraw
+        let force = self.toasts.take_changed()
+            | self.tooltips.take_changed()
+            | self.inspector.take_changed()
+            | completion_changed;
+        DRAWN_RECTS.with(|rects| rects.borrow_mut().clear());
+        let mut damage = Vec::new();
+        // overlays are drawn last, anything below could paint over them
+        let (overlays, regions): (Vec<_>, Vec<_>) = self
+            .regions
+            .iter_mut()
+            .partition(|region| matches!(region.placement, RegionPlacement::Overlay(_)));
+        for region in regions.into_iter().chain(overlays) {
+            let is_overlay = matches!(region.placement, RegionPlacement::Overlay(_));
+            if is_overlay {
+                damage.push(region.rect);
+            }
+            let mut root = region.control.borrow_mut();
+            if !region.updated {
+                Self::set_rect(root.deref_mut(), region.rect);
+                region.updated = true;
                 root.get_base_mut().need_redraw = true;
             }
-
+            let mut buffer = draw_context.buffer.window_mut(
+                position_to_image_size(region.rect.left_top),
+                position_to_image_size(region.rect.right_bottom),
+            );
             root.on_message(GuiMessage::Draw(
-                &mut draw_context.buffer,
+                &mut buffer,
                 &self.color_theme,
-                false,
+                force || is_overlay,
             ));
         }
+        if let Some(completion) = &self.completion {
+            damage.push(completion.get_rect(size));
+            completion.draw(&mut draw_context.buffer, &self.color_theme);
+        }
+        self.toasts
+            .draw(&mut draw_context.buffer, &self.color_theme);
+        self.tooltips
+            .draw(&mut draw_context.buffer, &self.color_theme);
+        if self.inspector.is_shown() {
+            let roots = self.get_debug_tree();
+            self.inspector.draw(
+                &mut draw_context.buffer,
+                &self.color_theme,
+                &roots,
+                |position| self.get_region_index_at(position),
+            );
+        }
+
+        self.damage = if force {
+            vec![Rect {
+                left_top: (0, 0),
+                right_bottom: size,
+            }]
+        } else {
+            damage.extend(DRAWN_RECTS.with(|rects| rects.take()));
+            damage.retain(|rect| {
+                rect.right_bottom.0 > rect.left_top.0 && rect.right_bottom.1 > rect.left_top.1
+            });
+            damage
+        };
+    }
+
+    /// Parts of the buffer changed by the last `on_draw`, only they have to be copied to
+    /// the screen. The rects can overlap.
+    pub fn get_damage(&self) -> &[Rect] {
+        &self.damage
+    }
+
+    pub fn set_tooltip_font(&mut self, font: Font) {
+        self.tooltips.set_font(font);
+    }
+
+    pub fn set_tooltip_delay(&mut self, delay: Duration) {
+        self.tooltips.set_delay(delay);
+    }
+
+    /// Zero stops the blinking of the caret.
+    pub fn set_caret_blink_period(&mut self, period: Duration) {
+        self.caret.set_period(period);
+    }
+
+    /// The inspector is drawn above all controls, the font has to be set before.
+    pub fn set_inspector_font(&mut self, font: Font) {
+        self.inspector.set_font(font);
+    }
+
+    /// While the inspector is shown, the clicks pick the controls instead of pressing them.
+    pub fn show_inspector(&mut self, shown: bool) {
+        self.inspector.set_shown(shown);
+    }
+
+    pub fn is_inspector_shown(&self) -> bool {
+        self.inspector.is_shown()
+    }
+
+    /// Layout of the root of each region, in the order of adding.
+    pub fn get_debug_tree(&self) -> Vec<DebugNode> {
+        self.regions
+            .iter()
+            .map(|region| DebugNode::new(region.control.borrow_mut().deref_mut()))
+            .collect()
+    }
+
+    /// The hotkey works whatever control is active, before the hotkeys of the controls.
+    pub fn add_global_hotkey(&mut self, hotkey: Hotkey, callback: Rc<dyn Fn() + 'static>) {
+        self.global_hotkeys
+            .insert(hotkey, HotkeyCallback::new(callback));
+    }
+
+    /// Completion popup of the focused input, if its list is open.
+    pub fn get_completion(&self) -> Option<CompletionPopup> {
+        let focus = self.get_focus()?;
+        let mut completion = None;
+        focus
+            .borrow_mut()
+            .on_message(GuiMessage::GetCompletion(&mut completion));
+        completion
+    }
+
+    /// The keys typed into a password input are not written to the input log.
+    pub fn is_masked_input_focused(&self) -> bool {
+        let focus = match self.get_focus() {
+            Some(focus) => focus,
+            None => return false,
+        };
+        let mut masked = false;
+        focus
+            .borrow_mut()
+            .on_message(GuiMessage::IsMasked(&mut masked));
+        return masked;
+    }
+
+    pub fn get_tooltip(&self) -> Option<Tooltip> {
+        self.tooltips.get_shown().cloned()
+    }
+
+    pub fn set_menu_bar(&mut self, menu_bar: &Rc<RefCell<dyn GuiControl>>) {
+        self.menu_bar = Some(Rc::downgrade(menu_bar));
+    }
+
+    /// Toasts are drawn above all controls, the font has to be set before.
+    /// The font has to be set before the message boxes are shown.
+    pub fn set_message_box_font(&mut self, font: Font) {
+        self.message_box_font = Some(font);
+    }
+
+    /// Shows the modal box in the center of the window, the clicks outside of it are
+    /// ignored. The callback gets the index of the pressed button or of the cancel
+    /// button on Escape, it is called once by the job system. Without the font the
+    /// box is not shown and the callback gets the cancel or the default button.
+    pub fn show_message_box(
+        &mut self,
+        message_box: MessageBox,
+        callback: impl Fn(usize) + 'static,
+    ) {
+        let callback = Rc::new(callback);
+        let cancel_button = message_box.get_cancel_button();
+        let font = match &self.message_box_font {
+            Some(font) => font.clone(),
+            None => {
+                let choice = cancel_button.unwrap_or(message_box.get_default_button());
+                self.job_system
+                    .add_callback(Rc::new(move || callback(choice)));
+                return;
+            }
+        };
+        let state = Rc::new(MessageBoxState::default());
+        let message_box = Rc::new(message_box);
+        let choose: Rc<dyn Fn(usize)> = {
+            let state = state.clone();
+            let callback = callback.clone();
+            let message_box = message_box.clone();
+            Rc::new(move |index| {
+                if state.choice.get().is_none() {
+                    state.choice.set(Some(index));
+                    message_box.report_check(&state, index);
+                    callback(index);
+                }
+            })
+        };
+        let built = message_box.build(&font, &self.color_theme, &state, choose);
+        let (_, region_id) =
+            self.add_region(RegionPlacement::Overlay(Rect::default()), built.frame);
+        let escape_handler_id = {
+            let state = state.clone();
+            let job_system = self.job_system.clone();
+            self.add_escape_handler(ESCAPE_PRIORITY_MESSAGE_BOX, move || {
+                // the box is modal, Escape doesn't go under it even without the cancel button
+                let index = match (cancel_button, state.choice.get()) {
+                    (_, Some(_)) => return false,
+                    (None, None) => return true,
+                    (Some(index), None) => index,
+                };
+                state.choice.set(Some(index));
+                let callback = callback.clone();
+                job_system.add_callback(Rc::new(move || callback(index)));
+                true
+            })
+        };
+        let active = ActiveMessageBox {
+            region_id,
+            escape_handler_id,
+            state,
+            collapsed_size: built.collapsed_size,
+            expanded_size: built.expanded_size,
+        };
+        self.set_region_placement(
+            region_id,
+            RegionPlacement::Overlay(active.get_rect(self.window_size)),
+        );
+        self.message_boxes.push(active);
+        if let Some(button) = built.default_button {
+            self.focus_control(&button);
+        }
+    }
+
+    pub fn has_message_box(&self) -> bool {
+        !self.message_boxes.is_empty()
+    }
+
+    /// Closes the chosen boxes and moves the others to the center of the window.
+    fn update_message_boxes(&mut self) {
+        let (closed, open): (Vec<_>, Vec<_>) = std::mem::take(&mut self.message_boxes)
+            .into_iter()
+            .partition(|active| active.state.choice.get().is_some());
+        for active in closed {
+            self.remove_region(active.region_id);
+            self.remove_escape_handler(active.escape_handler_id);
+        }
+        for active in &open {
+            self.set_region_placement(
+                active.region_id,
+                RegionPlacement::Overlay(active.get_rect(self.window_size)),
+            );
+        }
+        self.message_boxes = open;
+    }
+
+    pub fn set_toast_font(&mut self, font: Font) {
+        self.toasts.set_font(font);
+    }
+
+    pub fn show_toast(&mut self, toast: Toast) {
+        self.toasts.show(toast);
+    }
+
+    pub fn get_toasts(&self) -> Vec<Toast> {
+        self.toasts.get_toasts()
+    }
+
+    /// A long task is running, the input has to be blocked until it is done.
+    pub fn is_busy(&self) -> bool {
+        return self.job_system.is_busy();
+    }
+
+    pub fn has_tasks(&self) -> bool {
+        return self.job_system.has_tasks();
+    }
+
+    pub fn cancel_tasks(&self) {
+        self.job_system.cancel_tasks();
+    }
+
+    /// Adds a link to the chain of `on_escape`, handlers with bigger priority are asked
+    /// first, equal priorities go in the order of adding. The handler must not use the
+    /// gui system, it is called while the input is dispatched.
+    pub fn add_escape_handler(
+        &mut self,
+        priority: i32,
+        handler: impl Fn() -> bool + 'static,
+    ) -> usize {
+        self.last_escape_handler_id += 1;
+        let index = self
+            .escape_handlers
+            .iter()
+            .position(|escape_handler| escape_handler.priority < priority)
+            .unwrap_or(self.escape_handlers.len());
+        self.escape_handlers.insert(
+            index,
+            EscapeHandler {
+                id: self.last_escape_handler_id,
+                priority,
+                handler: Rc::new(handler),
+            },
+        );
+        self.last_escape_handler_id
+    }
+
+    pub fn remove_escape_handler(&mut self, id: usize) {
+        self.escape_handlers
+            .retain(|escape_handler| escape_handler.id != id);
+    }
+
+    /// Escape which neither the hotkeys nor the focused control took: the running tasks,
+    /// the background ones too, are cancelled, otherwise the handlers are asked one by one
+    /// until one of them cancels something, so each press cancels one thing.
+    pub fn on_escape(&mut self) -> bool {
+        if self.has_tasks() {
+            self.cancel_tasks();
+            return true;
+        }
+        // handlers can add or remove handlers
+        let handlers: Vec<_> = self
+            .escape_handlers
+            .iter()
+            .map(|escape_handler| escape_handler.handler.clone())
+            .collect();
+        for handler in handlers {
+            if handler() {
+                // the handler can change any region
+                self.invalidate_all();
+                self.updated_hotkeys = false;
+                return true;
+            }
+        }
+        false
     }
 
     pub fn on_timer(&mut self) -> bool {
-        if let Some(root) = &self.root {
-            let mut root = root.borrow_mut();
-            if root.on_message(GuiMessage::Timer) {
+        let mut result = self.toasts.on_timer() | binding::take_redraw_request();
+        if self.caret.on_timer() {
+            if let Some(focus) = self.get_focus() {
+                GuiSystem::mark_to_redraw(&focus);
+                result = true;
+            }
+        }
+        if let Some(position) = self.tooltips.take_due_position() {
+            result |= self.show_tooltip(position);
+        }
+        for region in &self.regions {
+            let mut root = region.control.borrow_mut();
+            if root.on_message(GuiMessage::Timer(self.job_system.clone())) {
                 root.get_base_mut().need_redraw = true;
-                return true;
+                result = true;
             }
         }
-        return false;
+        return result;
+    }
+
+    /// Asks the control under the mouse for its tooltip.
+    fn show_tooltip(&mut self, position: Position) -> bool {
+        if self.get_pressed().is_some() {
+            return false;
+        }
+        let handler = match self.get_root_at(position) {
+            Some(root) => Self::get_child(&root, position),
+            None => return false,
+        };
+        let mut tooltip = None;
+        handler
+            .borrow_mut()
+            .on_message(GuiMessage::GetTooltip(position, &mut tooltip));
+        match tooltip {
+            Some(tooltip) => {
+                self.tooltips.show(tooltip, position);
+                true
+            }
+            None => false,
+        }
     }
 
     pub fn set_color_theme(&mut self, color_theme: GuiColorTheme) {
@@ -376,21 +1044,105 @@ impl GuiSystem {
     }
 
     pub fn on_resize(&mut self) {
-        self.updated = false;
+        self.invalidate_all();
     }
 
+    /// Minimal size of the main root with the strips around it.
     pub fn get_minimal_size_of_system(&self) -> Position {
-        if let Some(root) = &self.root {
-            let mut root = root.borrow_mut();
-            root.on_message(GuiMessage::UpdateSizeConstraints);
-            root.get_base_mut().minimal_size
-        } else {
-            (0, 0)
+        let mut result = (0, 0);
+        for region in &self.regions {
+            let minimal_size = {
+                let mut root = region.control.borrow_mut();
+                root.on_message(GuiMessage::UpdateSizeConstraints);
+                root.get_base_mut().minimal_size
+            };
+            match region.placement {
+                RegionPlacement::Fill => {
+                    result.0 = result.0.max(minimal_size.0);
+                    result.1 += minimal_size.1;
+                }
+                RegionPlacement::Top(height) | RegionPlacement::Bottom(height) => {
+                    result.0 = result.0.max(minimal_size.0);
+                    result.1 += height;
+                }
+                RegionPlacement::Overlay(_) => {}
+            }
+        }
+        result
+    }
+
+    fn get_region_index_at(&self, position: Position) -> Option<usize> {
+        let is_overlay =
+            |region: &GuiRegion| matches!(region.placement, RegionPlacement::Overlay(_));
+        let contains = |region: &GuiRegion| region.rect.contains(position);
+        self.regions
+            .iter()
+            .rposition(|region| is_overlay(region) && contains(region))
+            .or_else(|| {
+                self.regions
+                    .iter()
+                    .position(|region| !is_overlay(region) && contains(region))
+            })
+    }
+
+    fn get_root_at(&self, position: Position) -> Option<Rc<RefCell<dyn GuiControl>>> {
+        let index = self.get_region_index_at(position)?;
+        Some(self.regions[index].control.clone())
+    }
+
+    /// The region under the position gets the new layout and is fully redrawn.
+    fn invalidate_region_at(&mut self, position: Position) {
+        match self.get_region_index_at(position) {
+            Some(index) => self.regions[index].updated = false,
+            None => self.invalidate_all(),
+        }
+    }
+
+    fn invalidate_region_of(&mut self, control: &Rc<RefCell<dyn GuiControl>>) {
+        let position = control.borrow_mut().get_base_mut().rect.left_top;
+        self.invalidate_region_at(position);
+    }
+
+    pub fn invalidate_region(&mut self, id: usize) {
+        if let Some(region) = self.regions.iter_mut().find(|region| region.id == id) {
+            region.updated = false;
+        }
+    }
+
+    pub fn invalidate_all(&mut self) {
+        for region in &mut self.regions {
+            region.updated = false;
         }
     }
 
     pub fn on_mouse_down(&mut self, position: Position) -> bool {
-        if let Some(root) = &self.root {
+        self.tooltips.cancel();
+        if self.inspector.on_mouse_down(position) {
+            return true;
+        }
+        if let Some(active) = self.message_boxes.last() {
+            if !active.get_rect(self.window_size).contains(position) {
+                return false;
+            }
+        }
+        if let Some(action) = self.toasts.on_mouse_down(position) {
+            if let Some(action) = action {
+                self.job_system.add_callback(action);
+            }
+            return true;
+        }
+        let clicked_candidate = self
+            .completion
+            .as_ref()
+            .and_then(|completion| completion.get_index_at(self.window_size, position));
+        if let (Some(index), Some(focus)) = (clicked_candidate, self.get_focus()) {
+            focus
+                .borrow_mut()
+                .on_message(GuiMessage::AcceptCompletion(index));
+            GuiSystem::mark_to_redraw(&focus);
+            return true;
+        }
+        if let Some(root) = self.get_root_at(position) {
             let child = Self::get_child(&root, position);
             if child
                 .borrow_mut()
@@ -398,6 +1150,7 @@ impl GuiSystem {
             {
                 let changed_focus = self.set_focus(Some(child.clone()), true);
                 let changed_pressed = self.set_pressed(Some(child.clone()));
+                self.caret.restart();
                 GuiSystem::mark_to_redraw(&child);
                 return changed_focus || changed_pressed;
             } else {
@@ -408,13 +1161,17 @@ impl GuiSystem {
     }
 
     pub fn on_mouse_move(&mut self, position: Position) -> bool {
-        if let Some(root) = &self.root {
-            let maybe_pressed = self.get_pressed();
-            let handler = if let Some(pressed) = &maybe_pressed {
-                pressed.clone()
-            } else {
-                Self::get_child(&root, position)
-            };
+        if self.inspector.on_mouse_move(position) {
+            return true;
+        }
+        let hidden_tooltip = self.tooltips.on_mouse_move(position);
+        let maybe_handler = match self.get_pressed() {
+            Some(pressed) => Some(pressed),
+            None => self
+                .get_root_at(position)
+                .map(|root| Self::get_child(&root, position)),
+        };
+        if let Some(handler) = maybe_handler {
             let handled = handler
                 .borrow_mut()
                 .on_message(GuiMessage::MouseMove(position));
@@ -422,23 +1179,68 @@ impl GuiSystem {
             if handled {
                 GuiSystem::mark_to_redraw(&handler);
             }
-            return handled || changed_highlight;
+            return handled || changed_highlight || hidden_tooltip;
         }
 
-        return false;
+        return hidden_tooltip;
     }
 
-    pub fn on_mouse_wheel(&mut self, position: Position, delta: i32) -> bool {
-        if let Some(root) = &self.root {
-            let maybe_pressed = self.get_pressed();
-            let handler = if let Some(pressed) = &maybe_pressed {
-                pressed.clone()
-            } else {
-                Self::get_child(&root, position)
-            };
-            let handled = handler
+    /// Cursor shape for the mouse at the position, asked from the control under it or
+    /// from the pressed one.
+    pub fn get_cursor(&self, position: Position) -> MouseCursor {
+        let mut cursor = MouseCursor::Arrow;
+        let maybe_handler = match self.get_pressed() {
+            Some(pressed) => Some(pressed),
+            None => self
+                .get_root_at(position)
+                .map(|root| Self::get_child(&root, position)),
+        };
+        if let Some(handler) = maybe_handler {
+            handler
                 .borrow_mut()
-                .on_message(GuiMessage::MouseWheel(position, delta));
+                .on_message(GuiMessage::GetCursor(position, &mut cursor));
+        }
+        cursor
+    }
+
+    /// Delta is measured in wheel notches and can be fractional for precise touchpads.
+    pub fn on_mouse_wheel(&mut self, position: Position, delta: f32) -> bool {
+        return self.send_positioned_message(position, GuiMessage::MouseWheel(position, delta));
+    }
+
+    /// Wheel turned with Shift held.
+    pub fn on_mouse_wheel_with_shift(&mut self, position: Position, delta: f32) -> bool {
+        return self
+            .send_positioned_message(position, GuiMessage::MouseWheelWithShift(position, delta));
+    }
+
+    pub fn on_middle_double_click(&mut self, position: Position) -> bool {
+        return self.send_positioned_message(position, GuiMessage::MiddleDoubleClick(position));
+    }
+
+    pub fn on_mouse_hwheel(&mut self, position: Position, delta: f32) -> bool {
+        return self.send_positioned_message(position, GuiMessage::MouseHWheel(position, delta));
+    }
+
+    /// Pinch gesture, factor is the ratio of the new and the old distance between fingers.
+    pub fn on_zoom(&mut self, position: Position, factor: f32) -> bool {
+        return self.send_positioned_message(position, GuiMessage::Zoom(position, factor));
+    }
+
+    /// Two-finger drag, delta is the movement of the fingers since the last message.
+    pub fn on_pan(&mut self, position: Position, delta: Position) -> bool {
+        return self.send_positioned_message(position, GuiMessage::Pan(position, delta));
+    }
+
+    fn send_positioned_message(&mut self, position: Position, m: GuiMessage) -> bool {
+        let maybe_handler = match self.get_pressed() {
+            Some(pressed) => Some(pressed),
+            None => self
+                .get_root_at(position)
+                .map(|root| Self::get_child(&root, position)),
+        };
+        if let Some(handler) = maybe_handler {
+            let handled = handler.borrow_mut().on_message(m);
             if handled {
                 GuiSystem::mark_to_redraw(&handler);
             }
@@ -449,29 +1251,34 @@ impl GuiSystem {
     }
 
     pub fn on_mouse_leave(&mut self) -> bool {
-        return self.set_highlight(None);
+        let hidden_tooltip = self.tooltips.cancel();
+        return self.set_highlight(None) || hidden_tooltip;
     }
 
     pub fn on_deactivate(&mut self) -> bool {
+        let hidden_tooltip = self.tooltips.cancel();
         let changed_highlight = self.set_highlight(None);
         let changed_pressed = self.set_pressed(None);
         let changed_focus = self.set_focus(None, false);
-        return changed_highlight || changed_pressed || changed_focus;
+        return changed_highlight || changed_pressed || changed_focus || hidden_tooltip;
     }
 
     pub fn on_mouse_up(&mut self, position: Position) -> bool {
-        if let Some(root) = &self.root {
-            let maybe_pressed = self.get_pressed();
-            let handler = if let Some(pressed) = &maybe_pressed {
-                pressed.clone()
-            } else {
-                Self::get_child(&root, position)
-            };
+        if self.toasts.on_mouse_up() || self.inspector.on_mouse_up() {
+            return true;
+        }
+        let maybe_handler = match self.get_pressed() {
+            Some(pressed) => Some(pressed),
+            None => self
+                .get_root_at(position)
+                .map(|root| Self::get_child(&root, position)),
+        };
+        if let Some(handler) = maybe_handler {
             if handler
                 .borrow_mut()
                 .on_message(GuiMessage::MouseUp(position, self.job_system.clone()))
             {
-                self.updated = false;
+                self.invalidate_region_of(&handler);
                 self.updated_hotkeys = false;
                 GuiSystem::mark_to_redraw(&handler);
                 return self.set_pressed(None);
@@ -484,6 +1291,7 @@ impl GuiSystem {
         if let Some(focus) = self.get_focus() {
             let handled = focus.borrow_mut().on_message(GuiMessage::Char(c));
             if handled {
+                self.caret.restart();
                 GuiSystem::mark_to_redraw(&focus);
             }
             return handled;
@@ -501,12 +1309,26 @@ impl GuiSystem {
                 &mut unfocus,
             ));
             if unfocus {
-                self.updated = false;
+                self.invalidate_region_of(&focus);
                 self.updated_hotkeys = false;
                 self.set_focus(None, false);
             }
             if handled {
+                self.caret.restart();
                 GuiSystem::mark_to_redraw(&focus);
+            } else if self.navigate_menu(&focus, k) {
+                return true;
+            } else if k == Key::Space || k == Key::Enter {
+                let mut nodes = Vec::new();
+                focus
+                    .borrow_mut()
+                    .on_message(GuiMessage::GetAccessibleNodes(&mut nodes));
+                let activatable = nodes.first().map_or(false, |node| {
+                    node.focusable() && node.role != AccessibleRole::Edit
+                });
+                if activatable {
+                    return self.activate_control(&focus);
+                }
             }
             return handled;
         }
@@ -514,16 +1336,268 @@ impl GuiSystem {
         return false;
     }
 
+    pub fn get_accessible_nodes(&self) -> Vec<AccessibleNode> {
+        let mut nodes = Vec::new();
+        for region in &self.regions {
+            region
+                .control
+                .borrow_mut()
+                .on_message(GuiMessage::GetAccessibleNodes(&mut nodes));
+        }
+        nodes
+    }
+
+    pub fn focus_control(&mut self, control: &Rc<RefCell<dyn GuiControl>>) -> bool {
+        let changed_focus = self.set_focus(Some(control.clone()), true);
+        control.borrow_mut().get_base_mut().focus_visible = true;
+        GuiSystem::mark_to_redraw(control);
+        changed_focus
+    }
+
+    pub fn activate_control(&mut self, control: &Rc<RefCell<dyn GuiControl>>) -> bool {
+        let rect = control.borrow_mut().get_base_mut().rect;
+        let position = (
+            (rect.left_top.0 + rect.right_bottom.0) / 2,
+            (rect.left_top.1 + rect.right_bottom.1) / 2,
+        );
+        if !control
+            .borrow_mut()
+            .on_message(GuiMessage::MouseDown(position))
+        {
+            return false;
+        }
+
+        self.focus_control(control);
+        self.set_pressed(Some(control.clone()));
+        control
+            .borrow_mut()
+            .on_message(GuiMessage::MouseUp(position, self.job_system.clone()));
+        self.set_pressed(None);
+        self.invalidate_region_of(control);
+        self.updated_hotkeys = false;
+        return true;
+    }
+
+    pub fn move_focus(&mut self, forward: bool) -> bool {
+        let mut focusable = Vec::new();
+        for node in &self.get_accessible_nodes() {
+            node.collect_focusable(&mut focusable);
+        }
+
+        if focusable.is_empty() {
+            return false;
+        }
+
+        let count = focusable.len();
+        let current = self.get_focus().and_then(|focus| {
+            focusable.iter().position(|control| {
+                std::ptr::eq(
+                    Rc::as_ptr(control) as *const u8,
+                    Rc::as_ptr(&focus) as *const u8,
+                )
+            })
+        });
+        let next = match (current, forward) {
+            (Some(index), true) => (index + 1) % count,
+            (Some(index), false) => (index + count - 1) % count,
+            (None, true) => 0,
+            (None, false) => count - 1,
+        };
+
+        let control = focusable[next].clone();
+        self.focus_control(&control);
+        return true;
+    }
+
+    fn is_same_control(a: &Rc<RefCell<dyn GuiControl>>, b: &Rc<RefCell<dyn GuiControl>>) -> bool {
+        std::ptr::eq(Rc::as_ptr(a) as *const u8, Rc::as_ptr(b) as *const u8)
+    }
+
+    fn clear_focus(&mut self) -> bool {
+        match self.get_focus() {
+            Some(focus) => {
+                self.invalidate_region_of(&focus);
+                self.updated_hotkeys = false;
+                self.set_focus(None, true)
+            }
+            None => false,
+        }
+    }
+
+    /// Activates the control with the accelerator, when several controls share it the
+    /// focus goes to the next of them instead.
+    pub fn on_accelerator(&mut self, c: char) -> bool {
+        let c = match c.to_lowercase().next() {
+            Some(c) => c,
+            None => return false,
+        };
+        // overlays are above the other regions, their controls go first
+        let mut nodes = Vec::new();
+        for region in self.regions.iter().rev() {
+            region
+                .control
+                .borrow_mut()
+                .on_message(GuiMessage::GetAccessibleNodes(&mut nodes));
+        }
+        let mut flat = Vec::new();
+        for node in &nodes {
+            node.flatten(&mut flat);
+        }
+        let matching: Vec<_> = flat
+            .iter()
+            .filter(|node| node.accelerator == Some(c) && node.focusable())
+            .filter_map(|node| node.get_control())
+            .collect();
+
+        match matching.len() {
+            0 => return false,
+            1 => return self.activate_control(&matching[0]),
+            count => {
+                let next = self
+                    .get_focus()
+                    .and_then(|focus| {
+                        matching
+                            .iter()
+                            .position(|control| Self::is_same_control(control, &focus))
+                    })
+                    .map_or(0, |index| (index + 1) % count);
+                self.focus_control(&matching[next]);
+                return true;
+            }
+        }
+    }
+
+    /// Focusable nodes of the menu bar: the tabs, then the items of the selected tab.
+    fn get_menu_nodes(&self) -> Vec<AccessibleNode> {
+        let menu_bar = match self.menu_bar.as_ref().and_then(Weak::upgrade) {
+            Some(menu_bar) => menu_bar,
+            None => return Vec::new(),
+        };
+        let mut nodes = Vec::new();
+        menu_bar
+            .borrow_mut()
+            .on_message(GuiMessage::GetAccessibleNodes(&mut nodes));
+        let mut flat = Vec::new();
+        for node in &nodes {
+            node.flatten(&mut flat);
+        }
+        flat.into_iter()
+            .filter(|node| node.focusable() && node.control.is_some())
+            .map(|node| AccessibleNode {
+                children: Vec::new(),
+                ..node.clone()
+            })
+            .collect()
+    }
+
+    fn get_selected_menu_tab(nodes: &[AccessibleNode]) -> Option<Rc<RefCell<dyn GuiControl>>> {
+        let mut tabs = nodes.iter().filter(|node| node.role == AccessibleRole::Tab);
+        let first = tabs.clone().next();
+        tabs.find(|node| node.checked == Some(true))
+            .or(first)
+            .and_then(AccessibleNode::get_control)
+    }
+
+    /// Alt alone moves the focus to the menu bar, or back out of it.
+    pub fn on_menu_key(&mut self) -> bool {
+        let nodes = self.get_menu_nodes();
+        let focus = self.get_focus();
+        let in_menu = focus.as_ref().map_or(false, |focus| {
+            nodes.iter().any(|node| {
+                node.get_control()
+                    .map_or(false, |control| Self::is_same_control(&control, focus))
+            })
+        });
+        if in_menu {
+            return self.clear_focus();
+        }
+        match Self::get_selected_menu_tab(&nodes) {
+            Some(tab) => {
+                self.focus_control(&tab);
+                return true;
+            }
+            None => return false,
+        }
+    }
+
+    /// Arrows walk the menu bar: left and right switch the tabs, down enters the items of
+    /// the tab, Escape goes back to the tab and then out of the menu.
+    fn navigate_menu(&mut self, focus: &Rc<RefCell<dyn GuiControl>>, k: Key) -> bool {
+        let nodes = self.get_menu_nodes();
+        let index = match nodes.iter().position(|node| {
+            node.get_control()
+                .map_or(false, |control| Self::is_same_control(&control, focus))
+        }) {
+            Some(index) => index,
+            None => return false,
+        };
+        let (tabs, items): (Vec<_>, Vec<_>) = nodes
+            .iter()
+            .partition(|node| node.role == AccessibleRole::Tab);
+
+        if nodes[index].role == AccessibleRole::Tab {
+            let tab_index = tabs
+                .iter()
+                .position(|node| std::ptr::eq(*node, &nodes[index]))
+                .unwrap_or(0);
+            let count = tabs.len();
+            let target = match k {
+                Key::Left => tabs[(tab_index + count - 1) % count].get_control(),
+                Key::Right => tabs[(tab_index + 1) % count].get_control(),
+                Key::Down => items.first().and_then(|node| node.get_control()),
+                Key::Escape => return self.clear_focus(),
+                _ => return false,
+            };
+            match (k, target) {
+                (Key::Down, Some(item)) => {
+                    self.focus_control(&item);
+                }
+                (_, Some(tab)) => {
+                    self.activate_control(&tab);
+                }
+                (_, None) => {}
+            }
+            return true;
+        }
+
+        let item_index = items
+            .iter()
+            .position(|node| std::ptr::eq(*node, &nodes[index]))
+            .unwrap_or(0);
+        let target = match k {
+            Key::Down | Key::Right => items.get(item_index + 1).or(items.first()),
+            Key::Up | Key::Left if item_index > 0 => items.get(item_index - 1),
+            Key::Up | Key::Escape => None,
+            _ => return false,
+        };
+        let target = match target {
+            Some(node) => node.get_control(),
+            None => Self::get_selected_menu_tab(&nodes),
+        };
+        if let Some(target) = target {
+            self.focus_control(&target);
+        }
+        return true;
+    }
+
     pub fn on_hotkey(&mut self, k: Hotkey) -> bool {
         if !self.updated_hotkeys {
             self.hotkeys.clear();
-            if let Some(root) = &self.root {
-                root.borrow_mut()
+            for region in &self.regions {
+                region
+                    .control
+                    .borrow_mut()
                     .on_message(GuiMessage::GetHotkeys(&mut self.hotkeys, true));
             }
             self.updated_hotkeys = true;
         }
 
+        // Tab takes the candidate of the open completion popup
+        let completes = |s: &Self| !k.shift && s.get_completion().is_some();
+        if k.key == Key::Tab && !k.ctrl && !k.alt && !completes(self) {
+            return self.move_focus(!k.shift);
+        }
+
         if let Some(focus) = self.get_focus() {
             let mut use_default_keydown = false;
             if focus
@@ -543,7 +1617,8 @@ impl GuiSystem {
             self.global_hotkeys.get(&k).or_else(|| self.hotkeys.get(&k))
         {
             self.job_system.add_callback(callback.clone());
-            self.updated = false;
+            // the callback can change any region
+            self.invalidate_all();
             self.updated_hotkeys = false;
             return true;
         }
@@ -572,15 +1647,17 @@ impl GuiSystem {
         (untyped, typed)
     }
 
-    pub fn set_root<Control: GuiControl>(&mut self, control: Control) -> Rc<RefCell<Control>> {
-        if let Some(root) = &self.root {
-            let mut root = root.borrow_mut();
-            if root.get_base_mut().visible {
-                root.on_message(GuiMessage::Hide);
-            }
-            root.on_message(GuiMessage::Destroy);
+    fn destroy_root(root: &Rc<RefCell<dyn GuiControl>>) {
+        let mut root = root.borrow_mut();
+        if root.get_base_mut().visible {
+            root.on_message(GuiMessage::Hide);
         }
+        root.on_message(GuiMessage::Destroy);
+    }
 
+    fn create_root<Control: GuiControl>(
+        control: Control,
+    ) -> (Rc<RefCell<dyn GuiControl>>, Rc<RefCell<Control>>) {
         let (untyped, typed) = Self::create_rc_by_control(control);
         {
             let mut untyped = untyped.borrow_mut();
@@ -589,10 +1666,88 @@ impl GuiSystem {
             }
             untyped.on_message(GuiMessage::Create);
         }
-        self.root = Some(untyped);
+        (untyped, typed)
+    }
+
+    /// Replaces the main root, the other regions stay.
+    pub fn set_root<Control: GuiControl>(&mut self, control: Control) -> Rc<RefCell<Control>> {
+        let (untyped, typed) = Self::create_root(control);
+        let region = GuiRegion {
+            id: MAIN_REGION,
+            placement: RegionPlacement::Fill,
+            control: untyped,
+            rect: Rect::default(),
+            updated: false,
+        };
+        match self
+            .regions
+            .iter()
+            .position(|region| region.id == MAIN_REGION)
+        {
+            Some(index) => {
+                Self::destroy_root(&self.regions[index].control);
+                self.regions[index] = region;
+            }
+            None => self.regions.insert(0, region),
+        }
         typed
     }
 
+    /// Adds an independent root, returns it with the id of the region.
+    pub fn add_region<Control: GuiControl>(
+        &mut self,
+        placement: RegionPlacement,
+        control: Control,
+    ) -> (Rc<RefCell<Control>>, usize) {
+        let (untyped, typed) = Self::create_root(control);
+        self.last_region_id += 1;
+        self.regions.push(GuiRegion {
+            id: self.last_region_id,
+            placement,
+            control: untyped,
+            rect: Rect::default(),
+            updated: false,
+        });
+        (typed, self.last_region_id)
+    }
+
+    pub fn remove_region(&mut self, id: usize) {
+        if let Some(index) = self.regions.iter().position(|region| region.id == id) {
+            let region = self.regions.remove(index);
+            Self::destroy_root(&region.control);
+            // the space of the region is given to the others
+            self.invalidate_all();
+            self.updated_hotkeys = false;
+        }
+    }
+
+    /// Removes all regions except the main one.
+    pub fn remove_regions(&mut self) {
+        let ids: Vec<_> = self
+            .regions
+            .iter()
+            .map(|region| region.id)
+            .filter(|id| *id != MAIN_REGION)
+            .collect();
+        for id in ids {
+            self.remove_region(id);
+        }
+    }
+
+    pub fn set_region_placement(&mut self, id: usize, placement: RegionPlacement) {
+        if let Some(region) = self.regions.iter_mut().find(|region| region.id == id) {
+            if region.placement != placement {
+                region.placement = placement;
+                self.invalidate_all();
+            }
+        }
+    }
+
+    pub fn get_region_rect(&self, id: usize) -> Option<Rect> {
+        let region = self.regions.iter().find(|region| region.id == id)?;
+        Some(region.rect)
+    }
+
     pub fn default_size(text: &str, hotkey: Option<Hotkey>, font: &Font) -> SizeConstraints {
         let text_size = font.get_size(text);
         if let Some(hotkey) = hotkey {
@@ -0,0 +1,224 @@
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use crate::font::*;
+use crate::gui::*;
+use crate::image::*;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ToastKind {
+    Info,
+    Warning,
+    Error,
+}
+
+impl ToastKind {
+    fn get_default_duration(self) -> Duration {
+        match self {
+            ToastKind::Info => Duration::from_secs(3),
+            ToastKind::Warning => Duration::from_secs(6),
+            ToastKind::Error => Duration::from_secs(10),
+        }
+    }
+
+    fn get_color(self, color_theme: &GuiColorTheme) -> u32 {
+        match self {
+            ToastKind::Info => color_theme.selected,
+            ToastKind::Warning => 0xDDAA00,
+            ToastKind::Error => 0xDD3333,
+        }
+    }
+}
+
+/// Non-modal message in the corner of the window, which disappears by itself.
+#[derive(Clone)]
+pub struct Toast {
+    kind: ToastKind,
+    text: String,
+    duration: Duration,
+    action: Option<Rc<dyn Fn() + 'static>>,
+}
+
+impl std::fmt::Debug for Toast {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Toast")
+            .field("kind", &self.kind)
+            .field("text", &self.text)
+            .field("duration", &self.duration)
+            .finish()
+    }
+}
+
+impl Toast {
+    pub fn new(kind: ToastKind, text: &str) -> Self {
+        Self {
+            kind,
+            text: text.to_string(),
+            duration: kind.get_default_duration(),
+            action: None,
+        }
+    }
+
+    pub fn info(text: &str) -> Self {
+        Self::new(ToastKind::Info, text)
+    }
+
+    pub fn warning(text: &str) -> Self {
+        Self::new(ToastKind::Warning, text)
+    }
+
+    pub fn error(text: &str) -> Self {
+        Self::new(ToastKind::Error, text)
+    }
+
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// Called when the toast is clicked, the toast is closed anyway.
+    pub fn action(mut self, action: impl Fn() + 'static) -> Self {
+        self.action = Some(Rc::new(action));
+        self
+    }
+
+    pub fn get_kind(&self) -> ToastKind {
+        self.kind
+    }
+
+    pub fn get_text(&self) -> &str {
+        &self.text
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct ActiveToast {
+    toast: Toast,
+    shown: Instant,
+    rect: Rect,
+}
+
+/// Toasts shown at once, the oldest ones are closed to show new.
+const MAX_TOASTS: usize = 5;
+
+#[derive(Debug, Default)]
+pub(crate) struct Toasts {
+    active: Vec<ActiveToast>,
+    font: Option<Font>,
+    changed: bool,
+    pressed: bool,
+}
+
+impl Toasts {
+    pub(crate) fn set_font(&mut self, font: Font) {
+        self.font = Some(font);
+        self.changed = true;
+    }
+
+    pub(crate) fn show(&mut self, toast: Toast) {
+        if self.active.len() >= MAX_TOASTS {
+            self.active.remove(0);
+        }
+        self.active.push(ActiveToast {
+            toast,
+            shown: Instant::now(),
+            rect: Rect::default(),
+        });
+        self.changed = true;
+    }
+
+    pub(crate) fn get_toasts(&self) -> Vec<Toast> {
+        self.active.iter().map(|t| t.toast.clone()).collect()
+    }
+
+    /// Closes expired toasts, returns true if the window has to be repainted.
+    pub(crate) fn on_timer(&mut self) -> bool {
+        let count = self.active.len();
+        self.active.retain(|t| t.shown.elapsed() < t.toast.duration);
+        if self.active.len() != count {
+            self.changed = true;
+        }
+        self.changed
+    }
+
+    /// Returns the toast action, if the toast under the position was clicked.
+    pub(crate) fn on_mouse_down(
+        &mut self,
+        position: Position,
+    ) -> Option<Option<Rc<dyn Fn() + 'static>>> {
+        let index = self.active.iter().position(|t| t.rect.contains(position))?;
+        let closed = self.active.remove(index);
+        self.changed = true;
+        self.pressed = true;
+        Some(closed.toast.action)
+    }
+
+    /// The mouse up after a click on a toast does not go to the controls under it.
+    pub(crate) fn on_mouse_up(&mut self) -> bool {
+        std::mem::replace(&mut self.pressed, false)
+    }
+
+    /// Returns true once after the toasts were changed, then the controls under them
+    /// have to be redrawn.
+    pub(crate) fn take_changed(&mut self) -> bool {
+        std::mem::replace(&mut self.changed, false)
+    }
+
+    /// Toasts are stacked from the bottom right corner upwards, the newest at the bottom.
+    pub(crate) fn draw(&mut self, buf: &mut ImageViewMut<u32>, color_theme: &GuiColorTheme) {
+        let font = match &self.font {
+            Some(font) => font.clone(),
+            None => return,
+        };
+        let buf_size = buf.get_size();
+        let line_height = font.get_size("8").1 as i32;
+        let padding = line_height / 2;
+        let border = 2;
+        let mut bottom = buf_size.1 as i32 - padding;
+        for active in self.active.iter_mut().rev() {
+            let lines: Vec<_> = active.toast.text.lines().collect();
+            let text_width = lines
+                .iter()
+                .map(|line| font.get_size(line).0 as i32)
+                .max()
+                .unwrap_or(0);
+            let width = (text_width + padding * 2).min(buf_size.0 as i32 - padding * 2);
+            let height = line_height * lines.len().max(1) as i32 + padding * 2;
+            let right = buf_size.0 as i32 - padding;
+            let rect = Rect {
+                left_top: (right - width, bottom - height),
+                right_bottom: (right, bottom),
+            };
+            bottom = rect.left_top.1 - padding / 2;
+            if rect.left_top.0 < 0 || rect.left_top.1 < 0 {
+                active.rect = Rect::default();
+                continue;
+            }
+            active.rect = rect;
+
+            let mut toast_buf = buf.window_mut(
+                position_to_image_size(rect.left_top),
+                position_to_image_size(rect.right_bottom),
+            );
+            let kind_color = active.toast.kind.get_color(color_theme);
+            toast_buf.fill(|p| *p = kind_color);
+            toast_buf
+                .window_mut(
+                    (border as usize, border as usize),
+                    ((width - border) as usize, (height - border) as usize),
+                )
+                .fill(|p| *p = color_theme.background);
+            let text_font = font
+                .color(color_theme.font)
+                .layout_vertical(TextLayoutVertical::TOP)
+                .layout_horizontal(TextLayoutHorizontal::LEFT);
+            for (index, line) in lines.iter().enumerate() {
+                text_font.draw(
+                    line,
+                    (padding, padding + line_height * index as i32),
+                    &mut toast_buf,
+                );
+            }
+        }
+    }
+}
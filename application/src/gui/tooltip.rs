@@ -0,0 +1,194 @@
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use crate::font::*;
+use crate::gui::*;
+use crate::image::*;
+
+/// Time the mouse has to rest over a control before its tooltip is shown.
+const TOOLTIP_DELAY: Duration = Duration::from_millis(600);
+
+/// Renders the image of the tooltip when it is shown, so the image is not stale.
+#[derive(Clone)]
+pub struct TooltipImageSource(Rc<dyn Fn() -> Option<Rc<Image<u32>>>>);
+
+impl std::fmt::Debug for TooltipImageSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("TooltipImageSource")
+    }
+}
+
+/// Hint shown near the mouse when it rests over a control, the image goes above the text.
+#[derive(Debug, Clone, Default)]
+pub struct Tooltip {
+    text: String,
+    image: Option<Rc<Image<u32>>>,
+    image_source: Option<TooltipImageSource>,
+}
+
+impl Tooltip {
+    pub fn new(text: &str) -> Self {
+        Self {
+            text: text.to_string(),
+            image: None,
+            image_source: None,
+        }
+    }
+
+    pub fn image(mut self, image: Rc<Image<u32>>) -> Self {
+        self.image = Some(image);
+        self
+    }
+
+    /// The image is rendered every time the tooltip is shown, the fixed image is left if
+    /// the source gives none.
+    pub fn image_source(mut self, source: impl Fn() -> Option<Rc<Image<u32>>> + 'static) -> Self {
+        self.image_source = Some(TooltipImageSource(Rc::new(source)));
+        self
+    }
+
+    pub fn get_text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn get_image(&self) -> Option<&Rc<Image<u32>>> {
+        self.image.as_ref()
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Tooltips {
+    font: Option<Font>,
+    delay: Option<Duration>,
+    /// Position where the mouse rests and since when.
+    hover: Option<(Position, Instant)>,
+    shown: Option<(Tooltip, Position)>,
+    changed: bool,
+}
+
+impl Tooltips {
+    pub(crate) fn set_font(&mut self, font: Font) {
+        self.font = Some(font);
+    }
+
+    pub(crate) fn set_delay(&mut self, delay: Duration) {
+        self.delay = Some(delay);
+    }
+
+    /// The mouse moved, the tooltip is hidden until the mouse rests again. Returns true
+    /// if the shown tooltip was hidden.
+    pub(crate) fn on_mouse_move(&mut self, position: Position) -> bool {
+        self.hover = Some((position, Instant::now()));
+        self.hide()
+    }
+
+    /// The mouse left the window or a button was pressed.
+    pub(crate) fn cancel(&mut self) -> bool {
+        self.hover = None;
+        self.hide()
+    }
+
+    fn hide(&mut self) -> bool {
+        let hidden = self.shown.take().is_some();
+        self.changed |= hidden;
+        hidden
+    }
+
+    /// Position to ask the tooltip for, once the mouse rested there long enough.
+    pub(crate) fn take_due_position(&mut self) -> Option<Position> {
+        let (position, since) = self.hover?;
+        if since.elapsed() < self.delay.unwrap_or(TOOLTIP_DELAY) {
+            return None;
+        }
+        self.hover = None;
+        Some(position)
+    }
+
+    pub(crate) fn show(&mut self, mut tooltip: Tooltip, position: Position) {
+        if let Some(image) = tooltip
+            .image_source
+            .as_ref()
+            .and_then(|source| (source.0)())
+        {
+            tooltip.image = Some(image);
+        }
+        self.shown = Some((tooltip, position));
+        self.changed = true;
+    }
+
+    pub(crate) fn get_shown(&self) -> Option<&Tooltip> {
+        self.shown.as_ref().map(|(tooltip, _)| tooltip)
+    }
+
+    pub(crate) fn take_changed(&mut self) -> bool {
+        std::mem::replace(&mut self.changed, false)
+    }
+
+    /// The tooltip goes below and to the right of the mouse, and is moved inside the window.
+    pub(crate) fn draw(&self, buf: &mut ImageViewMut<u32>, color_theme: &GuiColorTheme) {
+        let (font, (tooltip, position)) = match (&self.font, &self.shown) {
+            (Some(font), Some(shown)) => (font, shown),
+            _ => return,
+        };
+        let buf_size = buf.get_size();
+        let line_height = font.get_size("8").1 as i32;
+        let padding = line_height / 4 + 1;
+        let lines: Vec<_> = tooltip.text.lines().collect();
+        let image_size = tooltip
+            .image
+            .as_ref()
+            .map_or((0, 0), |image| image_size_to_position(image.get_size()));
+        let text_width = lines
+            .iter()
+            .map(|line| font.get_size(line).0 as i32)
+            .max()
+            .unwrap_or(0);
+        let image_gap = if image_size.1 > 0 && !lines.is_empty() {
+            padding
+        } else {
+            0
+        };
+        let width = text_width.max(image_size.0) + padding * 2;
+        let height = image_size.1 + image_gap + line_height * lines.len() as i32 + padding * 2;
+        if width > buf_size.0 as i32 || height > buf_size.1 as i32 {
+            return;
+        }
+        let left = (position.0 + line_height / 2).min(buf_size.0 as i32 - width);
+        let mut top = position.1 + line_height;
+        if top + height > buf_size.1 as i32 {
+            top = (position.1 - height).max(0);
+        }
+
+        let mut tooltip_buf = buf.window_mut(
+            position_to_image_size((left, top)),
+            position_to_image_size((left + width, top + height)),
+        );
+        tooltip_buf.fill(|p| *p = color_theme.splitter);
+        tooltip_buf
+            .window_mut((1, 1), ((width - 1) as usize, (height - 1) as usize))
+            .fill(|p| *p = color_theme.background);
+        if let Some(image) = &tooltip.image {
+            let image_left = (width - image_size.0) / 2;
+            let mut image_buf = tooltip_buf.window_mut(
+                position_to_image_size((image_left, padding)),
+                position_to_image_size((image_left + image_size.0, padding + image_size.1)),
+            );
+            let image = image.as_view();
+            for y in 0..image_buf.get_size().1 {
+                image_buf[y].copy_from_slice(&image[y]);
+            }
+        }
+        let text_font = font
+            .color(color_theme.font)
+            .layout_vertical(TextLayoutVertical::TOP)
+            .layout_horizontal(TextLayoutHorizontal::LEFT);
+        let text_top = padding + image_size.1 + image_gap;
+        for (index, line) in lines.iter().enumerate() {
+            text_font.draw(
+                line,
+                (padding, text_top + line_height * index as i32),
+                &mut tooltip_buf,
+            );
+        }
+    }
+}
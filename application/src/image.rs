@@ -246,6 +246,11 @@ impl<'i, Pixel> ImageView<'i, Pixel> {
         self.size
     }
 
+    /// Distance between the lines in pixels, it may be bigger than the width.
+    pub fn get_stride(&self) -> usize {
+        self.stride
+    }
+
     pub fn lines(&self, range: impl RangeBounds<usize>) -> LineIter<'i, Pixel> {
         lines!(self, range, LineIter);
     }
@@ -355,6 +360,13 @@ pub struct Image<Pixel: Default> {
     size: ImageSize,
 }
 
+// the pixels would flood the debug output of the controls showing images
+impl<Pixel: Default> std::fmt::Debug for Image<Pixel> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Image").field("size", &self.size).finish()
+    }
+}
+
 impl<Pixel: Default> Image<Pixel> {
     pub fn new(size: ImageSize) -> Self {
         let mut data = Vec::new();
@@ -399,7 +411,7 @@ mod tests {
     fn test_draw_same_size() {
         let (src, mut dst) = init_two_images();
         dst.as_view_mut()
-            .draw(src.as_view(), (0, 0), |d, s| *d = *s);
+            .draw(&src.as_view(), (0, 0), |d, s| *d = *s);
         assert_eq!(dst.as_view()[0][0], 42);
         assert_eq!(dst.as_view()[0][3], 42);
         assert_eq!(dst.as_view()[3][0], 42);
@@ -410,7 +422,7 @@ mod tests {
     fn test_draw_same_size_shifted_neg_neg() {
         let (src, mut dst) = init_two_images();
         dst.as_view_mut()
-            .draw(src.as_view(), (-2, -2), |d, s| *d = *s);
+            .draw(&src.as_view(), (-2, -2), |d, s| *d = *s);
         assert_eq!(dst.as_view()[0][0], 42);
         assert_eq!(dst.as_view()[0][3], 17);
         assert_eq!(dst.as_view()[3][0], 17);
@@ -421,7 +433,7 @@ mod tests {
     fn test_draw_same_size_shifted_neg_pos() {
         let (src, mut dst) = init_two_images();
         dst.as_view_mut()
-            .draw(src.as_view(), (-2, 2), |d, s| *d = *s);
+            .draw(&src.as_view(), (-2, 2), |d, s| *d = *s);
         assert_eq!(dst.as_view()[0][0], 17);
         assert_eq!(dst.as_view()[0][3], 17);
         assert_eq!(dst.as_view()[3][0], 42);
@@ -432,7 +444,7 @@ mod tests {
     fn test_draw_same_size_shifted_pos_neg() {
         let (src, mut dst) = init_two_images();
         dst.as_view_mut()
-            .draw(src.as_view(), (2, -2), |d, s| *d = *s);
+            .draw(&src.as_view(), (2, -2), |d, s| *d = *s);
         assert_eq!(dst.as_view()[0][0], 17);
         assert_eq!(dst.as_view()[0][3], 42);
         assert_eq!(dst.as_view()[3][0], 17);
@@ -443,7 +455,7 @@ mod tests {
     fn test_draw_same_size_shifted_pos_pos() {
         let (src, mut dst) = init_two_images();
         dst.as_view_mut()
-            .draw(src.as_view(), (2, 2), |d, s| *d = *s);
+            .draw(&src.as_view(), (2, 2), |d, s| *d = *s);
         assert_eq!(dst.as_view()[0][0], 17);
         assert_eq!(dst.as_view()[0][3], 17);
         assert_eq!(dst.as_view()[3][0], 17);
@@ -1,15 +1,76 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+struct FinishCallback(Rc<dyn Fn()>);
+
+impl std::fmt::Debug for FinishCallback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad("FinishCallback")
+    }
+}
+
+#[derive(Debug, Default)]
+struct TaskState {
+    name: String,
+    background: bool,
+    cancelled: Cell<bool>,
+    finished: Cell<bool>,
+    progress: Cell<f32>,
+    finish_callback: RefCell<Option<FinishCallback>>,
+}
+
+/// Handle of a long task; the task itself checks `is_cancelled` between its steps.
+#[derive(Debug, Clone)]
+pub struct JobHandle {
+    state: Rc<TaskState>,
+}
+
+impl JobHandle {
+    pub fn get_name(&self) -> &str {
+        &self.state.name
+    }
+
+    pub fn cancel(&self) {
+        self.state.cancelled.set(true);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.get()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.state.finished.get()
+    }
+
+    pub fn set_progress(&self, progress: f32) {
+        self.state.progress.set(progress.clamp(0.0, 1.0));
+    }
+
+    pub fn get_progress(&self) -> f32 {
+        self.state.progress.get()
+    }
+
+    /// Called as a job when the task is done or cancelled, the cancelled task doesn't
+    /// get its steps anymore, so it can clean up only here.
+    pub fn set_finish_callback(&self, callback: impl Fn() + 'static) {
+        *self.state.finish_callback.borrow_mut() = Some(FinishCallback(Rc::new(callback)));
+    }
+}
+
+type TaskStep = Box<dyn FnMut(&JobHandle) -> bool>;
 
 #[derive(Default, Clone)]
 pub struct JobSystem {
     jobs: Rc<RefCell<Vec<Rc<dyn Fn()>>>>,
+    tasks: Rc<RefCell<Vec<(JobHandle, TaskStep)>>>,
 }
 
 impl JobSystem {
     pub fn new() -> Self {
         Self {
             jobs: Rc::new(RefCell::new(Vec::new())),
+            tasks: Rc::new(RefCell::new(Vec::new())),
         }
     }
 
@@ -18,11 +79,125 @@ impl JobSystem {
     }
 
     pub fn run_all(&self) -> bool {
-        let result = !self.jobs.borrow().is_empty();
-        for callback in self.jobs.borrow().iter() {
-            callback();
+        let mut result = false;
+        loop {
+            // callbacks can add new jobs
+            let jobs = std::mem::take(&mut *self.jobs.borrow_mut());
+            if jobs.is_empty() {
+                return result;
+            }
+            result = true;
+            for callback in jobs {
+                callback();
+            }
+        }
+    }
+
+    /// Starts a long task, which is executed by small steps between input events.
+    /// The step returns true when the task is done.
+    pub fn start_task(
+        &self,
+        name: &str,
+        step: impl FnMut(&JobHandle) -> bool + 'static,
+    ) -> JobHandle {
+        self.add_task(name, false, Box::new(step))
+    }
+
+    /// Starts a task which doesn't block the input, its own controls have to stay usable
+    /// while it runs.
+    pub fn start_background_task(
+        &self,
+        name: &str,
+        step: impl FnMut(&JobHandle) -> bool + 'static,
+    ) -> JobHandle {
+        self.add_task(name, true, Box::new(step))
+    }
+
+    fn add_task(&self, name: &str, background: bool, step: TaskStep) -> JobHandle {
+        let handle = JobHandle {
+            state: Rc::new(TaskState {
+                name: name.to_string(),
+                background,
+                ..Default::default()
+            }),
+        };
+        self.tasks.borrow_mut().push((handle.clone(), step));
+        handle
+    }
+
+    /// A task which blocks the input is running.
+    pub fn is_busy(&self) -> bool {
+        self.tasks
+            .borrow()
+            .iter()
+            .any(|(handle, _)| !handle.state.background)
+    }
+
+    /// Any task is running, the background ones too.
+    pub fn has_tasks(&self) -> bool {
+        !self.tasks.borrow().is_empty()
+    }
+
+    pub fn get_tasks(&self) -> Vec<JobHandle> {
+        self.tasks
+            .borrow()
+            .iter()
+            .map(|(handle, _)| handle.clone())
+            .collect()
+    }
+
+    pub fn cancel_tasks(&self) {
+        for (handle, _) in self.tasks.borrow().iter() {
+            handle.cancel();
+        }
+    }
+
+    /// Runs steps of the first task until the time is over. Returns true if any step was done.
+    pub fn run_task_steps(&self, time_limit: Duration) -> bool {
+        let start = Instant::now();
+        let mut result = false;
+        while start.elapsed() < time_limit {
+            // the task is taken out, so its step can start other tasks
+            let (handle, mut step) = {
+                let mut tasks = self.tasks.borrow_mut();
+                if tasks.is_empty() {
+                    return result;
+                }
+                tasks.remove(0)
+            };
+            result = true;
+            if handle.is_cancelled() || step(&handle) {
+                handle.state.finished.set(true);
+                if let Some(FinishCallback(callback)) = handle.state.finish_callback.take() {
+                    self.add_callback(callback);
+                }
+            } else {
+                self.tasks.borrow_mut().insert(0, (handle, step));
+            }
         }
-        self.jobs.borrow_mut().clear();
         result
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finish_callback_runs_after_cancel() {
+        let job_system = JobSystem::new();
+        let finished = Rc::new(Cell::new(false));
+        let handle = job_system.start_task("test", |_| false);
+        {
+            let finished = finished.clone();
+            handle.set_finish_callback(move || finished.set(true));
+        }
+        job_system.run_task_steps(Duration::from_millis(1));
+        assert!(!finished.get());
+        handle.cancel();
+        job_system.run_task_steps(Duration::from_millis(1));
+        assert!(handle.is_finished());
+        assert!(job_system.run_all());
+        assert!(finished.get());
+    }
+}
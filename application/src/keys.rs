@@ -1,6 +1,9 @@
-#[derive(PartialEq, Eq, Hash, Copy, Clone)]
+use serde::{Deserialize, Serialize};
+
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Serialize, Deserialize)]
 pub enum Key {
     Space,
+    Tab,
     Left,
     Right,
     Up,
@@ -8,6 +11,10 @@ pub enum Key {
     Backspace,
     Delete,
     Insert,
+    Home,
+    End,
+    PageUp,
+    PageDown,
     Numpad0,
     Numpad1,
     Numpad2,
@@ -123,6 +130,7 @@ impl std::fmt::Debug for Key {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Space => f.pad("Space"),
+            Self::Tab => f.pad("Tab"),
             Self::Left => f.pad("Left"),
             Self::Right => f.pad("Right"),
             Self::Up => f.pad("Up"),
@@ -130,6 +138,10 @@ impl std::fmt::Debug for Key {
             Self::Backspace => f.pad("Back"),
             Self::Delete => f.pad("Del"),
             Self::Insert => f.pad("Ins"),
+            Self::Home => f.pad("Home"),
+            Self::End => f.pad("End"),
+            Self::PageUp => f.pad("PgUp"),
+            Self::PageDown => f.pad("PgDn"),
             Self::Numpad0 => f.pad("Numpad 0"),
             Self::Numpad1 => f.pad("Numpad 1"),
             Self::Numpad2 => f.pad("Numpad 2"),
@@ -194,7 +206,7 @@ impl std::fmt::Debug for Key {
     }
 }
 
-#[derive(PartialEq, Eq, Hash, Copy, Clone)]
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Serialize, Deserialize)]
 pub struct Hotkey {
     pub key: Key,
     pub ctrl: bool,
@@ -1,8 +1,13 @@
+pub mod angle_format;
 pub mod callback;
 pub mod clipboard;
 pub mod draw_context;
+pub mod file_watcher;
 pub mod font;
 pub mod gui;
 pub mod image;
 pub mod job_system;
 pub mod keys;
+pub mod number_format;
+pub mod property;
+pub mod shell;
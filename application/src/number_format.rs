@@ -0,0 +1,157 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+static SYSTEM_DECIMAL_SEPARATOR: AtomicU32 = AtomicU32::new('.' as u32);
+
+/// Platform layer sets the separator from the user locale at start.
+pub fn set_system_decimal_separator(separator: char) {
+    SYSTEM_DECIMAL_SEPARATOR.store(separator as u32, Ordering::Relaxed);
+}
+
+pub fn get_system_decimal_separator() -> char {
+    std::char::from_u32(SYSTEM_DECIMAL_SEPARATOR.load(Ordering::Relaxed)).unwrap_or('.')
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct NumberFormat {
+    /// None means the separator of the system locale.
+    #[serde(default)]
+    pub decimal_separator: Option<char>,
+
+    #[serde(default = "NumberFormat::default_precision")]
+    pub precision: usize,
+
+    #[serde(default = "NumberFormat::default_trim_trailing_zeros")]
+    pub trim_trailing_zeros: bool,
+
+    /// Numbers with absolute value not less than 10^threshold, or less than 10^-threshold,
+    /// are written with exponent.
+    #[serde(default = "NumberFormat::default_exponent_threshold")]
+    pub exponent_threshold: i32,
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        Self {
+            decimal_separator: None,
+            precision: Self::default_precision(),
+            trim_trailing_zeros: Self::default_trim_trailing_zeros(),
+            exponent_threshold: Self::default_exponent_threshold(),
+        }
+    }
+}
+
+impl NumberFormat {
+    fn default_precision() -> usize {
+        3
+    }
+
+    fn default_trim_trailing_zeros() -> bool {
+        true
+    }
+
+    fn default_exponent_threshold() -> i32 {
+        9
+    }
+
+    pub fn get_decimal_separator(&self) -> char {
+        self.decimal_separator
+            .unwrap_or_else(get_system_decimal_separator)
+    }
+
+    fn trim_zeros(&self, mantissa: &str) -> String {
+        if !self.trim_trailing_zeros || !mantissa.contains('.') {
+            return mantissa.to_string();
+        }
+        return mantissa
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .to_string();
+    }
+
+    pub fn format(&self, value: f64) -> String {
+        if !value.is_finite() {
+            return format!("{}", value);
+        }
+
+        let abs = value.abs();
+        let threshold = 10f64.powi(self.exponent_threshold);
+        let text = if abs != 0.0 && (abs >= threshold || abs * threshold < 1.0) {
+            let text = format!("{:.*e}", self.precision, value);
+            let (mantissa, exponent) = text.split_once('e').unwrap_or((&text, "0"));
+            format!("{}e{}", self.trim_zeros(mantissa), exponent)
+        } else {
+            self.trim_zeros(&format!("{:.*}", self.precision, value))
+        };
+
+        // Rounding can give "-0"
+        let text = if text
+            .trim_start_matches('-')
+            .chars()
+            .all(|c| c == '0' || c == '.')
+        {
+            text.trim_start_matches('-').to_string()
+        } else {
+            text
+        };
+        return text.replace('.', &self.get_decimal_separator().to_string());
+    }
+
+    /// Accepts both the point and the configured separator.
+    pub fn parse(&self, text: &str) -> Option<f64> {
+        let text = text.trim().replace(self.get_decimal_separator(), ".");
+        text.parse::<f64>().ok().filter(|value| value.is_finite())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format_with(decimal_separator: char) -> NumberFormat {
+        NumberFormat {
+            decimal_separator: Some(decimal_separator),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_format_fixed() {
+        let format = format_with('.');
+        assert_eq!(format.format(0.0), "0");
+        assert_eq!(format.format(10.0), "10");
+        assert_eq!(format.format(-2.5), "-2.5");
+        assert_eq!(format.format(1.0 / 3.0), "0.333");
+        assert_eq!(format.format(-0.0001), "0");
+        assert_eq!(format_with(',').format(12.125), "12,125");
+
+        let format = NumberFormat {
+            trim_trailing_zeros: false,
+            precision: 2,
+            ..format
+        };
+        assert_eq!(format.format(1.5), "1.50");
+    }
+
+    #[test]
+    fn test_format_exponent() {
+        let format = NumberFormat {
+            exponent_threshold: 3,
+            ..format_with(',')
+        };
+        assert_eq!(format.format(1500.0), "1,5e3");
+        assert_eq!(format.format(-0.00025), "-2,5e-4");
+        assert_eq!(format.format(999.0), "999");
+    }
+
+    #[test]
+    fn test_parse() {
+        let format = format_with(',');
+        assert_eq!(format.parse(" 1,25 "), Some(1.25));
+        assert_eq!(format.parse("1.25"), Some(1.25));
+        assert_eq!(format.parse("-3e2"), Some(-300.0));
+        assert_eq!(format.parse("abc"), None);
+        assert_eq!(format.parse("inf"), None);
+    }
+}
@@ -0,0 +1,171 @@
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+
+type Subscriber<T> = Rc<dyn Fn(&T) -> bool>;
+
+struct PropertyInner<T> {
+    value: T,
+    subscribers: Vec<(usize, Subscriber<T>)>,
+    last_subscriber_id: usize,
+}
+
+/// Observable value, the clones share it. Subscribers are called after every change,
+/// so the controls showing the value don't have to be updated by hand.
+pub struct Property<T> {
+    inner: Rc<RefCell<PropertyInner<T>>>,
+}
+
+impl<T> Clone for Property<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T: std::fmt::Debug> std::fmt::Debug for Property<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Property")
+            .field(&self.inner.borrow().value)
+            .finish()
+    }
+}
+
+impl<T: Default + PartialEq + Clone + 'static> Default for Property<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: PartialEq + Clone + 'static> Property<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            inner: Rc::new(RefCell::new(PropertyInner {
+                value,
+                subscribers: Vec::new(),
+                last_subscriber_id: 0,
+            })),
+        }
+    }
+
+    pub fn get(&self) -> T {
+        self.inner.borrow().value.clone()
+    }
+
+    /// Returns true if the value was changed, the subscribers are called only then.
+    pub fn set(&self, value: T) -> bool {
+        let subscribers = {
+            let mut inner = self.inner.borrow_mut();
+            if inner.value == value {
+                return false;
+            }
+            inner.value = value.clone();
+            inner.subscribers.clone()
+        };
+        // subscribers can set the property again or subscribe to it
+        let mut expired = Vec::new();
+        for (id, subscriber) in subscribers {
+            if !subscriber(&value) {
+                expired.push(id);
+            }
+        }
+        if !expired.is_empty() {
+            self.inner
+                .borrow_mut()
+                .subscribers
+                .retain(|(id, _)| !expired.contains(id));
+        }
+        true
+    }
+
+    /// Returns the id for `unsubscribe`.
+    pub fn subscribe(&self, subscriber: impl Fn(&T) + 'static) -> usize {
+        self.add_subscriber(Rc::new(move |value| {
+            subscriber(value);
+            true
+        }))
+    }
+
+    /// The subscription lives while the owner is alive, so the property doesn't keep
+    /// the controls bound to it.
+    pub fn subscribe_weak<O: ?Sized + 'static>(
+        &self,
+        owner: &Rc<RefCell<O>>,
+        subscriber: impl Fn(&mut O, &T) + 'static,
+    ) -> usize {
+        let owner: Weak<RefCell<O>> = Rc::downgrade(owner);
+        self.add_subscriber(Rc::new(move |value| match owner.upgrade() {
+            Some(owner) => {
+                subscriber(&mut owner.borrow_mut(), value);
+                true
+            }
+            None => false,
+        }))
+    }
+
+    pub fn unsubscribe(&self, id: usize) {
+        self.inner
+            .borrow_mut()
+            .subscribers
+            .retain(|(subscriber_id, _)| *subscriber_id != id);
+    }
+
+    pub fn subscriber_count(&self) -> usize {
+        self.inner.borrow().subscribers.len()
+    }
+
+    fn add_subscriber(&self, subscriber: Subscriber<T>) -> usize {
+        let mut inner = self.inner.borrow_mut();
+        inner.last_subscriber_id += 1;
+        let id = inner.last_subscriber_id;
+        inner.subscribers.push((id, subscriber));
+        id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn property_notifies_on_change_only() {
+        let property = Property::new(1);
+        let calls = Rc::new(RefCell::new(Vec::new()));
+        let id = property.subscribe({
+            let calls = calls.clone();
+            move |value| calls.borrow_mut().push(*value)
+        });
+        assert!(property.set(2));
+        assert!(!property.set(2));
+        property.clone().set(3);
+        property.unsubscribe(id);
+        property.set(4);
+        assert_eq!(*calls.borrow(), vec![2, 3]);
+        assert_eq!(property.get(), 4);
+    }
+
+    #[test]
+    fn weak_subscription_ends_with_owner() {
+        let property = Property::new(false);
+        let owner = Rc::new(RefCell::new(0));
+        property.subscribe_weak(&owner, |count, _| *count += 1);
+        property.set(true);
+        assert_eq!(*owner.borrow(), 1);
+        drop(owner);
+        property.set(false);
+        assert_eq!(property.subscriber_count(), 0);
+    }
+
+    #[test]
+    fn subscriber_can_set_property() {
+        let property = Property::new(0);
+        let inner = property.clone();
+        property.subscribe(move |value| {
+            if *value < 3 {
+                inner.set(value + 1);
+            }
+        });
+        property.set(1);
+        assert_eq!(property.get(), 3);
+    }
+}
@@ -0,0 +1,23 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+pub trait ShellHandler: std::fmt::Debug {
+    /// Opens the URL or the file location with the default system handler.
+    fn open(&mut self, target: &str) -> Result<(), String>;
+}
+
+#[derive(Debug, Clone)]
+pub struct Shell {
+    handler: Rc<RefCell<dyn ShellHandler>>,
+}
+
+impl Shell {
+    pub fn new(handler: impl ShellHandler + 'static) -> Self {
+        Self {
+            handler: Rc::new(RefCell::new(handler)),
+        }
+    }
+    pub fn open(&self, target: &str) -> Result<(), String> {
+        self.handler.borrow_mut().open(target)
+    }
+}
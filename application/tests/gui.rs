@@ -0,0 +1,1375 @@
+use std::cell::{Cell, RefCell};
+use std::path::Path;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use application::clipboard::*;
+use application::gui::binding::*;
+use application::gui::caret::*;
+use application::gui::completion::*;
+use application::gui::gui_components::*;
+use application::gui::headless::*;
+use application::gui::input_log::*;
+use application::gui::message_box::*;
+use application::gui::toast::*;
+use application::gui::tooltip::*;
+use application::gui::*;
+use application::image::*;
+use application::keys::*;
+use application::property::*;
+use application::shell::*;
+
+fn fixed(width: i32, height: i32) -> SizeConstraints {
+    SizeConstraints(SizeConstraint::fixed(width), SizeConstraint::fixed(height))
+}
+
+fn flexible() -> SizeConstraints {
+    SizeConstraints(SizeConstraint::flexible(0), SizeConstraint::flexible(0))
+}
+
+#[test]
+fn button_click_runs_callback() {
+    let mut gui = HeadlessGui::new((200, 100));
+    let font = gui.font(12);
+    let clicks = Rc::new(Cell::new(0));
+    let mut root = Container::new(flexible(), ContainerLayout::Vertical);
+    {
+        let clicks = clicks.clone();
+        root.add_child(
+            Button::new(fixed(80, 20), "Ok".to_string(), font)
+                .callback(move || clicks.set(clicks.get() + 1)),
+        );
+    }
+    gui.set_root(root);
+    gui.render();
+
+    assert!(gui.click_node(AccessibleRole::Button, "Ok"));
+    assert_eq!(clicks.get(), 1);
+
+    gui.click((150, 80));
+    assert_eq!(clicks.get(), 1);
+}
+
+#[test]
+fn long_task_blocks_input_until_cancelled() {
+    let mut gui = HeadlessGui::new((200, 100));
+    let font = gui.font(12);
+    let clicks = Rc::new(Cell::new(0));
+    let mut root = Container::new(flexible(), ContainerLayout::Vertical);
+    {
+        let clicks = clicks.clone();
+        root.add_child(
+            Button::new(fixed(80, 20), "Ok".to_string(), font)
+                .callback(move || clicks.set(clicks.get() + 1)),
+        );
+    }
+    gui.set_root(root);
+    gui.render();
+
+    let steps = Rc::new(Cell::new(0));
+    let task = {
+        let steps = steps.clone();
+        gui.job_system.start_task("test", move |_| {
+            steps.set(steps.get() + 1);
+            false
+        })
+    };
+    gui.job_system
+        .run_task_steps(std::time::Duration::from_millis(1));
+    assert!(steps.get() > 0);
+
+    assert!(gui.click_node(AccessibleRole::Button, "Ok"));
+    assert_eq!(clicks.get(), 0);
+
+    gui.key(Key::Escape);
+    assert!(task.is_cancelled());
+    gui.run_tasks();
+    assert!(task.is_finished());
+
+    gui.click_node(AccessibleRole::Button, "Ok");
+    assert_eq!(clicks.get(), 1);
+}
+
+#[test]
+fn background_task_is_cancelled_by_its_overlay_button() {
+    let mut gui = HeadlessGui::new((200, 100));
+    let font = gui.font(12);
+    let events = Rc::new(RefCell::new(Vec::new()));
+    gui.set_root(MouseLog {
+        base: GuiControlBase::new(flexible()),
+        events: events.clone(),
+    });
+    let task = gui.job_system.start_background_task("test", |_| false);
+    {
+        let task = task.clone();
+        gui.gui_system.add_region(
+            RegionPlacement::Overlay(Rect {
+                left_top: (60, 40),
+                right_bottom: (140, 60),
+            }),
+            Button::new(flexible(), "Отмена".to_string(), font).callback(move || task.cancel()),
+        );
+    }
+    gui.render();
+    gui.job_system
+        .run_task_steps(std::time::Duration::from_millis(1));
+    assert!(!gui.gui_system.is_busy());
+
+    // the drawing under the overlay still gets the wheel
+    assert!(gui.mouse_wheel((10, 20), 1.0));
+    assert_eq!(*events.borrow(), vec!["wheel (10, 20) 1".to_string()]);
+
+    assert!(gui.click_node(AccessibleRole::Button, "Отмена"));
+    assert!(task.is_cancelled());
+    gui.run_tasks();
+    assert!(task.is_finished());
+}
+
+#[test]
+fn escape_cancels_one_thing_per_press() {
+    let mut gui = HeadlessGui::new((200, 100));
+    gui.set_root(Container::new(flexible(), ContainerLayout::Vertical));
+    gui.render();
+
+    let tool = Rc::new(Cell::new(true));
+    let selection = Rc::new(Cell::new(true));
+    {
+        let selection = selection.clone();
+        gui.gui_system
+            .add_escape_handler(1, move || selection.replace(false));
+    }
+    let tool_handler = {
+        let tool = tool.clone();
+        gui.gui_system
+            .add_escape_handler(2, move || tool.replace(false))
+    };
+
+    let task = gui.job_system.start_task("test", |_| false);
+    assert!(gui.key(Key::Escape));
+    assert!(task.is_cancelled());
+    assert!(tool.get() && selection.get());
+    gui.run_tasks();
+
+    assert!(gui.key(Key::Escape));
+    assert!(!tool.get() && selection.get());
+    assert!(gui.key(Key::Escape));
+    assert!(!selection.get());
+    assert!(!gui.key(Key::Escape));
+
+    tool.set(true);
+    gui.gui_system.remove_escape_handler(tool_handler);
+    assert!(!gui.key(Key::Escape));
+    assert!(tool.get());
+}
+
+#[test]
+fn edit_receives_text_and_enter() {
+    let mut gui = HeadlessGui::new((200, 100));
+    let font = gui.font(12);
+    let entered = Rc::new(RefCell::new(String::new()));
+    let mut root = Container::new(flexible(), ContainerLayout::Vertical);
+    {
+        let entered = entered.clone();
+        root.add_child(
+            Edit::new(fixed(100, 20), font, gui.clipboard.clone())
+                .enter_callback(move |text| *entered.borrow_mut() = text.to_string()),
+        );
+    }
+    gui.set_root(root);
+    gui.render();
+
+    gui.click((10, 10));
+    gui.type_text("abc");
+    gui.key(Key::Backspace);
+    gui.type_text("d");
+    assert_eq!(gui.focused_node().unwrap().value.as_deref(), Some("abd"));
+
+    gui.key(Key::Enter);
+    assert_eq!(entered.borrow().as_str(), "abd");
+    assert!(gui.focused_node().is_none());
+}
+
+#[test]
+fn masked_edit_hides_text() {
+    let mut gui = HeadlessGui::new((200, 100));
+    let font = gui.font(12);
+    let entered = Rc::new(RefCell::new(String::new()));
+    let mut root = Container::new(flexible(), ContainerLayout::Vertical);
+    {
+        let entered = entered.clone();
+        root.add_child(
+            Edit::new(fixed(100, 20), font, gui.clipboard.clone())
+                .masked()
+                .enter_callback(move |text| *entered.borrow_mut() = text.to_string()),
+        );
+    }
+    gui.set_root(root);
+    gui.render();
+    assert!(!gui.gui_system.is_masked_input_focused());
+
+    gui.click((10, 10));
+    assert!(gui.gui_system.is_masked_input_focused());
+    gui.type_text("пароль");
+    gui.render();
+    assert_eq!(
+        gui.focused_node().unwrap().value.as_deref(),
+        Some("\u{2022}".repeat(6).as_str())
+    );
+
+    gui.key(Key::Enter);
+    assert_eq!(entered.borrow().as_str(), "пароль");
+    assert!(!gui.gui_system.is_masked_input_focused());
+}
+
+#[test]
+fn invalid_edit_text_is_not_committed() {
+    let mut gui = HeadlessGui::new((200, 100));
+    let font = gui.font(12);
+    let entered = Rc::new(RefCell::new(String::new()));
+    let mut root = Container::new(flexible(), ContainerLayout::Vertical);
+    let edit = {
+        let entered = entered.clone();
+        root.add_child(
+            Edit::new(fixed(100, 20), font, gui.clipboard.clone())
+                .validator(Validator::parsed("Not a number", |value: i32| {
+                    if value > 0 {
+                        Ok(())
+                    } else {
+                        Err("Not positive".to_string())
+                    }
+                }))
+                .enter_callback(move |text| *entered.borrow_mut() = text.to_string()),
+        )
+    };
+    gui.set_root(root);
+    gui.render();
+
+    gui.click((10, 10));
+    gui.type_text("-5");
+    gui.key(Key::Enter);
+    assert!(entered.borrow().is_empty());
+    assert!(gui.focused_node().is_some());
+    assert_eq!(edit.borrow().validate(), Err("Not positive".to_string()));
+
+    gui.gui_system.set_tooltip_delay(Duration::ZERO);
+    gui.mouse_move((10, 10));
+    gui.gui_system.on_timer();
+    let tooltip = gui.gui_system.get_tooltip().unwrap();
+    assert_eq!(tooltip.get_text(), "Not positive");
+
+    edit.borrow_mut().set_text("x");
+    assert_eq!(edit.borrow().validate(), Err("Not a number".to_string()));
+    edit.borrow_mut().set_text("5");
+    gui.key(Key::Enter);
+    assert_eq!(entered.borrow().as_str(), "5");
+    assert!(gui.focused_node().is_none());
+}
+
+#[test]
+fn edit_completion_is_chosen_by_keys_and_mouse() {
+    let mut gui = HeadlessGui::new((200, 200));
+    let font = gui.font(12);
+    let entered = Rc::new(RefCell::new(String::new()));
+    let words = ["Line", "Layer", "Circle"].map(String::from).to_vec();
+    let mut root = Container::new(flexible(), ContainerLayout::Vertical);
+    let edit = {
+        let entered = entered.clone();
+        root.add_child(
+            Edit::new(fixed(100, 20), font, gui.clipboard.clone())
+                .completion_provider(CompletionProvider::prefix(words))
+                .enter_callback(move |text| *entered.borrow_mut() = text.to_string()),
+        )
+    };
+    root.add_child(Button::new(
+        fixed(100, 20),
+        "Next".to_string(),
+        gui.font(12),
+    ));
+    gui.set_root(root);
+    gui.render();
+
+    gui.click((10, 10));
+    gui.type_text("l");
+    let completion = gui.gui_system.get_completion().unwrap();
+    assert_eq!(completion.candidates, vec!["Line", "Layer"]);
+    gui.key(Key::Down);
+    assert_eq!(gui.gui_system.get_completion().unwrap().selected, 1);
+    // Tab takes the candidate instead of moving the focus
+    gui.key(Key::Tab);
+    assert_eq!(edit.borrow().get_text(), "Layer");
+    assert!(gui.gui_system.get_completion().is_none());
+    assert!(entered.borrow().is_empty());
+
+    gui.key(Key::Backspace);
+    gui.key(Key::Backspace);
+    gui.key(Key::Backspace);
+    gui.key(Key::Backspace);
+    gui.type_text("i");
+    assert_eq!(
+        gui.gui_system.get_completion().unwrap().candidates,
+        vec!["Line"]
+    );
+    // Escape closes the list only, the text stays in the focused edit
+    gui.key(Key::Escape);
+    assert!(gui.gui_system.get_completion().is_none());
+    assert_eq!(gui.focused_node().unwrap().value.as_deref(), Some("Li"));
+
+    gui.type_text("n");
+    gui.render();
+    // the list is drawn under the edit, its first line is right below it
+    gui.click((10, 25));
+    assert_eq!(edit.borrow().get_text(), "Line");
+    gui.key(Key::Enter);
+    assert_eq!(entered.borrow().as_str(), "Line");
+}
+
+#[test]
+fn caret_blinks_in_focused_edit_only() {
+    let mut gui = HeadlessGui::new((200, 100));
+    let font = gui.font(12);
+    let mut root = Container::new(flexible(), ContainerLayout::Vertical);
+    root.add_child(Edit::new(fixed(100, 20), font, gui.clipboard.clone()));
+    gui.set_root(root);
+    gui.gui_system
+        .set_caret_blink_period(Duration::from_millis(10));
+    gui.render();
+    assert!(get_caret_rect().is_none());
+
+    gui.click((10, 10));
+    gui.render();
+    let caret = get_caret_rect().unwrap();
+
+    let start = Instant::now();
+    while !gui.gui_system.on_timer() {
+        assert!(start.elapsed() < Duration::from_secs(5));
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    gui.render();
+    let damage = gui.gui_system.get_damage();
+    assert_eq!(damage.len(), 1);
+    assert!(damage[0].contains(caret.left_top));
+
+    gui.key(Key::Escape);
+    gui.render();
+    assert!(get_caret_rect().is_none());
+}
+
+#[test]
+fn recorded_input_replays_into_gui() {
+    let path = std::env::temp_dir().join("otcad_recorded_input_replays_into_gui.jsonl");
+    let mut recorder = InputRecorder::create(&path).unwrap();
+    for event in [
+        InputEvent::Resize((300, 100)),
+        InputEvent::MouseDown((10, 10)),
+        InputEvent::MouseUp((10, 10)),
+        InputEvent::Char('x'),
+        InputEvent::Char('y'),
+        InputEvent::KeyDown(Hotkey::new(Key::Left)),
+        InputEvent::KeyUp(Key::Left),
+        InputEvent::Char('z'),
+    ] {
+        recorder.record(event).unwrap();
+    }
+    drop(recorder);
+
+    let player = InputPlayer::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(player.events().len(), 8);
+
+    let mut gui = HeadlessGui::new((200, 100));
+    let font = gui.font(12);
+    let mut root = Container::new(flexible(), ContainerLayout::Vertical);
+    root.add_child(Edit::new(fixed(100, 20), font, gui.clipboard.clone()));
+    gui.set_root(root);
+    gui.render();
+
+    gui.replay(&player);
+    assert_eq!(gui.render().get_size(), (300, 100));
+    assert_eq!(gui.focused_node().unwrap().value.as_deref(), Some("xzy"));
+}
+
+#[test]
+fn keyboard_focus_and_activation() {
+    let mut gui = HeadlessGui::new((200, 100));
+    let font = gui.font(12);
+    let pressed = Rc::new(RefCell::new(Vec::new()));
+    let mut root = Container::new(flexible(), ContainerLayout::Vertical);
+    for name in ["First", "Second"] {
+        let pressed = pressed.clone();
+        root.add_child(
+            Button::new(fixed(80, 20), name.to_string(), font.clone())
+                .callback(move || pressed.borrow_mut().push(name)),
+        );
+    }
+    gui.set_root(root);
+    gui.render();
+
+    gui.key(Key::Tab);
+    assert_eq!(gui.focused_node().unwrap().name, "First");
+    gui.key(Key::Tab);
+    assert_eq!(gui.focused_node().unwrap().name, "Second");
+    gui.hotkey(Hotkey::shift(Key::Tab));
+    assert_eq!(gui.focused_node().unwrap().name, "First");
+
+    gui.key(Key::Space);
+    gui.key(Key::Tab);
+    gui.key(Key::Enter);
+    assert_eq!(*pressed.borrow(), vec!["First", "Second"]);
+}
+
+fn tab_control_with_tabs(gui: &mut HeadlessGui, count: usize) -> Rc<RefCell<TabControl>> {
+    let font = gui.font(12);
+    let mut root = Container::new(flexible(), ContainerLayout::Vertical);
+    let tab_control = root.add_child(
+        TabControl::new(20, font.clone(), true)
+            .compressed()
+            .navigation_hotkeys(),
+    );
+    for index in 0..count {
+        tab_control.borrow_mut().add_tab(
+            format!("Tab{}", index),
+            60,
+            EmptySpace::new_empty(flexible()),
+        );
+    }
+    gui.set_root(root);
+    gui.render();
+    tab_control
+}
+
+#[test]
+fn tab_control_navigation_hotkeys() {
+    let mut gui = HeadlessGui::new((400, 100));
+    let tab_control = tab_control_with_tabs(&mut gui, 3);
+    let closed = Rc::new(Cell::new(None));
+    {
+        let closed = closed.clone();
+        tab_control
+            .borrow_mut()
+            .set_close_tab_callback(move |id| closed.set(Some(id)));
+    }
+
+    gui.hotkey(Hotkey::ctrl(Key::Tab));
+    assert_eq!(tab_control.borrow().selected_tab_id(), 1);
+    gui.hotkey(Hotkey::ctrl(Key::Num3));
+    assert_eq!(tab_control.borrow().selected_tab_id(), 2);
+    gui.hotkey(Hotkey::ctrl(Key::Tab));
+    assert_eq!(tab_control.borrow().selected_tab_id(), 0);
+    gui.hotkey(Hotkey::ctrl_shift(Key::Tab));
+    assert_eq!(tab_control.borrow().selected_tab_id(), 2);
+    gui.hotkey(Hotkey::ctrl(Key::Num1));
+    assert_eq!(tab_control.borrow().selected_tab_id(), 0);
+    gui.hotkey(Hotkey::ctrl(Key::Num9));
+    assert_eq!(tab_control.borrow().selected_tab_id(), 2);
+
+    gui.hotkey(Hotkey::ctrl(Key::W));
+    assert_eq!(closed.get(), Some(2));
+}
+
+#[test]
+fn tab_control_insert_rename_and_disable() {
+    let mut gui = HeadlessGui::new((400, 100));
+    let tab_control = tab_control_with_tabs(&mut gui, 2);
+    let (_, inserted_id) = tab_control.borrow_mut().insert_tab_with_id(
+        1,
+        "Inserted".to_string(),
+        60,
+        EmptySpace::new_empty(flexible()),
+    );
+    gui.resize((400, 100));
+    gui.render();
+    assert_eq!(tab_control.borrow().get_tab_ids(), vec![0, inserted_id, 1]);
+    let tab_names = |gui: &HeadlessGui| -> Vec<String> {
+        gui.accessible_nodes()
+            .into_iter()
+            .filter(|node| node.role == AccessibleRole::Tab && node.name != "x")
+            .map(|node| node.name)
+            .collect()
+    };
+    assert_eq!(tab_names(&gui), vec!["Tab0", "Inserted", "Tab1"]);
+
+    tab_control.borrow().set_tab_caption(inserted_id, "Renamed");
+    assert_eq!(tab_names(&gui), vec!["Tab0", "Renamed", "Tab1"]);
+
+    tab_control.borrow().set_tab_enabled(1, false);
+    gui.render();
+    assert!(!gui.find_node(AccessibleRole::Tab, "Tab1").unwrap().enabled);
+    assert!(gui.click_node(AccessibleRole::Tab, "Tab1"));
+    assert_eq!(tab_control.borrow().selected_tab_id(), 0);
+    assert!(gui.click_node(AccessibleRole::Tab, "Renamed"));
+    assert_eq!(tab_control.borrow().selected_tab_id(), inserted_id);
+}
+
+#[test]
+fn menu_is_driven_by_keyboard() {
+    let mut gui = HeadlessGui::new((400, 100));
+    let font = gui.font(12);
+    let cuts = Rc::new(Cell::new(0));
+    let mut root = Container::new(flexible(), ContainerLayout::Vertical);
+    let menu_bar = root.add_child(TabControl::new(20, font.clone(), false));
+    let file_menu = menu_bar.borrow_mut().add_tab(
+        "&File".to_string(),
+        60,
+        Container::new(flexible(), ContainerLayout::Vertical),
+    );
+    for caption in ["&New", "&Open"] {
+        file_menu.borrow_mut().add_child(Button::new(
+            fixed(80, 20),
+            caption.to_string(),
+            font.clone(),
+        ));
+    }
+    let edit_menu = menu_bar.borrow_mut().add_tab(
+        "&Edit".to_string(),
+        60,
+        Container::new(flexible(), ContainerLayout::Vertical),
+    );
+    {
+        let cuts = cuts.clone();
+        edit_menu.borrow_mut().add_child(
+            Button::new(fixed(80, 20), "Cu&t && paste".to_string(), font.clone())
+                .callback(move || cuts.set(cuts.get() + 1)),
+        );
+    }
+    gui.set_root(root);
+    let menu_bar_control: Rc<RefCell<dyn GuiControl>> = menu_bar.clone();
+    gui.gui_system.set_menu_bar(&menu_bar_control);
+    gui.render();
+    let focused_name = |gui: &HeadlessGui| gui.focused_node().map(|node| node.name);
+
+    assert!(gui.input(InputEvent::Accelerator('E')));
+    assert_eq!(menu_bar.borrow().selected_tab_id(), 1);
+    // the page of the tab gets its height with the new size constraints
+    gui.get_minimal_size();
+    gui.render();
+    assert!(gui.input(InputEvent::Accelerator('t')));
+    gui.run_jobs();
+    assert_eq!(cuts.get(), 1);
+    assert!(gui
+        .find_node(AccessibleRole::Button, "Cut & paste")
+        .is_some());
+    assert!(!gui.input(InputEvent::Accelerator('x')));
+
+    // the activated item keeps the focus, Alt leaves the menu and then enters it again
+    assert_eq!(focused_name(&gui).as_deref(), Some("Cut & paste"));
+    assert!(gui.input(InputEvent::MenuKey));
+    assert_eq!(focused_name(&gui), None);
+    assert!(gui.input(InputEvent::MenuKey));
+    assert_eq!(focused_name(&gui).as_deref(), Some("Edit"));
+    gui.key(Key::Left);
+    gui.render();
+    assert_eq!(menu_bar.borrow().selected_tab_id(), 0);
+    assert_eq!(focused_name(&gui).as_deref(), Some("File"));
+    gui.key(Key::Down);
+    assert_eq!(focused_name(&gui).as_deref(), Some("New"));
+    gui.key(Key::Down);
+    assert_eq!(focused_name(&gui).as_deref(), Some("Open"));
+    gui.key(Key::Up);
+    gui.key(Key::Up);
+    assert_eq!(focused_name(&gui).as_deref(), Some("File"));
+    gui.key(Key::Escape);
+    assert_eq!(focused_name(&gui), None);
+
+    assert!(gui.input(InputEvent::MenuKey));
+    assert!(gui.input(InputEvent::MenuKey));
+    assert_eq!(focused_name(&gui), None);
+}
+
+#[test]
+fn tab_control_header_overflow() {
+    let mut gui = HeadlessGui::new((200, 100));
+    let tab_control = tab_control_with_tabs(&mut gui, 6);
+    let visible_tabs = |gui: &HeadlessGui| -> Vec<String> {
+        gui.accessible_nodes()
+            .into_iter()
+            .filter(|node| node.role == AccessibleRole::Tab && node.name.starts_with("Tab"))
+            .map(|node| node.name)
+            .collect()
+    };
+
+    assert_eq!(visible_tabs(&gui)[0], "Tab0");
+    assert!(gui.find_node(AccessibleRole::Tab, ">").is_some());
+
+    gui.click_node(AccessibleRole::Tab, ">");
+    gui.render();
+    assert_eq!(visible_tabs(&gui)[0], "Tab1");
+
+    gui.hotkey(Hotkey::ctrl(Key::Num9));
+    gui.render();
+    assert_eq!(tab_control.borrow().selected_tab_id(), 5);
+    assert_eq!(visible_tabs(&gui).last().unwrap(), "Tab5");
+
+    gui.hotkey(Hotkey::ctrl(Key::Num1));
+    gui.render();
+    assert_eq!(visible_tabs(&gui)[0], "Tab0");
+}
+
+#[test]
+fn tab_control_scroll_stops_at_last_tab() {
+    let mut gui = HeadlessGui::new((200, 100));
+    tab_control_with_tabs(&mut gui, 6);
+    let first_visible_tab = |gui: &HeadlessGui| -> String {
+        gui.accessible_nodes()
+            .into_iter()
+            .find(|node| node.role == AccessibleRole::Tab && node.name.starts_with("Tab"))
+            .unwrap()
+            .name
+    };
+
+    let click_next = |gui: &mut HeadlessGui| {
+        for _ in 0..20 {
+            gui.click_node(AccessibleRole::Tab, ">");
+        }
+    };
+    click_next(&mut gui);
+    gui.render();
+    let last_page: usize = first_visible_tab(&gui)[3..].parse().unwrap();
+    assert!(last_page > 0);
+
+    // the clicks past the end are not counted, one click back scrolls at once
+    click_next(&mut gui);
+    gui.click_node(AccessibleRole::Tab, "<");
+    gui.render();
+    assert_eq!(first_visible_tab(&gui), format!("Tab{}", last_page - 1));
+}
+
+#[test]
+fn widgets_match_golden_image() {
+    let mut gui = HeadlessGui::new((120, 60));
+    gui.gui_system.set_color_theme(DARK_THEME);
+    let font = gui.font(8);
+    let mut root = Container::new(flexible(), ContainerLayout::Vertical);
+    root.add_child(Button::new(
+        fixed(80, 20),
+        "Button".to_string(),
+        font.clone(),
+    ));
+    root.add_child(Button::new(fixed(80, 20), "Check".to_string(), font).check_box(true));
+    gui.set_root(root);
+
+    let golden = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden/widgets.ppm");
+    assert_golden(&gui.render().as_view(), &golden, 0);
+}
+
+#[test]
+fn toast_click_runs_action_and_closes() {
+    let mut gui = HeadlessGui::new((300, 200));
+    let font = gui.font(12);
+    let clicks = Rc::new(Cell::new(0));
+    let mut root = Container::new(flexible(), ContainerLayout::Vertical);
+    {
+        let clicks = clicks.clone();
+        root.add_child(
+            Button::new(flexible(), "Ok".to_string(), font.clone())
+                .callback(move || clicks.set(clicks.get() + 1)),
+        );
+    }
+    gui.set_root(root);
+    gui.gui_system.set_toast_font(font);
+
+    let actions = Rc::new(Cell::new(0));
+    {
+        let actions = actions.clone();
+        gui.gui_system
+            .show_toast(Toast::info("Saved").action(move || actions.set(actions.get() + 1)));
+    }
+    gui.render();
+    assert_eq!(gui.gui_system.get_toasts().len(), 1);
+
+    // the toast is in the bottom right corner, above the button
+    gui.click((290, 190));
+    assert_eq!(actions.get(), 1);
+    assert_eq!(clicks.get(), 0);
+    assert!(gui.gui_system.get_toasts().is_empty());
+
+    gui.render();
+    gui.click((290, 190));
+    assert_eq!(clicks.get(), 1);
+}
+
+#[test]
+fn inspector_shows_tree_and_picks_without_pressing() {
+    let mut gui = HeadlessGui::new((300, 200));
+    let font = gui.font(12);
+    let clicks = Rc::new(Cell::new(0));
+    let mut root = Container::new(flexible(), ContainerLayout::Vertical);
+    {
+        let clicks = clicks.clone();
+        root.add_child(
+            Button::new(fixed(80, 20), "Ok".to_string(), font.clone())
+                .callback(move || clicks.set(clicks.get() + 1)),
+        );
+    }
+    root.add_child(ListBox::new(flexible(), 10, font.clone()));
+    gui.set_root(root);
+    gui.gui_system.set_inspector_font(font);
+    gui.render();
+
+    let tree = gui.gui_system.get_debug_tree();
+    assert_eq!(tree.len(), 1);
+    assert_eq!(tree[0].type_name, "Container");
+    let names: Vec<_> = tree[0].children.iter().map(|node| node.type_name).collect();
+    assert_eq!(names, ["Button", "ListBox"]);
+    assert_eq!(tree[0].children[1].children[0].type_name, "ScrollV");
+    assert_eq!(tree[0].children[0].rect.right_bottom, (80, 20));
+    assert_eq!(tree[0].find_path((10, 10)), [0]);
+    assert_eq!(tree[0].dump().lines().count(), 4);
+
+    gui.gui_system.show_inspector(true);
+    gui.click((10, 10));
+    assert_eq!(clicks.get(), 0);
+    // the picked button is outlined
+    let image = gui.render().as_view();
+    assert_eq!(image[10][0], image[10][79]);
+    assert_ne!(image[10][0], image[10][40]);
+
+    gui.gui_system.show_inspector(false);
+    gui.click((10, 10));
+    assert_eq!(clicks.get(), 1);
+}
+
+#[test]
+fn message_box_returns_choice_and_blocks_clicks() {
+    let mut gui = HeadlessGui::new((400, 300));
+    let font = gui.font(12);
+    let clicks = Rc::new(Cell::new(0));
+    let mut root = Container::new(flexible(), ContainerLayout::Vertical);
+    {
+        let clicks = clicks.clone();
+        root.add_child(
+            Button::new(fixed(80, 20), "Ok".to_string(), font.clone())
+                .callback(move || clicks.set(clicks.get() + 1)),
+        );
+    }
+    gui.set_root(root);
+    gui.gui_system.set_message_box_font(font);
+    gui.render();
+
+    let choices = Rc::new(RefCell::new(Vec::new()));
+    let show = |gui: &mut HeadlessGui, message_box: MessageBox| {
+        let choices = choices.clone();
+        gui.gui_system
+            .show_message_box(message_box, move |index| choices.borrow_mut().push(index));
+        gui.render();
+    };
+
+    show(
+        &mut gui,
+        MessageBox::yes_no_cancel("Сохранение", "Сохранить?").details("Первая\nВторая"),
+    );
+    assert!(gui.gui_system.has_message_box());
+    gui.click((10, 10));
+    assert_eq!(clicks.get(), 0);
+    assert!(gui.find_node(AccessibleRole::Text, "Вторая").is_none());
+    assert!(gui.click_node(AccessibleRole::Button, "Подробности"));
+    gui.render();
+    assert!(gui.find_node(AccessibleRole::Text, "Вторая").is_some());
+    assert!(gui.click_node(AccessibleRole::Button, "Нет"));
+    gui.render();
+    assert!(!gui.gui_system.has_message_box());
+    assert_eq!(*choices.borrow(), [MessageBox::NO]);
+
+    // the default button is focused, Enter presses it
+    show(
+        &mut gui,
+        MessageBox::yes_no_cancel("", "").default_button(1),
+    );
+    gui.key(Key::Enter);
+    gui.render();
+    show(&mut gui, MessageBox::yes_no_cancel("", ""));
+    gui.key(Key::Escape);
+    gui.render();
+    show(&mut gui, MessageBox::yes_no("", ""));
+    gui.key(Key::Escape);
+    gui.render();
+    assert!(gui.gui_system.has_message_box());
+    assert_eq!(
+        *choices.borrow(),
+        [MessageBox::NO, MessageBox::NO, MessageBox::CANCEL]
+    );
+
+    assert!(gui.click_node(AccessibleRole::Button, "Да"));
+    gui.render();
+    gui.click((10, 10));
+    assert_eq!(clicks.get(), 1);
+}
+
+#[test]
+fn message_box_check_box_is_reported_unless_cancelled() {
+    let mut gui = HeadlessGui::new((400, 300));
+    let font = gui.font(12);
+    gui.set_root(Container::new(flexible(), ContainerLayout::Vertical));
+    gui.gui_system.set_message_box_font(font);
+    gui.render();
+
+    let checks = Rc::new(RefCell::new(Vec::new()));
+    let show = |gui: &mut HeadlessGui| {
+        let checks = checks.clone();
+        gui.gui_system.show_message_box(
+            MessageBox::ok_cancel("", "Удалить?")
+                .check_box("Больше не спрашивать", move |checked| {
+                    checks.borrow_mut().push(checked)
+                }),
+            |_| {},
+        );
+        gui.render();
+        assert!(gui.click_node(AccessibleRole::CheckBox, "Больше не спрашивать"));
+    };
+
+    show(&mut gui);
+    gui.key(Key::Escape);
+    gui.render();
+    assert!(checks.borrow().is_empty());
+
+    show(&mut gui);
+    assert!(gui.click_node(AccessibleRole::Button, "ОК"));
+    gui.render();
+    assert_eq!(*checks.borrow(), [true]);
+}
+
+#[test]
+fn toasts_expire_on_timer() {
+    let mut gui = HeadlessGui::new((300, 200));
+    let font = gui.font(12);
+    gui.set_root(Container::new(flexible(), ContainerLayout::Vertical));
+    gui.gui_system.set_toast_font(font);
+    gui.gui_system
+        .show_toast(Toast::warning("Expired").duration(Duration::ZERO));
+    gui.gui_system.show_toast(Toast::error("Kept"));
+
+    assert!(gui.gui_system.on_timer());
+    let toasts = gui.gui_system.get_toasts();
+    assert_eq!(toasts.len(), 1);
+    assert_eq!(toasts[0].get_kind(), ToastKind::Error);
+    assert_eq!(toasts[0].get_text(), "Kept");
+}
+
+#[test]
+fn scroll_arrows_and_auto_repeat() {
+    let mut gui = HeadlessGui::new((100, 100));
+    let positions = Rc::new(RefCell::new(Vec::new()));
+    let mut root = Container::new(flexible(), ContainerLayout::Horizontal);
+    let scroll = {
+        let positions = positions.clone();
+        root.add_child(
+            ScrollV::new(fixed(10, 100))
+                .arrow_buttons(true)
+                .scroll_callback(move |position| positions.borrow_mut().push(position)),
+        )
+    };
+    scroll.borrow_mut().set_range(20, 5);
+    scroll.borrow_mut().set_scroll_position(0);
+    gui.set_root(root);
+    gui.render();
+
+    gui.click((5, 95));
+    assert_eq!(scroll.borrow().get_scroll_position(), 1);
+    gui.click((5, 80));
+    assert_eq!(scroll.borrow().get_scroll_position(), 6);
+    gui.click((5, 5));
+    assert_eq!(scroll.borrow().get_scroll_position(), 5);
+    assert_eq!(*positions.borrow(), vec![1, 6, 5]);
+
+    gui.mouse_move((5, 95));
+    gui.mouse_down((5, 95));
+    assert!(!gui.gui_system.on_timer());
+    std::thread::sleep(Duration::from_millis(450));
+    assert!(gui.gui_system.on_timer());
+    gui.run_jobs();
+    gui.mouse_up((5, 95));
+    assert!(!gui.gui_system.on_timer());
+    assert_eq!(scroll.borrow().get_scroll_position(), 7);
+    assert_eq!(*positions.borrow(), vec![1, 6, 5, 6, 7]);
+}
+
+#[test]
+fn list_box_scrolls_smoothly_by_keys() {
+    let mut gui = HeadlessGui::new((100, 60));
+    let font = gui.font(12);
+    let mut list = ListBox::new(flexible(), 10, font);
+    list.lines = (0..50).map(|i| format!("Line {}", i)).collect();
+    gui.set_root(list);
+    gui.render();
+    gui.click((50, 30));
+    gui.render();
+    let first_visible = |gui: &HeadlessGui| {
+        gui.accessible_nodes()
+            .into_iter()
+            .find(|node| node.role == AccessibleRole::ListItem && node.rect.right_bottom.1 > 0)
+            .map(|node| node.name)
+    };
+
+    assert!(gui.key(Key::Home));
+    assert!(gui.key(Key::Down));
+    assert_eq!(first_visible(&gui), Some("Line 1".to_string()));
+    assert!(gui.key(Key::End));
+    let last_page = first_visible(&gui).unwrap();
+    assert!(gui.key(Key::PageDown));
+    assert_eq!(first_visible(&gui), Some(last_page));
+
+    // the shown lines move to the new position during several timer ticks
+    std::thread::sleep(Duration::from_millis(20));
+    assert!(gui.gui_system.on_timer());
+    gui.render();
+    std::thread::sleep(Duration::from_millis(600));
+    assert!(gui.gui_system.on_timer());
+    gui.render();
+    assert!(!gui.gui_system.on_timer());
+}
+
+#[test]
+fn text_box_link_run_runs_callback() {
+    let mut gui = HeadlessGui::new((200, 100));
+    let font = gui.font(12);
+    let clicks = Rc::new(Cell::new(0));
+    let mut root = Container::new(flexible(), ContainerLayout::Vertical);
+    let (plain_width, height) = font.get_size("Text ");
+    {
+        let clicks = clicks.clone();
+        root.add_child(TextBox::new(fixed(200, 20), String::new(), font).runs(vec![
+            TextRun::new("Text ").bold(),
+            TextRun::link("link", move || clicks.set(clicks.get() + 1)),
+        ]));
+    }
+    gui.set_root(root);
+    gui.render();
+
+    assert!(gui.find_node(AccessibleRole::Text, "Text link").is_some());
+    let link_x = height as i32 / 2 + plain_width as i32 + 3;
+    gui.click((link_x, 10));
+    assert_eq!(clicks.get(), 1);
+
+    gui.click((height as i32 / 2 + 1, 10));
+    assert_eq!(clicks.get(), 1);
+}
+
+#[test]
+fn link_opens_target_with_hand_cursor() {
+    let mut gui = HeadlessGui::new((200, 100));
+    let font = gui.font(12);
+    let mut root = Container::new(flexible(), ContainerLayout::Vertical);
+    root.add_child(Link::new(
+        fixed(120, 20),
+        "Site".to_string(),
+        "https://example.com".to_string(),
+        font,
+        gui.shell.clone(),
+    ));
+    gui.set_root(root);
+    gui.render();
+
+    assert_eq!(gui.gui_system.get_cursor((10, 10)), MouseCursor::Hand);
+    assert_eq!(gui.gui_system.get_cursor((150, 50)), MouseCursor::Arrow);
+
+    assert!(gui.click_node(AccessibleRole::Link, "Site"));
+    assert_eq!(
+        gui.opened_targets(),
+        vec!["https://example.com".to_string()]
+    );
+
+    gui.gui_system.move_focus(true);
+    gui.key(Key::Enter);
+    assert_eq!(gui.opened_targets().len(), 2);
+}
+
+#[derive(Debug)]
+struct FailingShell;
+
+impl ShellHandler for FailingShell {
+    fn open(&mut self, target: &str) -> Result<(), String> {
+        Err(format!("no handler for {}", target))
+    }
+}
+
+#[test]
+fn link_reports_open_error() {
+    let mut gui = HeadlessGui::new((200, 100));
+    let font = gui.font(12);
+    let errors = Rc::new(RefCell::new(Vec::new()));
+    let mut root = Container::new(flexible(), ContainerLayout::Vertical);
+    {
+        let errors = errors.clone();
+        root.add_child(
+            Link::new(
+                fixed(120, 20),
+                "Folder".to_string(),
+                "/missing".to_string(),
+                font,
+                Shell::new(FailingShell),
+            )
+            .error_callback(move |e| errors.borrow_mut().push(e.to_string())),
+        );
+    }
+    gui.set_root(root);
+    gui.render();
+
+    assert!(gui.click_node(AccessibleRole::Link, "Folder"));
+    assert_eq!(
+        *errors.borrow(),
+        vec!["no handler for /missing".to_string()]
+    );
+}
+
+#[test]
+fn auto_repeat_button_fires_while_held() {
+    let mut gui = HeadlessGui::new((100, 100));
+    let font = gui.font(12);
+    let clicks = Rc::new(Cell::new(0));
+    let mut root = Container::new(flexible(), ContainerLayout::Vertical);
+    {
+        let clicks = clicks.clone();
+        root.add_child(
+            Button::new(fixed(80, 20), "+".to_string(), font)
+                .auto_repeat()
+                .callback(move || clicks.set(clicks.get() + 1)),
+        );
+    }
+    gui.set_root(root);
+    gui.render();
+
+    gui.click((10, 10));
+    assert_eq!(clicks.get(), 1);
+
+    gui.mouse_move((10, 10));
+    gui.mouse_down((10, 10));
+    assert!(gui.gui_system.on_timer());
+    gui.run_jobs();
+    assert_eq!(clicks.get(), 2);
+    assert!(!gui.gui_system.on_timer());
+    std::thread::sleep(Duration::from_millis(450));
+    assert!(gui.gui_system.on_timer());
+    gui.run_jobs();
+    assert_eq!(clicks.get(), 3);
+    gui.mouse_up((10, 10));
+    gui.run_jobs();
+    assert_eq!(clicks.get(), 3);
+}
+
+#[test]
+fn toggle_button_keeps_state() {
+    let mut gui = HeadlessGui::new((100, 100));
+    let font = gui.font(12);
+    let states = Rc::new(RefCell::new(Vec::new()));
+    let mut root = Container::new(flexible(), ContainerLayout::Vertical);
+    let button = {
+        let states = states.clone();
+        root.add_child(
+            Button::new(fixed(80, 20), "Grid".to_string(), font)
+                .toggle_button(false)
+                .toggle_callback(move |state| states.borrow_mut().push(state)),
+        )
+    };
+    gui.set_root(root);
+    gui.render();
+
+    assert!(gui.click_node(AccessibleRole::Button, "Grid"));
+    assert!(button.borrow().is_toggled());
+    let node = gui.find_node(AccessibleRole::Button, "Grid").unwrap();
+    assert_eq!(node.checked, Some(true));
+
+    button.borrow_mut().set_toggled(false);
+    assert!(!button.borrow().is_toggled());
+    assert!(gui.click_node(AccessibleRole::Button, "Grid"));
+    assert_eq!(*states.borrow(), vec![true, true]);
+}
+
+#[test]
+fn controls_follow_bound_properties() {
+    let mut gui = HeadlessGui::new((100, 100));
+    let font = gui.font(12);
+    let show_grid = Property::new(true);
+    let position = Property::new(None::<i32>);
+    let mut root = Container::new(flexible(), ContainerLayout::Vertical);
+    let check_box = root
+        .add_child(Button::new(fixed(80, 20), "Grid".to_string(), font.clone()).check_box(false));
+    let text_box = root.add_child(TextBox::new(fixed(80, 20), String::new(), font));
+    bind_check_box(&check_box, &show_grid);
+    bind_text_box(&text_box, &position, |position| match position {
+        Some(position) => format!("at {}", position),
+        None => "nowhere".to_string(),
+    });
+    gui.set_root(root);
+    gui.render();
+
+    let checked = |gui: &HeadlessGui| {
+        gui.find_node(AccessibleRole::CheckBox, "Grid")
+            .unwrap()
+            .checked
+    };
+    assert_eq!(checked(&gui), Some(true));
+    assert!(gui.click_node(AccessibleRole::CheckBox, "Grid"));
+    assert!(!show_grid.get());
+    show_grid.set(true);
+    assert_eq!(checked(&gui), Some(true));
+
+    assert!(gui.find_node(AccessibleRole::Text, "nowhere").is_some());
+    position.set(Some(5));
+    assert!(gui.find_node(AccessibleRole::Text, "at 5").is_some());
+
+    // the controls are not kept alive by the properties
+    gui.set_root(EmptySpace::new_empty(flexible()));
+    drop((check_box, text_box));
+    position.set(None);
+    assert_eq!(position.subscriber_count(), 0);
+}
+
+#[test]
+fn check_box_hotkey_toggles_it() {
+    let mut gui = HeadlessGui::new((100, 100));
+    let font = gui.font(12);
+    let snap = Property::new(true);
+    let mut root = Container::new(flexible(), ContainerLayout::Vertical);
+    let check_box = root.add_child(
+        Button::new(fixed(80, 20), "Snap".to_string(), font)
+            .check_box(false)
+            .hotkey(Hotkey::new(Key::F9), true),
+    );
+    bind_check_box(&check_box, &snap);
+    gui.set_root(root);
+    gui.render();
+
+    let checked = |gui: &HeadlessGui| {
+        gui.find_node(AccessibleRole::CheckBox, "Snap")
+            .unwrap()
+            .checked
+    };
+    assert!(gui.hotkey(Hotkey::new(Key::F9)));
+    assert!(!snap.get());
+    assert_eq!(checked(&gui), Some(false));
+    assert!(gui.hotkey(Hotkey::new(Key::F9)));
+    assert!(snap.get());
+    assert_eq!(checked(&gui), Some(true));
+}
+
+#[test]
+fn progress_bar_reports_value() {
+    let mut gui = HeadlessGui::new((100, 20));
+    let mut root = Container::new(flexible(), ContainerLayout::Vertical);
+    let progress_bar = root.add_child(ProgressBar::new(flexible()));
+    gui.set_root(root);
+    gui.render();
+    let value = |gui: &HeadlessGui| {
+        gui.find_node(AccessibleRole::ProgressBar, "")
+            .unwrap()
+            .value
+    };
+    assert_eq!(value(&gui).as_deref(), Some("0%"));
+    progress_bar.borrow_mut().set_progress(0.25);
+    gui.render();
+    assert_eq!(value(&gui).as_deref(), Some("25%"));
+    progress_bar.borrow_mut().set_progress(2.0);
+    assert_eq!(progress_bar.borrow().get_progress(), 1.0);
+}
+
+#[test]
+fn tooltip_is_shown_when_mouse_rests() {
+    let mut gui = HeadlessGui::new((200, 100));
+    let font = gui.font(12);
+    let thumbnail = Rc::new(Image::new((16, 8)));
+    let mut root = Container::new(flexible(), ContainerLayout::Vertical);
+    root.add_child(
+        Button::new(fixed(80, 20), "Drawing".to_string(), font.clone())
+            .tooltip(Tooltip::new("drawing.json").image(thumbnail.clone())),
+    );
+    root.add_child(Picture::new(fixed(80, 20), Some(thumbnail), "Preview"));
+    gui.set_root(root);
+    gui.gui_system.set_tooltip_font(font);
+    gui.gui_system.set_tooltip_delay(Duration::ZERO);
+    gui.render();
+    assert!(gui.find_node(AccessibleRole::Image, "Preview").is_some());
+
+    gui.mouse_move((10, 10));
+    assert!(gui.gui_system.on_timer());
+    let tooltip = gui.gui_system.get_tooltip().unwrap();
+    assert_eq!(tooltip.get_text(), "drawing.json");
+    assert_eq!(tooltip.get_image().unwrap().get_size(), (16, 8));
+    gui.render();
+
+    // moving hides the tooltip, the picture has none to show
+    assert!(gui.mouse_move((10, 30)));
+    assert!(gui.gui_system.get_tooltip().is_none());
+    gui.gui_system.on_timer();
+    assert!(gui.gui_system.get_tooltip().is_none());
+}
+
+#[test]
+fn tooltip_image_is_rendered_when_shown() {
+    let mut gui = HeadlessGui::new((200, 100));
+    let font = gui.font(12);
+    let renders = Rc::new(Cell::new(0));
+    let mut root = Container::new(flexible(), ContainerLayout::Vertical);
+    root.add_child(
+        Button::new(fixed(80, 20), "Drawing".to_string(), font.clone()).tooltip(
+            Tooltip::new("drawing.json").image_source({
+                let renders = renders.clone();
+                move || {
+                    renders.set(renders.get() + 1);
+                    Some(Rc::new(Image::new((renders.get(), 8))))
+                }
+            }),
+        ),
+    );
+    gui.set_root(root);
+    gui.gui_system.set_tooltip_font(font);
+    gui.gui_system.set_tooltip_delay(Duration::ZERO);
+    gui.render();
+    assert_eq!(renders.get(), 0);
+
+    for expected_width in [1, 2] {
+        gui.mouse_move((10, 10 + expected_width as i32));
+        assert!(gui.gui_system.on_timer());
+        let tooltip = gui.gui_system.get_tooltip().unwrap();
+        assert_eq!(tooltip.get_image().unwrap().get_size(), (expected_width, 8));
+        gui.render();
+    }
+    assert_eq!(renders.get(), 2);
+}
+
+#[derive(Debug)]
+struct DrawCounter {
+    base: GuiControlBase,
+    draws: Rc<Cell<usize>>,
+}
+
+impl GuiControl for DrawCounter {
+    fn get_base_mut(&mut self) -> &mut GuiControlBase {
+        &mut self.base
+    }
+
+    fn on_message(&mut self, m: GuiMessage) -> bool {
+        if let GuiMessage::Draw(_, _, force) = m {
+            if self.base.can_draw(force) {
+                self.draws.set(self.draws.get() + 1);
+            }
+        }
+        false
+    }
+}
+
+#[test]
+fn regions_are_laid_out_and_redrawn_independently() {
+    let mut gui = HeadlessGui::new((200, 100));
+    let font = gui.font(12);
+    let canvas_draws = Rc::new(Cell::new(0));
+    gui.set_root(DrawCounter {
+        base: GuiControlBase::new(flexible()),
+        draws: canvas_draws.clone(),
+    });
+    let clicks = Rc::new(Cell::new(0));
+    let (_, status_id) = {
+        let clicks = clicks.clone();
+        gui.gui_system.add_region(
+            RegionPlacement::Bottom(20),
+            Button::new(flexible(), "Status".to_string(), font)
+                .callback(move || clicks.set(clicks.get() + 1)),
+        )
+    };
+    gui.render();
+    assert_eq!(canvas_draws.get(), 1);
+    let status_rect = gui.gui_system.get_region_rect(status_id).unwrap();
+    assert_eq!(status_rect.left_top, (0, 80));
+    assert_eq!(status_rect.right_bottom, (200, 100));
+    assert_eq!(gui.get_minimal_size().1, 20);
+
+    gui.click((100, 90));
+    gui.render();
+    assert_eq!(clicks.get(), 1);
+    assert_eq!(canvas_draws.get(), 1);
+
+    gui.gui_system.remove_region(status_id);
+    gui.render();
+    assert_eq!(canvas_draws.get(), 2);
+    assert!(gui.find_node(AccessibleRole::Button, "Status").is_none());
+}
+
+#[test]
+fn damage_covers_only_redrawn_controls() {
+    let mut gui = HeadlessGui::new((200, 100));
+    let font = gui.font(12);
+    gui.set_root(DrawCounter {
+        base: GuiControlBase::new(flexible()),
+        draws: Rc::new(Cell::new(0)),
+    });
+    let (_, status_id) = gui.gui_system.add_region(
+        RegionPlacement::Bottom(20),
+        Button::new(flexible(), "Status".to_string(), font),
+    );
+    gui.render();
+    let status_rect = gui.gui_system.get_region_rect(status_id).unwrap();
+    assert!(gui.gui_system.get_damage().contains(&status_rect));
+
+    gui.render();
+    assert!(gui.gui_system.get_damage().is_empty());
+
+    gui.mouse_move((100, 90));
+    gui.render();
+    assert_eq!(gui.gui_system.get_damage(), &[status_rect]);
+}
+
+#[derive(Debug)]
+struct MouseLog {
+    base: GuiControlBase,
+    events: Rc<RefCell<Vec<String>>>,
+}
+
+impl GuiControl for MouseLog {
+    fn get_base_mut(&mut self) -> &mut GuiControlBase {
+        &mut self.base
+    }
+
+    fn on_message(&mut self, m: GuiMessage) -> bool {
+        let event = match m {
+            GuiMessage::MouseWheel(position, delta) => format!("wheel {:?} {}", position, delta),
+            GuiMessage::MouseWheelWithShift(position, delta) => {
+                format!("shift wheel {:?} {}", position, delta)
+            }
+            GuiMessage::MiddleDoubleClick(position) => format!("middle {:?}", position),
+            _ => return false,
+        };
+        self.events.borrow_mut().push(event);
+        true
+    }
+}
+
+#[test]
+fn wheel_with_shift_and_middle_double_click_reach_control() {
+    let mut gui = HeadlessGui::new((200, 100));
+    let events = Rc::new(RefCell::new(Vec::new()));
+    gui.set_root(MouseLog {
+        base: GuiControlBase::new(flexible()),
+        events: events.clone(),
+    });
+    gui.render();
+
+    assert!(gui.mouse_wheel((10, 20), 1.0));
+    assert!(gui.mouse_wheel_with_shift((30, 40), -1.0));
+    assert!(gui.middle_double_click((50, 60)));
+    assert_eq!(
+        *events.borrow(),
+        vec![
+            "wheel (10, 20) 1".to_string(),
+            "shift wheel (30, 40) -1".to_string(),
+            "middle (50, 60)".to_string(),
+        ]
+    );
+}
+
+#[test]
+fn clipboard_remembers_put_texts() {
+    let mut gui = HeadlessGui::new((100, 100));
+    for i in 0..CLIPBOARD_HISTORY_SIZE + 2 {
+        gui.clipboard.put_string(&i.to_string());
+    }
+    // the text put again moves to the top instead of being repeated
+    gui.clipboard.put_string("5");
+    let history = gui.clipboard.get_history();
+    assert_eq!(history.len(), CLIPBOARD_HISTORY_SIZE);
+    assert_eq!(history[0], "5");
+    assert_eq!(history[1], (CLIPBOARD_HISTORY_SIZE + 1).to_string());
+    assert_eq!(history.iter().filter(|text| *text == "5").count(), 1);
+    assert!(!history.contains(&"0".to_string()));
+    assert_eq!(gui.clipboard.get_string().as_deref(), Some("5"));
+
+    gui.clipboard.clear_history();
+    assert!(gui.clipboard.get_history().is_empty());
+}
@@ -0,0 +1,60 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use curves::points::*;
+use curves::*;
+use rand::{Rng, SeedableRng};
+
+/// Lines, circles and arcs of a drawing about a thousand units wide.
+fn random_drawing(count: usize) -> Vec<Curve<f64>> {
+    let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+    (0..count)
+        .map(|_| {
+            let center = Point::new(rng.gen_range(0.0..1000.0), rng.gen_range(0.0..1000.0));
+            let radius = rng.gen_range(0.1..100.0);
+            let begin = center + Point::angle(rng.gen_range(0.0..6.0)).scale(radius);
+            match rng.gen_range(0..3) {
+                0 => Curve::Segment(Segment::line(center, begin)),
+                1 => Curve::Contour(Contour::circle(center, radius)),
+                _ => Curve::Segment(Segment {
+                    contour: Contour::circle(center, radius),
+                    begin,
+                    end: center + (begin - center).rot90(),
+                    big: false,
+                }),
+            }
+        })
+        .collect()
+}
+
+fn bench_hit_test(c: &mut Criterion) {
+    let drawing = random_drawing(10_000);
+    let positions: Vec<_> = (0..100)
+        .map(|i| Point::new(i as f64 * 10.0, 1000.0 - i as f64 * 10.0))
+        .collect();
+    c.bench_function("hit_test_10k", |b| {
+        b.iter(|| {
+            for position in &positions {
+                let nearest = drawing
+                    .iter()
+                    .map(|curve| curve.distance(*position).abs())
+                    .enumerate()
+                    .min_by(|(_, a), (_, b)| a.total_cmp(b));
+                black_box(nearest);
+            }
+        })
+    });
+}
+
+fn bench_rect_select(c: &mut Criterion) {
+    let drawing = random_drawing(10_000);
+    c.bench_function("rect_select_10k", |b| {
+        b.iter(|| {
+            drawing
+                .iter()
+                .filter(|curve| curve.in_rect(Point::new(200.0, 300.0), Point::new(700.0, 600.0)))
+                .count()
+        })
+    });
+}
+
+criterion_group!(benches, bench_hit_test, bench_rect_select);
+criterion_main!(benches);
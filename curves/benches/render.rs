@@ -0,0 +1,85 @@
+use application::image::*;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use curves::points::*;
+use curves::render::*;
+use curves::*;
+use rand::{Rng, SeedableRng};
+
+const SIZE: usize = 1024;
+
+fn arc(center: Point<f32>, radius: f32) -> Curve<f32> {
+    Curve::Segment(Segment {
+        contour: Contour::circle(center, radius),
+        begin: center + Point::new(radius, 0.0),
+        end: center + Point::new(0.0, radius),
+        big: true,
+    })
+}
+
+/// Lines, circles and arcs spread over the image like the entities of a big drawing.
+fn random_scene(count: usize) -> Vec<Curve<f32>> {
+    let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+    let size = SIZE as f32;
+    (0..count)
+        .map(|_| {
+            let center = Point::new(rng.gen_range(0.0..size), rng.gen_range(0.0..size));
+            let radius = rng.gen_range(1.0..size / 8.0);
+            match rng.gen_range(0..3) {
+                0 => {
+                    let end = center + Point::angle(rng.gen_range(0.0..6.0)).scale(radius);
+                    Curve::Segment(Segment::line(center, end))
+                }
+                1 => Curve::Contour(Contour::circle(center, radius)),
+                _ => arc(center, radius),
+            }
+        })
+        .collect()
+}
+
+fn bench_draw_locc(c: &mut Criterion) {
+    let mut dst = Image::<u32>::new((SIZE, SIZE));
+    let center = Point::new(SIZE as f32 / 2.0, SIZE as f32 / 2.0);
+    let curves = [
+        (
+            "line",
+            Curve::Segment(Segment::line(
+                Point::new(10.0, 20.0),
+                Point::new(1000.0, 900.0),
+            )),
+        ),
+        ("circle", Curve::Contour(Contour::circle(center, 400.0))),
+        ("arc", arc(center, 400.0)),
+    ];
+    let mut group = c.benchmark_group("draw_locc");
+    for aa in [1, 2, 4] {
+        let mut span_buffer = vec![(0, 0); SIZE * aa];
+        for (name, curve) in &curves {
+            group.bench_with_input(BenchmarkId::new(*name, aa), &aa, |b, &aa| {
+                b.iter(|| draw_locc(&mut dst.as_view_mut(), curve, 0, 1.0, &mut span_buffer, aa))
+            });
+        }
+    }
+    group.finish();
+}
+
+fn bench_scene(c: &mut Criterion) {
+    let mut dst = Image::<u32>::new((SIZE, SIZE));
+    let scene = random_scene(10_000);
+    let mut group = c.benchmark_group("scene_10k");
+    group.sample_size(10);
+    for aa in [1, 2, 4] {
+        let mut span_buffer = vec![(0, 0); SIZE * aa];
+        group.bench_with_input(BenchmarkId::from_parameter(aa), &aa, |b, &aa| {
+            b.iter(|| {
+                dst.as_view_mut().fill(|p| *p = 0xFFFFFF);
+                for curve in &scene {
+                    draw_locc(&mut dst.as_view_mut(), curve, 0, 1.0, &mut span_buffer, aa);
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_draw_locc, bench_scene);
+criterion_main!(benches);
@@ -88,6 +88,15 @@ impl<T: Float> Contour<T> {
         }
     }
 
+    /// Mirrors across the X axis, the direction along the contour turns the other way.
+    pub fn flip_y(&self) -> Self {
+        Self {
+            a: self.a,
+            n: Point::new(self.n.x, -self.n.y),
+            c: self.c,
+        }
+    }
+
     pub fn translate(&self, delta: Point<T>) -> Self {
         let a = self.a;
         let n = self.n - delta.scale(a + a);
@@ -191,6 +200,66 @@ impl<T: Float> Contour<T> {
             && self.check_top(T::min(corner1.y, corner2.y))
     }
 
+    /// Points of a circle on the horizontal and vertical lines through its center, from
+    /// the rightmost one counterclockwise. Lines have none.
+    pub fn get_quadrant_points(&self) -> Vec<Point<T>> {
+        if self.a == T::zero() {
+            return Vec::new();
+        }
+        let center = self.get_center();
+        let radius = self.get_radius().abs();
+        vec![
+            center + Point::new(radius, T::zero()),
+            center + Point::new(T::zero(), radius),
+            center - Point::new(radius, T::zero()),
+            center - Point::new(T::zero(), radius),
+        ]
+    }
+
+    /// Part of an infinite line inside the rectangle, it goes the same way as the line.
+    /// None for circles and for the lines which miss the rectangle.
+    pub fn clip_line(&self, corner1: Point<T>, corner2: Point<T>) -> Option<Segment<T>> {
+        let sqr_length = self.n.sqr_length();
+        if self.a != T::zero() || sqr_length == T::zero() {
+            return None;
+        }
+        let origin = self.n.scale(-self.c / sqr_length);
+        let direction = self.n.rot90();
+        let mut from = T::neg_infinity();
+        let mut to = T::infinity();
+        for (o, d, c1, c2) in [
+            (origin.x, direction.x, corner1.x, corner2.x),
+            (origin.y, direction.y, corner1.y, corner2.y),
+        ] {
+            if d == T::zero() {
+                if o < c1.min(c2) || o > c1.max(c2) {
+                    return None;
+                }
+                continue;
+            }
+            let (t1, t2) = ((c1 - o) / d, (c2 - o) / d);
+            from = from.max(t1.min(t2));
+            to = to.min(t1.max(t2));
+        }
+        if from >= to {
+            return None;
+        }
+        Some(Segment {
+            contour: *self,
+            begin: origin + direction.scale(from),
+            end: origin + direction.scale(to),
+            big: false,
+        })
+    }
+
+    /// Point of a circle at the arc length from its rightmost point, in the direction
+    /// of the contour.
+    fn circle_point_at(&self, length: T) -> Point<T> {
+        let radius = self.get_radius();
+        let start = Point::new(radius.abs(), T::zero());
+        self.get_center() + complex_mul(start, Point::angle(length / radius))
+    }
+
     pub fn sqr_distance_to_center(&self, position: Point<T>, sqr_max: T) -> Option<(T, Point<T>)> {
         //(p-n/2a).length()
         let factor = self.a * T::from(2.0).unwrap();
@@ -253,6 +322,119 @@ impl<T: Float> Segment<T> {
         }
     }
 
+    /// Rotates around zero, direction is the unit vector of the angle.
+    pub fn rotate(self, direction: Point<T>) -> Self {
+        Self {
+            contour: self.contour.complex_mul(direction),
+            begin: complex_mul(self.begin, direction),
+            end: complex_mul(self.end, direction),
+            big: self.big,
+        }
+    }
+
+    /// Mirrors across the X axis, the ends swap because the contour turns the other way.
+    pub fn flip_y(self) -> Self {
+        let flip = |p: Point<T>| Point::new(p.x, -p.y);
+        Self {
+            contour: self.contour.flip_y(),
+            begin: flip(self.end),
+            end: flip(self.begin),
+            big: self.big,
+        }
+    }
+
+    /// The same curve walked from the end to the begin, an arc keeps its sweep.
+    pub fn reversed(&self) -> Self {
+        Self {
+            contour: self.contour.neg(),
+            begin: self.end,
+            end: self.begin,
+            big: self.big,
+        }
+    }
+
+    /// Angle of the arc in radians in the direction from the begin to the end, lines
+    /// have zero.
+    pub fn get_sweep(&self) -> T {
+        if self.contour.a == T::zero() {
+            return T::zero();
+        }
+        // the radius is negative for the arcs going clockwise
+        let radius = self.contour.get_radius();
+        let center = self.contour.get_center();
+        let (begin, end) = (self.begin - center, self.end - center);
+        let sweep = cross(begin, end).atan2(dot(begin, end)) * radius.signum();
+        if sweep < T::zero() || (sweep == T::zero() && self.big) {
+            sweep + T::from(std::f64::consts::TAU).unwrap()
+        } else {
+            sweep
+        }
+    }
+
+    pub fn get_length(&self) -> T {
+        if self.contour.a == T::zero() {
+            (self.end - self.begin).length()
+        } else {
+            self.get_sweep() * self.contour.get_radius().abs()
+        }
+    }
+
+    /// Point at the arc length from the begin, the negative length goes back beyond it.
+    pub fn point_at(&self, length: T) -> Point<T> {
+        if self.contour.a == T::zero() {
+            // the segment goes from the begin to the end along rot90 of the gradient
+            let direction = self.begin_direction().rot90().normalize();
+            self.begin + direction.scale(length)
+        } else {
+            let center = self.contour.get_center();
+            let rotation = Point::angle(length / self.contour.get_radius());
+            center + complex_mul(self.begin - center, rotation)
+        }
+    }
+
+    pub fn get_midpoint(&self) -> Point<T> {
+        self.point_at(self.get_length() / T::from(2.0).unwrap())
+    }
+
+    /// Quadrant points of the circle which lie on the arc.
+    pub fn get_quadrant_points(&self) -> Vec<Point<T>> {
+        self.contour
+            .get_quadrant_points()
+            .into_iter()
+            .filter(|p| self.inside_sector(*p, T::zero(), false))
+            .collect()
+    }
+
+    /// Moves the ends along the curve by the arc lengths, positive deltas make the segment
+    /// longer. Returns None if the segment vanishes or the arc closes on itself.
+    pub fn extended(&self, delta_begin: T, delta_end: T) -> Option<Self> {
+        if self.contour.a == T::zero() {
+            if self.get_length() + delta_begin + delta_end <= T::zero() {
+                return None;
+            }
+            let direction = self.begin_direction().rot90().normalize();
+            return Some(Self {
+                begin: self.point_at(-delta_begin),
+                end: self.end + direction.scale(delta_end),
+                ..*self
+            });
+        }
+
+        let tau = T::from(std::f64::consts::TAU).unwrap();
+        let radius = self.contour.get_radius();
+        let sweep = self.get_sweep() + (delta_begin + delta_end) / radius.abs();
+        if sweep <= T::zero() || sweep >= tau {
+            return None;
+        }
+        let center = self.contour.get_center();
+        Some(Self {
+            contour: self.contour,
+            begin: self.point_at(-delta_begin),
+            end: center + complex_mul(self.end - center, Point::angle(delta_end / radius)),
+            big: sweep > T::from(std::f64::consts::PI).unwrap(),
+        })
+    }
+
     pub fn inside_sector(&self, x: Point<T>, eps: T, strong: bool) -> bool {
         let mut eps = eps;
         if strong {
@@ -292,22 +474,10 @@ impl<T: Float> Segment<T> {
             return false;
         }
 
-        let begin_l = self.begin.x * T::from(2.0).unwrap() * self.contour.a < self.contour.n.x;
-        let begin_t = self.begin.y * T::from(2.0).unwrap() * self.contour.a < self.contour.n.y;
-        let end_l = self.begin.x * T::from(2.0).unwrap() * self.contour.a < self.contour.n.x;
-        let end_t = self.begin.y * T::from(2.0).unwrap() * self.contour.a < self.contour.n.y;
-
-        if self.big {
-            ((begin_t && !end_t) || self.contour.check_right(x2))
-                && ((!begin_l && end_l) || self.contour.check_bottom(x2))
-                && ((!begin_t && end_t) || self.contour.check_left(x2))
-                && ((begin_l && !end_l) || self.contour.check_top(x2))
-        } else {
-            (begin_t || !end_t || self.contour.check_right(x2))
-                && (!begin_l || end_l || self.contour.check_bottom(x2))
-                && (!begin_t || end_t || self.contour.check_left(x2))
-                && (begin_l || !end_l || self.contour.check_top(x2))
-        }
+        // the arc goes out of the rectangle of its ends only at the quadrant points
+        self.get_quadrant_points()
+            .into_iter()
+            .all(|p| p.x >= x1 && p.x <= x2 && p.y >= y1 && p.y <= y2)
     }
 }
 
@@ -341,6 +511,30 @@ impl<T: Float> Curve<T> {
         }
     }
 
+    /// Rotates around zero, direction is the unit vector of the angle.
+    pub fn rotate(&self, direction: Point<T>) -> Self {
+        match self {
+            Contour(c) => Contour(c.complex_mul(direction)),
+            Segment(s) => Segment(s.rotate(direction)),
+        }
+    }
+
+    /// Mirrors across the X axis.
+    pub fn flip_y(&self) -> Self {
+        match self {
+            Contour(c) => Contour(c.flip_y()),
+            Segment(s) => Segment(s.flip_y()),
+        }
+    }
+
+    /// The direction along the curve turns the other way, the points stay.
+    pub fn reversed(&self) -> Self {
+        match self {
+            Contour(c) => Contour(c.neg()),
+            Segment(s) => Segment(s.reversed()),
+        }
+    }
+
     pub fn distance(&self, x: Point<T>) -> T {
         match self {
             Contour(c) => c.distance(x),
@@ -354,11 +548,78 @@ impl<T: Float> Curve<T> {
             Segment(s) => s.in_rect(corner1, corner2),
         }
     }
+
+    /// Middle of a segment, closed and infinite curves have none.
+    pub fn get_midpoint(&self) -> Option<Point<T>> {
+        match self {
+            Contour(_) => None,
+            Segment(s) => Some(s.get_midpoint()),
+        }
+    }
+
+    pub fn get_quadrant_points(&self) -> Vec<Point<T>> {
+        match self {
+            Contour(c) => c.get_quadrant_points(),
+            Segment(s) => s.get_quadrant_points(),
+        }
+    }
+
+    /// Length of a segment or a circle, infinite lines have none.
+    pub fn get_length(&self) -> Option<T> {
+        match self {
+            Contour(c) if c.a == T::zero() => None,
+            Contour(c) => Some(c.get_radius().abs() * T::from(std::f64::consts::TAU).unwrap()),
+            Segment(s) => Some(s.get_length()),
+        }
+    }
+
+    /// Point at the arc length from the begin of a segment or from the rightmost point of a
+    /// circle.
+    fn point_at(&self, length: T) -> Point<T> {
+        match self {
+            Contour(c) => c.circle_point_at(length),
+            Segment(s) => s.point_at(length),
+        }
+    }
+
+    /// Points splitting the curve into parts of the same length. The ends of a segment are
+    /// not among them, a circle is split from its rightmost point.
+    pub fn divide(&self, count: usize) -> Vec<Point<T>> {
+        let length = match self.get_length() {
+            Some(length) if count > 0 => length,
+            _ => return Vec::new(),
+        };
+        let first = match self {
+            Contour(_) => 0,
+            Segment(_) => 1,
+        };
+        (first..count)
+            .map(|i| self.point_at(length * T::from(i).unwrap() / T::from(count).unwrap()))
+            .collect()
+    }
+
+    /// Points at the steps of the arc length from the begin, as `divide` does for the ends.
+    pub fn measure(&self, step: T) -> Vec<Point<T>> {
+        let length = match self.get_length() {
+            Some(length) if step > T::zero() => length,
+            _ => return Vec::new(),
+        };
+        let first = match self {
+            Contour(_) => 0,
+            Segment(_) => 1,
+        };
+        (first..)
+            .map(|i| step * T::from(i).unwrap())
+            .take_while(|distance| *distance < length)
+            .map(|distance| self.point_at(distance))
+            .collect()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     #[test]
     fn test_circle() {
@@ -383,4 +644,305 @@ mod tests {
         let another_radius = curve.change_radius(-0.001).unwrap();
         assert!((another_radius.discriminant() - 1.0).abs() < 0.0001);
     }
+
+    #[test]
+    fn test_extended() {
+        let line = Segment::<f64>::line(Point::new(0.0, 0.0), Point::new(2.0, 0.0));
+        let extended = line.extended(1.0, -0.5).unwrap();
+        assert!((extended.begin - Point::new(-1.0, 0.0)).length() < 1.0e-9);
+        assert!((extended.end - Point::new(1.5, 0.0)).length() < 1.0e-9);
+        assert!(line.extended(-1.0, -1.0).is_none());
+
+        // quarter of the unit circle going counterclockwise
+        let arc = Segment {
+            contour: Contour::<f64>::circle(Point::new(0.0, 0.0), 1.0),
+            begin: Point::new(1.0, 0.0),
+            end: Point::new(0.0, 1.0),
+            big: false,
+        };
+        let quarter = std::f64::consts::FRAC_PI_2;
+        let extended = arc.extended(quarter, quarter).unwrap();
+        assert!((extended.begin - Point::new(0.0, -1.0)).length() < 1.0e-9);
+        assert!((extended.end - Point::new(-1.0, 0.0)).length() < 1.0e-9);
+        assert!(extended.big);
+        let reversed = arc.reversed();
+        let extended = reversed.extended(0.0, quarter).unwrap();
+        assert!((extended.end - Point::new(0.0, -1.0)).length() < 1.0e-9);
+        assert!(!extended.big);
+        assert!(arc.extended(-quarter, 0.0).is_none());
+        assert!(arc.extended(quarter, quarter * 2.0).is_none());
+    }
+
+    #[test]
+    fn test_reversed() {
+        // three quarters of the unit circle going clockwise
+        let arc = Segment {
+            contour: Contour::<f64>::circle(Point::new(0.0, 0.0), 1.0).neg(),
+            begin: Point::new(1.0, 0.0),
+            end: Point::new(0.0, 1.0),
+            big: true,
+        };
+        let reversed = arc.reversed();
+        assert!((reversed.begin - arc.end).length() < 1.0e-9);
+        assert!((reversed.end - arc.begin).length() < 1.0e-9);
+        assert!((reversed.get_sweep() - arc.get_sweep()).abs() < 1.0e-9);
+        assert!((reversed.get_midpoint() - arc.get_midpoint()).length() < 1.0e-9);
+        assert!(reversed.contour.get_radius() > 0.0);
+        let back = reversed.reversed();
+        assert!((back.begin_direction() - arc.begin_direction()).length() < 1.0e-9);
+
+        let line = Segment::<f64>::line(Point::new(0.0, 0.0), Point::new(2.0, 0.0));
+        let reversed = line.reversed();
+        assert!((reversed.point_at(0.5) - Point::new(1.5, 0.0)).length() < 1.0e-9);
+        assert!((reversed.get_midpoint() - line.get_midpoint()).length() < 1.0e-9);
+    }
+
+    #[test]
+    fn test_clip_line() {
+        let corner1 = Point::new(0.0, 0.0);
+        let corner2 = Point::new(4.0, 2.0);
+        let line = Contour::<f64>::line(Point::new(-1.0, -1.0), Point::new(1.0, 1.0));
+        let clipped = line.clip_line(corner1, corner2).unwrap();
+        assert!((clipped.begin - Point::new(0.0, 0.0)).length() < 1.0e-9);
+        assert!((clipped.end - Point::new(2.0, 2.0)).length() < 1.0e-9);
+        let reversed = line.neg().clip_line(corner2, corner1).unwrap();
+        assert!((reversed.begin - Point::new(2.0, 2.0)).length() < 1.0e-9);
+
+        let vertical = Contour::<f64>::line(Point::new(3.0, 0.0), Point::new(3.0, 1.0));
+        let clipped = vertical.clip_line(corner1, corner2).unwrap();
+        assert!((clipped.begin - Point::new(3.0, 0.0)).length() < 1.0e-9);
+        assert!((clipped.end - Point::new(3.0, 2.0)).length() < 1.0e-9);
+
+        let outside = Contour::<f64>::line(Point::new(5.0, 0.0), Point::new(6.0, 1.0));
+        assert!(outside.clip_line(corner1, corner2).is_none());
+        let circle = Contour::<f64>::circle(Point::new(1.0, 1.0), 1.0);
+        assert!(circle.clip_line(corner1, corner2).is_none());
+    }
+
+    #[test]
+    fn test_division_points() {
+        // half of the unit circle going counterclockwise from the top
+        let arc = Segment {
+            contour: Contour::<f64>::circle(Point::new(0.0, 0.0), 1.0),
+            begin: Point::new(0.0, 1.0),
+            end: Point::new(0.0, -1.0),
+            big: false,
+        };
+        assert!((arc.get_length() - std::f64::consts::PI).abs() < 1.0e-9);
+        assert!((arc.get_midpoint() - Point::new(-1.0, 0.0)).length() < 1.0e-9);
+        let quadrants = arc.get_quadrant_points();
+        assert_eq!(quadrants.len(), 1);
+        assert!((quadrants[0] - Point::new(-1.0, 0.0)).length() < 1.0e-9);
+
+        let line = Curve::Segment(Segment::<f64>::line(
+            Point::new(0.0, 0.0),
+            Point::new(3.0, 0.0),
+        ));
+        let points = line.divide(3);
+        assert_eq!(points.len(), 2);
+        assert!((points[1] - Point::new(2.0, 0.0)).length() < 1.0e-9);
+        assert_eq!(line.measure(1.0).len(), 2);
+        assert_eq!(line.measure(0.7).len(), 4);
+
+        let circle = Curve::Contour(Contour::<f64>::circle(Point::new(1.0, 1.0), 1.0));
+        let points = circle.divide(4);
+        assert_eq!(points.len(), 4);
+        for (point, quadrant) in points.iter().zip(circle.get_quadrant_points()) {
+            assert!((*point - quadrant).length() < 1.0e-9);
+        }
+        assert!(Curve::Contour(Contour::<f64>::line(
+            Point::new(0.0, 0.0),
+            Point::new(1.0, 0.0)
+        ))
+        .divide(2)
+        .is_empty());
+    }
+
+    #[test]
+    fn test_flip_y() {
+        let flip = |p: Point<f64>| Point::new(p.x, -p.y);
+        // a quarter going counterclockwise and a line, both go to the bottom half
+        let arc = Segment {
+            contour: Contour::<f64>::circle(Point::new(1.0, 1.0), 1.0),
+            begin: Point::new(2.0, 1.0),
+            end: Point::new(1.0, 2.0),
+            big: false,
+        };
+        let line = Segment::line(Point::new(0.0, 1.0), Point::new(3.0, 2.0));
+        for segment in [arc, line] {
+            let flipped = segment.flip_y();
+            assert!((flipped.get_midpoint() - flip(segment.get_midpoint())).length() < 1.0e-9);
+            assert!((flipped.get_length() - segment.get_length()).abs() < 1.0e-9);
+            assert!(flipped.contour.distance(flip(segment.begin)).abs() < 1.0e-9);
+        }
+        let circle = Contour::<f64>::circle(Point::new(1.0, 1.0), 1.0).flip_y();
+        assert!((circle.get_center() - Point::new(1.0, -1.0)).length() < 1.0e-9);
+    }
+
+    #[test]
+    fn test_rotate() {
+        let arc = Segment {
+            contour: Contour::<f64>::circle(Point::new(1.0, 0.0), 1.0),
+            begin: Point::new(2.0, 0.0),
+            end: Point::new(0.0, 0.0),
+            big: false,
+        };
+        let rotated = arc.rotate(Point::angle(std::f64::consts::FRAC_PI_2));
+        let center = rotated.contour.get_center();
+        assert!((center - Point::new(0.0, 1.0)).length() < 1.0e-9);
+        assert!((rotated.begin - Point::new(0.0, 2.0)).length() < 1.0e-9);
+        assert!(rotated.contour.distance(rotated.end) < 1.0e-9);
+        assert!((rotated.contour.discriminant() - 1.0).abs() < 1.0e-9);
+    }
+
+    /// The coefficients of a known circle after the transforms, computed once by hand.
+    #[test]
+    fn test_golden_transforms() {
+        let circle = Contour::<f64>::circle(Point::new(1.0, 2.0), 0.5);
+        assert_eq!(
+            (circle.a, circle.n.x, circle.n.y, circle.c),
+            (1.0, -2.0, -4.0, 4.75)
+        );
+        let moved = circle.translate(Point::new(-1.0, -2.0));
+        assert!(moved.n.length() < 1.0e-12 && (moved.c + 0.25).abs() < 1.0e-12);
+        let turned = circle.complex_mul(Point::new(0.0, 1.0));
+        assert!((turned.get_center() - Point::new(-2.0, 1.0)).length() < 1.0e-12);
+        let bigger = circle.change_radius(0.5).unwrap();
+        assert!((bigger.get_radius() - 1.0).abs() < 1.0e-12);
+        assert!((bigger.get_center() - Point::new(1.0, 2.0)).length() < 1.0e-12);
+    }
+
+    fn point(range: std::ops::Range<f64>) -> impl Strategy<Value = Point<f64>> {
+        (range.clone(), range).prop_map(|(x, y)| Point::new(x, y))
+    }
+
+    /// Lines and circles with the sizes and the positions of the whole range of drawings.
+    fn contour() -> impl Strategy<Value = Contour<f64>> {
+        prop_oneof![
+            (point(-1.0e4..1.0e4), point(-1.0e4..1.0e4))
+                .prop_filter("different points", |(x, y)| (*x - *y).length() > 1.0e-3)
+                .prop_map(|(x, y)| Contour::line(x, y)),
+            (point(-1.0e4..1.0e4), -4.0..4.0f64, any::<bool>()).prop_map(
+                |(center, size, inner)| {
+                    let circle = Contour::circle(center, 10.0f64.powf(size));
+                    if inner {
+                        circle.neg()
+                    } else {
+                        circle
+                    }
+                }
+            ),
+        ]
+    }
+
+    /// Lines and counterclockwise arcs.
+    fn segment() -> impl Strategy<Value = Segment<f64>> {
+        prop_oneof![
+            (point(-1.0e4..1.0e4), point(-1.0e4..1.0e4))
+                .prop_filter("different points", |(x, y)| (*x - *y).length() > 1.0e-3)
+                .prop_map(|(x, y)| Segment::line(x, y)),
+            (
+                point(-1.0e4..1.0e4),
+                -2.0..4.0f64,
+                0.0..std::f64::consts::TAU,
+                0.01..6.27f64
+            )
+                .prop_map(|(center, size, begin, sweep)| {
+                    let radius = 10.0f64.powf(size);
+                    Segment {
+                        contour: Contour::circle(center, radius),
+                        begin: center + Point::angle(begin).scale(radius),
+                        end: center + Point::angle(begin + sweep).scale(radius),
+                        big: sweep > std::f64::consts::PI,
+                    }
+                }),
+        ]
+    }
+
+    #[derive(Debug, Clone)]
+    enum Transform {
+        Translate(Point<f64>),
+        Rotate(f64),
+        ChangeRadius(f64),
+    }
+
+    fn transform() -> impl Strategy<Value = Transform> {
+        prop_oneof![
+            point(-1.0e4..1.0e4).prop_map(Transform::Translate),
+            (-10.0..10.0f64).prop_map(Transform::Rotate),
+            (-1.0e3..1.0e3f64).prop_map(Transform::ChangeRadius),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn transforms_keep_discriminant(
+            contour in contour(),
+            transforms in prop::collection::vec(transform(), 1..8),
+        ) {
+            let mut contour = contour;
+            for transform in transforms {
+                contour = match transform {
+                    Transform::Translate(delta) => contour.translate(delta),
+                    Transform::Rotate(angle) => contour.complex_mul(Point::angle(angle)),
+                    // a circle can not be smaller than a point
+                    Transform::ChangeRadius(delta) => match contour.change_radius(delta) {
+                        Some(changed) => changed,
+                        None => continue,
+                    },
+                };
+                // the terms of the discriminant are huge for small circles far from zero
+                let terms = contour.n.sqr_length() + (4.0 * contour.a * contour.c).abs();
+                prop_assert!((contour.discriminant() - 1.0).abs() < 1.0e-9 * terms, "{:?}", contour);
+            }
+        }
+
+        #[test]
+        fn distance_is_symmetric(c1 in contour(), c2 in contour(), x in point(-1.0e4..1.0e4)) {
+            let (c1, c2) = (Curve::Contour(c1), Curve::Contour(c2));
+            let (p1, p2) = solver::closest_points(&c1, &c2, 1.0e-9);
+            let (q2, q1) = solver::closest_points(&c2, &c1, 1.0e-9);
+            let scale = 1.0 + x.length();
+            prop_assert!(((p2 - p1).length() - (q2 - q1).length()).abs() < 1.0e-6 * scale);
+            // the distance to the nearest point is the distance to the curve
+            let nearest = solver::nearest_point_on_curve(&c1, x);
+            prop_assert!(((nearest - x).length() - c1.distance(x).abs()).abs() < 1.0e-6 * scale);
+        }
+
+        #[test]
+        fn in_rect_is_conservative(
+            curve in prop_oneof![
+                contour().prop_map(Curve::Contour),
+                segment().prop_map(Curve::Segment),
+            ],
+            margin1 in point(-0.1..0.1),
+            margin2 in point(-0.1..0.1),
+        ) {
+            let mut points = curve.divide(256);
+            if let Curve::Segment(s) = curve {
+                points.extend([s.begin, s.end]);
+            }
+            // an infinite line is in no rectangle
+            let anchor = solver::nearest_point_on_curve(&curve, Point::new(0.0, 0.0));
+            let (mut min, mut max) = (anchor, anchor);
+            for point in &points {
+                min = Point::new(min.x.min(point.x), min.y.min(point.y));
+                max = Point::new(max.x.max(point.x), max.y.max(point.y));
+            }
+            // rectangles a bit smaller or bigger than the curve, the others are obvious
+            let size = (max - min).length() + 1.0;
+            let corner1 = min - margin1.scale(size);
+            let corner2 = max + margin2.scale(size);
+            if curve.in_rect(corner1, corner2) {
+                let eps = 1.0e-9 * (1.0 + corner1.length() + corner2.length());
+                prop_assert!(!points.is_empty());
+                for point in points {
+                    prop_assert!(point.x >= corner1.x.min(corner2.x) - eps);
+                    prop_assert!(point.x <= corner1.x.max(corner2.x) + eps);
+                    prop_assert!(point.y >= corner1.y.min(corner2.y) - eps);
+                    prop_assert!(point.y <= corner1.y.max(corner2.y) + eps);
+                }
+            }
+        }
+    }
 }
@@ -390,6 +390,38 @@ fn use_quadrant_bounds_aa4(
     }
 }
 
+/// Mixes the color into the pixel, the weight is in 1/65536 parts.
+fn blend_pixel(pixel: u32, color: u32, weight: i32) -> u32 {
+    let mut result = 0;
+    for shift in [0, 8, 16, 24] {
+        let p = ((pixel >> shift) & 0xff) as i32;
+        let c = ((color >> shift) & 0xff) as i32;
+        let r = p + (((c - p) * weight) >> 16);
+        result |= ((r as u32) & 0xff) << shift;
+    }
+    result
+}
+
+/// Each sample mixes in its share of the color, so the opaque color gives the same pixels
+/// as the blending of `use_quadrant_bounds_aa2` and `use_quadrant_bounds_aa4`.
+fn use_quadrant_bounds_blended(
+    dst: &mut ImageViewMut<u32>,
+    span_buffer: &[(usize, usize)],
+    ybounds: (usize, usize),
+    color: u32,
+    opacity: u8,
+    anti_aliasing: usize,
+) {
+    let weight = (opacity as usize * 65536 / (255 * anti_aliasing * anti_aliasing)) as i32;
+    for y in ybounds.0..ybounds.1 {
+        let line = &mut dst[y / anti_aliasing];
+        for x in span_buffer[y].0..span_buffer[y].1 {
+            let pixel = &mut line[x / anti_aliasing];
+            *pixel = blend_pixel(*pixel, color, weight);
+        }
+    }
+}
+
 pub fn draw_locc<T: Float + std::fmt::Debug>(
     dst: &mut ImageViewMut<u32>,
     locc: &Curve<T>,
@@ -397,8 +429,46 @@ pub fn draw_locc<T: Float + std::fmt::Debug>(
     width: T,
     span_buffer: &mut [(usize, usize)],
     anti_aliasing: usize,
+) {
+    draw_locc_blended(dst, locc, color, 255, width, span_buffer, anti_aliasing);
+}
+
+/// Draws the curve with the color mixed into the image, the opacity 255 covers it.
+pub fn draw_locc_blended<T: Float + std::fmt::Debug>(
+    dst: &mut ImageViewMut<u32>,
+    locc: &Curve<T>,
+    color: u32,
+    opacity: u8,
+    width: T,
+    span_buffer: &mut [(usize, usize)],
+    anti_aliasing: usize,
 ) {
     assert!(anti_aliasing == 1 || anti_aliasing == 2 || anti_aliasing == 4);
+    // the infinite line is cut by the image with a reserve for the width
+    if let Curve::Contour(c) = locc {
+        if c.a == T::zero() {
+            let reserve = width + T::one();
+            let size = dst.get_size();
+            let corner1 = Point::new(-reserve, -reserve);
+            let corner2 = Point::new(
+                T::from(size.0).unwrap() + reserve,
+                T::from(size.1).unwrap() + reserve,
+            );
+            if let Some(segment) = c.clip_line(corner1, corner2) {
+                let segment = Curve::Segment(segment);
+                draw_locc_blended(
+                    dst,
+                    &segment,
+                    color,
+                    opacity,
+                    width,
+                    span_buffer,
+                    anti_aliasing,
+                );
+            }
+            return;
+        }
+    }
     let entity = locc.scale(T::from(anti_aliasing).unwrap());
     let curve = entity.get_contour();
     let width = width * T::from(anti_aliasing).unwrap();
@@ -478,6 +548,9 @@ pub fn draw_locc<T: Float + std::fmt::Debug>(
         ($ys: ident) => {{
             let ys = $ys;
             match anti_aliasing {
+                _ if opacity < 255 => {
+                    use_quadrant_bounds_blended(dst, span_buffer, ys, color, opacity, anti_aliasing)
+                }
                 2 => use_quadrant_bounds_aa2(dst, span_buffer, ys, color),
                 4 => use_quadrant_bounds_aa4(dst, span_buffer, ys, color),
                 _ => use_quadrant_bounds(dst, span_buffer, ys, color),
@@ -714,6 +787,78 @@ mod tests {
         }
     }
 
+    #[test]
+    fn blended_line_mixes_colors() {
+        let mut dst = Image::<u32>::new((16, 16));
+        let mut span_buffer = vec![(0, 0); dst.get_size().1 * 4];
+        let line = Curve::Segment(Segment::line(Point::new(-4.0, 8.0), Point::new(20.0, 8.0)));
+        let mut draw = |opacity, anti_aliasing| {
+            dst.as_view_mut().fill(|p| *p = 0xffffff);
+            let mut view = dst.as_view_mut();
+            draw_locc_blended(
+                &mut view,
+                &line,
+                0,
+                opacity,
+                4.0,
+                &mut span_buffer,
+                anti_aliasing,
+            );
+            assert_eq!(dst.as_view()[0][8], 0xffffff);
+            dst.as_view()[8][8] & 0xff
+        };
+        assert_eq!(draw(255, 1), 0);
+        assert!(draw(128, 1).abs_diff(0x7f) <= 1);
+        // the samples of the anti-aliasing mix in their shares of the half covered color
+        for anti_aliasing in [2, 4] {
+            let opaque = draw(255, anti_aliasing);
+            let blended = draw(128, anti_aliasing);
+            assert!(opaque < blended && blended < 0xff);
+        }
+    }
+
+    #[test]
+    fn infinite_line_crosses_image() {
+        let mut dst = Image::<u32>::new((64, 32));
+        let mut span_buffer = vec![(0, 0); dst.get_size().1 * 4];
+        let lines = [
+            Contour::line(Point::new(0.0, 10.5), Point::new(1.0, 10.5)),
+            Contour::line(Point::new(1.0, 10.5), Point::new(0.0, 10.5)),
+        ];
+        for line in lines {
+            for anti_aliasing in [1, 2, 4] {
+                dst.as_view_mut().fill(|p| *p = 0xffffff);
+                draw_locc(
+                    &mut dst.as_view_mut(),
+                    &Curve::Contour(line),
+                    0,
+                    2.0,
+                    &mut span_buffer,
+                    anti_aliasing,
+                );
+                // the anti-aliased pixels are the same along the line
+                let view = dst.as_view();
+                assert_ne!(view[10][32], 0xffffff);
+                assert_eq!(view[10][0], view[10][32]);
+                assert_eq!(view[10][63], view[10][32]);
+                assert_eq!(view[20][32], 0xffffff);
+            }
+        }
+
+        // the line which misses the image draws nothing
+        dst.as_view_mut().fill(|p| *p = 0xffffff);
+        let outside = Contour::line(Point::new(0.0, -10.0), Point::new(1.0, -10.0));
+        draw_locc(
+            &mut dst.as_view_mut(),
+            &Curve::Contour(outside),
+            0,
+            2.0,
+            &mut span_buffer,
+            1,
+        );
+        assert!(dst.get_data().iter().all(|p| *p == 0xffffff));
+    }
+
     #[test]
     fn correct_test_line() {
         let mut dst = Image::<u32>::new((64, 64));
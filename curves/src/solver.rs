@@ -182,6 +182,98 @@ pub fn intersection_curves<T: Float>(c1: &Curve<T>, c2: &Curve<T>, eps: T) -> Ve
      ).collect()
 }
 
+/// Point of the curve nearest to the given one.
+pub fn nearest_point_on_curve<T: Float>(curve: &Curve<T>, x: Point<T>) -> Point<T> {
+    let nearest = x + curve.get_contour().translate(-x).nearest_point_to_zero();
+    match curve {
+        // along an arc the distance grows with the angle from the nearest point of the circle
+        Segment(s) if !s.inside_sector(nearest, T::zero(), false) => {
+            if (x - s.begin).sqr_length() < (x - s.end).sqr_length() {
+                s.begin
+            } else {
+                s.end
+            }
+        }
+        _ => nearest,
+    }
+}
+
+/// Points of the first curve where a common normal of the curves can meet it.
+fn normal_points<T: Float>(c1: &Curve<T>, c2: &Curve<T>) -> Vec<Point<T>> {
+    let (contour1, contour2) = (c1.get_contour(), c2.get_contour());
+    let points = if contour1.a == T::zero() {
+        // parallel lines have the same distance everywhere, any point will do
+        let base = if contour2.a == T::zero() {
+            Point::new(T::zero(), T::zero())
+        } else {
+            contour2.get_center()
+        };
+        vec![base + contour1.translate(-base).nearest_point_to_zero()]
+    } else {
+        let center = contour1.get_center();
+        let radius = contour1.get_radius().abs();
+        let direction = if contour2.a == T::zero() {
+            contour2.n
+        } else {
+            contour2.get_center() - center
+        };
+        // concentric circles have the same distance everywhere
+        let direction = if direction.sqr_length() == T::zero() {
+            Point::new(T::one(), T::zero())
+        } else {
+            direction.normalize()
+        };
+        vec![
+            center + direction.scale(radius),
+            center - direction.scale(radius),
+        ]
+    };
+    points
+        .into_iter()
+        .filter(|p| match c1 {
+            Segment(s) => s.inside_sector(*p, T::zero(), false),
+            _ => true,
+        })
+        .collect()
+}
+
+fn get_ends<T: Float>(curve: &Curve<T>) -> Vec<Point<T>> {
+    match curve {
+        Contour(_) => Vec::new(),
+        Segment(s) => vec![s.begin, s.end],
+    }
+}
+
+/// The nearest points of the curves, the first one is on `c1`. Crossing curves give the
+/// same point twice. The closest pair either has an end of a segment or lies on a common
+/// normal of the curves, so only these candidates are checked.
+pub fn closest_points<T: Float>(c1: &Curve<T>, c2: &Curve<T>, eps: T) -> (Point<T>, Point<T>) {
+    if let Some(cross) = intersection_curves(c1, c2, eps).first() {
+        return (*cross, *cross);
+    }
+    let mut candidates: Vec<_> = get_ends(c1)
+        .into_iter()
+        .chain(normal_points(c1, c2))
+        .map(|p| (p, nearest_point_on_curve(c2, p)))
+        .collect();
+    candidates.extend(
+        get_ends(c2)
+            .into_iter()
+            .chain(normal_points(c2, c1))
+            .map(|p| (nearest_point_on_curve(c1, p), p)),
+    );
+    candidates
+        .into_iter()
+        .fold(
+            None,
+            |best: Option<(Point<T>, Point<T>)>, (p1, p2)| match best {
+                Some((b1, b2)) if (b2 - b1).sqr_length() <= (p2 - p1).sqr_length() => best,
+                _ => Some((p1, p2)),
+            },
+        )
+        .expect("a contour always has a normal point")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -222,11 +314,11 @@ mod tests {
         }
         assert_eq!(
             solve_square_equation::<f32>(&(0.0, 0.0, 0.0), 1.0e-7),
-            vec![]
+            Vec::<f32>::new()
         );
         assert_eq!(
             solve_square_equation::<f32>(&(0.0, 0.0, 1.0), 1.0e-7),
-            vec![]
+            Vec::<f32>::new()
         );
         assert_eq!(
             solve_square_equation::<f32>(&(0.0, 1.0, 0.0), 1.0e-7),
@@ -372,4 +464,37 @@ mod tests {
             1.0e-3,
         );
     }
+
+    #[test]
+    fn test_closest_points() {
+        let check = |c1: Curve<f64>, c2: Curve<f64>, expected: f64| {
+            let (p1, p2) = closest_points(&c1, &c2, 1.0e-9);
+            assert!(c1.distance(p1).abs() < 1.0e-9 && c2.distance(p2).abs() < 1.0e-9);
+            assert!(((p2 - p1).length() - expected).abs() < 1.0e-9);
+            let (q2, q1) = closest_points(&c2, &c1, 1.0e-9);
+            assert!(((q2 - q1).length() - expected).abs() < 1.0e-9);
+        };
+        let line = |x1, y1, x2, y2| Segment(Segment::line(Point::new(x1, y1), Point::new(x2, y2)));
+        let circle = |x, y, r| Contour(Contour::circle(Point::new(x, y), r));
+
+        // parallel segments overlapping partially and collinear ones
+        check(line(0.0, 0.0, 2.0, 0.0), line(1.0, 1.0, 3.0, 1.0), 1.0);
+        check(line(0.0, 0.0, 1.0, 0.0), line(3.0, 0.0, 4.0, 0.0), 2.0);
+        // the end of one segment against an inner point of another, crossing segments
+        check(line(0.0, 0.0, 4.0, 0.0), line(2.0, 1.0, 3.0, 5.0), 1.0);
+        check(line(0.0, 0.0, 2.0, 2.0), line(0.0, 2.0, 2.0, 0.0), 0.0);
+        // circles outside and inside each other, a circle and a line
+        check(circle(0.0, 0.0, 1.0), circle(5.0, 0.0, 2.0), 2.0);
+        check(circle(0.0, 0.0, 5.0), circle(1.0, 0.0, 1.0), 3.0);
+        check(circle(0.0, 3.0, 1.0), line(-5.0, 0.0, 5.0, 0.0), 2.0);
+
+        // the nearest point of the circle is off the arc, so the end of the arc is taken
+        let arc = Segment(Segment {
+            contour: Contour::circle(Point::new(0.0, 0.0), 1.0),
+            begin: Point::new(1.0, 0.0),
+            end: Point::new(0.0, 1.0),
+            big: false,
+        });
+        check(arc, line(-3.0, -1.0, -3.0, 1.0), 3.0);
+    }
 }
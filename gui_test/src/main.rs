@@ -117,7 +117,7 @@ impl GuiControl for Surface {
                 return true;
             }
             GuiMessage::MouseWheel(_, diff) => {
-                if diff < 0 {
+                if diff < 0.0 {
                     self.scale *= 1.1;
                 } else {
                     self.scale /= 1.1;
@@ -136,7 +136,7 @@ impl GuiControl for Surface {
                 }
                 _ => return false,
             },
-            GuiMessage::Timer => {
+            GuiMessage::Timer(_) => {
                 let mut elapsed = self.time.elapsed().unwrap();
                 let dt = std::time::Duration::from_millis(1);
                 let mut result = false;
@@ -0,0 +1,103 @@
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+/// Timer period while the jobs run or the input is replayed.
+const BUSY_TIMER_INTERVAL: u32 = 1;
+/// Timer period of the hidden or inactive window, it only checks the jobs.
+const PAUSED_TIMER_INTERVAL: u32 = 250;
+
+/// Limits of the repainting and the timers, they save the power when nothing happens.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq)]
+#[serde(default)]
+pub struct FramePacing {
+    /// Repaints per second at most, zero means no limit.
+    pub max_fps: u32,
+    /// Animations and timers of the application also stop while another window is active,
+    /// they always stop while the window is minimized.
+    pub pause_in_background: bool,
+}
+
+impl Default for FramePacing {
+    fn default() -> Self {
+        Self {
+            max_fps: 60,
+            pause_in_background: true,
+        }
+    }
+}
+
+/// Coalesces the invalidations of the window into frames and chooses the timer period.
+#[derive(Default)]
+pub(crate) struct FramePacer {
+    pacing: FramePacing,
+    last_frame: Option<Instant>,
+    /// The window changed after the last frame, it is repainted when the frame is due.
+    pending: bool,
+    minimized: bool,
+    inactive: bool,
+    timer_interval: u32,
+}
+
+impl FramePacer {
+    pub fn set_pacing(&mut self, pacing: FramePacing) {
+        self.pacing = pacing;
+    }
+
+    pub fn set_minimized(&mut self, minimized: bool) {
+        self.minimized = minimized;
+    }
+
+    pub fn set_inactive(&mut self, inactive: bool) {
+        self.inactive = inactive;
+    }
+
+    fn get_frame_interval(&self) -> Duration {
+        match self.pacing.max_fps {
+            0 => Duration::ZERO,
+            max_fps => Duration::from_secs(1) / max_fps,
+        }
+    }
+
+    /// Returns true if the window has to be invalidated now, otherwise the frame is left
+    /// for `take_due_frame`.
+    pub fn request_frame(&mut self) -> bool {
+        let now = Instant::now();
+        let due = self
+            .last_frame
+            .is_none_or(|last_frame| now - last_frame >= self.get_frame_interval());
+        if due {
+            self.last_frame = Some(now);
+            self.pending = false;
+        } else {
+            self.pending = true;
+        }
+        due
+    }
+
+    /// Returns true once the delayed frame is due.
+    pub fn take_due_frame(&mut self) -> bool {
+        self.pending && self.request_frame()
+    }
+
+    /// Nothing is seen or nobody looks, so the animations can wait.
+    pub fn is_paused(&self) -> bool {
+        self.minimized || (self.inactive && self.pacing.pause_in_background)
+    }
+
+    /// Returns the new timer period in milliseconds if it has to be changed.
+    pub fn update_timer_interval(&mut self, busy: bool) -> Option<u32> {
+        let interval = if busy {
+            BUSY_TIMER_INTERVAL
+        } else if self.is_paused() && !self.pending {
+            PAUSED_TIMER_INTERVAL
+        } else {
+            (self.get_frame_interval().as_millis() as u32).max(BUSY_TIMER_INTERVAL)
+        };
+        if interval == self.timer_interval {
+            return None;
+        }
+        self.timer_interval = interval;
+        Some(interval)
+    }
+}
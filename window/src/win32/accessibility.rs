@@ -0,0 +1,867 @@
+#![allow(non_snake_case)]
+
+use std::cell::{Cell, RefCell};
+use std::mem::offset_of;
+use std::rc::Rc;
+
+use winapi::ctypes::{c_double, c_int, c_long, c_void};
+use winapi::shared::guiddef::{IsEqualGUID, GUID, REFIID};
+use winapi::shared::minwindef::*;
+use winapi::shared::ntdef::{HRESULT, LPCWSTR};
+use winapi::shared::windef::*;
+use winapi::shared::winerror::{E_FAIL, E_NOINTERFACE, E_NOTIMPL, S_OK};
+use winapi::shared::wtypes::{BSTR, VARIANT_FALSE, VARIANT_TRUE, VARTYPE, VT_BOOL, VT_BSTR, VT_I4};
+use winapi::um::oaidl::{SAFEARRAY, VARIANT};
+use winapi::um::oleauto::{
+    SafeArrayAccessData, SafeArrayCreateVector, SafeArrayUnaccessData, SysAllocString, VariantInit,
+};
+use winapi::um::unknwnbase::{IUnknown, IUnknownVtbl};
+use winapi::um::winuser::*;
+use winapi::Interface;
+use winapi::RIDL;
+
+use crate::win32::wide_strings::WideStringManager;
+use crate::win32::{Context, SystemContext};
+use application::gui::{AccessibleNode, AccessibleRole};
+
+/// Screen rectangle of an element in pixels.
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct UiaRect {
+    pub left: c_double,
+    pub top: c_double,
+    pub width: c_double,
+    pub height: c_double,
+}
+
+RIDL! {#[uuid(0xd6dd68d1, 0x86fd, 0x4332, 0x86, 0x66, 0x9a, 0xbe, 0xde, 0xa2, 0xd2, 0x4c)]
+interface IRawElementProviderSimple(IRawElementProviderSimpleVtbl): IUnknown(IUnknownVtbl) {
+    fn get_ProviderOptions(pRetVal: *mut c_int,) -> HRESULT,
+    fn GetPatternProvider(patternId: c_int, pRetVal: *mut *mut IUnknown,) -> HRESULT,
+    fn GetPropertyValue(propertyId: c_int, pRetVal: *mut VARIANT,) -> HRESULT,
+    fn get_HostRawElementProvider(pRetVal: *mut *mut IRawElementProviderSimple,) -> HRESULT,
+}}
+
+RIDL! {#[uuid(0xf7063da8, 0x8359, 0x439c, 0x92, 0x97, 0xbb, 0xc5, 0x29, 0x9a, 0x7d, 0x87)]
+interface IRawElementProviderFragment(IRawElementProviderFragmentVtbl): IUnknown(IUnknownVtbl) {
+    fn Navigate(direction: c_int, pRetVal: *mut *mut IRawElementProviderFragment,) -> HRESULT,
+    fn GetRuntimeId(pRetVal: *mut *mut SAFEARRAY,) -> HRESULT,
+    fn get_BoundingRectangle(pRetVal: *mut UiaRect,) -> HRESULT,
+    fn GetEmbeddedFragmentRoots(pRetVal: *mut *mut SAFEARRAY,) -> HRESULT,
+    fn SetFocus() -> HRESULT,
+    fn get_FragmentRoot(pRetVal: *mut *mut IRawElementProviderFragmentRoot,) -> HRESULT,
+}}
+
+RIDL! {#[uuid(0x620ce2a5, 0xab8f, 0x40a9, 0x86, 0xcb, 0xde, 0x3c, 0x75, 0x59, 0x9b, 0x58)]
+interface IRawElementProviderFragmentRoot(IRawElementProviderFragmentRootVtbl): IUnknown(IUnknownVtbl) {
+    fn ElementProviderFromPoint(
+        x: c_double,
+        y: c_double,
+        pRetVal: *mut *mut IRawElementProviderFragment,
+    ) -> HRESULT,
+    fn GetFocus(pRetVal: *mut *mut IRawElementProviderFragment,) -> HRESULT,
+}}
+
+RIDL! {#[uuid(0x54fcb24b, 0xe18e, 0x47a2, 0xb4, 0xd3, 0xec, 0xcb, 0xe7, 0x75, 0x99, 0xa2)]
+interface IInvokeProvider(IInvokeProviderVtbl): IUnknown(IUnknownVtbl) {
+    fn Invoke() -> HRESULT,
+}}
+
+RIDL! {#[uuid(0xc7935180, 0x6fb3, 0x4201, 0xb1, 0x74, 0x7d, 0xf7, 0x3a, 0xdb, 0xf6, 0x4a)]
+interface IValueProvider(IValueProviderVtbl): IUnknown(IUnknownVtbl) {
+    fn SetValue(val: LPCWSTR,) -> HRESULT,
+    fn get_Value(pRetVal: *mut BSTR,) -> HRESULT,
+    fn get_IsReadOnly(pRetVal: *mut BOOL,) -> HRESULT,
+}}
+
+RIDL! {#[uuid(0x56d00bd0, 0xc4f4, 0x433c, 0xa8, 0x36, 0x1a, 0x52, 0xa5, 0x7e, 0x08, 0x92)]
+interface IToggleProvider(IToggleProviderVtbl): IUnknown(IUnknownVtbl) {
+    fn Toggle() -> HRESULT,
+    fn get_ToggleState(pRetVal: *mut c_int,) -> HRESULT,
+}}
+
+RIDL! {#[uuid(0x2acad808, 0xb2d4, 0x452d, 0xa4, 0x07, 0x91, 0xff, 0x1a, 0xd1, 0x67, 0xb2)]
+interface ISelectionItemProvider(ISelectionItemProviderVtbl): IUnknown(IUnknownVtbl) {
+    fn Select() -> HRESULT,
+    fn AddToSelection() -> HRESULT,
+    fn RemoveFromSelection() -> HRESULT,
+    fn get_IsSelected(pRetVal: *mut BOOL,) -> HRESULT,
+    fn get_SelectionContainer(pRetVal: *mut *mut IRawElementProviderSimple,) -> HRESULT,
+}}
+
+#[link(name = "uiautomationcore")]
+extern "system" {
+    fn UiaReturnRawElementProvider(
+        hwnd: HWND,
+        wParam: WPARAM,
+        lParam: LPARAM,
+        el: *mut IRawElementProviderSimple,
+    ) -> LRESULT;
+    fn UiaHostProviderFromHwnd(
+        hwnd: HWND,
+        ppProvider: *mut *mut IRawElementProviderSimple,
+    ) -> HRESULT;
+    fn UiaRaiseAutomationEvent(pProvider: *mut IRawElementProviderSimple, id: c_int) -> HRESULT;
+    fn UiaClientsAreListening() -> BOOL;
+}
+
+pub const WM_ACCESSIBLE_ACTION: UINT = WM_APP + 1;
+pub const WM_ACCESSIBLE_FOCUS: UINT = WM_APP + 2;
+
+/// `lParam` of WM_GETOBJECT asking for the UI Automation provider.
+pub const UIA_ROOT_OBJECT_ID: c_long = -25;
+
+const UIA_E_ELEMENTNOTENABLED: HRESULT = 0x80040200_u32 as HRESULT;
+const UIA_E_ELEMENTNOTAVAILABLE: HRESULT = 0x80040201_u32 as HRESULT;
+const UIA_E_INVALIDOPERATION: HRESULT = 0x80131509_u32 as HRESULT;
+
+const PROVIDER_OPTIONS_SERVER_SIDE: c_int = 0x1;
+const UIA_APPEND_RUNTIME_ID: c_long = 3;
+
+const NAVIGATE_PARENT: c_int = 0;
+const NAVIGATE_NEXT_SIBLING: c_int = 1;
+const NAVIGATE_PREVIOUS_SIBLING: c_int = 2;
+const NAVIGATE_FIRST_CHILD: c_int = 3;
+const NAVIGATE_LAST_CHILD: c_int = 4;
+
+const UIA_INVOKE_PATTERN_ID: c_int = 10000;
+const UIA_VALUE_PATTERN_ID: c_int = 10002;
+const UIA_SELECTION_ITEM_PATTERN_ID: c_int = 10010;
+const UIA_TOGGLE_PATTERN_ID: c_int = 10015;
+
+const UIA_AUTOMATION_FOCUS_CHANGED_EVENT_ID: c_int = 20005;
+
+const UIA_CONTROL_TYPE_PROPERTY_ID: c_int = 30003;
+const UIA_NAME_PROPERTY_ID: c_int = 30005;
+const UIA_ACCELERATOR_KEY_PROPERTY_ID: c_int = 30006;
+const UIA_ACCESS_KEY_PROPERTY_ID: c_int = 30007;
+const UIA_HAS_KEYBOARD_FOCUS_PROPERTY_ID: c_int = 30008;
+const UIA_IS_KEYBOARD_FOCUSABLE_PROPERTY_ID: c_int = 30009;
+const UIA_IS_ENABLED_PROPERTY_ID: c_int = 30010;
+const UIA_IS_OFFSCREEN_PROPERTY_ID: c_int = 30022;
+
+const UIA_BUTTON_CONTROL_TYPE_ID: c_long = 50000;
+const UIA_CHECK_BOX_CONTROL_TYPE_ID: c_long = 50002;
+const UIA_EDIT_CONTROL_TYPE_ID: c_long = 50004;
+const UIA_HYPERLINK_CONTROL_TYPE_ID: c_long = 50005;
+const UIA_IMAGE_CONTROL_TYPE_ID: c_long = 50006;
+const UIA_LIST_ITEM_CONTROL_TYPE_ID: c_long = 50007;
+const UIA_LIST_CONTROL_TYPE_ID: c_long = 50008;
+const UIA_PROGRESS_BAR_CONTROL_TYPE_ID: c_long = 50012;
+const UIA_RADIO_BUTTON_CONTROL_TYPE_ID: c_long = 50013;
+const UIA_TAB_ITEM_CONTROL_TYPE_ID: c_long = 50019;
+const UIA_TEXT_CONTROL_TYPE_ID: c_long = 50020;
+
+const TOGGLE_STATE_OFF: c_int = 0;
+const TOGGLE_STATE_ON: c_int = 1;
+
+/// The node with the index of its parent in the flattened tree, none for the top nodes.
+type FlatNode = (AccessibleNode, Option<usize>);
+
+fn flatten(nodes: Vec<AccessibleNode>, parent: Option<usize>, result: &mut Vec<FlatNode>) {
+    for mut node in nodes {
+        let children = std::mem::take(&mut node.children);
+        let index = result.len();
+        result.push((node, parent));
+        flatten(children, Some(index), result);
+    }
+}
+
+pub fn get_accessible_nodes(context: &Context) -> Vec<FlatNode> {
+    let mut result = Vec::new();
+    flatten(context.gui_system.get_accessible_nodes(), None, &mut result);
+    result
+}
+
+/// The message parameter is the index of the node plus one.
+pub fn handle_request(context: Rc<RefCell<Context>>, msg: UINT, child_id: WPARAM) -> bool {
+    let maybe_control = get_accessible_nodes(&context.borrow())
+        .get(child_id.wrapping_sub(1))
+        .and_then(|(node, _)| node.get_control());
+    if let Some(control) = maybe_control {
+        let gui_system = &mut context.borrow_mut().gui_system;
+        if msg == WM_ACCESSIBLE_ACTION {
+            return gui_system.activate_control(&control);
+        } else {
+            return gui_system.focus_control(&control);
+        }
+    }
+    false
+}
+
+pub fn notify_focus(context: Rc<RefCell<Context>>, hwnd: HWND) {
+    let focus = {
+        let mut context = context.borrow_mut();
+        let focus = get_accessible_nodes(&context)
+            .iter()
+            .position(|(node, _)| node.focused);
+        if focus == context.accessible_focus {
+            return;
+        }
+        context.accessible_focus = focus;
+        focus
+    };
+    // the clients ask the properties of the element back, the context is not borrowed
+    if let Some(index) = focus {
+        unsafe {
+            if UiaClientsAreListening() != FALSE {
+                let element = Element::create(hwnd, Some(index));
+                UiaRaiseAutomationEvent(
+                    Element::simple(element),
+                    UIA_AUTOMATION_FOCUS_CHANGED_EVENT_ID,
+                );
+                element_release::<SIMPLE>(Element::simple(element) as *mut IUnknown);
+            }
+        }
+    }
+}
+
+/// Answers WM_GETOBJECT, the MSAA clients get the bridge to the same provider.
+pub unsafe fn get_object(hwnd: HWND, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    let root = Element::create(hwnd, None);
+    let result = UiaReturnRawElementProvider(hwnd, wparam, lparam, Element::simple(root));
+    element_release::<SIMPLE>(Element::simple(root) as *mut IUnknown);
+    result
+}
+
+/// Lets the clients release the elements of the destroyed window.
+pub unsafe fn disconnect(hwnd: HWND) {
+    UiaReturnRawElementProvider(hwnd, 0, 0, std::ptr::null_mut());
+}
+
+/// The window or one of its nodes. The interfaces are the vtable fields, so the object
+/// is found from any of them by the offset.
+#[repr(C)]
+struct Element {
+    simple_vtbl: *const IRawElementProviderSimpleVtbl,
+    fragment_vtbl: *const IRawElementProviderFragmentVtbl,
+    root_vtbl: *const IRawElementProviderFragmentRootVtbl,
+    references: Cell<ULONG>,
+    hwnd: HWND,
+    /// Index in the flattened tree, none for the window.
+    index: Option<usize>,
+}
+
+const SIMPLE: usize = offset_of!(Element, simple_vtbl);
+const FRAGMENT: usize = offset_of!(Element, fragment_vtbl);
+const ROOT: usize = offset_of!(Element, root_vtbl);
+
+impl Element {
+    /// The object has one reference.
+    fn create(hwnd: HWND, index: Option<usize>) -> *mut Element {
+        Box::into_raw(Box::new(Element {
+            simple_vtbl: &SIMPLE_VTBL,
+            fragment_vtbl: &FRAGMENT_VTBL,
+            root_vtbl: &ROOT_VTBL,
+            references: Cell::new(1),
+            hwnd,
+            index,
+        }))
+    }
+
+    unsafe fn interface<T>(element: *mut Element, offset: usize) -> *mut T {
+        (element as *mut u8).add(offset) as *mut T
+    }
+
+    unsafe fn simple(element: *mut Element) -> *mut IRawElementProviderSimple {
+        Self::interface(element, SIMPLE)
+    }
+
+    unsafe fn fragment(element: *mut Element) -> *mut IRawElementProviderFragment {
+        Self::interface(element, FRAGMENT)
+    }
+}
+
+unsafe fn this<'a, T, const OFFSET: usize>(this: *mut T) -> &'a Element {
+    &*((this as *const u8).sub(OFFSET) as *const Element)
+}
+
+unsafe fn with_nodes<T>(hwnd: HWND, f: impl FnOnce(Vec<FlatNode>) -> T) -> Option<T> {
+    let system_context = GetWindowLongPtrW(hwnd, GWL_USERDATA) as *const SystemContext;
+    if system_context.is_null() {
+        return None;
+    }
+
+    let nodes = get_accessible_nodes(&*(*system_context).context.try_borrow().ok()?);
+    Some(f(nodes))
+}
+
+/// Calls `f` with the node and all the nodes, the node may be gone after the changes
+/// of the controls.
+unsafe fn with_node(
+    hwnd: HWND,
+    index: usize,
+    f: impl FnOnce(&AccessibleNode, &[FlatNode]) -> HRESULT,
+) -> HRESULT {
+    with_nodes(hwnd, |nodes| match nodes.get(index) {
+        Some((node, _)) => f(node, &nodes),
+        None => UIA_E_ELEMENTNOTAVAILABLE,
+    })
+    .unwrap_or(E_FAIL)
+}
+
+unsafe fn alloc_string(value: &str) -> BSTR {
+    let mut wide_strings = WideStringManager::new();
+    SysAllocString(wide_strings.from_str(value))
+}
+
+unsafe fn set_long(variant: *mut VARIANT, value: c_long) {
+    (*variant).n1.n2_mut().vt = VT_I4 as VARTYPE;
+    *(*variant).n1.n2_mut().n3.lVal_mut() = value;
+}
+
+unsafe fn set_bool(variant: *mut VARIANT, value: bool) {
+    (*variant).n1.n2_mut().vt = VT_BOOL as VARTYPE;
+    *(*variant).n1.n2_mut().n3.boolVal_mut() = if value { VARIANT_TRUE } else { VARIANT_FALSE };
+}
+
+unsafe fn set_string(variant: *mut VARIANT, value: &str) {
+    (*variant).n1.n2_mut().vt = VT_BSTR as VARTYPE;
+    *(*variant).n1.n2_mut().n3.bstrVal_mut() = alloc_string(value);
+}
+
+/// Element of the node for the fragment result, none gives the window.
+unsafe fn set_fragment(
+    result: *mut *mut IRawElementProviderFragment,
+    hwnd: HWND,
+    index: Option<Option<usize>>,
+) {
+    *result = match index {
+        Some(index) => Element::fragment(Element::create(hwnd, index)),
+        None => std::ptr::null_mut(),
+    };
+}
+
+unsafe extern "system" fn element_query_interface<const OFFSET: usize>(
+    this_ptr: *mut IUnknown,
+    riid: REFIID,
+    object: *mut *mut c_void,
+) -> HRESULT {
+    let element = this::<_, OFFSET>(this_ptr);
+    let riid = &*riid;
+    let offset = if IsEqualGUID(riid, &IUnknown::uuidof())
+        || IsEqualGUID(riid, &IRawElementProviderSimple::uuidof())
+    {
+        Some(SIMPLE)
+    } else if IsEqualGUID(riid, &IRawElementProviderFragment::uuidof()) {
+        Some(FRAGMENT)
+    } else if element.index.is_none()
+        && IsEqualGUID(riid, &IRawElementProviderFragmentRoot::uuidof())
+    {
+        Some(ROOT)
+    } else {
+        None
+    };
+    match offset {
+        Some(offset) => {
+            element.references.set(element.references.get() + 1);
+            *object = Element::interface(element as *const Element as *mut Element, offset);
+            S_OK
+        }
+        None => {
+            *object = std::ptr::null_mut();
+            E_NOINTERFACE
+        }
+    }
+}
+
+unsafe extern "system" fn element_add_ref<const OFFSET: usize>(this_ptr: *mut IUnknown) -> ULONG {
+    let element = this::<_, OFFSET>(this_ptr);
+    element.references.set(element.references.get() + 1);
+    element.references.get()
+}
+
+unsafe extern "system" fn element_release<const OFFSET: usize>(this_ptr: *mut IUnknown) -> ULONG {
+    let element = this::<_, OFFSET>(this_ptr);
+    let references = element.references.get() - 1;
+    element.references.set(references);
+    if references == 0 {
+        drop(Box::from_raw(element as *const Element as *mut Element));
+    }
+    references
+}
+
+const fn element_unknown_vtbl<const OFFSET: usize>() -> IUnknownVtbl {
+    IUnknownVtbl {
+        QueryInterface: element_query_interface::<OFFSET>,
+        AddRef: element_add_ref::<OFFSET>,
+        Release: element_release::<OFFSET>,
+    }
+}
+
+unsafe extern "system" fn get_provider_options(
+    _: *mut IRawElementProviderSimple,
+    options: *mut c_int,
+) -> HRESULT {
+    *options = PROVIDER_OPTIONS_SERVER_SIDE;
+    S_OK
+}
+
+unsafe extern "system" fn get_pattern_provider(
+    this_ptr: *mut IRawElementProviderSimple,
+    pattern_id: c_int,
+    provider: *mut *mut IUnknown,
+) -> HRESULT {
+    *provider = std::ptr::null_mut();
+    let element = this::<_, SIMPLE>(this_ptr);
+    let index = match element.index {
+        Some(index) => index,
+        None => return S_OK,
+    };
+    with_node(element.hwnd, index, |node, _| {
+        let pattern = match (pattern_id, node.role) {
+            (UIA_INVOKE_PATTERN_ID, AccessibleRole::Button | AccessibleRole::Link) => Some((
+                &INVOKE_VTBL as *const _ as *const c_void,
+                IInvokeProvider::uuidof(),
+            )),
+            (UIA_TOGGLE_PATTERN_ID, AccessibleRole::CheckBox) => Some((
+                &TOGGLE_VTBL as *const _ as *const c_void,
+                IToggleProvider::uuidof(),
+            )),
+            (
+                UIA_SELECTION_ITEM_PATTERN_ID,
+                AccessibleRole::RadioButton | AccessibleRole::Tab | AccessibleRole::ListItem,
+            ) => Some((
+                &SELECTION_ITEM_VTBL as *const _ as *const c_void,
+                ISelectionItemProvider::uuidof(),
+            )),
+            (UIA_VALUE_PATTERN_ID, AccessibleRole::Edit | AccessibleRole::ProgressBar) => Some((
+                &VALUE_VTBL as *const _ as *const c_void,
+                IValueProvider::uuidof(),
+            )),
+            _ => None,
+        };
+        if let Some((vtbl, iid)) = pattern {
+            *provider = Box::into_raw(Box::new(Pattern {
+                vtbl,
+                iid,
+                references: Cell::new(1),
+                hwnd: element.hwnd,
+                index,
+            })) as *mut IUnknown;
+        }
+        S_OK
+    })
+}
+
+unsafe extern "system" fn get_property_value(
+    this_ptr: *mut IRawElementProviderSimple,
+    property_id: c_int,
+    value: *mut VARIANT,
+) -> HRESULT {
+    VariantInit(value);
+    let element = this::<_, SIMPLE>(this_ptr);
+    // the window properties come from the host provider
+    let index = match element.index {
+        Some(index) => index,
+        None => return S_OK,
+    };
+    with_node(element.hwnd, index, |node, _| {
+        match property_id {
+            UIA_CONTROL_TYPE_PROPERTY_ID => set_long(
+                value,
+                match node.role {
+                    AccessibleRole::Button => UIA_BUTTON_CONTROL_TYPE_ID,
+                    AccessibleRole::CheckBox => UIA_CHECK_BOX_CONTROL_TYPE_ID,
+                    AccessibleRole::RadioButton => UIA_RADIO_BUTTON_CONTROL_TYPE_ID,
+                    AccessibleRole::Tab => UIA_TAB_ITEM_CONTROL_TYPE_ID,
+                    AccessibleRole::Text => UIA_TEXT_CONTROL_TYPE_ID,
+                    AccessibleRole::Edit => UIA_EDIT_CONTROL_TYPE_ID,
+                    AccessibleRole::List => UIA_LIST_CONTROL_TYPE_ID,
+                    AccessibleRole::ListItem => UIA_LIST_ITEM_CONTROL_TYPE_ID,
+                    AccessibleRole::Link => UIA_HYPERLINK_CONTROL_TYPE_ID,
+                    AccessibleRole::ProgressBar => UIA_PROGRESS_BAR_CONTROL_TYPE_ID,
+                    AccessibleRole::Image => UIA_IMAGE_CONTROL_TYPE_ID,
+                },
+            ),
+            UIA_NAME_PROPERTY_ID if !node.name.is_empty() => set_string(value, &node.name),
+            UIA_ACCELERATOR_KEY_PROPERTY_ID => {
+                if let Some(hotkey) = node.shortcut {
+                    set_string(value, &format!("{:?}", hotkey));
+                }
+            }
+            UIA_ACCESS_KEY_PROPERTY_ID => {
+                if let Some(accelerator) = node.accelerator {
+                    set_string(value, &format!("Alt+{}", accelerator));
+                }
+            }
+            UIA_HAS_KEYBOARD_FOCUS_PROPERTY_ID => set_bool(value, node.focused),
+            UIA_IS_KEYBOARD_FOCUSABLE_PROPERTY_ID => set_bool(value, node.focusable()),
+            UIA_IS_ENABLED_PROPERTY_ID => set_bool(value, node.enabled),
+            UIA_IS_OFFSCREEN_PROPERTY_ID => {
+                set_bool(value, node.rect.left_top == node.rect.right_bottom)
+            }
+            _ => {}
+        }
+        S_OK
+    })
+}
+
+unsafe extern "system" fn get_host_raw_element_provider(
+    this_ptr: *mut IRawElementProviderSimple,
+    provider: *mut *mut IRawElementProviderSimple,
+) -> HRESULT {
+    let element = this::<_, SIMPLE>(this_ptr);
+    if element.index.is_none() {
+        return UiaHostProviderFromHwnd(element.hwnd, provider);
+    }
+    *provider = std::ptr::null_mut();
+    S_OK
+}
+
+unsafe extern "system" fn navigate(
+    this_ptr: *mut IRawElementProviderFragment,
+    direction: c_int,
+    result: *mut *mut IRawElementProviderFragment,
+) -> HRESULT {
+    *result = std::ptr::null_mut();
+    let element = this::<_, FRAGMENT>(this_ptr);
+    with_nodes(element.hwnd, |nodes| {
+        if element.index.is_some_and(|index| index >= nodes.len()) {
+            return UIA_E_ELEMENTNOTAVAILABLE;
+        }
+        let parent = |index: usize| nodes[index].1;
+        // the siblings of the window are found by the host provider
+        let target = match (element.index, direction) {
+            (Some(index), NAVIGATE_PARENT) => Some(parent(index)),
+            (_, NAVIGATE_FIRST_CHILD) => nodes
+                .iter()
+                .position(|(_, parent)| *parent == element.index)
+                .map(Some),
+            (_, NAVIGATE_LAST_CHILD) => nodes
+                .iter()
+                .rposition(|(_, parent)| *parent == element.index)
+                .map(Some),
+            (Some(index), NAVIGATE_NEXT_SIBLING) => (index + 1..nodes.len())
+                .find(|other| parent(*other) == parent(index))
+                .map(Some),
+            (Some(index), NAVIGATE_PREVIOUS_SIBLING) => (0..index)
+                .rev()
+                .find(|other| parent(*other) == parent(index))
+                .map(Some),
+            _ => None,
+        };
+        set_fragment(result, element.hwnd, target);
+        S_OK
+    })
+    .unwrap_or(E_FAIL)
+}
+
+unsafe extern "system" fn get_runtime_id(
+    this_ptr: *mut IRawElementProviderFragment,
+    result: *mut *mut SAFEARRAY,
+) -> HRESULT {
+    *result = std::ptr::null_mut();
+    let element = this::<_, FRAGMENT>(this_ptr);
+    let index = match element.index {
+        Some(index) => index,
+        None => return S_OK,
+    };
+    let array = SafeArrayCreateVector(VT_I4 as VARTYPE, 0, 2);
+    if array.is_null() {
+        return E_FAIL;
+    }
+    let mut data = std::ptr::null_mut();
+    if SafeArrayAccessData(array, &mut data) != S_OK {
+        return E_FAIL;
+    }
+    let data = data as *mut c_long;
+    *data = UIA_APPEND_RUNTIME_ID;
+    *data.add(1) = index as c_long + 1;
+    SafeArrayUnaccessData(array);
+    *result = array;
+    S_OK
+}
+
+unsafe extern "system" fn get_bounding_rectangle(
+    this_ptr: *mut IRawElementProviderFragment,
+    rect: *mut UiaRect,
+) -> HRESULT {
+    *rect = UiaRect {
+        left: 0.0,
+        top: 0.0,
+        width: 0.0,
+        height: 0.0,
+    };
+    let element = this::<_, FRAGMENT>(this_ptr);
+    let index = match element.index {
+        Some(index) => index,
+        None => return S_OK,
+    };
+    with_node(element.hwnd, index, |node, _| {
+        let mut point = POINT {
+            x: node.rect.left_top.0,
+            y: node.rect.left_top.1,
+        };
+        ClientToScreen(element.hwnd, &mut point);
+        *rect = UiaRect {
+            left: point.x as c_double,
+            top: point.y as c_double,
+            width: (node.rect.right_bottom.0 - node.rect.left_top.0) as c_double,
+            height: (node.rect.right_bottom.1 - node.rect.left_top.1) as c_double,
+        };
+        S_OK
+    })
+}
+
+unsafe extern "system" fn get_embedded_fragment_roots(
+    _: *mut IRawElementProviderFragment,
+    result: *mut *mut SAFEARRAY,
+) -> HRESULT {
+    *result = std::ptr::null_mut();
+    S_OK
+}
+
+unsafe extern "system" fn set_focus(this_ptr: *mut IRawElementProviderFragment) -> HRESULT {
+    let element = this::<_, FRAGMENT>(this_ptr);
+    let index = match element.index {
+        Some(index) => index,
+        None => return S_OK,
+    };
+    with_node(element.hwnd, index, |node, _| {
+        if node.focusable() {
+            PostMessageW(element.hwnd, WM_ACCESSIBLE_FOCUS, index + 1, 0);
+        }
+        S_OK
+    })
+}
+
+unsafe extern "system" fn get_fragment_root(
+    this_ptr: *mut IRawElementProviderFragment,
+    root: *mut *mut IRawElementProviderFragmentRoot,
+) -> HRESULT {
+    let element = this::<_, FRAGMENT>(this_ptr);
+    *root = Element::interface(Element::create(element.hwnd, None), ROOT);
+    S_OK
+}
+
+unsafe extern "system" fn element_provider_from_point(
+    this_ptr: *mut IRawElementProviderFragmentRoot,
+    x: c_double,
+    y: c_double,
+    result: *mut *mut IRawElementProviderFragment,
+) -> HRESULT {
+    *result = std::ptr::null_mut();
+    let element = this::<_, ROOT>(this_ptr);
+    let mut point = POINT {
+        x: x as c_long,
+        y: y as c_long,
+    };
+    ScreenToClient(element.hwnd, &mut point);
+    with_nodes(element.hwnd, |nodes| {
+        let index = nodes
+            .iter()
+            .rposition(|(node, _)| node.rect.contains((point.x, point.y)));
+        set_fragment(result, element.hwnd, index.map(Some));
+        S_OK
+    })
+    .unwrap_or(E_FAIL)
+}
+
+unsafe extern "system" fn get_focus(
+    this_ptr: *mut IRawElementProviderFragmentRoot,
+    result: *mut *mut IRawElementProviderFragment,
+) -> HRESULT {
+    *result = std::ptr::null_mut();
+    let element = this::<_, ROOT>(this_ptr);
+    with_nodes(element.hwnd, |nodes| {
+        let index = nodes.iter().position(|(node, _)| node.focused);
+        set_fragment(result, element.hwnd, index.map(Some));
+        S_OK
+    })
+    .unwrap_or(E_FAIL)
+}
+
+static SIMPLE_VTBL: IRawElementProviderSimpleVtbl = IRawElementProviderSimpleVtbl {
+    parent: element_unknown_vtbl::<SIMPLE>(),
+    get_ProviderOptions: get_provider_options,
+    GetPatternProvider: get_pattern_provider,
+    GetPropertyValue: get_property_value,
+    get_HostRawElementProvider: get_host_raw_element_provider,
+};
+
+static FRAGMENT_VTBL: IRawElementProviderFragmentVtbl = IRawElementProviderFragmentVtbl {
+    parent: element_unknown_vtbl::<FRAGMENT>(),
+    Navigate: navigate,
+    GetRuntimeId: get_runtime_id,
+    get_BoundingRectangle: get_bounding_rectangle,
+    GetEmbeddedFragmentRoots: get_embedded_fragment_roots,
+    SetFocus: set_focus,
+    get_FragmentRoot: get_fragment_root,
+};
+
+static ROOT_VTBL: IRawElementProviderFragmentRootVtbl = IRawElementProviderFragmentRootVtbl {
+    parent: element_unknown_vtbl::<ROOT>(),
+    ElementProviderFromPoint: element_provider_from_point,
+    GetFocus: get_focus,
+};
+
+/// Control pattern of a node, a separate object with one interface given by `iid`.
+#[repr(C)]
+struct Pattern {
+    vtbl: *const c_void,
+    iid: GUID,
+    references: Cell<ULONG>,
+    hwnd: HWND,
+    index: usize,
+}
+
+unsafe fn pattern<'a, T>(this: *mut T) -> &'a Pattern {
+    &*(this as *const Pattern)
+}
+
+/// Posts the request for the node, it is handled after the call returns.
+unsafe fn post_request(pattern: &Pattern, msg: UINT) -> HRESULT {
+    with_node(pattern.hwnd, pattern.index, |node, _| {
+        if !node.enabled {
+            return UIA_E_ELEMENTNOTENABLED;
+        }
+        PostMessageW(pattern.hwnd, msg, pattern.index + 1, 0);
+        S_OK
+    })
+}
+
+unsafe extern "system" fn pattern_query_interface(
+    this_ptr: *mut IUnknown,
+    riid: REFIID,
+    object: *mut *mut c_void,
+) -> HRESULT {
+    let riid = &*riid;
+    if IsEqualGUID(riid, &IUnknown::uuidof()) || IsEqualGUID(riid, &pattern(this_ptr).iid) {
+        pattern_add_ref(this_ptr);
+        *object = this_ptr as *mut _;
+        S_OK
+    } else {
+        *object = std::ptr::null_mut();
+        E_NOINTERFACE
+    }
+}
+
+unsafe extern "system" fn pattern_add_ref(this_ptr: *mut IUnknown) -> ULONG {
+    let object = pattern(this_ptr);
+    object.references.set(object.references.get() + 1);
+    object.references.get()
+}
+
+unsafe extern "system" fn pattern_release(this_ptr: *mut IUnknown) -> ULONG {
+    let object = pattern(this_ptr);
+    let references = object.references.get() - 1;
+    object.references.set(references);
+    if references == 0 {
+        drop(Box::from_raw(this_ptr as *mut Pattern));
+    }
+    references
+}
+
+const PATTERN_UNKNOWN_VTBL: IUnknownVtbl = IUnknownVtbl {
+    QueryInterface: pattern_query_interface,
+    AddRef: pattern_add_ref,
+    Release: pattern_release,
+};
+
+unsafe extern "system" fn invoke(this_ptr: *mut IInvokeProvider) -> HRESULT {
+    post_request(pattern(this_ptr), WM_ACCESSIBLE_ACTION)
+}
+
+unsafe extern "system" fn toggle(this_ptr: *mut IToggleProvider) -> HRESULT {
+    post_request(pattern(this_ptr), WM_ACCESSIBLE_ACTION)
+}
+
+unsafe extern "system" fn get_toggle_state(
+    this_ptr: *mut IToggleProvider,
+    state: *mut c_int,
+) -> HRESULT {
+    let object = pattern(this_ptr);
+    with_node(object.hwnd, object.index, |node, _| {
+        *state = if node.checked == Some(true) {
+            TOGGLE_STATE_ON
+        } else {
+            TOGGLE_STATE_OFF
+        };
+        S_OK
+    })
+}
+
+unsafe extern "system" fn select(this_ptr: *mut ISelectionItemProvider) -> HRESULT {
+    post_request(pattern(this_ptr), WM_ACCESSIBLE_ACTION)
+}
+
+/// One item is selected at a time, it can't be added or removed alone.
+unsafe extern "system" fn change_selection(_: *mut ISelectionItemProvider) -> HRESULT {
+    UIA_E_INVALIDOPERATION
+}
+
+unsafe extern "system" fn get_is_selected(
+    this_ptr: *mut ISelectionItemProvider,
+    selected: *mut BOOL,
+) -> HRESULT {
+    let object = pattern(this_ptr);
+    with_node(object.hwnd, object.index, |node, _| {
+        *selected = (node.checked == Some(true)) as BOOL;
+        S_OK
+    })
+}
+
+unsafe extern "system" fn get_selection_container(
+    this_ptr: *mut ISelectionItemProvider,
+    container: *mut *mut IRawElementProviderSimple,
+) -> HRESULT {
+    *container = std::ptr::null_mut();
+    let object = pattern(this_ptr);
+    with_node(object.hwnd, object.index, |_, nodes| {
+        if let Some(parent) = nodes[object.index].1 {
+            *container = Element::simple(Element::create(object.hwnd, Some(parent)));
+        }
+        S_OK
+    })
+}
+
+unsafe extern "system" fn set_value(_: *mut IValueProvider, _: LPCWSTR) -> HRESULT {
+    E_NOTIMPL
+}
+
+unsafe extern "system" fn get_value(this_ptr: *mut IValueProvider, value: *mut BSTR) -> HRESULT {
+    let object = pattern(this_ptr);
+    with_node(object.hwnd, object.index, |node, _| {
+        *value = alloc_string(node.value.as_deref().unwrap_or(""));
+        S_OK
+    })
+}
+
+unsafe extern "system" fn get_is_read_only(
+    this_ptr: *mut IValueProvider,
+    read_only: *mut BOOL,
+) -> HRESULT {
+    let object = pattern(this_ptr);
+    with_node(object.hwnd, object.index, |node, _| {
+        *read_only = (node.role != AccessibleRole::Edit) as BOOL;
+        S_OK
+    })
+}
+
+static INVOKE_VTBL: IInvokeProviderVtbl = IInvokeProviderVtbl {
+    parent: PATTERN_UNKNOWN_VTBL,
+    Invoke: invoke,
+};
+
+static TOGGLE_VTBL: IToggleProviderVtbl = IToggleProviderVtbl {
+    parent: PATTERN_UNKNOWN_VTBL,
+    Toggle: toggle,
+    get_ToggleState: get_toggle_state,
+};
+
+static SELECTION_ITEM_VTBL: ISelectionItemProviderVtbl = ISelectionItemProviderVtbl {
+    parent: PATTERN_UNKNOWN_VTBL,
+    Select: select,
+    AddToSelection: change_selection,
+    RemoveFromSelection: change_selection,
+    get_IsSelected: get_is_selected,
+    get_SelectionContainer: get_selection_container,
+};
+
+static VALUE_VTBL: IValueProviderVtbl = IValueProviderVtbl {
+    parent: PATTERN_UNKNOWN_VTBL,
+    SetValue: set_value,
+    get_Value: get_value,
+    get_IsReadOnly: get_is_read_only,
+};
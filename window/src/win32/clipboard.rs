@@ -1,7 +1,7 @@
-use crate::APIResult;
-use crate::APIResultCode;
-use crate::GlobalLockedPointer;
-use crate::OpenedClipboard;
+use crate::win32::APIResult;
+use crate::win32::APIResultCode;
+use crate::win32::GlobalLockedPointer;
+use crate::win32::OpenedClipboard;
 
 use std::os::windows::ffi::OsStringExt;
 use winapi::shared::ntdef::*;
@@ -8,25 +8,37 @@ use winapi::um::winnt::LONG;
 
 use application::image::*;
 
-use crate::APIResult;
-use crate::APIResultCode;
-use crate::AutoHDC;
-use crate::AutoHGDIObj;
-use crate::GotHDC;
+use crate::win32::APIResult;
+use crate::win32::APIResultCode;
+use crate::win32::AutoHDC;
+use crate::win32::AutoHGDIObj;
+use crate::win32::GotHDC;
 
+/// The bitmap is allocated with this step, so resizing the window by a few pixels
+/// reuses it.
+const CAPACITY_STEP: usize = 256;
+
+fn get_capacity(size: ImageSize) -> ImageSize {
+    let round_up = |length: usize| (length / CAPACITY_STEP + 1) * CAPACITY_STEP;
+    (round_up(size.0), round_up(size.1))
+}
+
+/// Bitmap which can be bigger than the window, only its left top part of `size` is used.
 pub struct DIBSection {
     dc: AutoHDC,
     memory: *mut c_void,
     size: ImageSize,
+    capacity: ImageSize,
 }
 
 impl DIBSection {
     pub fn new(size: ImageSize) -> APIResult<Self> {
+        let capacity = get_capacity(size);
         let bitmap_info = BITMAPINFO {
             bmiHeader: BITMAPINFOHEADER {
                 biSize: std::mem::size_of::<BITMAPINFOHEADER>() as DWORD,
-                biWidth: size.0 as LONG,
-                biHeight: -(size.1 as LONG),
+                biWidth: capacity.0 as LONG,
+                biHeight: -(capacity.1 as LONG),
                 biPlanes: 1,
                 biBitCount: 32,
                 biCompression: BI_RGB,
@@ -58,7 +70,12 @@ impl DIBSection {
             ))? as HGDIOBJ);
 
             run_api!(SelectObject(dc.get_dc(), handle.get_handle()))?;
-            Ok(Self { dc, memory, size })
+            Ok(Self {
+                dc,
+                memory,
+                size,
+                capacity,
+            })
         }
     }
 }
@@ -68,15 +85,30 @@ impl<'i> DIBSection {
         self.size
     }
 
+    /// Uses the same bitmap for the new size if it fits and doesn't waste too much memory,
+    /// otherwise returns false and the bitmap has to be created again.
+    pub fn try_resize(&mut self, size: ImageSize) -> bool {
+        if get_capacity(size) != self.capacity
+            && (size.0 > self.capacity.0
+                || size.1 > self.capacity.1
+                || size.0 * 2 < self.capacity.0
+                || size.1 * 2 < self.capacity.1)
+        {
+            return false;
+        }
+        self.size = size;
+        true
+    }
+
     pub unsafe fn get_dc(&self) -> HDC {
         self.dc.get_dc()
     }
 
     pub fn as_view(&self) -> ImageView<u32> {
-        unsafe { ImageView::from_raw(std::mem::transmute(self.memory), self.size, self.size.0) }
+        unsafe { ImageView::from_raw(std::mem::transmute(self.memory), self.size, self.capacity.0) }
     }
 
     pub fn as_view_mut(&mut self) -> ImageViewMut<u32> {
-        unsafe { ImageViewMut::from_raw(std::mem::transmute(self.memory), self.size, self.size.0) }
+        unsafe { ImageViewMut::from_raw(std::mem::transmute(self.memory), self.size, self.capacity.0) }
     }
 }
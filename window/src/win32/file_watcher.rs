@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::os::windows::ffi::{OsStrExt, OsStringExt};
+use std::path::{Path, PathBuf};
+use std::ptr::null_mut;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use winapi::shared::minwindef::*;
+use winapi::um::fileapi::*;
+use winapi::um::handleapi::*;
+use winapi::um::ioapiset::*;
+use winapi::um::winbase::*;
+use winapi::um::winnt::*;
+
+type Changes = Arc<Mutex<Vec<PathBuf>>>;
+
+/// Folder watched by a thread blocked in ReadDirectoryChangesW.
+#[derive(Debug)]
+struct FolderWatch {
+    // the handle is owned by the thread, it is kept here only to cancel the reading
+    handle: usize,
+    stopped: Arc<AtomicBool>,
+}
+
+impl Drop for FolderWatch {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        unsafe {
+            CancelIoEx(self.handle as HANDLE, null_mut());
+        }
+    }
+}
+
+unsafe fn read_changes(handle: HANDLE, folder: &Path, changes: &Changes, stopped: &AtomicBool) {
+    // FILE_NOTIFY_INFORMATION records are DWORD aligned
+    let mut buffer = vec![0u32; 4096];
+    while !stopped.load(Ordering::SeqCst) {
+        let mut bytes: DWORD = 0;
+        if ReadDirectoryChangesW(
+            handle,
+            buffer.as_mut_ptr() as LPVOID,
+            (buffer.len() * std::mem::size_of::<u32>()) as DWORD,
+            FALSE,
+            FILE_NOTIFY_CHANGE_FILE_NAME | FILE_NOTIFY_CHANGE_LAST_WRITE | FILE_NOTIFY_CHANGE_SIZE,
+            &mut bytes,
+            null_mut(),
+            None,
+        ) == 0
+        {
+            return;
+        }
+        // zero bytes means the buffer overflowed, the changes are lost
+        let mut offset = 0;
+        while (offset as DWORD) < bytes {
+            let info =
+                &*((buffer.as_ptr() as *const u8).add(offset) as *const FILE_NOTIFY_INFORMATION);
+            let name = std::slice::from_raw_parts(
+                info.FileName.as_ptr(),
+                info.FileNameLength as usize / std::mem::size_of::<u16>(),
+            );
+            let file = folder.join(OsString::from_wide(name));
+            changes.lock().unwrap().push(file);
+            if info.NextEntryOffset == 0 {
+                break;
+            }
+            offset += info.NextEntryOffset as usize;
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct FileWatcher {
+    folders: HashMap<PathBuf, FolderWatch>,
+    changes: Changes,
+}
+
+impl FileWatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl application::file_watcher::FileWatcherHandler for FileWatcher {
+    fn watch_folder(&mut self, folder: &Path) {
+        if self.folders.contains_key(folder) {
+            return;
+        }
+        let wide_folder: Vec<u16> = folder.as_os_str().encode_wide().chain(Some(0)).collect();
+        let handle = unsafe {
+            CreateFileW(
+                wide_folder.as_ptr(),
+                FILE_LIST_DIRECTORY,
+                FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE,
+                null_mut(),
+                OPEN_EXISTING,
+                FILE_FLAG_BACKUP_SEMANTICS,
+                null_mut(),
+            )
+        };
+        if handle == INVALID_HANDLE_VALUE {
+            return;
+        }
+
+        let stopped = Arc::new(AtomicBool::new(false));
+        let handle_value = handle as usize;
+        let thread_folder = folder.to_path_buf();
+        let thread_changes = self.changes.clone();
+        let thread_stopped = stopped.clone();
+        std::thread::spawn(move || unsafe {
+            read_changes(
+                handle_value as HANDLE,
+                &thread_folder,
+                &thread_changes,
+                &thread_stopped,
+            );
+            CloseHandle(handle_value as HANDLE);
+        });
+        self.folders.insert(
+            folder.to_path_buf(),
+            FolderWatch {
+                handle: handle_value,
+                stopped,
+            },
+        );
+    }
+
+    fn unwatch_folder(&mut self, folder: &Path) {
+        self.folders.remove(folder);
+    }
+
+    fn take_changes(&mut self) -> Vec<PathBuf> {
+        std::mem::take(&mut *self.changes.lock().unwrap())
+    }
+}
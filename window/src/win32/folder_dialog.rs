@@ -0,0 +1,64 @@
+use std::cell::RefCell;
+use std::os::windows::ffi::OsStringExt;
+use std::path::PathBuf;
+use std::ptr::null_mut;
+use std::rc::Rc;
+
+use winapi::shared::minwindef::*;
+use winapi::shared::winerror::*;
+use winapi::shared::wtypesbase::*;
+use winapi::um::combaseapi::*;
+use winapi::um::objbase::*;
+use winapi::um::shobjidl::*;
+use winapi::um::shobjidl_core::*;
+use winapi::um::winnt::*;
+use winapi::Interface;
+
+use crate::win32::{Context, RecursiveAPIProtector};
+
+unsafe fn pick_folder(hwnd: winapi::shared::windef::HWND) -> Option<PathBuf> {
+    let mut dialog: *mut IFileOpenDialog = null_mut();
+    if FAILED(CoCreateInstance(
+        &CLSID_FileOpenDialog,
+        null_mut(),
+        CLSCTX_INPROC_SERVER,
+        &IFileOpenDialog::uuidof(),
+        &mut dialog as *mut *mut IFileOpenDialog as *mut LPVOID,
+    )) {
+        return None;
+    }
+    let dialog = &*dialog;
+    let mut options: DWORD = 0;
+    dialog.GetOptions(&mut options);
+    dialog.SetOptions(options | FOS_PICKFOLDERS | FOS_FORCEFILESYSTEM);
+
+    let mut result = None;
+    let mut item: *mut IShellItem = null_mut();
+    if SUCCEEDED(dialog.Show(hwnd)) && SUCCEEDED(dialog.GetResult(&mut item)) {
+        let mut name: LPWSTR = null_mut();
+        if SUCCEEDED((*item).GetDisplayName(SIGDN_FILESYSPATH, &mut name)) {
+            let length = (0..).take_while(|&i| *name.offset(i) != 0).count();
+            let name_slice = std::slice::from_raw_parts(name, length);
+            result = Some(PathBuf::from(std::ffi::OsString::from_wide(name_slice)));
+            CoTaskMemFree(name as LPVOID);
+        }
+        (*item).Release();
+    }
+    dialog.Release();
+    result
+}
+
+/// Returns None if the dialog is cancelled.
+pub fn show_folder_dialog(context: Rc<RefCell<Context>>) -> Option<PathBuf> {
+    let hwnd = context.borrow().hwnd;
+    let _protector = RecursiveAPIProtector::new(context.clone());
+    unsafe {
+        // the dialog is a COM object, the thread may be initialized already
+        let initialized = SUCCEEDED(CoInitializeEx(null_mut(), COINIT_APARTMENTTHREADED));
+        let result = pick_folder(hwnd);
+        if initialized {
+            CoUninitialize();
+        }
+        result
+    }
+}
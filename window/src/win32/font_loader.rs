@@ -9,13 +9,13 @@ use winapi::um::errhandlingapi::*;
 use winapi::um::wingdi::*;
 use winapi::um::winuser::*;
 
-use crate::DIBSection;
-use crate::WideStringManager;
+use crate::win32::DIBSection;
+use crate::win32::WideStringManager;
 
-use crate::APIResult;
-use crate::APIResultCode;
+use crate::win32::APIResult;
+use crate::win32::APIResultCode;
 
-use crate::AutoHGDIObj;
+use crate::win32::AutoHGDIObj;
 
 pub struct GDIFontLoader {}
 
@@ -0,0 +1,146 @@
+#![allow(non_snake_case)]
+
+use winapi::shared::minwindef::*;
+use winapi::shared::ntdef::{SHORT, ULONGLONG};
+use winapi::shared::windef::*;
+use winapi::um::winuser::ScreenToClient;
+
+use application::gui::input_log::InputEvent;
+use application::image::Position;
+
+// Gesture API is missing in winapi 0.3.9
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct POINTS {
+    x: SHORT,
+    y: SHORT,
+}
+
+#[repr(C)]
+struct GESTUREINFO {
+    cbSize: UINT,
+    dwFlags: DWORD,
+    dwID: DWORD,
+    hwndTarget: HWND,
+    ptsLocation: POINTS,
+    dwInstanceID: DWORD,
+    dwSequenceID: DWORD,
+    ullArguments: ULONGLONG,
+    cbExtraArgs: UINT,
+}
+
+#[repr(C)]
+struct GESTURECONFIG {
+    dwID: DWORD,
+    dwWant: DWORD,
+    dwBlock: DWORD,
+}
+
+type HGESTUREINFO = *mut std::ffi::c_void;
+
+const GID_ZOOM: DWORD = 3;
+const GID_PAN: DWORD = 4;
+const GF_BEGIN: DWORD = 1;
+const GC_ZOOM: DWORD = 1;
+const GC_PAN: DWORD = 1;
+const GC_PAN_WITH_SINGLE_FINGER_VERTICALLY: DWORD = 2;
+const GC_PAN_WITH_SINGLE_FINGER_HORIZONTALLY: DWORD = 4;
+const GC_PAN_WITH_GUTTER: DWORD = 8;
+
+#[link(name = "user32")]
+extern "system" {
+    fn GetGestureInfo(hGestureInfo: HGESTUREINFO, pGestureInfo: *mut GESTUREINFO) -> BOOL;
+    fn CloseGestureInfoHandle(hGestureInfo: HGESTUREINFO) -> BOOL;
+    fn SetGestureConfig(
+        hwnd: HWND,
+        dwReserved: DWORD,
+        cIDs: UINT,
+        pGestureConfig: *mut GESTURECONFIG,
+        cbSize: UINT,
+    ) -> BOOL;
+}
+
+#[derive(Default)]
+pub struct GestureState {
+    last_position: Position,
+    last_distance: u32,
+}
+
+/// Single finger pan is blocked, so a single touch still works as a mouse.
+pub fn configure(hwnd: HWND) {
+    let mut config = [
+        GESTURECONFIG {
+            dwID: GID_ZOOM,
+            dwWant: GC_ZOOM,
+            dwBlock: 0,
+        },
+        GESTURECONFIG {
+            dwID: GID_PAN,
+            dwWant: GC_PAN,
+            dwBlock: GC_PAN_WITH_SINGLE_FINGER_VERTICALLY
+                | GC_PAN_WITH_SINGLE_FINGER_HORIZONTALLY
+                | GC_PAN_WITH_GUTTER,
+        },
+    ];
+    unsafe {
+        // Gestures are optional, ignore errors
+        SetGestureConfig(
+            hwnd,
+            0,
+            config.len() as UINT,
+            config.as_mut_ptr(),
+            std::mem::size_of::<GESTURECONFIG>() as UINT,
+        );
+    }
+}
+
+/// Returns false if the gesture is not handled and should be passed to DefWindowProc.
+pub fn translate(
+    state: &mut GestureState,
+    hwnd: HWND,
+    lparam: LPARAM,
+    event: &mut Option<InputEvent>,
+) -> bool {
+    let handle = lparam as HGESTUREINFO;
+    unsafe {
+        let mut info: GESTUREINFO = std::mem::zeroed();
+        info.cbSize = std::mem::size_of::<GESTUREINFO>() as UINT;
+        if GetGestureInfo(handle, &mut info) == 0 {
+            return false;
+        }
+
+        let mut point = POINT {
+            x: info.ptsLocation.x as i32,
+            y: info.ptsLocation.y as i32,
+        };
+        ScreenToClient(hwnd, &mut point);
+        let position = (point.x, point.y);
+        let begin = info.dwFlags & GF_BEGIN != 0;
+
+        match info.dwID {
+            GID_ZOOM => {
+                let distance = info.ullArguments as u32;
+                if !begin && state.last_distance > 0 && distance > 0 {
+                    let factor = distance as f32 / state.last_distance as f32;
+                    *event = Some(InputEvent::Zoom(position, factor));
+                }
+                state.last_distance = distance;
+            }
+            GID_PAN => {
+                if !begin {
+                    let delta = (
+                        position.0 - state.last_position.0,
+                        position.1 - state.last_position.1,
+                    );
+                    *event = Some(InputEvent::Pan(position, delta));
+                }
+            }
+            _ => return false,
+        }
+
+        state.last_position = position;
+        CloseGestureInfoHandle(handle);
+        return true;
+    }
+}
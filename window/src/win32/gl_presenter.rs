@@ -0,0 +1,199 @@
+#![allow(non_snake_case)]
+
+use winapi::shared::minwindef::*;
+use winapi::shared::windef::*;
+use winapi::shared::winerror::ERROR_INVALID_PIXEL_FORMAT;
+use winapi::um::errhandlingapi::*;
+use winapi::um::wingdi::*;
+
+use application::image::*;
+
+use crate::win32::APIResult;
+use crate::win32::APIResultCode;
+use crate::win32::GotHDC;
+
+// OpenGL 1.1 is exported by opengl32.dll, winapi 0.3.9 has no bindings for it
+
+type GLenum = u32;
+type GLint = i32;
+type GLuint = u32;
+type GLsizei = i32;
+type GLfloat = f32;
+
+const GL_TEXTURE_2D: GLenum = 0x0DE1;
+const GL_QUADS: GLenum = 0x0007;
+const GL_RGBA8: GLint = 0x8058;
+const GL_BGRA_EXT: GLenum = 0x80E1;
+const GL_UNSIGNED_BYTE: GLenum = 0x1401;
+const GL_TEXTURE_MIN_FILTER: GLenum = 0x2801;
+const GL_TEXTURE_MAG_FILTER: GLenum = 0x2800;
+const GL_NEAREST: GLint = 0x2600;
+const GL_UNPACK_ROW_LENGTH: GLenum = 0x0CF2;
+
+#[link(name = "opengl32")]
+extern "system" {
+    fn glGenTextures(n: GLsizei, textures: *mut GLuint);
+    fn glDeleteTextures(n: GLsizei, textures: *const GLuint);
+    fn glBindTexture(target: GLenum, texture: GLuint);
+    fn glTexParameteri(target: GLenum, name: GLenum, param: GLint);
+    fn glPixelStorei(name: GLenum, param: GLint);
+    fn glTexImage2D(
+        target: GLenum,
+        level: GLint,
+        internal_format: GLint,
+        width: GLsizei,
+        height: GLsizei,
+        border: GLint,
+        format: GLenum,
+        pixel_type: GLenum,
+        pixels: *const std::ffi::c_void,
+    );
+    fn glTexSubImage2D(
+        target: GLenum,
+        level: GLint,
+        x: GLint,
+        y: GLint,
+        width: GLsizei,
+        height: GLsizei,
+        format: GLenum,
+        pixel_type: GLenum,
+        pixels: *const std::ffi::c_void,
+    );
+    fn glEnable(capability: GLenum);
+    fn glViewport(x: GLint, y: GLint, width: GLsizei, height: GLsizei);
+    fn glBegin(mode: GLenum);
+    fn glEnd();
+    fn glTexCoord2f(s: GLfloat, t: GLfloat);
+    fn glVertex2f(x: GLfloat, y: GLfloat);
+}
+
+type SwapIntervalProc = unsafe extern "system" fn(interval: i32) -> BOOL;
+
+/// OpenGL context of the window with the texture the frames are uploaded to.
+pub struct GlPresenter {
+    dc: GotHDC,
+    context: HGLRC,
+    texture: GLuint,
+    /// Powers of two, so old drivers accept the texture; the frame takes its left top part.
+    texture_size: ImageSize,
+}
+
+impl GlPresenter {
+    /// Fails if the driver has no OpenGL, then the window stays with `Presentation::Gdi`.
+    pub unsafe fn new(hwnd: HWND, vsync: bool) -> APIResult<Self> {
+        let dc = GotHDC::new(hwnd)?;
+        // the pixel format of a window is set once, a second presenter reuses it
+        if GetPixelFormat(dc.get_dc()) == 0 {
+            let mut descriptor: PIXELFORMATDESCRIPTOR = std::mem::zeroed();
+            descriptor.nSize = std::mem::size_of::<PIXELFORMATDESCRIPTOR>() as WORD;
+            descriptor.nVersion = 1;
+            descriptor.dwFlags = PFD_DRAW_TO_WINDOW | PFD_SUPPORT_OPENGL | PFD_DOUBLEBUFFER;
+            descriptor.iPixelType = PFD_TYPE_RGBA;
+            descriptor.cColorBits = 32;
+            descriptor.iLayerType = PFD_MAIN_PLANE;
+            let format = run_api!(ChoosePixelFormat(dc.get_dc(), &descriptor))?;
+            if format == 0 {
+                return Err(APIResultCode::new(ERROR_INVALID_PIXEL_FORMAT));
+            }
+            run_api!(SetPixelFormat(dc.get_dc(), format, &descriptor))?;
+        }
+
+        let context = run_api!(wglCreateContext(dc.get_dc()))?;
+        if context.is_null() {
+            return Err(APIResultCode::new(ERROR_INVALID_PIXEL_FORMAT));
+        }
+        if wglMakeCurrent(dc.get_dc(), context) == FALSE {
+            wglDeleteContext(context);
+            return Err(APIResultCode::new(GetLastError()));
+        }
+
+        let swap_interval = wglGetProcAddress(b"wglSwapIntervalEXT\0".as_ptr() as *const i8);
+        if !swap_interval.is_null() {
+            let swap_interval: SwapIntervalProc = std::mem::transmute(swap_interval);
+            swap_interval(vsync as i32);
+        }
+
+        let mut texture = 0;
+        glGenTextures(1, &mut texture);
+        glBindTexture(GL_TEXTURE_2D, texture);
+        // the frame is shown pixel to pixel
+        glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MIN_FILTER, GL_NEAREST);
+        glTexParameteri(GL_TEXTURE_2D, GL_TEXTURE_MAG_FILTER, GL_NEAREST);
+        glEnable(GL_TEXTURE_2D);
+
+        Ok(Self {
+            dc,
+            context,
+            texture,
+            texture_size: (0, 0),
+        })
+    }
+
+    /// Shows the frame in the whole client area, its pixels are 0x00RRGGBB.
+    pub unsafe fn present(&mut self, frame: ImageView<u32>) -> APIResult<()> {
+        let size = frame.get_size();
+        if size.0 == 0 || size.1 == 0 {
+            return Ok(());
+        }
+        if wglMakeCurrent(self.dc.get_dc(), self.context) == FALSE {
+            return Err(APIResultCode::new(GetLastError()));
+        }
+
+        if self.texture_size.0 < size.0 || self.texture_size.1 < size.1 {
+            self.texture_size = (size.0.next_power_of_two(), size.1.next_power_of_two());
+            glTexImage2D(
+                GL_TEXTURE_2D,
+                0,
+                GL_RGBA8,
+                self.texture_size.0 as GLsizei,
+                self.texture_size.1 as GLsizei,
+                0,
+                GL_BGRA_EXT,
+                GL_UNSIGNED_BYTE,
+                std::ptr::null(),
+            );
+        }
+        glPixelStorei(GL_UNPACK_ROW_LENGTH, frame.get_stride() as GLint);
+        glTexSubImage2D(
+            GL_TEXTURE_2D,
+            0,
+            0,
+            0,
+            size.0 as GLsizei,
+            size.1 as GLsizei,
+            GL_BGRA_EXT,
+            GL_UNSIGNED_BYTE,
+            frame.as_ptr() as *const std::ffi::c_void,
+        );
+
+        // the first line of the frame is the top of the window
+        let right = size.0 as GLfloat / self.texture_size.0 as GLfloat;
+        let bottom = size.1 as GLfloat / self.texture_size.1 as GLfloat;
+        glViewport(0, 0, size.0 as GLsizei, size.1 as GLsizei);
+        glBegin(GL_QUADS);
+        glTexCoord2f(0.0, bottom);
+        glVertex2f(-1.0, -1.0);
+        glTexCoord2f(right, bottom);
+        glVertex2f(1.0, -1.0);
+        glTexCoord2f(right, 0.0);
+        glVertex2f(1.0, 1.0);
+        glTexCoord2f(0.0, 0.0);
+        glVertex2f(-1.0, 1.0);
+        glEnd();
+
+        run_api!(SwapBuffers(self.dc.get_dc()))?;
+        Ok(())
+    }
+}
+
+impl Drop for GlPresenter {
+    fn drop(&mut self) {
+        unsafe {
+            if wglMakeCurrent(self.dc.get_dc(), self.context) != FALSE {
+                glDeleteTextures(1, &self.texture);
+            }
+            wglMakeCurrent(std::ptr::null_mut(), std::ptr::null_mut());
+            wglDeleteContext(self.context);
+        }
+    }
+}
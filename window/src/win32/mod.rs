@@ -0,0 +1,1470 @@
+use std::cell::RefCell;
+use std::cmp::{max, min};
+use std::mem::MaybeUninit;
+use std::ops::DerefMut;
+use std::os::windows::ffi::OsStringExt;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use winapi::shared::minwindef::*;
+use winapi::shared::ntdef::LONG;
+use winapi::shared::windef::*;
+use winapi::um::commdlg::*;
+use winapi::um::dwmapi::*;
+use winapi::um::errhandlingapi::*;
+use winapi::um::libloaderapi::*;
+use winapi::um::wingdi::*;
+use winapi::um::winnls::GetLocaleInfoW;
+use winapi::um::winnt::LOCALE_USER_DEFAULT;
+use winapi::um::winuser::*;
+
+use crate::win32::dib_section::DIBSection;
+use crate::win32::errors::*;
+use crate::win32::resources::*;
+use crate::win32::wide_strings::WideStringManager;
+use crate::{frame_pacing, Application, FramePacing, MonitorInfo, Presentation, WindowPosition};
+use application::clipboard::*;
+use application::draw_context::*;
+use application::file_watcher::*;
+use application::font::*;
+use application::gui::input_log::*;
+use application::gui::{GuiSystem, MouseCursor};
+use application::image::*;
+use application::job_system::*;
+use application::keys::*;
+use application::shell::*;
+
+#[macro_use]
+mod errors;
+mod accessibility;
+mod clipboard;
+mod dib_section;
+mod file_watcher;
+mod folder_dialog;
+mod font_loader;
+mod gesture;
+mod gl_presenter;
+mod resources;
+mod shell;
+mod wide_strings;
+
+pub use folder_dialog::show_folder_dialog;
+
+pub struct SystemContext {
+    application: Box<dyn Application>,
+    buffer: Option<DIBSection>,
+    context: Rc<RefCell<Context>>,
+}
+
+pub struct Context {
+    hwnd: HWND,
+    showing_message: bool,
+    accessible_focus: Option<usize>,
+    input_recorder: Option<InputRecorder>,
+    input_player: Option<InputPlayer>,
+    gesture_state: gesture::GestureState,
+    /// Alt is pressed and no other key was pressed since, its release focuses the menu bar.
+    menu_key_pending: bool,
+    /// The frame of the window is dragged by the mouse.
+    sizing: bool,
+    last_sizing_redraw: Option<Instant>,
+    /// The old frame is stretched to the window and waits for the redraw.
+    sizing_stretched: bool,
+    frame_pacer: frame_pacing::FramePacer,
+    presentation: Presentation,
+    gl_presenter: Option<gl_presenter::GlPresenter>,
+    pub font_factory: FontFactory,
+    pub clipboard: Clipboard,
+    pub shell: Shell,
+    pub file_watcher: FileWatcher,
+    pub job_system: JobSystem,
+    pub gui_system: GuiSystem,
+}
+
+impl Context {
+    /// Writes all input of the window to the file, so it can be replayed later.
+    pub fn start_recording(&mut self, path: &Path) -> std::io::Result<()> {
+        self.input_recorder = Some(InputRecorder::create(path)?);
+        Ok(())
+    }
+
+    /// Replays the recorded input in real time; user input is ignored until the end.
+    pub fn start_playback(&mut self, path: &Path) -> std::io::Result<()> {
+        self.input_player = Some(InputPlayer::load(path)?);
+        Ok(())
+    }
+
+    /// Limits the repainting and the timers of the window.
+    pub fn set_frame_pacing(&mut self, pacing: FramePacing) {
+        self.frame_pacer.set_pacing(pacing);
+    }
+
+    /// Chooses how the frames get to the screen, OpenGL falls back to GDI if it fails.
+    pub fn set_presentation(&mut self, presentation: Presentation) {
+        if presentation != self.presentation {
+            self.presentation = presentation;
+            self.gl_presenter = None;
+        }
+    }
+
+    /// Returns false if the frame has to be copied to the window by GDI.
+    unsafe fn present_with_opengl(&mut self, hwnd: HWND, frame: ImageView<u32>) -> bool {
+        let vsync = match self.presentation {
+            Presentation::Gdi => return false,
+            Presentation::OpenGl => false,
+            Presentation::OpenGlVsync => true,
+        };
+        if self.gl_presenter.is_none() {
+            match gl_presenter::GlPresenter::new(hwnd, vsync) {
+                Ok(presenter) => self.gl_presenter = Some(presenter),
+                Err(e) => {
+                    eprintln!("OpenGL presentation is unavailable: {:?}", e);
+                    self.presentation = Presentation::Gdi;
+                    return false;
+                }
+            }
+        }
+        if let Err(e) = self.gl_presenter.as_mut().unwrap().present(frame) {
+            eprintln!("OpenGL presentation failed: {:?}", e);
+            self.set_presentation(Presentation::Gdi);
+            return false;
+        }
+        true
+    }
+
+    /// While the frame of the window is dragged, it is redrawn a few times a second, the
+    /// other frames stretch the last drawn one.
+    fn skip_sizing_redraw(&mut self) -> bool {
+        if !self.sizing {
+            return false;
+        }
+        let now = Instant::now();
+        if self
+            .last_sizing_redraw
+            .map_or(false, |last_redraw| now - last_redraw < SIZING_REDRAW_INTERVAL)
+        {
+            self.sizing_stretched = true;
+            return true;
+        }
+        self.last_sizing_redraw = Some(now);
+        false
+    }
+
+    /// Returns true once the stretched frame has to be redrawn.
+    fn take_due_sizing_redraw(&mut self) -> bool {
+        let due = self.sizing_stretched
+            && self
+                .last_sizing_redraw
+                .map_or(true, |last_redraw| last_redraw.elapsed() >= SIZING_REDRAW_INTERVAL);
+        if due {
+            self.sizing_stretched = false;
+        }
+        due
+    }
+}
+
+/// Full redraws of the window while its frame is dragged are not more often.
+const SIZING_REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+pub fn get_client_rect(hwnd: HWND) -> APIResult<RECT> {
+    unsafe {
+        let mut rect = MaybeUninit::uninit();
+        run_api!(GetClientRect(hwnd, rect.as_mut_ptr()))?;
+        Ok(rect.assume_init())
+    }
+}
+
+pub fn get_window_rect(hwnd: HWND) -> APIResult<RECT> {
+    unsafe {
+        let mut rect = MaybeUninit::uninit();
+        run_api!(GetWindowRect(hwnd, rect.as_mut_ptr()))?;
+        Ok(rect.assume_init())
+    }
+}
+
+pub fn get_desctop_rect() -> APIResult<RECT> {
+    unsafe {
+        let mut rect = MaybeUninit::<RECT>::uninit();
+        run_api!(SystemParametersInfoW(
+            SPI_GETWORKAREA,
+            0,
+            std::mem::transmute(rect.as_mut_ptr()),
+            0
+        ))?;
+        Ok(rect.assume_init())
+    }
+}
+
+impl MonitorInfo {
+    fn work_rect(&self) -> RECT {
+        RECT {
+            left: self.work_left_top.0,
+            top: self.work_left_top.1,
+            right: self.work_right_bottom.0,
+            bottom: self.work_right_bottom.1,
+        }
+    }
+}
+
+fn get_monitor_info(monitor: HMONITOR) -> APIResult<MonitorInfo> {
+    unsafe {
+        let mut info = MaybeUninit::<MONITORINFO>::zeroed().assume_init();
+        info.cbSize = std::mem::size_of::<MONITORINFO>() as u32;
+        run_api!(GetMonitorInfoW(monitor, &mut info))?;
+        Ok(MonitorInfo {
+            left_top: (info.rcMonitor.left, info.rcMonitor.top),
+            right_bottom: (info.rcMonitor.right, info.rcMonitor.bottom),
+            work_left_top: (info.rcWork.left, info.rcWork.top),
+            work_right_bottom: (info.rcWork.right, info.rcWork.bottom),
+            primary: info.dwFlags & MONITORINFOF_PRIMARY != 0,
+        })
+    }
+}
+
+pub fn get_monitors() -> Vec<MonitorInfo> {
+    unsafe extern "system" fn enum_proc(
+        monitor: HMONITOR,
+        _dc: HDC,
+        _rect: LPRECT,
+        data: LPARAM,
+    ) -> BOOL {
+        let monitors = &mut *(data as *mut Vec<MonitorInfo>);
+        if let Ok(info) = get_monitor_info(monitor) {
+            monitors.push(info);
+        }
+        TRUE
+    }
+
+    let mut monitors = Vec::new();
+    unsafe {
+        EnumDisplayMonitors(
+            0 as HDC,
+            0 as *const RECT,
+            Some(enum_proc),
+            &mut monitors as *mut Vec<MonitorInfo> as LPARAM,
+        );
+    }
+    monitors
+}
+
+/// The monitor nearest to the rect, so a rect saved on a removed monitor gets a valid one.
+pub fn get_monitor_by_rect(rect: &RECT) -> APIResult<MonitorInfo> {
+    get_monitor_info(unsafe { MonitorFromRect(rect, MONITOR_DEFAULTTONEAREST) })
+}
+
+pub fn get_monitor_under_cursor() -> APIResult<MonitorInfo> {
+    unsafe {
+        let mut point = POINT { x: 0, y: 0 };
+        run_api!(GetCursorPos(&mut point))?;
+        get_monitor_info(MonitorFromPoint(point, MONITOR_DEFAULTTOPRIMARY))
+    }
+}
+
+pub fn get_window_move_bounds(hwnd: HWND) -> APIResult<RECT> {
+    get_window_move_bounds_for_rect(hwnd, &get_window_rect(hwnd)?)
+}
+
+/// Bounds of the work area of the monitor where the window with the given rect would be,
+/// extended by the invisible part of the window frame.
+pub fn get_window_move_bounds_for_rect(hwnd: HWND, rect: &RECT) -> APIResult<RECT> {
+    unsafe {
+        let window_rect = get_window_rect(hwnd)?;
+        let mut frame_rect = window_rect;
+        let _dwa_result = DwmGetWindowAttribute(
+            hwnd,
+            DWMWA_EXTENDED_FRAME_BOUNDS,
+            &mut frame_rect as *mut RECT as *mut _,
+            std::mem::size_of::<RECT>() as u32,
+        );
+        // dont care about result, window_rect is ok for us
+        let desktop_rect = get_monitor_by_rect(rect)?.work_rect();
+
+        Ok(RECT {
+            left: desktop_rect.left + window_rect.left - frame_rect.left,
+            top: desktop_rect.top + window_rect.top - frame_rect.top,
+            right: desktop_rect.right + window_rect.right - frame_rect.right,
+            bottom: desktop_rect.bottom + window_rect.bottom - frame_rect.bottom,
+        })
+    }
+}
+
+pub fn adjust_rect(src: RECT, dst: &mut RECT) {
+    if dst.left < src.left {
+        dst.right += src.left - dst.left;
+        dst.left = src.left;
+    }
+
+    if dst.top < src.top {
+        dst.bottom += src.top - dst.top;
+        dst.top = src.top;
+    }
+
+    if dst.right > src.right {
+        dst.left -= dst.right - src.right;
+        dst.right = src.right;
+    }
+
+    if dst.bottom > src.bottom {
+        dst.top -= dst.bottom - src.bottom;
+        dst.bottom = src.bottom;
+    }
+
+    dst.left = max(dst.left, src.left);
+    dst.top = max(dst.top, src.top);
+    dst.right = min(dst.right, src.right);
+    dst.bottom = min(dst.bottom, src.bottom);
+}
+
+const VK_0: i32 = '0' as i32;
+const VK_1: i32 = '1' as i32;
+const VK_2: i32 = '2' as i32;
+const VK_3: i32 = '3' as i32;
+const VK_4: i32 = '4' as i32;
+const VK_5: i32 = '5' as i32;
+const VK_6: i32 = '6' as i32;
+const VK_7: i32 = '7' as i32;
+const VK_8: i32 = '8' as i32;
+const VK_9: i32 = '9' as i32;
+const VK_A: i32 = 'A' as i32;
+const VK_B: i32 = 'B' as i32;
+const VK_C: i32 = 'C' as i32;
+const VK_D: i32 = 'D' as i32;
+const VK_E: i32 = 'E' as i32;
+const VK_F: i32 = 'F' as i32;
+const VK_G: i32 = 'G' as i32;
+const VK_H: i32 = 'H' as i32;
+const VK_I: i32 = 'I' as i32;
+const VK_J: i32 = 'J' as i32;
+const VK_K: i32 = 'K' as i32;
+const VK_L: i32 = 'L' as i32;
+const VK_M: i32 = 'M' as i32;
+const VK_N: i32 = 'N' as i32;
+const VK_O: i32 = 'O' as i32;
+const VK_P: i32 = 'P' as i32;
+const VK_Q: i32 = 'Q' as i32;
+const VK_R: i32 = 'R' as i32;
+const VK_S: i32 = 'S' as i32;
+const VK_T: i32 = 'T' as i32;
+const VK_U: i32 = 'U' as i32;
+const VK_V: i32 = 'V' as i32;
+const VK_W: i32 = 'W' as i32;
+const VK_X: i32 = 'X' as i32;
+const VK_Y: i32 = 'Y' as i32;
+const VK_Z: i32 = 'Z' as i32;
+
+fn wparam_to_key(code: WPARAM) -> Option<Key> {
+    match code as i32 {
+        VK_LEFT => return Some(Key::Left),
+        VK_RIGHT => return Some(Key::Right),
+        VK_UP => return Some(Key::Up),
+        VK_DOWN => return Some(Key::Down),
+        VK_BACK => return Some(Key::Backspace),
+        VK_INSERT => return Some(Key::Insert),
+        VK_HOME => return Some(Key::Home),
+        VK_END => return Some(Key::End),
+        VK_PRIOR => return Some(Key::PageUp),
+        VK_NEXT => return Some(Key::PageDown),
+        VK_DELETE => return Some(Key::Delete),
+        VK_SPACE => return Some(Key::Space),
+        VK_TAB => return Some(Key::Tab),
+        VK_NUMPAD0 => return Some(Key::Numpad0),
+        VK_NUMPAD1 => return Some(Key::Numpad1),
+        VK_NUMPAD2 => return Some(Key::Numpad2),
+        VK_NUMPAD3 => return Some(Key::Numpad3),
+        VK_NUMPAD4 => return Some(Key::Numpad4),
+        VK_NUMPAD5 => return Some(Key::Numpad5),
+        VK_NUMPAD6 => return Some(Key::Numpad6),
+        VK_NUMPAD7 => return Some(Key::Numpad7),
+        VK_NUMPAD8 => return Some(Key::Numpad8),
+        VK_NUMPAD9 => return Some(Key::Numpad9),
+        VK_ESCAPE => return Some(Key::Escape),
+        VK_RETURN => return Some(Key::Enter),
+        VK_F1 => return Some(Key::F1),
+        VK_F2 => return Some(Key::F2),
+        VK_F3 => return Some(Key::F3),
+        VK_F4 => return Some(Key::F4),
+        VK_F5 => return Some(Key::F5),
+        VK_F6 => return Some(Key::F6),
+        VK_F7 => return Some(Key::F7),
+        VK_F8 => return Some(Key::F8),
+        VK_F9 => return Some(Key::F9),
+        VK_F10 => return Some(Key::F10),
+        VK_F11 => return Some(Key::F11),
+        VK_F12 => return Some(Key::F12),
+        VK_0 => return Some(Key::Num0),
+        VK_1 => return Some(Key::Num1),
+        VK_2 => return Some(Key::Num2),
+        VK_3 => return Some(Key::Num3),
+        VK_4 => return Some(Key::Num4),
+        VK_5 => return Some(Key::Num5),
+        VK_6 => return Some(Key::Num6),
+        VK_7 => return Some(Key::Num7),
+        VK_8 => return Some(Key::Num8),
+        VK_9 => return Some(Key::Num9),
+        VK_A => return Some(Key::A),
+        VK_B => return Some(Key::B),
+        VK_C => return Some(Key::C),
+        VK_D => return Some(Key::D),
+        VK_E => return Some(Key::E),
+        VK_F => return Some(Key::F),
+        VK_G => return Some(Key::G),
+        VK_H => return Some(Key::H),
+        VK_I => return Some(Key::I),
+        VK_J => return Some(Key::J),
+        VK_K => return Some(Key::K),
+        VK_L => return Some(Key::L),
+        VK_M => return Some(Key::M),
+        VK_N => return Some(Key::N),
+        VK_O => return Some(Key::O),
+        VK_P => return Some(Key::P),
+        VK_Q => return Some(Key::Q),
+        VK_R => return Some(Key::R),
+        VK_S => return Some(Key::S),
+        VK_T => return Some(Key::T),
+        VK_U => return Some(Key::U),
+        VK_V => return Some(Key::V),
+        VK_W => return Some(Key::W),
+        VK_X => return Some(Key::X),
+        VK_Y => return Some(Key::Y),
+        VK_Z => return Some(Key::Z),
+        _ => return None,
+    }
+}
+
+fn adjust_window_size(context: Rc<RefCell<Context>>, hwnd: HWND) -> APIResult<()> {
+    let minimal_size = context.borrow().gui_system.get_minimal_size_of_system();
+    let client_rect = get_client_rect(hwnd)?;
+    if client_rect.right - client_rect.left >= minimal_size.0
+        && client_rect.bottom - client_rect.top >= minimal_size.1
+    {
+        return Ok(());
+    }
+
+    let dx = max(0, minimal_size.0 - (client_rect.right - client_rect.left));
+    let dy = max(0, minimal_size.1 - (client_rect.bottom - client_rect.top));
+    let mut window_rect = get_window_rect(hwnd)?;
+    window_rect.left -= dx / 2;
+    window_rect.top -= dy / 2;
+    window_rect.right += (dx + 1) / 2;
+    window_rect.bottom += (dy + 1) / 2;
+    adjust_rect(get_window_move_bounds(hwnd)?, &mut window_rect);
+
+    unsafe {
+        run_api!(SetWindowPos(
+            hwnd,
+            0 as HWND,
+            window_rect.left,
+            window_rect.top,
+            window_rect.right - window_rect.left,
+            window_rect.bottom - window_rect.top,
+            0
+        ))?;
+    }
+
+    Ok(())
+}
+
+fn run_jobs(context: Rc<RefCell<Context>>, hwnd: HWND) -> APIResult<()> {
+    if context.borrow().showing_message {
+        // attempt to recursion, wait for jobs
+    } else {
+        let job_system = context.borrow_mut().job_system.clone();
+        if job_system.run_all() {
+            // jobs can borrow context
+            adjust_window_size(context.clone(), hwnd)?;
+        }
+        accessibility::notify_focus(context, hwnd);
+    }
+    Ok(())
+}
+
+fn get_window_position(hwnd: HWND) -> APIResult<WindowPosition> {
+    unsafe {
+        let mut wp = MaybeUninit::<WINDOWPLACEMENT>::zeroed().assume_init();
+        wp.length = std::mem::size_of::<WINDOWPLACEMENT>() as u32;
+        run_api!(GetWindowPlacement(hwnd, &mut wp))?;
+        let rect = get_window_rect(hwnd)?;
+        let maximized = wp.showCmd as i32 == SW_SHOWMAXIMIZED;
+        Ok(WindowPosition {
+            maximized,
+            left_top: (rect.left, rect.top),
+            right_bottom: (rect.right, rect.bottom),
+        })
+    }
+}
+
+/// Asks for WM_PAINT without invalidating the window, so its paint rect only has the parts
+/// damaged by the system and the rest is copied by the damage of the frame.
+unsafe fn invalidate(hwnd: HWND) -> APIResult<BOOL> {
+    run_api!(RedrawWindow(
+        hwnd,
+        0 as *const RECT,
+        0 as HRGN,
+        RDW_INTERNALPAINT
+    ))
+}
+
+/// Invalidates the window at most once a frame, later requests wait for the timer.
+unsafe fn repaint(context: &Rc<RefCell<Context>>, hwnd: HWND) -> APIResult<()> {
+    if context.borrow_mut().frame_pacer.request_frame() {
+        invalidate(hwnd)?;
+    }
+    Ok(())
+}
+
+fn dispatch_input(context: Rc<RefCell<Context>>, hwnd: HWND, event: InputEvent) -> APIResult<()> {
+    if let InputEvent::Resize(size) = event {
+        let client_rect = get_client_rect(hwnd)?;
+        let window_rect = get_window_rect(hwnd)?;
+        unsafe {
+            ShowWindow(hwnd, SW_RESTORE);
+            run_api!(SetWindowPos(
+                hwnd,
+                0 as HWND,
+                0,
+                0,
+                size.0 as i32 + (window_rect.right - window_rect.left)
+                    - (client_rect.right - client_rect.left),
+                size.1 as i32 + (window_rect.bottom - window_rect.top)
+                    - (client_rect.bottom - client_rect.top),
+                SWP_NOMOVE | SWP_NOZORDER
+            ))?;
+        }
+        return Ok(());
+    }
+
+    if event.dispatch(&mut context.borrow_mut().gui_system) {
+        run_jobs(context.clone(), hwnd)?;
+        unsafe {
+            repaint(&context, hwnd)?;
+        }
+    }
+    Ok(())
+}
+
+fn handle_input(context: Rc<RefCell<Context>>, hwnd: HWND, event: InputEvent) -> APIResult<()> {
+    if context.borrow().input_player.is_some() {
+        return Ok(());
+    }
+
+    // the password is not written to the log, the playback has to type it again
+    let skip_recording = event.is_typing() && context.borrow().gui_system.is_masked_input_focused();
+    let record_result = match context.borrow_mut().input_recorder.as_mut() {
+        Some(recorder) if !skip_recording => recorder.record(event),
+        _ => Ok(()),
+    };
+    if let Err(e) = record_result {
+        eprintln!("Input recording stopped: {}", e);
+        context.borrow_mut().input_recorder = None;
+    }
+
+    if let InputEvent::Resize(_) = event {
+        return Ok(());
+    }
+    dispatch_input(context, hwnd, event)
+}
+
+fn play_input(context: Rc<RefCell<Context>>, hwnd: HWND) -> APIResult<()> {
+    loop {
+        let event = match context.borrow_mut().input_player.as_mut() {
+            Some(player) => player.next_due_event(),
+            None => return Ok(()),
+        };
+        match event {
+            Some(event) => dispatch_input(context.clone(), hwnd, event)?,
+            None => break,
+        }
+    }
+
+    let mut context_ref = context.borrow_mut();
+    if context_ref.input_player.as_ref().map_or(false, |p| p.is_finished()) {
+        context_ref.input_player = None;
+    }
+    Ok(())
+}
+
+unsafe fn maybe_window_proc(
+    hwnd: HWND,
+    msg: UINT,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> APIResult<LRESULT> {
+    let get_context =
+        || -> APIResult<(&mut dyn Application, &mut Option<DIBSection>, Rc<RefCell<Context>>)> {
+            let system_context: &mut SystemContext =
+                std::mem::transmute(run_api!(GetWindowLongPtrW(hwnd, GWL_USERDATA))?);
+            Ok((
+                system_context.application.deref_mut(),
+                &mut system_context.buffer,
+                system_context.context.clone(),
+            ))
+        };
+
+    match msg {
+        WM_CHAR => {
+            let codes = [wparam as u16];
+            let str = std::ffi::OsString::from_wide(&codes)
+                .into_string()
+                .map_err(|_| {
+                    APIResultCode::new(0x203D) // ERROR_DS_DECODING_ERROR
+                })?;
+
+            let (_, _, context) = get_context()?;
+            for c in str.chars() {
+                handle_input(context.clone(), hwnd, InputEvent::Char(c))?;
+            }
+        }
+
+        WM_KEYDOWN => {
+            get_context()?.2.borrow_mut().menu_key_pending = false;
+            if let Some(key) = wparam_to_key(wparam) {
+                let hotkey = Hotkey {
+                    key,
+                    ctrl: GetAsyncKeyState(VK_CONTROL) < 0,
+                    alt: false,
+                    shift: GetAsyncKeyState(VK_SHIFT) < 0,
+                };
+
+                let (_, _, context) = get_context()?;
+                handle_input(context, hwnd, InputEvent::KeyDown(hotkey))?;
+            }
+        }
+
+        WM_KEYUP => {
+            if let Some(key) = wparam_to_key(wparam) {
+                let (_, _, context) = get_context()?;
+                handle_input(context, hwnd, InputEvent::KeyUp(key))?;
+            }
+        }
+
+        WM_SYSKEYDOWN => {
+            // repeated Alt keeps the flag, any other key clears it
+            get_context()?.2.borrow_mut().menu_key_pending = wparam as i32 == VK_MENU;
+            if let Some(key) = wparam_to_key(wparam) {
+                let hotkey = Hotkey {
+                    key,
+                    ctrl: GetAsyncKeyState(VK_CONTROL) < 0,
+                    alt: true,
+                    shift: GetAsyncKeyState(VK_SHIFT) < 0,
+                };
+
+                let (_, _, context) = get_context()?;
+                handle_input(context, hwnd, InputEvent::KeyDown(hotkey))?;
+            }
+        }
+
+        WM_SYSCHAR => {
+            let codes = [wparam as u16];
+            if let Ok(str) = std::ffi::OsString::from_wide(&codes).into_string() {
+                let (_, _, context) = get_context()?;
+                for c in str.chars() {
+                    handle_input(context.clone(), hwnd, InputEvent::Accelerator(c))?;
+                }
+            }
+            return Ok(0);
+        }
+
+        WM_SYSKEYUP => {
+            if wparam as i32 == VK_MENU {
+                let (_, _, context) = get_context()?;
+                let pending = std::mem::replace(&mut context.borrow_mut().menu_key_pending, false);
+                if pending {
+                    // the system menu is not entered, the menu bar of the window is used instead
+                    handle_input(context, hwnd, InputEvent::MenuKey)?;
+                    return Ok(0);
+                }
+            }
+        }
+
+        WM_PAINT => {
+            let rect = get_client_rect(hwnd)?;
+            let rect_size = (
+                (rect.right - rect.left) as usize,
+                (rect.bottom - rect.top) as usize,
+            );
+            let (_, buffer, context) = get_context()?;
+            let old_size = buffer.as_ref().map(DIBSection::get_size);
+            if let (Some(old_size), Some(old_buffer)) = (old_size, buffer.as_ref()) {
+                if old_size != rect_size && context.borrow_mut().skip_sizing_redraw() {
+                    let paint_struct_context = PaintStructContext::new(hwnd)?;
+                    SetStretchBltMode(paint_struct_context.get_dc(), COLORONCOLOR);
+                    run_api!(StretchBlt(
+                        paint_struct_context.get_dc(),
+                        0,
+                        0,
+                        rect_size.0 as i32,
+                        rect_size.1 as i32,
+                        old_buffer.get_dc(),
+                        0,
+                        0,
+                        old_size.0 as i32,
+                        old_size.1 as i32,
+                        SRCCOPY
+                    ))?;
+                    return Ok(0);
+                }
+            }
+            if old_size != Some(rect_size) {
+                // the bitmap has a reserve, so it is not created again on every resize
+                if !buffer.as_mut().map_or(false, |buffer| buffer.try_resize(rect_size)) {
+                    *buffer = Some(DIBSection::new(rect_size)?);
+                }
+                context.borrow_mut().gui_system.on_resize();
+            }
+
+            let buffer = buffer.as_mut().unwrap();
+            let mut context_borrow_mut = context.borrow_mut();
+            let context_ref = context_borrow_mut.deref_mut();
+            let mut draw_context = DrawContext {
+                buffer: buffer.as_view_mut(),
+                font_factory: &mut context_ref.font_factory,
+            };
+
+            context_ref.gui_system.on_draw(&mut draw_context);
+            // the taskbar asks for the new pictures when it shows them
+            let _dwm_result = DwmInvalidateIconicBitmaps(hwnd);
+            let paint_struct_context = PaintStructContext::new(hwnd)?;
+            if context_ref.present_with_opengl(hwnd, buffer.as_view()) {
+                return Ok(0);
+            }
+            let paint_rect = paint_struct_context.get_paint_rect();
+            let damage = context_ref.gui_system.get_damage().iter().map(|rect| RECT {
+                left: rect.left_top.0,
+                top: rect.left_top.1,
+                right: rect.right_bottom.0,
+                bottom: rect.right_bottom.1,
+            });
+            for rect in std::iter::once(paint_rect).chain(damage) {
+                let left = max(rect.left, 0);
+                let top = max(rect.top, 0);
+                let right = min(rect.right, rect_size.0 as i32);
+                let bottom = min(rect.bottom, rect_size.1 as i32);
+                if right <= left || bottom <= top {
+                    continue;
+                }
+                run_api!(BitBlt(
+                    paint_struct_context.get_dc(),
+                    left,
+                    top,
+                    right - left,
+                    bottom - top,
+                    buffer.get_dc(),
+                    left,
+                    top,
+                    SRCCOPY
+                ))?;
+            }
+        }
+
+        WM_DWMSENDICONICTHUMBNAIL => {
+            let max_size = (HIWORD(lparam as DWORD) as usize, LOWORD(lparam as DWORD) as usize);
+            let (application, buffer, _) = get_context()?;
+            let thumbnail = match application.get_taskbar_thumbnail(max_size) {
+                Some(thumbnail) => thumbnail,
+                None => match buffer {
+                    Some(buffer) => shrink_to_fit(buffer.as_view(), max_size),
+                    None => return Ok(0),
+                },
+            };
+            let bitmap = create_dwm_bitmap(thumbnail.as_view())?;
+            let _dwm_result = DwmSetIconicThumbnail(hwnd, bitmap.get_handle() as HBITMAP, 0);
+            return Ok(0);
+        }
+
+        // the peek shows the last frame, as the window would show it without the thumbnail
+        WM_DWMSENDICONICLIVEPREVIEWBITMAP => {
+            let (_, buffer, _) = get_context()?;
+            if let Some(buffer) = buffer {
+                let bitmap = create_dwm_bitmap(buffer.as_view())?;
+                let window_rect = get_window_rect(hwnd)?;
+                let mut client_offset = POINT { x: 0, y: 0 };
+                run_api!(ClientToScreen(hwnd, &mut client_offset))?;
+                client_offset.x -= window_rect.left;
+                client_offset.y -= window_rect.top;
+                let _dwm_result = DwmSetIconicLivePreviewBitmap(
+                    hwnd,
+                    bitmap.get_handle() as HBITMAP,
+                    &mut client_offset,
+                    DWM_SIT_DISPLAYFRAME,
+                );
+            }
+            return Ok(0);
+        }
+
+        // the frame covers the whole window, erasing the background only makes it flash
+        WM_ERASEBKGND => {
+            return Ok(1);
+        }
+
+        WM_ENTERSIZEMOVE => {
+            let (_, _, context) = get_context()?;
+            let mut context = context.borrow_mut();
+            context.sizing = true;
+            context.last_sizing_redraw = None;
+        }
+
+        WM_EXITSIZEMOVE => {
+            let (_, _, context) = get_context()?;
+            {
+                let mut context = context.borrow_mut();
+                context.sizing = false;
+                context.sizing_stretched = false;
+            }
+            run_api!(InvalidateRect(hwnd, 0 as *const RECT, FALSE))?;
+        }
+
+        WM_SIZING => {
+            let p_rect: *mut RECT = std::mem::transmute(lparam);
+            let rect = &mut *p_rect;
+
+            let client_rect = get_client_rect(hwnd)?;
+            let window_rect = get_window_rect(hwnd)?;
+
+            let (_, _, context) = get_context()?;
+            let minimal_size = context.borrow().gui_system.get_minimal_size_of_system();
+            // Count size of bevel...
+            let minimal_size_x = minimal_size.0 as i32 + (window_rect.right - window_rect.left)
+                - (client_rect.right - client_rect.left);
+            let minimal_size_y = minimal_size.1 as i32 + (window_rect.bottom - window_rect.top)
+                - (client_rect.bottom - client_rect.top);
+
+            match wparam as u32 {
+                WMSZ_BOTTOMRIGHT | WMSZ_RIGHT | WMSZ_TOPRIGHT => {
+                    rect.right = max(rect.right, rect.left + minimal_size_x)
+                }
+                WMSZ_BOTTOMLEFT | WMSZ_LEFT | WMSZ_TOPLEFT => {
+                    rect.left = min(rect.left, rect.right - minimal_size_x)
+                }
+                WMSZ_BOTTOM | WMSZ_TOP => {}
+                _ => eprintln!("Wrong wparam {} in WM_SIZING message!", wparam),
+            }
+
+            match wparam as u32 {
+                WMSZ_BOTTOM | WMSZ_BOTTOMLEFT | WMSZ_BOTTOMRIGHT => {
+                    rect.bottom = max(rect.bottom, rect.top + minimal_size_y)
+                }
+                WMSZ_TOP | WMSZ_TOPLEFT | WMSZ_TOPRIGHT => {
+                    rect.top = min(rect.top, rect.bottom - minimal_size_y)
+                }
+                WMSZ_LEFT | WMSZ_RIGHT => {}
+                _ => eprintln!("Wrong wparam {} in WM_SIZING message!", wparam),
+            }
+        }
+
+        WM_SIZE => {
+            let (application, _, context) = get_context()?;
+            let size = (
+                LOWORD(lparam as u32) as usize,
+                HIWORD(lparam as u32) as usize,
+            );
+            context
+                .borrow_mut()
+                .frame_pacer
+                .set_minimized(wparam == SIZE_MINIMIZED);
+            if wparam != SIZE_MINIMIZED {
+                handle_input(context.clone(), hwnd, InputEvent::Resize(size))?;
+            }
+            adjust_window_size(context.clone(), hwnd)?;
+            application.on_change_position(get_window_position(hwnd)?);
+        }
+
+        WM_MOVING => {
+            let p_rect: *mut RECT = std::mem::transmute(lparam);
+            let rect = &mut *p_rect;
+            adjust_rect(get_window_move_bounds_for_rect(hwnd, rect)?, rect);
+        }
+
+        WM_MOVE => {
+            let (application, _, _) = get_context()?;
+            application.on_change_position(get_window_position(hwnd)?);
+        }
+
+        // the window class gets double clicks, the second press of the left button is
+        // still a press
+        WM_LBUTTONDOWN | WM_LBUTTONDBLCLK => {
+            run_api!(SetCapture(hwnd))?;
+            let position = (
+                LOWORD(lparam as u32) as i16 as i32,
+                HIWORD(lparam as u32) as i16 as i32,
+            );
+
+            let (_, _, context) = get_context()?;
+            handle_input(context, hwnd, InputEvent::MouseDown(position))?;
+        }
+
+        WM_MOUSEWHEEL => {
+            let mut point = POINT {
+                x: LOWORD(lparam as u32) as i16 as i32,
+                y: HIWORD(lparam as u32) as i16 as i32,
+            };
+
+            run_api!(ScreenToClient(hwnd, &mut point))?;
+            let position = (point.x, point.y);
+            let delta = -(HIWORD(wparam as u32) as i16 as f32) / (WHEEL_DELTA as f32);
+            let event = if LOWORD(wparam as u32) as usize & MK_SHIFT != 0 {
+                InputEvent::MouseWheelWithShift(position, delta)
+            } else {
+                InputEvent::MouseWheel(position, delta)
+            };
+            let (_, _, context) = get_context()?;
+            handle_input(context, hwnd, event)?;
+        }
+
+        WM_MBUTTONDBLCLK => {
+            let position = (
+                LOWORD(lparam as u32) as i16 as i32,
+                HIWORD(lparam as u32) as i16 as i32,
+            );
+            let (_, _, context) = get_context()?;
+            handle_input(context, hwnd, InputEvent::MiddleDoubleClick(position))?;
+        }
+
+        WM_MOUSEHWHEEL => {
+            let mut point = POINT {
+                x: LOWORD(lparam as u32) as i16 as i32,
+                y: HIWORD(lparam as u32) as i16 as i32,
+            };
+
+            run_api!(ScreenToClient(hwnd, &mut point))?;
+            let position = (point.x, point.y);
+            let delta = (HIWORD(wparam as u32) as i16 as f32) / (WHEEL_DELTA as f32);
+            let (_, _, context) = get_context()?;
+            handle_input(context, hwnd, InputEvent::MouseHWheel(position, delta))?;
+        }
+
+        WM_MOUSEMOVE => {
+            let position = (
+                LOWORD(lparam as u32) as i16 as i32,
+                HIWORD(lparam as u32) as i16 as i32,
+            );
+            let (_, _, context) = get_context()?;
+            handle_input(context, hwnd, InputEvent::MouseMove(position))?;
+
+            let mut tme = TRACKMOUSEEVENT {
+                cbSize: std::mem::size_of::<TRACKMOUSEEVENT>() as u32,
+                hwndTrack: hwnd,
+                dwFlags: TME_HOVER | TME_LEAVE,
+                dwHoverTime: HOVER_DEFAULT,
+            };
+            run_api!(TrackMouseEvent(&mut tme))?;
+        }
+
+        WM_MOUSELEAVE => {
+            let (_, _, context) = get_context()?;
+            handle_input(context, hwnd, InputEvent::MouseLeave)?;
+        }
+
+        WM_LBUTTONUP => {
+            run_api!(ReleaseCapture())?;
+            let position = (
+                LOWORD(lparam as u32) as i16 as i32,
+                HIWORD(lparam as u32) as i16 as i32,
+            );
+            let (_, _, context) = get_context()?;
+            handle_input(context, hwnd, InputEvent::MouseUp(position))?;
+        }
+
+        WM_GESTURE => {
+            let (_, _, context) = get_context()?;
+            let mut event = None;
+            let handled = gesture::translate(
+                &mut context.borrow_mut().gesture_state,
+                hwnd,
+                lparam,
+                &mut event,
+            );
+            if let Some(event) = event {
+                handle_input(context, hwnd, event)?;
+            }
+            if handled {
+                return Ok(0);
+            }
+        }
+
+        WM_ACTIVATE => {
+            let (_, _, context) = get_context()?;
+            let inactive = LOWORD(wparam as u32) == WA_INACTIVE as WORD;
+            context.borrow_mut().frame_pacer.set_inactive(inactive);
+            if wparam == WA_INACTIVE as WPARAM {
+                handle_input(context, hwnd, InputEvent::Deactivate)?;
+            }
+        }
+
+        WM_SETCURSOR => {
+            let (_, _, context) = get_context()?;
+            if LOWORD(lparam as u32) as isize == HTCLIENT && context.borrow().job_system.is_busy()
+            {
+                SetCursor(run_api!(LoadCursorW(0 as HINSTANCE, IDC_WAIT))?);
+                return Ok(TRUE as LRESULT);
+            }
+            if LOWORD(lparam as u32) as isize == HTCLIENT {
+                let mut point = POINT { x: 0, y: 0 };
+                if GetCursorPos(&mut point) != 0 && ScreenToClient(hwnd, &mut point) != 0 {
+                    let cursor = context.borrow().gui_system.get_cursor((point.x, point.y));
+                    if cursor == MouseCursor::Hand {
+                        SetCursor(run_api!(LoadCursorW(0 as HINSTANCE, IDC_HAND))?);
+                        return Ok(TRUE as LRESULT);
+                    }
+                }
+            }
+        }
+
+        WM_TIMER => {
+            let (application, _, context) = get_context()?;
+            play_input(context.clone(), hwnd)?;
+            let paused = context.borrow().frame_pacer.is_paused();
+            if !paused && application.on_timer(context.clone()) {
+                run_jobs(context.clone(), hwnd)?;
+                repaint(&context, hwnd)?;
+            }
+            let job_system = context.borrow().job_system.clone();
+            if job_system.run_task_steps(std::time::Duration::from_millis(30)) {
+                if !job_system.is_busy() {
+                    SetCursor(run_api!(LoadCursorW(0 as HINSTANCE, IDC_ARROW))?);
+                }
+                run_jobs(context.clone(), hwnd)?;
+                repaint(&context, hwnd)?;
+            }
+            if !paused && context.borrow_mut().gui_system.on_timer() {
+                run_jobs(context.clone(), hwnd)?;
+                repaint(&context, hwnd)?;
+            }
+
+            if context.borrow_mut().frame_pacer.take_due_frame() {
+                invalidate(hwnd)?;
+            }
+            if context.borrow_mut().take_due_sizing_redraw() {
+                run_api!(InvalidateRect(hwnd, 0 as *const RECT, FALSE))?;
+            }
+            let busy = job_system.has_tasks() || context.borrow().input_player.is_some();
+            let interval = context.borrow_mut().frame_pacer.update_timer_interval(busy);
+            if let Some(interval) = interval {
+                SetTimer(hwnd, 0, interval, None);
+            }
+            return Ok(0);
+        }
+
+        WM_GETOBJECT => {
+            let object_id = lparam as DWORD as LONG;
+            if object_id == OBJID_CLIENT || object_id == accessibility::UIA_ROOT_OBJECT_ID {
+                return Ok(accessibility::get_object(hwnd, wparam, lparam));
+            }
+        }
+
+        accessibility::WM_ACCESSIBLE_ACTION | accessibility::WM_ACCESSIBLE_FOCUS => {
+            let (_, _, context) = get_context()?;
+            if accessibility::handle_request(context.clone(), msg, wparam) {
+                run_jobs(context.clone(), hwnd)?;
+                repaint(&context, hwnd)?;
+            }
+        }
+
+        WM_CLOSE => {
+            let (application, _, context) = get_context()?;
+            application.on_close(context.clone());
+        }
+
+        WM_DESTROY => {
+            accessibility::disconnect(hwnd);
+            PostQuitMessage(0);
+        }
+        _ => {}
+    }
+
+    Ok(DefWindowProcW(hwnd, msg, wparam, lparam))
+}
+
+unsafe extern "system" fn window_proc(
+    hwnd: HWND,
+    msg: UINT,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    match maybe_window_proc(hwnd, msg, wparam, lparam) {
+        Ok(l_result) => return l_result,
+        Err(_) => {
+            // Do nothing, read message and continue
+            return DefWindowProcW(hwnd, msg, wparam, lparam);
+        }
+    }
+}
+
+fn create_window(
+    name: &str,
+    context: *mut SystemContext,
+    window_position: Option<WindowPosition>,
+) -> APIResult<HWND> {
+    let mut wide_strings = WideStringManager::new();
+
+    unsafe {
+        let hinstance = run_api!(GetModuleHandleW(0 as *const u16))?;
+        let wnd_class = WNDCLASSW {
+            style: CS_OWNDC | CS_HREDRAW | CS_VREDRAW | CS_DBLCLKS,
+            lpfnWndProc: Some(window_proc),
+            hInstance: hinstance,
+            lpszClassName: wide_strings.from_str(name),
+            cbClsExtra: 0,
+            cbWndExtra: 0,
+            hIcon: 0 as HICON,
+            hCursor: run_api!(LoadCursorW(0 as HINSTANCE, IDC_ARROW))?,
+            hbrBackground: 0 as HBRUSH,
+            lpszMenuName: 0 as *const u16,
+        };
+
+        run_api!(RegisterClassW(&wnd_class))?;
+        let hwnd = run_api!(CreateWindowExW(
+            0,                           // dwExStyle
+            wide_strings.from_str(name), // class we registered
+            wide_strings.from_str(name), // title
+            WS_OVERLAPPEDWINDOW,         // dwStyle
+            CW_USEDEFAULT,
+            CW_USEDEFAULT,
+            CW_USEDEFAULT,
+            CW_USEDEFAULT,                // size and position
+            0 as HWND,                    // hWndParent
+            0 as HMENU,                   // hMenu
+            hinstance,                    // hInstance
+            std::mem::transmute(context)  // lpParam
+        ))?;
+
+        run_api!(SetWindowLongPtrW(
+            hwnd,
+            GWL_USERDATA,
+            std::mem::transmute(context)
+        ))?;
+
+        let mut window_rect = if let Some(window_position) = window_position {
+            RECT {
+                left: window_position.left_top.0,
+                top: window_position.left_top.1,
+                right: window_position.right_bottom.0,
+                bottom: window_position.right_bottom.1,
+            }
+        } else {
+            // Open new windows on the monitor under the cursor, not always on the primary one
+            let work_rect = get_monitor_under_cursor()?.work_rect();
+            let width = (work_rect.right - work_rect.left) * 3 / 4;
+            let height = (work_rect.bottom - work_rect.top) * 3 / 4;
+            let left = (work_rect.left + work_rect.right - width) / 2;
+            let top = (work_rect.top + work_rect.bottom - height) / 2;
+            RECT {
+                left,
+                top,
+                right: left + width,
+                bottom: top + height,
+            }
+        };
+        adjust_rect(
+            get_window_move_bounds_for_rect(hwnd, &window_rect)?,
+            &mut window_rect,
+        );
+        run_api!(SetWindowPos(
+            hwnd,
+            0 as HWND,
+            window_rect.left,
+            window_rect.top,
+            window_rect.right - window_rect.left,
+            window_rect.bottom - window_rect.top,
+            0
+        ))?;
+
+        if let Some(window_position) = window_position {
+
+            if window_position.maximized {
+                run_api!(ShowWindow(hwnd, SW_SHOWMAXIMIZED))?;
+            } else {
+                run_api!(ShowWindow(hwnd, SW_SHOWDEFAULT))?;
+            }
+        } else {
+            run_api!(ShowWindow(hwnd, SW_SHOWDEFAULT))?;
+        }
+
+        SetTimer(hwnd, 0, 1, None);
+        gesture::configure(hwnd);
+
+        // the taskbar shows the picture given by the application instead of the window
+        let enable: BOOL = TRUE;
+        for attribute in [DWMWA_FORCE_ICONIC_REPRESENTATION, DWMWA_HAS_ICONIC_BITMAP] {
+            let _dwa_result = DwmSetWindowAttribute(
+                hwnd,
+                attribute,
+                &enable as *const BOOL as *const _,
+                std::mem::size_of::<BOOL>() as u32,
+            );
+        }
+
+        Ok(hwnd)
+    }
+}
+
+/// Opaque top-down bitmap with the picture, DWM takes only such ones.
+unsafe fn create_dwm_bitmap(image: ImageView<u32>) -> APIResult<AutoHGDIObj> {
+    let size = image.get_size();
+    let bitmap_info = BITMAPINFO {
+        bmiHeader: BITMAPINFOHEADER {
+            biSize: std::mem::size_of::<BITMAPINFOHEADER>() as DWORD,
+            biWidth: size.0 as LONG,
+            biHeight: -(size.1 as LONG),
+            biPlanes: 1,
+            biBitCount: 32,
+            biCompression: BI_RGB,
+            biSizeImage: 0,
+            biXPelsPerMeter: 0,
+            biYPelsPerMeter: 0,
+            biClrUsed: 0,
+            biClrImportant: 0,
+        },
+        bmiColors: [RGBQUAD {
+            rgbBlue: 0,
+            rgbGreen: 0,
+            rgbRed: 0,
+            rgbReserved: 0,
+        }],
+    };
+    let mut memory = 0 as *mut winapi::ctypes::c_void;
+    let bitmap = AutoHGDIObj::new(run_api!(CreateDIBSection(
+        0 as HDC,
+        &bitmap_info,
+        DIB_RGB_COLORS,
+        &mut memory,
+        0 as winapi::um::winnt::HANDLE,
+        0
+    ))? as HGDIOBJ);
+    let mut bitmap_view = ImageViewMut::from_raw(memory as *mut u32, size, size.0);
+    for y in 0..size.1 {
+        for (dst, src) in bitmap_view[y].iter_mut().zip(image[y].iter()) {
+            *dst = *src | 0xFF000000;
+        }
+    }
+    Ok(bitmap)
+}
+
+/// Nearest pixels of the image in the smaller picture of the same proportions.
+fn shrink_to_fit(image: ImageView<u32>, max_size: ImageSize) -> Image<u32> {
+    let size = image.get_size();
+    let scale = f64::min(
+        1.0,
+        f64::min(
+            max_size.0 as f64 / size.0.max(1) as f64,
+            max_size.1 as f64 / size.1.max(1) as f64,
+        ),
+    );
+    let new_size = (
+        ((size.0 as f64 * scale) as usize).max(1),
+        ((size.1 as f64 * scale) as usize).max(1),
+    );
+    let mut result = Image::new(new_size);
+    result.as_view_mut().fill_with_coord(|p, (x, y)| {
+        *p = image[(y * size.1 / new_size.1).min(size.1 - 1)]
+            [(x * size.0 / new_size.0).min(size.0 - 1)];
+    });
+    result
+}
+
+fn handle_message() -> APIResult<bool> {
+    unsafe {
+        let mut msg = MaybeUninit::<MSG>::uninit();
+        if run_api!(GetMessageW(msg.as_mut_ptr(), 0 as HWND, 0, 0))? > 0 {
+            // skip errors
+            let _ = run_api!(TranslateMessage(msg.as_ptr()));
+            let _ = run_api!(DispatchMessageW(msg.as_ptr()));
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+}
+
+struct RecursiveAPIProtector {
+    context: Rc<RefCell<Context>>,
+}
+
+impl RecursiveAPIProtector {
+    pub fn new(context: Rc<RefCell<Context>>) -> Self {
+        context.borrow_mut().showing_message = true;
+        Self { context }
+    }
+}
+
+impl Drop for RecursiveAPIProtector {
+    fn drop(&mut self) {
+        self.context.borrow_mut().showing_message = false;
+    }
+}
+
+pub fn show_message(context: Rc<RefCell<Context>>, text: &str, caption: &str) {
+    let mut wide_strings = WideStringManager::new();
+    let hwnd = context.borrow().hwnd;
+    let _protector = RecursiveAPIProtector::new(context.clone());
+    unsafe {
+        MessageBoxW(
+            hwnd,
+            wide_strings.from_str(text),
+            wide_strings.from_str(caption),
+            MB_OK,
+        );
+    }
+}
+
+/// Returns None if the dialog is cancelled.
+pub fn show_open_file_dialog(
+    context: Rc<RefCell<Context>>,
+    filter_name: &str,
+    extension: &str,
+) -> Option<PathBuf> {
+    let mut wide_strings = WideStringManager::new();
+    let hwnd = context.borrow().hwnd;
+    let _protector = RecursiveAPIProtector::new(context.clone());
+    // filter is a list of zero terminated strings
+    let filter = format!("{} (*.{})\0*.{}\0", filter_name, extension, extension);
+    let mut file_name = [0u16; 1024];
+    unsafe {
+        let mut open_file_name: OPENFILENAMEW = std::mem::zeroed();
+        open_file_name.lStructSize = std::mem::size_of::<OPENFILENAMEW>() as DWORD;
+        open_file_name.hwndOwner = hwnd;
+        open_file_name.lpstrFilter = wide_strings.from_str(&filter);
+        open_file_name.lpstrFile = file_name.as_mut_ptr();
+        open_file_name.nMaxFile = file_name.len() as DWORD;
+        open_file_name.Flags = OFN_FILEMUSTEXIST | OFN_PATHMUSTEXIST | OFN_NOCHANGEDIR;
+        if GetOpenFileNameW(&mut open_file_name) == 0 {
+            return None;
+        }
+    }
+    let length = file_name.iter().position(|c| *c == 0).unwrap_or(file_name.len());
+    Some(PathBuf::from(std::ffi::OsString::from_wide(&file_name[..length])))
+}
+
+/// Returns None if the dialog is cancelled, the extension is added if it is not typed.
+pub fn show_save_file_dialog(
+    context: Rc<RefCell<Context>>,
+    filter_name: &str,
+    extension: &str,
+) -> Option<PathBuf> {
+    let mut wide_strings = WideStringManager::new();
+    let hwnd = context.borrow().hwnd;
+    let _protector = RecursiveAPIProtector::new(context.clone());
+    let filter = format!("{} (*.{})\0*.{}\0", filter_name, extension, extension);
+    let mut file_name = [0u16; 1024];
+    unsafe {
+        let mut save_file_name: OPENFILENAMEW = std::mem::zeroed();
+        save_file_name.lStructSize = std::mem::size_of::<OPENFILENAMEW>() as DWORD;
+        save_file_name.hwndOwner = hwnd;
+        save_file_name.lpstrFilter = wide_strings.from_str(&filter);
+        save_file_name.lpstrDefExt = wide_strings.from_str(extension);
+        save_file_name.lpstrFile = file_name.as_mut_ptr();
+        save_file_name.nMaxFile = file_name.len() as DWORD;
+        save_file_name.Flags = OFN_OVERWRITEPROMPT | OFN_PATHMUSTEXIST | OFN_NOCHANGEDIR;
+        if GetSaveFileNameW(&mut save_file_name) == 0 {
+            return None;
+        }
+    }
+    let length = file_name.iter().position(|c| *c == 0).unwrap_or(file_name.len());
+    Some(PathBuf::from(std::ffi::OsString::from_wide(&file_name[..length])))
+}
+
+const LOCALE_SDECIMAL: DWORD = 0x0E;
+
+pub fn get_locale_decimal_separator() -> Option<char> {
+    let mut buffer = [0u16; 8];
+    let length = unsafe {
+        GetLocaleInfoW(
+            LOCALE_USER_DEFAULT,
+            LOCALE_SDECIMAL,
+            buffer.as_mut_ptr(),
+            buffer.len() as i32,
+        )
+    };
+    if length <= 1 {
+        return None;
+    }
+    String::from_utf16_lossy(&buffer[..length as usize - 1])
+        .chars()
+        .next()
+}
+
+pub fn get_screen_resolution() -> ImageSize {
+    unsafe {
+        (
+            GetSystemMetrics(SM_CXSCREEN) as usize,
+            GetSystemMetrics(SM_CYSCREEN) as usize,
+        )
+    }
+}
+
+pub fn run_application(
+    name: &str,
+    application: Box<dyn Application>,
+    window_position: Option<WindowPosition>,
+) -> APIResult<()> { 
+
+    std::panic::set_hook(Box::new(|info| {
+        use backtrace::Backtrace;
+        let bt = Backtrace::new();
+        eprintln!("{:#?}", info);
+        eprintln!("{:?}", bt);
+        eprintln!("exit(3)");
+        std::process::exit(3);
+    }));
+
+    unsafe {
+      SetProcessDPIAware();
+    }
+
+    if let Some(separator) = get_locale_decimal_separator() {
+        application::number_format::set_system_decimal_separator(separator);
+    }
+
+    let font_factory = FontFactory::new(font_loader::GDIFontLoader {});
+    let clipboard = application::clipboard::Clipboard::new(clipboard::Clipboard::new());
+    let shell = application::shell::Shell::new(shell::Shell::new());
+    let file_watcher = FileWatcher::new(file_watcher::FileWatcher::new());
+    let job_system = JobSystem::new();
+    let mut gui_system = GuiSystem::new(job_system.clone());
+    // the caret blinks as in the other programs, INFINITE turns the blinking off
+    let caret_blink_time = unsafe { GetCaretBlinkTime() };
+    gui_system.set_caret_blink_period(if caret_blink_time == winapi::um::winbase::INFINITE {
+        std::time::Duration::ZERO
+    } else {
+        std::time::Duration::from_millis(caret_blink_time as u64)
+    });
+
+    let mut system_context = SystemContext {
+        application,
+        buffer: None,
+        context: Rc::new(RefCell::new(Context {
+            hwnd: 0 as HWND,
+            showing_message: false,
+            accessible_focus: None,
+            input_recorder: None,
+            input_player: None,
+            gesture_state: Default::default(),
+            menu_key_pending: false,
+            sizing: false,
+            last_sizing_redraw: None,
+            sizing_stretched: false,
+            frame_pacer: Default::default(),
+            presentation: Presentation::Gdi,
+            gl_presenter: None,
+            clipboard,
+            shell,
+            file_watcher,
+            job_system,
+            gui_system,
+            font_factory,
+        })),
+    };
+
+    system_context
+        .application
+        .on_create(system_context.context.clone());
+    let hwnd = create_window(name, &mut system_context, window_position)?;
+    system_context.context.borrow_mut().hwnd = hwnd;
+    loop {
+        if !handle_message()? {
+            break;
+        }
+    }
+
+    Ok(())
+}
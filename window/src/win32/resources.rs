@@ -7,8 +7,8 @@ use winapi::um::winbase::*;
 use winapi::um::wingdi::*;
 use winapi::um::winuser::*;
 
-use crate::APIResult;
-use crate::APIResultCode;
+use crate::win32::APIResult;
+use crate::win32::APIResultCode;
 
 pub struct PaintStructContext {
     paint_struct: PAINTSTRUCT,
@@ -30,6 +30,11 @@ impl PaintStructContext {
     pub unsafe fn get_dc(&self) -> HDC {
         self.dc
     }
+
+    /// Part of the window invalidated by the system, empty if only the frame has changed.
+    pub fn get_paint_rect(&self) -> RECT {
+        self.paint_struct.rcPaint
+    }
 }
 
 impl Drop for PaintStructContext {
@@ -0,0 +1,39 @@
+use winapi::shared::minwindef::*;
+use winapi::shared::ntdef::*;
+use winapi::shared::windef::*;
+use winapi::um::shellapi::*;
+use winapi::um::winuser::*;
+
+use crate::win32::errors::code_to_string;
+use crate::win32::wide_strings::WideStringManager;
+
+#[derive(Debug)]
+pub struct Shell {}
+
+impl Shell {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl application::shell::ShellHandler for Shell {
+    fn open(&mut self, target: &str) -> Result<(), String> {
+        let mut wide_strings = WideStringManager::new();
+        let result = unsafe {
+            ShellExecuteW(
+                NULL as HWND,
+                wide_strings.from_str("open"),
+                wide_strings.from_str(target),
+                NULL as *const u16,
+                NULL as *const u16,
+                SW_SHOWNORMAL,
+            )
+        };
+        // values up to 32 are error codes
+        let code = result as usize;
+        if code <= 32 {
+            return Err(code_to_string(code as DWORD));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,183 @@
+use std::cell::RefCell;
+use std::os::raw::*;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use crate::x11::connection::Connection;
+use crate::x11::xlib::*;
+
+/// The owner of the clipboard answers in this time or it is taken as empty.
+const CONVERSION_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// CLIPBOARD selection of the window. The text put by the window is kept here and given to
+/// the other programs on their requests, so it is lost when the window is closed.
+pub struct Selection {
+    connection: Rc<Connection>,
+    window: Window,
+    clipboard: Atom,
+    targets: Atom,
+    utf8_string: Atom,
+    string: Atom,
+    text: Atom,
+    property: Atom,
+    owned_text: RefCell<Option<String>>,
+}
+
+impl Selection {
+    pub fn new(connection: Rc<Connection>, window: Window) -> Self {
+        Self {
+            clipboard: connection.intern_atom("CLIPBOARD"),
+            targets: connection.intern_atom("TARGETS"),
+            utf8_string: connection.intern_atom("UTF8_STRING"),
+            string: connection.intern_atom("STRING"),
+            text: connection.intern_atom("TEXT"),
+            property: connection.intern_atom("OTCAD_CLIPBOARD"),
+            connection,
+            window,
+            owned_text: RefCell::new(None),
+        }
+    }
+
+    /// Another program took the clipboard.
+    pub fn on_clear(&self, event: &XSelectionClearEvent) {
+        if event.selection == self.clipboard {
+            *self.owned_text.borrow_mut() = None;
+        }
+    }
+
+    /// Gives the text to the program which pastes it.
+    pub fn on_request(&self, request: &XSelectionRequestEvent) {
+        // obsolete clients give no property, the target is used instead
+        let property = if request.property == 0 {
+            request.target
+        } else {
+            request.property
+        };
+        let owned_text = self.owned_text.borrow();
+        let answered = match owned_text.as_ref() {
+            Some(_) if request.selection != self.clipboard => false,
+            Some(_) if request.target == self.targets => {
+                let targets = [self.targets, self.utf8_string, self.string, self.text];
+                unsafe {
+                    XChangeProperty(
+                        self.connection.display,
+                        request.requestor,
+                        property,
+                        XA_ATOM,
+                        32,
+                        PropModeReplace,
+                        targets.as_ptr() as *const c_uchar,
+                        targets.len() as c_int,
+                    );
+                }
+                true
+            }
+            Some(text) if [self.utf8_string, self.string, self.text].contains(&request.target) => {
+                unsafe {
+                    XChangeProperty(
+                        self.connection.display,
+                        request.requestor,
+                        property,
+                        self.utf8_string,
+                        8,
+                        PropModeReplace,
+                        text.as_ptr(),
+                        text.len() as c_int,
+                    );
+                }
+                true
+            }
+            _ => false,
+        };
+
+        let mut event = XEvent::zeroed();
+        event.selection = XSelectionEvent {
+            type_: SelectionNotify,
+            serial: 0,
+            send_event: True,
+            display: self.connection.display,
+            requestor: request.requestor,
+            selection: request.selection,
+            target: request.target,
+            property: if answered { property } else { 0 },
+            time: request.time,
+        };
+        unsafe {
+            XSendEvent(
+                self.connection.display,
+                request.requestor,
+                False,
+                0,
+                &mut event,
+            );
+            XFlush(self.connection.display);
+        }
+    }
+
+    /// Waits for the owner of the clipboard to convert it to UTF-8.
+    unsafe fn convert(&self) -> Option<String> {
+        let display = self.connection.display;
+        XConvertSelection(
+            display,
+            self.clipboard,
+            self.utf8_string,
+            self.property,
+            self.window,
+            CurrentTime,
+        );
+        XFlush(display);
+        let start = Instant::now();
+        let mut event = XEvent::zeroed();
+        while XCheckTypedWindowEvent(display, self.window, SelectionNotify, &mut event) == False {
+            if start.elapsed() > CONVERSION_TIMEOUT {
+                return None;
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        if event.selection.property == 0 {
+            return None;
+        }
+        let bytes = self
+            .connection
+            .get_property(self.window, self.property, true)?;
+        Some(String::from_utf8_lossy(&bytes).into_owned())
+    }
+}
+
+pub struct Clipboard {
+    selection: Rc<Selection>,
+}
+
+impl Clipboard {
+    pub fn new(selection: Rc<Selection>) -> Self {
+        Self { selection }
+    }
+}
+
+impl application::clipboard::ClipboardHandler for Clipboard {
+    fn get_string(&self) -> Option<String> {
+        if let Some(text) = self.selection.owned_text.borrow().as_ref() {
+            return Some(text.clone());
+        }
+        unsafe { self.selection.convert() }
+    }
+
+    fn put_string(&mut self, text: &str) {
+        *self.selection.owned_text.borrow_mut() = Some(text.to_string());
+        unsafe {
+            XSetSelectionOwner(
+                self.selection.connection.display,
+                self.selection.clipboard,
+                self.selection.window,
+                CurrentTime,
+            );
+            XFlush(self.selection.connection.display);
+        }
+    }
+}
+
+impl std::fmt::Debug for Clipboard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad("X11 clipboard")
+    }
+}
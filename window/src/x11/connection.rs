@@ -0,0 +1,204 @@
+use std::cmp::{max, min};
+use std::ffi::CString;
+use std::os::raw::*;
+use std::rc::Rc;
+
+use crate::x11::xlib::*;
+use crate::MonitorInfo;
+
+/// Open display with the defaults of its screen, the window, the clipboard and the fonts
+/// share it.
+pub struct Connection {
+    pub display: *mut Display,
+    pub screen: c_int,
+    pub root: Window,
+    pub visual: *mut Visual,
+    pub depth: c_int,
+    pub colormap: Colormap,
+}
+
+/// Errors of single requests are only reported, the default handler would end the process.
+unsafe extern "C" fn handle_error(_display: *mut Display, error: *mut XErrorEvent) -> c_int {
+    eprintln!(
+        "X error {} in request {}.{}",
+        (*error).error_code,
+        (*error).request_code,
+        (*error).minor_code
+    );
+    0
+}
+
+impl Connection {
+    /// Opens the display of the DISPLAY variable. The frames are copied to the window as
+    /// they are drawn, so only the true color screens are supported.
+    pub fn open() -> Result<Rc<Self>, String> {
+        unsafe {
+            let display = XOpenDisplay(std::ptr::null());
+            if display.is_null() {
+                return Err("can't open the X display".to_string());
+            }
+            XSetErrorHandler(Some(handle_error));
+            let screen = XDefaultScreen(display);
+            let connection = Self {
+                display,
+                screen,
+                root: XRootWindow(display, screen),
+                visual: XDefaultVisual(display, screen),
+                depth: XDefaultDepth(display, screen),
+                colormap: XDefaultColormap(display, screen),
+            };
+            if connection.depth != 24 && connection.depth != 32 {
+                return Err(format!(
+                    "screen depth {} is not supported",
+                    connection.depth
+                ));
+            }
+            Ok(Rc::new(connection))
+        }
+    }
+
+    pub fn intern_atom(&self, name: &str) -> Atom {
+        let name = CString::new(name).unwrap_or_default();
+        unsafe { XInternAtom(self.display, name.as_ptr(), False) }
+    }
+
+    /// Size of the whole screen, all the monitors together.
+    pub fn get_screen_size(&self) -> (c_int, c_int) {
+        unsafe {
+            (
+                XDisplayWidth(self.display, self.screen),
+                XDisplayHeight(self.display, self.screen),
+            )
+        }
+    }
+
+    /// Property of the window as bytes, None if the window has no such property.
+    pub fn get_property(&self, window: Window, property: Atom, delete: bool) -> Option<Vec<u8>> {
+        unsafe {
+            let mut actual_type = 0;
+            let mut actual_format = 0;
+            let mut count = 0;
+            let mut bytes_after = 0;
+            let mut data = std::ptr::null_mut();
+            XGetWindowProperty(
+                self.display,
+                window,
+                property,
+                0,
+                c_long::MAX / 4,
+                if delete { True } else { False },
+                AnyPropertyType,
+                &mut actual_type,
+                &mut actual_format,
+                &mut count,
+                &mut bytes_after,
+                &mut data,
+            );
+            if data.is_null() {
+                return None;
+            }
+            // the items of format 32 are longs in the memory of the client
+            let item_size = match actual_format {
+                8 => 1,
+                16 => std::mem::size_of::<c_short>(),
+                _ => std::mem::size_of::<c_long>(),
+            };
+            let result = std::slice::from_raw_parts(data, count as usize * item_size).to_vec();
+            XFree(data as *mut c_void);
+            Some(result)
+        }
+    }
+
+    /// Items of the property of format 32, they are longs whatever their type is.
+    fn get_long_property(&self, window: Window, property: Atom) -> Vec<c_ulong> {
+        self.get_property(window, property, false)
+            .unwrap_or_default()
+            .chunks_exact(std::mem::size_of::<c_ulong>())
+            .map(|chunk| c_ulong::from_ne_bytes(chunk.try_into().unwrap()))
+            .collect()
+    }
+
+    /// Atoms in the property of format 32.
+    pub fn get_atom_property(&self, window: Window, property: Atom) -> Vec<Atom> {
+        self.get_long_property(window, property)
+    }
+
+    /// Monitors of RandR, the whole screen is one monitor without it. The work area is
+    /// the one of the window manager for all the monitors, cut by each of them.
+    pub fn get_monitors(&self) -> Vec<MonitorInfo> {
+        let screen_size = self.get_screen_size();
+        let mut monitors = unsafe { self.get_randr_monitors() };
+        if monitors.is_empty() {
+            monitors.push(MonitorInfo {
+                left_top: (0, 0),
+                right_bottom: screen_size,
+                work_left_top: (0, 0),
+                work_right_bottom: screen_size,
+                primary: true,
+            });
+        }
+        let work_area = self.get_long_property(self.root, self.intern_atom("_NET_WORKAREA"));
+        if let [left, top, width, height, ..] = work_area[..] {
+            let (left, top) = (left as c_int, top as c_int);
+            let (right, bottom) = (left + width as c_int, top + height as c_int);
+            for monitor in &mut monitors {
+                let work_left_top = (max(monitor.left_top.0, left), max(monitor.left_top.1, top));
+                let work_right_bottom = (
+                    min(monitor.right_bottom.0, right),
+                    min(monitor.right_bottom.1, bottom),
+                );
+                // the panel of another monitor is not taken away
+                if work_left_top.0 < work_right_bottom.0 && work_left_top.1 < work_right_bottom.1 {
+                    monitor.work_left_top = work_left_top;
+                    monitor.work_right_bottom = work_right_bottom;
+                }
+            }
+        }
+        if !monitors.iter().any(|monitor| monitor.primary) {
+            monitors[0].primary = true;
+        }
+        monitors
+    }
+
+    unsafe fn get_randr_monitors(&self) -> Vec<MonitorInfo> {
+        let library = libc::dlopen(
+            c"libXrandr.so.2".as_ptr(),
+            libc::RTLD_LAZY | libc::RTLD_LOCAL,
+        );
+        if library.is_null() {
+            return Vec::new();
+        }
+        let get_monitors = libc::dlsym(library, c"XRRGetMonitors".as_ptr());
+        let free_monitors = libc::dlsym(library, c"XRRFreeMonitors".as_ptr());
+        let mut result = Vec::new();
+        if !get_monitors.is_null() && !free_monitors.is_null() {
+            let get_monitors: XRRGetMonitors = std::mem::transmute(get_monitors);
+            let free_monitors: XRRFreeMonitors = std::mem::transmute(free_monitors);
+            let mut count = 0;
+            let monitors = get_monitors(self.display, self.root, True, &mut count);
+            if !monitors.is_null() {
+                for monitor in std::slice::from_raw_parts(monitors, max(count, 0) as usize) {
+                    let right_bottom = (monitor.x + monitor.width, monitor.y + monitor.height);
+                    result.push(MonitorInfo {
+                        left_top: (monitor.x, monitor.y),
+                        right_bottom,
+                        work_left_top: (monitor.x, monitor.y),
+                        work_right_bottom: right_bottom,
+                        primary: monitor.primary != False,
+                    });
+                }
+                free_monitors(monitors);
+            }
+        }
+        libc::dlclose(library);
+        result
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        unsafe {
+            XCloseDisplay(self.display);
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::x11::{Context, RecursiveAPIProtector};
+
+// X11 has no standard dialogs, the ones of the desktop are run by zenity or kdialog
+
+/// How often the window is drawn again while the dialog is open.
+const MODAL_PUMP_INTERVAL: Duration = Duration::from_millis(15);
+
+/// Output of the first dialog program which is installed, None if it is cancelled or there
+/// is no such program.
+fn run_dialog_program(commands: &[(&str, Vec<String>)]) -> Option<String> {
+    for (program, args) in commands {
+        let output = match Command::new(program)
+            .args(args)
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+        {
+            Ok(output) => output,
+            Err(_) => continue,
+        };
+        if !output.status.success() {
+            return None;
+        }
+        let text = String::from_utf8_lossy(&output.stdout);
+        return Some(text.trim_end_matches('\n').to_string());
+    }
+    eprintln!("No dialog program: zenity or kdialog is needed");
+    None
+}
+
+/// Runs the dialog program on a thread, the window is kept drawn until it ends.
+fn run_dialog(
+    context: &Rc<RefCell<Context>>,
+    commands: Vec<(&'static str, Vec<String>)>,
+) -> Option<String> {
+    let modal_pump = context.borrow().modal_pump.clone();
+    let dialog = std::thread::spawn(move || run_dialog_program(&commands));
+    while !dialog.is_finished() {
+        modal_pump.pump();
+        std::thread::sleep(MODAL_PUMP_INTERVAL);
+    }
+    modal_pump.finish();
+    dialog.join().unwrap_or(None)
+}
+
+fn to_path(output: Option<String>) -> Option<PathBuf> {
+    output.filter(|path| !path.is_empty()).map(PathBuf::from)
+}
+
+pub fn show_message(context: Rc<RefCell<Context>>, text: &str, caption: &str) {
+    let _protector = RecursiveAPIProtector::new(context.clone());
+    let shown = run_dialog(
+        &context,
+        vec![
+            (
+                "zenity",
+                vec![
+                    "--info".to_string(),
+                    "--no-markup".to_string(),
+                    format!("--title={}", caption),
+                    format!("--text={}", text),
+                ],
+            ),
+            (
+                "kdialog",
+                vec![
+                    "--title".to_string(),
+                    caption.to_string(),
+                    "--msgbox".to_string(),
+                    text.to_string(),
+                ],
+            ),
+        ],
+    );
+    if shown.is_none() {
+        eprintln!("{}: {}", caption, text);
+    }
+}
+
+/// Returns None if the dialog is cancelled.
+pub fn show_open_file_dialog(
+    context: Rc<RefCell<Context>>,
+    filter_name: &str,
+    extension: &str,
+) -> Option<PathBuf> {
+    let _protector = RecursiveAPIProtector::new(context.clone());
+    to_path(run_dialog(
+        &context,
+        vec![
+            (
+                "zenity",
+                vec![
+                    "--file-selection".to_string(),
+                    format!(
+                        "--file-filter={} (*.{}) | *.{}",
+                        filter_name, extension, extension
+                    ),
+                ],
+            ),
+            (
+                "kdialog",
+                vec![
+                    "--getopenfilename".to_string(),
+                    ".".to_string(),
+                    format!("*.{}|{}", extension, filter_name),
+                ],
+            ),
+        ],
+    ))
+}
+
+/// Returns None if the dialog is cancelled, the extension is added if it is not typed.
+pub fn show_save_file_dialog(
+    context: Rc<RefCell<Context>>,
+    filter_name: &str,
+    extension: &str,
+) -> Option<PathBuf> {
+    let _protector = RecursiveAPIProtector::new(context.clone());
+    let mut path = to_path(run_dialog(
+        &context,
+        vec![
+            (
+                "zenity",
+                vec![
+                    "--file-selection".to_string(),
+                    "--save".to_string(),
+                    "--confirm-overwrite".to_string(),
+                    format!(
+                        "--file-filter={} (*.{}) | *.{}",
+                        filter_name, extension, extension
+                    ),
+                ],
+            ),
+            (
+                "kdialog",
+                vec![
+                    "--getsavefilename".to_string(),
+                    ".".to_string(),
+                    format!("*.{}|{}", extension, filter_name),
+                ],
+            ),
+        ],
+    ))?;
+    if path.extension().is_none() {
+        path.set_extension(extension);
+    }
+    Some(path)
+}
+
+/// Returns None if the dialog is cancelled.
+pub fn show_folder_dialog(context: Rc<RefCell<Context>>) -> Option<PathBuf> {
+    let _protector = RecursiveAPIProtector::new(context.clone());
+    to_path(run_dialog(
+        &context,
+        vec![
+            (
+                "zenity",
+                vec!["--file-selection".to_string(), "--directory".to_string()],
+            ),
+            (
+                "kdialog",
+                vec!["--getexistingdirectory".to_string(), ".".to_string()],
+            ),
+        ],
+    ))
+}
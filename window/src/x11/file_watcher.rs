@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+use std::ffi::{CString, OsStr};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+
+/// Changes of the names and of the contents of the files, as on Windows.
+const WATCH_MASK: u32 = libc::IN_CLOSE_WRITE
+    | libc::IN_MODIFY
+    | libc::IN_CREATE
+    | libc::IN_DELETE
+    | libc::IN_MOVED_FROM
+    | libc::IN_MOVED_TO;
+
+/// Folders watched by one inotify descriptor, it is read without blocking when the
+/// changes are taken.
+#[derive(Debug)]
+pub struct FileWatcher {
+    fd: i32,
+    folders: HashMap<i32, PathBuf>,
+}
+
+impl FileWatcher {
+    pub fn new() -> Self {
+        Self {
+            fd: unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) },
+            folders: HashMap::new(),
+        }
+    }
+}
+
+impl Drop for FileWatcher {
+    fn drop(&mut self) {
+        if self.fd >= 0 {
+            unsafe {
+                libc::close(self.fd);
+            }
+        }
+    }
+}
+
+impl application::file_watcher::FileWatcherHandler for FileWatcher {
+    fn watch_folder(&mut self, folder: &Path) {
+        if self.fd < 0 || self.folders.values().any(|watched| watched == folder) {
+            return;
+        }
+        // the empty folder is the current one
+        let name = if folder.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            folder
+        };
+        let name = match CString::new(name.as_os_str().as_bytes()) {
+            Ok(name) => name,
+            Err(_) => return,
+        };
+        let watch = unsafe { libc::inotify_add_watch(self.fd, name.as_ptr(), WATCH_MASK) };
+        if watch >= 0 {
+            self.folders.insert(watch, folder.to_path_buf());
+        }
+    }
+
+    fn unwatch_folder(&mut self, folder: &Path) {
+        let watches: Vec<_> = self
+            .folders
+            .iter()
+            .filter(|(_, watched)| *watched == folder)
+            .map(|(watch, _)| *watch)
+            .collect();
+        for watch in watches {
+            unsafe {
+                libc::inotify_rm_watch(self.fd, watch);
+            }
+            self.folders.remove(&watch);
+        }
+    }
+
+    fn take_changes(&mut self) -> Vec<PathBuf> {
+        let mut result = Vec::new();
+        if self.fd < 0 {
+            return result;
+        }
+        // the events are aligned as the header
+        let mut buffer = vec![0u32; 4096];
+        let header_size = std::mem::size_of::<libc::inotify_event>();
+        loop {
+            let bytes = unsafe {
+                libc::read(
+                    self.fd,
+                    buffer.as_mut_ptr() as *mut libc::c_void,
+                    buffer.len() * std::mem::size_of::<u32>(),
+                )
+            };
+            if bytes <= 0 {
+                return result;
+            }
+            let data =
+                unsafe { std::slice::from_raw_parts(buffer.as_ptr() as *const u8, bytes as usize) };
+            let mut offset = 0;
+            while offset + header_size <= data.len() {
+                let event = unsafe {
+                    std::ptr::read_unaligned(data[offset..].as_ptr() as *const libc::inotify_event)
+                };
+                let name_begin = offset + header_size;
+                let name_end = (name_begin + event.len as usize).min(data.len());
+                // the name is padded by zeros
+                let name = &data[name_begin..name_end];
+                let name = &name[..name.iter().position(|c| *c == 0).unwrap_or(name.len())];
+                if let Some(folder) = self.folders.get(&event.wd) {
+                    if !name.is_empty() {
+                        result.push(folder.join(OsStr::from_bytes(name)));
+                    }
+                }
+                offset = name_end;
+            }
+        }
+    }
+}
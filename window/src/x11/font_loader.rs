@@ -0,0 +1,247 @@
+use application::font::*;
+use application::image::*;
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::os::raw::*;
+use std::rc::Rc;
+
+use crate::x11::connection::Connection;
+use crate::x11::xlib::*;
+
+/// Fonts are found by fontconfig and drawn by Xft, the name is a family or a pattern.
+pub struct XftFontLoader {
+    connection: Rc<Connection>,
+}
+
+/// Xft objects of one font size, freed in the reverse order.
+struct GlyphCanvas<'i> {
+    connection: &'i Connection,
+    font: *mut XftFont,
+    pixmap: Pixmap,
+    draw: *mut XftDraw,
+    white: XftColor,
+    black: XftColor,
+    size: (c_uint, c_uint),
+}
+
+impl<'i> GlyphCanvas<'i> {
+    unsafe fn new(connection: &'i Connection, font: *mut XftFont) -> Option<Self> {
+        let size = (
+            ((*font).max_advance_width.max(1) * 2) as c_uint,
+            ((*font).ascent + (*font).descent).max(1) as c_uint,
+        );
+        let pixmap = XCreatePixmap(
+            connection.display,
+            connection.root,
+            size.0,
+            size.1,
+            connection.depth as c_uint,
+        );
+        let draw = XftDrawCreate(
+            connection.display,
+            pixmap,
+            connection.visual,
+            connection.colormap,
+        );
+        if draw.is_null() {
+            XFreePixmap(connection.display, pixmap);
+            return None;
+        }
+        let mut canvas = Self {
+            connection,
+            font,
+            pixmap,
+            draw,
+            white: XftColor::default(),
+            black: XftColor::default(),
+            size,
+        };
+        for (value, color) in [(0xFFFF, &mut canvas.white), (0, &mut canvas.black)] {
+            let render_color = XRenderColor {
+                red: value,
+                green: value,
+                blue: value,
+                alpha: 0xFFFF,
+            };
+            XftColorAllocValue(
+                connection.display,
+                connection.visual,
+                connection.colormap,
+                &render_color,
+                color,
+            );
+        }
+        Some(canvas)
+    }
+
+    /// The char in white on black, in the cell of its advance and of the line height.
+    unsafe fn draw(&mut self, c: char) -> Image<u32> {
+        let display = self.connection.display;
+        let mut text = [0u8; 4];
+        let text = c.encode_utf8(&mut text);
+        let mut extents = XGlyphInfo::default();
+        XftTextExtentsUtf8(
+            display,
+            self.font,
+            text.as_ptr(),
+            text.len() as c_int,
+            &mut extents,
+        );
+        let width = (extents.xOff.max(0) as c_uint).min(self.size.0);
+        let mut result = Image::new((width as usize, self.size.1 as usize));
+        if width == 0 {
+            return result;
+        }
+        XftDrawRect(self.draw, &self.black, 0, 0, self.size.0, self.size.1);
+        XftDrawStringUtf8(
+            self.draw,
+            &self.white,
+            self.font,
+            0,
+            (*self.font).ascent,
+            text.as_ptr(),
+            text.len() as c_int,
+        );
+        let image = XGetImage(
+            display,
+            self.pixmap,
+            0,
+            0,
+            width,
+            self.size.1,
+            AllPlanes,
+            ZPixmap,
+        );
+        if image.is_null() {
+            return result;
+        }
+        result.as_view_mut().fill_with_coord(|p, (x, y)| {
+            *p = XGetPixel(image, x as c_int, y as c_int) as u32 & 0xFFFFFF;
+        });
+        XDestroyImage(image);
+        result
+    }
+}
+
+impl<'i> Drop for GlyphCanvas<'i> {
+    fn drop(&mut self) {
+        unsafe {
+            let connection = self.connection;
+            for color in [&mut self.white, &mut self.black] {
+                XftColorFree(
+                    connection.display,
+                    connection.visual,
+                    connection.colormap,
+                    color,
+                );
+            }
+            XftDrawDestroy(self.draw);
+            XFreePixmap(connection.display, self.pixmap);
+        }
+    }
+}
+
+impl XftFontLoader {
+    pub fn new(connection: Rc<Connection>) -> Self {
+        Self { connection }
+    }
+
+    /// The size is the height of the line as on Windows, the pixel size of the font is
+    /// fitted to it.
+    unsafe fn open_font(
+        &self,
+        font_name: &str,
+        font_size: i32,
+        anti_aliasing_mode: FontAntiAliasingMode,
+    ) -> *mut XftFont {
+        let (antialias, rgba) = match anti_aliasing_mode {
+            FontAntiAliasingMode::NoAA => ("false", "none"),
+            FontAntiAliasingMode::AA => ("true", "none"),
+            FontAntiAliasingMode::TT => ("true", "rgb"),
+        };
+        let open = |pixel_size: f64| {
+            let pattern = format!(
+                "{}:pixelsize={}:antialias={}:rgba={}:hinting=true",
+                font_name.replace(['-', ':'], " "),
+                pixel_size,
+                antialias,
+                rgba
+            );
+            let pattern = CString::new(pattern).unwrap_or_default();
+            XftFontOpenName(
+                self.connection.display,
+                self.connection.screen,
+                pattern.as_ptr(),
+            )
+        };
+        let font_size = font_size.abs().max(1) as f64;
+        let font = open(font_size);
+        if font.is_null() {
+            return font;
+        }
+        let height = ((*font).ascent + (*font).descent) as f64;
+        if height <= font_size {
+            return font;
+        }
+        let fitted = open((font_size * font_size / height).floor().max(1.0));
+        if fitted.is_null() {
+            return font;
+        }
+        XftFontClose(self.connection.display, font);
+        fitted
+    }
+}
+
+impl FontLoader for XftFontLoader {
+    fn load_glyphs(
+        &mut self,
+        font_name: &str,
+        font_size: i32,
+        code_from: u32,
+        code_to: u32,
+        anti_aliasing_mode: FontAntiAliasingMode,
+    ) -> HashMap<char, Glyph> {
+        let mut result = HashMap::new();
+        unsafe {
+            let font = self.open_font(font_name, font_size, anti_aliasing_mode);
+            if font.is_null() {
+                return result;
+            }
+            if let Some(mut canvas) = GlyphCanvas::new(&self.connection, font) {
+                for c in (code_from..code_to).filter_map(char::from_u32) {
+                    // the coverage is the same for the dark and for the light text
+                    let light = canvas.draw(c);
+                    let glyph = match anti_aliasing_mode {
+                        FontAntiAliasingMode::NoAA => {
+                            let mut image = Image::new(light.get_size());
+                            image
+                                .as_view_mut()
+                                .draw(&light.as_view(), (0, 0), |d, s| *d = *s != 0);
+                            Glyph::NoAA(image)
+                        }
+                        FontAntiAliasingMode::AA => {
+                            let mut image = Image::new(light.get_size());
+                            image
+                                .as_view_mut()
+                                .draw(&light.as_view(), (0, 0), |d, s| *d = (*s >> 8) as u8);
+                            let mut image_light = Image::new(light.get_size());
+                            image_light
+                                .as_view_mut()
+                                .draw(&image.as_view(), (0, 0), |d, s| *d = *s);
+                            Glyph::AA(image, image_light)
+                        }
+                        FontAntiAliasingMode::TT => {
+                            let mut dark = Image::new(light.get_size());
+                            dark.as_view_mut()
+                                .draw(&light.as_view(), (0, 0), |d, s| *d = *s | 0xFF000000);
+                            Glyph::TT(dark, light)
+                        }
+                    };
+                    result.insert(c, glyph);
+                }
+            }
+            XftFontClose(self.connection.display, font);
+        }
+        result
+    }
+}
@@ -0,0 +1,1127 @@
+use std::cell::{Cell, RefCell};
+use std::cmp::{max, min};
+use std::ffi::{CStr, CString};
+use std::ops::DerefMut;
+use std::os::raw::*;
+use std::path::Path;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use application::clipboard::*;
+use application::draw_context::*;
+use application::file_watcher::*;
+use application::font::*;
+use application::gui::input_log::*;
+use application::gui::{GuiSystem, MouseCursor, Rect};
+use application::image::*;
+use application::job_system::*;
+use application::keys::*;
+use application::shell::*;
+
+use crate::x11::clipboard::Selection;
+use crate::x11::connection::Connection;
+use crate::x11::xlib::*;
+use crate::{frame_pacing, Application, MonitorInfo, Presentation, WindowPosition};
+
+mod clipboard;
+mod connection;
+mod dialogs;
+mod file_watcher;
+mod font_loader;
+mod shell;
+mod xlib;
+
+pub use dialogs::*;
+
+/// A second press of the middle button in this time is a double click, X11 has no double
+/// clicks itself.
+const DOUBLE_CLICK_TIME: Duration = Duration::from_millis(500);
+
+pub struct Context {
+    showing_message: bool,
+    modal_pump: Rc<ModalPump>,
+    input_recorder: Option<InputRecorder>,
+    input_player: Option<InputPlayer>,
+    /// Alt is pressed and no other key was pressed since, its release focuses the menu bar.
+    menu_key_pending: bool,
+    /// The window has to be drawn, X11 has no invalidation of its own.
+    paint_pending: bool,
+    frame_pacer: frame_pacing::FramePacer,
+    presentation: Presentation,
+    pub font_factory: FontFactory,
+    pub clipboard: Clipboard,
+    pub shell: Shell,
+    pub file_watcher: FileWatcher,
+    pub job_system: JobSystem,
+    pub gui_system: GuiSystem,
+}
+
+impl Context {
+    /// Writes all input of the window to the file, so it can be replayed later.
+    pub fn start_recording(&mut self, path: &Path) -> std::io::Result<()> {
+        self.input_recorder = Some(InputRecorder::create(path)?);
+        Ok(())
+    }
+
+    /// Replays the recorded input in real time; user input is ignored until the end.
+    pub fn start_playback(&mut self, path: &Path) -> std::io::Result<()> {
+        self.input_player = Some(InputPlayer::load(path)?);
+        Ok(())
+    }
+
+    /// Limits the repainting and the timers of the window.
+    pub fn set_frame_pacing(&mut self, pacing: FramePacing) {
+        self.frame_pacer.set_pacing(pacing);
+    }
+
+    /// The frames are always copied by XPutImage, OpenGL falls back to it at once.
+    pub fn set_presentation(&mut self, presentation: Presentation) {
+        if presentation != Presentation::Gdi {
+            eprintln!("OpenGL presentation is unavailable on X11");
+        }
+        self.presentation = Presentation::Gdi;
+    }
+}
+
+pub use crate::FramePacing;
+
+/// The application window with the X11 objects drawing it and reading its input.
+struct X11Window {
+    connection: Rc<Connection>,
+    window: Window,
+    gc: GC,
+    im: XIM,
+    ic: XIC,
+    wm_delete_window: Atom,
+    net_wm_state: Atom,
+    maximized_atoms: [Atom; 2],
+    cursors: [Cursor; 3],
+    current_cursor: Cell<usize>,
+    size: Cell<ImageSize>,
+    minimal_size: Cell<Position>,
+    /// Parts of the window shown again by the system, they are copied on the next paint.
+    exposed: RefCell<Vec<Rect>>,
+}
+
+const ARROW_CURSOR: usize = 0;
+const HAND_CURSOR: usize = 1;
+const WAIT_CURSOR: usize = 2;
+
+impl X11Window {
+    unsafe fn new(
+        connection: Rc<Connection>,
+        name: &str,
+        window_position: Option<WindowPosition>,
+    ) -> Self {
+        let display = connection.display;
+        let (left_top, size) = match window_position {
+            Some(window_position) => (
+                window_position.left_top,
+                (
+                    window_position.right_bottom.0 - window_position.left_top.0,
+                    window_position.right_bottom.1 - window_position.left_top.1,
+                ),
+            ),
+            None => {
+                let monitors = connection.get_monitors();
+                let monitor = monitors
+                    .iter()
+                    .find(|monitor| monitor.primary)
+                    .unwrap_or(&monitors[0]);
+                let (left, top) = monitor.work_left_top;
+                let (right, bottom) = monitor.work_right_bottom;
+                let size = ((right - left) * 3 / 4, (bottom - top) * 3 / 4);
+                (
+                    ((left + right - size.0) / 2, (top + bottom - size.1) / 2),
+                    size,
+                )
+            }
+        };
+        let size = (max(size.0, 1), max(size.1, 1));
+        let window = XCreateSimpleWindow(
+            display,
+            connection.root,
+            left_top.0,
+            left_top.1,
+            size.0 as c_uint,
+            size.1 as c_uint,
+            0,
+            XBlackPixel(display, connection.screen),
+            XBlackPixel(display, connection.screen),
+        );
+        XSelectInput(
+            display,
+            window,
+            KeyPressMask
+                | KeyReleaseMask
+                | ButtonPressMask
+                | ButtonReleaseMask
+                | LeaveWindowMask
+                | PointerMotionMask
+                | ExposureMask
+                | StructureNotifyMask
+                | FocusChangeMask,
+        );
+
+        let title = CString::new(name).unwrap_or_default();
+        XStoreName(display, window, title.as_ptr());
+        XChangeProperty(
+            display,
+            window,
+            connection.intern_atom("_NET_WM_NAME"),
+            connection.intern_atom("UTF8_STRING"),
+            8,
+            PropModeReplace,
+            name.as_ptr(),
+            name.len() as c_int,
+        );
+
+        let mut wm_delete_window = connection.intern_atom("WM_DELETE_WINDOW");
+        XSetWMProtocols(display, window, &mut wm_delete_window, 1);
+
+        // the saved position is of the inside of the window, the frame is put around it
+        let mut hints = XSizeHints {
+            flags: USPosition | USSize | PWinGravity,
+            x: left_top.0,
+            y: left_top.1,
+            width: size.0,
+            height: size.1,
+            win_gravity: StaticGravity,
+            ..Default::default()
+        };
+        XSetWMNormalHints(display, window, &mut hints);
+
+        let net_wm_state = connection.intern_atom("_NET_WM_STATE");
+        let maximized_atoms = [
+            connection.intern_atom("_NET_WM_STATE_MAXIMIZED_VERT"),
+            connection.intern_atom("_NET_WM_STATE_MAXIMIZED_HORZ"),
+        ];
+        if window_position.is_some_and(|position| position.maximized) {
+            XChangeProperty(
+                display,
+                window,
+                net_wm_state,
+                XA_ATOM,
+                32,
+                PropModeReplace,
+                maximized_atoms.as_ptr() as *const c_uchar,
+                maximized_atoms.len() as c_int,
+            );
+        }
+
+        // without the detectable repeat a held key gives releases between the presses
+        XkbSetDetectableAutoRepeat(display, True, std::ptr::null_mut());
+        XSetLocaleModifiers(c"".as_ptr());
+        let im = XOpenIM(
+            display,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        );
+        let ic = if im.is_null() {
+            std::ptr::null_mut()
+        } else {
+            XCreateIC(
+                im,
+                XNInputStyle.as_ptr() as *const c_char,
+                XIMPreeditNothing | XIMStatusNothing,
+                XNClientWindow.as_ptr() as *const c_char,
+                window,
+                XNFocusWindow.as_ptr() as *const c_char,
+                window,
+                std::ptr::null_mut::<c_void>(),
+            )
+        };
+
+        let cursors = [
+            XCreateFontCursor(display, XC_left_ptr),
+            XCreateFontCursor(display, XC_hand2),
+            XCreateFontCursor(display, XC_watch),
+        ];
+        XDefineCursor(display, window, cursors[ARROW_CURSOR]);
+        let gc = XCreateGC(display, window, 0, std::ptr::null_mut());
+
+        Self {
+            connection,
+            window,
+            gc,
+            im,
+            ic,
+            wm_delete_window,
+            net_wm_state,
+            maximized_atoms,
+            cursors,
+            current_cursor: Cell::new(ARROW_CURSOR),
+            size: Cell::new((size.0 as usize, size.1 as usize)),
+            minimal_size: Cell::new((0, 0)),
+            exposed: RefCell::new(Vec::new()),
+        }
+    }
+
+    fn show(&self) {
+        unsafe {
+            XMapWindow(self.connection.display, self.window);
+            XFlush(self.connection.display);
+        }
+    }
+
+    fn get_window_position(&self) -> WindowPosition {
+        let mut left = 0;
+        let mut top = 0;
+        let mut child = 0;
+        unsafe {
+            XTranslateCoordinates(
+                self.connection.display,
+                self.window,
+                self.connection.root,
+                0,
+                0,
+                &mut left,
+                &mut top,
+                &mut child,
+            );
+        }
+        let state = self
+            .connection
+            .get_atom_property(self.window, self.net_wm_state);
+        let size = image_size_to_position(self.size.get());
+        WindowPosition {
+            maximized: self.maximized_atoms.iter().all(|atom| state.contains(atom)),
+            left_top: (left, top),
+            right_bottom: (left + size.0, top + size.1),
+        }
+    }
+
+    fn set_cursor(&self, cursor: usize) {
+        if self.current_cursor.replace(cursor) != cursor {
+            unsafe {
+                XDefineCursor(self.connection.display, self.window, self.cursors[cursor]);
+            }
+        }
+    }
+
+    /// Text typed by the key, through the input method if there is one.
+    unsafe fn lookup_text(&self, event: &mut XKeyEvent) -> String {
+        let mut buffer = [0u8; 64];
+        let mut keysym = 0;
+        if self.ic.is_null() {
+            let length = XLookupString(
+                event,
+                buffer.as_mut_ptr() as *mut c_char,
+                buffer.len() as c_int,
+                &mut keysym,
+                std::ptr::null_mut(),
+            );
+            // without the input method the text is Latin-1
+            return buffer[..length.max(0) as usize]
+                .iter()
+                .map(|c| *c as char)
+                .collect();
+        }
+        let mut status = 0;
+        let length = Xutf8LookupString(
+            self.ic,
+            event,
+            buffer.as_mut_ptr() as *mut c_char,
+            buffer.len() as c_int,
+            &mut keysym,
+            &mut status,
+        );
+        if status != XLookupChars && status != XLookupBoth {
+            return String::new();
+        }
+        String::from_utf8_lossy(&buffer[..length.max(0) as usize]).into_owned()
+    }
+
+    /// The key of the first layout, so the hotkeys are the same whatever layout is on.
+    unsafe fn lookup_key(&self, event: &XKeyEvent) -> (KeySym, Option<Key>) {
+        let display = self.connection.display;
+        let keycode = event.keycode as c_uchar;
+        let mut keysym = XkbKeycodeToKeysym(display, keycode, 0, 0);
+        // the keypad gives digits only with Num Lock
+        if (XK_KP_HOME..=XK_KP_DELETE).contains(&keysym) && event.state & Mod2Mask != 0 {
+            keysym = XkbKeycodeToKeysym(display, keycode, 0, 1);
+        }
+        (keysym, keysym_to_key(keysym))
+    }
+
+    /// Copies the parts of the frame to the window.
+    fn put_image(&self, buffer: &mut Image<u32>, rects: impl Iterator<Item = Rect>) {
+        let size = buffer.get_size();
+        let mut image = XImage {
+            width: size.0 as c_int,
+            height: size.1 as c_int,
+            xoffset: 0,
+            format: ZPixmap,
+            data: buffer.get_data_mut().as_mut_ptr() as *mut c_char,
+            byte_order: LSBFirst,
+            bitmap_unit: 32,
+            bitmap_bit_order: LSBFirst,
+            bitmap_pad: 32,
+            depth: self.connection.depth,
+            bytes_per_line: (size.0 * std::mem::size_of::<u32>()) as c_int,
+            bits_per_pixel: 32,
+            red_mask: 0xFF0000,
+            green_mask: 0xFF00,
+            blue_mask: 0xFF,
+            obdata: std::ptr::null_mut(),
+            f: [std::ptr::null_mut(); 6],
+        };
+        unsafe {
+            if XInitImage(&mut image) == 0 {
+                return;
+            }
+            for rect in rects {
+                let left = max(rect.left_top.0, 0);
+                let top = max(rect.left_top.1, 0);
+                let right = min(rect.right_bottom.0, size.0 as i32);
+                let bottom = min(rect.right_bottom.1, size.1 as i32);
+                if right <= left || bottom <= top {
+                    continue;
+                }
+                XPutImage(
+                    self.connection.display,
+                    self.window,
+                    self.gc,
+                    &mut image,
+                    left,
+                    top,
+                    left,
+                    top,
+                    (right - left) as c_uint,
+                    (bottom - top) as c_uint,
+                );
+            }
+        }
+    }
+}
+
+impl Drop for X11Window {
+    fn drop(&mut self) {
+        unsafe {
+            let display = self.connection.display;
+            if !self.ic.is_null() {
+                XDestroyIC(self.ic);
+            }
+            if !self.im.is_null() {
+                XCloseIM(self.im);
+            }
+            XFreeGC(display, self.gc);
+            XDestroyWindow(display, self.window);
+        }
+    }
+}
+
+const XK_BACKSPACE: KeySym = 0xFF08;
+const XK_TAB: KeySym = 0xFF09;
+const XK_RETURN: KeySym = 0xFF0D;
+const XK_ESCAPE: KeySym = 0xFF1B;
+const XK_HOME: KeySym = 0xFF50;
+const XK_LEFT: KeySym = 0xFF51;
+const XK_UP: KeySym = 0xFF52;
+const XK_RIGHT: KeySym = 0xFF53;
+const XK_DOWN: KeySym = 0xFF54;
+const XK_PRIOR: KeySym = 0xFF55;
+const XK_NEXT: KeySym = 0xFF56;
+const XK_END: KeySym = 0xFF57;
+const XK_INSERT: KeySym = 0xFF63;
+const XK_KP_ENTER: KeySym = 0xFF8D;
+const XK_KP_HOME: KeySym = 0xFF95;
+const XK_KP_LEFT: KeySym = 0xFF96;
+const XK_KP_UP: KeySym = 0xFF97;
+const XK_KP_RIGHT: KeySym = 0xFF98;
+const XK_KP_DOWN: KeySym = 0xFF99;
+const XK_KP_PRIOR: KeySym = 0xFF9A;
+const XK_KP_NEXT: KeySym = 0xFF9B;
+const XK_KP_END: KeySym = 0xFF9C;
+const XK_KP_INSERT: KeySym = 0xFF9E;
+const XK_KP_DELETE: KeySym = 0xFF9F;
+const XK_KP_0: KeySym = 0xFFB0;
+const XK_F1: KeySym = 0xFFBE;
+const XK_ALT_L: KeySym = 0xFFE9;
+const XK_ALT_R: KeySym = 0xFFEA;
+const XK_DELETE: KeySym = 0xFFFF;
+
+fn keysym_to_key(keysym: KeySym) -> Option<Key> {
+    const NUMPAD: [Key; 10] = [
+        Key::Numpad0,
+        Key::Numpad1,
+        Key::Numpad2,
+        Key::Numpad3,
+        Key::Numpad4,
+        Key::Numpad5,
+        Key::Numpad6,
+        Key::Numpad7,
+        Key::Numpad8,
+        Key::Numpad9,
+    ];
+    const FUNCTION: [Key; 12] = [
+        Key::F1,
+        Key::F2,
+        Key::F3,
+        Key::F4,
+        Key::F5,
+        Key::F6,
+        Key::F7,
+        Key::F8,
+        Key::F9,
+        Key::F10,
+        Key::F11,
+        Key::F12,
+    ];
+    const DIGITS: [Key; 10] = [
+        Key::Num0,
+        Key::Num1,
+        Key::Num2,
+        Key::Num3,
+        Key::Num4,
+        Key::Num5,
+        Key::Num6,
+        Key::Num7,
+        Key::Num8,
+        Key::Num9,
+    ];
+    const LETTERS: [Key; 26] = [
+        Key::A,
+        Key::B,
+        Key::C,
+        Key::D,
+        Key::E,
+        Key::F,
+        Key::G,
+        Key::H,
+        Key::I,
+        Key::J,
+        Key::K,
+        Key::L,
+        Key::M,
+        Key::N,
+        Key::O,
+        Key::P,
+        Key::Q,
+        Key::R,
+        Key::S,
+        Key::T,
+        Key::U,
+        Key::V,
+        Key::W,
+        Key::X,
+        Key::Y,
+        Key::Z,
+    ];
+    match keysym {
+        // the keypad without Num Lock moves as the other keys
+        XK_LEFT | XK_KP_LEFT => return Some(Key::Left),
+        XK_RIGHT | XK_KP_RIGHT => return Some(Key::Right),
+        XK_UP | XK_KP_UP => return Some(Key::Up),
+        XK_DOWN | XK_KP_DOWN => return Some(Key::Down),
+        XK_BACKSPACE => return Some(Key::Backspace),
+        XK_INSERT | XK_KP_INSERT => return Some(Key::Insert),
+        XK_HOME | XK_KP_HOME => return Some(Key::Home),
+        XK_END | XK_KP_END => return Some(Key::End),
+        XK_PRIOR | XK_KP_PRIOR => return Some(Key::PageUp),
+        XK_NEXT | XK_KP_NEXT => return Some(Key::PageDown),
+        XK_DELETE | XK_KP_DELETE => return Some(Key::Delete),
+        0x20 => return Some(Key::Space),
+        XK_TAB => return Some(Key::Tab),
+        XK_ESCAPE => return Some(Key::Escape),
+        XK_RETURN | XK_KP_ENTER => return Some(Key::Enter),
+        _ => {}
+    }
+    let index = |first: KeySym| (keysym - first) as usize;
+    match keysym {
+        XK_KP_0..=0xFFB9 => Some(NUMPAD[index(XK_KP_0)]),
+        XK_F1..=0xFFC9 => Some(FUNCTION[index(XK_F1)]),
+        0x30..=0x39 => Some(DIGITS[index(0x30)]),
+        0x41..=0x5A => Some(LETTERS[index(0x41)]),
+        0x61..=0x7A => Some(LETTERS[index(0x61)]),
+        _ => None,
+    }
+}
+
+struct SystemContext {
+    application: Box<dyn Application>,
+    buffer: Rc<RefCell<Option<Image<u32>>>>,
+    context: Rc<RefCell<Context>>,
+    window: Rc<X11Window>,
+    selection: Rc<Selection>,
+    /// Time and place of the last press of the middle button.
+    middle_press: Option<(Instant, Position)>,
+}
+
+fn adjust_window_size(context: &Rc<RefCell<Context>>, window: &X11Window) {
+    let minimal_size = context.borrow().gui_system.get_minimal_size_of_system();
+    if window.minimal_size.replace(minimal_size) != minimal_size {
+        let mut hints = XSizeHints {
+            flags: PMinSize | PWinGravity,
+            min_width: minimal_size.0,
+            min_height: minimal_size.1,
+            win_gravity: StaticGravity,
+            ..Default::default()
+        };
+        unsafe {
+            XSetWMNormalHints(window.connection.display, window.window, &mut hints);
+        }
+    }
+    let size = image_size_to_position(window.size.get());
+    if size.0 < minimal_size.0 || size.1 < minimal_size.1 {
+        unsafe {
+            XResizeWindow(
+                window.connection.display,
+                window.window,
+                max(size.0, minimal_size.0) as c_uint,
+                max(size.1, minimal_size.1) as c_uint,
+            );
+        }
+    }
+}
+
+fn run_jobs(context: &Rc<RefCell<Context>>, window: &X11Window) {
+    if context.borrow().showing_message {
+        // attempt to recursion, wait for jobs
+        return;
+    }
+    let job_system = context.borrow_mut().job_system.clone();
+    if job_system.run_all() {
+        // jobs can borrow context
+        adjust_window_size(context, window);
+    }
+}
+
+/// Draws the window at most once a frame, later requests wait for the timer.
+fn repaint(context: &Rc<RefCell<Context>>) {
+    let mut context = context.borrow_mut();
+    if context.frame_pacer.request_frame() {
+        context.paint_pending = true;
+    }
+}
+
+fn dispatch_input(context: &Rc<RefCell<Context>>, window: &X11Window, event: InputEvent) {
+    if let InputEvent::Resize(size) = event {
+        unsafe {
+            XResizeWindow(
+                window.connection.display,
+                window.window,
+                max(size.0, 1) as c_uint,
+                max(size.1, 1) as c_uint,
+            );
+        }
+        return;
+    }
+
+    if event.dispatch(&mut context.borrow_mut().gui_system) {
+        run_jobs(context, window);
+        repaint(context);
+    }
+}
+
+fn handle_input(context: &Rc<RefCell<Context>>, window: &X11Window, event: InputEvent) {
+    if context.borrow().input_player.is_some() {
+        return;
+    }
+
+    // the password is not written to the log, the playback has to type it again
+    let skip_recording = event.is_typing() && context.borrow().gui_system.is_masked_input_focused();
+    let record_result = match context.borrow_mut().input_recorder.as_mut() {
+        Some(recorder) if !skip_recording => recorder.record(event),
+        _ => Ok(()),
+    };
+    if let Err(e) = record_result {
+        eprintln!("Input recording stopped: {}", e);
+        context.borrow_mut().input_recorder = None;
+    }
+
+    if let InputEvent::Resize(_) = event {
+        return;
+    }
+    dispatch_input(context, window, event)
+}
+
+fn play_input(context: &Rc<RefCell<Context>>, window: &X11Window) {
+    loop {
+        let event = match context.borrow_mut().input_player.as_mut() {
+            Some(player) => player.next_due_event(),
+            None => return,
+        };
+        match event {
+            Some(event) => dispatch_input(context, window, event),
+            None => break,
+        }
+    }
+
+    let mut context_ref = context.borrow_mut();
+    if context_ref
+        .input_player
+        .as_ref()
+        .is_some_and(|p| p.is_finished())
+    {
+        context_ref.input_player = None;
+    }
+}
+
+/// The wait cursor while the jobs run, the hand over the links.
+fn update_cursor(context: &Rc<RefCell<Context>>, window: &X11Window, position: Position) {
+    let context = context.borrow();
+    let cursor = if context.job_system.is_busy() {
+        WAIT_CURSOR
+    } else if context.gui_system.get_cursor(position) == MouseCursor::Hand {
+        HAND_CURSOR
+    } else {
+        ARROW_CURSOR
+    };
+    window.set_cursor(cursor);
+}
+
+impl SystemContext {
+    /// Returns false when the window is closed.
+    #[allow(non_upper_case_globals)]
+    unsafe fn handle_event(&mut self, event: &mut XEvent) -> bool {
+        let context = self.context.clone();
+        let window = &self.window;
+        match event.type_ {
+            KeyPress => {
+                let (keysym, key) = window.lookup_key(&event.key);
+                let state = event.key.state;
+                let alt = state & Mod1Mask != 0;
+                // repeated Alt keeps the flag, any other key clears it
+                context.borrow_mut().menu_key_pending = keysym == XK_ALT_L || keysym == XK_ALT_R;
+                if let Some(key) = key {
+                    let hotkey = Hotkey {
+                        key,
+                        ctrl: state & ControlMask != 0,
+                        alt,
+                        shift: state & ShiftMask != 0,
+                    };
+                    handle_input(&context, window, InputEvent::KeyDown(hotkey));
+                }
+                // Delete types nothing on Windows
+                let text = window.lookup_text(&mut event.key);
+                for c in text.chars().filter(|c| *c != '\x7F') {
+                    let event = if alt {
+                        InputEvent::Accelerator(c)
+                    } else {
+                        InputEvent::Char(c)
+                    };
+                    handle_input(&context, window, event);
+                }
+            }
+
+            KeyRelease => {
+                let (keysym, key) = window.lookup_key(&event.key);
+                if let Some(key) = key {
+                    handle_input(&context, window, InputEvent::KeyUp(key));
+                }
+                if keysym == XK_ALT_L || keysym == XK_ALT_R {
+                    let pending =
+                        std::mem::replace(&mut context.borrow_mut().menu_key_pending, false);
+                    if pending {
+                        handle_input(&context, window, InputEvent::MenuKey);
+                    }
+                }
+            }
+
+            ButtonPress => {
+                let position = (event.key.x, event.key.y);
+                let shift = event.key.state & ShiftMask != 0;
+                let input = match event.key.keycode {
+                    1 => Some(InputEvent::MouseDown(position)),
+                    2 => {
+                        let now = Instant::now();
+                        let double = self.middle_press.is_some_and(|(time, place)| {
+                            now - time < DOUBLE_CLICK_TIME && place == position
+                        });
+                        self.middle_press = if double { None } else { Some((now, position)) };
+                        if double {
+                            Some(InputEvent::MiddleDoubleClick(position))
+                        } else {
+                            None
+                        }
+                    }
+                    // a step of the wheel away from the user scrolls down as on Windows
+                    4 | 5 => {
+                        let delta = if event.key.keycode == 4 { -1.0 } else { 1.0 };
+                        Some(if shift {
+                            InputEvent::MouseWheelWithShift(position, delta)
+                        } else {
+                            InputEvent::MouseWheel(position, delta)
+                        })
+                    }
+                    6 => Some(InputEvent::MouseHWheel(position, -1.0)),
+                    7 => Some(InputEvent::MouseHWheel(position, 1.0)),
+                    _ => None,
+                };
+                if let Some(input) = input {
+                    handle_input(&context, window, input);
+                }
+            }
+
+            ButtonRelease if event.key.keycode == 1 => {
+                let position = (event.key.x, event.key.y);
+                handle_input(&context, window, InputEvent::MouseUp(position));
+            }
+
+            MotionNotify => {
+                let position = (event.key.x, event.key.y);
+                handle_input(&context, window, InputEvent::MouseMove(position));
+                update_cursor(&context, window, position);
+            }
+
+            LeaveNotify => {
+                handle_input(&context, window, InputEvent::MouseLeave);
+            }
+
+            FocusIn => {
+                if !window.ic.is_null() {
+                    XSetICFocus(window.ic);
+                }
+                context.borrow_mut().frame_pacer.set_inactive(false);
+            }
+
+            FocusOut => {
+                if !window.ic.is_null() {
+                    XUnsetICFocus(window.ic);
+                }
+                context.borrow_mut().frame_pacer.set_inactive(true);
+                handle_input(&context, window, InputEvent::Deactivate);
+            }
+
+            Expose => {
+                let expose = event.expose;
+                window.exposed.borrow_mut().push(Rect {
+                    left_top: (expose.x, expose.y),
+                    right_bottom: (expose.x + expose.width, expose.y + expose.height),
+                });
+                context.borrow_mut().paint_pending = true;
+            }
+
+            MapNotify => {
+                context.borrow_mut().frame_pacer.set_minimized(false);
+            }
+
+            UnmapNotify => {
+                context.borrow_mut().frame_pacer.set_minimized(true);
+            }
+
+            ConfigureNotify => {
+                let size = (
+                    max(event.configure.width, 0) as usize,
+                    max(event.configure.height, 0) as usize,
+                );
+                if window.size.replace(size) != size {
+                    handle_input(&context, window, InputEvent::Resize(size));
+                    context.borrow_mut().paint_pending = true;
+                }
+                adjust_window_size(&context, window);
+                self.application
+                    .on_change_position(window.get_window_position());
+            }
+
+            SelectionRequest => self.selection.on_request(&event.selection_request),
+
+            SelectionClear => self.selection.on_clear(&event.selection_clear),
+
+            ClientMessage if event.client_message.data[0] as Atom == window.wm_delete_window => {
+                self.application.on_close(context.clone());
+                return false;
+            }
+
+            _ => {}
+        }
+        true
+    }
+
+    /// The timer of the window: the animations, the jobs and the delayed frames. Returns
+    /// the new period in milliseconds if it has to be changed.
+    fn on_timer(&mut self) -> Option<u32> {
+        let context = self.context.clone();
+        let window = &self.window;
+        play_input(&context, window);
+        let paused = context.borrow().frame_pacer.is_paused();
+        if !paused && self.application.on_timer(context.clone()) {
+            run_jobs(&context, window);
+            repaint(&context);
+        }
+        let job_system = context.borrow().job_system.clone();
+        if job_system.run_task_steps(Duration::from_millis(30)) {
+            if !job_system.is_busy() {
+                window.set_cursor(ARROW_CURSOR);
+            }
+            run_jobs(&context, window);
+            repaint(&context);
+        }
+        if !paused && context.borrow_mut().gui_system.on_timer() {
+            run_jobs(&context, window);
+            repaint(&context);
+        }
+
+        if context.borrow_mut().frame_pacer.take_due_frame() {
+            context.borrow_mut().paint_pending = true;
+        }
+        let busy = job_system.has_tasks() || context.borrow().input_player.is_some();
+        let interval = context.borrow_mut().frame_pacer.update_timer_interval(busy);
+        interval
+    }
+
+    fn paint(&mut self) {
+        let size = self.window.size.get();
+        if size.0 == 0 || size.1 == 0 {
+            return;
+        }
+        let mut context_borrow_mut = self.context.borrow_mut();
+        let context_ref = context_borrow_mut.deref_mut();
+        context_ref.paint_pending = false;
+        let mut resized = false;
+        let mut buffer_ref = self.buffer.borrow_mut();
+        if buffer_ref.as_ref().map(Image::get_size) != Some(size) {
+            *buffer_ref = Some(Image::new(size));
+            context_ref.gui_system.on_resize();
+            resized = true;
+        }
+
+        let buffer = buffer_ref.as_mut().unwrap();
+        let mut draw_context = DrawContext {
+            buffer: buffer.as_view_mut(),
+            font_factory: &mut context_ref.font_factory,
+        };
+        context_ref.gui_system.on_draw(&mut draw_context);
+
+        let whole = Rect {
+            left_top: (0, 0),
+            right_bottom: image_size_to_position(size),
+        };
+        let exposed = std::mem::take(&mut *self.window.exposed.borrow_mut());
+        let damage = context_ref.gui_system.get_damage().to_vec();
+        let rects =
+            exposed
+                .into_iter()
+                .chain(damage)
+                .chain(if resized { Some(whole) } else { None });
+        self.window.put_image(buffer, rects);
+    }
+}
+
+/// Keeps the window drawn while a dialog program runs, the main loop waits for it then.
+struct ModalPump {
+    window: Rc<X11Window>,
+    selection: Rc<Selection>,
+    buffer: Rc<RefCell<Option<Image<u32>>>>,
+    /// Events of the window state, the main loop handles them after the dialog.
+    deferred: RefCell<Vec<XEvent>>,
+}
+
+impl ModalPump {
+    /// The last frame is copied again where the window is shown and the clipboard is
+    /// served. The presses are dropped, like the ones of a window disabled by a dialog.
+    #[allow(non_upper_case_globals)]
+    fn pump(&self) {
+        let display = self.window.connection.display;
+        unsafe {
+            while XPending(display) > 0 {
+                let mut event = XEvent::zeroed();
+                XNextEvent(display, &mut event);
+                if XFilterEvent(&mut event, 0) != False {
+                    continue;
+                }
+                match event.type_ {
+                    Expose => {
+                        let expose = event.expose;
+                        self.window.exposed.borrow_mut().push(Rect {
+                            left_top: (expose.x, expose.y),
+                            right_bottom: (expose.x + expose.width, expose.y + expose.height),
+                        });
+                    }
+                    SelectionRequest => self.selection.on_request(&event.selection_request),
+                    SelectionClear => self.selection.on_clear(&event.selection_clear),
+                    KeyPress | ButtonPress | MotionNotify => {}
+                    _ => self.deferred.borrow_mut().push(event),
+                }
+            }
+            if let Some(buffer) = self.buffer.borrow_mut().as_mut() {
+                let exposed = std::mem::take(&mut *self.window.exposed.borrow_mut());
+                self.window.put_image(buffer, exposed.into_iter());
+            }
+            XFlush(display);
+        }
+    }
+
+    /// Returns the deferred events to the queue in their order.
+    fn finish(&self) {
+        let display = self.window.connection.display;
+        for mut event in self.deferred.take().into_iter().rev() {
+            unsafe {
+                XPutBackEvent(display, &mut event);
+            }
+        }
+    }
+}
+
+struct RecursiveAPIProtector {
+    context: Rc<RefCell<Context>>,
+}
+
+impl RecursiveAPIProtector {
+    pub fn new(context: Rc<RefCell<Context>>) -> Self {
+        context.borrow_mut().showing_message = true;
+        Self { context }
+    }
+}
+
+impl Drop for RecursiveAPIProtector {
+    fn drop(&mut self) {
+        self.context.borrow_mut().showing_message = false;
+    }
+}
+
+pub fn get_locale_decimal_separator() -> Option<char> {
+    unsafe {
+        libc::setlocale(libc::LC_NUMERIC, c"".as_ptr());
+        let conversion = libc::localeconv();
+        let result = if conversion.is_null() || (*conversion).decimal_point.is_null() {
+            None
+        } else {
+            CStr::from_ptr((*conversion).decimal_point)
+                .to_str()
+                .ok()
+                .and_then(|separator| separator.chars().next())
+        };
+        // the numbers of C libraries keep the point
+        libc::setlocale(libc::LC_NUMERIC, c"C".as_ptr());
+        result
+    }
+}
+
+/// Size of the whole screen, zero without the display.
+pub fn get_screen_resolution() -> ImageSize {
+    match Connection::open() {
+        Ok(connection) => {
+            let size = connection.get_screen_size();
+            (max(size.0, 0) as usize, max(size.1, 0) as usize)
+        }
+        Err(_) => (0, 0),
+    }
+}
+
+/// Monitors of the desktop, empty without the display.
+pub fn get_monitors() -> Vec<MonitorInfo> {
+    match Connection::open() {
+        Ok(connection) => connection.get_monitors(),
+        Err(_) => Vec::new(),
+    }
+}
+
+pub fn run_application(
+    name: &str,
+    application: Box<dyn Application>,
+    window_position: Option<WindowPosition>,
+) -> Result<(), String> {
+    std::panic::set_hook(Box::new(|info| {
+        use backtrace::Backtrace;
+        let bt = Backtrace::new();
+        eprintln!("{:#?}", info);
+        eprintln!("{:?}", bt);
+        eprintln!("exit(3)");
+        std::process::exit(3);
+    }));
+
+    // the input method gives the text in the encoding of the locale
+    unsafe {
+        libc::setlocale(libc::LC_CTYPE, c"".as_ptr());
+    }
+    if let Some(separator) = get_locale_decimal_separator() {
+        application::number_format::set_system_decimal_separator(separator);
+    }
+
+    // there is nothing to show a message box on
+    let connection = Connection::open().inspect_err(|e| eprintln!("{}", e))?;
+    let window = Rc::new(unsafe { X11Window::new(connection.clone(), name, window_position) });
+    let selection = Rc::new(Selection::new(connection.clone(), window.window));
+    let buffer = Rc::new(RefCell::new(None));
+    let modal_pump = Rc::new(ModalPump {
+        window: window.clone(),
+        selection: selection.clone(),
+        buffer: buffer.clone(),
+        deferred: RefCell::new(Vec::new()),
+    });
+
+    let font_factory = FontFactory::new(font_loader::XftFontLoader::new(connection.clone()));
+    let clipboard =
+        application::clipboard::Clipboard::new(clipboard::Clipboard::new(selection.clone()));
+    let shell = application::shell::Shell::new(shell::Shell::new());
+    let file_watcher = FileWatcher::new(file_watcher::FileWatcher::new());
+    let job_system = JobSystem::new();
+    let gui_system = GuiSystem::new(job_system.clone());
+
+    let mut system_context = SystemContext {
+        application,
+        buffer,
+        context: Rc::new(RefCell::new(Context {
+            showing_message: false,
+            modal_pump,
+            input_recorder: None,
+            input_player: None,
+            menu_key_pending: false,
+            paint_pending: false,
+            frame_pacer: Default::default(),
+            presentation: Presentation::Gdi,
+            clipboard,
+            shell,
+            file_watcher,
+            job_system,
+            gui_system,
+            font_factory,
+        })),
+        window,
+        selection,
+        middle_press: None,
+    };
+
+    system_context
+        .application
+        .on_create(system_context.context.clone());
+    system_context.window.show();
+
+    let display = connection.display;
+    let fd = unsafe { XConnectionNumber(display) };
+    let mut timer_interval = Duration::from_millis(1);
+    let mut next_timer = Instant::now();
+    loop {
+        unsafe {
+            while XPending(display) > 0 {
+                let mut event = XEvent::zeroed();
+                XNextEvent(display, &mut event);
+                // the input method takes the keys of the composed chars
+                if XFilterEvent(&mut event, 0) != False {
+                    continue;
+                }
+                if !system_context.handle_event(&mut event) {
+                    return Ok(());
+                }
+            }
+        }
+
+        let now = Instant::now();
+        if now >= next_timer {
+            if let Some(interval) = system_context.on_timer() {
+                timer_interval = Duration::from_millis(interval as u64);
+            }
+            next_timer = now + timer_interval;
+        }
+        if system_context.context.borrow().paint_pending {
+            system_context.paint();
+        }
+
+        unsafe {
+            XFlush(display);
+            if XPending(display) > 0 {
+                continue;
+            }
+            let mut poll_fd = libc::pollfd {
+                fd,
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            let timeout = next_timer.saturating_duration_since(Instant::now());
+            libc::poll(&mut poll_fd, 1, timeout.as_millis() as c_int);
+        }
+    }
+}
@@ -0,0 +1,23 @@
+use std::process::Command;
+
+#[derive(Debug)]
+pub struct Shell {}
+
+impl Shell {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl application::shell::ShellHandler for Shell {
+    /// The desktop opens the target by xdg-open, it may wait for the opened program, so
+    /// it is waited for in another thread.
+    fn open(&mut self, target: &str) -> Result<(), String> {
+        let mut child = Command::new("xdg-open")
+            .arg(target)
+            .spawn()
+            .map_err(|e| e.to_string())?;
+        std::thread::spawn(move || child.wait());
+        Ok(())
+    }
+}
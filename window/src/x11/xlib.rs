@@ -0,0 +1,597 @@
+#![allow(non_upper_case_globals)]
+#![allow(non_snake_case)]
+#![allow(clippy::upper_case_acronyms)]
+
+// Only the part of Xlib and Xft used by the window is declared, there is no crate with
+// the bindings among the dependencies
+
+use std::os::raw::*;
+
+pub enum Display {}
+pub enum Visual {}
+pub enum XGC {}
+pub enum XIMRec {}
+pub enum XICRec {}
+pub enum XftDraw {}
+
+pub type XID = c_ulong;
+pub type Window = XID;
+pub type Drawable = XID;
+pub type Pixmap = XID;
+pub type Cursor = XID;
+pub type Colormap = XID;
+pub type Atom = c_ulong;
+pub type KeySym = c_ulong;
+pub type Time = c_ulong;
+pub type Bool = c_int;
+pub type Status = c_int;
+pub type GC = *mut XGC;
+pub type XIM = *mut XIMRec;
+pub type XIC = *mut XICRec;
+
+pub const False: Bool = 0;
+pub const True: Bool = 1;
+pub const CurrentTime: Time = 0;
+pub const AnyPropertyType: Atom = 0;
+pub const XA_ATOM: Atom = 4;
+pub const PropModeReplace: c_int = 0;
+
+// event types
+pub const KeyPress: c_int = 2;
+pub const KeyRelease: c_int = 3;
+pub const ButtonPress: c_int = 4;
+pub const ButtonRelease: c_int = 5;
+pub const MotionNotify: c_int = 6;
+pub const LeaveNotify: c_int = 8;
+pub const FocusIn: c_int = 9;
+pub const FocusOut: c_int = 10;
+pub const Expose: c_int = 12;
+pub const UnmapNotify: c_int = 18;
+pub const MapNotify: c_int = 19;
+pub const ConfigureNotify: c_int = 22;
+pub const SelectionClear: c_int = 29;
+pub const SelectionRequest: c_int = 30;
+pub const SelectionNotify: c_int = 31;
+pub const ClientMessage: c_int = 33;
+
+// event masks
+pub const KeyPressMask: c_long = 1 << 0;
+pub const KeyReleaseMask: c_long = 1 << 1;
+pub const ButtonPressMask: c_long = 1 << 2;
+pub const ButtonReleaseMask: c_long = 1 << 3;
+pub const LeaveWindowMask: c_long = 1 << 5;
+pub const PointerMotionMask: c_long = 1 << 6;
+pub const ExposureMask: c_long = 1 << 15;
+pub const StructureNotifyMask: c_long = 1 << 17;
+pub const FocusChangeMask: c_long = 1 << 21;
+
+// modifier state
+pub const ShiftMask: c_uint = 1 << 0;
+pub const ControlMask: c_uint = 1 << 2;
+pub const Mod1Mask: c_uint = 1 << 3;
+pub const Mod2Mask: c_uint = 1 << 4;
+
+// window manager hints
+pub const USPosition: c_long = 1 << 0;
+pub const USSize: c_long = 1 << 1;
+pub const PMinSize: c_long = 1 << 4;
+pub const PWinGravity: c_long = 1 << 9;
+pub const StaticGravity: c_int = 10;
+
+// images
+pub const ZPixmap: c_int = 2;
+pub const LSBFirst: c_int = 0;
+pub const AllPlanes: c_ulong = !0;
+
+// cursor font shapes
+pub const XC_hand2: c_uint = 60;
+pub const XC_left_ptr: c_uint = 68;
+pub const XC_watch: c_uint = 150;
+
+// input methods
+pub const XIMPreeditNothing: c_ulong = 0x0008;
+pub const XIMStatusNothing: c_ulong = 0x0400;
+pub const XNInputStyle: &[u8] = b"inputStyle\0";
+pub const XNClientWindow: &[u8] = b"clientWindow\0";
+pub const XNFocusWindow: &[u8] = b"focusWindow\0";
+pub const XLookupChars: Status = 2;
+pub const XLookupBoth: Status = 4;
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct XAnyEvent {
+    pub type_: c_int,
+    pub serial: c_ulong,
+    pub send_event: Bool,
+    pub display: *mut Display,
+    pub window: Window,
+}
+
+/// Key, button and motion events share the layout up to the state.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct XKeyEvent {
+    pub type_: c_int,
+    pub serial: c_ulong,
+    pub send_event: Bool,
+    pub display: *mut Display,
+    pub window: Window,
+    pub root: Window,
+    pub subwindow: Window,
+    pub time: Time,
+    pub x: c_int,
+    pub y: c_int,
+    pub x_root: c_int,
+    pub y_root: c_int,
+    pub state: c_uint,
+    /// The key code of the key events and the button of the button events.
+    pub keycode: c_uint,
+    pub same_screen: Bool,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct XExposeEvent {
+    pub type_: c_int,
+    pub serial: c_ulong,
+    pub send_event: Bool,
+    pub display: *mut Display,
+    pub window: Window,
+    pub x: c_int,
+    pub y: c_int,
+    pub width: c_int,
+    pub height: c_int,
+    pub count: c_int,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct XConfigureEvent {
+    pub type_: c_int,
+    pub serial: c_ulong,
+    pub send_event: Bool,
+    pub display: *mut Display,
+    pub event: Window,
+    pub window: Window,
+    pub x: c_int,
+    pub y: c_int,
+    pub width: c_int,
+    pub height: c_int,
+    pub border_width: c_int,
+    pub above: Window,
+    pub override_redirect: Bool,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct XClientMessageEvent {
+    pub type_: c_int,
+    pub serial: c_ulong,
+    pub send_event: Bool,
+    pub display: *mut Display,
+    pub window: Window,
+    pub message_type: Atom,
+    pub format: c_int,
+    pub data: [c_long; 5],
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct XSelectionRequestEvent {
+    pub type_: c_int,
+    pub serial: c_ulong,
+    pub send_event: Bool,
+    pub display: *mut Display,
+    pub owner: Window,
+    pub requestor: Window,
+    pub selection: Atom,
+    pub target: Atom,
+    pub property: Atom,
+    pub time: Time,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct XSelectionEvent {
+    pub type_: c_int,
+    pub serial: c_ulong,
+    pub send_event: Bool,
+    pub display: *mut Display,
+    pub requestor: Window,
+    pub selection: Atom,
+    pub target: Atom,
+    pub property: Atom,
+    pub time: Time,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct XSelectionClearEvent {
+    pub type_: c_int,
+    pub serial: c_ulong,
+    pub send_event: Bool,
+    pub display: *mut Display,
+    pub window: Window,
+    pub selection: Atom,
+    pub time: Time,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub union XEvent {
+    pub type_: c_int,
+    pub any: XAnyEvent,
+    pub key: XKeyEvent,
+    pub expose: XExposeEvent,
+    pub configure: XConfigureEvent,
+    pub client_message: XClientMessageEvent,
+    pub selection_request: XSelectionRequestEvent,
+    pub selection: XSelectionEvent,
+    pub selection_clear: XSelectionClearEvent,
+    pad: [c_long; 24],
+}
+
+impl XEvent {
+    pub fn zeroed() -> Self {
+        Self { pad: [0; 24] }
+    }
+}
+
+#[repr(C)]
+pub struct XErrorEvent {
+    pub type_: c_int,
+    pub display: *mut Display,
+    pub resourceid: XID,
+    pub serial: c_ulong,
+    pub error_code: c_uchar,
+    pub request_code: c_uchar,
+    pub minor_code: c_uchar,
+}
+
+#[repr(C)]
+#[derive(Default)]
+pub struct XSizeHints {
+    pub flags: c_long,
+    pub x: c_int,
+    pub y: c_int,
+    pub width: c_int,
+    pub height: c_int,
+    pub min_width: c_int,
+    pub min_height: c_int,
+    pub max_width: c_int,
+    pub max_height: c_int,
+    pub width_inc: c_int,
+    pub height_inc: c_int,
+    pub min_aspect: [c_int; 2],
+    pub max_aspect: [c_int; 2],
+    pub base_width: c_int,
+    pub base_height: c_int,
+    pub win_gravity: c_int,
+}
+
+#[repr(C)]
+pub struct XImage {
+    pub width: c_int,
+    pub height: c_int,
+    pub xoffset: c_int,
+    pub format: c_int,
+    pub data: *mut c_char,
+    pub byte_order: c_int,
+    pub bitmap_unit: c_int,
+    pub bitmap_bit_order: c_int,
+    pub bitmap_pad: c_int,
+    pub depth: c_int,
+    pub bytes_per_line: c_int,
+    pub bits_per_pixel: c_int,
+    pub red_mask: c_ulong,
+    pub green_mask: c_ulong,
+    pub blue_mask: c_ulong,
+    pub obdata: *mut c_char,
+    /// Functions of the image, `XInitImage` fills them.
+    pub f: [*mut c_void; 6],
+}
+
+#[repr(C)]
+pub struct XftFont {
+    pub ascent: c_int,
+    pub descent: c_int,
+    pub height: c_int,
+    pub max_advance_width: c_int,
+    pub charset: *mut c_void,
+    pub pattern: *mut c_void,
+}
+
+#[repr(C)]
+#[derive(Default)]
+pub struct XGlyphInfo {
+    pub width: c_ushort,
+    pub height: c_ushort,
+    pub x: c_short,
+    pub y: c_short,
+    pub xOff: c_short,
+    pub yOff: c_short,
+}
+
+#[repr(C)]
+#[derive(Default, Copy, Clone)]
+pub struct XRenderColor {
+    pub red: c_ushort,
+    pub green: c_ushort,
+    pub blue: c_ushort,
+    pub alpha: c_ushort,
+}
+
+#[repr(C)]
+#[derive(Default)]
+pub struct XftColor {
+    pub pixel: c_ulong,
+    pub color: XRenderColor,
+}
+
+/// Monitor of RandR 1.5, the library is loaded at run time, it may be absent.
+#[repr(C)]
+pub struct XRRMonitorInfo {
+    pub name: Atom,
+    pub primary: Bool,
+    pub automatic: Bool,
+    pub noutput: c_int,
+    pub x: c_int,
+    pub y: c_int,
+    pub width: c_int,
+    pub height: c_int,
+    pub mwidth: c_int,
+    pub mheight: c_int,
+    pub outputs: *mut XID,
+}
+
+pub type XRRGetMonitors =
+    unsafe extern "C" fn(*mut Display, Window, Bool, *mut c_int) -> *mut XRRMonitorInfo;
+pub type XRRFreeMonitors = unsafe extern "C" fn(*mut XRRMonitorInfo);
+
+pub type XErrorHandler = Option<unsafe extern "C" fn(*mut Display, *mut XErrorEvent) -> c_int>;
+
+#[link(name = "X11")]
+extern "C" {
+    pub fn XOpenDisplay(name: *const c_char) -> *mut Display;
+    pub fn XCloseDisplay(display: *mut Display) -> c_int;
+    pub fn XSetErrorHandler(handler: XErrorHandler) -> XErrorHandler;
+    pub fn XDefaultScreen(display: *mut Display) -> c_int;
+    pub fn XRootWindow(display: *mut Display, screen: c_int) -> Window;
+    pub fn XDefaultVisual(display: *mut Display, screen: c_int) -> *mut Visual;
+    pub fn XDefaultDepth(display: *mut Display, screen: c_int) -> c_int;
+    pub fn XDefaultColormap(display: *mut Display, screen: c_int) -> Colormap;
+    pub fn XDisplayWidth(display: *mut Display, screen: c_int) -> c_int;
+    pub fn XDisplayHeight(display: *mut Display, screen: c_int) -> c_int;
+    pub fn XBlackPixel(display: *mut Display, screen: c_int) -> c_ulong;
+    pub fn XConnectionNumber(display: *mut Display) -> c_int;
+    pub fn XInternAtom(display: *mut Display, name: *const c_char, only_if_exists: Bool) -> Atom;
+    pub fn XFree(data: *mut c_void) -> c_int;
+    pub fn XFlush(display: *mut Display) -> c_int;
+    pub fn XPending(display: *mut Display) -> c_int;
+    pub fn XNextEvent(display: *mut Display, event: *mut XEvent) -> c_int;
+    pub fn XPutBackEvent(display: *mut Display, event: *mut XEvent) -> c_int;
+    pub fn XCheckTypedWindowEvent(
+        display: *mut Display,
+        window: Window,
+        event_type: c_int,
+        event: *mut XEvent,
+    ) -> Bool;
+    pub fn XSendEvent(
+        display: *mut Display,
+        window: Window,
+        propagate: Bool,
+        event_mask: c_long,
+        event: *mut XEvent,
+    ) -> Status;
+    pub fn XFilterEvent(event: *mut XEvent, window: Window) -> Bool;
+
+    pub fn XCreateSimpleWindow(
+        display: *mut Display,
+        parent: Window,
+        x: c_int,
+        y: c_int,
+        width: c_uint,
+        height: c_uint,
+        border_width: c_uint,
+        border: c_ulong,
+        background: c_ulong,
+    ) -> Window;
+    pub fn XDestroyWindow(display: *mut Display, window: Window) -> c_int;
+    pub fn XSelectInput(display: *mut Display, window: Window, event_mask: c_long) -> c_int;
+    pub fn XStoreName(display: *mut Display, window: Window, name: *const c_char) -> c_int;
+    pub fn XSetWMProtocols(
+        display: *mut Display,
+        window: Window,
+        protocols: *mut Atom,
+        count: c_int,
+    ) -> Status;
+    pub fn XSetWMNormalHints(display: *mut Display, window: Window, hints: *mut XSizeHints);
+    pub fn XMapWindow(display: *mut Display, window: Window) -> c_int;
+    pub fn XResizeWindow(
+        display: *mut Display,
+        window: Window,
+        width: c_uint,
+        height: c_uint,
+    ) -> c_int;
+    pub fn XTranslateCoordinates(
+        display: *mut Display,
+        src: Window,
+        dst: Window,
+        src_x: c_int,
+        src_y: c_int,
+        dst_x: *mut c_int,
+        dst_y: *mut c_int,
+        child: *mut Window,
+    ) -> Bool;
+    pub fn XChangeProperty(
+        display: *mut Display,
+        window: Window,
+        property: Atom,
+        type_: Atom,
+        format: c_int,
+        mode: c_int,
+        data: *const c_uchar,
+        count: c_int,
+    ) -> c_int;
+    pub fn XGetWindowProperty(
+        display: *mut Display,
+        window: Window,
+        property: Atom,
+        long_offset: c_long,
+        long_length: c_long,
+        delete: Bool,
+        req_type: Atom,
+        actual_type: *mut Atom,
+        actual_format: *mut c_int,
+        count: *mut c_ulong,
+        bytes_after: *mut c_ulong,
+        data: *mut *mut c_uchar,
+    ) -> c_int;
+
+    pub fn XSetSelectionOwner(display: *mut Display, selection: Atom, owner: Window, time: Time);
+    pub fn XConvertSelection(
+        display: *mut Display,
+        selection: Atom,
+        target: Atom,
+        property: Atom,
+        requestor: Window,
+        time: Time,
+    ) -> c_int;
+
+    pub fn XCreateFontCursor(display: *mut Display, shape: c_uint) -> Cursor;
+    pub fn XDefineCursor(display: *mut Display, window: Window, cursor: Cursor) -> c_int;
+
+    pub fn XCreateGC(
+        display: *mut Display,
+        drawable: Drawable,
+        value_mask: c_ulong,
+        values: *mut c_void,
+    ) -> GC;
+    pub fn XFreeGC(display: *mut Display, gc: GC) -> c_int;
+    pub fn XCreatePixmap(
+        display: *mut Display,
+        drawable: Drawable,
+        width: c_uint,
+        height: c_uint,
+        depth: c_uint,
+    ) -> Pixmap;
+    pub fn XFreePixmap(display: *mut Display, pixmap: Pixmap) -> c_int;
+    pub fn XInitImage(image: *mut XImage) -> Status;
+    pub fn XPutImage(
+        display: *mut Display,
+        drawable: Drawable,
+        gc: GC,
+        image: *mut XImage,
+        src_x: c_int,
+        src_y: c_int,
+        dst_x: c_int,
+        dst_y: c_int,
+        width: c_uint,
+        height: c_uint,
+    ) -> c_int;
+    pub fn XGetImage(
+        display: *mut Display,
+        drawable: Drawable,
+        x: c_int,
+        y: c_int,
+        width: c_uint,
+        height: c_uint,
+        plane_mask: c_ulong,
+        format: c_int,
+    ) -> *mut XImage;
+    pub fn XGetPixel(image: *mut XImage, x: c_int, y: c_int) -> c_ulong;
+    pub fn XDestroyImage(image: *mut XImage) -> c_int;
+
+    pub fn XkbKeycodeToKeysym(
+        display: *mut Display,
+        keycode: c_uchar,
+        group: c_int,
+        level: c_int,
+    ) -> KeySym;
+    pub fn XkbSetDetectableAutoRepeat(
+        display: *mut Display,
+        detectable: Bool,
+        supported: *mut Bool,
+    ) -> Bool;
+    pub fn XSetLocaleModifiers(modifiers: *const c_char) -> *mut c_char;
+    pub fn XOpenIM(
+        display: *mut Display,
+        database: *mut c_void,
+        res_name: *mut c_char,
+        res_class: *mut c_char,
+    ) -> XIM;
+    pub fn XCloseIM(im: XIM) -> Status;
+    pub fn XCreateIC(im: XIM, ...) -> XIC;
+    pub fn XDestroyIC(ic: XIC);
+    pub fn XSetICFocus(ic: XIC);
+    pub fn XUnsetICFocus(ic: XIC);
+    pub fn Xutf8LookupString(
+        ic: XIC,
+        event: *mut XKeyEvent,
+        buffer: *mut c_char,
+        length: c_int,
+        keysym: *mut KeySym,
+        status: *mut Status,
+    ) -> c_int;
+    pub fn XLookupString(
+        event: *mut XKeyEvent,
+        buffer: *mut c_char,
+        length: c_int,
+        keysym: *mut KeySym,
+        compose: *mut c_void,
+    ) -> c_int;
+}
+
+#[link(name = "Xft")]
+extern "C" {
+    pub fn XftFontOpenName(
+        display: *mut Display,
+        screen: c_int,
+        name: *const c_char,
+    ) -> *mut XftFont;
+    pub fn XftFontClose(display: *mut Display, font: *mut XftFont);
+    pub fn XftTextExtentsUtf8(
+        display: *mut Display,
+        font: *mut XftFont,
+        text: *const c_uchar,
+        length: c_int,
+        extents: *mut XGlyphInfo,
+    );
+    pub fn XftDrawCreate(
+        display: *mut Display,
+        drawable: Drawable,
+        visual: *mut Visual,
+        colormap: Colormap,
+    ) -> *mut XftDraw;
+    pub fn XftDrawDestroy(draw: *mut XftDraw);
+    pub fn XftColorAllocValue(
+        display: *mut Display,
+        visual: *mut Visual,
+        colormap: Colormap,
+        value: *const XRenderColor,
+        result: *mut XftColor,
+    ) -> Bool;
+    pub fn XftColorFree(
+        display: *mut Display,
+        visual: *mut Visual,
+        colormap: Colormap,
+        color: *mut XftColor,
+    );
+    pub fn XftDrawRect(
+        draw: *mut XftDraw,
+        color: *const XftColor,
+        x: c_int,
+        y: c_int,
+        width: c_uint,
+        height: c_uint,
+    );
+    pub fn XftDrawStringUtf8(
+        draw: *mut XftDraw,
+        color: *const XftColor,
+        font: *mut XftFont,
+        x: c_int,
+        y: c_int,
+        text: *const c_uchar,
+        length: c_int,
+    );
+}